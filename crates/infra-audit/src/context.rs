@@ -1,6 +1,11 @@
 //! Audit context.
 
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT_CONTEXT: AuditContext;
+}
 
 /// Actor who performed an action
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +104,9 @@ pub struct AuditContext {
     pub server: Option<String>,
     /// Environment
     pub environment: Option<String>,
+    /// Actor attached to events logged within a scope
+    #[serde(skip)]
+    pub actor: Option<Actor>,
 }
 
 impl AuditContext {
@@ -142,6 +150,68 @@ impl AuditContext {
         self.environment = Some(env.into());
         self
     }
+
+    /// Set the actor for events logged within a scope
+    pub fn actor(mut self, actor: Actor) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    /// Run a future with this context attached to every audit event
+    /// logged within it.
+    ///
+    /// Nested scopes inherit any fields they do not set themselves.
+    pub async fn scope<F: Future>(ctx: AuditContext, fut: F) -> F::Output {
+        let ctx = match Self::current() {
+            Some(parent) => ctx.inherit(&parent),
+            None => ctx,
+        };
+        CURRENT_CONTEXT.scope(ctx, fut).await
+    }
+
+    /// Get the context of the current scope, if any
+    pub fn current() -> Option<AuditContext> {
+        CURRENT_CONTEXT.try_with(Clone::clone).ok()
+    }
+
+    /// Fill fields that are not set from another context
+    pub fn inherit(mut self, parent: &AuditContext) -> Self {
+        fn fill<T: Clone>(field: &mut Option<T>, parent: &Option<T>) {
+            if field.is_none() {
+                field.clone_from(parent);
+            }
+        }
+        fill(&mut self.request_id, &parent.request_id);
+        fill(&mut self.trace_id, &parent.trace_id);
+        fill(&mut self.session_id, &parent.session_id);
+        fill(&mut self.client_ip, &parent.client_ip);
+        fill(&mut self.server, &parent.server);
+        fill(&mut self.environment, &parent.environment);
+        fill(&mut self.actor, &parent.actor);
+        self
+    }
+
+    /// Fill fields from the current scope and active trace
+    pub(crate) fn resolve(self) -> Self {
+        let ctx = match Self::current() {
+            Some(scoped) => self.inherit(&scoped),
+            None => self,
+        };
+        ctx.with_current_trace()
+    }
+
+    #[cfg(feature = "otel")]
+    fn with_current_trace(mut self) -> Self {
+        if self.trace_id.is_none() {
+            self.trace_id = infra_otel::TraceContext::current().map(|t| t.trace_id);
+        }
+        self
+    }
+
+    #[cfg(not(feature = "otel"))]
+    fn with_current_trace(self) -> Self {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -170,4 +240,24 @@ mod tests {
         assert_eq!(ctx.trace_id, Some("trace-456".to_string()));
         assert_eq!(ctx.environment, Some("production".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_scope_propagation() {
+        assert!(AuditContext::current().is_none());
+
+        let outer = AuditContext::new()
+            .request_id("req-1")
+            .actor(Actor::user("alice"));
+        AuditContext::scope(outer, async {
+            let inner = AuditContext::new().trace_id("trace-2");
+            AuditContext::scope(inner, async {
+                let ctx = AuditContext::current().unwrap();
+                assert_eq!(ctx.request_id.as_deref(), Some("req-1"));
+                assert_eq!(ctx.trace_id.as_deref(), Some("trace-2"));
+                assert_eq!(ctx.actor.unwrap().id, "alice");
+            })
+            .await;
+        })
+        .await;
+    }
 }
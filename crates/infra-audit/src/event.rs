@@ -24,6 +24,8 @@ pub enum EventType {
     System,
     /// Security events
     Security,
+    /// LLM operation events
+    Llm,
     /// Custom event type
     Custom,
 }
@@ -42,6 +44,30 @@ pub enum Outcome {
     Unknown,
 }
 
+/// Event severity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Routine activity
+    #[default]
+    Info,
+    /// Suspicious or failed activity
+    Warning,
+    /// Activity requiring immediate attention
+    Critical,
+}
+
+impl Severity {
+    /// Infer a severity from the event type and outcome
+    pub fn infer(event_type: EventType, outcome: Outcome) -> Self {
+        match (event_type, outcome) {
+            (EventType::Security, Outcome::Failure | Outcome::Denied) => Self::Critical,
+            (EventType::Security, _) | (_, Outcome::Failure | Outcome::Denied) => Self::Warning,
+            _ => Self::Info,
+        }
+    }
+}
+
 /// Audit event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
@@ -55,6 +81,9 @@ pub struct AuditEvent {
     action: String,
     /// Event outcome
     outcome: Outcome,
+    /// Event severity
+    #[serde(default)]
+    severity: Severity,
     /// Actor who performed the action
     actor: Option<Actor>,
     /// Resource affected
@@ -95,6 +124,11 @@ impl AuditEvent {
         self.outcome
     }
 
+    /// Get the severity
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
     /// Get the actor
     pub fn actor(&self) -> Option<&Actor> {
         self.actor.as_ref()
@@ -105,10 +139,49 @@ impl AuditEvent {
         self.resource.as_deref()
     }
 
+    /// Get the resource ID
+    pub fn resource_id(&self) -> Option<&str> {
+        self.resource_id.as_deref()
+    }
+
     /// Get metadata
     pub fn metadata(&self) -> &HashMap<String, serde_json::Value> {
         &self.metadata
     }
+
+    /// Get mutable metadata
+    pub fn metadata_mut(&mut self) -> &mut HashMap<String, serde_json::Value> {
+        &mut self.metadata
+    }
+
+    /// Get the error message
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Get the mutable error message
+    pub(crate) fn error_mut(&mut self) -> Option<&mut String> {
+        self.error.as_mut()
+    }
+
+    /// Get the context
+    pub fn context(&self) -> &AuditContext {
+        &self.context
+    }
+
+    /// Restamp the event, e.g. with a simulated time
+    pub(crate) fn set_timestamp(&mut self, timestamp: DateTime<Utc>) {
+        self.timestamp = timestamp;
+    }
+
+    /// Attach the scoped audit context and actor to the event
+    pub(crate) fn resolve_context(&mut self) {
+        let ctx = std::mem::take(&mut self.context).resolve();
+        if self.actor.is_none() {
+            self.actor.clone_from(&ctx.actor);
+        }
+        self.context = ctx;
+    }
 }
 
 /// Audit event builder
@@ -116,6 +189,7 @@ pub struct AuditEventBuilder {
     event_type: EventType,
     action: String,
     outcome: Outcome,
+    severity: Option<Severity>,
     actor: Option<Actor>,
     resource: Option<String>,
     resource_id: Option<String>,
@@ -131,6 +205,7 @@ impl AuditEventBuilder {
             event_type,
             action: String::new(),
             outcome: Outcome::Unknown,
+            severity: None,
             actor: None,
             resource: None,
             resource_id: None,
@@ -152,6 +227,12 @@ impl AuditEventBuilder {
         self
     }
 
+    /// Set the severity (inferred from type and outcome if not set)
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
     /// Set the actor
     pub fn actor(mut self, actor: Actor) -> Self {
         self.actor = Some(actor);
@@ -196,6 +277,9 @@ impl AuditEventBuilder {
             event_type: self.event_type,
             action: self.action,
             outcome: self.outcome,
+            severity: self
+                .severity
+                .unwrap_or_else(|| Severity::infer(self.event_type, self.outcome)),
             actor: self.actor,
             resource: self.resource,
             resource_id: self.resource_id,
@@ -225,5 +309,24 @@ mod tests {
         assert_eq!(event.action(), "read");
         assert_eq!(event.outcome(), Outcome::Success);
         assert_eq!(event.resource(), Some("users"));
+        assert_eq!(event.severity(), Severity::Info);
+    }
+
+    #[test]
+    fn test_severity_inference() {
+        let denied = AuditEventBuilder::new(EventType::Security)
+            .outcome(Outcome::Denied)
+            .build();
+        assert_eq!(denied.severity(), Severity::Critical);
+
+        let failed = AuditEventBuilder::new(EventType::DataAccess)
+            .outcome(Outcome::Failure)
+            .build();
+        assert_eq!(failed.severity(), Severity::Warning);
+
+        let explicit = AuditEventBuilder::new(EventType::System)
+            .severity(Severity::Critical)
+            .build();
+        assert_eq!(explicit.severity(), Severity::Critical);
     }
 }
@@ -2,16 +2,39 @@
 //!
 //! This crate provides structured audit logging for security-sensitive
 //! operations with support for multiple backends.
+//!
+//! [`AuditLogger::with_time_source`] stamps events with the time of an
+//! `infra-time` time source such as a simulated clock.
 
 mod event;
 mod logger;
 mod sink;
 mod context;
+mod query;
+mod redact;
+pub mod retention;
+mod routing;
+#[cfg(feature = "webhook")]
+mod webhook;
+#[cfg(feature = "mq")]
+mod mq;
+pub mod llm;
 
-pub use event::{AuditEvent, AuditEventBuilder, EventType, Outcome};
-pub use logger::{AuditLogger, LoggerConfig};
-pub use sink::{AuditSink, ConsoleSink, MemorySink};
+pub use event::{AuditEvent, AuditEventBuilder, EventType, Outcome, Severity};
+pub use logger::{AuditLogger, LoggerConfig, LoggerStats, OverflowPolicy};
+pub use sink::{AuditSink, ConsoleSink, FileSink, MemorySink};
 pub use context::{AuditContext, Actor};
+pub use llm::{LlmEventBuilder, LlmEventData, LlmEventKind};
+pub use redact::{MaskingRedactor, NoopRedactor, Redactor};
+pub use retention::{
+    ExportFormat, FileArchiver, RetentionManager, RetentionPolicy, RetentionReport, RetentionSink,
+};
+pub use routing::{Route, RoutingSink};
+#[cfg(feature = "webhook")]
+pub use webhook::WebhookSink;
+#[cfg(feature = "mq")]
+pub use mq::{MqSink, HEADER_EVENT_ID, HEADER_EVENT_TYPE, HEADER_SEVERITY};
+pub use query::{AuditPage, AuditQuery, QueryableSink, DEFAULT_PAGE_SIZE};
 
 use infra_errors::InfraResult;
 use std::sync::Arc;
@@ -0,0 +1,330 @@
+//! LLM-specific audit events.
+
+use crate::context::{Actor, AuditContext};
+use crate::event::{AuditEvent, AuditEventBuilder, EventType, Outcome};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Metadata key for the LLM event kind
+pub const KEY_KIND: &str = "llm.kind";
+/// Metadata key for the model name
+pub const KEY_MODEL: &str = "llm.model";
+/// Metadata key for the provider name
+pub const KEY_PROVIDER: &str = "llm.provider";
+/// Metadata key for prompt token count
+pub const KEY_PROMPT_TOKENS: &str = "llm.prompt_tokens";
+/// Metadata key for completion token count
+pub const KEY_COMPLETION_TOKENS: &str = "llm.completion_tokens";
+/// Metadata key for latency in milliseconds
+pub const KEY_LATENCY_MS: &str = "llm.latency_ms";
+/// Metadata key for the invoked tool name
+pub const KEY_TOOL: &str = "llm.tool";
+/// Metadata key for the safety filter category
+pub const KEY_FILTER_CATEGORY: &str = "llm.filter_category";
+/// Metadata key for cost in USD
+pub const KEY_COST_USD: &str = "llm.cost_usd";
+/// Metadata key for the finish reason
+pub const KEY_FINISH_REASON: &str = "llm.finish_reason";
+
+/// LLM operation kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmEventKind {
+    /// A prompt was sent to a model
+    PromptSubmitted,
+    /// A completion was received from a model
+    CompletionReturned,
+    /// A tool/function was invoked on behalf of a model
+    ToolInvoked,
+    /// Content was blocked or altered by a safety filter
+    SafetyFiltered,
+    /// Cost was incurred for a model call
+    CostIncurred,
+}
+
+impl LlmEventKind {
+    /// Get the action name used for this kind
+    pub fn action(&self) -> &'static str {
+        match self {
+            Self::PromptSubmitted => "llm.prompt_submitted",
+            Self::CompletionReturned => "llm.completion_returned",
+            Self::ToolInvoked => "llm.tool_invoked",
+            Self::SafetyFiltered => "llm.safety_filtered",
+            Self::CostIncurred => "llm.cost_incurred",
+        }
+    }
+
+    fn from_action(action: &str) -> Option<Self> {
+        match action {
+            "llm.prompt_submitted" => Some(Self::PromptSubmitted),
+            "llm.completion_returned" => Some(Self::CompletionReturned),
+            "llm.tool_invoked" => Some(Self::ToolInvoked),
+            "llm.safety_filtered" => Some(Self::SafetyFiltered),
+            "llm.cost_incurred" => Some(Self::CostIncurred),
+            _ => None,
+        }
+    }
+}
+
+/// Standardized fields of an LLM audit event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmEventData {
+    /// Model name
+    pub model: Option<String>,
+    /// Provider name
+    pub provider: Option<String>,
+    /// Prompt token count
+    pub prompt_tokens: Option<u64>,
+    /// Completion token count
+    pub completion_tokens: Option<u64>,
+    /// Call latency
+    pub latency: Option<Duration>,
+    /// Tool name
+    pub tool: Option<String>,
+    /// Safety filter category
+    pub filter_category: Option<String>,
+    /// Cost in USD
+    pub cost_usd: Option<f64>,
+    /// Finish reason
+    pub finish_reason: Option<String>,
+}
+
+impl LlmEventData {
+    /// Extract LLM fields from an audit event
+    ///
+    /// Returns `None` if the event is not an LLM event.
+    pub fn from_event(event: &AuditEvent) -> Option<(LlmEventKind, Self)> {
+        if event.event_type() != EventType::Llm {
+            return None;
+        }
+        let kind = LlmEventKind::from_action(event.action())?;
+        let meta = event.metadata();
+        let string = |key: &str| meta.get(key).and_then(|v| v.as_str()).map(String::from);
+        let uint = |key: &str| meta.get(key).and_then(serde_json::Value::as_u64);
+
+        let data = Self {
+            model: string(KEY_MODEL),
+            provider: string(KEY_PROVIDER),
+            prompt_tokens: uint(KEY_PROMPT_TOKENS),
+            completion_tokens: uint(KEY_COMPLETION_TOKENS),
+            latency: uint(KEY_LATENCY_MS).map(Duration::from_millis),
+            tool: string(KEY_TOOL),
+            filter_category: string(KEY_FILTER_CATEGORY),
+            cost_usd: meta.get(KEY_COST_USD).and_then(serde_json::Value::as_f64),
+            finish_reason: string(KEY_FINISH_REASON),
+        };
+        Some((kind, data))
+    }
+
+    /// Total token count, if both counts are known
+    pub fn total_tokens(&self) -> Option<u64> {
+        Some(self.prompt_tokens? + self.completion_tokens?)
+    }
+}
+
+/// Builder for LLM audit events
+pub struct LlmEventBuilder {
+    kind: LlmEventKind,
+    data: LlmEventData,
+    inner: AuditEventBuilder,
+}
+
+impl LlmEventBuilder {
+    /// Create a builder for an LLM event kind
+    pub fn new(kind: LlmEventKind, model: impl Into<String>, provider: impl Into<String>) -> Self {
+        let outcome = match kind {
+            LlmEventKind::SafetyFiltered => Outcome::Denied,
+            _ => Outcome::Success,
+        };
+        Self {
+            kind,
+            data: LlmEventData {
+                model: Some(model.into()),
+                provider: Some(provider.into()),
+                ..Default::default()
+            },
+            inner: AuditEventBuilder::new(EventType::Llm)
+                .action(kind.action())
+                .outcome(outcome),
+        }
+    }
+
+    /// Create a prompt-submitted event
+    pub fn prompt_submitted(model: impl Into<String>, provider: impl Into<String>) -> Self {
+        Self::new(LlmEventKind::PromptSubmitted, model, provider)
+    }
+
+    /// Create a completion-returned event
+    pub fn completion_returned(model: impl Into<String>, provider: impl Into<String>) -> Self {
+        Self::new(LlmEventKind::CompletionReturned, model, provider)
+    }
+
+    /// Create a tool-invoked event
+    pub fn tool_invoked(
+        model: impl Into<String>,
+        provider: impl Into<String>,
+        tool: impl Into<String>,
+    ) -> Self {
+        let mut builder = Self::new(LlmEventKind::ToolInvoked, model, provider);
+        builder.data.tool = Some(tool.into());
+        builder
+    }
+
+    /// Create a safety-filtered event
+    pub fn safety_filtered(
+        model: impl Into<String>,
+        provider: impl Into<String>,
+        category: impl Into<String>,
+    ) -> Self {
+        let mut builder = Self::new(LlmEventKind::SafetyFiltered, model, provider);
+        builder.data.filter_category = Some(category.into());
+        builder
+    }
+
+    /// Create a cost-incurred event
+    pub fn cost_incurred(model: impl Into<String>, provider: impl Into<String>, cost_usd: f64) -> Self {
+        let mut builder = Self::new(LlmEventKind::CostIncurred, model, provider);
+        builder.data.cost_usd = Some(cost_usd);
+        builder
+    }
+
+    /// Set token counts
+    pub fn tokens(mut self, prompt: u64, completion: u64) -> Self {
+        self.data.prompt_tokens = Some(prompt);
+        self.data.completion_tokens = Some(completion);
+        self
+    }
+
+    /// Set prompt token count
+    pub fn prompt_tokens(mut self, tokens: u64) -> Self {
+        self.data.prompt_tokens = Some(tokens);
+        self
+    }
+
+    /// Set completion token count
+    pub fn completion_tokens(mut self, tokens: u64) -> Self {
+        self.data.completion_tokens = Some(tokens);
+        self
+    }
+
+    /// Set latency
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.data.latency = Some(latency);
+        self
+    }
+
+    /// Set cost in USD
+    pub fn cost(mut self, cost_usd: f64) -> Self {
+        self.data.cost_usd = Some(cost_usd);
+        self
+    }
+
+    /// Set finish reason
+    pub fn finish_reason(mut self, reason: impl Into<String>) -> Self {
+        self.data.finish_reason = Some(reason.into());
+        self
+    }
+
+    /// Set the outcome
+    pub fn outcome(mut self, outcome: Outcome) -> Self {
+        self.inner = self.inner.outcome(outcome);
+        self
+    }
+
+    /// Set the actor
+    pub fn actor(mut self, actor: Actor) -> Self {
+        self.inner = self.inner.actor(actor);
+        self
+    }
+
+    /// Set the context
+    pub fn context(mut self, context: AuditContext) -> Self {
+        self.inner = self.inner.context(context);
+        self
+    }
+
+    /// Add custom metadata
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.inner = self.inner.metadata(key, value);
+        self
+    }
+
+    /// Set error message
+    pub fn error(mut self, error: impl Into<String>) -> Self {
+        self.inner = self.inner.error(error);
+        self
+    }
+
+    /// Build the event
+    pub fn build(self) -> AuditEvent {
+        let data = self.data;
+        let mut inner = self
+            .inner
+            .metadata(KEY_KIND, serde_json::to_value(self.kind).unwrap_or_default());
+
+        if let Some(model) = &data.model {
+            inner = inner.resource("model").resource_id(model.clone());
+        }
+        let fields: [(&str, Option<serde_json::Value>); 9] = [
+            (KEY_MODEL, data.model.map(Into::into)),
+            (KEY_PROVIDER, data.provider.map(Into::into)),
+            (KEY_PROMPT_TOKENS, data.prompt_tokens.map(Into::into)),
+            (KEY_COMPLETION_TOKENS, data.completion_tokens.map(Into::into)),
+            (
+                KEY_LATENCY_MS,
+                data.latency
+                    .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX).into()),
+            ),
+            (KEY_TOOL, data.tool.map(Into::into)),
+            (KEY_FILTER_CATEGORY, data.filter_category.map(Into::into)),
+            (KEY_COST_USD, data.cost_usd.map(Into::into)),
+            (KEY_FINISH_REASON, data.finish_reason.map(Into::into)),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                inner = inner.metadata(key, value);
+            }
+        }
+        inner.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_event_roundtrip() {
+        let event = LlmEventBuilder::completion_returned("gpt-4o", "openai")
+            .tokens(120, 45)
+            .latency(Duration::from_millis(850))
+            .finish_reason("stop")
+            .build();
+
+        assert_eq!(event.event_type(), EventType::Llm);
+        assert_eq!(event.action(), "llm.completion_returned");
+        assert_eq!(event.resource_id(), Some("gpt-4o"));
+
+        let (kind, data) = LlmEventData::from_event(&event).unwrap();
+        assert_eq!(kind, LlmEventKind::CompletionReturned);
+        assert_eq!(data.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(data.total_tokens(), Some(165));
+        assert_eq!(data.latency, Some(Duration::from_millis(850)));
+        assert_eq!(data.finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[test]
+    fn test_safety_filtered_defaults_to_denied() {
+        let event = LlmEventBuilder::safety_filtered("claude", "anthropic", "pii").build();
+        assert_eq!(event.outcome(), Outcome::Denied);
+
+        let (_, data) = LlmEventData::from_event(&event).unwrap();
+        assert_eq!(data.filter_category.as_deref(), Some("pii"));
+    }
+
+    #[test]
+    fn test_non_llm_event() {
+        let event = AuditEventBuilder::new(EventType::System).action("boot").build();
+        assert!(LlmEventData::from_event(&event).is_none());
+    }
+}
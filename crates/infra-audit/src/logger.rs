@@ -1,9 +1,25 @@
 //! Audit logger.
 
 use crate::event::AuditEvent;
+use crate::redact::{MaskingRedactor, Redactor};
 use crate::sink::AuditSink;
-use infra_errors::InfraResult;
-use std::sync::Arc;
+use infra_errors::{InfraError, InfraResult};
+use infra_time::{TimeHandle, TimeSource};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Behavior when the buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for space in the buffer
+    Block,
+    /// Drop the incoming event and count it
+    DropNewest,
+    /// Return an error to the caller
+    Error,
+}
 
 /// Logger configuration
 #[derive(Debug, Clone)]
@@ -12,81 +28,513 @@ pub struct LoggerConfig {
     pub buffer_size: usize,
     /// Whether to log synchronously
     pub sync_mode: bool,
+    /// Maximum events written per batch
+    pub batch_size: usize,
+    /// Interval between periodic flushes
+    pub flush_interval: Duration,
+    /// Behavior when the buffer is full
+    pub overflow_policy: OverflowPolicy,
 }
 
 impl Default for LoggerConfig {
     fn default() -> Self {
         Self {
             buffer_size: 1000,
+            sync_mode: true,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(1),
+            overflow_policy: OverflowPolicy::DropNewest,
+        }
+    }
+}
+
+impl LoggerConfig {
+    /// Create a buffered configuration
+    pub fn buffered(buffer_size: usize) -> Self {
+        Self {
+            buffer_size,
             sync_mode: false,
+            ..Default::default()
+        }
+    }
+
+    /// Set the batch size
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Set the flush interval
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Set the overflow policy
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+}
+
+/// Logger statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoggerStats {
+    /// Events accepted by the logger
+    pub enqueued: u64,
+    /// Events written to sinks
+    pub written: u64,
+    /// Events dropped due to overflow
+    pub dropped: u64,
+    /// Events that failed to write to at least one sink
+    pub failed: u64,
+    /// Batches flushed
+    pub batches: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    enqueued: AtomicU64,
+    written: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+    batches: AtomicU64,
+}
+
+impl Counters {
+    fn snapshot(&self) -> LoggerStats {
+        LoggerStats {
+            enqueued: self.enqueued.load(Ordering::Relaxed),
+            written: self.written.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
         }
     }
 }
 
+enum Command {
+    Event(Box<AuditEvent>),
+    Flush(oneshot::Sender<InfraResult<()>>),
+}
+
+type SinkList = Arc<RwLock<Vec<Arc<dyn AuditSink>>>>;
+
 /// Audit logger
 pub struct AuditLogger {
-    sinks: Vec<Arc<dyn AuditSink>>,
+    sinks: SinkList,
     config: LoggerConfig,
+    counters: Arc<Counters>,
+    sender: Option<mpsc::Sender<Command>>,
+    redactor: Arc<dyn Redactor>,
+    clock: TimeHandle,
 }
 
 impl AuditLogger {
     /// Create a new logger with a sink
     pub fn new(sink: Arc<dyn AuditSink>) -> Self {
-        Self {
-            sinks: vec![sink],
-            config: LoggerConfig::default(),
-        }
+        Self::with_config(sink, LoggerConfig::default())
     }
 
     /// Create a new logger with configuration
+    ///
+    /// When `sync_mode` is false, events are buffered and written by a
+    /// background task, so this must be called within a Tokio runtime.
     pub fn with_config(sink: Arc<dyn AuditSink>, config: LoggerConfig) -> Self {
+        let sinks: SinkList = Arc::new(RwLock::new(vec![sink]));
+        let counters = Arc::new(Counters::default());
+
+        let sender = if config.sync_mode {
+            None
+        } else {
+            let (tx, rx) = mpsc::channel(config.buffer_size.max(1));
+            tokio::spawn(run_flusher(
+                rx,
+                sinks.clone(),
+                counters.clone(),
+                config.batch_size.max(1),
+                config.flush_interval,
+            ));
+            Some(tx)
+        };
+
         Self {
-            sinks: vec![sink],
+            sinks,
             config,
+            counters,
+            sender,
+            redactor: Arc::new(MaskingRedactor::default()),
+            clock: TimeHandle::default(),
         }
     }
 
+    /// Set the redaction stage applied before events reach any sink
+    pub fn with_redactor(mut self, redactor: Arc<dyn Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Stamp events with the time of a (simulated) time source when logged
+    ///
+    /// Without a time source, events keep the timestamp set when built.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self
+    }
+
     /// Add a sink
     pub fn add_sink(&mut self, sink: Arc<dyn AuditSink>) {
-        self.sinks.push(sink);
+        self.sinks
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(sink);
+    }
+
+    /// Get the logger configuration
+    pub fn config(&self) -> &LoggerConfig {
+        &self.config
+    }
+
+    /// Get logger statistics
+    pub fn stats(&self) -> LoggerStats {
+        self.counters.snapshot()
     }
 
     /// Log an event to all sinks
-    pub async fn log(&self, event: AuditEvent) -> InfraResult<()> {
-        for sink in &self.sinks {
-            sink.write(&event).await?;
+    pub async fn log(&self, mut event: AuditEvent) -> InfraResult<()> {
+        if self.clock.is_overridden() {
+            event.set_timestamp(self.clock.system_time().into());
         }
+        event.resolve_context();
+        self.redactor.redact(&mut event);
+
+        let Some(sender) = &self.sender else {
+            self.counters.enqueued.fetch_add(1, Ordering::Relaxed);
+            for sink in snapshot(&self.sinks) {
+                if let Err(e) = sink.write(&event).await {
+                    self.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    return Err(e);
+                }
+            }
+            self.counters.written.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        };
+
+        let command = Command::Event(Box::new(event));
+        match self.config.overflow_policy {
+            OverflowPolicy::Block => {
+                sender.send(command).await.map_err(|_| closed_error())?;
+            }
+            OverflowPolicy::DropNewest | OverflowPolicy::Error => {
+                match sender.try_send(command) {
+                    Ok(()) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                        if self.config.overflow_policy == OverflowPolicy::Error {
+                            return Err(InfraError::External {
+                                service: "audit".to_string(),
+                                operation: "log".to_string(),
+                                message: "Audit buffer is full".to_string(),
+                                retry_after: Some(self.config.flush_interval),
+                                context: None,
+                                source: None,
+                            });
+                        }
+                        tracing::warn!("Audit buffer full, dropping event");
+                        return Ok(());
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => return Err(closed_error()),
+                }
+            }
+        }
+        self.counters.enqueued.fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
 
     /// Flush all sinks
+    ///
+    /// In buffered mode this waits until every previously logged event
+    /// has been written, and returns the first sink error since the
+    /// previous flush.
     pub async fn flush(&self) -> InfraResult<()> {
-        for sink in &self.sinks {
+        if let Some(sender) = &self.sender {
+            let (tx, rx) = oneshot::channel();
+            sender
+                .send(Command::Flush(tx))
+                .await
+                .map_err(|_| closed_error())?;
+            return rx.await.map_err(|_| closed_error())?;
+        }
+
+        for sink in snapshot(&self.sinks) {
             sink.flush().await?;
         }
         Ok(())
     }
 }
 
+fn snapshot(sinks: &SinkList) -> Vec<Arc<dyn AuditSink>> {
+    sinks
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+fn closed_error() -> InfraError {
+    InfraError::External {
+        service: "audit".to_string(),
+        operation: "log".to_string(),
+        message: "Audit logger background task has stopped".to_string(),
+        retry_after: None,
+        context: None,
+        source: None,
+    }
+}
+
+async fn run_flusher(
+    mut rx: mpsc::Receiver<Command>,
+    sinks: SinkList,
+    counters: Arc<Counters>,
+    batch_size: usize,
+    flush_interval: Duration,
+) {
+    let mut batch: Vec<AuditEvent> = Vec::with_capacity(batch_size);
+    // First sink error since the last flush, reported to the flush caller
+    let mut error: Option<InfraError> = None;
+    let mut ticker = tokio::time::interval(flush_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            command = rx.recv() => match command {
+                Some(Command::Event(event)) => {
+                    batch.push(*event);
+                    if batch.len() >= batch_size {
+                        keep_first(&mut error, write_batch(&sinks, &counters, &mut batch).await);
+                    }
+                }
+                Some(Command::Flush(done)) => {
+                    keep_first(&mut error, write_batch(&sinks, &counters, &mut batch).await);
+                    keep_first(&mut error, flush_sinks(&sinks).await);
+                    let _ = done.send(error.take().map_or(Ok(()), Err));
+                }
+                None => {
+                    write_batch(&sinks, &counters, &mut batch).await;
+                    flush_sinks(&sinks).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => {
+                if !batch.is_empty() {
+                    keep_first(&mut error, write_batch(&sinks, &counters, &mut batch).await);
+                }
+            }
+        }
+    }
+}
+
+fn keep_first(error: &mut Option<InfraError>, result: Option<InfraError>) {
+    if error.is_none() {
+        *error = result;
+    }
+}
+
+/// Write a batch to every sink, returning the first error
+async fn write_batch(
+    sinks: &SinkList,
+    counters: &Counters,
+    batch: &mut Vec<AuditEvent>,
+) -> Option<InfraError> {
+    if batch.is_empty() {
+        return None;
+    }
+    let events = std::mem::take(batch);
+    let mut error = None;
+
+    for sink in snapshot(sinks) {
+        if let Err(e) = sink.write_batch(&events).await {
+            tracing::error!(sink = sink.name(), error = %e, "Failed to write audit batch");
+            keep_first(&mut error, Some(e));
+        }
+    }
+
+    let count = events.len() as u64;
+    if error.is_some() {
+        counters.failed.fetch_add(count, Ordering::Relaxed);
+    } else {
+        counters.written.fetch_add(count, Ordering::Relaxed);
+    }
+    counters.batches.fetch_add(1, Ordering::Relaxed);
+    error
+}
+
+/// Flush every sink, returning the first error
+async fn flush_sinks(sinks: &SinkList) -> Option<InfraError> {
+    let mut error = None;
+    for sink in snapshot(sinks) {
+        if let Err(e) = sink.flush().await {
+            tracing::error!(sink = sink.name(), error = %e, "Failed to flush audit sink");
+            keep_first(&mut error, Some(e));
+        }
+    }
+    error
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::sink::MemorySink;
     use crate::event::{AuditEventBuilder, EventType, Outcome};
 
+    fn event() -> AuditEvent {
+        AuditEventBuilder::new(EventType::System)
+            .action("test")
+            .outcome(Outcome::Success)
+            .build()
+    }
+
     #[tokio::test]
     async fn test_logger() {
         let sink = Arc::new(MemorySink::new());
         let logger = AuditLogger::new(sink.clone());
 
-        let event = AuditEventBuilder::new(EventType::System)
-            .action("test")
-            .outcome(Outcome::Success)
-            .build();
+        logger.log(event()).await.unwrap();
+
+        let events = sink.events().await;
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_with_simulated_time() {
+        use infra_sim::SimulatedClock;
+        use std::time::UNIX_EPOCH;
+
+        let clock = Arc::new(SimulatedClock::starting_at(UNIX_EPOCH));
+        let sink = Arc::new(MemorySink::new());
+        let logger = AuditLogger::new(sink.clone()).with_time_source(clock.clone());
+
+        logger.log(event()).await.unwrap();
+        clock.advance(Duration::from_secs(90));
+        logger.log(event()).await.unwrap();
+
+        let events = sink.events().await;
+        assert_eq!(events[0].timestamp().timestamp(), 0);
+        assert_eq!(events[1].timestamp().timestamp(), 90);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_logger_flush() {
+        let sink = Arc::new(MemorySink::new());
+        let config = LoggerConfig::buffered(16)
+            .with_batch_size(4)
+            .with_flush_interval(Duration::from_secs(60));
+        let logger = AuditLogger::with_config(sink.clone(), config);
+
+        for _ in 0..6 {
+            logger.log(event()).await.unwrap();
+        }
+        logger.flush().await.unwrap();
+
+        assert_eq!(sink.count().await, 6);
+        let stats = logger.stats();
+        assert_eq!(stats.enqueued, 6);
+        assert_eq!(stats.written, 6);
+        assert_eq!(stats.batches, 2);
+    }
+
+    struct FailingSink {
+        flushes: AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl AuditSink for FailingSink {
+        async fn write(&self, _event: &AuditEvent) -> InfraResult<()> {
+            Err(InfraError::External {
+                service: "audit".to_string(),
+                operation: "write".to_string(),
+                message: "sink unavailable".to_string(),
+                retry_after: None,
+                context: None,
+                source: None,
+            })
+        }
 
+        async fn flush(&self) -> InfraResult<()> {
+            self.flushes.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "failing"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buffered_flush_reports_sink_errors() {
+        let sink = Arc::new(FailingSink {
+            flushes: AtomicU64::new(0),
+        });
+        let config = LoggerConfig::buffered(16).with_batch_size(2);
+        let logger = AuditLogger::with_config(sink.clone(), config);
+
+        for _ in 0..4 {
+            logger.log(event()).await.unwrap();
+        }
+        assert!(logger.flush().await.is_err());
+        // Sinks are flushed on request, not after every batch
+        assert_eq!(sink.flushes.load(Ordering::Relaxed), 1);
+        assert_eq!(logger.stats().failed, 4);
+
+        // The error is reported once
+        logger.flush().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_logger_redacts_by_default() {
+        let sink = Arc::new(MemorySink::new());
+        let logger = AuditLogger::new(sink.clone());
+
+        let event = AuditEventBuilder::new(EventType::Llm)
+            .action("llm.prompt_submitted")
+            .metadata("prompt", "my key is sk-abcdefghijklmnopqrstuv")
+            .build();
         logger.log(event).await.unwrap();
 
         let events = sink.events().await;
-        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metadata()["prompt"], "my key is [REDACTED]");
+    }
+
+    #[tokio::test]
+    async fn test_logger_attaches_scoped_context() {
+        use crate::context::{Actor, AuditContext};
+
+        let sink = Arc::new(MemorySink::new());
+        let logger = AuditLogger::new(sink.clone());
+
+        let ctx = AuditContext::new()
+            .request_id("req-42")
+            .actor(Actor::service("billing"));
+        AuditContext::scope(ctx, logger.log(event())).await.unwrap();
+
+        let events = sink.events().await;
+        assert_eq!(events[0].context().request_id.as_deref(), Some("req-42"));
+        assert_eq!(events[0].actor().unwrap().id, "billing");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_buffered_logger_overflow() {
+        let sink = Arc::new(MemorySink::new());
+        let config = LoggerConfig::buffered(2).with_overflow_policy(OverflowPolicy::Error);
+        let logger = AuditLogger::with_config(sink.clone(), config);
+
+        // The flusher cannot run until we yield, so the buffer fills up
+        logger.log(event()).await.unwrap();
+        logger.log(event()).await.unwrap();
+        assert!(logger.log(event()).await.is_err());
+        assert_eq!(logger.stats().dropped, 1);
+
+        logger.flush().await.unwrap();
+        assert_eq!(sink.count().await, 2);
     }
 }
@@ -0,0 +1,254 @@
+//! Message queue sink.
+
+use crate::event::AuditEvent;
+use crate::sink::AuditSink;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, MqOperation, SerializationFormat};
+use infra_mq::{Message, MessageBuilder, Queue};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Header carrying the audit event ID, for consumer-side deduplication
+pub const HEADER_EVENT_ID: &str = "x-audit-event-id";
+/// Header carrying the audit event type
+pub const HEADER_EVENT_TYPE: &str = "x-audit-event-type";
+/// Header carrying the audit event severity
+pub const HEADER_SEVERITY: &str = "x-audit-severity";
+
+/// Sink publishing events to an infra-mq queue with at-least-once delivery
+///
+/// Failed publishes are retried with backoff; events that still cannot be
+/// published are held and redelivered before the next write or on flush.
+/// Consumers should deduplicate on [`HEADER_EVENT_ID`].
+pub struct MqSink {
+    queue: Arc<dyn Queue>,
+    max_attempts: u32,
+    retry_delay: Duration,
+    max_pending: usize,
+    pending: Mutex<VecDeque<Message>>,
+}
+
+impl MqSink {
+    /// Create a new MQ sink
+    pub fn new(queue: Arc<dyn Queue>) -> Self {
+        Self {
+            queue,
+            max_attempts: 3,
+            retry_delay: Duration::from_millis(100),
+            max_pending: 10_000,
+            pending: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Set the number of publish attempts per message
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the delay between publish attempts
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Set the maximum number of undelivered events held for redelivery
+    pub fn with_max_pending(mut self, max: usize) -> Self {
+        self.max_pending = max;
+        self
+    }
+
+    /// Get the number of events awaiting redelivery
+    pub async fn pending(&self) -> usize {
+        self.pending.lock().await.len()
+    }
+
+    fn to_message(event: &AuditEvent) -> InfraResult<Message> {
+        let label = |v: serde_json::Value| v.as_str().unwrap_or_default().to_string();
+        let builder = MessageBuilder::new()
+            .body_json(event)
+            .map_err(|e| InfraError::Serialization {
+                format: SerializationFormat::Json,
+                message: e.to_string(),
+                location: None,
+                context: None,
+                source: None,
+            })?
+            .header(HEADER_EVENT_ID, event.id())
+            .header(
+                HEADER_EVENT_TYPE,
+                label(serde_json::to_value(event.event_type())?),
+            )
+            .header(HEADER_SEVERITY, label(serde_json::to_value(event.severity())?));
+        Ok(builder.build())
+    }
+
+    async fn publish_with_retry(&self, message: &Message) -> InfraResult<()> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+        loop {
+            match self.queue.publish(message.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.max_attempts => return Err(e),
+                Err(e) => {
+                    tracing::warn!(
+                        queue = self.queue.name(),
+                        attempt,
+                        error = %e,
+                        "Audit publish failed, retrying"
+                    );
+                }
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Publish held messages in order, stopping at the first failure
+    async fn drain_pending(&self, pending: &mut VecDeque<Message>) -> InfraResult<()> {
+        while let Some(message) = pending.front() {
+            self.publish_with_retry(message).await?;
+            pending.pop_front();
+        }
+        Ok(())
+    }
+
+    fn hold(&self, pending: &mut VecDeque<Message>, message: Message) -> InfraResult<()> {
+        if pending.len() >= self.max_pending {
+            return Err(InfraError::MessageQueue {
+                queue: self.queue.name().to_string(),
+                operation: MqOperation::Publish,
+                message: "Audit redelivery buffer is full, event lost".to_string(),
+                context: None,
+                source: None,
+            });
+        }
+        pending.push_back(message);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditSink for MqSink {
+    async fn write(&self, event: &AuditEvent) -> InfraResult<()> {
+        let message = Self::to_message(event)?;
+        let mut pending = self.pending.lock().await;
+
+        if let Err(e) = self.drain_pending(&mut pending).await {
+            self.hold(&mut pending, message)?;
+            return Err(e);
+        }
+        if let Err(e) = self.publish_with_retry(&message).await {
+            self.hold(&mut pending, message)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    async fn flush(&self) -> InfraResult<()> {
+        let mut pending = self.pending.lock().await;
+        self.drain_pending(&mut pending).await
+    }
+
+    fn name(&self) -> &str {
+        "mq"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{AuditEventBuilder, EventType, Outcome};
+    use infra_mq::{Ack, MemoryQueue};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Queue that fails while `down` is set
+    struct FlakyQueue {
+        inner: MemoryQueue,
+        down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl Queue for FlakyQueue {
+        fn name(&self) -> &str {
+            self.inner.name()
+        }
+
+        async fn publish(&self, message: Message) -> InfraResult<()> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(InfraError::MessageQueue {
+                    queue: "flaky".to_string(),
+                    operation: MqOperation::Publish,
+                    message: "unavailable".to_string(),
+                    context: None,
+                    source: None,
+                });
+            }
+            self.inner.publish(message).await
+        }
+
+        async fn receive(&self) -> InfraResult<Option<Message>> {
+            self.inner.receive().await
+        }
+
+        async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>> {
+            self.inner.receive_timeout(timeout).await
+        }
+
+        async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()> {
+            self.inner.ack(message_id, ack).await
+        }
+
+        async fn len(&self) -> InfraResult<usize> {
+            self.inner.len().await
+        }
+
+        async fn purge(&self) -> InfraResult<usize> {
+            self.inner.purge().await
+        }
+    }
+
+    fn event() -> AuditEvent {
+        AuditEventBuilder::new(EventType::Security)
+            .action("login")
+            .outcome(Outcome::Denied)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_mq_sink_publishes() {
+        let queue = Arc::new(MemoryQueue::new("audit"));
+        let sink = MqSink::new(queue.clone());
+
+        let event = event();
+        sink.write(&event).await.unwrap();
+
+        let message = queue.receive().await.unwrap().unwrap();
+        assert_eq!(message.header(HEADER_EVENT_ID).map(String::as_str), Some(event.id()));
+        assert_eq!(message.header(HEADER_SEVERITY).map(String::as_str), Some("critical"));
+        let decoded: AuditEvent = message.body_json().unwrap();
+        assert_eq!(decoded.action(), "login");
+    }
+
+    #[tokio::test]
+    async fn test_mq_sink_redelivers_after_outage() {
+        let queue = Arc::new(FlakyQueue {
+            inner: MemoryQueue::new("audit"),
+            down: AtomicBool::new(true),
+        });
+        let sink = MqSink::new(queue.clone())
+            .with_max_attempts(2)
+            .with_retry_delay(Duration::from_millis(1));
+
+        assert!(sink.write(&event()).await.is_err());
+        assert_eq!(sink.pending().await, 1);
+
+        queue.down.store(false, Ordering::SeqCst);
+        sink.flush().await.unwrap();
+        assert_eq!(sink.pending().await, 0);
+        assert_eq!(queue.len().await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+//! Audit event querying.
+
+use crate::event::{AuditEvent, EventType, Outcome, Severity};
+use crate::retention::{self, ExportFormat};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use infra_errors::InfraResult;
+use std::ops::Range;
+
+/// Default page size for queries
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Audit query filter
+#[derive(Debug, Clone)]
+pub struct AuditQuery {
+    /// Only events at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only events before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Only events by this actor ID
+    pub actor_id: Option<String>,
+    /// Only events of these types (empty matches all)
+    pub event_types: Vec<EventType>,
+    /// Only events with this outcome
+    pub outcome: Option<Outcome>,
+    /// Only events at or above this severity
+    pub min_severity: Option<Severity>,
+    /// Only events affecting this resource
+    pub resource: Option<String>,
+    /// Only events affecting this resource ID
+    pub resource_id: Option<String>,
+    /// Number of matching events to skip
+    pub offset: usize,
+    /// Maximum number of events to return
+    pub limit: usize,
+}
+
+impl Default for AuditQuery {
+    fn default() -> Self {
+        Self {
+            from: None,
+            to: None,
+            actor_id: None,
+            event_types: Vec::new(),
+            outcome: None,
+            min_severity: None,
+            resource: None,
+            resource_id: None,
+            offset: 0,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+impl AuditQuery {
+    /// Create a query matching all events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict to a time range (`from` inclusive, `to` exclusive)
+    pub fn time_range(mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Restrict to events at or after a time
+    pub fn since(mut self, from: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Restrict to events before a time
+    pub fn until(mut self, to: DateTime<Utc>) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Restrict to an actor
+    pub fn actor(mut self, actor_id: impl Into<String>) -> Self {
+        self.actor_id = Some(actor_id.into());
+        self
+    }
+
+    /// Restrict to an event type (may be called multiple times)
+    pub fn event_type(mut self, event_type: EventType) -> Self {
+        self.event_types.push(event_type);
+        self
+    }
+
+    /// Restrict to an outcome
+    pub fn outcome(mut self, outcome: Outcome) -> Self {
+        self.outcome = Some(outcome);
+        self
+    }
+
+    /// Restrict to events at or above a severity
+    pub fn min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = Some(severity);
+        self
+    }
+
+    /// Restrict to a resource
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resource = Some(resource.into());
+        self
+    }
+
+    /// Restrict to a resource ID
+    pub fn resource_id(mut self, id: impl Into<String>) -> Self {
+        self.resource_id = Some(id.into());
+        self
+    }
+
+    /// Skip the first `offset` matching events
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Return at most `limit` events
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Check whether an event matches the filter (ignores pagination)
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        if let Some(from) = self.from {
+            if event.timestamp() < from {
+                return false;
+            }
+        }
+        if let Some(to) = self.to {
+            if event.timestamp() >= to {
+                return false;
+            }
+        }
+        if let Some(actor_id) = &self.actor_id {
+            if event.actor().map(|a| a.id.as_str()) != Some(actor_id.as_str()) {
+                return false;
+            }
+        }
+        if !self.event_types.is_empty() && !self.event_types.contains(&event.event_type()) {
+            return false;
+        }
+        if let Some(outcome) = self.outcome {
+            if event.outcome() != outcome {
+                return false;
+            }
+        }
+        if let Some(severity) = self.min_severity {
+            if event.severity() < severity {
+                return false;
+            }
+        }
+        if let Some(resource) = &self.resource {
+            if event.resource() != Some(resource.as_str()) {
+                return false;
+            }
+        }
+        if let Some(resource_id) = &self.resource_id {
+            if event.resource_id() != Some(resource_id.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Apply the filter and pagination to a stream of events
+    pub fn apply<'a, I>(&self, events: I) -> AuditPage
+    where
+        I: IntoIterator<Item = &'a AuditEvent>,
+    {
+        let mut total = 0;
+        let mut page = Vec::new();
+        for event in events.into_iter().filter(|e| self.matches(e)) {
+            if total >= self.offset && page.len() < self.limit {
+                page.push(event.clone());
+            }
+            total += 1;
+        }
+        AuditPage {
+            events: page,
+            total,
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+/// A page of query results
+#[derive(Debug, Clone)]
+pub struct AuditPage {
+    /// Events in this page
+    pub events: Vec<AuditEvent>,
+    /// Total number of matching events
+    pub total: usize,
+    /// Offset of this page
+    pub offset: usize,
+    /// Page size limit
+    pub limit: usize,
+}
+
+impl AuditPage {
+    /// Check if more results are available
+    pub fn has_more(&self) -> bool {
+        self.offset + self.events.len() < self.total
+    }
+
+    /// Query for the next page, if any
+    pub fn next_query(&self, query: &AuditQuery) -> Option<AuditQuery> {
+        if self.has_more() {
+            Some(query.clone().offset(self.offset + self.events.len()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Sink that supports reading events back
+#[async_trait]
+pub trait QueryableSink: Send + Sync {
+    /// Query stored events in chronological order
+    async fn query(&self, query: &AuditQuery) -> InfraResult<AuditPage>;
+
+    /// Count stored events matching a query
+    async fn count_matching(&self, query: &AuditQuery) -> InfraResult<usize> {
+        Ok(self.query(&query.clone().limit(0)).await?.total)
+    }
+
+    /// Export events in a time range for SIEM ingestion
+    async fn export(
+        &self,
+        range: Range<DateTime<Utc>>,
+        format: ExportFormat,
+    ) -> InfraResult<String> {
+        retention::export(self, range, format).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Actor;
+    use crate::event::AuditEventBuilder;
+
+    fn event(event_type: EventType, actor: &str, outcome: Outcome) -> AuditEvent {
+        AuditEventBuilder::new(event_type)
+            .action("test")
+            .actor(Actor::user(actor))
+            .outcome(outcome)
+            .resource("documents")
+            .build()
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let e = event(EventType::DataAccess, "alice", Outcome::Success);
+
+        assert!(AuditQuery::new().matches(&e));
+        assert!(AuditQuery::new().actor("alice").matches(&e));
+        assert!(!AuditQuery::new().actor("bob").matches(&e));
+        assert!(AuditQuery::new()
+            .event_type(EventType::Security)
+            .event_type(EventType::DataAccess)
+            .matches(&e));
+        assert!(!AuditQuery::new().outcome(Outcome::Denied).matches(&e));
+        assert!(AuditQuery::new().resource("documents").matches(&e));
+        assert!(!AuditQuery::new().until(e.timestamp()).matches(&e));
+        assert!(AuditQuery::new().since(e.timestamp()).matches(&e));
+    }
+
+    #[test]
+    fn test_query_pagination() {
+        let events: Vec<_> = (0..5)
+            .map(|_| event(EventType::System, "svc", Outcome::Success))
+            .collect();
+
+        let query = AuditQuery::new().limit(2);
+        let page = query.apply(&events);
+        assert_eq!(page.events.len(), 2);
+        assert_eq!(page.total, 5);
+        assert!(page.has_more());
+
+        let next = page.next_query(&query).unwrap();
+        assert_eq!(next.offset, 2);
+
+        let last = query.clone().offset(4).apply(&events);
+        assert_eq!(last.events.len(), 1);
+        assert!(!last.has_more());
+    }
+}
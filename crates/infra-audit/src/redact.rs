@@ -0,0 +1,104 @@
+//! Redaction of sensitive data before events reach sinks.
+
+use crate::event::AuditEvent;
+use infra_json::Masker;
+
+/// Redaction stage applied to every event before it is written
+pub trait Redactor: Send + Sync {
+    /// Redact sensitive data from an event in place
+    fn redact(&self, event: &mut AuditEvent);
+}
+
+/// Redactor that leaves events unchanged
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopRedactor;
+
+impl Redactor for NoopRedactor {
+    fn redact(&self, _event: &mut AuditEvent) {}
+}
+
+/// Redactor backed by an infra-json [`Masker`]
+///
+/// Only the free-form parts of an event are masked: metadata, where prompts
+/// and completions are recorded, and the error message. Identifiers such as
+/// the actor and resource IDs are left intact so events remain queryable.
+/// Paths are relative to the metadata, e.g. `prompt` or `request.*.body`.
+#[derive(Debug, Clone)]
+pub struct MaskingRedactor {
+    masker: Masker,
+}
+
+impl MaskingRedactor {
+    /// Create a redactor from a masker
+    pub fn new(masker: Masker) -> Self {
+        Self { masker }
+    }
+
+    /// Create a redactor masking emails, API keys and bearer tokens
+    pub fn pii() -> Self {
+        Self::new(Masker::pii())
+    }
+
+    /// Mask the value at a path
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.masker = self.masker.with_path(path);
+        self
+    }
+}
+
+impl Default for MaskingRedactor {
+    fn default() -> Self {
+        Self::pii()
+    }
+}
+
+impl Redactor for MaskingRedactor {
+    fn redact(&self, event: &mut AuditEvent) {
+        if self.masker.is_empty() {
+            return;
+        }
+        for (key, value) in event.metadata_mut() {
+            self.masker.mask_member(key, value);
+        }
+        if let Some(error) = event.error_mut() {
+            *error = self.masker.mask_str(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Actor;
+    use crate::event::{AuditEventBuilder, EventType, Outcome};
+
+    #[test]
+    fn test_masking_redactor() {
+        let mut event = AuditEventBuilder::new(EventType::Llm)
+            .action("llm.prompt_submitted")
+            .outcome(Outcome::Failure)
+            .actor(Actor::user("jane@example.com").with_ip("10.0.0.1"))
+            .resource_id("sk-abcdefghijklmnopqrstuv")
+            .metadata("prompt", "email me at jane@example.com")
+            .metadata("request", serde_json::json!({"api_key": "k-1", "model": "gpt-4"}))
+            .error("rejected bearer abc.def")
+            .build();
+        let id = event.id().to_string();
+
+        MaskingRedactor::pii()
+            .with_path("request.api_key")
+            .redact(&mut event);
+
+        assert_eq!(event.id(), id);
+        assert_eq!(
+            event.metadata()["prompt"],
+            serde_json::json!("email me at [REDACTED]")
+        );
+        assert_eq!(event.metadata()["request"]["api_key"], "[REDACTED]");
+        assert_eq!(event.metadata()["request"]["model"], "gpt-4");
+        assert_eq!(event.error(), Some("rejected [REDACTED]"));
+        // Identifiers stay queryable
+        assert_eq!(event.actor().unwrap().id, "jane@example.com");
+        assert_eq!(event.resource_id(), Some("sk-abcdefghijklmnopqrstuv"));
+    }
+}
@@ -0,0 +1,439 @@
+//! Retention, archival and export of audit events.
+
+use crate::event::{AuditEvent, Severity};
+use crate::query::{AuditQuery, QueryableSink};
+use crate::sink::io_error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use infra_errors::{InfraResult, IoOperation};
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Retention policy
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// Maximum event age
+    pub max_age: Option<Duration>,
+    /// Maximum number of retained events
+    pub max_events: Option<usize>,
+    /// Maximum serialized size of retained events in bytes
+    pub max_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Create a policy that retains everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum event age
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Set the maximum number of events
+    pub fn max_events(mut self, count: usize) -> Self {
+        self.max_events = Some(count);
+        self
+    }
+
+    /// Set the maximum serialized size in bytes
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Split chronologically ordered events into `(kept, expired)`
+    pub fn partition(
+        &self,
+        events: Vec<AuditEvent>,
+        now: DateTime<Utc>,
+    ) -> (Vec<AuditEvent>, Vec<AuditEvent>) {
+        let cutoff = self
+            .max_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+            .map(|age| now - age);
+
+        let (mut expired, mut kept): (Vec<_>, Vec<_>) = events
+            .into_iter()
+            .partition(|e| cutoff.is_some_and(|c| e.timestamp() < c));
+
+        if let Some(max) = self.max_events {
+            if kept.len() > max {
+                let excess = kept.len() - max;
+                expired.extend(kept.drain(..excess));
+            }
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            let sizes: Vec<u64> = kept.iter().map(serialized_len).collect();
+            let mut total: u64 = sizes.iter().sum();
+            let mut excess = 0;
+            while total > max_bytes && excess < kept.len() {
+                total -= sizes[excess];
+                excess += 1;
+            }
+            expired.extend(kept.drain(..excess));
+        }
+
+        expired.sort_by_key(AuditEvent::timestamp);
+        (kept, expired)
+    }
+}
+
+fn serialized_len(event: &AuditEvent) -> u64 {
+    serde_json::to_vec(event).map_or(0, |v| v.len() as u64 + 1)
+}
+
+/// Sink that supports pruning old events
+#[async_trait]
+pub trait RetentionSink: Send + Sync {
+    /// Get the events violating the policy, without removing them
+    async fn expired(&self, policy: &RetentionPolicy) -> InfraResult<Vec<AuditEvent>>;
+
+    /// Remove events by ID, returning the number of removed events
+    async fn remove(&self, events: &[AuditEvent]) -> InfraResult<usize>;
+
+    /// Remove events violating the policy, returning the removed events
+    async fn prune(&self, policy: &RetentionPolicy) -> InfraResult<Vec<AuditEvent>> {
+        let expired = self.expired(policy).await?;
+        self.remove(&expired).await?;
+        Ok(expired)
+    }
+}
+
+/// Archives pruned events to gzip-compressed JSON lines files
+#[derive(Debug, Clone)]
+pub struct FileArchiver {
+    dir: PathBuf,
+    prefix: String,
+}
+
+impl FileArchiver {
+    /// Create an archiver writing into `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            prefix: "audit".to_string(),
+        }
+    }
+
+    /// Set the archive file name prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Get the archive directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Archive events, returning the archive path
+    pub async fn archive(&self, events: &[AuditEvent]) -> InfraResult<Option<PathBuf>> {
+        if events.is_empty() {
+            return Ok(None);
+        }
+
+        let data = export_events(events, ExportFormat::Jsonl)?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(data.as_bytes())
+            .map_err(|e| io_error(IoOperation::Write, &self.dir, e))?;
+        let compressed = encoder
+            .finish()
+            .map_err(|e| io_error(IoOperation::Write, &self.dir, e))?;
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| io_error(IoOperation::Create, &self.dir, e))?;
+        let name = format!(
+            "{}-{}-{}.jsonl.gz",
+            self.prefix,
+            events[0].timestamp().format("%Y%m%dT%H%M%S"),
+            events[events.len() - 1].id()
+        );
+        let path = self.dir.join(name);
+        tokio::fs::write(&path, compressed)
+            .await
+            .map_err(|e| io_error(IoOperation::Write, &path, e))?;
+        Ok(Some(path))
+    }
+}
+
+/// Result of a retention run
+#[derive(Debug, Clone, Default)]
+pub struct RetentionReport {
+    /// Number of pruned events
+    pub pruned: usize,
+    /// Archive file, if events were archived
+    pub archive: Option<PathBuf>,
+}
+
+/// Applies a retention policy to a sink, optionally archiving pruned events
+pub struct RetentionManager {
+    sink: Arc<dyn RetentionSink>,
+    policy: RetentionPolicy,
+    archiver: Option<FileArchiver>,
+}
+
+impl RetentionManager {
+    /// Create a new retention manager
+    pub fn new(sink: Arc<dyn RetentionSink>, policy: RetentionPolicy) -> Self {
+        Self {
+            sink,
+            policy,
+            archiver: None,
+        }
+    }
+
+    /// Archive pruned events
+    pub fn with_archiver(mut self, archiver: FileArchiver) -> Self {
+        self.archiver = Some(archiver);
+        self
+    }
+
+    /// Prune once
+    ///
+    /// Expired events are archived before they are removed, so an archive
+    /// failure leaves them in the sink for the next run.
+    pub async fn run_once(&self) -> InfraResult<RetentionReport> {
+        let Some(archiver) = &self.archiver else {
+            let pruned = self.sink.prune(&self.policy).await?;
+            return Ok(RetentionReport {
+                pruned: pruned.len(),
+                archive: None,
+            });
+        };
+
+        let expired = self.sink.expired(&self.policy).await?;
+        let archive = archiver.archive(&expired).await?;
+        let pruned = self.sink.remove(&expired).await?;
+        Ok(RetentionReport { pruned, archive })
+    }
+
+    /// Prune periodically in a background task
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.run_once().await {
+                    Ok(report) if report.pruned > 0 => {
+                        tracing::info!(pruned = report.pruned, "Pruned audit events");
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!(error = %e, "Audit retention run failed"),
+                }
+            }
+        })
+    }
+}
+
+/// Export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// JSON lines
+    Jsonl,
+    /// ArcSight Common Event Format
+    Cef,
+}
+
+/// Export events in chronological order
+pub async fn export<S>(
+    sink: &S,
+    range: Range<DateTime<Utc>>,
+    format: ExportFormat,
+) -> InfraResult<String>
+where
+    S: QueryableSink + ?Sized,
+{
+    let mut query = AuditQuery::new().time_range(range.start, range.end);
+    let mut out = String::new();
+    loop {
+        let page = sink.query(&query).await?;
+        out.push_str(&export_events(&page.events, format)?);
+        match page.next_query(&query) {
+            Some(next) => query = next,
+            None => break,
+        }
+    }
+    Ok(out)
+}
+
+/// Render events in an export format
+pub fn export_events(events: &[AuditEvent], format: ExportFormat) -> InfraResult<String> {
+    let mut out = String::new();
+    for event in events {
+        match format {
+            ExportFormat::Jsonl => out.push_str(&serde_json::to_string(event)?),
+            ExportFormat::Cef => out.push_str(&to_cef(event)),
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn to_cef(event: &AuditEvent) -> String {
+    let event_type = serde_json::to_value(event.event_type())
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    let outcome = serde_json::to_value(event.outcome())
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default();
+    let severity = match event.severity() {
+        Severity::Info => 3,
+        Severity::Warning => 6,
+        Severity::Critical => 9,
+    };
+
+    let mut ext = vec![
+        ("rt", event.timestamp().timestamp_millis().to_string()),
+        ("externalId", event.id().to_string()),
+        ("cat", event_type.clone()),
+        ("act", event.action().to_string()),
+        ("outcome", outcome),
+    ];
+    if let Some(actor) = event.actor() {
+        ext.push(("suser", actor.id.clone()));
+        if let Some(ip) = &actor.ip_address {
+            ext.push(("src", ip.clone()));
+        }
+    }
+    if let Some(resource) = event.resource() {
+        ext.push(("cs1Label", "resource".to_string()));
+        ext.push(("cs1", resource.to_string()));
+    }
+    if let Some(id) = event.resource_id() {
+        ext.push(("cs2Label", "resourceId".to_string()));
+        ext.push(("cs2", id.to_string()));
+    }
+    if let Some(request_id) = &event.context().request_id {
+        ext.push(("requestContext", request_id.clone()));
+    }
+    if let Some(error) = event.error() {
+        ext.push(("reason", error.to_string()));
+    }
+
+    let extension = ext
+        .iter()
+        .map(|(k, v)| format!("{k}={}", cef_escape_extension(v)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "CEF:0|LLM-Dev-Ops|infra-audit|{}|{}|{}|{}|{}",
+        env!("CARGO_PKG_VERSION"),
+        cef_escape_header(&event_type),
+        cef_escape_header(event.action()),
+        severity,
+        extension
+    )
+}
+
+fn cef_escape_header(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn cef_escape_extension(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Actor;
+    use crate::event::{AuditEventBuilder, EventType, Outcome};
+    use crate::sink::{AuditSink, MemorySink};
+
+    fn event(action: &str) -> AuditEvent {
+        AuditEventBuilder::new(EventType::DataAccess)
+            .action(action)
+            .actor(Actor::user("alice"))
+            .outcome(Outcome::Denied)
+            .build()
+    }
+
+    #[test]
+    fn test_partition_by_count_and_age() {
+        let events: Vec<_> = (0..5).map(|i| event(&format!("a{i}"))).collect();
+
+        let (kept, expired) = RetentionPolicy::new().max_events(2).partition(events.clone(), Utc::now());
+        assert_eq!(kept.len(), 2);
+        assert_eq!(expired.len(), 3);
+        assert_eq!(kept[0].action(), "a3");
+
+        let later = Utc::now() + chrono::Duration::hours(2);
+        let (kept, expired) = RetentionPolicy::new()
+            .max_age(Duration::from_secs(3600))
+            .partition(events, later);
+        assert!(kept.is_empty());
+        assert_eq!(expired.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_retention_manager_archives() {
+        let sink = Arc::new(MemorySink::new());
+        for i in 0..4 {
+            sink.write(&event(&format!("a{i}"))).await.unwrap();
+        }
+
+        let dir = std::env::temp_dir().join(format!("audit-archive-{}", infra_id::generate_short_id()));
+        let manager = RetentionManager::new(sink.clone(), RetentionPolicy::new().max_events(1))
+            .with_archiver(FileArchiver::new(&dir));
+
+        let report = manager.run_once().await.unwrap();
+        assert_eq!(report.pruned, 3);
+        assert_eq!(sink.count().await, 1);
+        assert!(report.archive.unwrap().exists());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_archive_failure_keeps_events() {
+        let sink = Arc::new(MemorySink::new());
+        for i in 0..4 {
+            sink.write(&event(&format!("a{i}"))).await.unwrap();
+        }
+
+        // A file where the archive directory should be
+        let path = std::env::temp_dir().join(format!("audit-archive-{}", infra_id::generate_short_id()));
+        tokio::fs::write(&path, b"").await.unwrap();
+        let manager = RetentionManager::new(sink.clone(), RetentionPolicy::new().max_events(1))
+            .with_archiver(FileArchiver::new(&path));
+
+        assert!(manager.run_once().await.is_err());
+        assert_eq!(sink.count().await, 4);
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let sink = MemorySink::new();
+        sink.write(&event("read|secret=1")).await.unwrap();
+        let range = Utc::now() - chrono::Duration::minutes(1)..Utc::now() + chrono::Duration::minutes(1);
+
+        let jsonl = sink.export(range.clone(), ExportFormat::Jsonl).await.unwrap();
+        assert_eq!(jsonl.lines().count(), 1);
+
+        let cef = export(&sink, range, ExportFormat::Cef).await.unwrap();
+        assert!(cef.starts_with("CEF:0|LLM-Dev-Ops|infra-audit|"));
+        assert!(cef.contains("|read\\|secret=1|6|"));
+        assert!(cef.contains("act=read|secret\\=1"));
+        assert!(cef.contains("suser=alice"));
+    }
+}
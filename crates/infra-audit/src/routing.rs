@@ -0,0 +1,140 @@
+//! Severity and event-type based routing of audit events.
+
+use crate::event::{AuditEvent, EventType, Severity};
+use crate::sink::AuditSink;
+use async_trait::async_trait;
+use infra_errors::InfraResult;
+use std::sync::Arc;
+
+/// A routing rule sending matching events to a sink
+#[derive(Clone)]
+pub struct Route {
+    sink: Arc<dyn AuditSink>,
+    min_severity: Severity,
+    event_types: Vec<EventType>,
+}
+
+impl Route {
+    /// Route every event to a sink
+    pub fn all(sink: Arc<dyn AuditSink>) -> Self {
+        Self {
+            sink,
+            min_severity: Severity::Info,
+            event_types: Vec::new(),
+        }
+    }
+
+    /// Route events at or above a severity to a sink
+    pub fn min_severity(severity: Severity, sink: Arc<dyn AuditSink>) -> Self {
+        Self::all(sink).with_min_severity(severity)
+    }
+
+    /// Require a minimum severity
+    pub fn with_min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    /// Restrict to an event type (may be called multiple times)
+    pub fn with_event_type(mut self, event_type: EventType) -> Self {
+        self.event_types.push(event_type);
+        self
+    }
+
+    /// Check whether an event matches this route
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        event.severity() >= self.min_severity
+            && (self.event_types.is_empty() || self.event_types.contains(&event.event_type()))
+    }
+}
+
+/// Sink fanning out events to every matching route
+///
+/// Every matching route is written even if an earlier one fails; the
+/// first error is returned.
+#[derive(Clone, Default)]
+pub struct RoutingSink {
+    routes: Vec<Route>,
+}
+
+impl RoutingSink {
+    /// Create an empty routing sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a route
+    pub fn route(mut self, route: Route) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    /// Get the number of routes
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Check if there are no routes
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+#[async_trait]
+impl AuditSink for RoutingSink {
+    async fn write(&self, event: &AuditEvent) -> InfraResult<()> {
+        let mut result = Ok(());
+        for route in self.routes.iter().filter(|r| r.matches(event)) {
+            if let Err(e) = route.sink.write(event).await {
+                tracing::error!(sink = route.sink.name(), error = %e, "Audit route failed");
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    async fn flush(&self) -> InfraResult<()> {
+        for route in &self.routes {
+            route.sink.flush().await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "routing"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{AuditEventBuilder, Outcome};
+    use crate::sink::MemorySink;
+
+    #[tokio::test]
+    async fn test_routing_by_severity_and_type() {
+        let all = Arc::new(MemorySink::new());
+        let critical = Arc::new(MemorySink::new());
+        let auth = Arc::new(MemorySink::new());
+
+        let router = RoutingSink::new()
+            .route(Route::all(all.clone()))
+            .route(Route::min_severity(Severity::Critical, critical.clone()))
+            .route(Route::all(auth.clone()).with_event_type(EventType::Authentication));
+
+        let events = [
+            AuditEventBuilder::new(EventType::System).outcome(Outcome::Success).build(),
+            AuditEventBuilder::new(EventType::Security).outcome(Outcome::Denied).build(),
+            AuditEventBuilder::new(EventType::Authentication).outcome(Outcome::Success).build(),
+        ];
+        for event in &events {
+            router.write(event).await.unwrap();
+        }
+
+        assert_eq!(all.count().await, 3);
+        assert_eq!(critical.count().await, 1);
+        assert_eq!(auth.count().await, 1);
+    }
+}
@@ -1,10 +1,15 @@
 //! Audit sinks.
 
 use crate::event::AuditEvent;
+use crate::query::{AuditPage, AuditQuery, QueryableSink};
+use crate::retention::{RetentionPolicy, RetentionSink};
 use async_trait::async_trait;
-use infra_errors::InfraResult;
+use infra_errors::{InfraError, InfraResult, IoOperation};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
 
 /// Audit sink trait
 #[async_trait]
@@ -12,6 +17,14 @@ pub trait AuditSink: Send + Sync {
     /// Write an event
     async fn write(&self, event: &AuditEvent) -> InfraResult<()>;
 
+    /// Write a batch of events
+    async fn write_batch(&self, events: &[AuditEvent]) -> InfraResult<()> {
+        for event in events {
+            self.write(event).await?;
+        }
+        Ok(())
+    }
+
     /// Flush pending events
     async fn flush(&self) -> InfraResult<()> {
         Ok(())
@@ -133,6 +146,178 @@ impl AuditSink for MemorySink {
     }
 }
 
+#[async_trait]
+impl QueryableSink for MemorySink {
+    async fn query(&self, query: &AuditQuery) -> InfraResult<AuditPage> {
+        let events = self.events.read().await;
+        Ok(query.apply(events.iter()))
+    }
+}
+
+#[async_trait]
+impl RetentionSink for MemorySink {
+    async fn expired(&self, policy: &RetentionPolicy) -> InfraResult<Vec<AuditEvent>> {
+        let events = self.events.read().await.clone();
+        Ok(policy.partition(events, chrono::Utc::now()).1)
+    }
+
+    async fn remove(&self, events: &[AuditEvent]) -> InfraResult<usize> {
+        let ids: HashSet<&str> = events.iter().map(AuditEvent::id).collect();
+        let mut stored = self.events.write().await;
+        let before = stored.len();
+        stored.retain(|e| !ids.contains(e.id()));
+        Ok(before - stored.len())
+    }
+
+    async fn prune(&self, policy: &RetentionPolicy) -> InfraResult<Vec<AuditEvent>> {
+        let mut events = self.events.write().await;
+        let (kept, expired) = policy.partition(std::mem::take(&mut *events), chrono::Utc::now());
+        *events = kept;
+        Ok(expired)
+    }
+}
+
+/// File sink (appends events as JSON lines)
+pub struct FileSink {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileSink {
+    /// Create a new file sink writing to `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Get the file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read all events from the file
+    pub async fn read_all(&self) -> InfraResult<Vec<AuditEvent>> {
+        let _guard = self.lock.lock().await;
+        self.read_locked().await
+    }
+
+    async fn read_locked(&self) -> InfraResult<Vec<AuditEvent>> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(IoOperation::Read, &self.path, e)),
+        };
+
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(InfraError::from))
+            .collect()
+    }
+
+    async fn rewrite_locked(&self, events: &[AuditEvent]) -> InfraResult<()> {
+        let mut buf = String::new();
+        for event in events {
+            buf.push_str(&serde_json::to_string(event)?);
+            buf.push('\n');
+        }
+
+        // Rewrite through a temporary file so a crash never truncates the log
+        let tmp = self.path.with_extension("prune.tmp");
+        tokio::fs::write(&tmp, buf)
+            .await
+            .map_err(|e| io_error(IoOperation::Write, &tmp, e))?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .map_err(|e| io_error(IoOperation::Move, &self.path, e))
+    }
+}
+
+#[async_trait]
+impl AuditSink for FileSink {
+    async fn write(&self, event: &AuditEvent) -> InfraResult<()> {
+        self.write_batch(std::slice::from_ref(event)).await
+    }
+
+    async fn write_batch(&self, events: &[AuditEvent]) -> InfraResult<()> {
+        let mut buf = String::new();
+        for event in events {
+            buf.push_str(&serde_json::to_string(event)?);
+            buf.push('\n');
+        }
+
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| io_error(IoOperation::Write, &self.path, e))?;
+        file.write_all(buf.as_bytes())
+            .await
+            .map_err(|e| io_error(IoOperation::Write, &self.path, e))?;
+        file.flush()
+            .await
+            .map_err(|e| io_error(IoOperation::Write, &self.path, e))
+    }
+
+    fn name(&self) -> &str {
+        "file"
+    }
+}
+
+#[async_trait]
+impl RetentionSink for FileSink {
+    async fn expired(&self, policy: &RetentionPolicy) -> InfraResult<Vec<AuditEvent>> {
+        let events = self.read_all().await?;
+        Ok(policy.partition(events, chrono::Utc::now()).1)
+    }
+
+    async fn remove(&self, events: &[AuditEvent]) -> InfraResult<usize> {
+        let ids: HashSet<&str> = events.iter().map(AuditEvent::id).collect();
+        let _guard = self.lock.lock().await;
+        let (removed, kept): (Vec<_>, Vec<_>) = self
+            .read_locked()
+            .await?
+            .into_iter()
+            .partition(|e| ids.contains(e.id()));
+        if !removed.is_empty() {
+            self.rewrite_locked(&kept).await?;
+        }
+        Ok(removed.len())
+    }
+
+    async fn prune(&self, policy: &RetentionPolicy) -> InfraResult<Vec<AuditEvent>> {
+        let _guard = self.lock.lock().await;
+        let events = self.read_locked().await?;
+        let (kept, expired) = policy.partition(events, chrono::Utc::now());
+        if !expired.is_empty() {
+            self.rewrite_locked(&kept).await?;
+        }
+        Ok(expired)
+    }
+}
+
+#[async_trait]
+impl QueryableSink for FileSink {
+    async fn query(&self, query: &AuditQuery) -> InfraResult<AuditPage> {
+        let events = self.read_all().await?;
+        Ok(query.apply(events.iter()))
+    }
+}
+
+pub(crate) fn io_error(operation: IoOperation, path: &Path, err: std::io::Error) -> InfraError {
+    InfraError::Io {
+        operation,
+        path: Some(path.to_path_buf()),
+        message: err.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,4 +354,61 @@ mod tests {
         // Should only have last 2 events
         assert_eq!(sink.count().await, 2);
     }
+
+    #[tokio::test]
+    async fn test_memory_sink_query() {
+        let sink = MemorySink::new();
+
+        for outcome in [Outcome::Success, Outcome::Denied, Outcome::Success] {
+            let event = AuditEventBuilder::new(EventType::Authorization)
+                .action("access")
+                .outcome(outcome)
+                .build();
+            sink.write(&event).await.unwrap();
+        }
+
+        let page = sink
+            .query(&AuditQuery::new().outcome(Outcome::Success))
+            .await
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(
+            sink.count_matching(&AuditQuery::new().outcome(Outcome::Denied))
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_sink_roundtrip() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", infra_id::generate_short_id()));
+        let sink = FileSink::new(&path);
+
+        for i in 0..3 {
+            let event = AuditEventBuilder::new(EventType::DataAccess)
+                .action(format!("read-{i}"))
+                .resource("users")
+                .outcome(Outcome::Success)
+                .build();
+            sink.write(&event).await.unwrap();
+        }
+
+        let page = sink
+            .query(&AuditQuery::new().resource("users").offset(1).limit(1))
+            .await
+            .unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.events.len(), 1);
+        assert_eq!(page.events[0].action(), "read-1");
+
+        let pruned = sink
+            .prune(&RetentionPolicy::new().max_events(1))
+            .await
+            .unwrap();
+        assert_eq!(pruned.len(), 2);
+        assert_eq!(sink.read_all().await.unwrap()[0].action(), "read-2");
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
 }
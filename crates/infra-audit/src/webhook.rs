@@ -0,0 +1,45 @@
+//! HTTP webhook sink.
+
+use crate::event::AuditEvent;
+use crate::sink::AuditSink;
+use async_trait::async_trait;
+use infra_errors::InfraResult;
+use infra_http::HttpClient;
+
+/// Sink posting each event as JSON to a webhook URL
+pub struct WebhookSink {
+    client: HttpClient,
+    url: String,
+}
+
+impl WebhookSink {
+    /// Create a webhook sink with a default client
+    pub fn new(url: impl Into<String>) -> InfraResult<Self> {
+        Ok(Self::with_client(HttpClient::new()?, url))
+    }
+
+    /// Create a webhook sink with a configured client
+    pub fn with_client(client: HttpClient, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+
+    /// Get the webhook URL
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl AuditSink for WebhookSink {
+    async fn write(&self, event: &AuditEvent) -> InfraResult<()> {
+        self.client.post(&self.url, event).await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "webhook"
+    }
+}
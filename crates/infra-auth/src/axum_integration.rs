@@ -0,0 +1,760 @@
+//! Axum integration.
+//!
+//! [`AuthLayer`] authenticates requests with bearer tokens, API keys or
+//! mutual TLS client certificates and
+//! stores the resulting [`AuthContext`] and [`Identity`] in the request
+//! extensions, where handlers extract them. [`RequireLayer`] guards routes
+//! with a role, scope or policy check. Rejections are JSON bodies of the
+//! form `{"error": "invalid_token", "message": "Invalid token"}` with status
+//! 401 or 403, or 503 when credentials cannot be checked because a provider
+//! is unavailable.
+//!
+//! ```ignore
+//! let app = Router::new()
+//!     .route("/models", get(list_models))
+//!     .route_layer(RequireLayer::scope("models", Action::Read))
+//!     .layer(AuthLayer::new().with_bearer(provider));
+//! ```
+
+use crate::condition::RequestContext;
+use crate::identity::{AsyncIdentityProvider, Identity};
+use crate::middleware::{bearer_token, AuthContext, AuthError};
+use crate::mtls::{ClientCertificate, MtlsIdentityProvider, XFCC_HEADER};
+use crate::permission::{Action, ScopeSet};
+use crate::policy::PolicyEngine;
+use crate::roles::{RoleGraph, RoleResolver};
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts, Request};
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Default header carrying API keys
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+impl AuthError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AuthError::InsufficientPermissions => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            AuthError::Missing => "missing_credentials",
+            AuthError::InvalidToken => "invalid_token",
+            AuthError::TokenExpired => "token_expired",
+            AuthError::InsufficientPermissions => "insufficient_permissions",
+            AuthError::Other(_) => "unauthorized",
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = serde_json::json!({
+            "error": self.code(),
+            "message": self.to_string(),
+        });
+        let mut response = (status, Json(body)).into_response();
+
+        let challenge = match self {
+            AuthError::Missing => Some("Bearer"),
+            AuthError::InvalidToken | AuthError::TokenExpired => {
+                Some("Bearer error=\"invalid_token\"")
+            }
+            _ => None,
+        };
+        if let Some(challenge) = challenge {
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static(challenge));
+        }
+        response
+    }
+}
+
+/// Why [`AuthLayer`] turned a request away
+enum Rejection {
+    /// The credentials were missing or not accepted
+    Denied(AuthError),
+    /// The credentials could not be checked, such as during a provider outage
+    Unavailable,
+}
+
+impl From<AuthError> for Rejection {
+    fn from(err: AuthError) -> Self {
+        Rejection::Denied(err)
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        match self {
+            Rejection::Denied(err) => err.into_response(),
+            Rejection::Unavailable => {
+                let body = serde_json::json!({
+                    "error": "auth_unavailable",
+                    "message": "Authentication is temporarily unavailable",
+                });
+                (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+/// Map a provider's verification failure to a rejection
+///
+/// Failures about the credentials themselves deny the request, while
+/// anything else means the provider could not decide.
+fn rejection(err: &InfraError) -> Rejection {
+    match err {
+        InfraError::Auth {
+            kind: AuthErrorKind::TokenExpired,
+            ..
+        } => AuthError::TokenExpired.into(),
+        InfraError::Auth { .. }
+        | InfraError::Validation { .. }
+        | InfraError::Crypto { .. }
+        | InfraError::Serialization { .. }
+        | InfraError::NotFound { .. } => AuthError::InvalidToken.into(),
+        _ => {
+            tracing::warn!(error = %err, "Could not verify credentials");
+            Rejection::Unavailable
+        }
+    }
+}
+
+/// Layer authenticating requests
+///
+/// Credentials are read from an `Authorization: Bearer` header, or from the
+/// API key header, and verified by the matching provider. Without either,
+/// services are identified by their [`ClientCertificate`], which a TLS
+/// acceptor puts in the request extensions either directly or as
+/// [`ConnectInfo`] (serving with
+/// `into_make_service_with_connect_info::<ClientCertificate>()`), or which
+/// a trusted proxy forwards. The certificate is then left in the request
+/// extensions for handlers to extract. Requests without credentials are
+/// rejected unless anonymous access is allowed, in which case handlers see
+/// an unauthenticated [`AuthContext`]. A malformed bearer header is always
+/// rejected rather than treated as missing credentials.
+#[derive(Clone)]
+pub struct AuthLayer {
+    bearer: Option<Arc<dyn AsyncIdentityProvider>>,
+    api_key: Option<Arc<dyn AsyncIdentityProvider>>,
+    api_key_header: HeaderName,
+    mtls: Option<Arc<MtlsIdentityProvider>>,
+    trust_forwarded_certs: bool,
+    roles: Option<Arc<RoleResolver>>,
+    allow_anonymous: bool,
+}
+
+impl AuthLayer {
+    /// Create a layer accepting no credentials until providers are added
+    pub fn new() -> Self {
+        Self {
+            bearer: None,
+            api_key: None,
+            api_key_header: HeaderName::from_static(API_KEY_HEADER),
+            mtls: None,
+            trust_forwarded_certs: false,
+            roles: None,
+            allow_anonymous: false,
+        }
+    }
+
+    /// Verify bearer tokens with `provider`
+    pub fn with_bearer(mut self, provider: Arc<dyn AsyncIdentityProvider>) -> Self {
+        self.bearer = Some(provider);
+        self
+    }
+
+    /// Verify API keys with `provider`
+    pub fn with_api_keys(mut self, provider: Arc<dyn AsyncIdentityProvider>) -> Self {
+        self.api_key = Some(provider);
+        self
+    }
+
+    /// Read API keys from `header` instead of `x-api-key`
+    pub fn with_api_key_header(mut self, header: HeaderName) -> Self {
+        self.api_key_header = header;
+        self
+    }
+
+    /// Identify services by their client certificates with `provider`
+    pub fn with_mtls(mut self, provider: Arc<MtlsIdentityProvider>) -> Self {
+        self.mtls = Some(provider);
+        self
+    }
+
+    /// Read client certificates from the `x-forwarded-client-cert` header
+    ///
+    /// Only enable this behind a proxy that terminates mutual TLS and
+    /// overwrites the header, since clients could otherwise forge it.
+    pub fn trust_forwarded_certs(mut self) -> Self {
+        self.trust_forwarded_certs = true;
+        self
+    }
+
+    /// Add group and inherited roles to authenticated identities
+    pub fn with_role_resolver(mut self, resolver: Arc<RoleResolver>) -> Self {
+        self.roles = Some(resolver);
+        self
+    }
+
+    /// Let requests without credentials through unauthenticated
+    pub fn allow_anonymous(mut self) -> Self {
+        self.allow_anonymous = true;
+        self
+    }
+
+    /// Authenticate a request's credentials, if it has any
+    async fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        certificate: Option<InfraResult<ClientCertificate>>,
+    ) -> Result<Option<AuthContext>, Rejection> {
+        let bearer = match (headers.get(AUTHORIZATION), &self.bearer) {
+            (Some(value), Some(_)) => {
+                let value = value.to_str().map_err(|_| AuthError::InvalidToken)?;
+                bearer_token(value)?
+            }
+            _ => None,
+        };
+        let api_key = headers
+            .get(&self.api_key_header)
+            .and_then(|value| value.to_str().ok());
+
+        let (token, provider) = match (bearer, &self.bearer, api_key, &self.api_key) {
+            (Some(token), Some(provider), _, _) | (_, _, Some(token), Some(provider)) => {
+                (token, provider)
+            }
+            _ => {
+                return match self.client_certificate(certificate)? {
+                    Some(identity) => self.resolve(identity, None).await.map(Some),
+                    None => Ok(None),
+                }
+            }
+        };
+
+        let identity = provider.verify(token).await.map_err(|e| {
+            tracing::debug!(error = %e, "Rejected credentials");
+            rejection(&e)
+        })?;
+        self.resolve(identity, Some(token)).await.map(Some)
+    }
+
+    /// Get the client certificate presented with a request, if certificates
+    /// identify clients
+    fn presented_certificate(&self, request: &Request) -> Option<InfraResult<ClientCertificate>> {
+        self.mtls.as_ref()?;
+        let extensions = request.extensions();
+        if let Some(certificate) = extensions.get::<ClientCertificate>() {
+            return Some(Ok(certificate.clone()));
+        }
+        if let Some(ConnectInfo(certificate)) = extensions.get::<ConnectInfo<ClientCertificate>>() {
+            return Some(Ok(certificate.clone()));
+        }
+        if !self.trust_forwarded_certs {
+            return None;
+        }
+        let header = request.headers().get(XFCC_HEADER)?.to_str().ok()?;
+        Some(ClientCertificate::from_xfcc(header))
+    }
+
+    /// Identify the client certificate of a request, if it has one
+    fn client_certificate(
+        &self,
+        certificate: Option<InfraResult<ClientCertificate>>,
+    ) -> Result<Option<Identity>, Rejection> {
+        let (Some(provider), Some(certificate)) = (&self.mtls, certificate) else {
+            return Ok(None);
+        };
+        let certificate = certificate.map_err(|e| rejection(&e))?;
+
+        provider.identify(&certificate).map(Some).map_err(|e| {
+            tracing::debug!(error = %e, "Rejected client certificate");
+            rejection(&e)
+        })
+    }
+
+    /// Resolve the roles of an authenticated identity
+    async fn resolve(
+        &self,
+        mut identity: Identity,
+        token: Option<&str>,
+    ) -> Result<AuthContext, Rejection> {
+        if let Some(roles) = &self.roles {
+            identity = roles.resolve(identity).await.map_err(|e| {
+                tracing::warn!(error = %e, "Failed to resolve roles");
+                Rejection::Unavailable
+            })?;
+        }
+        Ok(match token {
+            Some(token) => AuthContext::with_token(token.to_string(), identity),
+            None => AuthContext::with_identity(identity),
+        })
+    }
+}
+
+impl Default for AuthLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            layer: Arc::new(self.clone()),
+        }
+    }
+}
+
+/// Service created by [`AuthLayer`]
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    layer: Arc<AuthLayer>,
+}
+
+impl<S> Service<Request> for AuthService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let layer = self.layer.clone();
+        // Call the service that was polled ready, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let certificate = layer.presented_certificate(&request);
+            let context = match layer
+                .authenticate(request.headers(), certificate.clone())
+                .await
+            {
+                Ok(Some(context)) => context,
+                Ok(None) if layer.allow_anonymous => AuthContext::new(),
+                Ok(None) => return Ok(AuthError::Missing.into_response()),
+                Err(e) => return Ok(e.into_response()),
+            };
+
+            if let Some(Ok(certificate)) = certificate {
+                request.extensions_mut().insert(certificate);
+            }
+
+            if let Some(identity) = context.identity() {
+                request.extensions_mut().insert(identity.clone());
+            }
+            request.extensions_mut().insert(context);
+            inner.call(request).await
+        })
+    }
+}
+
+/// What a route requires of the caller
+enum Requirement {
+    Role(String),
+    InheritedRole(Arc<RoleGraph>, String),
+    Scope(String, Action),
+    Policy(Arc<PolicyEngine>, String, Action),
+}
+
+/// Layer rejecting requests whose identity lacks a permission
+///
+/// Must run inside an [`AuthLayer`]. Unauthenticated requests get a 401,
+/// and authenticated ones without the permission a 403.
+#[derive(Clone)]
+pub struct RequireLayer {
+    requirement: Arc<Requirement>,
+}
+
+impl RequireLayer {
+    /// Require a role
+    ///
+    /// Only roles the identity holds count, which include inherited roles
+    /// when the [`AuthLayer`] has a [`RoleResolver`]. Otherwise use
+    /// [`inherited_role`](Self::inherited_role).
+    pub fn role(role: impl Into<String>) -> Self {
+        Self::new(Requirement::Role(role.into()))
+    }
+
+    /// Require a role, held directly or inherited through `graph`
+    pub fn inherited_role(graph: Arc<RoleGraph>, role: impl Into<String>) -> Self {
+        Self::new(Requirement::InheritedRole(graph, role.into()))
+    }
+
+    /// Require a scope granting `action` on `resource`
+    pub fn scope(resource: impl Into<String>, action: Action) -> Self {
+        Self::new(Requirement::Scope(resource.into(), action))
+    }
+
+    /// Require `engine` to allow `action` on `resource`
+    ///
+    /// Policy conditions can use the request's `context.method` and
+    /// `context.path`.
+    pub fn policy(engine: Arc<PolicyEngine>, resource: impl Into<String>, action: Action) -> Self {
+        Self::new(Requirement::Policy(engine, resource.into(), action))
+    }
+
+    fn new(requirement: Requirement) -> Self {
+        Self {
+            requirement: Arc::new(requirement),
+        }
+    }
+
+    fn check(&self, request: &Request) -> Result<(), AuthError> {
+        let auth = request
+            .extensions()
+            .get::<AuthContext>()
+            .ok_or(AuthError::Missing)?;
+        let identity = auth.identity().ok_or(AuthError::Missing)?;
+
+        let allowed = match &*self.requirement {
+            Requirement::Role(role) => identity.has_role(role),
+            Requirement::InheritedRole(graph, role) => graph.has_role(identity, role),
+            Requirement::Scope(resource, action) => {
+                ScopeSet::from_identity(identity).allows(resource, *action)
+            }
+            Requirement::Policy(engine, resource, action) => {
+                let context = RequestContext::new()
+                    .with_value("method", request.method().as_str())
+                    .with_value("path", request.uri().path());
+                engine
+                    .evaluate_auth(auth, resource, *action, &context)
+                    .is_allowed()
+            }
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(AuthError::InsufficientPermissions)
+        }
+    }
+}
+
+impl<S> Layer<S> for RequireLayer {
+    type Service = RequireService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service created by [`RequireLayer`]
+#[derive(Clone)]
+pub struct RequireService<S> {
+    inner: S,
+    layer: RequireLayer,
+}
+
+impl<S> Service<Request> for RequireService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        match self.layer.check(&request) {
+            Ok(()) => Box::pin(self.inner.call(request)),
+            Err(e) => Box::pin(async move { Ok(e.into_response()) }),
+        }
+    }
+}
+
+/// Extracts the authenticated identity, rejecting unauthenticated requests
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Identity {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Identity>()
+            .cloned()
+            .ok_or(AuthError::Missing)
+    }
+}
+
+/// Extracts the client certificate an [`AuthLayer`] with mutual TLS found,
+/// rejecting requests without one
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientCertificate {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientCertificate>()
+            .cloned()
+            .ok_or(AuthError::Missing)
+    }
+}
+
+/// Extracts the authentication context, which is unauthenticated outside
+/// an [`AuthLayer`]
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<AuthContext>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::IdentityProvider;
+    use axum::body::{to_bytes, Body};
+    use axum::routing::get;
+    use axum::Router;
+    use infra_errors::InfraResult;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    /// Tokens for an admin and a user, plus failing ones
+    struct Tokens;
+
+    impl IdentityProvider for Tokens {
+        fn verify(&self, token: &str) -> InfraResult<Identity> {
+            match token {
+                "admin-token" => Ok(Identity::user("ada")
+                    .with_role("admin")
+                    .with_attribute("scope", "models:read")),
+                "user-token" => Ok(Identity::user("bob")),
+                "expired-token" => Err(AuthError::TokenExpired.into()),
+                "outage-token" => Err(InfraError::timeout(
+                    "introspect",
+                    std::time::Duration::from_secs(1),
+                )),
+                _ => Err(AuthError::InvalidToken.into()),
+            }
+        }
+    }
+
+    fn app() -> Router {
+        let provider: Arc<dyn AsyncIdentityProvider> = Arc::new(Tokens);
+        Router::new()
+            .route(
+                "/admin",
+                get(|identity: Identity| async move { identity.id }),
+            )
+            .route_layer(RequireLayer::role("admin"))
+            .route(
+                "/reports",
+                get(|| async { "reports" }).route_layer(RequireLayer::inherited_role(
+                    Arc::new(RoleGraph::new().inherit("admin", ["auditor"])),
+                    "auditor",
+                )),
+            )
+            .route(
+                "/models",
+                get(|| async { "models" }).route_layer(RequireLayer::scope("models", Action::Read)),
+            )
+            .route(
+                "/certificate",
+                get(|certificate: ClientCertificate| async move {
+                    certificate.subject.unwrap_or_default()
+                }),
+            )
+            .route(
+                "/whoami",
+                get(|context: AuthContext| async move {
+                    context
+                        .identity()
+                        .map_or("anonymous".to_string(), |i| i.id.clone())
+                }),
+            )
+            .layer(
+                AuthLayer::new()
+                    .with_bearer(provider.clone())
+                    .with_api_keys(provider)
+                    .with_mtls(Arc::new(MtlsIdentityProvider::new("example.org")))
+                    .trust_forwarded_certs()
+                    .allow_anonymous(),
+            )
+    }
+
+    async fn send(uri: &str, header: Option<(&str, &str)>) -> (StatusCode, Vec<u8>) {
+        let mut request = Request::builder().uri(uri);
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    fn error(body: &[u8]) -> String {
+        let body: Value = serde_json::from_slice(body).unwrap();
+        body["error"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_authentication() {
+        let (status, body) = send("/whoami", Some(("authorization", "Bearer admin-token"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"ada");
+
+        let (status, body) = send("/whoami", Some((API_KEY_HEADER, "user-token"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"bob");
+
+        let (status, body) = send("/whoami", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"anonymous");
+
+        let (status, body) = send("/whoami", Some(("authorization", "Bearer forged"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error(&body), "invalid_token");
+
+        let (status, body) = send("/whoami", Some(("authorization", "bearer expired-token"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error(&body), "token_expired");
+
+        // Malformed bearer headers are not mistaken for anonymous requests
+        for header in ["Bearer", "Bearer  ", "Bearer a b"] {
+            let (status, body) = send("/whoami", Some(("authorization", header))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(error(&body), "invalid_token");
+        }
+
+        let (status, body) = send("/whoami", Some(("authorization", "Bearer outage-token"))).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error(&body), "auth_unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_route_requirements() {
+        let (status, body) = send("/admin", Some(("authorization", "Bearer admin-token"))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"ada");
+
+        let (status, body) = send("/admin", Some(("authorization", "Bearer user-token"))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(error(&body), "insufficient_permissions");
+
+        let (status, body) = send("/admin", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error(&body), "missing_credentials");
+
+        let (status, _) = send("/models", Some(("authorization", "Bearer admin-token"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send("/models", Some(("authorization", "Bearer user-token"))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Admins inherit the auditor role
+        let (status, _) = send("/reports", Some(("authorization", "Bearer admin-token"))).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send("/reports", Some(("authorization", "Bearer user-token"))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_missing_credentials() {
+        let provider: Arc<dyn AsyncIdentityProvider> = Arc::new(Tokens);
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(AuthLayer::new().with_bearer(provider));
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+    }
+
+    #[tokio::test]
+    async fn test_client_certificates() {
+        let xfcc = "By=spiffe://example.org/mesh;URI=spiffe://example.org/sa/billing";
+        let (status, body) = send("/whoami", Some((XFCC_HEADER, xfcc))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"spiffe://example.org/sa/billing");
+
+        let (status, body) =
+            send("/whoami", Some((XFCC_HEADER, "URI=spiffe://evil.org/sa/x"))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error(&body), "invalid_token");
+
+        let mut request = Request::builder()
+            .uri("/whoami")
+            .body(Body::empty())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ClientCertificate::new().with_uri("spiffe://example.org/sa/ledger"));
+        let response = app().oneshot(request).await.unwrap();
+        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"spiffe://example.org/sa/ledger");
+
+        // Certificates from a TLS acceptor's connection info
+        let mut request = Request::builder()
+            .uri("/certificate")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(
+            ClientCertificate::new()
+                .with_subject("CN=ledger")
+                .with_uri("spiffe://example.org/sa/ledger"),
+        ));
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"CN=ledger");
+
+        // Forwarded certificates are extracted too
+        let xfcc = "Subject=\"CN=billing\";URI=spiffe://example.org/sa/billing";
+        let (status, body) = send("/certificate", Some((XFCC_HEADER, xfcc))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"CN=billing");
+
+        let (status, _) = send("/certificate", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,859 @@
+//! Attribute-based policy conditions.
+//!
+//! Conditions compare attributes of the identity or the request against
+//! values, and combine into expressions such as:
+//!
+//! ```text
+//! identity.department == "research" && context.ip in_range "10.0.0.0/8"
+//! identity.tenant == context.tenant || identity.roles in ["admin", "auditor"]
+//! !(context.model matches "gpt-4*") && identity.clearance >= 3
+//! ```
+//!
+//! Attributes are dotted paths rooted at `identity` or `context`. On the
+//! identity, `id`, `type`, `name`, `email`, `roles` and `tenant` name its
+//! fields and anything else is looked up in its attributes. `&&`/`and`,
+//! `||`/`or` and `!`/`not` combine conditions, and parentheses group them,
+//! up to [`MAX_DEPTH`] levels deep.
+//!
+//! A condition on a missing attribute is unknown rather than false, and so
+//! is its negation: `!(identity.region == "eu")` does not hold for an
+//! identity without a region.
+
+use crate::identity::Identity;
+use infra_errors::{InfraError, InfraResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Maximum nesting of negations and parentheses in an expression
+pub const MAX_DEPTH: usize = 32;
+
+/// Attributes of the request being authorized
+///
+/// Holds values that are not part of the identity, such as the client IP,
+/// the requested model, or the time of day.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    values: HashMap<String, Value>,
+}
+
+impl RequestContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value
+    pub fn with_value(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Set a value
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    /// Get a value
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+}
+
+/// Comparison operator of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    /// Equal (`==`)
+    Eq,
+    /// Not equal (`!=`)
+    Ne,
+    /// Equal to any of a list of values, or sharing one with a list
+    /// attribute (`in`)
+    In,
+    /// Greater than (`>`)
+    Gt,
+    /// Greater than or equal (`>=`)
+    Gte,
+    /// Less than (`<`)
+    Lt,
+    /// Less than or equal (`<=`)
+    Lte,
+    /// Matches a glob pattern with `*` and `?` (`matches`)
+    Pattern,
+    /// IP address within a CIDR range (`in_range`)
+    IpRange,
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::In => "in",
+            Operator::Gt => ">",
+            Operator::Gte => ">=",
+            Operator::Lt => "<",
+            Operator::Lte => "<=",
+            Operator::Pattern => "matches",
+            Operator::IpRange => "in_range",
+        }
+    }
+}
+
+/// Right-hand side of a condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// A literal value
+    Value(Value),
+    /// Another attribute, such as `context.tenant`
+    Attribute(String),
+}
+
+/// A comparison of an attribute against an operand
+///
+/// A condition on a missing attribute is never met, whatever the operator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    /// Dotted path of the attribute
+    pub attribute: String,
+    /// Comparison operator
+    pub operator: Operator,
+    /// Value compared against
+    pub operand: Operand,
+}
+
+impl Condition {
+    /// Create a condition comparing an attribute against a value
+    pub fn new(attribute: impl Into<String>, operator: Operator, value: impl Into<Value>) -> Self {
+        Self {
+            attribute: attribute.into(),
+            operator,
+            operand: Operand::Value(value.into()),
+        }
+    }
+
+    /// Create a condition comparing two attributes
+    pub fn attributes(
+        attribute: impl Into<String>,
+        operator: Operator,
+        other: impl Into<String>,
+    ) -> Self {
+        Self {
+            attribute: attribute.into(),
+            operator,
+            operand: Operand::Attribute(other.into()),
+        }
+    }
+
+    /// Check the condition against an identity and request
+    pub fn evaluate(&self, identity: &Identity, context: &RequestContext) -> bool {
+        self.check(identity, context) == Some(true)
+    }
+
+    /// Check the condition, or `None` if an attribute is missing
+    fn check(&self, identity: &Identity, context: &RequestContext) -> Option<bool> {
+        let actual = resolve(&self.attribute, identity, context)?;
+        let expected = match &self.operand {
+            Operand::Value(value) => value.clone(),
+            Operand::Attribute(path) => resolve(path, identity, context)?,
+        };
+        Some(compare(self.operator, &actual, &expected))
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ", self.attribute, self.operator.symbol())?;
+        match &self.operand {
+            Operand::Value(value) => write!(f, "{value}"),
+            Operand::Attribute(path) => f.write_str(path),
+        }
+    }
+}
+
+/// A boolean combination of conditions
+///
+/// Parsed from and serialized as the expression syntax described in the
+/// [module docs](self), so it can be written directly in config files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionExpr {
+    /// A single condition
+    Condition(Condition),
+    /// All of the expressions hold
+    All(Vec<ConditionExpr>),
+    /// Any of the expressions holds
+    Any(Vec<ConditionExpr>),
+    /// The expression does not hold
+    Not(Box<ConditionExpr>),
+}
+
+impl ConditionExpr {
+    /// Parse an expression
+    pub fn parse(input: &str) -> InfraResult<Self> {
+        let invalid = |reason: String| {
+            InfraError::validation(format!("Invalid condition {input:?}: {reason}"))
+        };
+        let tokens = tokenize(input).map_err(invalid)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.or().map_err(invalid)?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(invalid(format!("unexpected {token}"))),
+        }
+    }
+
+    /// Check the expression against an identity and request
+    ///
+    /// Expressions that depend on a missing attribute do not hold.
+    pub fn evaluate(&self, identity: &Identity, context: &RequestContext) -> bool {
+        self.check(identity, context) == Some(true)
+    }
+
+    /// Check the expression with three-valued logic, where `None` means
+    /// unknown because an attribute is missing
+    fn check(&self, identity: &Identity, context: &RequestContext) -> Option<bool> {
+        match self {
+            ConditionExpr::Condition(condition) => condition.check(identity, context),
+            ConditionExpr::All(exprs) => {
+                let mut result = Some(true);
+                for expr in exprs {
+                    match expr.check(identity, context) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                result
+            }
+            ConditionExpr::Any(exprs) => {
+                let mut result = Some(false);
+                for expr in exprs {
+                    match expr.check(identity, context) {
+                        Some(true) => return Some(true),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                result
+            }
+            ConditionExpr::Not(expr) => expr.check(identity, context).map(|held| !held),
+        }
+    }
+}
+
+impl From<Condition> for ConditionExpr {
+    fn from(condition: Condition) -> Self {
+        ConditionExpr::Condition(condition)
+    }
+}
+
+impl FromStr for ConditionExpr {
+    type Err = InfraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for ConditionExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter<'_>, exprs: &[ConditionExpr], sep: &str| {
+            for (i, expr) in exprs.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(sep)?;
+                }
+                match expr {
+                    ConditionExpr::All(_) | ConditionExpr::Any(_) => write!(f, "({expr})")?,
+                    _ => write!(f, "{expr}")?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            ConditionExpr::Condition(condition) => write!(f, "{condition}"),
+            ConditionExpr::All(exprs) => join(f, exprs, " && "),
+            ConditionExpr::Any(exprs) => join(f, exprs, " || "),
+            ConditionExpr::Not(expr) => write!(f, "!({expr})"),
+        }
+    }
+}
+
+impl Serialize for ConditionExpr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConditionExpr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Self::parse(&input).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Look up an attribute by its dotted path
+fn resolve(path: &str, identity: &Identity, context: &RequestContext) -> Option<Value> {
+    let mut parts = path.split('.');
+    let root = parts.next()?;
+    let name = parts.next()?;
+    let value = match root {
+        "identity" => match name {
+            "id" => Value::from(identity.id.as_str()),
+            "type" => serde_json::to_value(identity.identity_type).ok()?,
+            "name" => Value::from(identity.name.as_deref()?),
+            "email" => Value::from(identity.email.as_deref()?),
+            "roles" => Value::from(identity.roles.clone()),
+            "tenant" => match &identity.tenant {
+                Some(tenant) => Value::from(tenant.id()),
+                None => identity.attributes.get(name)?.clone(),
+            },
+            _ => identity.attributes.get(name)?.clone(),
+        },
+        "context" => context.get(name)?.clone(),
+        _ => return None,
+    };
+    parts.try_fold(value, |value, part| value.get(part).cloned())
+}
+
+/// Compare two values, treating numbers of any representation as equal
+pub(crate) fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => number_order(a, b) == Some(Ordering::Equal),
+        _ => a == b,
+    }
+}
+
+/// Order numbers exactly when both are integers, and as floats otherwise
+fn number_order(a: &serde_json::Number, b: &serde_json::Number) -> Option<Ordering> {
+    let integer = |n: &serde_json::Number| {
+        n.as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+    };
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    }
+}
+
+fn compare(operator: Operator, actual: &Value, expected: &Value) -> bool {
+    let any = |test: &dyn Fn(&Value) -> bool| match expected {
+        Value::Array(items) => items.iter().any(test),
+        other => test(other),
+    };
+    match operator {
+        Operator::Eq => values_equal(actual, expected),
+        Operator::Ne => !values_equal(actual, expected),
+        Operator::In => match actual {
+            Value::Array(values) => values
+                .iter()
+                .any(|value| any(&|item| values_equal(value, item))),
+            value => any(&|item| values_equal(value, item)),
+        },
+        Operator::Gt => order(actual, expected) == Some(Ordering::Greater),
+        Operator::Gte => matches!(
+            order(actual, expected),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        Operator::Lt => order(actual, expected) == Some(Ordering::Less),
+        Operator::Lte => matches!(
+            order(actual, expected),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        Operator::Pattern => actual.as_str().is_some_and(|text| {
+            any(&|pattern| pattern.as_str().is_some_and(|p| glob_match(p, text)))
+        }),
+        Operator::IpRange => actual
+            .as_str()
+            .and_then(|ip| ip.parse::<IpAddr>().ok())
+            .is_some_and(|ip| any(&|range| range.as_str().is_some_and(|r| in_range(ip, r)))),
+    }
+}
+
+/// Order numbers numerically, RFC 3339 timestamps chronologically and other
+/// strings lexically
+fn order(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => number_order(a, b),
+        (Value::String(a), Value::String(b)) => {
+            let time = |s: &str| chrono::DateTime::parse_from_rfc3339(s).ok();
+            match (time(a), time(b)) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                (None, None) => Some(a.cmp(b)),
+                // A timestamp and a plain string are not comparable
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Match text against a glob pattern, where `*` matches any run of
+/// characters and `?` any single character
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // Let the last `*` absorb one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Check whether an IP address is within a CIDR range, or equals a bare
+/// address
+fn in_range(ip: IpAddr, range: &str) -> bool {
+    let (network, prefix) = match range.split_once('/') {
+        Some((network, prefix)) => match prefix.parse::<u32>() {
+            Ok(prefix) => (network, Some(prefix)),
+            Err(_) => return false,
+        },
+        None => (range, None),
+    };
+    let Ok(network) = network.parse::<IpAddr>() else {
+        return false;
+    };
+
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let prefix = prefix.unwrap_or(32);
+            prefix <= 32 && {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(ip) & mask == u32::from(network) & mask
+            }
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let prefix = prefix.unwrap_or(128);
+            prefix <= 128 && {
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(ip) & mask == u128::from(network) & mask
+            }
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Path(String),
+    Literal(Value),
+    Op(Operator),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Path(path) => write!(f, "'{path}'"),
+            Token::Literal(value) => write!(f, "{value}"),
+            Token::Op(op) => write!(f, "'{}'", op.symbol()),
+            Token::And => f.write_str("'&&'"),
+            Token::Or => f.write_str("'||'"),
+            Token::Not => f.write_str("'!'"),
+            Token::LParen => f.write_str("'('"),
+            Token::RParen => f.write_str("')'"),
+            Token::LBracket => f.write_str("'['"),
+            Token::RBracket => f.write_str("']'"),
+            Token::Comma => f.write_str("','"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('=', Some('=')) => (Token::Op(Operator::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Operator::Ne), 2),
+            ('>', Some('=')) => (Token::Op(Operator::Gte), 2),
+            ('<', Some('=')) => (Token::Op(Operator::Lte), 2),
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('>', _) => (Token::Op(Operator::Gt), 1),
+            ('<', _) => (Token::Op(Operator::Lt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            (',', _) => (Token::Comma, 1),
+            ('"', _) => {
+                // Double-quoted strings use JSON escapes
+                let mut end = i + 1;
+                while end < chars.len() && chars[end] != '"' {
+                    end += if chars[end] == '\\' { 2 } else { 1 };
+                }
+                if end >= chars.len() {
+                    return Err("unterminated string".to_string());
+                }
+                let literal: String = chars[i..=end].iter().collect();
+                let value = serde_json::from_str(&literal).map_err(|e| e.to_string())?;
+                (Token::Literal(value), end + 1 - i)
+            }
+            ('\'', _) => {
+                let Some(len) = chars[i + 1..].iter().position(|&c| c == '\'') else {
+                    return Err("unterminated string".to_string());
+                };
+                let value: String = chars[i + 1..i + 1 + len].iter().collect();
+                (Token::Literal(value.into()), len + 2)
+            }
+            (c, next)
+                if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let len = 1 + chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit() || **c == '.')
+                    .count();
+                let literal: String = chars[i..i + len].iter().collect();
+                let value = serde_json::from_str(&literal)
+                    .map_err(|_| format!("invalid number {literal}"))?;
+                (Token::Literal(value), len)
+            }
+            (c, _) if c.is_ascii_alphabetic() || c == '_' => {
+                let len = 1 + chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+                    .count();
+                let word: String = chars[i..i + len].iter().collect();
+                let token = match word.as_str() {
+                    "in" => Token::Op(Operator::In),
+                    "matches" => Token::Op(Operator::Pattern),
+                    "in_range" => Token::Op(Operator::IpRange),
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "true" => Token::Literal(true.into()),
+                    "false" => Token::Literal(false.into()),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Path(word),
+                };
+                (token, len)
+            }
+            (c, _) => return Err(format!("unexpected character {c:?}")),
+        };
+        tokens.push(token);
+        i += len;
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, binding `!` tighter than `&&` tighter than `||`
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Current nesting of negations and parentheses
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of expression".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<ConditionExpr, String> {
+        let mut exprs = vec![self.and()?];
+        while self.eat(&Token::Or) {
+            exprs.push(self.and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            ConditionExpr::Any(exprs)
+        })
+    }
+
+    fn and(&mut self) -> Result<ConditionExpr, String> {
+        let mut exprs = vec![self.unary()?];
+        while self.eat(&Token::And) {
+            exprs.push(self.unary()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            ConditionExpr::All(exprs)
+        })
+    }
+
+    fn unary(&mut self) -> Result<ConditionExpr, String> {
+        if self.eat(&Token::Not) {
+            let expr = self.nested(Self::unary)?;
+            return Ok(ConditionExpr::Not(Box::new(expr)));
+        }
+        if self.eat(&Token::LParen) {
+            let expr = self.nested(Self::or)?;
+            return match self.next()? {
+                Token::RParen => Ok(expr),
+                token => Err(format!("expected ')', found {token}")),
+            };
+        }
+        self.condition().map(ConditionExpr::Condition)
+    }
+
+    /// Parse a nested expression, enforcing [`MAX_DEPTH`]
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<ConditionExpr, String>,
+    ) -> Result<ConditionExpr, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("nested more than {MAX_DEPTH} levels deep"));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let attribute = match self.next()? {
+            Token::Path(path) => attribute_path(path)?,
+            token => return Err(format!("expected an attribute, found {token}")),
+        };
+        let operator = match self.next()? {
+            Token::Op(operator) => operator,
+            token => return Err(format!("expected an operator, found {token}")),
+        };
+        let operand = match self.next()? {
+            Token::Path(path) => Operand::Attribute(attribute_path(path)?),
+            Token::Literal(value) => Operand::Value(value),
+            Token::LBracket => Operand::Value(Value::Array(self.list()?)),
+            token => return Err(format!("expected a value, found {token}")),
+        };
+        Ok(Condition {
+            attribute,
+            operator,
+            operand,
+        })
+    }
+
+    /// Parse the rest of a list after its opening bracket
+    fn list(&mut self) -> Result<Vec<Value>, String> {
+        let mut values = Vec::new();
+        if self.eat(&Token::RBracket) {
+            return Ok(values);
+        }
+        loop {
+            match self.next()? {
+                Token::Literal(value) => values.push(value),
+                token => return Err(format!("expected a list value, found {token}")),
+            }
+            match self.next()? {
+                Token::Comma => {}
+                Token::RBracket => return Ok(values),
+                token => return Err(format!("expected ',' or ']', found {token}")),
+            }
+        }
+    }
+}
+
+/// Check that an attribute path has a known root and a name
+fn attribute_path(path: String) -> Result<String, String> {
+    match path.split_once('.') {
+        Some(("identity" | "context", name)) if !name.is_empty() => Ok(path),
+        _ => Err(format!(
+            "attribute '{path}' must start with 'identity.' or 'context.'"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity() -> Identity {
+        Identity::user("user-1")
+            .with_role("analyst")
+            .with_attribute("department", "research")
+            .with_attribute("clearance", 3)
+            .with_attribute("tenant", "acme")
+    }
+
+    fn check(expr: &str, context: &RequestContext) -> bool {
+        ConditionExpr::parse(expr)
+            .unwrap()
+            .evaluate(&identity(), context)
+    }
+
+    #[test]
+    fn test_operators() {
+        let context = RequestContext::new()
+            .with_value("ip", "10.1.2.3")
+            .with_value("model", "gpt-4o-mini")
+            .with_value("tenant", "acme")
+            .with_value("requested_at", "2026-03-01T12:00:00Z");
+
+        assert!(check(r#"identity.department == "research""#, &context));
+        assert!(check("identity.department != 'sales'", &context));
+        assert!(check("identity.clearance >= 3.0", &context));
+        assert!(!check("identity.clearance > 3", &context));
+        assert!(check(r#"identity.roles in ["admin", "analyst"]"#, &context));
+        assert!(check("identity.id in ['user-1', 'user-2']", &context));
+        assert!(check("context.model matches 'gpt-4*'", &context));
+        assert!(!check("context.model matches 'gpt-3.?'", &context));
+        assert!(check("context.ip in_range '10.0.0.0/8'", &context));
+        assert!(!check(
+            "context.ip in_range ['192.168.0.0/16', '::1']",
+            &context
+        ));
+        assert!(check(
+            "context.requested_at < '2026-06-01T00:00:00Z'",
+            &context
+        ));
+        assert!(check("identity.tenant == context.tenant", &context));
+
+        // Missing attributes never match, even negated
+        for expr in [
+            "identity.region != 'eu'",
+            "!(identity.region == 'eu')",
+            "not (identity.region == 'eu' || identity.clearance > 5)",
+            "identity.region == 'eu' && identity.clearance == 3",
+            "!(identity.region == 'eu' && identity.clearance == 3)",
+        ] {
+            assert!(!check(expr, &context), "{expr}");
+        }
+        // Unless the rest of the expression decides it
+        for expr in [
+            "identity.region == 'eu' || identity.clearance == 3",
+            "!(identity.region == 'eu' && identity.clearance == 4)",
+        ] {
+            assert!(check(expr, &context), "{expr}");
+        }
+    }
+
+    #[test]
+    fn test_exact_comparisons() {
+        let context = RequestContext::new()
+            .with_value("big", 9_007_199_254_740_993_i64)
+            .with_value("max", u64::MAX)
+            .with_value("requested_at", "2026-03-01T12:00:00+02:00");
+
+        // Integers beyond f64 precision still compare exactly
+        assert!(!check("context.big == 9007199254740992", &context));
+        assert!(check("context.big > 9007199254740992", &context));
+        assert!(check("context.max > 9223372036854775807", &context));
+
+        // Timestamps compare as instants, whatever their offset
+        assert!(check(
+            "context.requested_at < '2026-03-01T11:00:00Z'",
+            &context
+        ));
+        assert!(!check("context.requested_at < 'later'", &context));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let internal = RequestContext::new().with_value("ip", "10.0.0.7");
+        let external = RequestContext::new().with_value("ip", "203.0.113.9");
+        let expr = "identity.department == 'research' and \
+                    (context.ip in_range '10.0.0.0/8' || identity.roles in ['admin'])";
+
+        assert!(check(expr, &internal));
+        assert!(!check(expr, &external));
+        assert!(check(&format!("!({expr})"), &external));
+
+        let parsed = ConditionExpr::parse(expr).unwrap();
+        assert_eq!(ConditionExpr::parse(&parsed.to_string()).unwrap(), parsed);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for expr in [
+            "",
+            "identity.department ==",
+            "department == 'research'",
+            "identity.clearance >> 3",
+            "(identity.clearance > 3",
+            "identity.department == 'research' extra",
+            "identity.roles in [identity.id]",
+        ] {
+            assert!(ConditionExpr::parse(expr).is_err(), "{expr}");
+        }
+
+        let nested = |depth: usize| {
+            format!(
+                "{}identity.clearance > 1{}",
+                "!(".repeat(depth),
+                ")".repeat(depth)
+            )
+        };
+        assert!(ConditionExpr::parse(&nested(MAX_DEPTH / 2)).is_ok());
+        assert!(ConditionExpr::parse(&nested(MAX_DEPTH)).is_err());
+        assert!(ConditionExpr::parse(&"not ".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_glob_and_ip_range() {
+        assert!(glob_match("models:gpt-4*", "models:gpt-4-turbo"));
+        assert!(glob_match("*/datasets/*", "projects/p1/datasets/d2"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b", "aXbYc"));
+        assert!(glob_match("a*", "a*b"));
+        assert!(glob_match("*?", "**"));
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(in_range(ip("192.168.1.20"), "192.168.0.0/16"));
+        assert!(in_range(ip("8.8.8.8"), "0.0.0.0/0"));
+        assert!(in_range(ip("10.0.0.1"), "10.0.0.1"));
+        assert!(in_range(ip("2001:db8::1"), "2001:db8::/32"));
+        assert!(!in_range(ip("10.0.0.1"), "2001:db8::/32"));
+        assert!(!in_range(ip("10.0.0.1"), "10.0.0.0/33"));
+    }
+}
@@ -0,0 +1,231 @@
+//! File-backed session store.
+
+use crate::session::{evictions, Session, SessionStore};
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, IoOperation};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Session store keeping each session in a JSON file
+///
+/// Sessions are written to `{id}.json` in the store's directory through a
+/// temporary file, so a crash never leaves a partially written session.
+/// Listing an identity's sessions scans the whole directory, which suits a
+/// single node with a moderate number of sessions. Writes are serialized
+/// within the store, so session limits hold as long as one store instance
+/// owns the directory.
+pub struct FileSessionStore {
+    dir: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileSessionStore {
+    /// Create a store in `dir`, creating the directory if needed
+    pub async fn new(dir: impl Into<PathBuf>) -> InfraResult<Self> {
+        let dir = dir.into();
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| io_error(IoOperation::Create, &dir, e))?;
+        Ok(Self {
+            dir,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Get the store's directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of a session's file
+    ///
+    /// IDs are restricted to URL-safe characters so they cannot escape the
+    /// store's directory.
+    fn path(&self, id: &str) -> InfraResult<PathBuf> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(InfraError::validation(format!(
+                "Invalid session ID: {id:?}"
+            )));
+        }
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+
+    async fn write(&self, session: &Session) -> InfraResult<()> {
+        let path = self.path(&session.id)?;
+        let json = serde_json::to_vec(session)?;
+
+        let tmp = self
+            .dir
+            .join(format!(".{}.{}.tmp", session.id, Uuid::new_v4()));
+        tokio::fs::write(&tmp, json)
+            .await
+            .map_err(|e| io_error(IoOperation::Write, &tmp, e))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| io_error(IoOperation::Move, &path, e))
+    }
+
+    async fn remove(&self, id: &str) -> InfraResult<()> {
+        let path = self.path(id)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(IoOperation::Delete, &path, e)),
+        }
+    }
+
+    async fn read(path: &Path) -> InfraResult<Option<Session>> {
+        match tokio::fs::read(path).await {
+            Ok(json) => Ok(Some(serde_json::from_slice(&json)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(IoOperation::Read, path, e)),
+        }
+    }
+
+    /// Read every stored session
+    async fn read_all(&self) -> InfraResult<Vec<Session>> {
+        let mut entries = tokio::fs::read_dir(&self.dir)
+            .await
+            .map_err(|e| io_error(IoOperation::List, &self.dir, e))?;
+
+        let mut sessions = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| io_error(IoOperation::List, &self.dir, e))?
+        {
+            let path = entry.path();
+            if path.extension() != Some(OsStr::new("json")) {
+                continue;
+            }
+            match Self::read(&path).await {
+                Ok(Some(session)) => sessions.push(session),
+                // Deleted since the directory was listed
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable session");
+                }
+            }
+        }
+        Ok(sessions)
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileSessionStore {
+    async fn create(&self, session: Session) -> InfraResult<()> {
+        let _guard = self.lock.lock().await;
+        self.write(&session).await
+    }
+
+    async fn get(&self, id: &str) -> InfraResult<Option<Session>> {
+        Self::read(&self.path(id)?).await
+    }
+
+    async fn update(&self, session: Session) -> InfraResult<()> {
+        let _guard = self.lock.lock().await;
+        let path = self.path(&session.id)?;
+        let exists = tokio::fs::try_exists(&path)
+            .await
+            .map_err(|e| io_error(IoOperation::Read, &path, e))?;
+        if !exists {
+            return Ok(());
+        }
+        self.write(&session).await
+    }
+
+    async fn delete(&self, id: &str) -> InfraResult<()> {
+        let _guard = self.lock.lock().await;
+        self.remove(id).await
+    }
+
+    async fn list(&self, identity_id: &str) -> InfraResult<Vec<Session>> {
+        let mut sessions = self.read_all().await?;
+        sessions.retain(|session| session.identity.id == identity_id);
+        Ok(sessions)
+    }
+
+    async fn create_limited(&self, session: Session, max: usize) -> InfraResult<()> {
+        let _guard = self.lock.lock().await;
+        let mut active = self.read_all().await?;
+        active.retain(|other| other.identity.id == session.identity.id);
+        for evicted in evictions(active, &session.id, max) {
+            self.remove(&evicted.id).await?;
+        }
+        self.write(&session).await
+    }
+
+    async fn cleanup(&self) -> InfraResult<usize> {
+        let _guard = self.lock.lock().await;
+        let mut cleaned = 0;
+        for session in self.read_all().await? {
+            if session.is_expired() {
+                self.remove(&session.id).await?;
+                cleaned += 1;
+            }
+        }
+        Ok(cleaned)
+    }
+}
+
+fn io_error(operation: IoOperation, path: &Path, err: std::io::Error) -> InfraError {
+    InfraError::Io {
+        operation,
+        path: Some(path.to_path_buf()),
+        message: err.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Identity;
+    use chrono::{Duration, Utc};
+
+    #[tokio::test]
+    async fn test_file_session_store() {
+        let dir = std::env::temp_dir().join(format!("sessions-{}", Uuid::new_v4()));
+        let store = FileSessionStore::new(&dir).await.unwrap();
+
+        let mut session = Session::new("sess123", Identity::user("user123"), Duration::hours(1));
+        session.set("theme", "dark");
+        store.create(session.clone()).await.unwrap();
+        let mut expired = Session::new("expired", Identity::user("user123"), Duration::hours(1));
+        expired.expires_at = Utc::now() - Duration::hours(1);
+        store.create(expired).await.unwrap();
+
+        // Sessions survive reopening the store
+        let store = FileSessionStore::new(&dir).await.unwrap();
+        let retrieved = store.get("sess123").await.unwrap().unwrap();
+        assert_eq!(retrieved.get("theme"), Some(&"dark".into()));
+        assert_eq!(store.list("user123").await.unwrap().len(), 2);
+        assert!(store.list("other").await.unwrap().is_empty());
+
+        assert_eq!(store.cleanup().await.unwrap(), 1);
+        assert!(store.get("expired").await.unwrap().is_none());
+
+        store.delete("sess123").await.unwrap();
+        store.delete("sess123").await.unwrap();
+        assert!(store.get("sess123").await.unwrap().is_none());
+        store.update(session).await.unwrap();
+        assert!(store.get("sess123").await.unwrap().is_none());
+
+        for id in ["a", "b", "c"] {
+            let session = Session::new(id, Identity::user("limited"), Duration::hours(1));
+            store.create_limited(session, 2).await.unwrap();
+        }
+        assert!(store.get("a").await.unwrap().is_none());
+        assert_eq!(store.list("limited").await.unwrap().len(), 2);
+        assert!(store.get("../secrets").await.is_err());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
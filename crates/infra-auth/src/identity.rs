@@ -1,5 +1,7 @@
 //! Identity types.
 
+use crate::tenant::Tenant;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use infra_crypto::jwt::{Claims, JwtSigner};
 use infra_errors::{AuthErrorKind, InfraError, InfraResult};
@@ -21,6 +23,9 @@ pub struct Identity {
     pub roles: Vec<String>,
     /// Additional attributes
     pub attributes: HashMap<String, serde_json::Value>,
+    /// Tenant the identity belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<Tenant>,
 }
 
 impl Identity {
@@ -33,6 +38,7 @@ impl Identity {
             email: None,
             roles: Vec::new(),
             attributes: HashMap::new(),
+            tenant: None,
         }
     }
 
@@ -45,6 +51,7 @@ impl Identity {
             email: None,
             roles: Vec::new(),
             attributes: HashMap::new(),
+            tenant: None,
         }
     }
 
@@ -57,6 +64,7 @@ impl Identity {
             email: None,
             roles: Vec::new(),
             attributes: HashMap::new(),
+            tenant: None,
         }
     }
 
@@ -94,6 +102,17 @@ impl Identity {
         self
     }
 
+    /// Set the tenant
+    pub fn with_tenant(mut self, tenant: Tenant) -> Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    /// Get the tenant
+    pub fn tenant(&self) -> Option<&Tenant> {
+        self.tenant.as_ref()
+    }
+
     /// Check if the identity has a role
     pub fn has_role(&self, role: &str) -> bool {
         self.roles.iter().any(|r| r == role)
@@ -123,6 +142,23 @@ pub trait IdentityProvider: Send + Sync {
     fn verify(&self, token: &str) -> InfraResult<Identity>;
 }
 
+/// Identity provider that verifies tokens asynchronously, such as by
+/// fetching signing keys or checking a revocation list
+///
+/// Every [`IdentityProvider`] is also an asynchronous one.
+#[async_trait]
+pub trait AsyncIdentityProvider: Send + Sync {
+    /// Verify a token and return the identity
+    async fn verify(&self, token: &str) -> InfraResult<Identity>;
+}
+
+#[async_trait]
+impl<P: IdentityProvider + ?Sized> AsyncIdentityProvider for P {
+    async fn verify(&self, token: &str) -> InfraResult<Identity> {
+        IdentityProvider::verify(self, token)
+    }
+}
+
 /// Token-based identity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenIdentity {
@@ -136,21 +172,27 @@ pub struct TokenIdentity {
 
 impl TokenIdentity {
     /// Create from a JWT token
+    ///
+    /// Access tokens issued by a [`TokenService`](crate::TokenService) are
+    /// rejected, since only [`TokenService::verify`](crate::TokenService::verify)
+    /// checks whether they were revoked.
     pub fn from_token(token: &str, secret: &[u8]) -> InfraResult<Self> {
         let signer = JwtSigner::hs256(secret);
-        let claims: Claims<TokenPayload> = signer.verify(token)?;
-
-        let identity = Identity {
-            id: claims.sub.unwrap_or_default(),
-            identity_type: claims.payload.identity_type.unwrap_or(IdentityType::User),
-            name: claims.payload.name,
-            email: claims.payload.email,
-            roles: claims.payload.roles.unwrap_or_default(),
-            attributes: claims.payload.attributes.unwrap_or_default(),
-        };
+        let claims: Claims<serde_json::Map<String, serde_json::Value>> = signer.verify(token)?;
+        if claims.payload.contains_key(REFRESH_FAMILY_CLAIM) {
+            return Err(InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                message: "Token service tokens must be verified by the token service".to_string(),
+                identity: claims.sub,
+                context: None,
+                source: None,
+            });
+        }
+        let payload: TokenPayload =
+            serde_json::from_value(serde_json::Value::Object(claims.payload))?;
 
         Ok(Self {
-            identity,
+            identity: payload.into_identity(claims.sub.unwrap_or_default()),
             expires_at: DateTime::from_timestamp(claims.exp, 0)
                 .unwrap_or_else(|| Utc::now()),
             token_id: claims.jti,
@@ -161,13 +203,7 @@ impl TokenIdentity {
     pub fn to_token(&self, secret: &[u8], expiry: chrono::Duration) -> InfraResult<String> {
         let signer = JwtSigner::hs256(secret);
 
-        let payload = TokenPayload {
-            identity_type: Some(self.identity.identity_type),
-            name: self.identity.name.clone(),
-            email: self.identity.email.clone(),
-            roles: Some(self.identity.roles.clone()),
-            attributes: Some(self.identity.attributes.clone()),
-        };
+        let payload = TokenPayload::new(&self.identity);
 
         let claims = Claims::with_payload(payload, expiry)
             .with_subject(&self.identity.id);
@@ -177,12 +213,22 @@ impl TokenIdentity {
 
     /// Check if the token is expired
     pub fn is_expired(&self) -> bool {
-        Utc::now() > self.expires_at
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Check if the token is expired at the given time
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        now > self.expires_at
     }
 }
 
+/// Claim naming the refresh family of access tokens issued by a token
+/// service
+pub(crate) const REFRESH_FAMILY_CLAIM: &str = "fam";
+
+/// Identity claims carried in a JWT
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
-struct TokenPayload {
+pub(crate) struct TokenPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     identity_type: Option<IdentityType>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -193,6 +239,33 @@ struct TokenPayload {
     roles: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     attributes: Option<HashMap<String, serde_json::Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant: Option<Tenant>,
+}
+
+impl TokenPayload {
+    pub(crate) fn new(identity: &Identity) -> Self {
+        Self {
+            identity_type: Some(identity.identity_type),
+            name: identity.name.clone(),
+            email: identity.email.clone(),
+            roles: Some(identity.roles.clone()),
+            attributes: Some(identity.attributes.clone()),
+            tenant: identity.tenant.clone(),
+        }
+    }
+
+    pub(crate) fn into_identity(self, id: String) -> Identity {
+        Identity {
+            id,
+            identity_type: self.identity_type.unwrap_or(IdentityType::User),
+            name: self.name,
+            email: self.email,
+            roles: self.roles.unwrap_or_default(),
+            attributes: self.attributes.unwrap_or_default(),
+            tenant: self.tenant,
+        }
+    }
 }
 
 #[cfg(test)]
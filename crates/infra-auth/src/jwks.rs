@@ -0,0 +1,325 @@
+//! Token verification against JSON Web Key Sets.
+
+use async_trait::async_trait;
+use infra_crypto::jwt::{self, Claims, JwkSet, JwtSigner};
+use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+
+/// Source of JSON Web Key Sets
+#[async_trait]
+pub trait JwksSource: Send + Sync {
+    /// Fetch the current key set
+    async fn fetch(&self) -> InfraResult<JwkSet>;
+}
+
+/// A fixed key set
+#[async_trait]
+impl JwksSource for JwkSet {
+    async fn fetch(&self) -> InfraResult<JwkSet> {
+        Ok(self.clone())
+    }
+}
+
+/// Key set fetched from a JWKS endpoint over HTTP
+#[cfg(feature = "http")]
+pub struct HttpJwksSource {
+    client: infra_http::HttpClient,
+    url: String,
+}
+
+#[cfg(feature = "http")]
+impl HttpJwksSource {
+    /// Create a source for a JWKS URL, such as an IdP's `jwks_uri`
+    pub fn new(url: impl Into<String>) -> InfraResult<Self> {
+        Ok(Self::with_client(infra_http::HttpClient::new()?, url))
+    }
+
+    /// Create a source using an existing HTTP client
+    pub fn with_client(client: infra_http::HttpClient, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+#[async_trait]
+impl JwksSource for HttpJwksSource {
+    async fn fetch(&self) -> InfraResult<JwkSet> {
+        self.client.get_json(&self.url).await
+    }
+}
+
+/// Verifiers for the keys of the last fetched set
+#[derive(Default)]
+struct KeyCache {
+    signers: Vec<Arc<JwtSigner>>,
+    fetched_at: Option<Instant>,
+    attempted_at: Option<Instant>,
+}
+
+impl KeyCache {
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        self.fetched_at
+            .is_some_and(|fetched_at| fetched_at.elapsed() < max_age)
+    }
+
+    /// Find the key for a token's `kid`, or the only key if it has none
+    fn select(&self, kid: Option<&str>) -> Option<Arc<JwtSigner>> {
+        match kid {
+            Some(kid) => self
+                .signers
+                .iter()
+                .find(|signer| signer.key_id() == Some(kid))
+                .cloned(),
+            None if self.signers.len() == 1 => self.signers.first().cloned(),
+            None => None,
+        }
+    }
+}
+
+/// Verifies JWTs signed with RSA or ECDSA keys from a JWKS document
+///
+/// Keys are selected by the token's `kid` header and cached. The set is
+/// fetched again once the refresh interval passes, or early when a token
+/// names an unknown key so rotated keys are picked up, but never more than
+/// once per minimum refresh interval. If a refresh fails, the previously
+/// fetched keys keep being used.
+pub struct JwksVerifier {
+    source: Arc<dyn JwksSource>,
+    cache: RwLock<KeyCache>,
+    refresh: Mutex<()>,
+    refresh_interval: Duration,
+    min_refresh_interval: Duration,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwksVerifier {
+    /// Create a verifier for keys from `source`
+    pub fn new(source: Arc<dyn JwksSource>) -> Self {
+        Self {
+            source,
+            cache: RwLock::new(KeyCache::default()),
+            refresh: Mutex::new(()),
+            refresh_interval: Duration::from_secs(15 * 60),
+            min_refresh_interval: Duration::from_secs(30),
+            issuer: None,
+            audience: None,
+        }
+    }
+
+    /// Create a verifier for keys from a JWKS URL
+    #[cfg(feature = "http")]
+    pub fn from_url(url: impl Into<String>) -> InfraResult<Self> {
+        Ok(Self::new(Arc::new(HttpJwksSource::new(url)?)))
+    }
+
+    /// Set how long fetched keys are used before fetching them again
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Set the minimum time between fetches
+    pub fn with_min_refresh_interval(mut self, interval: Duration) -> Self {
+        self.min_refresh_interval = interval;
+        self
+    }
+
+    /// Only accept tokens issued by `iss`
+    pub fn with_issuer(mut self, iss: impl Into<String>) -> Self {
+        self.issuer = Some(iss.into());
+        self
+    }
+
+    /// Only accept tokens intended for `aud`
+    pub fn with_audience(mut self, aud: impl Into<String>) -> Self {
+        self.audience = Some(aud.into());
+        self
+    }
+
+    /// Verify and decode a JWT
+    pub async fn verify<T: DeserializeOwned>(&self, token: &str) -> InfraResult<Claims<T>> {
+        let kid = jwt::key_id(token)?;
+        let signer = self.signer(kid.as_deref()).await?;
+        signer.verify(token)
+    }
+
+    /// Fetch the key set now, unless it was fetched within the minimum
+    /// refresh interval
+    pub async fn refresh(&self) -> InfraResult<()> {
+        let _refreshing = self.refresh.lock().await;
+        let recently = self
+            .cache
+            .read()
+            .await
+            .attempted_at
+            .is_some_and(|attempted_at| attempted_at.elapsed() < self.min_refresh_interval);
+        if recently {
+            return Ok(());
+        }
+
+        self.cache.write().await.attempted_at = Some(Instant::now());
+        let set = self.source.fetch().await?;
+        let signers = self.signers(&set);
+        tracing::debug!(keys = signers.len(), "Fetched JWKS");
+
+        let mut cache = self.cache.write().await;
+        cache.signers = signers;
+        cache.fetched_at = Some(Instant::now());
+        Ok(())
+    }
+
+    async fn signer(&self, kid: Option<&str>) -> InfraResult<Arc<JwtSigner>> {
+        {
+            let cache = self.cache.read().await;
+            if cache.is_fresh(self.refresh_interval) {
+                if let Some(signer) = cache.select(kid) {
+                    return Ok(signer);
+                }
+            }
+        }
+
+        if let Err(e) = self.refresh().await {
+            tracing::warn!(error = %e, "Failed to refresh JWKS");
+            if self.cache.read().await.fetched_at.is_none() {
+                return Err(e);
+            }
+        }
+
+        self.cache
+            .read()
+            .await
+            .select(kid)
+            .ok_or_else(|| InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                message: format!("Unknown signing key: {}", kid.unwrap_or("<none>")),
+                identity: None,
+                context: None,
+                source: None,
+            })
+    }
+
+    /// Build verifiers for the usable keys of a set
+    fn signers(&self, set: &JwkSet) -> Vec<Arc<JwtSigner>> {
+        set.keys
+            .iter()
+            .filter_map(|jwk| match JwtSigner::from_jwk(jwk) {
+                Ok(signer) => Some(signer),
+                Err(e) => {
+                    tracing::debug!(kid = ?jwk.common.key_id, error = %e, "Skipping JWK");
+                    None
+                }
+            })
+            .map(|mut signer| {
+                if let Some(iss) = &self.issuer {
+                    signer = signer.with_issuer(iss);
+                }
+                if let Some(aud) = &self.audience {
+                    signer = signer.with_audience(aud);
+                }
+                Arc::new(signer)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex as StdMutex;
+
+    /// Key set the test can rotate, counting fetches
+    struct Rotating {
+        set: StdMutex<JwkSet>,
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl JwksSource for Rotating {
+        async fn fetch(&self) -> InfraResult<JwkSet> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(self.set.lock().unwrap().clone())
+        }
+    }
+
+    fn jwks(json: &str) -> JwkSet {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn token(signer: &JwtSigner) -> String {
+        let claims: Claims<()> = Claims::new(ChronoDuration::hours(1))
+            .with_subject("user-1")
+            .with_issuer("https://idp.example.com")
+            .with_audience("api");
+        signer.sign(&claims).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_key_rotation() {
+        let rsa = JwtSigner::rs256(
+            include_bytes!("../testdata/rsa_private.pem"),
+            include_bytes!("../testdata/rsa_public.pem"),
+        )
+        .unwrap()
+        .with_key_id("rsa-1");
+        let ec = JwtSigner::es256(
+            include_bytes!("../testdata/ec_private.pem"),
+            include_bytes!("../testdata/ec_public.pem"),
+        )
+        .unwrap()
+        .with_key_id("ec-1");
+
+        let source = Arc::new(Rotating {
+            set: StdMutex::new(jwks(include_str!("../testdata/jwks_rsa.json"))),
+            fetches: AtomicUsize::new(0),
+        });
+        let verifier = JwksVerifier::new(source.clone())
+            .with_min_refresh_interval(Duration::ZERO)
+            .with_issuer("https://idp.example.com")
+            .with_audience("api");
+
+        let claims: Claims<()> = verifier.verify(&token(&rsa)).await.unwrap();
+        assert_eq!(claims.sub, Some("user-1".to_string()));
+        verifier.verify::<()>(&token(&rsa)).await.unwrap();
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 1);
+
+        // A token signed with a key not yet published fails
+        assert!(verifier.verify::<()>(&token(&ec)).await.is_err());
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 2);
+
+        // Once the IdP rotates the key in, it is fetched on demand
+        *source.set.lock().unwrap() = jwks(include_str!("../testdata/jwks_rotated.json"));
+        verifier.verify::<()>(&token(&ec)).await.unwrap();
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_keys_are_rate_limited() {
+        let source = Arc::new(Rotating {
+            set: StdMutex::new(jwks(include_str!("../testdata/jwks_rsa.json"))),
+            fetches: AtomicUsize::new(0),
+        });
+        let verifier = JwksVerifier::new(source.clone()).with_audience("api");
+        let forged = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!").with_key_id("nope");
+
+        for _ in 0..3 {
+            let err = verifier.verify::<()>(&token(&forged)).await.unwrap_err();
+            assert!(matches!(
+                err,
+                InfraError::Auth {
+                    kind: AuthErrorKind::InvalidToken,
+                    ..
+                }
+            ));
+        }
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 1);
+    }
+}
@@ -5,15 +5,40 @@
 
 mod identity;
 mod session;
+mod file_session;
+#[cfg(feature = "redis")]
+mod redis_session;
 mod permission;
 mod policy;
+mod condition;
+mod roles;
+mod tenant;
 mod middleware;
+mod mtls;
+mod jwks;
+mod oidc;
+mod token;
 
-pub use identity::{Identity, IdentityProvider, TokenIdentity};
-pub use session::{Session, SessionStore, MemorySessionStore};
-pub use permission::{Permission, PermissionSet, Action, Resource};
+pub use identity::{AsyncIdentityProvider, Identity, IdentityProvider, TokenIdentity};
+pub use session::{Session, SessionManager, SessionStore, MemorySessionStore};
+pub use file_session::FileSessionStore;
+pub use permission::{resource_matches, Permission, PermissionSet, Action, Resource, Scope, ScopeSet};
 pub use policy::{Policy, PolicyEngine, PolicyDecision, Effect};
-pub use middleware::{AuthContext, AuthError};
+pub use condition::{Condition, ConditionExpr, Operand, Operator, RequestContext};
+pub use roles::{GroupResolver, RoleGraph, RoleResolver};
+pub use tenant::{tenant_of, Tenant};
+pub use middleware::{bearer_token, AuthContext, AuthError};
+pub use mtls::{ClientCertificate, MtlsIdentityProvider, SpiffeId, XFCC_HEADER};
+pub use jwks::{JwksSource, JwksVerifier};
+pub use oidc::{OidcDiscovery, OidcProvider};
+pub use token::{
+    IdentityLoader, MemoryTokenStore, RefreshRecord, TokenPair, TokenService, TokenStore,
+};
+
+#[cfg(feature = "http")]
+pub use jwks::HttpJwksSource;
+#[cfg(feature = "redis")]
+pub use redis_session::RedisSessionStore;
 
 #[cfg(feature = "axum")]
 pub mod axum_integration;
@@ -1,7 +1,8 @@
 //! Authentication middleware.
 
 use crate::identity::Identity;
-use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use crate::tenant::Tenant;
+use infra_errors::{AuthErrorKind, ErrorSource, InfraError, InfraResult};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -39,10 +40,28 @@ impl From<AuthError> for InfraError {
             message: err.to_string(),
             identity: None,
             context: None,
+            source: Some(ErrorSource::new(err)),
         }
     }
 }
 
+/// Extract the token from an `Authorization` header value
+///
+/// Returns `None` for schemes other than `Bearer`, and rejects a bearer
+/// header whose token is missing or contains whitespace.
+pub fn bearer_token(header: &str) -> Result<Option<&str>, AuthError> {
+    let header = header.trim();
+    let (scheme, token) = header.split_once(' ').unwrap_or((header, ""));
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return Ok(None);
+    }
+    let token = token.trim();
+    if token.is_empty() || token.contains(char::is_whitespace) {
+        return Err(AuthError::InvalidToken);
+    }
+    Ok(Some(token))
+}
+
 /// Authentication context
 #[derive(Debug, Clone)]
 pub struct AuthContext {
@@ -50,6 +69,8 @@ pub struct AuthContext {
     identity: Option<Identity>,
     /// Token (if authenticated via token)
     token: Option<String>,
+    /// Tenant acted on behalf of, if not the identity's own
+    tenant: Option<Tenant>,
 }
 
 impl AuthContext {
@@ -58,6 +79,7 @@ impl AuthContext {
         Self {
             identity: None,
             token: None,
+            tenant: None,
         }
     }
 
@@ -66,6 +88,7 @@ impl AuthContext {
         Self {
             identity: Some(identity),
             token: None,
+            tenant: None,
         }
     }
 
@@ -74,6 +97,7 @@ impl AuthContext {
         Self {
             identity: Some(identity),
             token: Some(token),
+            tenant: None,
         }
     }
 
@@ -104,6 +128,19 @@ impl AuthContext {
     pub fn token(&self) -> Option<&str> {
         self.token.as_deref()
     }
+
+    /// Act on behalf of a tenant other than the identity's own
+    pub fn for_tenant(mut self, tenant: Tenant) -> Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    /// Get the tenant acted on behalf of, defaulting to the identity's
+    pub fn tenant(&self) -> Option<&Tenant> {
+        self.tenant
+            .as_ref()
+            .or_else(|| self.identity.as_ref()?.tenant())
+    }
 }
 
 impl Default for AuthContext {
@@ -170,6 +207,16 @@ mod tests {
         assert!(!ctx.has_role("guest"));
     }
 
+    #[test]
+    fn test_bearer_token() {
+        assert_eq!(bearer_token("Bearer abc").unwrap(), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc ").unwrap(), Some("abc"));
+        assert_eq!(bearer_token("Basic dXNlcg==").unwrap(), None);
+        for malformed in ["Bearer", "Bearer ", "Bearer a b"] {
+            assert!(bearer_token(malformed).is_err(), "{malformed}");
+        }
+    }
+
     #[tokio::test]
     async fn test_request_auth_context() {
         let ctx = RequestAuthContext::new();
@@ -0,0 +1,401 @@
+//! Service identities from mutual TLS client certificates.
+
+use crate::identity::Identity;
+use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Header in which mesh proxies such as Envoy forward client certificate
+/// details
+pub const XFCC_HEADER: &str = "x-forwarded-client-cert";
+
+/// Names from a verified client certificate
+///
+/// The TLS terminator verifies the certificate chain; this only carries the
+/// names used to identify the peer. Insert it into request extensions, build
+/// it from DER with the `x509` feature, or parse it from the
+/// `x-forwarded-client-cert` header set by a trusted sidecar proxy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// Subject distinguished name
+    pub subject: Option<String>,
+    /// URI subject alternative names
+    pub uris: Vec<String>,
+    /// DNS subject alternative names
+    pub dns_names: Vec<String>,
+}
+
+impl ClientCertificate {
+    /// Create a certificate without names
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the subject
+    pub fn with_subject(mut self, subject: impl Into<String>) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// Add a URI SAN
+    pub fn with_uri(mut self, uri: impl Into<String>) -> Self {
+        self.uris.push(uri.into());
+        self
+    }
+
+    /// Add a DNS SAN
+    pub fn with_dns_name(mut self, name: impl Into<String>) -> Self {
+        self.dns_names.push(name.into());
+        self
+    }
+
+    /// Read the names of a DER-encoded certificate
+    #[cfg(feature = "x509")]
+    pub fn from_der(der: &[u8]) -> InfraResult<Self> {
+        use x509_parser::extensions::GeneralName;
+
+        let (_, cert) = x509_parser::parse_x509_certificate(der)
+            .map_err(|e| invalid_certificate(format!("Malformed client certificate: {e}")))?;
+        let mut certificate = Self::new().with_subject(cert.subject().to_string());
+        let san = cert
+            .subject_alternative_name()
+            .map_err(|e| invalid_certificate(format!("Malformed SAN extension: {e}")))?;
+        for name in san.iter().flat_map(|ext| &ext.value.general_names) {
+            match name {
+                GeneralName::URI(uri) => certificate.uris.push(uri.to_string()),
+                GeneralName::DNSName(dns) => certificate.dns_names.push(dns.to_string()),
+                _ => {}
+            }
+        }
+        Ok(certificate)
+    }
+
+    /// Parse an `x-forwarded-client-cert` header
+    ///
+    /// Each proxy appends an element, so the last one describes the
+    /// certificate the nearest proxy verified.
+    pub fn from_xfcc(header: &str) -> InfraResult<Self> {
+        let element = split_unquoted(header, ',')
+            .into_iter()
+            .last()
+            .filter(|element| !element.trim().is_empty())
+            .ok_or_else(|| invalid_certificate("Empty x-forwarded-client-cert header"))?;
+
+        let mut certificate = Self::new();
+        for pair in split_unquoted(element, ';') {
+            let Some((key, value)) = pair.split_once('=') else {
+                return Err(invalid_certificate(format!(
+                    "Malformed x-forwarded-client-cert pair: {pair:?}"
+                )));
+            };
+            let value = unquote(value.trim());
+            match key.trim().to_ascii_lowercase().as_str() {
+                "subject" => certificate.subject = Some(value),
+                "uri" => certificate.uris.push(value),
+                "dns" => certificate.dns_names.push(value),
+                _ => {}
+            }
+        }
+        Ok(certificate)
+    }
+
+    /// Get the first SPIFFE ID among the URI SANs
+    pub fn spiffe_id(&self) -> Option<SpiffeId> {
+        self.uris.iter().find_map(|uri| uri.parse().ok())
+    }
+}
+
+/// Split on a separator outside double quotes
+fn split_unquoted(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Strip quotes and escapes from a header value
+fn unquote(value: &str) -> String {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+fn invalid_certificate(message: impl Into<String>) -> InfraError {
+    InfraError::Auth {
+        kind: AuthErrorKind::InvalidCredentials,
+        message: message.into(),
+        identity: None,
+        context: None,
+        source: None,
+    }
+}
+
+/// SPIFFE ID of a workload, such as `spiffe://example.org/ns/prod/sa/billing`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpiffeId {
+    trust_domain: String,
+    path: String,
+}
+
+impl SpiffeId {
+    /// Get the trust domain
+    pub fn trust_domain(&self) -> &str {
+        &self.trust_domain
+    }
+
+    /// Get the workload path, starting with `/`
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get the last path segment, usually the service account name
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+impl FromStr for SpiffeId {
+    type Err = InfraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InfraError::validation(format!("Invalid SPIFFE ID: {s:?}"));
+        let rest = s.strip_prefix("spiffe://").ok_or_else(invalid)?;
+        let (trust_domain, path) = rest.split_once('/').ok_or_else(invalid)?;
+        let valid_domain = !trust_domain.is_empty()
+            && trust_domain
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c));
+        let valid_path = path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+        if !valid_domain || !valid_path || s.contains(['?', '#']) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            trust_domain: trust_domain.to_string(),
+            path: format!("/{path}"),
+        })
+    }
+}
+
+impl fmt::Display for SpiffeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "spiffe://{}{}", self.trust_domain, self.path)
+    }
+}
+
+/// Identity provider for services authenticated by mutual TLS
+///
+/// Certificates are identified by their SPIFFE ID, which must belong to a
+/// trusted domain; the ID becomes the identity's ID and its last segment the
+/// name. Certificates without a SPIFFE ID can be identified by a DNS SAN
+/// under an allowed suffix. Roles are granted per service ID.
+#[derive(Debug, Clone)]
+pub struct MtlsIdentityProvider {
+    trust_domains: Vec<String>,
+    dns_suffixes: Vec<String>,
+    roles: HashMap<String, Vec<String>>,
+}
+
+impl MtlsIdentityProvider {
+    /// Create a provider trusting SPIFFE IDs of `trust_domain`
+    pub fn new(trust_domain: impl Into<String>) -> Self {
+        Self {
+            trust_domains: vec![trust_domain.into()],
+            dns_suffixes: Vec::new(),
+            roles: HashMap::new(),
+        }
+    }
+
+    /// Also trust SPIFFE IDs of `trust_domain`
+    pub fn with_trust_domain(mut self, trust_domain: impl Into<String>) -> Self {
+        self.trust_domains.push(trust_domain.into());
+        self
+    }
+
+    /// Identify certificates without a SPIFFE ID by DNS SANs under the
+    /// domain `suffix`, such as `.prod.svc.cluster.local`
+    ///
+    /// The suffix matches whole labels, so `example.com` admits
+    /// `api.example.com` but not `evilexample.com` or `example.com` itself.
+    pub fn with_dns_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.dns_suffixes.push(suffix.into());
+        self
+    }
+
+    /// Grant roles to the service with the given SPIFFE ID or DNS name
+    pub fn with_service_roles<I, S>(mut self, id: impl Into<String>, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles
+            .entry(id.into())
+            .or_default()
+            .extend(roles.into_iter().map(Into::into));
+        self
+    }
+
+    /// Identify the service presenting a certificate
+    pub fn identify(&self, certificate: &ClientCertificate) -> InfraResult<Identity> {
+        let identity = if let Some(spiffe_id) = certificate.spiffe_id() {
+            if !self
+                .trust_domains
+                .iter()
+                .any(|d| d == spiffe_id.trust_domain())
+            {
+                return Err(InfraError::Auth {
+                    kind: AuthErrorKind::InvalidCredentials,
+                    message: format!(
+                        "Untrusted SPIFFE trust domain: {}",
+                        spiffe_id.trust_domain()
+                    ),
+                    identity: Some(spiffe_id.to_string()),
+                    context: None,
+                    source: None,
+                });
+            }
+            Identity::service(spiffe_id.to_string())
+                .with_name(spiffe_id.name())
+                .with_attribute("spiffe_id", spiffe_id.to_string())
+                .with_attribute("trust_domain", spiffe_id.trust_domain())
+        } else if let Some(dns) = certificate.dns_names.iter().find(|name| {
+            self.dns_suffixes
+                .iter()
+                .any(|suffix| under_domain(name, suffix))
+        }) {
+            Identity::service(dns.clone()).with_name(dns.split('.').next().unwrap_or(dns))
+        } else {
+            return Err(invalid_certificate(
+                "Client certificate has no trusted SPIFFE ID or DNS name",
+            ));
+        };
+
+        let roles = self.roles.get(&identity.id).cloned().unwrap_or_default();
+        Ok(identity.with_roles(roles))
+    }
+}
+
+/// Check whether a DNS name is a subdomain of `domain`, ignoring case and a
+/// leading dot on the domain
+fn under_domain(name: &str, domain: &str) -> bool {
+    let domain = domain.strip_prefix('.').unwrap_or(domain);
+    let Some(split) = name.len().checked_sub(domain.len() + 1) else {
+        return false;
+    };
+    split > 0 && name.as_bytes()[split] == b'.' && name[split + 1..].eq_ignore_ascii_case(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spiffe_identity() {
+        let provider = MtlsIdentityProvider::new("example.org")
+            .with_dns_suffix(".prod.svc")
+            .with_service_roles("spiffe://example.org/ns/prod/sa/billing", ["billing"]);
+
+        let certificate =
+            ClientCertificate::new().with_uri("spiffe://example.org/ns/prod/sa/billing");
+        let identity = provider.identify(&certificate).unwrap();
+        assert_eq!(identity.id, "spiffe://example.org/ns/prod/sa/billing");
+        assert_eq!(identity.name.as_deref(), Some("billing"));
+        assert!(identity.has_role("billing"));
+
+        let foreign = ClientCertificate::new().with_uri("spiffe://evil.org/ns/prod/sa/billing");
+        assert!(provider.identify(&foreign).is_err());
+
+        let dns = ClientCertificate::new().with_dns_name("ledger.prod.svc");
+        assert_eq!(provider.identify(&dns).unwrap().id, "ledger.prod.svc");
+        let dns = ClientCertificate::new().with_dns_name("ledger.staging.svc");
+        assert!(provider.identify(&dns).is_err());
+        let dns = ClientCertificate::new().with_dns_name("ledger-prod.svc");
+        assert!(provider.identify(&dns).is_err());
+
+        assert!(under_domain("api.example.com", "example.com"));
+        assert!(under_domain("API.Example.com", ".example.com"));
+        assert!(!under_domain("evilexample.com", "example.com"));
+        assert!(!under_domain("example.com", "example.com"));
+        assert!(!under_domain(".example.com", "example.com"));
+
+        assert!("spiffe://example.org".parse::<SpiffeId>().is_err());
+        assert!("spiffe://Example.org/a".parse::<SpiffeId>().is_err());
+        assert!("spiffe://example.org/a/../b".parse::<SpiffeId>().is_err());
+    }
+
+    #[test]
+    fn test_xfcc_header() {
+        let header = concat!(
+            "By=spiffe://example.org/ns/edge/sa/gateway;URI=spiffe://example.org/ns/web/sa/frontend,",
+            "By=spiffe://example.org/ns/prod/sa/api;Hash=abc123;",
+            "Subject=\"CN=billing,O=Example\\\"s\";URI=spiffe://example.org/ns/prod/sa/billing;",
+            "DNS=billing.prod.svc"
+        );
+        let certificate = ClientCertificate::from_xfcc(header).unwrap();
+        assert_eq!(
+            certificate.subject.as_deref(),
+            Some("CN=billing,O=Example\"s")
+        );
+        assert_eq!(certificate.dns_names, ["billing.prod.svc"]);
+        assert_eq!(
+            certificate.spiffe_id().unwrap().path(),
+            "/ns/prod/sa/billing"
+        );
+        assert!(ClientCertificate::from_xfcc("").is_err());
+    }
+
+    #[cfg(feature = "x509")]
+    #[test]
+    fn test_certificate_from_der() {
+        use base64::Engine;
+
+        const CERT: &str = concat!(
+            "MIIBwjCCAWmgAwIBAgIUGXiVyeUwVQXcoUmEji94VUBybw4wCgYIKoZIzj0EAwIw",
+            "EjEQMA4GA1UEAwwHYmlsbGluZzAgFw0yNjEwMTUwODQxMDVaGA8yMTI2MDkyMTA4",
+            "NDEwNVowEjEQMA4GA1UEAwwHYmlsbGluZzBZMBMGByqGSM49AgEGCCqGSM49AwEH",
+            "A0IABIRMq8/KCcaYJhQo2Jefk2jLZ6Td67xlwH8rsEfyKthgAa+4N/SH6QbY0gV5",
+            "NXRzxTemjlkaDXkVcPeUG4K98zWjgZowgZcwHQYDVR0OBBYEFN004puge+Mri4gQ",
+            "0U13+skBVJYNMB8GA1UdIwQYMBaAFN004puge+Mri4gQ0U13+skBVJYNMA8GA1Ud",
+            "EwEB/wQFMAMBAf8wRAYDVR0RBD0wO4Ync3BpZmZlOi8vZXhhbXBsZS5vcmcvbnMv",
+            "cHJvZC9zYS9iaWxsaW5nghBiaWxsaW5nLnByb2Quc3ZjMAoGCCqGSM49BAMCA0cA",
+            "MEQCIA+1VCLjmJ6c+iwHAOG8NYAHao62jVXTLnOUx2bcCPPcAiBV/6nY3ygAqCIx",
+            "fDWlozeCY6FLHOoST28HylUIP/Xg7g==",
+        );
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(CERT)
+            .unwrap();
+        let certificate = ClientCertificate::from_der(&der).unwrap();
+        assert_eq!(certificate.subject.as_deref(), Some("CN=billing"));
+        assert_eq!(
+            certificate.uris,
+            ["spiffe://example.org/ns/prod/sa/billing"]
+        );
+        assert_eq!(certificate.dns_names, ["billing.prod.svc"]);
+        assert!(ClientCertificate::from_der(b"not a certificate").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//! OpenID Connect identity provider.
+
+use crate::identity::{AsyncIdentityProvider, Identity};
+use crate::jwks::{JwksSource, JwksVerifier};
+use crate::tenant::Tenant;
+use async_trait::async_trait;
+use infra_crypto::constant_time_eq;
+use infra_crypto::jwt::Claims;
+use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// OpenID Connect discovery document
+///
+/// Only the fields used for token validation and common client flows are
+/// kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcDiscovery {
+    /// Issuer identifier
+    pub issuer: String,
+    /// URL of the provider's JWKS document
+    pub jwks_uri: String,
+    /// Authorization endpoint
+    #[serde(default)]
+    pub authorization_endpoint: Option<String>,
+    /// Token endpoint
+    #[serde(default)]
+    pub token_endpoint: Option<String>,
+    /// UserInfo endpoint
+    #[serde(default)]
+    pub userinfo_endpoint: Option<String>,
+    /// Algorithms the provider signs ID tokens with
+    #[serde(default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+}
+
+/// Identity provider trusting tokens from an OpenID Connect issuer
+///
+/// Tokens must be signed by a key from the issuer's JWKS, and carry the
+/// expected issuer and audience. Claims map to an [`Identity`]: `sub` becomes
+/// the ID, `name` (or `preferred_username`) and `email` fill the profile,
+/// roles are read from the configured role claims, the tenant from the
+/// tenant claim if one is configured, and the remaining claims become
+/// attributes.
+///
+/// Role claims are claim names or dotted paths into nested claims, such as
+/// `roles` for Entra ID or `realm_access.roles` for Keycloak. Namespaced
+/// claims like Auth0's `https://example.com/roles` are matched by exact name
+/// first. Standard OpenID Connect scopes such as `openid` and `profile` are
+/// never roles, so `scope` can be used as a role claim.
+pub struct OidcProvider {
+    issuer: String,
+    verifier: JwksVerifier,
+    role_claims: Vec<String>,
+    tenant_claim: Option<String>,
+    discovery: Option<OidcDiscovery>,
+}
+
+/// Scopes defined by OpenID Connect, which grant access to claims rather
+/// than name roles
+const OIDC_SCOPES: &[&str] = &[
+    "openid",
+    "profile",
+    "email",
+    "address",
+    "phone",
+    "offline_access",
+];
+
+impl OidcProvider {
+    /// Create a provider for tokens from `issuer` intended for `audience`,
+    /// verified with keys from `keys`
+    pub fn new(
+        issuer: impl Into<String>,
+        audience: impl Into<String>,
+        keys: Arc<dyn JwksSource>,
+    ) -> Self {
+        let issuer = issuer.into();
+        Self {
+            verifier: JwksVerifier::new(keys)
+                .with_issuer(&issuer)
+                .with_audience(audience),
+            issuer,
+            role_claims: vec!["roles".to_string()],
+            tenant_claim: None,
+            discovery: None,
+        }
+    }
+
+    /// Create a provider from the issuer's discovery document
+    ///
+    /// Fetches `{issuer}/.well-known/openid-configuration` and verifies
+    /// tokens with keys from its `jwks_uri`. The document's issuer must be
+    /// exactly `issuer`.
+    #[cfg(feature = "http")]
+    pub async fn discover(issuer: &str, audience: impl Into<String>) -> InfraResult<Self> {
+        let client = infra_http::HttpClient::new()?;
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let discovery: OidcDiscovery = client.get_json(&url).await?;
+        if discovery.issuer != issuer {
+            return Err(InfraError::config(format!(
+                "Discovery document issuer {} does not match {issuer}",
+                discovery.issuer
+            )));
+        }
+
+        let keys = crate::jwks::HttpJwksSource::with_client(client, &discovery.jwks_uri);
+        let mut provider = Self::new(&discovery.issuer, audience, Arc::new(keys));
+        provider.discovery = Some(discovery);
+        Ok(provider)
+    }
+
+    /// Read roles from these claims instead of `roles`
+    pub fn with_role_claims<I, S>(mut self, claims: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.role_claims = claims.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Read the identity's tenant from this claim, such as `tid` for Entra
+    /// ID
+    ///
+    /// Tokens whose claim is not a valid tenant ID are rejected.
+    pub fn with_tenant_claim(mut self, claim: impl Into<String>) -> Self {
+        self.tenant_claim = Some(claim.into());
+        self
+    }
+
+    /// Get the issuer
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Get the discovery document, if the provider was discovered
+    pub fn discovery(&self) -> Option<&OidcDiscovery> {
+        self.discovery.as_ref()
+    }
+
+    /// Verify an ID token issued for a login started with `nonce`
+    pub async fn verify_id_token(&self, token: &str, nonce: &str) -> InfraResult<Identity> {
+        let claims: Claims<Map<String, Value>> = self.verifier.verify(token).await?;
+        let claimed = claims.payload.get("nonce").and_then(Value::as_str);
+        if !claimed.is_some_and(|claimed| constant_time_eq(claimed.as_bytes(), nonce.as_bytes())) {
+            return Err(InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                message: "ID token nonce mismatch".to_string(),
+                identity: claims.sub,
+                context: None,
+                source: None,
+            });
+        }
+        self.identity(claims)
+    }
+
+    /// Map verified claims to an identity
+    fn identity(&self, claims: Claims<Map<String, Value>>) -> InfraResult<Identity> {
+        let Some(sub) = claims.sub else {
+            return Err(InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                message: "Token has no subject".to_string(),
+                identity: None,
+                context: None,
+                source: None,
+            });
+        };
+        let mut attributes = claims.payload;
+        attributes.remove("nonce");
+
+        let text = |key: &str| {
+            attributes
+                .get(key)
+                .and_then(Value::as_str)
+                .map(String::from)
+        };
+        let mut identity = Identity::user(sub);
+        identity.name = text("name").or_else(|| text("preferred_username"));
+        identity.email = text("email");
+        for role in self
+            .role_claims
+            .iter()
+            .flat_map(|claim| roles(&attributes, claim))
+            .filter(|role| !OIDC_SCOPES.contains(&role.as_str()))
+        {
+            if !identity.has_role(&role) {
+                identity.roles.push(role);
+            }
+        }
+        if let Some(claim) = &self.tenant_claim {
+            if let Some(tenant) = attributes.get(claim).and_then(Value::as_str) {
+                identity.tenant = Some(Tenant::new(tenant).map_err(|e| InfraError::Auth {
+                    kind: AuthErrorKind::InvalidToken,
+                    message: format!("Invalid tenant claim {claim}: {e}"),
+                    identity: Some(identity.id.clone()),
+                    context: None,
+                    source: None,
+                })?);
+            }
+        }
+        identity.attributes = attributes.into_iter().collect();
+        if let Some(iss) = claims.iss {
+            identity.attributes.insert("iss".to_string(), iss.into());
+        }
+        Ok(identity)
+    }
+}
+
+#[async_trait]
+impl AsyncIdentityProvider for OidcProvider {
+    async fn verify(&self, token: &str) -> InfraResult<Identity> {
+        let claims = self.verifier.verify(token).await?;
+        self.identity(claims)
+    }
+}
+
+/// Read the roles in a claim, by exact name or dotted path
+///
+/// Accepts a list of strings, or a single string of space-separated roles.
+fn roles(claims: &Map<String, Value>, claim: &str) -> Vec<String> {
+    let value = claims.get(claim).or_else(|| {
+        let mut parts = claim.split('.');
+        let first = claims.get(parts.next()?)?;
+        parts.try_fold(first, |value, part| value.get(part))
+    });
+
+    match value {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        Some(Value::String(value)) => value.split_whitespace().map(String::from).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use infra_crypto::jwt::{JwkSet, JwtSigner};
+    use serde_json::json;
+
+    const ISSUER: &str = "https://idp.example.com/realms/llm";
+
+    fn signer() -> JwtSigner {
+        JwtSigner::rs256(
+            include_bytes!("../testdata/rsa_private.pem"),
+            include_bytes!("../testdata/rsa_public.pem"),
+        )
+        .unwrap()
+        .with_key_id("rsa-1")
+    }
+
+    fn provider() -> OidcProvider {
+        let keys: JwkSet = serde_json::from_str(include_str!("../testdata/jwks_rsa.json")).unwrap();
+        OidcProvider::new(ISSUER, "llm-gateway", Arc::new(keys))
+    }
+
+    fn token(payload: Value, audience: &str) -> String {
+        let Value::Object(payload) = payload else {
+            unreachable!()
+        };
+        let claims = Claims::with_payload(payload, Duration::hours(1))
+            .with_subject("user-1")
+            .with_issuer(ISSUER)
+            .with_audience(audience);
+        signer().sign(&claims).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_claims_to_identity() {
+        let provider = provider()
+            .with_role_claims(["realm_access.roles", "scope"])
+            .with_tenant_claim("tenant");
+        let token = token(
+            json!({
+                "preferred_username": "ada",
+                "email": "ada@example.com",
+                "realm_access": { "roles": ["admin", "user"] },
+                "scope": "openid user",
+                "tenant": "acme",
+            }),
+            "llm-gateway",
+        );
+
+        let identity = provider.verify(&token).await.unwrap();
+        assert_eq!(identity.id, "user-1");
+        assert_eq!(identity.name.as_deref(), Some("ada"));
+        assert_eq!(identity.email.as_deref(), Some("ada@example.com"));
+        assert_eq!(identity.roles, ["admin", "user"]);
+        assert_eq!(identity.tenant().map(Tenant::id), Some("acme"));
+        assert_eq!(identity.attributes["iss"], ISSUER);
+
+        let token = self::token(json!({ "tenant": "../acme" }), "llm-gateway");
+        assert!(provider.verify(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_audience_and_nonce() {
+        let provider = provider();
+
+        let other = token(json!({}), "another-app");
+        assert!(provider.verify(&other).await.is_err());
+
+        let id_token = token(json!({ "nonce": "n-0S6_WzA2Mj" }), "llm-gateway");
+        assert!(provider
+            .verify_id_token(&id_token, "n-0S6_WzA2Mj")
+            .await
+            .is_ok());
+        let err = provider
+            .verify_id_token(&id_token, "replayed")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_discovery_document() {
+        let discovery: OidcDiscovery = serde_json::from_value(json!({
+            "issuer": ISSUER,
+            "jwks_uri": format!("{ISSUER}/protocol/openid-connect/certs"),
+            "id_token_signing_alg_values_supported": ["RS256", "ES256"],
+            "claims_supported": ["sub", "email"],
+        }))
+        .unwrap();
+        assert!(discovery.jwks_uri.ends_with("/certs"));
+        assert!(discovery.token_endpoint.is_none());
+    }
+}
@@ -1,7 +1,47 @@
 //! Permission types.
 
+use crate::condition::glob_match;
+use infra_errors::{InfraError, InfraResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Check whether a resource name matches a pattern
+///
+/// Names are hierarchical, with `/` separating levels. In a pattern, `*` and
+/// `?` match within a level, so `models:gpt-4*` matches `models:gpt-4o` and
+/// `projects/*/datasets` matches `projects/p1/datasets`, while a `**` level
+/// matches any number of levels, so `projects/p1/**` matches the project and
+/// everything under it. A pattern of just `*` matches every resource.
+pub fn resource_matches(pattern: &str, resource: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let resource: Vec<&str> = resource.split('/').collect();
+    segments_match(&pattern, &resource)
+}
+
+/// Match levels in time proportional to the product of their counts, however
+/// many `**` levels the pattern has
+fn segments_match(pattern: &[&str], resource: &[&str]) -> bool {
+    // matched[j]: the pattern levels so far match the first j resource levels
+    let mut matched = vec![false; resource.len() + 1];
+    matched[0] = true;
+    for level in pattern {
+        if *level == "**" {
+            for j in 1..=resource.len() {
+                matched[j] = matched[j] || matched[j - 1];
+            }
+        } else {
+            for j in (1..=resource.len()).rev() {
+                matched[j] = matched[j - 1] && glob_match(level, resource[j - 1]);
+            }
+            matched[0] = false;
+        }
+    }
+    matched[resource.len()]
+}
 
 /// A resource that can be accessed
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -30,6 +70,8 @@ impl Resource {
     }
 
     /// Check if this resource matches another (considering wildcards)
+    ///
+    /// The ID may be a pattern, as accepted by [`resource_matches`].
     pub fn matches(&self, other: &Resource) -> bool {
         if self.resource_type != other.resource_type {
             return false;
@@ -37,7 +79,7 @@ impl Resource {
 
         match (&self.id, &other.id) {
             (None, _) => true, // Wildcard matches everything
-            (Some(a), Some(b)) => a == b,
+            (Some(a), Some(b)) => resource_matches(a, b),
             (Some(_), None) => false, // Specific doesn't match wildcard
         }
     }
@@ -61,6 +103,220 @@ impl Action {
     pub fn matches(&self, other: &Action) -> bool {
         *self == Action::All || *self == *other
     }
+
+    /// Get the action's name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Read => "read",
+            Action::Write => "write",
+            Action::Create => "create",
+            Action::Delete => "delete",
+            Action::Execute => "execute",
+            Action::Admin => "admin",
+            Action::All => "all",
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Action {
+    type Err = InfraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Action::Read),
+            "write" => Ok(Action::Write),
+            "create" => Ok(Action::Create),
+            "delete" => Ok(Action::Delete),
+            "execute" => Ok(Action::Execute),
+            "admin" => Ok(Action::Admin),
+            "all" => Ok(Action::All),
+            _ => Err(InfraError::validation(format!("Unknown action: {s}"))),
+        }
+    }
+}
+
+/// A permission granted by an OAuth scope
+///
+/// A scope containing `:` or `/` is a resource pattern followed by an
+/// action after the last of them, such as `models:gpt-4*:execute` or
+/// `projects/*/datasets/read`, and fails to parse unless that last part is
+/// an [`Action`]. A scope without either, like `openid`, names no resource
+/// and grants nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Scope {
+    /// Resource pattern, as accepted by [`resource_matches`]
+    pub resource: String,
+    /// Granted action, or `None` for scopes that grant nothing
+    pub action: Option<Action>,
+    /// Separator before the action, kept so the scope displays as parsed
+    separator: char,
+}
+
+impl Scope {
+    /// Create a scope
+    pub fn new(resource: impl Into<String>, action: Action) -> Self {
+        Self {
+            resource: resource.into(),
+            action: Some(action),
+            separator: ':',
+        }
+    }
+
+    /// Parse a single scope
+    pub fn parse(scope: &str) -> InfraResult<Self> {
+        if scope.is_empty() || scope.contains(char::is_whitespace) {
+            return Err(InfraError::validation(format!("Invalid scope: {scope:?}")));
+        }
+
+        let Some(i) = scope.rfind([':', '/']) else {
+            return Ok(Self {
+                resource: scope.to_string(),
+                action: None,
+                separator: ':',
+            });
+        };
+        let (resource, action) = (&scope[..i], &scope[i + 1..]);
+        if resource.is_empty() {
+            return Err(InfraError::validation(format!(
+                "Scope has no resource: {scope:?}"
+            )));
+        }
+        let action = action
+            .parse()
+            .map_err(|_| InfraError::validation(format!("Scope has no valid action: {scope:?}")))?;
+        Ok(Self {
+            resource: resource.to_string(),
+            action: Some(action),
+            separator: scope[i..].chars().next().unwrap_or(':'),
+        })
+    }
+
+    /// Check if the scope allows an action on a resource
+    pub fn allows(&self, resource: &str, action: Action) -> bool {
+        self.action.is_some_and(|granted| granted.matches(&action))
+            && resource_matches(&self.resource, resource)
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            Some(action) => write!(f, "{}{}{action}", self.resource, self.separator),
+            None => f.write_str(&self.resource),
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = InfraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Scopes granted to a client, as in an OAuth `scope` parameter or claim
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopeSet {
+    scopes: Vec<Scope>,
+}
+
+impl ScopeSet {
+    /// Create an empty scope set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a space-separated list of scopes
+    pub fn parse(scopes: &str) -> InfraResult<Self> {
+        Ok(Self {
+            scopes: scopes
+                .split_whitespace()
+                .map(Scope::parse)
+                .collect::<InfraResult<_>>()?,
+        })
+    }
+
+    /// Read the scopes an identity was granted
+    ///
+    /// Uses the `scope` attribute (a space-separated string) or the `scp`
+    /// attribute (a list of scopes), as found in OAuth access tokens. Scopes
+    /// that fail to parse are skipped.
+    pub fn from_identity(identity: &crate::identity::Identity) -> Self {
+        let names: Vec<&str> = match identity
+            .attributes
+            .get("scope")
+            .or_else(|| identity.attributes.get("scp"))
+        {
+            Some(serde_json::Value::String(scopes)) => scopes.split_whitespace().collect(),
+            Some(serde_json::Value::Array(scopes)) => scopes
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            scopes: names
+                .into_iter()
+                .filter_map(|name| Scope::parse(name).ok())
+                .collect(),
+        }
+    }
+
+    /// Add a scope
+    pub fn grant(&mut self, scope: Scope) {
+        if !self.scopes.contains(&scope) {
+            self.scopes.push(scope);
+        }
+    }
+
+    /// Check if any scope allows an action on a resource
+    pub fn allows(&self, resource: &str, action: Action) -> bool {
+        self.scopes
+            .iter()
+            .any(|scope| scope.allows(resource, action))
+    }
+
+    /// Get all scopes
+    pub fn scopes(&self) -> impl Iterator<Item = &Scope> {
+        self.scopes.iter()
+    }
+
+    /// Get the number of scopes
+    pub fn len(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+}
+
+impl std::fmt::Display for ScopeSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, scope) in self.scopes.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{scope}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ScopeSet {
+    type Err = InfraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
 }
 
 /// A permission
@@ -174,4 +430,71 @@ mod tests {
         // Not granted
         assert!(!perms.has(&Permission::new(Resource::new("users"), Action::Delete)));
     }
+
+    #[test]
+    fn test_resource_patterns() {
+        assert!(resource_matches("*", "projects/p1/datasets/d1"));
+        assert!(resource_matches("models:gpt-4*", "models:gpt-4o"));
+        assert!(!resource_matches("models:gpt-4*", "models:claude-3"));
+        assert!(resource_matches(
+            "projects/*/datasets",
+            "projects/p1/datasets"
+        ));
+        assert!(!resource_matches(
+            "projects/*/datasets",
+            "projects/p1/p2/datasets"
+        ));
+        assert!(resource_matches("projects/p1/**", "projects/p1"));
+        assert!(resource_matches(
+            "projects/p1/**",
+            "projects/p1/datasets/d1"
+        ));
+        assert!(resource_matches(
+            "projects/**/d1",
+            "projects/p1/datasets/d1"
+        ));
+        assert!(!resource_matches("projects/p1/**", "projects/p2/datasets"));
+        assert!(resource_matches("**/d1/**", "d1"));
+        assert!(!resource_matches("**/a", "a/b"));
+
+        // Many `**` levels against a long path that almost matches
+        let pattern = "a/**/".repeat(20) + "b";
+        let resource = "a/".repeat(40) + "c";
+        assert!(!resource_matches(&pattern, &resource));
+
+        let gpt4 = Resource::with_id("models", "gpt-4*");
+        assert!(gpt4.matches(&Resource::with_id("models", "gpt-4-turbo")));
+        assert!(!gpt4.matches(&Resource::with_id("models", "gpt-3.5-turbo")));
+    }
+
+    #[test]
+    fn test_scopes() {
+        let scopes = ScopeSet::parse(
+            "openid models:gpt-4*:execute projects/*/datasets/read collections/c1/**:write",
+        )
+        .unwrap();
+        assert_eq!(scopes.len(), 4);
+
+        assert!(!scopes.allows("openid", Action::Read));
+        assert!(scopes.allows("models:gpt-4o", Action::Execute));
+        assert!(!scopes.allows("models:gpt-4o", Action::Admin));
+        assert!(scopes.allows("projects/p1/datasets", Action::Read));
+        assert!(!scopes.allows("projects/p1/datasets", Action::Write));
+        assert!(scopes.allows("collections/c1/docs/42", Action::Write));
+        assert!(!scopes.allows("collections/c2", Action::Write));
+
+        let scope = Scope::parse("projects/*/datasets/read").unwrap();
+        assert_eq!(scope.resource, "projects/*/datasets");
+        assert_eq!(scope.action, Some(Action::Read));
+        assert_eq!(Scope::parse("openid").unwrap().action, None);
+
+        // Scopes display as they were written
+        let text = "openid models:gpt-4*:execute projects/*/datasets/read collections/c1/**:write";
+        assert_eq!(scopes.to_string(), text);
+        assert_eq!(ScopeSet::parse(&scopes.to_string()).unwrap(), scopes);
+
+        assert!(Scope::parse(":read").is_err());
+        assert!(Scope::parse("models:gpt-4o").is_err());
+        assert!(Scope::parse("projects/p1").is_err());
+    }
 }
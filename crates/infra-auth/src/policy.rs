@@ -1,7 +1,11 @@
 //! Policy-based authorization.
 
+use crate::condition::{self, ConditionExpr, RequestContext};
 use crate::identity::Identity;
-use crate::permission::{Action, Permission, Resource};
+use crate::middleware::AuthContext;
+use crate::permission::{resource_matches, Action, Permission, Resource};
+use crate::roles::RoleGraph;
+use crate::tenant::{tenant_of, Tenant};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -74,14 +78,22 @@ pub struct Policy {
     pub effect: Effect,
     /// Required roles (any of these)
     pub roles: Option<Vec<String>>,
-    /// Required attributes
+    /// Required identity attributes
     pub attributes: Option<HashMap<String, serde_json::Value>>,
-    /// Resources this policy applies to
+    /// Condition on the identity and request, such as
+    /// `identity.tenant == context.tenant`, set with [`Policy::when`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    condition: Option<ConditionExpr>,
+    /// Resource patterns this policy applies to
     pub resources: Option<Vec<String>>,
     /// Actions this policy applies to
     pub actions: Option<Vec<Action>>,
     /// Priority (higher = evaluated first)
     pub priority: i32,
+    /// Whether the policy applies to resources of other tenants when the
+    /// engine isolates tenants
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cross_tenant: bool,
 }
 
 impl Policy {
@@ -93,9 +105,11 @@ impl Policy {
             effect: Effect::Allow,
             roles: None,
             attributes: None,
+            condition: None,
             resources: None,
             actions: None,
             priority: 0,
+            cross_tenant: false,
         }
     }
 
@@ -107,9 +121,11 @@ impl Policy {
             effect: Effect::Deny,
             roles: None,
             attributes: None,
+            condition: None,
             resources: None,
             actions: None,
             priority: 0,
+            cross_tenant: false,
         }
     }
 
@@ -131,6 +147,35 @@ impl Policy {
         self
     }
 
+    /// Require an identity attribute to have a value
+    pub fn with_attribute(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.attributes
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// Only apply when a condition holds
+    pub fn when(mut self, condition: ConditionExpr) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Get the condition, if any
+    pub fn condition(&self) -> Option<&ConditionExpr> {
+        self.condition.as_ref()
+    }
+
+    /// Apply to resources of other tenants, when the engine isolates tenants
+    pub fn allow_cross_tenant(mut self) -> Self {
+        self.cross_tenant = true;
+        self
+    }
+
     /// Set priority
     pub fn priority(mut self, priority: i32) -> Self {
         self.priority = priority;
@@ -138,36 +183,122 @@ impl Policy {
     }
 
     /// Check if this policy applies to the given request
-    fn applies(&self, identity: &Identity, resource: &str, action: Action) -> bool {
+    fn applies(
+        &self,
+        identity: &Identity,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> bool {
+        self.mismatch(identity, resource, action, context).is_none()
+    }
+
+    /// Explain why this policy does not apply to a request, or `None` if it
+    /// does
+    fn mismatch(
+        &self,
+        identity: &Identity,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> Option<String> {
         // Check roles
         if let Some(required_roles) = &self.roles {
             if !required_roles.iter().any(|r| identity.has_role(r)) {
-                return false;
+                return Some(format!(
+                    "requires one of roles {}",
+                    required_roles.join(", ")
+                ));
             }
         }
 
         // Check resources
         if let Some(resources) = &self.resources {
-            if !resources.iter().any(|r| r == "*" || r == resource) {
-                return false;
+            if !resources.iter().any(|r| resource_matches(r, resource)) {
+                return Some(format!(
+                    "resource {resource} does not match {}",
+                    resources.join(", ")
+                ));
             }
         }
 
         // Check actions
         if let Some(actions) = &self.actions {
             if !actions.iter().any(|a| *a == Action::All || *a == action) {
-                return false;
+                return Some(format!("action {action} is not covered"));
+            }
+        }
+
+        // Check attributes
+        if let Some(attributes) = &self.attributes {
+            let missing = attributes.iter().find(|(key, expected)| {
+                !identity
+                    .attributes
+                    .get(*key)
+                    .is_some_and(|actual| condition::values_equal(actual, expected))
+            });
+            if let Some((key, expected)) = missing {
+                return Some(format!("requires attribute {key} = {expected}"));
             }
         }
 
-        true
+        // Check the condition
+        if let Some(condition) = &self.condition {
+            if !condition.evaluate(identity, context) {
+                return Some(format!("condition not met: {condition}"));
+            }
+        }
+
+        None
     }
 }
 
+/// How a single policy fared against a request
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyEvaluation {
+    /// Policy ID
+    pub policy_id: String,
+    /// Policy effect
+    pub effect: Effect,
+    /// Policy priority
+    pub priority: i32,
+    /// Whether the policy applies to the request
+    pub matched: bool,
+    /// Why the policy does not apply, if it does not
+    pub reason: Option<String>,
+}
+
+/// A decision along with the evaluation of every policy
+#[derive(Debug, Clone)]
+pub struct PolicyExplanation {
+    /// The final decision
+    pub decision: PolicyDecision,
+    /// Every policy, in evaluation order
+    ///
+    /// Policies after the deciding one are included so shadowed policies can
+    /// be spotted.
+    pub evaluations: Vec<PolicyEvaluation>,
+}
+
 /// Policy engine
 pub struct PolicyEngine {
     policies: Vec<Policy>,
     default_effect: Effect,
+    roles: RoleGraph,
+    tenant_isolation: bool,
+    #[cfg(feature = "audit")]
+    audit: Option<tokio::sync::mpsc::Sender<AuditCommand>>,
+}
+
+/// Decisions buffered for the audit logger before new ones are dropped
+#[cfg(feature = "audit")]
+const AUDIT_BUFFER: usize = 1024;
+
+/// Message to the task forwarding decisions to the audit logger
+#[cfg(feature = "audit")]
+enum AuditCommand {
+    Event(Box<infra_audit::AuditEvent>),
+    Flush(tokio::sync::oneshot::Sender<infra_errors::InfraResult<()>>),
 }
 
 impl PolicyEngine {
@@ -176,17 +307,88 @@ impl PolicyEngine {
         Self {
             policies: Vec::new(),
             default_effect: Effect::Deny,
+            roles: RoleGraph::new(),
+            tenant_isolation: false,
+            #[cfg(feature = "audit")]
+            audit: None,
         }
     }
 
     /// Create with allow-by-default (not recommended for production)
     pub fn allow_by_default() -> Self {
         Self {
-            policies: Vec::new(),
             default_effect: Effect::Allow,
+            ..Self::new()
         }
     }
 
+    /// Match policy roles against identities' inherited roles too
+    pub fn with_role_graph(mut self, roles: RoleGraph) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    /// Deny access to resources of other tenants
+    ///
+    /// Requests for resources scoped to a tenant other than the identity's,
+    /// or by identities without a tenant, are only allowed by policies that
+    /// [allow cross-tenant access](Policy::allow_cross_tenant), and are
+    /// denied otherwise regardless of the default effect.
+    pub fn with_tenant_isolation(mut self) -> Self {
+        self.tenant_isolation = true;
+        self
+    }
+
+    /// Log every decision as an authorization audit event
+    ///
+    /// Events are handed to a background task through a bounded buffer, so
+    /// this must be called within a Tokio runtime. Decisions made while the
+    /// buffer is full are not logged. Call [`flush_audit`](Self::flush_audit)
+    /// before shutting down so buffered events are written.
+    #[cfg(feature = "audit")]
+    pub fn with_audit(mut self, logger: std::sync::Arc<infra_audit::AuditLogger>) -> Self {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(AUDIT_BUFFER);
+        tokio::spawn(async move {
+            while let Some(command) = receiver.recv().await {
+                match command {
+                    AuditCommand::Event(event) => {
+                        if let Err(e) = logger.log(*event).await {
+                            tracing::warn!(error = %e, "Failed to log authorization decision");
+                        }
+                    }
+                    AuditCommand::Flush(done) => {
+                        let _ = done.send(logger.flush().await);
+                    }
+                }
+            }
+        });
+        self.audit = Some(sender);
+        self
+    }
+
+    /// Wait until every decision made so far has been written to the audit
+    /// logger's sinks
+    #[cfg(feature = "audit")]
+    pub async fn flush_audit(&self) -> infra_errors::InfraResult<()> {
+        let Some(sender) = &self.audit else {
+            return Ok(());
+        };
+        let stopped = || infra_errors::InfraError::External {
+            service: "audit".to_string(),
+            operation: "flush".to_string(),
+            message: "Authorization audit task has stopped".to_string(),
+            retry_after: None,
+            context: None,
+            source: None,
+        };
+        let (done, result) = tokio::sync::oneshot::channel();
+        sender
+            .send(AuditCommand::Flush(done))
+            .await
+            .map_err(|_| stopped())?;
+        result.await.map_err(|_| stopped())?
+    }
+
     /// Add a policy
     pub fn add_policy(&mut self, policy: Policy) {
         self.policies.push(policy);
@@ -201,23 +403,267 @@ impl PolicyEngine {
         resource: &str,
         action: Action,
     ) -> PolicyDecision {
+        self.evaluate_with_context(identity, resource, action, &RequestContext::default())
+    }
+
+    /// Evaluate a request, with request attributes for policy conditions
+    pub fn evaluate_with_context(
+        &self,
+        identity: &Identity,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> PolicyDecision {
+        self.evaluate_as(identity, None, resource, action, context)
+    }
+
+    /// Evaluate a request made with an authentication context
+    ///
+    /// With tenant isolation, resources are isolated to the tenant the
+    /// context [acts for](AuthContext::for_tenant), and acting for a tenant
+    /// other than the identity's own is cross-tenant access. Unauthenticated
+    /// contexts are evaluated as [`Identity::anonymous`].
+    pub fn evaluate_auth(
+        &self,
+        auth: &AuthContext,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> PolicyDecision {
+        let anonymous;
+        let identity = match auth.identity() {
+            Some(identity) => identity,
+            None => {
+                anonymous = Identity::anonymous();
+                &anonymous
+            }
+        };
+        self.evaluate_as(identity, auth.tenant(), resource, action, context)
+    }
+
+    /// Explain a request made with an authentication context
+    ///
+    /// The decision is the one [`evaluate_auth`](Self::evaluate_auth) makes
+    /// for the same request, including the tenant the context acts for.
+    pub fn explain_auth(
+        &self,
+        auth: &AuthContext,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> PolicyExplanation {
+        let anonymous;
+        let identity = match auth.identity() {
+            Some(identity) => identity,
+            None => {
+                anonymous = Identity::anonymous();
+                &anonymous
+            }
+        };
+        self.explain_as(identity, auth.tenant(), resource, action, context)
+    }
+
+    fn evaluate_as(
+        &self,
+        identity: &Identity,
+        acting: Option<&Tenant>,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> PolicyDecision {
+        let expanded = self.expand(identity);
+        let identity = expanded.as_ref().unwrap_or(identity);
+
+        let foreign = self.foreign_tenant(identity, acting, resource);
+        let decision = self
+            .policies
+            .iter()
+            .find(|policy| {
+                (foreign.is_none() || policy.cross_tenant)
+                    && policy.applies(identity, resource, action, context)
+            })
+            .map_or_else(|| self.default_decision(foreign), Self::decision);
+        self.record(identity, resource, action, &decision);
+        decision
+    }
+
+    /// Evaluate a request, explaining how every policy fared
+    ///
+    /// Use [`explain_auth`](Self::explain_auth) for requests made with an
+    /// authentication context acting for a tenant.
+    pub fn evaluate_explained(
+        &self,
+        identity: &Identity,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> PolicyExplanation {
+        self.explain_as(identity, None, resource, action, context)
+    }
+
+    fn explain_as(
+        &self,
+        identity: &Identity,
+        acting: Option<&Tenant>,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+    ) -> PolicyExplanation {
+        let expanded = self.expand(identity);
+        let identity = expanded.as_ref().unwrap_or(identity);
+        let foreign = self.foreign_tenant(identity, acting, resource);
+
+        let mut decision = None;
+        let mut evaluations = Vec::with_capacity(self.policies.len());
         for policy in &self.policies {
-            if policy.applies(identity, resource, action) {
-                return PolicyDecision {
-                    effect: policy.effect,
-                    policy_id: Some(policy.id.clone()),
-                    reason: policy.name.clone(),
-                };
+            let reason = Self::mismatch(policy, identity, resource, action, context, foreign);
+            if reason.is_none() && decision.is_none() {
+                decision = Some(Self::decision(policy));
             }
+            evaluations.push(PolicyEvaluation {
+                policy_id: policy.id.clone(),
+                effect: policy.effect,
+                priority: policy.priority,
+                matched: reason.is_none(),
+                reason,
+            });
+        }
+
+        let decision = decision.unwrap_or_else(|| self.default_decision(foreign));
+        self.record(identity, resource, action, &decision);
+        PolicyExplanation {
+            decision,
+            evaluations,
+        }
+    }
+
+    /// Add inherited roles to an identity, if there are any to add
+    fn expand(&self, identity: &Identity) -> Option<Identity> {
+        (!self.roles.is_empty()).then(|| self.roles.expand_identity(identity.clone()))
+    }
+
+    /// Get the tenant isolation keeps a request out of, if any
+    ///
+    /// This is the resource's tenant if it is not the one acted for, or the
+    /// tenant acted for if it is not the identity's own. Malformed
+    /// tenant-scoped resource names are always foreign.
+    fn foreign_tenant<'a>(
+        &self,
+        identity: &'a Identity,
+        acting: Option<&'a Tenant>,
+        resource: &'a str,
+    ) -> Option<&'a str> {
+        if !self.tenant_isolation {
+            return None;
+        }
+        let own = identity.tenant().map(Tenant::id);
+        let acting = acting.map(Tenant::id).or(own);
+        match tenant_of(resource) {
+            Err(_) => Some(resource),
+            Ok(Some(tenant)) if acting != Some(tenant) => Some(tenant),
+            _ if acting != own => acting,
+            _ => None,
+        }
+    }
+
+    /// Explain why a policy does not apply, accounting for tenant isolation
+    fn mismatch(
+        policy: &Policy,
+        identity: &Identity,
+        resource: &str,
+        action: Action,
+        context: &RequestContext,
+        foreign: Option<&str>,
+    ) -> Option<String> {
+        if foreign.is_some() && !policy.cross_tenant {
+            return Some("does not allow cross-tenant access".to_string());
         }
+        policy.mismatch(identity, resource, action, context)
+    }
 
-        // Return default decision
+    fn decision(policy: &Policy) -> PolicyDecision {
+        PolicyDecision {
+            effect: policy.effect,
+            policy_id: Some(policy.id.clone()),
+            reason: policy.name.clone(),
+        }
+    }
+
+    fn default_decision(&self, foreign: Option<&str>) -> PolicyDecision {
+        if let Some(tenant) = foreign {
+            return PolicyDecision::deny().with_reason(format!("Cross-tenant access to {tenant}"));
+        }
         PolicyDecision {
             effect: self.default_effect,
             policy_id: None,
             reason: Some("No matching policy".to_string()),
         }
     }
+
+    /// Log a decision to the audit logger, if one is set
+    #[cfg_attr(not(feature = "audit"), allow(unused_variables))]
+    fn record(
+        &self,
+        identity: &Identity,
+        resource: &str,
+        action: Action,
+        decision: &PolicyDecision,
+    ) {
+        #[cfg(feature = "audit")]
+        if let Some(sender) = &self.audit {
+            let event = audit_event(identity, resource, action, decision);
+            if let Err(e) = sender.try_send(AuditCommand::Event(Box::new(event))) {
+                tracing::warn!(error = %e, "Dropping authorization decision audit event");
+            }
+        }
+    }
+}
+
+/// Build the audit event recording a decision
+#[cfg(feature = "audit")]
+fn audit_event(
+    identity: &Identity,
+    resource: &str,
+    action: Action,
+    decision: &PolicyDecision,
+) -> infra_audit::AuditEvent {
+    use crate::identity::IdentityType;
+    use infra_audit::{Actor, AuditContext, AuditEventBuilder, EventType, Outcome};
+
+    let mut actor = match identity.identity_type {
+        IdentityType::Service => Actor::service(&identity.id),
+        IdentityType::User | IdentityType::Anonymous => Actor::user(&identity.id),
+    };
+    if let Some(name) = &identity.name {
+        actor = actor.with_name(name);
+    }
+
+    let mut event = AuditEventBuilder::new(EventType::Authorization)
+        .action(action.as_str())
+        .outcome(if decision.is_allowed() {
+            Outcome::Success
+        } else {
+            Outcome::Denied
+        })
+        .actor(actor)
+        .resource(resource)
+        .context(AuditContext::current().unwrap_or_default());
+    if let Some(policy_id) = &decision.policy_id {
+        event = event.metadata("policy_id", policy_id.as_str());
+    }
+    let reason = decision.reason.clone().or_else(|| {
+        decision
+            .policy_id
+            .as_ref()
+            .map(|id| format!("Matched policy {id}"))
+    });
+    if let Some(reason) = reason {
+        if !decision.is_allowed() {
+            event = event.error(&reason);
+        }
+        event = event.metadata("reason", reason);
+    }
+    event.build()
 }
 
 impl Default for PolicyEngine {
@@ -229,6 +675,7 @@ impl Default for PolicyEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tenant::Tenant;
 
     #[test]
     fn test_policy_engine() {
@@ -261,4 +708,318 @@ mod tests {
         // User cannot delete
         assert!(!engine.evaluate(&user, "posts", Action::Delete).is_allowed());
     }
+
+    #[test]
+    fn test_attribute_conditions() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::deny("block-external-admin")
+                .for_actions(vec![Action::Admin])
+                .when(ConditionExpr::parse("!(context.ip in_range '10.0.0.0/8')").unwrap())
+                .priority(100),
+        );
+        engine.add_policy(
+            Policy::allow("research-models")
+                .on_resources(vec!["models".to_string()])
+                .with_attribute("department", "research")
+                .when(ConditionExpr::parse("identity.tenant == context.tenant").unwrap()),
+        );
+        engine.add_policy(
+            Policy::allow("ops-admin")
+                .for_roles(vec!["ops".to_string()])
+                .for_actions(vec![Action::Admin]),
+        );
+
+        let researcher = Identity::user("user1")
+            .with_attribute("department", "research")
+            .with_attribute("tenant", "acme");
+        let acme = RequestContext::new().with_value("tenant", "acme");
+        let globex = RequestContext::new().with_value("tenant", "globex");
+
+        assert!(engine
+            .evaluate_with_context(&researcher, "models", Action::Read, &acme)
+            .is_allowed());
+        assert!(!engine
+            .evaluate_with_context(&researcher, "models", Action::Read, &globex)
+            .is_allowed());
+        assert!(!engine
+            .evaluate(&Identity::user("user2"), "models", Action::Read)
+            .is_allowed());
+
+        let ops = Identity::service("deployer").with_role("ops");
+        let internal = RequestContext::new().with_value("ip", "10.4.0.12");
+        let external = RequestContext::new().with_value("ip", "198.51.100.7");
+        assert!(engine
+            .evaluate_with_context(&ops, "cluster", Action::Admin, &internal)
+            .is_allowed());
+        let decision = engine.evaluate_with_context(&ops, "cluster", Action::Admin, &external);
+        assert_eq!(decision.policy_id.as_deref(), Some("block-external-admin"));
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let policy: Policy = serde_json::from_value(serde_json::json!({
+            "id": "eu-only",
+            "name": "EU data stays in the EU",
+            "effect": "allow",
+            "resources": ["datasets"],
+            "condition": "context.region in ['eu-west-1', 'eu-central-1'] && identity.clearance >= 2",
+            "priority": 10
+        }))
+        .unwrap();
+
+        let identity = Identity::user("user1").with_attribute("clearance", 2);
+        let context = RequestContext::new().with_value("region", "eu-west-1");
+        assert!(policy.applies(&identity, "datasets", Action::Read, &context));
+        assert!(!policy.applies(&identity, "datasets", Action::Read, &RequestContext::new()));
+
+        let json = serde_json::to_value(&policy).unwrap();
+        let reparsed: Policy = serde_json::from_value(json).unwrap();
+        assert_eq!(reparsed.condition, policy.condition);
+
+        let invalid = serde_json::from_value::<Policy>(serde_json::json!({
+            "id": "broken",
+            "effect": "deny",
+            "condition": "region == 'eu'",
+            "priority": 0
+        }));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_resource_hierarchy() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::allow("team-models")
+                .for_roles(vec!["ml".to_string()])
+                .on_resources(vec![
+                    "models:gpt-4*".to_string(),
+                    "projects/ml/**".to_string(),
+                ]),
+        );
+
+        let member = Identity::user("user1").with_role("ml");
+        assert!(engine
+            .evaluate(&member, "models:gpt-4o", Action::Execute)
+            .is_allowed());
+        assert!(engine
+            .evaluate(&member, "projects/ml/datasets/train", Action::Read)
+            .is_allowed());
+        assert!(!engine
+            .evaluate(&member, "models:o1", Action::Execute)
+            .is_allowed());
+        assert!(!engine
+            .evaluate(&member, "projects/finance/datasets", Action::Read)
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_inherited_roles() {
+        let roles = RoleGraph::new().inherit("admin", ["editor"]);
+        let mut engine = PolicyEngine::new().with_role_graph(roles);
+        engine.add_policy(
+            Policy::allow("editor-write")
+                .for_roles(vec!["editor".to_string()])
+                .for_actions(vec![Action::Write]),
+        );
+
+        let admin = Identity::user("admin1").with_role("admin");
+        assert!(engine.evaluate(&admin, "posts", Action::Write).is_allowed());
+        assert!(!engine
+            .evaluate(&admin, "posts", Action::Delete)
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_evaluate_explained() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::allow("admin-all")
+                .for_roles(vec!["admin".to_string()])
+                .priority(100),
+        );
+        engine.add_policy(
+            Policy::allow("user-read")
+                .for_roles(vec!["user".to_string()])
+                .for_actions(vec![Action::Read])
+                .priority(50),
+        );
+        engine.add_policy(Policy::allow("models").on_resources(vec!["models:*".to_string()]));
+
+        let user = Identity::user("user1").with_role("user");
+        let explanation =
+            engine.evaluate_explained(&user, "models:gpt-4o", Action::Read, &RequestContext::new());
+        assert_eq!(explanation.decision.policy_id.as_deref(), Some("user-read"));
+
+        let evaluations = &explanation.evaluations;
+        assert_eq!(evaluations.len(), 3);
+        assert_eq!(evaluations[0].policy_id, "admin-all");
+        assert!(!evaluations[0].matched);
+        assert_eq!(
+            evaluations[0].reason.as_deref(),
+            Some("requires one of roles admin")
+        );
+        assert!(evaluations[1].matched && evaluations[1].reason.is_none());
+        // Shadowed by the deciding policy, but still reported
+        assert!(evaluations[2].matched);
+
+        let denied =
+            engine.evaluate_explained(&user, "users", Action::Delete, &RequestContext::new());
+        assert!(!denied.decision.is_allowed());
+        assert_eq!(
+            denied.evaluations[1].reason.as_deref(),
+            Some("action delete is not covered")
+        );
+    }
+
+    #[cfg(feature = "audit")]
+    #[tokio::test]
+    async fn test_decisions_are_audited() {
+        use infra_audit::{AuditLogger, EventType, MemorySink, Outcome};
+        use std::sync::Arc;
+
+        let sink = Arc::new(MemorySink::new());
+        let mut engine = PolicyEngine::new().with_audit(Arc::new(AuditLogger::new(sink.clone())));
+        engine.add_policy(
+            Policy::deny("no-deletes")
+                .for_actions(vec![Action::Delete])
+                .priority(10),
+        );
+        engine.add_policy(Policy::allow("read-all").for_actions(vec![Action::Read]));
+
+        let user = Identity::user("user1");
+        assert!(engine.evaluate(&user, "posts", Action::Read).is_allowed());
+        assert!(!engine.evaluate(&user, "posts", Action::Delete).is_allowed());
+
+        engine.flush_audit().await.unwrap();
+        let events = sink.events().await;
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| e.event_type() == EventType::Authorization));
+        let denied = events
+            .iter()
+            .find(|e| e.outcome() == Outcome::Denied)
+            .unwrap();
+        assert_eq!(denied.action(), "delete");
+        assert_eq!(denied.resource(), Some("posts"));
+        assert_eq!(denied.metadata()["policy_id"], "no-deletes");
+        assert_eq!(denied.error(), Some("Matched policy no-deletes"));
+    }
+
+    #[test]
+    fn test_tenant_isolation() {
+        let acme = Tenant::new("acme").unwrap();
+        let mut engine = PolicyEngine::allow_by_default().with_tenant_isolation();
+        engine.add_policy(
+            Policy::allow("support-read")
+                .for_roles(vec!["support".to_string()])
+                .for_actions(vec![Action::Read])
+                .allow_cross_tenant(),
+        );
+
+        let alice = Identity::user("alice").with_tenant(acme.clone());
+        let mallory = Identity::user("mallory").with_tenant(Tenant::new("globex").unwrap());
+        let support = Identity::user("sam").with_role("support");
+        let models = acme.scope("models");
+
+        assert!(engine.evaluate(&alice, &models, Action::Write).is_allowed());
+        assert!(engine
+            .evaluate(&mallory, "models", Action::Read)
+            .is_allowed());
+
+        let denied = engine.evaluate(&mallory, &models, Action::Read);
+        assert!(!denied.is_allowed());
+        assert_eq!(
+            denied.reason.as_deref(),
+            Some("Cross-tenant access to acme")
+        );
+
+        assert!(engine
+            .evaluate(&support, &models, Action::Read)
+            .is_allowed());
+        assert!(!engine
+            .evaluate(&support, &models, Action::Delete)
+            .is_allowed());
+        assert!(!engine
+            .evaluate(&mallory, "tenants//acme/models", Action::Read)
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_tenant_isolation_acting_for_tenant() {
+        let acme = Tenant::new("acme").unwrap();
+        let globex = Tenant::new("globex").unwrap();
+        let mut engine = PolicyEngine::allow_by_default().with_tenant_isolation();
+        engine.add_policy(
+            Policy::allow("support-read")
+                .for_roles(vec!["support".to_string()])
+                .for_actions(vec![Action::Read])
+                .allow_cross_tenant(),
+        );
+        let (acme_models, globex_models) = (acme.scope("models"), globex.scope("models"));
+        let check = |auth: &AuthContext, resource: &str, action| {
+            engine.evaluate_auth(auth, resource, action, &RequestContext::new())
+        };
+
+        let alice = AuthContext::with_identity(Identity::user("alice").with_tenant(acme));
+        assert!(check(&alice, &acme_models, Action::Write).is_allowed());
+
+        // Acting for another tenant is cross-tenant, even on its resources
+        let alice = alice.for_tenant(globex.clone());
+        let decision = check(&alice, &globex_models, Action::Read);
+        assert_eq!(
+            decision.reason.as_deref(),
+            Some("Cross-tenant access to globex")
+        );
+        assert!(!check(&alice, &acme_models, Action::Read).is_allowed());
+
+        let support = AuthContext::with_identity(Identity::user("sam").with_role("support"))
+            .for_tenant(globex);
+        assert!(check(&support, &globex_models, Action::Read).is_allowed());
+        assert!(!check(&support, &globex_models, Action::Write).is_allowed());
+    }
+
+    #[test]
+    fn test_explain_auth_matches_evaluate_auth() {
+        let acme = Tenant::new("acme").unwrap();
+        let globex = Tenant::new("globex").unwrap();
+        let mut engine = PolicyEngine::allow_by_default().with_tenant_isolation();
+        engine.add_policy(
+            Policy::allow("support-read")
+                .for_roles(vec!["support".to_string()])
+                .for_actions(vec![Action::Read])
+                .allow_cross_tenant(),
+        );
+        let globex_models = globex.scope("models");
+        let context = RequestContext::new();
+
+        let alice = AuthContext::with_identity(Identity::user("alice").with_tenant(acme))
+            .for_tenant(globex);
+        let support = AuthContext::with_identity(Identity::user("sam").with_role("support"));
+        let requests = [
+            (&alice, globex_models.as_str()),
+            (&alice, "models"),
+            (&support, globex_models.as_str()),
+        ];
+        for (auth, resource) in requests {
+            let decision = engine.evaluate_auth(auth, resource, Action::Read, &context);
+            let explanation = engine.explain_auth(auth, resource, Action::Read, &context);
+            assert_eq!(explanation.decision.effect, decision.effect);
+            assert_eq!(explanation.decision.reason, decision.reason);
+        }
+
+        // Acting for another tenant is cross-tenant even on unscoped resources,
+        // which the identity alone would be allowed
+        let explanation = engine.explain_auth(&alice, "models", Action::Read, &context);
+        assert_eq!(
+            explanation.decision.reason.as_deref(),
+            Some("Cross-tenant access to globex")
+        );
+        let identity = alice.identity().unwrap();
+        assert!(engine
+            .evaluate_explained(identity, "models", Action::Read, &context)
+            .decision
+            .is_allowed());
+    }
 }
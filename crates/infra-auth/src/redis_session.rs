@@ -0,0 +1,217 @@
+//! Redis-backed session store.
+
+use crate::session::{Session, SessionStore};
+use async_trait::async_trait;
+use chrono::Utc;
+use infra_errors::{InfraError, InfraResult};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+
+/// Session store keeping sessions in Redis
+///
+/// Each session is stored as JSON under `{prefix}session:{id}` with a TTL
+/// matching its expiry, so Redis removes expired sessions itself. The IDs of
+/// an identity's sessions are kept in the sorted set `{prefix}identity:{id}`,
+/// scored by last activity. The set expires with the identity's longest-lived
+/// session and is pruned of expired sessions whenever it is listed.
+///
+/// Writes run as a Lua script, so updates never recreate deleted sessions
+/// and session limits are applied atomically. The script touches the keys of
+/// other sessions of the identity, so all of a store's keys must live on one
+/// Redis node.
+pub struct RedisSessionStore {
+    conn: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisSessionStore {
+    /// Connect to Redis at `url`, such as `redis://127.0.0.1/`
+    pub async fn new(url: &str) -> InfraResult<Self> {
+        let client = redis::Client::open(url).map_err(|e| redis_error("connect", e))?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .map_err(|e| redis_error("connect", e))?;
+        Ok(Self::with_connection(conn))
+    }
+
+    /// Create a store using an existing connection
+    pub fn with_connection(conn: ConnectionManager) -> Self {
+        Self {
+            conn,
+            prefix: "auth:".to_string(),
+        }
+    }
+
+    /// Set the prefix of the store's keys (default `auth:`)
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn session_key(&self, id: &str) -> String {
+        format!("{}session:{id}", self.prefix)
+    }
+
+    fn identity_key(&self, identity_id: &str) -> String {
+        format!("{}identity:{identity_id}", self.prefix)
+    }
+
+    /// Write a session
+    ///
+    /// With `update`, the session is only written if it still exists. A
+    /// non-zero `max` first ends the identity's least recently active
+    /// sessions so that at most `max` remain.
+    async fn write(&self, session: &Session, update: bool, max: usize) -> InfraResult<()> {
+        let json = serde_json::to_string(session)?;
+        let ttl = (session.expires_at - Utc::now()).num_seconds();
+        if ttl <= 0 {
+            return self.delete(&session.id).await;
+        }
+
+        let mut conn = self.conn.clone();
+        redis::Script::new(WRITE_SCRIPT)
+            .key(self.session_key(&session.id))
+            .key(self.identity_key(&session.identity.id))
+            .arg(&session.id)
+            .arg(json)
+            .arg(ttl)
+            .arg(session.last_activity.timestamp_millis())
+            .arg(max)
+            .arg(update)
+            .arg(self.session_key(""))
+            .invoke_async::<()>(&mut conn)
+            .await
+            .map_err(|e| redis_error("write", e))
+    }
+}
+
+#[async_trait]
+impl SessionStore for RedisSessionStore {
+    async fn create(&self, session: Session) -> InfraResult<()> {
+        self.write(&session, false, 0).await
+    }
+
+    async fn get(&self, id: &str) -> InfraResult<Option<Session>> {
+        let mut conn = self.conn.clone();
+        let json: Option<String> = conn
+            .get(self.session_key(id))
+            .await
+            .map_err(|e| redis_error("get", e))?;
+        json.map(|json| serde_json::from_str(&json).map_err(InfraError::from))
+            .transpose()
+    }
+
+    async fn update(&self, session: Session) -> InfraResult<()> {
+        self.write(&session, true, 0).await
+    }
+
+    async fn delete(&self, id: &str) -> InfraResult<()> {
+        let Some(session) = self.get(id).await? else {
+            return Ok(());
+        };
+
+        let mut conn = self.conn.clone();
+        redis::pipe()
+            .atomic()
+            .del(self.session_key(id))
+            .ignore()
+            .zrem(self.identity_key(&session.identity.id), id)
+            .ignore()
+            .query_async::<()>(&mut conn)
+            .await
+            .map_err(|e| redis_error("delete", e))
+    }
+
+    async fn list(&self, identity_id: &str) -> InfraResult<Vec<Session>> {
+        let mut conn = self.conn.clone();
+        let index = self.identity_key(identity_id);
+        let ids: Vec<String> = conn
+            .zrange(&index, 0, -1)
+            .await
+            .map_err(|e| redis_error("list", e))?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = ids.iter().map(|id| self.session_key(id)).collect();
+        let values: Vec<Option<String>> = redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| redis_error("list", e))?;
+
+        let mut sessions = Vec::new();
+        let mut gone = Vec::new();
+        for (id, json) in ids.into_iter().zip(values) {
+            match json {
+                Some(json) => sessions.push(serde_json::from_str(&json)?),
+                None => gone.push(id),
+            }
+        }
+        if !gone.is_empty() {
+            conn.zrem::<_, _, ()>(&index, gone)
+                .await
+                .map_err(|e| redis_error("list", e))?;
+        }
+        Ok(sessions)
+    }
+
+    async fn create_limited(&self, session: Session, max: usize) -> InfraResult<()> {
+        self.write(&session, false, max).await
+    }
+
+    /// Expired sessions are removed by Redis, so there is nothing to clean up
+    async fn cleanup(&self) -> InfraResult<usize> {
+        Ok(0)
+    }
+}
+
+/// Writes a session and maintains its identity's index
+///
+/// KEYS: session key, identity index. ARGV: session ID, JSON, TTL in seconds,
+/// last activity in milliseconds, session limit (0 for none), whether this is
+/// an update, and the prefix of session keys.
+const WRITE_SCRIPT: &str = r#"
+local key, index = KEYS[1], KEYS[2]
+local id, json, ttl, score = ARGV[1], ARGV[2], tonumber(ARGV[3]), ARGV[4]
+local max, update, prefix = tonumber(ARGV[5]), ARGV[6] == "1", ARGV[7]
+
+if update and redis.call("EXISTS", key) == 0 then
+  return 0
+end
+
+if max > 0 then
+  local live = {}
+  for _, other in ipairs(redis.call("ZRANGE", index, 0, -1)) do
+    if other ~= id then
+      if redis.call("EXISTS", prefix .. other) == 1 then
+        table.insert(live, other)
+      else
+        redis.call("ZREM", index, other)
+      end
+    end
+  end
+  for i = 1, #live - max + 1 do
+    redis.call("DEL", prefix .. live[i])
+    redis.call("ZREM", index, live[i])
+  end
+end
+
+redis.call("SET", key, json, "EX", ttl)
+redis.call("ZADD", index, score, id)
+if redis.call("TTL", index) < ttl then
+  redis.call("EXPIRE", index, ttl)
+end
+return 1
+"#;
+
+fn redis_error(operation: &str, err: redis::RedisError) -> InfraError {
+    InfraError::External {
+        service: "redis".to_string(),
+        operation: operation.to_string(),
+        message: err.to_string(),
+        retry_after: None,
+        context: None,
+        source: None,
+    }
+}
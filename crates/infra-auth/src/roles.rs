@@ -0,0 +1,366 @@
+//! Role hierarchy and group membership.
+
+use crate::identity::Identity;
+use async_trait::async_trait;
+use infra_errors::InfraResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Roles inheriting from other roles
+///
+/// An identity holding a role also holds every role it inherits from,
+/// directly or transitively. Cycles are tolerated. Deserializes from a map of
+/// each role to the roles it inherits, such as
+/// `{"admin": ["editor"], "editor": ["viewer"]}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RoleGraph {
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl RoleGraph {
+    /// Create an empty graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `role` inherit from `parents`
+    pub fn inherit<I, S>(mut self, role: impl Into<String>, parents: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let entry = self.parents.entry(role.into()).or_default();
+        for parent in parents {
+            let parent = parent.into();
+            if !entry.contains(&parent) {
+                entry.push(parent);
+            }
+        }
+        self
+    }
+
+    /// Check if the graph defines no inheritance
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Expand roles with every role they inherit
+    ///
+    /// The given roles come first, followed by inherited roles nearest first.
+    pub fn expand(&self, roles: &[String]) -> Vec<String> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut expanded = Vec::new();
+        let mut queue: VecDeque<&str> = roles.iter().map(String::as_str).collect();
+
+        while let Some(role) = queue.pop_front() {
+            if !seen.insert(role) {
+                continue;
+            }
+            expanded.push(role.to_string());
+            if let Some(parents) = self.parents.get(role) {
+                queue.extend(parents.iter().map(String::as_str));
+            }
+        }
+        expanded
+    }
+
+    /// Check if an identity holds a role, directly or by inheritance
+    pub fn has_role(&self, identity: &Identity, role: &str) -> bool {
+        identity.has_role(role) || self.expand(&identity.roles).iter().any(|r| r == role)
+    }
+
+    /// Add inherited roles to an identity, so [`Identity::has_role`]
+    /// consults the expanded set
+    pub fn expand_identity(&self, mut identity: Identity) -> Identity {
+        identity.roles = self.expand(&identity.roles);
+        identity
+    }
+}
+
+/// Source of the groups an identity belongs to, such as a directory service
+#[async_trait]
+pub trait GroupResolver: Send + Sync {
+    /// Get the groups an identity belongs to
+    async fn groups(&self, identity: &Identity) -> InfraResult<Vec<String>>;
+}
+
+/// Cache key of an identity: its tenant ID and its ID
+type CacheKey = (Option<String>, String);
+
+fn cache_key(identity: &Identity) -> CacheKey {
+    (
+        identity.tenant().map(|t| t.id().to_string()),
+        identity.id.clone(),
+    )
+}
+
+/// Groups fetched for an identity
+struct CachedGroups {
+    groups: Vec<String>,
+    fetched_at: Instant,
+}
+
+/// Group memberships, with their keys in the order they were fetched
+#[derive(Default)]
+struct GroupCache {
+    entries: HashMap<CacheKey, CachedGroups>,
+    order: VecDeque<(CacheKey, Instant)>,
+}
+
+impl GroupCache {
+    /// Cache groups, then drop expired entries and the oldest ones beyond
+    /// `capacity`
+    fn insert(&mut self, key: CacheKey, groups: Vec<String>, ttl: Duration, capacity: usize) {
+        let fetched_at = Instant::now();
+        self.order.push_back((key.clone(), fetched_at));
+        self.entries
+            .insert(key, CachedGroups { groups, fetched_at });
+
+        while let Some((key, fetched_at)) = self.order.front() {
+            // Keys refetched or invalidated since are stale in the order
+            let current = self
+                .entries
+                .get(key)
+                .is_some_and(|cached| cached.fetched_at == *fetched_at);
+            if current && self.entries.len() <= capacity && fetched_at.elapsed() < ttl {
+                break;
+            }
+            if let Some((key, _)) = self.order.pop_front() {
+                if current {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+    }
+}
+
+/// Resolves the full set of roles of an identity
+///
+/// Adds the roles mapped from the identity's groups, then everything those
+/// and the identity's own roles inherit in the [`RoleGraph`]. Groups without
+/// a mapping grant no roles. Group memberships of up to the cache capacity
+/// of identities are cached per identity and tenant for the cache TTL.
+/// Expired memberships are never used, so resolution fails while they
+/// cannot be fetched.
+pub struct RoleResolver {
+    graph: RoleGraph,
+    groups: Option<Arc<dyn GroupResolver>>,
+    group_roles: HashMap<String, Vec<String>>,
+    cache: RwLock<GroupCache>,
+    cache_ttl: Duration,
+    cache_capacity: usize,
+}
+
+impl RoleResolver {
+    /// Create a resolver expanding roles through `graph`
+    pub fn new(graph: RoleGraph) -> Self {
+        Self {
+            graph,
+            groups: None,
+            group_roles: HashMap::new(),
+            cache: RwLock::new(GroupCache::default()),
+            cache_ttl: Duration::from_secs(5 * 60),
+            cache_capacity: 10_000,
+        }
+    }
+
+    /// Resolve group memberships with `resolver`
+    pub fn with_groups(mut self, resolver: Arc<dyn GroupResolver>) -> Self {
+        self.groups = Some(resolver);
+        self
+    }
+
+    /// Grant roles to members of a group
+    pub fn with_group_roles<I, S>(mut self, group: impl Into<String>, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.group_roles
+            .entry(group.into())
+            .or_default()
+            .extend(roles.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set how long group memberships are cached
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Set how many identities' group memberships are cached
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// Get the role graph
+    pub fn graph(&self) -> &RoleGraph {
+        &self.graph
+    }
+
+    /// Add group and inherited roles to an identity
+    pub async fn resolve(&self, mut identity: Identity) -> InfraResult<Identity> {
+        for group in self.groups(&identity).await? {
+            for role in self.group_roles.get(&group).into_iter().flatten() {
+                if !identity.has_role(role) {
+                    identity.roles.push(role.clone());
+                }
+            }
+        }
+        Ok(self.graph.expand_identity(identity))
+    }
+
+    /// Forget cached group memberships of an identity, in every tenant
+    pub async fn invalidate(&self, identity_id: &str) {
+        self.cache
+            .write()
+            .await
+            .entries
+            .retain(|(_, id), _| id != identity_id);
+    }
+
+    async fn groups(&self, identity: &Identity) -> InfraResult<Vec<String>> {
+        let Some(resolver) = &self.groups else {
+            return Ok(Vec::new());
+        };
+
+        let key = cache_key(identity);
+        if let Some(cached) = self.cache.read().await.entries.get(&key) {
+            if cached.fetched_at.elapsed() < self.cache_ttl {
+                return Ok(cached.groups.clone());
+            }
+        }
+
+        let groups = resolver.groups(identity).await.map_err(|e| {
+            tracing::warn!(identity = %identity.id, error = %e, "Failed to fetch groups");
+            e
+        })?;
+        self.cache
+            .write()
+            .await
+            .insert(key, groups.clone(), self.cache_ttl, self.cache_capacity);
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn graph() -> RoleGraph {
+        RoleGraph::new()
+            .inherit("admin", ["editor", "billing"])
+            .inherit("editor", ["viewer"])
+            .inherit("viewer", ["admin-readonly"])
+            .inherit("admin-readonly", ["viewer"])
+    }
+
+    #[test]
+    fn test_role_inheritance() {
+        let graph = graph();
+        assert_eq!(
+            graph.expand(&["admin".to_string()]),
+            ["admin", "editor", "billing", "viewer", "admin-readonly"]
+        );
+
+        let editor = Identity::user("user1").with_role("editor");
+        assert!(graph.has_role(&editor, "viewer"));
+        assert!(!graph.has_role(&editor, "billing"));
+        assert!(graph.expand_identity(editor).has_role("admin-readonly"));
+
+        let parsed: RoleGraph =
+            serde_json::from_str(r#"{"admin": ["editor"], "editor": ["viewer"]}"#).unwrap();
+        assert_eq!(parsed.expand(&["admin".to_string()]).len(), 3);
+    }
+
+    struct Directory {
+        lookups: AtomicUsize,
+        down: std::sync::atomic::AtomicBool,
+    }
+
+    impl Directory {
+        fn new() -> Self {
+            Self {
+                lookups: AtomicUsize::new(0),
+                down: std::sync::atomic::AtomicBool::new(false),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl GroupResolver for Directory {
+        async fn groups(&self, identity: &Identity) -> InfraResult<Vec<String>> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(infra_errors::InfraError::validation("Directory is down"));
+            }
+            Ok(
+                match (identity.tenant().map(|t| t.id()), identity.id.as_str()) {
+                    (None, "ada") => vec!["ml-team".to_string(), "everyone".to_string()],
+                    _ => vec!["everyone".to_string()],
+                },
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_group_roles_are_cached() {
+        let directory = Arc::new(Directory::new());
+        let resolver = RoleResolver::new(graph())
+            .with_groups(directory.clone())
+            .with_group_roles("ml-team", ["editor"]);
+
+        let ada = resolver.resolve(Identity::user("ada")).await.unwrap();
+        assert_eq!(ada.roles, ["editor", "viewer", "admin-readonly"]);
+        let bob = resolver.resolve(Identity::user("bob")).await.unwrap();
+        assert!(bob.roles.is_empty());
+
+        resolver.resolve(Identity::user("ada")).await.unwrap();
+        assert_eq!(directory.lookups.load(Ordering::SeqCst), 2);
+
+        resolver.invalidate("ada").await;
+        resolver.resolve(Identity::user("ada")).await.unwrap();
+        assert_eq!(directory.lookups.load(Ordering::SeqCst), 3);
+
+        // The same ID in another tenant is another identity
+        let globex = crate::tenant::Tenant::new("globex").unwrap();
+        let other = resolver
+            .resolve(Identity::user("ada").with_tenant(globex))
+            .await
+            .unwrap();
+        assert!(other.roles.is_empty());
+        assert_eq!(directory.lookups.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_group_cache_is_bounded_and_fails_closed() {
+        let directory = Arc::new(Directory::new());
+        let resolver = RoleResolver::new(graph())
+            .with_groups(directory.clone())
+            .with_group_roles("ml-team", ["editor"])
+            .with_cache_capacity(2);
+
+        for id in ["ada", "bob", "cy", "ada"] {
+            resolver.resolve(Identity::user(id)).await.unwrap();
+        }
+        // ada was evicted as the oldest entry before being fetched again
+        assert_eq!(directory.lookups.load(Ordering::SeqCst), 4);
+        assert_eq!(resolver.cache.read().await.entries.len(), 2);
+
+        // Memberships are not reused once expired, even if the directory fails
+        let resolver = RoleResolver::new(graph())
+            .with_groups(directory.clone())
+            .with_group_roles("ml-team", ["editor"])
+            .with_cache_ttl(Duration::ZERO);
+        resolver.resolve(Identity::user("ada")).await.unwrap();
+        directory.down.store(true, Ordering::SeqCst);
+        assert!(resolver.resolve(Identity::user("ada")).await.is_err());
+    }
+}
@@ -3,7 +3,8 @@
 use crate::identity::Identity;
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
-use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use infra_crypto::random_token;
+use infra_errors::{InfraError, InfraResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -77,11 +78,40 @@ pub trait SessionStore: Send + Sync {
     async fn get(&self, id: &str) -> InfraResult<Option<Session>>;
 
     /// Update a session
+    ///
+    /// Does nothing if the session no longer exists, so a session deleted
+    /// while it was being used is not brought back.
     async fn update(&self, session: Session) -> InfraResult<()>;
 
     /// Delete a session
     async fn delete(&self, id: &str) -> InfraResult<()>;
 
+    /// List the sessions of an identity
+    ///
+    /// Stores that cannot list sessions return a configuration error, which
+    /// also rules out limiting concurrent sessions with them.
+    async fn list(&self, identity_id: &str) -> InfraResult<Vec<Session>> {
+        let _ = identity_id;
+        Err(InfraError::config(
+            "Session store does not support listing sessions",
+        ))
+    }
+
+    /// Create a session, first ending sessions of the same identity so that
+    /// at most `max` remain including the new one
+    ///
+    /// Expired sessions are removed, then the least recently active ones. The
+    /// default implementation lists and deletes sessions one at a time, so
+    /// concurrent creations may briefly exceed the limit; stores should
+    /// override it to apply the limit atomically.
+    async fn create_limited(&self, session: Session, max: usize) -> InfraResult<()> {
+        let active = self.list(&session.identity.id).await?;
+        for evicted in evictions(active, &session.id, max) {
+            self.delete(&evicted.id).await?;
+        }
+        self.create(session).await
+    }
+
     /// Clean up expired sessions
     async fn cleanup(&self) -> InfraResult<usize>;
 }
@@ -121,7 +151,9 @@ impl SessionStore for MemorySessionStore {
 
     async fn update(&self, session: Session) -> InfraResult<()> {
         let mut sessions = self.sessions.write().await;
-        sessions.insert(session.id.clone(), session);
+        if let Some(stored) = sessions.get_mut(&session.id) {
+            *stored = session;
+        }
         Ok(())
     }
 
@@ -131,9 +163,31 @@ impl SessionStore for MemorySessionStore {
         Ok(())
     }
 
+    async fn list(&self, identity_id: &str) -> InfraResult<Vec<Session>> {
+        let sessions = self.sessions.read().await;
+        Ok(sessions
+            .values()
+            .filter(|session| session.identity.id == identity_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_limited(&self, session: Session, max: usize) -> InfraResult<()> {
+        let mut sessions = self.sessions.write().await;
+        let active = sessions
+            .values()
+            .filter(|other| other.identity.id == session.identity.id)
+            .cloned()
+            .collect();
+        for evicted in evictions(active, &session.id, max) {
+            sessions.remove(&evicted.id);
+        }
+        sessions.insert(session.id.clone(), session);
+        Ok(())
+    }
+
     async fn cleanup(&self) -> InfraResult<usize> {
         let mut sessions = self.sessions.write().await;
-        let now = Utc::now();
         let before = sessions.len();
 
         sessions.retain(|_, session| !session.is_expired());
@@ -142,6 +196,129 @@ impl SessionStore for MemorySessionStore {
     }
 }
 
+/// Creates and resolves sessions on top of a [`SessionStore`]
+///
+/// Sessions last for the configured TTL. With sliding expiration, each
+/// successful [`get`](Self::get) extends the session by the TTL again. When
+/// an identity reaches the session limit, its least recently active sessions
+/// are ended to make room for new ones.
+pub struct SessionManager {
+    store: Arc<dyn SessionStore>,
+    ttl: Duration,
+    sliding: bool,
+    max_sessions: Option<usize>,
+}
+
+impl SessionManager {
+    /// Create a manager for sessions lasting `ttl`
+    pub fn new(store: Arc<dyn SessionStore>, ttl: Duration) -> Self {
+        Self {
+            store,
+            ttl,
+            sliding: false,
+            max_sessions: None,
+        }
+    }
+
+    /// Extend sessions by the TTL each time they are used
+    pub fn with_sliding_expiration(mut self, sliding: bool) -> Self {
+        self.sliding = sliding;
+        self
+    }
+
+    /// Limit the number of concurrent sessions per identity
+    pub fn with_max_sessions(mut self, max: usize) -> Self {
+        self.max_sessions = Some(max);
+        self
+    }
+
+    /// Get the underlying store
+    pub fn store(&self) -> &Arc<dyn SessionStore> {
+        &self.store
+    }
+
+    /// Start a session for an identity
+    pub async fn create(&self, identity: Identity) -> InfraResult<Session> {
+        if let Some(max) = self.max_sessions {
+            if max == 0 {
+                return Err(InfraError::config("Session limit must be at least 1"));
+            }
+
+            let session = Session::new(session_id(), identity, self.ttl);
+            self.store.create_limited(session.clone(), max).await?;
+            return Ok(session);
+        }
+
+        let session = Session::new(session_id(), identity, self.ttl);
+        self.store.create(session.clone()).await?;
+        Ok(session)
+    }
+
+    /// Get a live session, extending it if expiration is sliding
+    ///
+    /// Expired sessions are deleted and reported as missing.
+    pub async fn get(&self, id: &str) -> InfraResult<Option<Session>> {
+        let Some(mut session) = self.store.get(id).await? else {
+            return Ok(None);
+        };
+        if session.is_expired() {
+            self.store.delete(id).await?;
+            return Ok(None);
+        }
+
+        if self.sliding {
+            session.refresh(self.ttl);
+            self.store.update(session.clone()).await?;
+        }
+        Ok(Some(session))
+    }
+
+    /// Save changes to a session's data
+    pub async fn save(&self, session: Session) -> InfraResult<()> {
+        self.store.update(session).await
+    }
+
+    /// End a session
+    pub async fn end(&self, id: &str) -> InfraResult<()> {
+        self.store.delete(id).await
+    }
+
+    /// End all sessions of an identity, returning how many were ended
+    pub async fn end_all(&self, identity_id: &str) -> InfraResult<usize> {
+        let sessions = self.store.list(identity_id).await?;
+        for session in &sessions {
+            self.store.delete(&session.id).await?;
+        }
+        Ok(sessions.len())
+    }
+}
+
+/// Pick the sessions to end before adding `new_id` under a limit of `max`
+///
+/// Returns the expired sessions followed by the least recently active live
+/// ones over the limit.
+pub(crate) fn evictions(mut active: Vec<Session>, new_id: &str, max: usize) -> Vec<Session> {
+    active.retain(|session| session.id != new_id);
+    active.sort_by_key(|session| session.last_activity);
+    let (mut evicted, live): (Vec<_>, Vec<_>) = active.into_iter().partition(Session::is_expired);
+
+    let excess = (live.len() + 1).saturating_sub(max);
+    for session in live.into_iter().take(excess) {
+        tracing::debug!(
+            session_id = %session.id,
+            identity = %session.identity.id,
+            "Ending session over the concurrent session limit"
+        );
+        evicted.push(session);
+    }
+    evicted
+}
+
+/// Generate an unguessable session ID
+fn session_id() -> String {
+    random_token(32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,4 +358,72 @@ mod tests {
         assert!(store.get("valid").await.unwrap().is_some());
         assert!(store.get("expired").await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn test_concurrent_session_limit() {
+        let manager = SessionManager::new(Arc::new(MemorySessionStore::new()), Duration::hours(1))
+            .with_max_sessions(2);
+
+        let first = manager.create(Identity::user("user123")).await.unwrap();
+        let second = manager.create(Identity::user("user123")).await.unwrap();
+        manager.create(Identity::user("other")).await.unwrap();
+        let third = manager.create(Identity::user("user123")).await.unwrap();
+
+        assert!(manager.get(&first.id).await.unwrap().is_none());
+        assert!(manager.get(&second.id).await.unwrap().is_some());
+        assert!(manager.get(&third.id).await.unwrap().is_some());
+        assert_eq!(manager.store().list("other").await.unwrap().len(), 1);
+
+        assert_eq!(manager.end_all("user123").await.unwrap(), 2);
+        assert!(manager.store().list("user123").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_limit_is_atomic() {
+        let manager = Arc::new(
+            SessionManager::new(Arc::new(MemorySessionStore::new()), Duration::hours(1))
+                .with_max_sessions(3),
+        );
+
+        let tasks: Vec<_> = (0..20)
+            .map(|_| {
+                let manager = manager.clone();
+                tokio::spawn(async move { manager.create(Identity::user("user123")).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(manager.store().list("user123").await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_update_does_not_resurrect() {
+        let store = MemorySessionStore::new();
+        let session = Session::new("sess123", Identity::user("user123"), Duration::hours(1));
+        store.create(session.clone()).await.unwrap();
+        store.delete("sess123").await.unwrap();
+
+        store.update(session).await.unwrap();
+        assert!(store.get("sess123").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sliding_expiration() {
+        let store = Arc::new(MemorySessionStore::new());
+        let manager =
+            SessionManager::new(store.clone(), Duration::minutes(30)).with_sliding_expiration(true);
+
+        let mut session = manager.create(Identity::user("user123")).await.unwrap();
+        session.expires_at = Utc::now() + Duration::minutes(1);
+        store.update(session.clone()).await.unwrap();
+
+        let extended = manager.get(&session.id).await.unwrap().unwrap();
+        assert!(extended.expires_at > Utc::now() + Duration::minutes(29));
+
+        session.expires_at = Utc::now() - Duration::seconds(1);
+        store.update(session.clone()).await.unwrap();
+        assert!(manager.get(&session.id).await.unwrap().is_none());
+        assert!(store.get(&session.id).await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,125 @@
+//! Multi-tenancy.
+
+use infra_errors::{InfraError, InfraResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Prefix of tenant-scoped resource names
+const TENANTS_PREFIX: &str = "tenants/";
+
+/// A tenant that identities and resources belong to
+///
+/// Resources are scoped to a tenant by prefixing their name with
+/// `tenants/{id}/`, as in `tenants/acme/models:gpt-4o`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Tenant(String);
+
+impl Tenant {
+    /// Create a tenant
+    ///
+    /// IDs must be non-empty and cannot contain `/`.
+    pub fn new(id: impl Into<String>) -> InfraResult<Self> {
+        let id = id.into();
+        if id.is_empty() || id.contains('/') {
+            return Err(InfraError::validation(format!("Invalid tenant ID: {id:?}")));
+        }
+        Ok(Self(id))
+    }
+
+    /// Get the tenant ID
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+
+    /// Scope a resource name to this tenant
+    pub fn scope(&self, resource: &str) -> String {
+        format!("{TENANTS_PREFIX}{}/{resource}", self.0)
+    }
+
+    /// Strip this tenant's scope from a resource name, if it is scoped to
+    /// this tenant
+    pub fn unscope<'a>(&self, resource: &'a str) -> Option<&'a str> {
+        resource
+            .strip_prefix(TENANTS_PREFIX)?
+            .strip_prefix(self.0.as_str())?
+            .strip_prefix('/')
+    }
+
+    /// Check if a resource is scoped to this tenant
+    pub fn owns(&self, resource: &str) -> bool {
+        matches!(tenant_of(resource), Ok(Some(id)) if id == self.id())
+    }
+}
+
+impl TryFrom<String> for Tenant {
+    type Error = InfraError;
+
+    fn try_from(id: String) -> InfraResult<Self> {
+        Self::new(id)
+    }
+}
+
+impl From<Tenant> for String {
+    fn from(tenant: Tenant) -> Self {
+        tenant.0
+    }
+}
+
+impl fmt::Display for Tenant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Get the ID of the tenant a resource is scoped to, if any
+///
+/// Returns an error for names with the tenant prefix but no valid tenant,
+/// such as `tenants//acme/models`, so they cannot pass for unscoped ones.
+pub fn tenant_of(resource: &str) -> InfraResult<Option<&str>> {
+    let Some(rest) = resource.strip_prefix(TENANTS_PREFIX) else {
+        return Ok(None);
+    };
+    match rest.split('/').next() {
+        Some(id) if !id.is_empty() => Ok(Some(id)),
+        _ => Err(InfraError::validation(format!(
+            "Invalid tenant-scoped resource: {resource:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_scoping() {
+        let acme = Tenant::new("acme").unwrap();
+        let scoped = acme.scope("models:gpt-4o");
+        assert_eq!(scoped, "tenants/acme/models:gpt-4o");
+        assert_eq!(tenant_of(&scoped).unwrap(), Some("acme"));
+        assert_eq!(acme.unscope(&scoped), Some("models:gpt-4o"));
+        assert!(acme.owns(&scoped));
+
+        let globex = Tenant::new("globex").unwrap();
+        assert!(!globex.owns(&scoped));
+        assert_eq!(globex.unscope(&scoped), None);
+        assert_eq!(Tenant::new("acme-eu").unwrap().unscope(&scoped), None);
+        assert_eq!(tenant_of("models:gpt-4o").unwrap(), None);
+        assert!(tenant_of("tenants//acme/models").is_err());
+        assert!(tenant_of("tenants/").is_err());
+        assert!(!acme.owns("tenants//acme/models"));
+
+        assert!(Tenant::new("").is_err());
+        assert!(Tenant::new("a/b").is_err());
+    }
+
+    #[test]
+    fn test_tenant_deserialization_validates() {
+        let acme: Tenant = serde_json::from_str(r#""acme""#).unwrap();
+        assert_eq!(acme.id(), "acme");
+        assert_eq!(serde_json::to_string(&acme).unwrap(), r#""acme""#);
+        assert!(serde_json::from_str::<Tenant>(r#""a/b""#).is_err());
+        assert!(serde_json::from_str::<Tenant>("\"\"").is_err());
+    }
+}
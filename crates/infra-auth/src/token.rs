@@ -0,0 +1,503 @@
+//! Access and refresh token issuance.
+
+use crate::identity::{AsyncIdentityProvider, Identity, TokenIdentity, TokenPayload};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use infra_crypto::jwt::{Claims, JwtSigner};
+use infra_crypto::{random_token, Hasher, Sha256Hasher};
+use infra_errors::{AuthErrorKind, InfraError, InfraResult};
+use infra_time::{TimeHandle, TimeSource};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// An access token and the refresh token to renew it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPair {
+    /// Signed JWT access token
+    pub access_token: String,
+    /// Opaque refresh token
+    pub refresh_token: String,
+    /// Access token expiration
+    pub expires_at: DateTime<Utc>,
+    /// Refresh token expiration
+    pub refresh_expires_at: DateTime<Utc>,
+}
+
+/// A stored refresh token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshRecord {
+    /// Rotation family, shared by every token refreshed from one login
+    pub family: String,
+    /// Identity the token was issued to
+    pub identity: Identity,
+    /// Expiration time
+    pub expires_at: DateTime<Utc>,
+    /// Whether the token has already been exchanged
+    pub used: bool,
+}
+
+/// Storage for refresh tokens and revocations
+#[async_trait]
+pub trait TokenStore: Send + Sync {
+    /// Store a refresh token under the hash of its value
+    async fn insert_refresh(&self, token_hash: &str, record: RefreshRecord) -> InfraResult<()>;
+
+    /// Mark a refresh token used, returning its record as it was before
+    ///
+    /// Must be atomic, so that only one of several concurrent exchanges of
+    /// a token sees it unused.
+    async fn use_refresh(&self, token_hash: &str) -> InfraResult<Option<RefreshRecord>>;
+
+    /// Revoke a token ID or refresh family until `expires_at`
+    async fn revoke(&self, id: &str, expires_at: DateTime<Utc>) -> InfraResult<()>;
+
+    /// Check if a token ID or refresh family is revoked
+    async fn is_revoked(&self, id: &str) -> InfraResult<bool>;
+
+    /// Clean up expired refresh tokens and revocations
+    async fn cleanup(&self) -> InfraResult<usize>;
+}
+
+/// In-memory token store
+#[derive(Default)]
+pub struct MemoryTokenStore {
+    refresh: RwLock<HashMap<String, RefreshRecord>>,
+    revoked: RwLock<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MemoryTokenStore {
+    /// Create a new memory token store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn insert_refresh(&self, token_hash: &str, record: RefreshRecord) -> InfraResult<()> {
+        let mut refresh = self.refresh.write().await;
+        refresh.insert(token_hash.to_string(), record);
+        Ok(())
+    }
+
+    async fn use_refresh(&self, token_hash: &str) -> InfraResult<Option<RefreshRecord>> {
+        let mut refresh = self.refresh.write().await;
+        Ok(refresh.get_mut(token_hash).map(|record| {
+            let previous = record.clone();
+            record.used = true;
+            previous
+        }))
+    }
+
+    async fn revoke(&self, id: &str, expires_at: DateTime<Utc>) -> InfraResult<()> {
+        let mut revoked = self.revoked.write().await;
+        revoked.insert(id.to_string(), expires_at);
+        Ok(())
+    }
+
+    async fn is_revoked(&self, id: &str) -> InfraResult<bool> {
+        let revoked = self.revoked.read().await;
+        Ok(revoked.get(id).is_some_and(|until| *until > Utc::now()))
+    }
+
+    async fn cleanup(&self) -> InfraResult<usize> {
+        let now = Utc::now();
+        let mut refresh = self.refresh.write().await;
+        let mut revoked = self.revoked.write().await;
+        let before = refresh.len() + revoked.len();
+
+        refresh.retain(|_, record| record.expires_at > now);
+        revoked.retain(|_, until| *until > now);
+
+        Ok(before - refresh.len() - revoked.len())
+    }
+}
+
+/// Access token claims
+#[derive(Serialize, Deserialize)]
+struct AccessPayload {
+    /// Refresh family the token was issued in, named by
+    /// [`REFRESH_FAMILY_CLAIM`](crate::identity::REFRESH_FAMILY_CLAIM)
+    fam: String,
+    #[serde(flatten)]
+    identity: TokenPayload,
+}
+
+/// Source of the current state of identities, such as a user directory
+#[async_trait]
+pub trait IdentityLoader: Send + Sync {
+    /// Load the current state of an identity, or `None` if it no longer
+    /// exists
+    async fn load(&self, identity: &Identity) -> InfraResult<Option<Identity>>;
+}
+
+/// Issues access and refresh token pairs
+///
+/// Access tokens are short-lived JWTs. Refresh tokens are opaque, stored
+/// hashed, and rotated on every use: exchanging one returns a new pair and
+/// invalidates the old refresh token. Presenting a refresh token a second
+/// time means it was leaked, so the whole family descending from the same
+/// login is revoked, including its access tokens.
+///
+/// Refreshed tokens carry the identity reloaded by the
+/// [`IdentityLoader`], if one is set, so role changes apply at the next
+/// refresh; otherwise they carry the identity as it was at login.
+pub struct TokenService {
+    signer: JwtSigner,
+    store: Arc<dyn TokenStore>,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+    clock: TimeHandle,
+    loader: Option<Arc<dyn IdentityLoader>>,
+}
+
+impl TokenService {
+    /// Create a new token service
+    pub fn new(signer: JwtSigner, store: Arc<dyn TokenStore>) -> Self {
+        Self {
+            signer,
+            store,
+            access_ttl: Duration::minutes(15),
+            refresh_ttl: Duration::days(30),
+            clock: TimeHandle::default(),
+            loader: None,
+        }
+    }
+
+    /// Set the access token lifetime
+    pub fn with_access_ttl(mut self, ttl: Duration) -> Self {
+        self.access_ttl = ttl;
+        self
+    }
+
+    /// Set the refresh token lifetime
+    pub fn with_refresh_ttl(mut self, ttl: Duration) -> Self {
+        self.refresh_ttl = ttl;
+        self
+    }
+
+    /// Reload identities with `loader` when refreshing tokens
+    ///
+    /// Refreshing fails, and revokes the family, for identities the loader
+    /// no longer knows.
+    pub fn with_identity_loader(mut self, loader: Arc<dyn IdentityLoader>) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Issue and check tokens against a time source, such as a simulated
+    /// clock, rather than the system clock
+    ///
+    /// Revocations kept by the [`TokenStore`] still expire by the system
+    /// clock.
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self
+    }
+
+    /// Issue a token pair for a new login
+    pub async fn issue(&self, identity: &Identity) -> InfraResult<TokenPair> {
+        self.issue_in(Uuid::new_v4().to_string(), identity).await
+    }
+
+    /// Exchange a refresh token for a new pair
+    pub async fn refresh(&self, refresh_token: &str) -> InfraResult<TokenPair> {
+        let Some(record) = self.store.use_refresh(&hash(refresh_token)).await? else {
+            return Err(auth_error(
+                AuthErrorKind::InvalidToken,
+                "Unknown refresh token",
+            ));
+        };
+
+        if record.used {
+            tracing::warn!(
+                identity = %record.identity.id,
+                family = %record.family,
+                "Refresh token reuse detected, revoking family"
+            );
+            self.revoke_family(&record.family).await?;
+            return Err(auth_error(
+                AuthErrorKind::InvalidToken,
+                "Refresh token reuse detected",
+            ));
+        }
+        if self.store.is_revoked(&record.family).await? {
+            return Err(auth_error(
+                AuthErrorKind::InvalidToken,
+                "Refresh token revoked",
+            ));
+        }
+        if record.expires_at <= self.now() {
+            return Err(auth_error(
+                AuthErrorKind::TokenExpired,
+                "Refresh token expired",
+            ));
+        }
+
+        let identity = match &self.loader {
+            Some(loader) => match loader.load(&record.identity).await? {
+                Some(identity) => identity,
+                None => {
+                    self.revoke_family(&record.family).await?;
+                    return Err(auth_error(
+                        AuthErrorKind::InvalidCredentials,
+                        "Identity no longer exists",
+                    ));
+                }
+            },
+            None => record.identity,
+        };
+        self.issue_in(record.family, &identity).await
+    }
+
+    /// Verify an access token
+    pub async fn verify(&self, access_token: &str) -> InfraResult<TokenIdentity> {
+        let claims: Claims<AccessPayload> = self.signer.verify_at(access_token, self.now())?;
+        let jti = claims.jti.unwrap_or_default();
+        if self.store.is_revoked(&jti).await? || self.store.is_revoked(&claims.payload.fam).await? {
+            return Err(auth_error(AuthErrorKind::InvalidToken, "Token revoked"));
+        }
+
+        Ok(TokenIdentity {
+            identity: claims
+                .payload
+                .identity
+                .into_identity(claims.sub.unwrap_or_default()),
+            expires_at: DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now),
+            token_id: Some(jti),
+        })
+    }
+
+    /// Revoke an access token until it expires
+    pub async fn revoke(&self, access_token: &str) -> InfraResult<()> {
+        let claims: Claims<AccessPayload> = self.signer.verify_ignore_expiry(access_token)?;
+        let (Some(jti), Some(expires_at)) = (claims.jti, DateTime::from_timestamp(claims.exp, 0))
+        else {
+            return Err(auth_error(AuthErrorKind::InvalidToken, "Token has no ID"));
+        };
+        self.store.revoke(&jti, expires_at).await
+    }
+
+    /// Revoke a refresh token and every token in its family, as on logout
+    pub async fn revoke_refresh(&self, refresh_token: &str) -> InfraResult<()> {
+        match self.store.use_refresh(&hash(refresh_token)).await? {
+            Some(record) => self.revoke_family(&record.family).await,
+            None => Err(auth_error(
+                AuthErrorKind::InvalidToken,
+                "Unknown refresh token",
+            )),
+        }
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.clock.system_time().into()
+    }
+
+    async fn revoke_family(&self, family: &str) -> InfraResult<()> {
+        // Every token in the family was issued before now, so expires before
+        // this
+        let until = self.now() + self.refresh_ttl.max(self.access_ttl);
+        self.store.revoke(family, until).await
+    }
+
+    async fn issue_in(&self, family: String, identity: &Identity) -> InfraResult<TokenPair> {
+        let payload = AccessPayload {
+            fam: family.clone(),
+            identity: TokenPayload::new(identity),
+        };
+        let now = self.now();
+        let claims = Claims::with_payload(payload, self.access_ttl)
+            .issued_at(now)
+            .with_subject(&identity.id)
+            .with_jti(Uuid::new_v4().to_string());
+        let access_token = self.signer.sign(&claims)?;
+
+        let refresh_token = random_token(32);
+        let refresh_expires_at = now + self.refresh_ttl;
+        let record = RefreshRecord {
+            family,
+            identity: identity.clone(),
+            expires_at: refresh_expires_at,
+            used: false,
+        };
+        self.store
+            .insert_refresh(&hash(&refresh_token), record)
+            .await?;
+
+        Ok(TokenPair {
+            access_token,
+            refresh_token,
+            expires_at: DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now),
+            refresh_expires_at,
+        })
+    }
+}
+
+/// Verifies access tokens, so the service can authenticate requests
+#[async_trait]
+impl AsyncIdentityProvider for TokenService {
+    async fn verify(&self, token: &str) -> InfraResult<Identity> {
+        Ok(TokenService::verify(self, token).await?.identity)
+    }
+}
+
+/// Hash a refresh token for storage
+fn hash(token: &str) -> String {
+    Sha256Hasher::new().hash_hex(token.as_bytes())
+}
+
+fn auth_error(kind: AuthErrorKind, message: &str) -> InfraError {
+    InfraError::Auth {
+        kind,
+        message: message.to_string(),
+        identity: None,
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> TokenService {
+        TokenService::new(
+            JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!"),
+            Arc::new(MemoryTokenStore::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_tokens() {
+        let service = service();
+        let identity = Identity::user("user123").with_role("admin");
+
+        let pair = service.issue(&identity).await.unwrap();
+        let verified = service.verify(&pair.access_token).await.unwrap();
+        assert_eq!(verified.identity.id, "user123");
+        assert!(verified.identity.has_role("admin"));
+
+        let rotated = service.refresh(&pair.refresh_token).await.unwrap();
+        assert_ne!(rotated.refresh_token, pair.refresh_token);
+        assert!(service.verify(&rotated.access_token).await.is_ok());
+        let next = service.refresh(&rotated.refresh_token).await.unwrap();
+
+        // Replaying a rotated-out token revokes the whole family
+        assert!(service.refresh(&pair.refresh_token).await.is_err());
+        assert!(service.refresh(&next.refresh_token).await.is_err());
+        assert!(service.verify(&next.access_token).await.is_err());
+
+        // Other logins are unaffected
+        let other = service.issue(&identity).await.unwrap();
+        assert!(service.verify(&other.access_token).await.is_ok());
+    }
+
+    /// Directory where bob has been removed and ada demoted
+    struct Directory;
+
+    #[async_trait]
+    impl IdentityLoader for Directory {
+        async fn load(&self, identity: &Identity) -> InfraResult<Option<Identity>> {
+            Ok((identity.id == "ada").then(|| Identity::user("ada").with_role("viewer")))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_reloads_identity() {
+        let service = service().with_identity_loader(Arc::new(Directory));
+
+        let ada = Identity::user("ada").with_role("admin");
+        let pair = service.issue(&ada).await.unwrap();
+        let pair = service.refresh(&pair.refresh_token).await.unwrap();
+        let verified = service.verify(&pair.access_token).await.unwrap();
+        assert_eq!(verified.identity.roles, ["viewer"]);
+
+        let pair = service.issue(&Identity::user("bob")).await.unwrap();
+        assert!(service.refresh(&pair.refresh_token).await.is_err());
+        assert!(service.verify(&pair.access_token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_identity_rejects_service_tokens() {
+        let secret = b"super_secret_key_at_least_32_bytes!";
+        let identity = TokenIdentity {
+            identity: Identity::user("user123"),
+            expires_at: Utc::now(),
+            token_id: None,
+        };
+        let token = identity.to_token(secret, Duration::minutes(5)).unwrap();
+        assert!(TokenIdentity::from_token(&token, secret).is_ok());
+
+        let pair = service().issue(&Identity::user("user123")).await.unwrap();
+        assert!(TokenIdentity::from_token(&pair.access_token, secret).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revocation() {
+        let service = service();
+        let identity = Identity::user("user123");
+
+        let pair = service.issue(&identity).await.unwrap();
+        service.revoke(&pair.access_token).await.unwrap();
+        assert!(service.verify(&pair.access_token).await.is_err());
+        // The refresh token still works after revoking an access token
+        let pair = service.refresh(&pair.refresh_token).await.unwrap();
+
+        service.revoke_refresh(&pair.refresh_token).await.unwrap();
+        assert!(service.verify(&pair.access_token).await.is_err());
+        assert!(service.refresh(&pair.refresh_token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_cleanup() {
+        let store = MemoryTokenStore::new();
+        let record = RefreshRecord {
+            family: "fam".to_string(),
+            identity: Identity::user("user123"),
+            expires_at: Utc::now() - Duration::hours(1),
+            used: false,
+        };
+        store.insert_refresh("expired", record).await.unwrap();
+        store
+            .revoke("jti", Utc::now() + Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(store.cleanup().await.unwrap(), 1);
+        assert!(store.is_revoked("jti").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_expiry_with_simulated_time() {
+        let clock = Arc::new(infra_sim::SimulatedClock::new());
+        let service = service().with_time_source(clock.clone());
+        let pair = service.issue(&Identity::user("user123")).await.unwrap();
+
+        // Past the 15 minute lifetime and the 60 second leeway
+        clock.advance(std::time::Duration::from_secs(17 * 60));
+        let err = service.verify(&pair.access_token).await.unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::Auth {
+                kind: AuthErrorKind::TokenExpired,
+                ..
+            }
+        ));
+
+        let rotated = service.refresh(&pair.refresh_token).await.unwrap();
+        assert!(service.verify(&rotated.access_token).await.is_ok());
+
+        clock.advance(std::time::Duration::from_secs(31 * 24 * 3600));
+        let err = service.refresh(&rotated.refresh_token).await.unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::Auth {
+                kind: AuthErrorKind::TokenExpired,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,51 @@
+//! Compares reads through `Cache::get`, which deserializes every value, with
+//! `TypedCache::get`, which shares the stored instance.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use infra_cache::{Cache, InMemoryCache, TypedCache};
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+
+#[derive(Serialize, Deserialize)]
+struct Completion {
+    model: String,
+    choices: Vec<String>,
+    usage: Vec<u32>,
+}
+
+fn completion() -> Completion {
+    Completion {
+        model: "gpt-4".to_string(),
+        choices: (0..16)
+            .map(|i| format!("choice {i}: {}", "token ".repeat(64)))
+            .collect(),
+        usage: (0..64).collect(),
+    }
+}
+
+fn bench_get(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("get");
+
+    let cache = InMemoryCache::unlimited();
+    runtime
+        .block_on(cache.set("key", completion(), None))
+        .unwrap();
+    group.bench_function("serialized", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let value: Option<Completion> = cache.get(black_box("key")).await.unwrap();
+            black_box(value)
+        });
+    });
+
+    let typed = TypedCache::new(InMemoryCache::unlimited());
+    typed.set("key", completion(), None).unwrap();
+    group.bench_function("typed", |b| {
+        b.iter(|| black_box(typed.get(black_box("key")).unwrap()));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_get);
+criterion_main!(benches);
@@ -38,19 +38,25 @@ impl<T> CacheEntry<T> {
 
     /// Check if this entry has expired.
     pub fn is_expired(&self) -> bool {
-        if let Some(ttl) = self.ttl {
-            if let Ok(elapsed) = self.created_at.elapsed() {
-                return elapsed > ttl;
-            }
-        }
-        false
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Check if this entry has expired at the given time.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.ttl
+            .zip(now.duration_since(self.created_at).ok())
+            .is_some_and(|(ttl, elapsed)| elapsed > ttl)
     }
 
     /// Get the remaining time until expiration.
     pub fn time_to_expiry(&self) -> Option<Duration> {
+        self.time_to_expiry_at(SystemTime::now())
+    }
+
+    /// Get the remaining time until expiration, as of the given time.
+    pub fn time_to_expiry_at(&self, now: SystemTime) -> Option<Duration> {
         self.ttl.and_then(|ttl| {
-            self.created_at
-                .elapsed()
+            now.duration_since(self.created_at)
                 .ok()
                 .and_then(|elapsed| ttl.checked_sub(elapsed))
         })
@@ -79,6 +85,50 @@ pub trait Cache: Send + Sync {
     /// Returns `true` if the key existed, `false` otherwise.
     async fn delete(&self, key: &str) -> CacheResult<bool>;
 
+    /// Get several values at once.
+    ///
+    /// Returns one result per key, in the order of `keys`, so one key's
+    /// failure (such as a value that does not deserialize) does not hide
+    /// the others. The outer error reports a failure of the whole batch.
+    /// The default implementation calls [`get`](Self::get) for each key.
+    async fn mget<T>(&self, keys: &[&str]) -> CacheResult<Vec<CacheResult<Option<T>>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get(key).await);
+        }
+        Ok(values)
+    }
+
+    /// Set several values at once with the same optional TTL.
+    ///
+    /// The default implementation calls [`set`](Self::set) for each entry.
+    async fn mset<T>(&self, entries: Vec<(String, T)>, ttl: Option<Duration>) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        for (key, value) in entries {
+            self.set(&key, value, ttl).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete several values at once.
+    ///
+    /// Returns the number of keys that existed. The default implementation
+    /// calls [`delete`](Self::delete) for each key.
+    async fn mdelete(&self, keys: &[&str]) -> CacheResult<usize> {
+        let mut deleted = 0;
+        for key in keys {
+            if self.delete(key).await? {
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
     /// Clear all entries from the cache.
     async fn clear(&self) -> CacheResult<()>;
 
@@ -90,8 +140,46 @@ pub trait Cache: Send + Sync {
     /// Get the number of entries in the cache.
     async fn len(&self) -> CacheResult<usize>;
 
+    /// Get the remaining time to live of a key.
+    ///
+    /// Returns `None` if the key doesn't exist or never expires, or if the
+    /// implementation does not track expiry (the default).
+    async fn ttl(&self, _key: &str) -> CacheResult<Option<Duration>> {
+        Ok(None)
+    }
+
     /// Check if the cache is empty.
     async fn is_empty(&self) -> CacheResult<bool> {
         Ok(self.len().await? == 0)
     }
 }
+
+/// Cache able to invalidate groups of related entries at once.
+///
+/// For example, tagging every completion derived from a document with
+/// `"doc:42"` lets all of them be purged when the document changes.
+#[async_trait]
+pub trait TaggedCache: Cache {
+    /// Set a value associated with `tags`.
+    ///
+    /// Setting the key again replaces its tags.
+    async fn set_tagged<T>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<Duration>,
+        tags: &[&str],
+    ) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static;
+
+    /// Delete all entries associated with `tag`.
+    ///
+    /// Returns the deleted keys.
+    async fn invalidate_tag(&self, tag: &str) -> CacheResult<Vec<String>>;
+
+    /// Delete all entries whose key starts with `prefix`.
+    ///
+    /// Returns the deleted keys.
+    async fn invalidate_prefix(&self, prefix: &str) -> CacheResult<Vec<String>>;
+}
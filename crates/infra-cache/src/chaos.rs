@@ -0,0 +1,159 @@
+//! Fault injection for resilience tests.
+//!
+//! [`ChaosCache`] wraps a cache and injects latency, failures and corrupted
+//! reads according to an infra-sim [`ChaosPolicy`].
+
+use async_trait::async_trait;
+use infra_sim::ChaosPolicy;
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+use crate::cache::Cache;
+use crate::error::{CacheError, CacheResult};
+
+/// Cache injecting faults into another cache.
+///
+/// Operations are named after the [`Cache`] methods (`get`, `set`,
+/// `delete`, `clear`, `exists` and `len`); batch methods go through the
+/// single-key ones. Injected failures are [`CacheError::NetworkError`], as
+/// from an unreachable distributed cache. A corrupted read has one bit of
+/// the stored JSON flipped, so it either fails to deserialize or yields a
+/// subtly different value.
+///
+/// # Examples
+///
+/// ```
+/// use infra_cache::{Cache, ChaosCache, InMemoryCache};
+/// use infra_sim::{ChaosConfig, ChaosMode, ChaosPolicy};
+///
+/// # async fn example() {
+/// let policy = ChaosPolicy::disabled().operation(
+///     "set",
+///     ChaosConfig::new(ChaosMode::AlwaysFail),
+/// );
+/// let cache = ChaosCache::new(InMemoryCache::with_defaults(), policy);
+/// assert!(cache.set("key", 1, None).await.is_err());
+/// # }
+/// ```
+pub struct ChaosCache<C> {
+    inner: C,
+    policy: ChaosPolicy,
+}
+
+impl<C: Cache> ChaosCache<C> {
+    /// Wrap a cache.
+    pub fn new(inner: C, policy: impl Into<ChaosPolicy>) -> Self {
+        Self {
+            inner,
+            policy: policy.into(),
+        }
+    }
+
+    /// Get the wrapped cache.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    async fn disturb(&self, operation: &str) -> CacheResult<()> {
+        self.policy
+            .disturb(operation)
+            .await
+            .map_err(CacheError::NetworkError)
+    }
+}
+
+#[async_trait]
+impl<C: Cache> Cache for ChaosCache<C> {
+    async fn get<T>(&self, key: &str) -> CacheResult<Option<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.disturb("get").await?;
+        let injector = self.policy.injector("get");
+        if !injector.should_corrupt() {
+            return self.inner.get(key).await;
+        }
+
+        let Some(value) = self.inner.get::<serde_json::Value>(key).await? else {
+            return Ok(None);
+        };
+        let mut bytes = serde_json::to_vec(&value)?;
+        injector.corrupt_bytes(&mut bytes);
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| CacheError::DeserializationError(e.to_string()))
+    }
+
+    async fn set<T>(&self, key: &str, value: T, ttl: Option<Duration>) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.disturb("set").await?;
+        self.inner.set(key, value, ttl).await
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<bool> {
+        self.disturb("delete").await?;
+        self.inner.delete(key).await
+    }
+
+    async fn clear(&self) -> CacheResult<()> {
+        self.disturb("clear").await?;
+        self.inner.clear().await
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        self.disturb("exists").await?;
+        self.inner.exists(key).await
+    }
+
+    async fn len(&self) -> CacheResult<usize> {
+        self.disturb("len").await?;
+        self.inner.len().await
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        self.disturb("ttl").await?;
+        self.inner.ttl(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::InMemoryCache;
+    use infra_sim::{ChaosConfig, ChaosMode};
+
+    #[tokio::test]
+    async fn test_chaos_cache_failures() {
+        let policy = ChaosPolicy::disabled().operation(
+            "set",
+            ChaosConfig::new(ChaosMode::AlwaysFail).with_error_message("injected"),
+        );
+        let cache = ChaosCache::new(InMemoryCache::with_defaults(), policy);
+
+        let err = cache.set("key", 1, None).await.unwrap_err();
+        assert!(matches!(err, CacheError::NetworkError(ref m) if m == "injected"));
+
+        cache.inner().set("key", 1, None).await.unwrap();
+        assert_eq!(cache.get::<i32>("key").await.unwrap(), Some(1));
+        assert!(cache.exists("key").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_chaos_cache_corruption() {
+        let cache = ChaosCache::new(
+            InMemoryCache::with_defaults(),
+            ChaosConfig::default().with_corruption_probability(1.0),
+        );
+        cache.set("key", "value".to_string(), None).await.unwrap();
+
+        for _ in 0..10 {
+            match cache.get::<String>("key").await {
+                Ok(value) => assert_ne!(value.as_deref(), Some("value")),
+                Err(err) => assert!(matches!(err, CacheError::DeserializationError(_))),
+            }
+        }
+        assert_eq!(cache.get::<String>("missing").await.unwrap(), None);
+    }
+}
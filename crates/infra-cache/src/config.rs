@@ -27,6 +27,11 @@ pub struct CacheConfig {
     /// None means unlimited.
     pub max_size: Option<usize>,
 
+    /// Maximum total size of the serialized values in bytes.
+    /// None means unlimited.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+
     /// Default time-to-live for cache entries.
     /// None means entries don't expire by default.
     #[serde(
@@ -47,6 +52,7 @@ impl Default for CacheConfig {
     fn default() -> Self {
         Self {
             max_size: Some(1000),
+            max_bytes: None,
             default_ttl: Some(Duration::from_secs(3600)), // 1 hour
             eviction_policy: EvictionPolicy::LRU,
             enable_metrics: false,
@@ -59,6 +65,7 @@ impl CacheConfig {
     pub fn unlimited() -> Self {
         Self {
             max_size: None,
+            max_bytes: None,
             default_ttl: None,
             eviction_policy: EvictionPolicy::LRU,
             enable_metrics: false,
@@ -73,6 +80,13 @@ impl CacheConfig {
         }
     }
 
+    /// Set the maximum total size of the serialized values in bytes.
+    #[must_use]
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
     /// Set the default TTL.
     pub fn with_ttl(mut self, ttl: Duration) -> Self {
         self.default_ttl = Some(ttl);
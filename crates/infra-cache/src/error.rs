@@ -21,6 +21,15 @@ pub enum CacheError {
     #[error("Cache is full (max size: {0})")]
     CacheFull(usize),
 
+    /// Entry is larger than the cache's byte limit.
+    #[error("Cache entry of {size} bytes exceeds the limit of {max_bytes} bytes")]
+    EntryTooLarge {
+        /// Serialized size of the entry.
+        size: usize,
+        /// Configured byte limit.
+        max_bytes: usize,
+    },
+
     /// Entry has expired.
     #[error("Cache entry has expired")]
     EntryExpired,
@@ -0,0 +1,192 @@
+//! Structured cache key construction.
+
+use infra_crypto::{Blake3Hasher, Hasher};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::CacheResult;
+
+/// Builder for namespaced, versioned cache keys.
+///
+/// Components are sorted by name, so the order they are added in does not
+/// change the key, and separator characters in values are escaped. Large or
+/// sensitive inputs such as prompts can be added as hashes, and structured
+/// ones such as sampling parameters as hashes of their JSON form.
+///
+/// Keys have the form `namespace:v1:model=gpt-4:prompt=<blake3 hex>`.
+/// Bumping the version invalidates every key built with the old one.
+///
+/// # Examples
+///
+/// ```
+/// use infra_cache::CacheKey;
+/// use serde_json::json;
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let key = CacheKey::new("completions")
+///     .with_version(2)
+///     .with("model", "gpt-4")
+///     .with_hashed("prompt", "Explain caching")
+///     .with_json("params", &json!({ "temperature": 0.2, "max_tokens": 256 }))?
+///     .build();
+///
+/// assert!(key.starts_with("completions:v2:model=gpt-4:params="));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    namespace: String,
+    version: u32,
+    components: BTreeMap<String, String>,
+}
+
+impl CacheKey {
+    /// Start a key in `namespace` at version 1.
+    pub fn new(namespace: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+            version: 1,
+            components: BTreeMap::new(),
+        }
+    }
+
+    /// Set the key version.
+    #[must_use]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Add a component with a literal value.
+    ///
+    /// Adding a component with the same name again replaces it.
+    #[must_use]
+    pub fn with(mut self, name: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.components.insert(name.into(), value.to_string());
+        self
+    }
+
+    /// Add a component whose value is the Blake3 hash of `data`.
+    #[must_use]
+    pub fn with_hashed(mut self, name: impl Into<String>, data: impl AsRef<[u8]>) -> Self {
+        let hash = Blake3Hasher::new().hash_hex(data.as_ref());
+        self.components.insert(name.into(), hash);
+        self
+    }
+
+    /// Add a component whose value is the hash of `value`'s JSON form.
+    ///
+    /// Object fields are serialized in sorted order, so equal values give
+    /// equal hashes regardless of how they were built.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` cannot be serialized.
+    pub fn with_json<T: Serialize>(self, name: impl Into<String>, value: &T) -> CacheResult<Self> {
+        let json = serde_json::to_value(value)?.to_string();
+        Ok(self.with_hashed(name, json))
+    }
+
+    /// Build the key string.
+    #[must_use]
+    pub fn build(&self) -> String {
+        self.to_string()
+    }
+
+    /// Build a fixed-length key, hashing all components together.
+    ///
+    /// Useful for backends that limit key length. The namespace and version
+    /// stay readable so prefix invalidation still works.
+    #[must_use]
+    pub fn build_hashed(&self) -> String {
+        let components = self.components_string();
+        format!(
+            "{}:v{}:{}",
+            escape(&self.namespace),
+            self.version,
+            Blake3Hasher::new().hash_hex(components.as_bytes())
+        )
+    }
+
+    /// Get the prefix shared by all keys of this namespace and version.
+    #[must_use]
+    pub fn prefix(&self) -> String {
+        format!("{}:v{}:", escape(&self.namespace), self.version)
+    }
+
+    fn components_string(&self) -> String {
+        self.components
+            .iter()
+            .map(|(name, value)| format!("{}={}", escape(name), escape(value)))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix(), self.components_string())
+    }
+}
+
+impl From<CacheKey> for String {
+    fn from(key: CacheKey) -> Self {
+        key.build()
+    }
+}
+
+/// Percent-encode the characters used as separators in keys.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            ':' => escaped.push_str("%3A"),
+            '=' => escaped.push_str("%3D"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_components_are_sorted_and_escaped() {
+        let a = CacheKey::new("embeddings")
+            .with("model", "text-embedding-3")
+            .with("input", "a:b=c")
+            .build();
+        let b = CacheKey::new("embeddings")
+            .with("input", "a:b=c")
+            .with("model", "text-embedding-3")
+            .build();
+
+        assert_eq!(a, b);
+        assert_eq!(a, "embeddings:v1:input=a%3Ab%3Dc:model=text-embedding-3");
+        assert_ne!(a, CacheKey::new("embeddings").with_version(2).build());
+    }
+
+    #[test]
+    fn test_hashed_components() {
+        let params = |temperature| {
+            CacheKey::new("completions")
+                .with_json("params", &json!({ "temperature": temperature, "top_p": 1 }))
+                .unwrap()
+        };
+        assert_eq!(params(0.2), params(0.2));
+        assert_ne!(params(0.2), params(0.7));
+
+        let key = CacheKey::new("completions").with_hashed("prompt", "Hello");
+        assert_eq!(key.build().len(), "completions:v1:prompt=".len() + 64);
+
+        let hashed = key.build_hashed();
+        assert!(hashed.starts_with(&key.prefix()));
+        assert_eq!(hashed.len(), key.prefix().len() + 64);
+    }
+}
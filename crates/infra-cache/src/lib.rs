@@ -1,7 +1,17 @@
 //! Caching abstraction for LLM-Dev-Ops infrastructure.
 //!
 //! This crate provides a flexible caching abstraction with support for
-//! in-memory and distributed cache implementations.
+//! in-memory and distributed cache implementations, which can be layered
+//! with [`TieredCache`].
+//!
+//! # Features
+//!
+//! - `otel`: Per-tier hit and miss counters for [`TieredCache`] through
+//!   `infra-otel`.
+//! - `chaos`: [`ChaosCache`], injecting faults through `infra-sim`.
+//!
+//! [`InMemoryCache::with_time_source`] measures TTLs against an `infra-time`
+//! time source, such as a simulated clock, rather than the system clock.
 //!
 //! # Examples
 //!
@@ -38,12 +48,26 @@
 #![allow(clippy::module_name_repetitions)]
 
 pub mod cache;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod config;
 pub mod error;
+pub mod key;
 pub mod memory;
+pub mod stats;
+pub mod sweeper;
+pub mod tiered;
+pub mod typed;
 
 // Re-export main types
-pub use cache::{Cache, CacheEntry};
+pub use cache::{Cache, CacheEntry, TaggedCache};
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosCache;
 pub use config::{CacheConfig, EvictionPolicy};
 pub use error::{CacheError, CacheResult};
-pub use memory::InMemoryCache;
+pub use key::CacheKey;
+pub use memory::{EvictionStats, InMemoryCache};
+pub use stats::{CacheListener, CacheStats};
+pub use sweeper::SweeperHandle;
+pub use tiered::{PromotionPolicy, TieredCache, TieredStats, DEFAULT_MAX_TRACKED_KEYS};
+pub use typed::TypedCache;
@@ -1,27 +1,124 @@
 //! In-memory cache implementation.
 
 use async_trait::async_trait;
-use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use dashmap::mapref::one::Ref;
+use dashmap::{DashMap, DashSet};
+use infra_time::{TimeHandle, TimeSource};
+use parking_lot::Mutex;
 use serde::{de::DeserializeOwned, Serialize};
+use std::any::Any;
+use std::collections::{BTreeSet, HashSet};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
-use crate::cache::{Cache, CacheEntry};
-use crate::config::CacheConfig;
+use crate::cache::{Cache, CacheEntry, TaggedCache};
+use crate::config::{CacheConfig, EvictionPolicy};
 use crate::error::{CacheError, CacheResult};
+use crate::stats::{CacheListener, CacheStats, Listeners};
+use crate::sweeper::{self, SweeperHandle};
 
 /// Internal cache entry that stores serialized data.
-#[derive(Debug, Clone)]
-struct InternalEntry {
+#[derive(Debug)]
+pub(crate) struct InternalEntry {
     data: Vec<u8>,
     entry: CacheEntry<()>,
+    /// Age after which the entry is stale and refreshed by
+    /// [`InMemoryCache::get_or_refresh`], while still served until its TTL.
+    soft_ttl: Option<Duration>,
+    /// Tick at which the entry was inserted, for FIFO eviction.
+    inserted: u64,
+    /// Tick of the last read, for LRU eviction.
+    last_access: AtomicU64,
+    /// Number of reads, for LFU eviction.
+    hits: AtomicU64,
+    /// Tags the entry is indexed under.
+    tags: Vec<String>,
+    /// Deserialized value kept by a [`TypedCache`](crate::TypedCache).
+    object: Option<SharedValue>,
+}
+
+/// Eviction counts of an [`InMemoryCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EvictionStats {
+    /// Entries removed by the eviction policy to respect the size limits.
+    pub evicted: u64,
+    /// Entries removed because their TTL elapsed.
+    pub expired: u64,
+}
+
+/// Value shared with readers without deserializing it again.
+pub(crate) type SharedValue = Arc<dyn Any + Send + Sync>;
+
+impl InternalEntry {
+    fn touch(&self, tick: u64) {
+        self.last_access.store(tick, Ordering::Relaxed);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn is_stale_at(&self, now: SystemTime) -> bool {
+        self.soft_ttl
+            .zip(now.duration_since(self.entry.created_at).ok())
+            .is_some_and(|(soft_ttl, elapsed)| elapsed > soft_ttl)
+    }
+}
+
+/// Clears a key's in-flight refresh mark when the refresh ends, including
+/// when its loader panics.
+struct RefreshGuard {
+    refreshing: Arc<DashSet<String>>,
+    key: String,
 }
 
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        self.refreshing.remove(&self.key);
+    }
+}
+
+/// Deserialize a stored value.
+fn decode<T: DeserializeOwned>(data: &[u8]) -> CacheResult<T> {
+    serde_json::from_slice(data)
+        .map_err(|e| CacheError::DeserializationError(format!("Failed to deserialize: {e}")))
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    /// Logical clock ordering inserts and reads.
+    clock: AtomicU64,
+    /// Total size of the serialized values.
+    bytes: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evicted: AtomicU64,
+    expired: AtomicU64,
+}
+
+/// Position of an entry in the eviction order; lower ranks are evicted first.
+type Rank = (u64, u64);
+
 /// In-memory cache implementation using DashMap.
+///
+/// When `max_size` or `max_bytes` is reached, entries are evicted according
+/// to the configured [`EvictionPolicy`]. Caches with a limit keep their
+/// entries ordered by eviction rank, so finding a victim takes logarithmic
+/// time; in exchange, reads update that order under a lock.
 #[derive(Debug, Clone)]
 pub struct InMemoryCache {
     store: Arc<DashMap<String, InternalEntry>>,
     config: Arc<CacheConfig>,
+    counters: Arc<Counters>,
+    /// Keys by eviction rank, maintained only when a limit is configured.
+    order: Arc<Mutex<BTreeSet<(Rank, String)>>>,
+    /// Keys with a background refresh in flight.
+    refreshing: Arc<DashSet<String>>,
+    listeners: Arc<Listeners>,
+    /// Keys associated with each tag.
+    tags: Arc<DashMap<String, HashSet<String>>>,
+    /// Time TTLs are measured against.
+    time: TimeHandle,
 }
 
 impl InMemoryCache {
@@ -30,9 +127,25 @@ impl InMemoryCache {
         Self {
             store: Arc::new(DashMap::new()),
             config: Arc::new(config),
+            counters: Arc::new(Counters::default()),
+            order: Arc::new(Mutex::new(BTreeSet::new())),
+            refreshing: Arc::new(DashSet::new()),
+            listeners: Arc::new(Listeners::default()),
+            tags: Arc::new(DashMap::new()),
+            time: TimeHandle::default(),
         }
     }
 
+    /// Measure TTLs against a time source, such as a simulated clock, rather
+    /// than the system clock.
+    ///
+    /// Set this before inserting entries, as they are timestamped on insert.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.time = TimeHandle::from_source(source);
+        self
+    }
+
     /// Create a new in-memory cache with default configuration.
     pub fn with_defaults() -> Self {
         Self::new(CacheConfig::default())
@@ -43,114 +156,569 @@ impl InMemoryCache {
         Self::new(CacheConfig::unlimited())
     }
 
-    /// Remove expired entries from the cache.
-    fn evict_expired(&self) {
-        self.store.retain(|_, entry| !entry.entry.is_expired());
+    /// Get the total size of the stored serialized values in bytes.
+    #[must_use]
+    pub fn size_bytes(&self) -> usize {
+        self.counters.bytes.load(Ordering::Relaxed)
     }
 
-    /// Check if the cache is full and needs eviction.
-    fn needs_eviction(&self) -> bool {
-        if let Some(max_size) = self.config.max_size {
-            self.store.len() >= max_size
-        } else {
-            false
+    /// Get the number of entries evicted and expired so far.
+    #[must_use]
+    pub fn eviction_stats(&self) -> EvictionStats {
+        EvictionStats {
+            evicted: self.counters.evicted.load(Ordering::Relaxed),
+            expired: self.counters.expired.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get a snapshot of the cache's counters.
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            evictions: self.counters.evicted.load(Ordering::Relaxed),
+            expired: self.counters.expired.load(Ordering::Relaxed),
+            entries: self.store.len(),
+            bytes: self.size_bytes(),
         }
     }
 
-    /// Evict one entry according to the eviction policy.
-    fn evict_one(&self) -> CacheResult<()> {
-        // First, try to remove expired entries
+    /// Register a listener for insert, delete, clear, evict and expire
+    /// events.
+    ///
+    /// Listeners are shared by all clones of this cache.
+    pub fn subscribe(&self, listener: Arc<dyn CacheListener>) {
+        self.listeners.add(listener);
+    }
+
+    /// Remove expired entries, then evict entries until the cache is within
+    /// its size limits.
+    ///
+    /// Expired entries are otherwise only removed when touched or when room
+    /// is needed; [`spawn_sweeper`](Self::spawn_sweeper) calls this
+    /// periodically.
+    pub fn sweep(&self) {
         self.evict_expired();
+        while self.is_over_limits() {
+            if !self.evict_one() {
+                break;
+            }
+        }
+    }
 
-        // If still full, remove based on eviction policy
-        if self.needs_eviction() {
-            // For now, just remove the first entry (FIFO-like behavior)
-            // TODO: Implement proper LRU/LFU tracking
-            if let Some(entry) = self.store.iter().next() {
-                let key = entry.key().clone();
-                drop(entry);
-                self.store.remove(&key);
+    /// Sweep the cache every `interval` in a background task.
+    ///
+    /// The task runs until [`SweeperHandle::shutdown`] is called or the
+    /// handle is dropped. Must be called from within a Tokio runtime.
+    #[must_use = "the sweeper stops when its handle is dropped"]
+    pub fn spawn_sweeper(&self, interval: Duration) -> SweeperHandle {
+        sweeper::spawn(self.clone(), interval)
+    }
+
+    /// Store a value, associated with `tags`, that becomes stale after
+    /// `soft_ttl`.
+    ///
+    /// Stale values are still returned by [`Cache::get`] until `ttl` (or the
+    /// default TTL) elapses; [`get_or_refresh`](Self::get_or_refresh) also
+    /// refreshes them, keeping their tags.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value cannot be serialized or exceeds the
+    /// cache's byte limit.
+    pub fn set_with_soft_ttl<T>(
+        &self,
+        key: &str,
+        value: &T,
+        soft_ttl: Duration,
+        ttl: Option<Duration>,
+        tags: &[&str],
+    ) -> CacheResult<()>
+    where
+        T: Serialize,
+    {
+        self.insert(key, value, ttl, Some(soft_ttl), tags, None, None)
+    }
+
+    /// Get a value, serving stale entries while they are refreshed.
+    ///
+    /// On a miss, `loader` is awaited and its value stored with the given
+    /// soft and hard TTLs. Once an entry is older than `soft_ttl`, its stale
+    /// value is returned immediately and `loader` runs in a background task
+    /// to replace it, so readers never wait for a popular entry to reload.
+    /// Only one refresh per key runs at a time; a failed refresh keeps the
+    /// stale value until the next read retries it. A refresh keeps the
+    /// entry's tags, and is discarded if the entry was deleted or replaced
+    /// while the loader ran.
+    ///
+    /// Must be called within a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns the loader's error on a miss, or an error if the value cannot
+    /// be serialized or deserialized.
+    pub async fn get_or_refresh<T, F, Fut>(
+        &self,
+        key: &str,
+        soft_ttl: Duration,
+        ttl: Option<Duration>,
+        loader: F,
+    ) -> CacheResult<T>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = CacheResult<T>> + Send + 'static,
+    {
+        let Some(entry) = self.live_entry(key) else {
+            let value = loader().await?;
+            self.insert(key, &value, ttl, Some(soft_ttl), &[], None, None)?;
+            return Ok(value);
+        };
+        let value = decode(&entry.data)?;
+        let stale = entry
+            .is_stale_at(self.time.system_time())
+            .then(|| (entry.inserted, entry.tags.clone()));
+        drop(entry);
+
+        if let Some((generation, tags)) = stale {
+            if self.refreshing.insert(key.to_string()) {
+                let guard = RefreshGuard {
+                    refreshing: self.refreshing.clone(),
+                    key: key.to_string(),
+                };
+                let cache = self.clone();
+                tokio::spawn(async move {
+                    if let Ok(value) = loader().await {
+                        // On failure the stale value stays until the next read
+                        let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+                        let _ = cache.insert(
+                            &guard.key,
+                            &value,
+                            ttl,
+                            Some(soft_ttl),
+                            &tags,
+                            None,
+                            Some(generation),
+                        );
+                    }
+                });
             }
         }
+        Ok(value)
+    }
 
-        Ok(())
+    /// Look up a value.
+    fn lookup<T>(&self, key: &str) -> CacheResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.live_entry(key)
+            .map(|entry| decode(&entry.data))
+            .transpose()
     }
-}
 
-#[async_trait]
-impl Cache for InMemoryCache {
-    async fn get<T>(&self, key: &str) -> CacheResult<Option<T>>
+    /// Look up a value stored by a [`TypedCache`](crate::TypedCache),
+    /// deserializing it only if it was stored in serialized form alone.
+    pub(crate) fn lookup_shared<T>(&self, key: &str) -> CacheResult<Option<Arc<T>>>
     where
-        T: DeserializeOwned + Send + 'static,
+        T: DeserializeOwned + Send + Sync + 'static,
     {
+        let Some(entry) = self.live_entry(key) else {
+            return Ok(None);
+        };
+        if let Some(value) = entry.object.clone().and_then(|o| o.downcast::<T>().ok()) {
+            return Ok(Some(value));
+        }
+
+        Ok(Some(Arc::new(decode(&entry.data)?)))
+    }
+
+    /// Store a [`TypedCache`](crate::TypedCache) value with its bytes.
+    pub(crate) fn insert_shared<T>(
+        &self,
+        key: &str,
+        value: &Arc<T>,
+        ttl: Option<Duration>,
+    ) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let object: SharedValue = value.clone();
+        self.insert(key, value.as_ref(), ttl, None, &[], Some(object), None)
+    }
+
+    /// Get an unexpired entry, recording the hit or miss.
+    fn live_entry(&self, key: &str) -> Option<Ref<'_, String, InternalEntry>> {
         // Remove expired entries periodically
         if self.store.len() % 100 == 0 {
             self.evict_expired();
         }
 
-        if let Some(entry) = self.store.get(key) {
-            // Check if expired
-            if entry.entry.is_expired() {
-                drop(entry);
-                self.store.remove(key);
-                return Ok(None);
-            }
+        let Some(entry) = self.store.get(key) else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
 
-            // Deserialize the value
-            let value: T = serde_json::from_slice(&entry.data).map_err(|e| {
-                CacheError::DeserializationError(format!("Failed to deserialize: {}", e))
-            })?;
+        // Check if expired
+        if entry.entry.is_expired_at(self.time.system_time()) {
+            drop(entry);
+            self.expire(key);
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.touch(key, &entry);
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry)
+    }
 
-            Ok(Some(value))
-        } else {
-            Ok(None)
+    /// Record a read of `entry`, moving it in the eviction order.
+    fn touch(&self, key: &str, entry: &InternalEntry) {
+        let tick = self.tick();
+        if !self.is_bounded() || self.config.eviction_policy == EvictionPolicy::FIFO {
+            entry.touch(tick);
+            return;
+        }
+
+        let mut order = self.order.lock();
+        let before = (self.rank(entry), key.to_string());
+        entry.touch(tick);
+        // An entry missing from the order is being evicted; leave it out
+        if order.remove(&before) {
+            order.insert((self.rank(entry), before.1));
         }
     }
 
-    async fn set<T>(&self, key: &str, value: T, ttl: Option<Duration>) -> CacheResult<()>
+    /// Serialize and store a value, evicting entries as needed.
+    ///
+    /// With `generation`, the value only replaces the entry inserted at that
+    /// tick, and is dropped if the key was deleted or set again since.
+    #[allow(clippy::too_many_arguments)]
+    fn insert<T>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+        soft_ttl: Option<Duration>,
+        tags: &[&str],
+        object: Option<SharedValue>,
+        generation: Option<u64>,
+    ) -> CacheResult<()>
     where
-        T: Serialize + Send + Sync + 'static,
+        T: Serialize,
     {
-        // Check if we need to evict entries
-        if self.needs_eviction() && !self.store.contains_key(key) {
-            self.evict_one()?;
+        // Serialize the value
+        let data = serde_json::to_vec(value)?;
+        if let Some(max_bytes) = self.config.max_bytes {
+            if data.len() > max_bytes {
+                return Err(CacheError::EntryTooLarge {
+                    size: data.len(),
+                    max_bytes,
+                });
+            }
         }
 
-        // Serialize the value
-        let data = serde_json::to_vec(&value)?;
+        let current = self
+            .store
+            .get(key)
+            .map(|entry| (entry.data.len(), entry.inserted));
+        if generation.is_some() && current.map(|(_, inserted)| inserted) != generation {
+            return Ok(());
+        }
+
+        // Evict entries until the new one fits in place of any it replaces
+        let replacing = current.map(|(size, _)| size);
+        while self.needs_eviction(data.len(), replacing) {
+            if !self.evict_one() {
+                break;
+            }
+        }
 
         // Determine TTL
         let entry_ttl = ttl.or(self.config.default_ttl);
 
         // Create the entry
-        let entry = if let Some(ttl) = entry_ttl {
+        let mut entry = if let Some(ttl) = entry_ttl {
             CacheEntry::with_ttl((), ttl)
         } else {
             CacheEntry::new(())
         };
+        entry.created_at = self.time.system_time();
 
-        let internal_entry = InternalEntry { data, entry };
+        let tick = self.tick();
+        let size = data.len();
+        let mut internal_entry = InternalEntry {
+            data,
+            entry,
+            soft_ttl,
+            inserted: tick,
+            last_access: AtomicU64::new(tick),
+            hits: AtomicU64::new(0),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            object,
+        };
 
-        // Store the entry
-        self.store.insert(key.to_string(), internal_entry);
+        // Store the entry, replacing any existing one in place so readers
+        // never miss the key. Its tags are indexed first, under the entry's
+        // lock, so a concurrent invalidation either sees the key or runs
+        // entirely before the write
+        let replaced = match self.store.entry(key.to_string()) {
+            Entry::Occupied(mut occupied) => {
+                if generation.is_some_and(|generation| occupied.get().inserted != generation) {
+                    return Ok(());
+                }
+                // Keep the access frequency of the replaced entry
+                let hits = occupied.get().hits.load(Ordering::Relaxed);
+                internal_entry.hits = AtomicU64::new(hits);
+                self.tag(key, tags);
+                self.order(key, &internal_entry);
+                let replaced = occupied.insert(internal_entry);
+                self.unorder(key, &replaced);
+                Some(replaced)
+            }
+            Entry::Vacant(vacant) => {
+                if generation.is_some() {
+                    return Ok(());
+                }
+                self.tag(key, tags);
+                self.order(key, &internal_entry);
+                vacant.insert(internal_entry);
+                None
+            }
+        };
+        self.counters.bytes.fetch_add(size, Ordering::Relaxed);
+        if let Some(replaced) = replaced {
+            self.counters
+                .bytes
+                .fetch_sub(replaced.data.len(), Ordering::Relaxed);
+            let dropped: Vec<String> = replaced
+                .tags
+                .into_iter()
+                .filter(|tag| !tags.contains(&tag.as_str()))
+                .collect();
+            self.untag(key, &dropped);
+        }
+        self.listeners.emit(|l| l.on_insert(key, size));
 
         Ok(())
     }
 
+    fn tick(&self) -> u64 {
+        self.counters.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Check if a size limit is configured, and so eviction order tracked.
+    fn is_bounded(&self) -> bool {
+        self.config.max_size.is_some() || self.config.max_bytes.is_some()
+    }
+
+    /// Get the eviction rank of an entry under the configured policy.
+    fn rank(&self, entry: &InternalEntry) -> Rank {
+        let last_access = entry.last_access.load(Ordering::Relaxed);
+        match self.config.eviction_policy {
+            EvictionPolicy::LRU => (last_access, 0),
+            EvictionPolicy::LFU => (entry.hits.load(Ordering::Relaxed), last_access),
+            EvictionPolicy::FIFO => (entry.inserted, 0),
+        }
+    }
+
+    /// Add an entry to the eviction order.
+    fn order(&self, key: &str, entry: &InternalEntry) {
+        if self.is_bounded() {
+            self.order
+                .lock()
+                .insert((self.rank(entry), key.to_string()));
+        }
+    }
+
+    /// Remove an entry from the eviction order.
+    fn unorder(&self, key: &str, entry: &InternalEntry) {
+        if self.is_bounded() {
+            self.order
+                .lock()
+                .remove(&(self.rank(entry), key.to_string()));
+        }
+    }
+
+    /// Delete an entry on request, notifying listeners.
+    pub(crate) fn delete_entry(&self, key: &str) -> bool {
+        let deleted = self.remove_entry(key).is_some();
+        if deleted {
+            self.listeners.emit(|l| l.on_delete(key));
+        }
+        deleted
+    }
+
+    /// Remove an entry, releasing its bytes.
+    fn remove_entry(&self, key: &str) -> Option<InternalEntry> {
+        let (_, entry) = self.store.remove(key)?;
+        self.unorder(key, &entry);
+        self.counters
+            .bytes
+            .fetch_sub(entry.data.len(), Ordering::Relaxed);
+        self.untag(key, &entry.tags);
+        Some(entry)
+    }
+
+    /// Add `key` to the index of each of `tags`.
+    fn tag(&self, key: &str, tags: &[&str]) {
+        for tag in tags {
+            self.tags
+                .entry((*tag).to_string())
+                .or_default()
+                .insert(key.to_string());
+        }
+    }
+
+    /// Remove `key` from the index of each of `tags`.
+    fn untag(&self, key: &str, tags: &[String]) {
+        for tag in tags {
+            if let Some(mut keys) = self.tags.get_mut(tag) {
+                keys.remove(key);
+            }
+            self.tags.remove_if(tag, |_, keys| keys.is_empty());
+        }
+    }
+
+    /// Remove an entry whose TTL elapsed.
+    fn expire(&self, key: &str) {
+        if self.remove_entry(key).is_some() {
+            self.counters.expired.fetch_add(1, Ordering::Relaxed);
+            self.listeners.emit(|l| l.on_expire(key));
+        }
+    }
+
+    /// Remove expired entries from the cache.
+    fn evict_expired(&self) {
+        let now = self.time.system_time();
+        let mut expired = Vec::new();
+        self.store.retain(|key, entry| {
+            if entry.entry.is_expired_at(now) {
+                self.unorder(key, entry);
+                self.counters
+                    .bytes
+                    .fetch_sub(entry.data.len(), Ordering::Relaxed);
+                self.counters.expired.fetch_add(1, Ordering::Relaxed);
+                self.untag(key, &entry.tags);
+                expired.push(key.clone());
+                false
+            } else {
+                true
+            }
+        });
+        // Notify once the shard locks are released
+        for key in &expired {
+            self.listeners.emit(|l| l.on_expire(key));
+        }
+    }
+
+    /// Check if storing `incoming` bytes exceeds a limit, when replacing an
+    /// entry of `replacing` bytes or adding a new one.
+    fn needs_eviction(&self, incoming: usize, replacing: Option<usize>) -> bool {
+        let full = replacing.is_none()
+            && self
+                .config
+                .max_size
+                .is_some_and(|max_size| self.store.len() >= max_size);
+        let too_large = self.config.max_bytes.is_some_and(|max_bytes| {
+            self.size_bytes().saturating_sub(replacing.unwrap_or(0)) + incoming > max_bytes
+        });
+        full || too_large
+    }
+
+    /// Check if the cache currently exceeds a limit.
+    fn is_over_limits(&self) -> bool {
+        let over_size = self
+            .config
+            .max_size
+            .is_some_and(|max_size| self.store.len() > max_size);
+        let over_bytes = self
+            .config
+            .max_bytes
+            .is_some_and(|max_bytes| self.size_bytes() > max_bytes);
+        over_size || over_bytes
+    }
+
+    /// Evict the lowest-ranked entry according to the eviction policy.
+    ///
+    /// A victim whose TTL already elapsed counts as expired rather than
+    /// evicted. Returns `false` if there was nothing left to evict.
+    fn evict_one(&self) -> bool {
+        let Some((_, key)) = self.order.lock().pop_first() else {
+            return false;
+        };
+        if let Some(entry) = self.remove_entry(&key) {
+            if entry.entry.is_expired_at(self.time.system_time()) {
+                self.counters.expired.fetch_add(1, Ordering::Relaxed);
+                self.listeners.emit(|l| l.on_expire(&key));
+            } else {
+                self.counters.evicted.fetch_add(1, Ordering::Relaxed);
+                self.listeners.emit(|l| l.on_evict(&key));
+            }
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl Cache for InMemoryCache {
+    async fn get<T>(&self, key: &str) -> CacheResult<Option<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.lookup(key)
+    }
+
+    async fn set<T>(&self, key: &str, value: T, ttl: Option<Duration>) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.insert(key, &value, ttl, None, &[], None, None)
+    }
+
     async fn delete(&self, key: &str) -> CacheResult<bool> {
-        Ok(self.store.remove(key).is_some())
+        Ok(self.delete_entry(key))
+    }
+
+    async fn mget<T>(&self, keys: &[&str]) -> CacheResult<Vec<CacheResult<Option<T>>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        Ok(keys.iter().map(|key| self.lookup(key)).collect())
+    }
+
+    async fn mset<T>(&self, entries: Vec<(String, T)>, ttl: Option<Duration>) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        for (key, value) in &entries {
+            self.insert(key, value, ttl, None, &[], None, None)?;
+        }
+        Ok(())
+    }
+
+    async fn mdelete(&self, keys: &[&str]) -> CacheResult<usize> {
+        Ok(keys.iter().filter(|key| self.delete_entry(key)).count())
     }
 
     async fn clear(&self) -> CacheResult<()> {
-        self.store.clear();
+        self.store.retain(|key, entry| {
+            self.unorder(key, entry);
+            self.counters
+                .bytes
+                .fetch_sub(entry.data.len(), Ordering::Relaxed);
+            false
+        });
+        self.tags.clear();
+        self.listeners.emit(|l| l.on_clear());
         Ok(())
     }
 
     async fn exists(&self, key: &str) -> CacheResult<bool> {
         if let Some(entry) = self.store.get(key) {
-            if entry.entry.is_expired() {
+            if entry.entry.is_expired_at(self.time.system_time()) {
                 drop(entry);
-                self.store.remove(key);
+                self.expire(key);
                 Ok(false)
             } else {
                 Ok(true)
@@ -165,6 +733,53 @@ impl Cache for InMemoryCache {
         self.evict_expired();
         Ok(self.store.len())
     }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        let now = self.time.system_time();
+        Ok(self
+            .store
+            .get(key)
+            .and_then(|entry| entry.entry.time_to_expiry_at(now)))
+    }
+}
+
+#[async_trait]
+impl TaggedCache for InMemoryCache {
+    async fn set_tagged<T>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<Duration>,
+        tags: &[&str],
+    ) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        self.insert(key, &value, ttl, None, tags, None, None)
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> CacheResult<Vec<String>> {
+        let Some((_, keys)) = self.tags.remove(tag) else {
+            return Ok(Vec::new());
+        };
+        Ok(keys
+            .into_iter()
+            .filter(|key| self.delete_entry(key))
+            .collect())
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> CacheResult<Vec<String>> {
+        let keys: Vec<String> = self
+            .store
+            .iter()
+            .filter(|entry| entry.key().starts_with(prefix))
+            .map(|entry| entry.key().clone())
+            .collect();
+        Ok(keys
+            .into_iter()
+            .filter(|key| self.delete_entry(key))
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -227,6 +842,23 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[tokio::test]
+    async fn test_ttl_with_simulated_time() {
+        let clock = Arc::new(infra_sim::SimulatedClock::new());
+        let cache = InMemoryCache::with_defaults().with_time_source(clock.clone());
+        cache
+            .set("key1", "value1".to_string(), Some(Duration::from_secs(3600)))
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_secs(3599));
+        assert!(cache.exists("key1").await.unwrap());
+
+        clock.advance(Duration::from_secs(2));
+        assert!(!cache.exists("key1").await.unwrap());
+        assert_eq!(cache.stats().expired, 1);
+    }
+
     #[tokio::test]
     async fn test_max_size() {
         let config = CacheConfig::with_max_size(2);
@@ -251,4 +883,402 @@ mod tests {
         // Cache should have at most 2 items
         assert!(cache.len().await.unwrap() <= 2);
     }
+
+    #[tokio::test]
+    async fn test_lru_eviction() {
+        let cache = InMemoryCache::new(CacheConfig::with_max_size(2));
+
+        cache.set("key1", 1, None).await.unwrap();
+        cache.set("key2", 2, None).await.unwrap();
+        // Reading key1 makes key2 the least recently used
+        cache.get::<i32>("key1").await.unwrap();
+        cache.set("key3", 3, None).await.unwrap();
+
+        assert!(cache.exists("key1").await.unwrap());
+        assert!(!cache.exists("key2").await.unwrap());
+        assert!(cache.exists("key3").await.unwrap());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replace_at_capacity() {
+        let config = CacheConfig::with_max_size(2).with_max_bytes(8);
+        let cache = InMemoryCache::new(config);
+
+        cache.set("key1", 1000, None).await.unwrap();
+        cache.set("key2", 2000, None).await.unwrap();
+        // Replacing a key neither adds an entry nor grows the total size
+        cache.set("key1", 1001, None).await.unwrap();
+
+        assert_eq!(cache.get::<i32>("key1").await.unwrap(), Some(1001));
+        assert_eq!(cache.get::<i32>("key2").await.unwrap(), Some(2000));
+        assert_eq!(cache.stats().evictions, 0);
+        assert_eq!(cache.order.lock().len(), 2);
+
+        // key2 was read last, so key1 goes first
+        cache.set("key3", 3000, None).await.unwrap();
+        assert!(!cache.exists("key1").await.unwrap());
+        assert_eq!(cache.order.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_lfu_eviction() {
+        let config = CacheConfig::with_max_size(2).with_eviction_policy(EvictionPolicy::LFU);
+        let cache = InMemoryCache::new(config);
+
+        cache.set("key1", 1, None).await.unwrap();
+        cache.set("key2", 2, None).await.unwrap();
+        cache.get::<i32>("key1").await.unwrap();
+        cache.get::<i32>("key1").await.unwrap();
+        cache.get::<i32>("key2").await.unwrap();
+        // Updating keeps key1's frequency
+        cache.set("key1", 10, None).await.unwrap();
+        cache.set("key3", 3, None).await.unwrap();
+
+        assert_eq!(cache.get::<i32>("key1").await.unwrap(), Some(10));
+        assert!(!cache.exists("key2").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_max_bytes() {
+        let config = CacheConfig::unlimited()
+            .with_max_bytes(10)
+            .with_eviction_policy(EvictionPolicy::FIFO);
+        let cache = InMemoryCache::new(config);
+
+        // Each value serializes to 4 bytes
+        cache.set("key1", 1000, None).await.unwrap();
+        cache.set("key2", 2000, None).await.unwrap();
+        assert_eq!(cache.size_bytes(), 8);
+        cache.set("key3", 3000, None).await.unwrap();
+
+        assert!(!cache.exists("key1").await.unwrap());
+        assert_eq!(cache.size_bytes(), 8);
+        assert_eq!(cache.len().await.unwrap(), 2);
+
+        let err = cache.set("big", "x".repeat(20), None).await.unwrap_err();
+        assert!(matches!(err, CacheError::EntryTooLarge { size: 22, .. }));
+
+        cache.clear().await.unwrap();
+        assert_eq!(cache.size_bytes(), 0);
+    }
+
+    /// Create a cache on a simulated clock.
+    fn simulated_cache() -> (InMemoryCache, Arc<infra_sim::SimulatedClock>) {
+        let clock = Arc::new(infra_sim::SimulatedClock::new());
+        let cache = InMemoryCache::unlimited().with_time_source(clock.clone());
+        (cache, clock)
+    }
+
+    /// Wait for background refreshes to finish, failing after a second.
+    async fn settle(cache: &InMemoryCache) {
+        let refreshed = async {
+            while !cache.refreshing.is_empty() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), refreshed)
+            .await
+            .expect("background refresh did not finish");
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate() {
+        let (cache, clock) = simulated_cache();
+        let soft_ttl = Duration::from_secs(60);
+
+        // Miss: loaded inline
+        let value = cache
+            .get_or_refresh("key", soft_ttl, None, || async { Ok(1) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        // Fresh: loader not called
+        let value = cache
+            .get_or_refresh("key", soft_ttl, None, || async { Ok(2) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        clock.advance(Duration::from_secs(61));
+
+        // Stale: old value served, refreshed in the background
+        let value = cache
+            .get_or_refresh("key", soft_ttl, None, || async { Ok(3) })
+            .await
+            .unwrap();
+        assert_eq!(value, 1);
+
+        settle(&cache).await;
+        assert_eq!(cache.get::<i32>("key").await.unwrap(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_stale_entry_survives_failed_refresh() {
+        let (cache, clock) = simulated_cache();
+        let soft_ttl = Duration::from_secs(60);
+        cache
+            .set_with_soft_ttl("failing", &"old", soft_ttl, None, &[])
+            .unwrap();
+        cache
+            .set_with_soft_ttl("panicking", &"old", soft_ttl, None, &[])
+            .unwrap();
+        clock.advance(Duration::from_secs(61));
+
+        let value: String = cache
+            .get_or_refresh("failing", soft_ttl, None, || async {
+                Err(CacheError::NetworkError("unavailable".to_string()))
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, "old");
+        let value: String = cache
+            .get_or_refresh("panicking", soft_ttl, None, || async {
+                panic!("loader failed")
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, "old");
+
+        // Neither refresh leaves its key marked as in flight
+        settle(&cache).await;
+        for key in ["failing", "panicking"] {
+            assert_eq!(
+                cache.get::<String>(key).await.unwrap(),
+                Some("old".to_string())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_yields_to_concurrent_writes() {
+        let (cache, clock) = simulated_cache();
+        let soft_ttl = Duration::from_secs(60);
+        for key in ["deleted", "replaced"] {
+            cache
+                .set_with_soft_ttl(key, &1, soft_ttl, None, &[])
+                .unwrap();
+        }
+        clock.advance(Duration::from_secs(61));
+
+        for key in ["deleted", "replaced"] {
+            let value = cache
+                .get_or_refresh(key, soft_ttl, None, || async { Ok(2) })
+                .await
+                .unwrap();
+            assert_eq!(value, 1);
+        }
+        assert!(cache.delete("deleted").await.unwrap());
+        cache.set("replaced", 3, None).await.unwrap();
+
+        settle(&cache).await;
+        assert_eq!(cache.get::<i32>("deleted").await.unwrap(), None);
+        assert_eq!(cache.get::<i32>("replaced").await.unwrap(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_tags() {
+        let (cache, clock) = simulated_cache();
+        let soft_ttl = Duration::from_secs(60);
+        cache
+            .set_with_soft_ttl("key", &1, soft_ttl, None, &["doc:42"])
+            .unwrap();
+        clock.advance(Duration::from_secs(61));
+
+        cache
+            .get_or_refresh("key", soft_ttl, None, || async { Ok(2) })
+            .await
+            .unwrap();
+        settle(&cache).await;
+
+        assert_eq!(cache.get::<i32>("key").await.unwrap(), Some(2));
+        assert_eq!(cache.invalidate_tag("doc:42").await.unwrap(), vec!["key"]);
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        events: parking_lot::Mutex<Vec<String>>,
+    }
+
+    impl CacheListener for Recorder {
+        fn on_insert(&self, key: &str, size: usize) {
+            self.events.lock().push(format!("insert {key} {size}"));
+        }
+
+        fn on_evict(&self, key: &str) {
+            self.events.lock().push(format!("evict {key}"));
+        }
+
+        fn on_expire(&self, key: &str) {
+            self.events.lock().push(format!("expire {key}"));
+        }
+
+        fn on_delete(&self, key: &str) {
+            self.events.lock().push(format!("delete {key}"));
+        }
+
+        fn on_clear(&self) {
+            self.events.lock().push("clear".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_listeners() {
+        let cache = InMemoryCache::new(CacheConfig::with_max_size(1));
+        let recorder = Arc::new(Recorder::default());
+        cache.subscribe(recorder.clone());
+
+        cache
+            .set("short", 1, Some(Duration::from_millis(10)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(cache.get::<i32>("short").await.unwrap(), None);
+
+        cache.set("key1", 10, None).await.unwrap();
+        cache.set("key2", 20, None).await.unwrap();
+        assert_eq!(cache.get::<i32>("key2").await.unwrap(), Some(20));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!((stats.evictions, stats.expired), (1, 1));
+        assert_eq!((stats.entries, stats.bytes), (1, 2));
+        assert_eq!(stats.hit_ratio(), Some(0.5));
+
+        assert_eq!(
+            *recorder.events.lock(),
+            vec![
+                "insert short 1",
+                "expire short",
+                "insert key1 2",
+                "evict key1",
+                "insert key2 2",
+            ]
+        );
+        assert_eq!(
+            cache.eviction_stats(),
+            EvictionStats {
+                evicted: 1,
+                expired: 1
+            }
+        );
+
+        recorder.events.lock().clear();
+        assert!(cache.delete("key2").await.unwrap());
+        assert!(!cache.delete("key2").await.unwrap());
+        cache.set_tagged("key3", 30, None, &["tag"]).await.unwrap();
+        cache.invalidate_tag("tag").await.unwrap();
+        cache.clear().await.unwrap();
+        assert_eq!(
+            *recorder.events.lock(),
+            vec!["delete key2", "insert key3 2", "delete key3", "clear"]
+        );
+    }
+
+    /// Subscribes another recorder on the first insert it sees.
+    struct Subscriber {
+        cache: InMemoryCache,
+        added: Arc<Recorder>,
+    }
+
+    impl CacheListener for Subscriber {
+        fn on_insert(&self, _key: &str, _size: usize) {
+            self.cache.subscribe(self.added.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_from_listener() {
+        let cache = InMemoryCache::unlimited();
+        let added = Arc::new(Recorder::default());
+        cache.subscribe(Arc::new(Subscriber {
+            cache: cache.clone(),
+            added: added.clone(),
+        }));
+
+        cache.set("key1", 1, None).await.unwrap();
+        cache.delete("key1").await.unwrap();
+        assert_eq!(*added.events.lock(), vec!["delete key1"]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_operations() {
+        let cache = InMemoryCache::unlimited();
+
+        let entries = (1..=3).map(|i| (format!("key{i}"), i)).collect();
+        cache.mset(entries, None).await.unwrap();
+        assert_eq!(cache.len().await.unwrap(), 3);
+
+        cache.set("text", "not a number", None).await.unwrap();
+        let values = cache
+            .mget::<i32>(&["key1", "missing", "text", "key3"])
+            .await
+            .unwrap();
+        assert_eq!(values.len(), 4);
+        assert_eq!(values[0].as_ref().unwrap(), &Some(1));
+        assert_eq!(values[1].as_ref().unwrap(), &None);
+        assert!(matches!(
+            values[2],
+            Err(CacheError::DeserializationError(_))
+        ));
+        assert_eq!(values[3].as_ref().unwrap(), &Some(3));
+        cache.delete("text").await.unwrap();
+
+        assert_eq!(cache.mdelete(&["key1", "key2", "missing"]).await.unwrap(), 2);
+        assert_eq!(cache.len().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tag_and_prefix_invalidation() {
+        let cache = InMemoryCache::unlimited();
+        cache
+            .set_tagged("completion:1", "a", None, &["doc:42", "tenant:1"])
+            .await
+            .unwrap();
+        cache
+            .set_tagged("embedding:1", "b", None, &["doc:42"])
+            .await
+            .unwrap();
+        cache
+            .set_tagged("completion:2", "c", None, &["tenant:1"])
+            .await
+            .unwrap();
+        // Re-setting without tags removes the key from its tags
+        cache.set("completion:2", "d", None).await.unwrap();
+
+        let mut purged = cache.invalidate_tag("doc:42").await.unwrap();
+        purged.sort();
+        assert_eq!(purged, vec!["completion:1", "embedding:1"]);
+        assert!(cache.invalidate_tag("tenant:1").await.unwrap().is_empty());
+        assert!(cache.tags.is_empty());
+
+        cache.set("completion:3", "e", None).await.unwrap();
+        assert_eq!(cache.invalidate_prefix("completion:").await.unwrap().len(), 2);
+        assert!(cache.is_empty().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_retagging_keeps_shared_tags() {
+        let cache = InMemoryCache::unlimited();
+        cache.set_tagged("key", 1, None, &["a", "b"]).await.unwrap();
+        cache.set_tagged("key", 2, None, &["b", "c"]).await.unwrap();
+
+        assert!(!cache.tags.contains_key("a"));
+        assert!(cache.invalidate_tag("a").await.unwrap().is_empty());
+        assert_eq!(cache.invalidate_tag("b").await.unwrap(), vec!["key"]);
+        assert!(cache.is_empty().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_stops_when_dropped() {
+        let cache = InMemoryCache::unlimited();
+        let sweeper = cache.spawn_sweeper(Duration::from_millis(5));
+        assert_eq!(Arc::strong_count(&cache.store), 2);
+
+        drop(sweeper);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        // The task released its clone of the cache
+        assert_eq!(Arc::strong_count(&cache.store), 1);
+    }
 }
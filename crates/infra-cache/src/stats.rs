@@ -0,0 +1,79 @@
+//! Cache statistics and event hooks.
+
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// Snapshot of a cache's counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Reads that found a live entry.
+    pub hits: u64,
+    /// Reads that found no entry or an expired one.
+    pub misses: u64,
+    /// Entries removed by the eviction policy to respect the size limits.
+    pub evictions: u64,
+    /// Entries removed because their TTL elapsed.
+    pub expired: u64,
+    /// Number of entries currently stored.
+    pub entries: usize,
+    /// Total size of the stored serialized values.
+    pub bytes: usize,
+}
+
+impl CacheStats {
+    /// Get the fraction of reads that were hits, or `None` before any read.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let reads = self.hits + self.misses;
+        (reads > 0).then(|| self.hits as f64 / reads as f64)
+    }
+}
+
+/// Receives cache events, for example to export metrics or alert on them.
+///
+/// Listeners are called synchronously after the cache has released its
+/// locks, so they may use the cache, including subscribing further
+/// listeners, but should return quickly.
+pub trait CacheListener: Send + Sync {
+    /// Called after a value of `size` serialized bytes is stored under `key`.
+    fn on_insert(&self, _key: &str, _size: usize) {}
+
+    /// Called after `key` is evicted to make room for other entries.
+    fn on_evict(&self, _key: &str) {}
+
+    /// Called after `key` is removed because its TTL elapsed.
+    fn on_expire(&self, _key: &str) {}
+
+    /// Called after `key` is deleted, directly or through an invalidation.
+    fn on_delete(&self, _key: &str) {}
+
+    /// Called after all entries are cleared.
+    fn on_clear(&self) {}
+}
+
+/// Listeners registered with a cache.
+#[derive(Default)]
+pub(crate) struct Listeners(RwLock<Vec<Arc<dyn CacheListener>>>);
+
+impl Listeners {
+    pub(crate) fn add(&self, listener: Arc<dyn CacheListener>) {
+        self.0.write().push(listener);
+    }
+
+    pub(crate) fn emit(&self, event: impl Fn(&dyn CacheListener)) {
+        // Call a snapshot so listeners may subscribe without deadlocking
+        let listeners = self.0.read().clone();
+        for listener in &listeners {
+            event(listener.as_ref());
+        }
+    }
+}
+
+impl std::fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Listeners")
+            .field("count", &self.0.read().len())
+            .finish()
+    }
+}
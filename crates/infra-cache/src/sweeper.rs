@@ -0,0 +1,75 @@
+//! Background removal of expired entries.
+
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::memory::InMemoryCache;
+
+/// Handle to a sweeper started by [`InMemoryCache::spawn_sweeper`].
+///
+/// Dropping the handle also stops the sweeper, after any sweep in progress.
+#[derive(Debug)]
+pub struct SweeperHandle {
+    stop: watch::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl SweeperHandle {
+    /// Stop the sweeper and wait for it to finish.
+    pub async fn shutdown(self) {
+        // Fails only if the task already exited
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+
+    /// Check if the sweeper has stopped.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+pub(crate) fn spawn(cache: InMemoryCache, interval: Duration) -> SweeperHandle {
+    let (stop, mut stopped) = watch::channel(());
+    let task = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => cache.sweep(),
+                // Signalled by shutdown, or an error once the handle is dropped
+                _ = stopped.changed() => break,
+            }
+        }
+    });
+    SweeperHandle { stop, task }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::Cache;
+    use crate::memory::InMemoryCache;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_sweeper_removes_expired_entries() {
+        let cache = InMemoryCache::unlimited();
+        for i in 0..3 {
+            cache
+                .set(&format!("key{i}"), i, Some(Duration::from_millis(10)))
+                .await
+                .unwrap();
+        }
+        cache.set("kept", 0, None).await.unwrap();
+
+        let sweeper = cache.spawn_sweeper(Duration::from_millis(5));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let stats = cache.stats();
+        assert_eq!(stats.expired, 3);
+        assert_eq!(stats.entries, 1);
+
+        sweeper.shutdown().await;
+    }
+}
@@ -0,0 +1,728 @@
+//! Two-level cache composing a fast local tier with a shared one.
+//!
+//! [`TieredCache`] reads from its first tier (L1, typically an
+//! [`InMemoryCache`](crate::InMemoryCache)) and falls back to the second
+//! (L2, typically a remote cache), promoting L2 hits into L1. Writes go to
+//! both tiers, L2 first, so L1 never holds a value L2 did not accept.
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::cache::{Cache, CacheEntry, TaggedCache};
+use crate::error::{CacheError, CacheResult};
+
+/// Default number of keys tracked by the negative cache and by promotion
+/// read counts.
+pub const DEFAULT_MAX_TRACKED_KEYS: usize = 10_000;
+
+/// When values found in L2 are copied into L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PromotionPolicy {
+    /// Promote on every L2 hit.
+    #[default]
+    Always,
+    /// Never promote; L1 only holds values written through this cache.
+    Never,
+    /// Promote once a key has been read from L2 this many times.
+    AfterHits(u32),
+}
+
+/// Hit and miss counts of a [`TieredCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TieredStats {
+    /// Reads served by L1.
+    pub l1_hits: u64,
+    /// Reads served by L2.
+    pub l2_hits: u64,
+    /// Reads that found the key in neither tier.
+    pub misses: u64,
+    /// Reads answered from the negative cache without querying either tier.
+    pub negative_hits: u64,
+    /// L2 hits that could not be copied into L1, for example because the
+    /// value exceeds L1's size limit. The reads still return the L2 value.
+    pub promotion_failures: u64,
+}
+
+#[derive(Debug, Default)]
+struct TieredCounters {
+    l1_hits: AtomicU64,
+    l2_hits: AtomicU64,
+    misses: AtomicU64,
+    negative_hits: AtomicU64,
+    promotion_failures: AtomicU64,
+}
+
+#[cfg(feature = "otel")]
+#[derive(Clone)]
+struct TieredMetrics {
+    l1_hits: std::sync::Arc<infra_otel::Counter>,
+    l2_hits: std::sync::Arc<infra_otel::Counter>,
+    misses: std::sync::Arc<infra_otel::Counter>,
+}
+
+/// Cache with a fast first tier in front of a slower second tier.
+///
+/// # Examples
+///
+/// ```
+/// use infra_cache::{Cache, InMemoryCache, PromotionPolicy, TieredCache};
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let cache = TieredCache::new(InMemoryCache::with_defaults(), InMemoryCache::unlimited())
+///     .with_promotion(PromotionPolicy::AfterHits(2))
+///     .with_promotion_ttl(Duration::from_secs(60))
+///     .with_negative_ttl(Duration::from_secs(5));
+///
+/// cache.set("user:123", "John Doe".to_string(), None).await?;
+/// let name: Option<String> = cache.get("user:123").await?;
+/// assert_eq!(name, Some("John Doe".to_string()));
+/// assert_eq!(cache.stats().l1_hits, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TieredCache<L1, L2> {
+    l1: L1,
+    l2: L2,
+    promotion: PromotionPolicy,
+    promotion_ttl: Option<Duration>,
+    negative_ttl: Option<Duration>,
+    negative: DashMap<String, CacheEntry<()>>,
+    l2_reads: DashMap<String, u32>,
+    max_tracked_keys: usize,
+    counters: TieredCounters,
+    #[cfg(feature = "otel")]
+    metrics: Option<TieredMetrics>,
+}
+
+impl<L1: Cache, L2: Cache> TieredCache<L1, L2> {
+    /// Create a tiered cache promoting every L2 hit, without negative caching.
+    pub fn new(l1: L1, l2: L2) -> Self {
+        Self {
+            l1,
+            l2,
+            promotion: PromotionPolicy::default(),
+            promotion_ttl: None,
+            negative_ttl: None,
+            negative: DashMap::new(),
+            l2_reads: DashMap::new(),
+            max_tracked_keys: DEFAULT_MAX_TRACKED_KEYS,
+            counters: TieredCounters::default(),
+            #[cfg(feature = "otel")]
+            metrics: None,
+        }
+    }
+
+    /// Set when L2 hits are promoted into L1.
+    #[must_use]
+    pub fn with_promotion(mut self, promotion: PromotionPolicy) -> Self {
+        self.promotion = promotion;
+        self
+    }
+
+    /// Set the TTL of promoted L1 entries.
+    ///
+    /// Promoted entries never outlive their L2 entry when L2 reports its
+    /// remaining TTL (see [`Cache::ttl`]); this caps them further. Without
+    /// either, promoted entries use L1's default TTL.
+    #[must_use]
+    pub fn with_promotion_ttl(mut self, ttl: Duration) -> Self {
+        self.promotion_ttl = Some(ttl);
+        self
+    }
+
+    /// Remember keys missing from both tiers for `ttl`.
+    ///
+    /// Reads of such keys return `None` without querying either tier until
+    /// the key is written or `ttl` elapses.
+    #[must_use]
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(ttl);
+        self
+    }
+
+    /// Set how many keys the negative cache and the
+    /// [`AfterHits`](PromotionPolicy::AfterHits) read counts may each track.
+    ///
+    /// When the negative cache is full, its expired entries are dropped, or
+    /// all of them if that frees less than half; when the read counts are
+    /// full, they restart from zero. Defaults to
+    /// [`DEFAULT_MAX_TRACKED_KEYS`].
+    #[must_use]
+    pub fn with_max_tracked_keys(mut self, max: usize) -> Self {
+        self.max_tracked_keys = max;
+        self
+    }
+
+    /// Count hits per tier and misses in `registry`, labelled with `name`.
+    #[cfg(feature = "otel")]
+    #[must_use]
+    pub fn with_metrics(mut self, registry: &infra_otel::MetricsRegistry, name: &str) -> Self {
+        self.metrics = Some(TieredMetrics {
+            l1_hits: registry.counter_with("cache_hits_total", &[("cache", name), ("tier", "l1")]),
+            l2_hits: registry.counter_with("cache_hits_total", &[("cache", name), ("tier", "l2")]),
+            misses: registry.counter_with("cache_misses_total", &[("cache", name)]),
+        });
+        self
+    }
+
+    /// Get the first tier.
+    pub fn l1(&self) -> &L1 {
+        &self.l1
+    }
+
+    /// Get the second tier.
+    pub fn l2(&self) -> &L2 {
+        &self.l2
+    }
+
+    /// Get the hit and miss counts so far.
+    pub fn stats(&self) -> TieredStats {
+        TieredStats {
+            l1_hits: self.counters.l1_hits.load(Ordering::Relaxed),
+            l2_hits: self.counters.l2_hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            negative_hits: self.counters.negative_hits.load(Ordering::Relaxed),
+            promotion_failures: self.counters.promotion_failures.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Check the negative cache, dropping the key if its entry expired.
+    fn is_known_missing(&self, key: &str) -> bool {
+        if let Some(entry) = self.negative.get(key) {
+            if !entry.is_expired() {
+                return true;
+            }
+            drop(entry);
+            self.negative.remove(key);
+        }
+        false
+    }
+
+    fn remember_missing(&self, key: &str) {
+        if let Some(ttl) = self.negative_ttl {
+            if self.negative.len() >= self.max_tracked_keys {
+                self.negative.retain(|_, entry| !entry.is_expired());
+                // Clear outright rather than rescan on every miss
+                if self.negative.len() > self.max_tracked_keys / 2 {
+                    self.negative.clear();
+                }
+            }
+            self.negative
+                .insert(key.to_string(), CacheEntry::with_ttl((), ttl));
+        }
+    }
+
+    fn forget(&self, key: &str) {
+        self.negative.remove(key);
+        self.l2_reads.remove(key);
+    }
+
+    fn record_l1_hit(&self) {
+        self.counters.l1_hits.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "otel")]
+        if let Some(metrics) = &self.metrics {
+            metrics.l1_hits.inc();
+        }
+    }
+
+    fn record_l2_hit(&self) {
+        self.counters.l2_hits.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "otel")]
+        if let Some(metrics) = &self.metrics {
+            metrics.l2_hits.inc();
+        }
+    }
+
+    fn record_miss(&self, key: &str) {
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "otel")]
+        if let Some(metrics) = &self.metrics {
+            metrics.misses.inc();
+        }
+        self.remember_missing(key);
+    }
+
+    /// Decide whether an L2 hit for `key` should be copied into L1.
+    fn should_promote(&self, key: &str) -> bool {
+        match self.promotion {
+            PromotionPolicy::Always => true,
+            PromotionPolicy::Never => false,
+            PromotionPolicy::AfterHits(hits) => {
+                if self.l2_reads.len() >= self.max_tracked_keys && !self.l2_reads.contains_key(key)
+                {
+                    self.l2_reads.clear();
+                }
+                let mut reads = self.l2_reads.entry(key.to_string()).or_insert(0);
+                *reads += 1;
+                if *reads >= hits {
+                    drop(reads);
+                    self.l2_reads.remove(key);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Get the TTL of an entry promoted into L1: the promotion TTL, capped
+    /// by the time the L2 entry has left.
+    async fn promoted_ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        let remaining = self.l2.ttl(key).await?;
+        Ok(match (self.promotion_ttl, remaining) {
+            (Some(ttl), Some(remaining)) => Some(ttl.min(remaining)),
+            (ttl, remaining) => ttl.or(remaining),
+        })
+    }
+
+    /// Copy an L2 value into L1, counting rather than returning failures,
+    /// as the read that found it in L2 still succeeds.
+    async fn promote(&self, key: &str, value: serde_json::Value) {
+        let promoted = match self.promoted_ttl(key).await {
+            Ok(ttl) => self.l1.set(key, value, ttl).await,
+            Err(e) => Err(e),
+        };
+        if promoted.is_err() {
+            self.counters
+                .promotion_failures
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn from_json<T: DeserializeOwned>(value: serde_json::Value) -> CacheResult<T> {
+    serde_json::from_value(value)
+        .map_err(|e| CacheError::DeserializationError(format!("Failed to deserialize: {e}")))
+}
+
+impl<L1, L2> std::fmt::Debug for TieredCache<L1, L2>
+where
+    L1: std::fmt::Debug,
+    L2: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TieredCache")
+            .field("l1", &self.l1)
+            .field("l2", &self.l2)
+            .field("promotion", &self.promotion)
+            .field("promotion_ttl", &self.promotion_ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<L1: Cache, L2: Cache> Cache for TieredCache<L1, L2> {
+    async fn get<T>(&self, key: &str) -> CacheResult<Option<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        if self.is_known_missing(key) {
+            self.counters.negative_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+
+        if let Some(value) = self.l1.get::<T>(key).await? {
+            self.record_l1_hit();
+            return Ok(Some(value));
+        }
+
+        // Read L2 as JSON so the same value can be promoted and returned.
+        let Some(value) = self.l2.get::<serde_json::Value>(key).await? else {
+            self.record_miss(key);
+            return Ok(None);
+        };
+        self.record_l2_hit();
+
+        if self.should_promote(key) {
+            self.promote(key, value.clone()).await;
+        }
+
+        from_json(value).map(Some)
+    }
+
+    async fn mget<T>(&self, keys: &[&str]) -> CacheResult<Vec<CacheResult<Option<T>>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let mut values: Vec<CacheResult<Option<T>>> = std::iter::repeat_with(|| Ok(None))
+            .take(keys.len())
+            .collect();
+
+        let candidates: Vec<usize> = (0..keys.len())
+            .filter(|&i| {
+                let missing = self.is_known_missing(keys[i]);
+                if missing {
+                    self.counters.negative_hits.fetch_add(1, Ordering::Relaxed);
+                }
+                !missing
+            })
+            .collect();
+        let l1_keys: Vec<&str> = candidates.iter().map(|&i| keys[i]).collect();
+        let mut pending = Vec::new();
+        for (i, value) in candidates
+            .into_iter()
+            .zip(self.l1.mget::<T>(&l1_keys).await?)
+        {
+            match value {
+                Ok(None) => pending.push(i),
+                Ok(Some(value)) => {
+                    self.record_l1_hit();
+                    values[i] = Ok(Some(value));
+                }
+                Err(e) => values[i] = Err(e),
+            }
+        }
+        if pending.is_empty() {
+            return Ok(values);
+        }
+
+        let l2_keys: Vec<&str> = pending.iter().map(|&i| keys[i]).collect();
+        let l2_values = self.l2.mget::<serde_json::Value>(&l2_keys).await?;
+        let mut promoted = Vec::new();
+        for (i, value) in pending.into_iter().zip(l2_values) {
+            let value = match value {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    self.record_miss(keys[i]);
+                    continue;
+                }
+                Err(e) => {
+                    values[i] = Err(e);
+                    continue;
+                }
+            };
+            self.record_l2_hit();
+            if self.should_promote(keys[i]) {
+                promoted.push((keys[i].to_string(), value.clone()));
+            }
+            values[i] = from_json(value).map(Some);
+        }
+        for (key, value) in promoted {
+            self.promote(&key, value).await;
+        }
+        Ok(values)
+    }
+
+    async fn set<T>(&self, key: &str, value: T, ttl: Option<Duration>) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let value = serde_json::to_value(value)?;
+        self.l2.set(key, value.clone(), ttl).await?;
+        self.l1.set(key, value, ttl).await?;
+        self.forget(key);
+        Ok(())
+    }
+
+    async fn mset<T>(&self, entries: Vec<(String, T)>, ttl: Option<Duration>) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| Ok((key, serde_json::to_value(value)?)))
+            .collect::<CacheResult<Vec<_>>>()?;
+        self.l2.mset(entries.clone(), ttl).await?;
+        for (key, _) in &entries {
+            self.forget(key);
+        }
+        self.l1.mset(entries, ttl).await
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<bool> {
+        let in_l2 = self.l2.delete(key).await?;
+        let in_l1 = self.l1.delete(key).await?;
+        self.forget(key);
+        Ok(in_l1 || in_l2)
+    }
+
+    async fn clear(&self) -> CacheResult<()> {
+        self.l2.clear().await?;
+        self.l1.clear().await?;
+        self.negative.clear();
+        self.l2_reads.clear();
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        if self.is_known_missing(key) {
+            return Ok(false);
+        }
+        Ok(self.l1.exists(key).await? || self.l2.exists(key).await?)
+    }
+
+    /// Returns the number of entries in L2, which holds every written value.
+    async fn len(&self) -> CacheResult<usize> {
+        self.l2.len().await
+    }
+
+    /// Returns the remaining TTL in L2, which holds every written value.
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        self.l2.ttl(key).await
+    }
+}
+
+/// Tags are kept in both tiers. Entries promoted into L1 are untagged there,
+/// so keys invalidated in L2 are also deleted from L1.
+#[async_trait]
+impl<L1: TaggedCache, L2: TaggedCache> TaggedCache for TieredCache<L1, L2> {
+    async fn set_tagged<T>(
+        &self,
+        key: &str,
+        value: T,
+        ttl: Option<Duration>,
+        tags: &[&str],
+    ) -> CacheResult<()>
+    where
+        T: Serialize + Send + Sync + 'static,
+    {
+        let value = serde_json::to_value(value)?;
+        self.l2.set_tagged(key, value.clone(), ttl, tags).await?;
+        self.l1.set_tagged(key, value, ttl, tags).await?;
+        self.forget(key);
+        Ok(())
+    }
+
+    async fn invalidate_tag(&self, tag: &str) -> CacheResult<Vec<String>> {
+        let keys = self.l2.invalidate_tag(tag).await?;
+        self.l1.invalidate_tag(tag).await?;
+        let key_refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+        self.l1.mdelete(&key_refs).await?;
+        for key in &keys {
+            self.forget(key);
+        }
+        Ok(keys)
+    }
+
+    async fn invalidate_prefix(&self, prefix: &str) -> CacheResult<Vec<String>> {
+        let keys = self.l2.invalidate_prefix(prefix).await?;
+        self.l1.invalidate_prefix(prefix).await?;
+        self.negative.retain(|key, _| !key.starts_with(prefix));
+        self.l2_reads.retain(|key, _| !key.starts_with(prefix));
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CacheConfig;
+    use crate::memory::InMemoryCache;
+
+    fn tiers() -> (InMemoryCache, InMemoryCache) {
+        (
+            InMemoryCache::new(CacheConfig::unlimited()),
+            InMemoryCache::new(CacheConfig::unlimited()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_write_through_and_promotion() {
+        let (l1, l2) = tiers();
+        let cache = TieredCache::new(l1.clone(), l2.clone());
+
+        cache.set("key1", "value1".to_string(), None).await.unwrap();
+        assert!(l1.exists("key1").await.unwrap());
+        assert!(l2.exists("key1").await.unwrap());
+
+        // Only in L2: served from L2 and promoted
+        l2.set("key2", 42u32, None).await.unwrap();
+        assert_eq!(cache.get::<u32>("key2").await.unwrap(), Some(42));
+        assert_eq!(l1.get::<u32>("key2").await.unwrap(), Some(42));
+        assert_eq!(cache.get::<u32>("key2").await.unwrap(), Some(42));
+
+        let stats = cache.stats();
+        assert_eq!((stats.l1_hits, stats.l2_hits), (1, 1));
+
+        assert!(cache.delete("key1").await.unwrap());
+        assert!(!l2.exists("key1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_failed_promotion_still_returns_l2_value() {
+        let l1 = InMemoryCache::new(CacheConfig::unlimited().with_max_bytes(16));
+        let l2 = InMemoryCache::new(CacheConfig::unlimited());
+        let cache = TieredCache::new(l1.clone(), l2.clone());
+
+        let large = "x".repeat(64);
+        l2.set("large", large.clone(), None).await.unwrap();
+        l2.set("small", 1u32, None).await.unwrap();
+
+        assert_eq!(
+            cache.get::<String>("large").await.unwrap(),
+            Some(large.clone())
+        );
+        let values = cache
+            .mget::<serde_json::Value>(&["large", "small"])
+            .await
+            .unwrap();
+        assert_eq!(values[0].as_ref().unwrap(), &Some(serde_json::json!(large)));
+        assert_eq!(values[1].as_ref().unwrap(), &Some(serde_json::json!(1)));
+
+        assert!(!l1.exists("large").await.unwrap());
+        assert!(l1.exists("small").await.unwrap());
+        assert_eq!(cache.stats().promotion_failures, 2);
+    }
+
+    #[tokio::test]
+    async fn test_promotion_after_hits() {
+        let (l1, l2) = tiers();
+        let cache =
+            TieredCache::new(l1.clone(), l2.clone()).with_promotion(PromotionPolicy::AfterHits(2));
+
+        l2.set("key", "value".to_string(), None).await.unwrap();
+        cache.get::<String>("key").await.unwrap();
+        assert!(!l1.exists("key").await.unwrap());
+        cache.get::<String>("key").await.unwrap();
+        assert!(l1.exists("key").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_negative_caching() {
+        let (l1, l2) = tiers();
+        let cache = TieredCache::new(l1, l2.clone()).with_negative_ttl(Duration::from_secs(60));
+
+        assert_eq!(cache.get::<String>("missing").await.unwrap(), None);
+        // Written behind the cache's back: still reported missing
+        l2.set("missing", "value".to_string(), None).await.unwrap();
+        assert_eq!(cache.get::<String>("missing").await.unwrap(), None);
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(cache.stats().negative_hits, 1);
+
+        // Writing through the cache clears the negative entry
+        cache
+            .set("missing", "value".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.get::<String>("missing").await.unwrap(),
+            Some("value".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_promotion_keeps_l2_ttl() {
+        let (l1, l2) = tiers();
+        let cache =
+            TieredCache::new(l1.clone(), l2.clone()).with_promotion_ttl(Duration::from_secs(60));
+
+        l2.set("short", 1, Some(Duration::from_secs(10)))
+            .await
+            .unwrap();
+        l2.set("long", 2, Some(Duration::from_secs(3600)))
+            .await
+            .unwrap();
+        l2.set("forever", 3, None).await.unwrap();
+        cache.get::<i32>("short").await.unwrap();
+        let values = cache.mget::<i32>(&["long", "forever"]).await.unwrap();
+        assert_eq!(values.len(), 2);
+
+        let ttl = |key| {
+            let l1 = l1.clone();
+            async move { l1.ttl(key).await.unwrap().unwrap() }
+        };
+        assert!(ttl("short").await <= Duration::from_secs(10));
+        assert!(ttl("long").await > Duration::from_secs(10));
+        assert!(ttl("long").await <= Duration::from_secs(60));
+        assert!(ttl("forever").await <= Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_tracking_is_bounded() {
+        let (l1, l2) = tiers();
+        let cache = TieredCache::new(l1, l2.clone())
+            .with_promotion(PromotionPolicy::AfterHits(3))
+            .with_negative_ttl(Duration::from_secs(60))
+            .with_max_tracked_keys(4);
+
+        for i in 0..10 {
+            let key = format!("key{i}");
+            l2.set(&key, i, None).await.unwrap();
+            cache.get::<i32>(&key).await.unwrap();
+            cache.get::<i32>(&format!("missing{i}")).await.unwrap();
+            assert!(cache.l2_reads.len() <= 4);
+            assert!(cache.negative.len() <= 4);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_operations() {
+        let (l1, l2) = tiers();
+        let cache =
+            TieredCache::new(l1.clone(), l2.clone()).with_negative_ttl(Duration::from_secs(60));
+
+        cache
+            .mset(vec![("key1".to_string(), 1)], None)
+            .await
+            .unwrap();
+        l2.set("key2", 2, None).await.unwrap();
+
+        let values: Vec<Option<i32>> = cache
+            .mget(&["key1", "key2", "missing"])
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<CacheResult<_>>()
+            .unwrap();
+        assert_eq!(values, vec![Some(1), Some(2), None]);
+        assert_eq!(l1.get::<i32>("key2").await.unwrap(), Some(2));
+
+        let values = cache.mget::<i32>(&["missing"]).await.unwrap();
+        assert!(matches!(values[..], [Ok(None)]));
+        let stats = cache.stats();
+        assert_eq!((stats.l1_hits, stats.l2_hits), (1, 1));
+        assert_eq!((stats.misses, stats.negative_hits), (1, 1));
+
+        assert_eq!(cache.mdelete(&["key1", "key2"]).await.unwrap(), 2);
+        assert!(cache.is_empty().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_tag_purges_promoted_entries() {
+        let (l1, l2) = tiers();
+        let cache = TieredCache::new(l1.clone(), l2.clone());
+
+        l2.set_tagged("completion:1", "a", None, &["doc:42"])
+            .await
+            .unwrap();
+        cache.get::<String>("completion:1").await.unwrap();
+        assert!(l1.exists("completion:1").await.unwrap());
+
+        let purged = cache.invalidate_tag("doc:42").await.unwrap();
+        assert_eq!(purged, vec!["completion:1"]);
+        assert!(!l1.exists("completion:1").await.unwrap());
+        assert!(!cache.exists("completion:1").await.unwrap());
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn test_tier_metrics() {
+        let registry = infra_otel::MetricsRegistry::new();
+        let (l1, l2) = tiers();
+        let cache = TieredCache::new(l1, l2.clone()).with_metrics(&registry, "users");
+
+        l2.set("key", 1u8, None).await.unwrap();
+        cache.get::<u8>("key").await.unwrap();
+        cache.get::<u8>("key").await.unwrap();
+        cache.get::<u8>("other").await.unwrap();
+
+        let hits = |tier| {
+            registry
+                .counter_with("cache_hits_total", &[("cache", "users"), ("tier", tier)])
+                .get()
+        };
+        assert_eq!(hits("l1"), 1);
+        assert_eq!(hits("l2"), 1);
+        assert_eq!(
+            registry
+                .counter_with("cache_misses_total", &[("cache", "users")])
+                .get(),
+            1
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Typed cache keeping deserialized values alongside their bytes.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::CacheResult;
+use crate::memory::InMemoryCache;
+
+/// Cache of values of one type that skips deserialization on reads.
+///
+/// Values are serialized once when set, so byte limits and readers using
+/// [`Cache::get`](crate::Cache::get) on the underlying [`InMemoryCache`] keep working, and are
+/// also kept as an [`Arc<T>`] that [`get`](Self::get) returns directly.
+/// Entries set through the plain [`Cache`](crate::Cache) API are deserialized as usual.
+///
+/// # Examples
+///
+/// ```
+/// use infra_cache::{InMemoryCache, TypedCache};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let cache: TypedCache<Vec<String>> = TypedCache::new(InMemoryCache::with_defaults());
+///
+/// cache.set("tokens", vec!["Hello".to_string(), "world".to_string()], None)?;
+/// let tokens = cache.get("tokens")?.unwrap();
+/// assert_eq!(tokens.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TypedCache<T> {
+    cache: InMemoryCache,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedCache<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// Create a typed view of `cache`.
+    #[must_use]
+    pub fn new(cache: InMemoryCache) -> Self {
+        Self {
+            cache,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the underlying cache.
+    #[must_use]
+    pub fn inner(&self) -> &InMemoryCache {
+        &self.cache
+    }
+
+    /// Get a value, sharing the stored instance when there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry has to be deserialized and does not
+    /// hold a `T`.
+    pub fn get(&self, key: &str) -> CacheResult<Option<Arc<T>>> {
+        self.cache.lookup_shared(key)
+    }
+
+    /// Set a value with optional TTL, serializing it once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value cannot be serialized or exceeds the
+    /// cache's byte limit.
+    pub fn set(
+        &self,
+        key: &str,
+        value: impl Into<Arc<T>>,
+        ttl: Option<Duration>,
+    ) -> CacheResult<()> {
+        self.cache.insert_shared(key, &value.into(), ttl)
+    }
+
+    /// Delete a value, returning `true` if the key existed.
+    pub fn delete(&self, key: &str) -> bool {
+        self.cache.delete_entry(key)
+    }
+}
+
+impl<T> Clone for TypedCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for TypedCache<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedCache")
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::Cache;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Completion {
+        model: String,
+        text: String,
+    }
+
+    fn completion() -> Completion {
+        Completion {
+            model: "gpt-4".to_string(),
+            text: "Hello".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shares_stored_instance() {
+        let cache = TypedCache::new(InMemoryCache::unlimited());
+        let value = Arc::new(completion());
+        cache.set("key", value.clone(), None).unwrap();
+
+        let cached = cache.get("key").unwrap().unwrap();
+        assert!(Arc::ptr_eq(&cached, &value));
+
+        // The serialized form stays readable through the plain API
+        let plain: Option<Completion> = cache.inner().get("key").await.unwrap();
+        assert_eq!(plain, Some(completion()));
+        assert!(cache.inner().size_bytes() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_reads_plain_entries() {
+        let inner = InMemoryCache::unlimited();
+        inner.set("key", completion(), None).await.unwrap();
+
+        let cache: TypedCache<Completion> = TypedCache::new(inner);
+        assert_eq!(*cache.get("key").unwrap().unwrap(), completion());
+        assert!(cache.get("missing").unwrap().is_none());
+        assert!(cache.delete("key"));
+    }
+}
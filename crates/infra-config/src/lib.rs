@@ -55,11 +55,13 @@ pub fn parse<T: DeserializeOwned>(content: &str, format: ConfigFormat) -> InfraR
             key: None,
             message: format!("JSON parse error: {e}"),
             context: None,
+            source: None,
         }),
         ConfigFormat::Toml => toml::from_str(content).map_err(|e| InfraError::Config {
             key: None,
             message: format!("TOML parse error: {e}"),
             context: None,
+            source: None,
         }),
     }
 }
@@ -71,11 +73,13 @@ pub fn serialize<T: Serialize>(config: &T, format: ConfigFormat) -> InfraResult<
             key: None,
             message: format!("JSON serialize error: {e}"),
             context: None,
+            source: None,
         }),
         ConfigFormat::Toml => toml::to_string_pretty(config).map_err(|e| InfraError::Config {
             key: None,
             message: format!("TOML serialize error: {e}"),
             context: None,
+            source: None,
         }),
     }
 }
@@ -65,6 +65,7 @@ impl ConfigLoader {
             key: None,
             message: format!("Configuration deserialization error: {e}"),
             context: None,
+            source: None,
         })
     }
 
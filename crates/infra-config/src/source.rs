@@ -38,6 +38,7 @@ impl ConfigSource for FileSource {
             key: None,
             message: format!("Failed to read config file '{}': {e}", self.path.display()),
             context: None,
+            source: None,
         })?;
 
         let ext = self.path.extension().and_then(|e| e.to_str()).unwrap_or("");
@@ -47,12 +48,14 @@ impl ConfigSource for FileSource {
                 key: None,
                 message: format!("JSON parse error in '{}': {e}", self.path.display()),
                 context: None,
+                source: None,
             })?,
             "toml" => {
                 let toml_value: toml::Value = toml::from_str(&content).map_err(|e| InfraError::Config {
                     key: None,
                     message: format!("TOML parse error in '{}': {e}", self.path.display()),
                     context: None,
+                    source: None,
                 })?;
                 toml_to_json(toml_value)
             }
@@ -61,6 +64,7 @@ impl ConfigSource for FileSource {
                     key: None,
                     message: format!("Unsupported config format '{}' in file '{}'", ext, self.path.display()),
                     context: None,
+                    source: None,
                 });
             }
         };
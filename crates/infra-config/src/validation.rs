@@ -111,6 +111,7 @@ impl ConfigValidator {
                 expected: None,
                 actual: None,
                 context: None,
+                source: None,
             })
         }
     }
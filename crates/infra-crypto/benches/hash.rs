@@ -0,0 +1,51 @@
+//! Measures one-shot and streamed hashing throughput, comparing SHA-256,
+//! Blake3 and HMAC-SHA256 across input sizes. Blake3 inputs of 128 KiB and
+//! more are hashed on several threads with the `parallel` feature.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use infra_crypto::{Blake3Hasher, Hasher, Hmac, Mac, Sha256Hasher};
+use std::hint::black_box;
+
+const SIZES: [usize; 3] = [1024, 1024 * 1024, 16 * 1024 * 1024];
+
+fn one_shot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash");
+    let hmac = Hmac::sha256(b"benchmark key");
+    for size in SIZES {
+        let data = vec![0x5a; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("sha256", size), &data, |b, data| {
+            b.iter(|| Sha256Hasher::new().hash(black_box(data)));
+        });
+        group.bench_with_input(BenchmarkId::new("blake3", size), &data, |b, data| {
+            b.iter(|| Blake3Hasher::new().hash(black_box(data)));
+        });
+        group.bench_with_input(BenchmarkId::new("hmac_sha256", size), &data, |b, data| {
+            b.iter(|| hmac.tag(black_box(data)));
+        });
+    }
+    group.finish();
+}
+
+fn streamed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hash_stream");
+    let size = 16 * 1024 * 1024;
+    let data = vec![0x5a; size];
+    group.throughput(Throughput::Bytes(size as u64));
+    let hasher = Blake3Hasher::new();
+    for chunk in [64 * 1024, 1024 * 1024] {
+        group.bench_with_input(BenchmarkId::new("blake3", chunk), &chunk, |b, &chunk| {
+            b.iter(|| {
+                let mut stream = hasher.start();
+                for piece in data.chunks(chunk) {
+                    stream.update(black_box(piece));
+                }
+                stream.finalize()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, one_shot, streamed);
+criterion_main!(benches);
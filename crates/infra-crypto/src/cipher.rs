@@ -1,11 +1,16 @@
 //! Symmetric encryption implementations.
 
+use crate::secret::SecretBytes;
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
-use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use infra_errors::{CryptoOperation, ErrorSource, InfraError, InfraResult};
 use rand::RngCore;
+use zeroize::Zeroize;
+
+/// Length of AES-256 keys
+const KEY_LEN: usize = 32;
 
 /// Trait for symmetric ciphers
 pub trait Cipher: Send + Sync {
@@ -17,84 +22,109 @@ pub trait Cipher: Send + Sync {
 }
 
 /// AES-256-GCM cipher
+///
+/// The key is held as [`SecretBytes`], so it is wiped from memory on drop.
+#[derive(Clone)]
 pub struct Aes256GcmCipher {
-    key: [u8; 32],
+    key: SecretBytes,
 }
 
 impl Aes256GcmCipher {
     /// Create a new cipher with the given key
     #[must_use]
-    pub fn new(key: [u8; 32]) -> Self {
-        Self { key }
+    pub fn new(mut key: [u8; 32]) -> Self {
+        let cipher = Self {
+            key: SecretBytes::from(key.as_slice()),
+        };
+        key.zeroize();
+        cipher
     }
 
     /// Generate a new cipher with a random key
     pub fn generate() -> InfraResult<Self> {
-        let mut key = [0u8; 32];
-        rand::thread_rng().fill_bytes(&mut key);
-        Ok(Self { key })
+        Ok(Self {
+            key: SecretBytes::random(KEY_LEN),
+        })
     }
 
     /// Create from a byte slice (must be 32 bytes)
     pub fn from_bytes(bytes: &[u8]) -> InfraResult<Self> {
-        if bytes.len() != 32 {
+        if bytes.len() != KEY_LEN {
             return Err(InfraError::Crypto {
                 operation: CryptoOperation::KeyGeneration,
                 message: format!("Key must be 32 bytes, got {}", bytes.len()),
                 context: None,
+                source: None,
             });
         }
-        let mut key = [0u8; 32];
-        key.copy_from_slice(bytes);
-        Ok(Self { key })
+        Ok(Self {
+            key: SecretBytes::from(bytes),
+        })
     }
 
     /// Derive a key from a passphrase using Argon2
     pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> InfraResult<Self> {
         use argon2::Argon2;
 
-        let mut key = [0u8; 32];
+        let mut key = SecretBytes::new(vec![0; KEY_LEN]);
         Argon2::default()
-            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .hash_password_into(passphrase.as_bytes(), salt, key.expose_secret_mut())
             .map_err(|e| InfraError::Crypto {
                 operation: CryptoOperation::KeyDerivation,
                 message: e.to_string(),
                 context: None,
+                source: None,
             })?;
 
         Ok(Self { key })
     }
 
+    /// Create from a secret key (must be 32 bytes)
+    pub fn from_secret(secret: &SecretBytes) -> InfraResult<Self> {
+        Self::from_bytes(secret.expose_secret())
+    }
+
     /// Get the key (use carefully)
     #[must_use]
+    #[allow(clippy::missing_panics_doc)] // keys are checked to be 32 bytes
     pub fn key(&self) -> &[u8; 32] {
-        &self.key
+        self.key
+            .expose_secret()
+            .try_into()
+            .expect("AES-256 keys are 32 bytes")
     }
 
     /// Export key as base64
     #[must_use]
     pub fn key_base64(&self) -> String {
-        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &self.key)
+        base64::Engine::encode(&base64::engine::general_purpose::STANDARD, self.key())
     }
 
     /// Import key from base64
     pub fn from_base64(encoded: &str) -> InfraResult<Self> {
-        let key_bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
-            .map_err(|e| InfraError::Crypto {
-                operation: CryptoOperation::KeyGeneration,
-                message: format!("Invalid base64: {e}"),
-                context: None,
-            })?;
-        Self::from_bytes(&key_bytes)
+        let key = SecretBytes::from_base64(encoded).map_err(|e| InfraError::Crypto {
+            operation: CryptoOperation::KeyGeneration,
+            message: e.to_string(),
+            context: None,
+            source: Some(ErrorSource::new(e)),
+        })?;
+        Self::from_secret(&key)
     }
 }
 
-impl Cipher for Aes256GcmCipher {
-    fn encrypt(&self, plaintext: &[u8]) -> InfraResult<Vec<u8>> {
-        let cipher = Aes256Gcm::new_from_slice(&self.key).map_err(|e| InfraError::Crypto {
-            operation: CryptoOperation::Encrypt,
-            message: e.to_string(),
-            context: None,
+impl Aes256GcmCipher {
+    /// Encrypt plaintext, authenticating `aad` along with it
+    ///
+    /// The same associated data must be passed to
+    /// [`decrypt_with_aad`](Self::decrypt_with_aad).
+    pub fn encrypt_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> InfraResult<Vec<u8>> {
+        let cipher = Aes256Gcm::new_from_slice(self.key.expose_secret()).map_err(|e| {
+            InfraError::Crypto {
+                operation: CryptoOperation::Encrypt,
+                message: e.to_string(),
+                context: None,
+                source: Some(ErrorSource::new(e)),
+            }
         })?;
 
         // Generate random nonce
@@ -103,11 +133,18 @@ impl Cipher for Aes256GcmCipher {
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         // Encrypt
-        let ciphertext = cipher.encrypt(nonce, plaintext).map_err(|e| InfraError::Crypto {
-            operation: CryptoOperation::Encrypt,
-            message: e.to_string(),
-            context: None,
-        })?;
+        let payload = Payload {
+            msg: plaintext,
+            aad,
+        };
+        let ciphertext = cipher
+            .encrypt(nonce, payload)
+            .map_err(|e| InfraError::Crypto {
+                operation: CryptoOperation::Encrypt,
+                message: e.to_string(),
+                context: None,
+                source: Some(ErrorSource::new(e)),
+            })?;
 
         // Prepend nonce to ciphertext
         let mut result = nonce_bytes.to_vec();
@@ -116,44 +153,57 @@ impl Cipher for Aes256GcmCipher {
         Ok(result)
     }
 
-    fn decrypt(&self, ciphertext: &[u8]) -> InfraResult<Vec<u8>> {
+    /// Decrypt ciphertext produced by
+    /// [`encrypt_with_aad`](Self::encrypt_with_aad) with the same `aad`
+    pub fn decrypt_with_aad(&self, ciphertext: &[u8], aad: &[u8]) -> InfraResult<Vec<u8>> {
         if ciphertext.len() < 12 {
             return Err(InfraError::Crypto {
                 operation: CryptoOperation::Decrypt,
                 message: "Ciphertext too short (missing nonce)".to_string(),
                 context: None,
+                source: None,
             });
         }
 
-        let cipher = Aes256Gcm::new_from_slice(&self.key).map_err(|e| InfraError::Crypto {
-            operation: CryptoOperation::Decrypt,
-            message: e.to_string(),
-            context: None,
+        let cipher = Aes256Gcm::new_from_slice(self.key.expose_secret()).map_err(|e| {
+            InfraError::Crypto {
+                operation: CryptoOperation::Decrypt,
+                message: e.to_string(),
+                context: None,
+                source: Some(ErrorSource::new(e)),
+            }
         })?;
 
         let nonce = Nonce::from_slice(&ciphertext[..12]);
 
+        let payload = Payload {
+            msg: &ciphertext[12..],
+            aad,
+        };
         cipher
-            .decrypt(nonce, &ciphertext[12..])
+            .decrypt(nonce, payload)
             .map_err(|e| InfraError::Crypto {
                 operation: CryptoOperation::Decrypt,
                 message: e.to_string(),
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })
     }
 }
 
-impl Clone for Aes256GcmCipher {
-    fn clone(&self) -> Self {
-        Self { key: self.key }
+impl Cipher for Aes256GcmCipher {
+    fn encrypt(&self, plaintext: &[u8]) -> InfraResult<Vec<u8>> {
+        self.encrypt_with_aad(plaintext, &[])
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> InfraResult<Vec<u8>> {
+        self.decrypt_with_aad(ciphertext, &[])
     }
 }
 
-// Implement zeroize on drop for security
-impl Drop for Aes256GcmCipher {
-    fn drop(&mut self) {
-        // Zero out the key
-        self.key.iter_mut().for_each(|b| *b = 0);
+impl std::fmt::Debug for Aes256GcmCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Aes256GcmCipher([REDACTED])")
     }
 }
 
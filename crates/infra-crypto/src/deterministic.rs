@@ -0,0 +1,161 @@
+//! Deterministic authenticated encryption.
+
+use crate::secret::{encode_base64url, SecretBytes};
+use aes_siv::siv::Aes256Siv;
+use aes_siv::KeyInit;
+use infra_errors::{CryptoOperation, ErrorSource, InfraError, InfraResult};
+
+/// Context deriving the S2V (MAC) half of the SIV key from the cipher key
+const MAC_KEY_CONTEXT: &str = "infra-crypto 2024-01-01 deterministic siv";
+
+/// Context deriving the CTR (encryption) half of the SIV key from the
+/// cipher key
+const ENC_KEY_CONTEXT: &str = "infra-crypto 2024-01-01 deterministic enc";
+
+/// Deterministic AEAD (AES-SIV, RFC 5297), for encrypted values that must
+/// stay searchable by equality
+///
+/// The 128-bit synthetic IV is computed from the associated data and
+/// plaintext with AES-CMAC (S2V), prefixes the ciphertext and is checked
+/// again on decryption. Encrypting the same plaintext with the same key and
+/// associated data therefore always gives the same ciphertext, which can
+/// serve as a cache or database lookup key. Unlike a nonce-based AEAD with a
+/// derived nonce, AES-SIV is designed for this: equal inputs are the only
+/// thing it reveals.
+///
+/// That is also the trade-off: anyone seeing ciphertexts learns which
+/// values are equal, and how often each occurs, so low-cardinality values
+/// (booleans, countries, statuses) are effectively exposed by frequency
+/// analysis. Use it only where equality lookups are required, with
+/// high-entropy values such as emails or API key ids, and bind each use to
+/// its field with the associated data (say `b"users.email"`) so that equal
+/// values in different fields do not match; for that reason there is no
+/// [`Cipher`](crate::Cipher) implementation without associated data.
+/// Everywhere else, use [`Aes256GcmCipher`](crate::Aes256GcmCipher), whose
+/// random nonces hide equality.
+pub struct DeterministicCipher {
+    key: SecretBytes,
+}
+
+impl DeterministicCipher {
+    /// Create a cipher from a 32-byte key
+    ///
+    /// The two 32-byte halves of the AES-256-SIV key are derived from it.
+    pub fn new(key: &SecretBytes) -> InfraResult<Self> {
+        if key.len() != 32 {
+            return Err(InfraError::Crypto {
+                operation: CryptoOperation::KeyGeneration,
+                message: format!("Key must be 32 bytes, got {}", key.len()),
+                context: None,
+                source: None,
+            });
+        }
+        let mut siv_key = blake3::derive_key(MAC_KEY_CONTEXT, key.expose_secret()).to_vec();
+        siv_key.extend(blake3::derive_key(ENC_KEY_CONTEXT, key.expose_secret()));
+        Ok(Self {
+            key: SecretBytes::new(siv_key),
+        })
+    }
+
+    /// Create a cipher with a random key
+    pub fn generate() -> InfraResult<Self> {
+        Self::new(&SecretBytes::random(32))
+    }
+
+    /// Encrypt plaintext bound to associated data
+    pub fn encrypt_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> InfraResult<Vec<u8>> {
+        self.siv()?
+            .encrypt([aad], plaintext)
+            .map_err(|e| error(CryptoOperation::Encrypt, &e.to_string(), e))
+    }
+
+    /// Decrypt a ciphertext encrypted with the same associated data
+    pub fn decrypt_with_aad(&self, ciphertext: &[u8], aad: &[u8]) -> InfraResult<Vec<u8>> {
+        self.siv()?
+            .decrypt([aad], ciphertext)
+            .map_err(|e| error(CryptoOperation::Decrypt, "Authentication failed", e))
+    }
+
+    /// Encrypt a value into a URL-safe lookup key
+    pub fn lookup_key(&self, value: &[u8], aad: &[u8]) -> InfraResult<String> {
+        Ok(encode_base64url(&self.encrypt_with_aad(value, aad)?))
+    }
+
+    fn siv(&self) -> InfraResult<Aes256Siv> {
+        Aes256Siv::new_from_slice(self.key.expose_secret())
+            .map_err(|e| error(CryptoOperation::KeyGeneration, &e.to_string(), e))
+    }
+}
+
+impl std::fmt::Debug for DeterministicCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DeterministicCipher([REDACTED])")
+    }
+}
+
+fn error(
+    operation: CryptoOperation,
+    message: &str,
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> InfraError {
+    InfraError::Crypto {
+        operation,
+        message: message.to_string(),
+        context: None,
+        source: Some(ErrorSource::new(source)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Length of the synthetic IV prefixing ciphertexts
+    const SIV_LEN: usize = 16;
+
+    #[test]
+    fn test_deterministic() {
+        let key = SecretBytes::random(32);
+        let cipher = DeterministicCipher::new(&key).unwrap();
+        let email = b"alice@example.com";
+
+        let ct = cipher.encrypt_with_aad(email, b"users.email").unwrap();
+        assert_eq!(ct, cipher.encrypt_with_aad(email, b"users.email").unwrap());
+        assert_eq!(
+            ct,
+            DeterministicCipher::new(&key)
+                .unwrap()
+                .encrypt_with_aad(email, b"users.email")
+                .unwrap()
+        );
+        assert_ne!(ct, cipher.encrypt_with_aad(email, b"audit.actor").unwrap());
+        assert_ne!(
+            ct,
+            cipher
+                .encrypt_with_aad(b"bob@example.com", b"users.email")
+                .unwrap()
+        );
+        assert_eq!(cipher.decrypt_with_aad(&ct, b"users.email").unwrap(), email);
+        assert!(cipher.decrypt_with_aad(&ct, b"audit.actor").is_err());
+
+        let other = DeterministicCipher::generate().unwrap();
+        assert_ne!(other.encrypt_with_aad(email, b"users.email").unwrap(), ct);
+        assert!(other.decrypt_with_aad(&ct, b"users.email").is_err());
+        assert_eq!(
+            cipher.lookup_key(email, b"users.email").unwrap(),
+            encode_base64url(&ct)
+        );
+    }
+
+    #[test]
+    fn test_tampering() {
+        let cipher = DeterministicCipher::generate().unwrap();
+        let mut ct = cipher.encrypt_with_aad(b"value", b"field").unwrap();
+        assert_eq!(ct.len(), SIV_LEN + 5);
+        ct[SIV_LEN] ^= 1;
+        let err = cipher.decrypt_with_aad(&ct, b"field").unwrap_err();
+        assert_eq!(err.chain().count(), 2);
+        assert!(cipher.decrypt_with_aad(&ct[..4], b"field").is_err());
+        assert!(DeterministicCipher::new(&SecretBytes::random(16)).is_err());
+    }
+}
@@ -1,13 +1,105 @@
 //! Hashing implementations.
 
-use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use crate::secret::SecretBytes;
+use infra_errors::{CryptoOperation, InfraError, InfraResult, IoOperation};
 use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+/// Buffer size when hashing readers and files
+const READ_BUFFER_LEN: usize = 1024 * 1024;
+
+/// Blake3 inputs at least this large are hashed on several threads
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const PARALLEL_THRESHOLD: usize = 128 * 1024;
+
+/// Digest or MAC computation in progress, fed data in pieces
+pub trait DigestStream: Send {
+    /// Feed data
+    fn update(&mut self, data: &[u8]);
+
+    /// Get the digest of the data fed so far
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+
+    /// Verify that the data fed so far matches an expected digest, in
+    /// constant time
+    #[must_use]
+    fn verify(self: Box<Self>, expected: &[u8]) -> bool {
+        crate::secret::constant_time_eq(&self.finalize(), expected)
+    }
+}
+
+/// Feed everything a reader yields to a stream
+pub(crate) fn update_from_reader(
+    stream: &mut dyn DigestStream,
+    reader: &mut dyn Read,
+    path: Option<&Path>,
+) -> InfraResult<()> {
+    let mut buffer = vec![0; READ_BUFFER_LEN];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => stream.update(&buffer[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                return Err(InfraError::Io {
+                    operation: IoOperation::Read,
+                    path: path.map(Path::to_path_buf),
+                    message: e.to_string(),
+                    context: None,
+                    source: None,
+                })
+            }
+        }
+    }
+}
+
+/// Feed a file to a stream, without loading it in memory
+pub(crate) fn update_from_file(stream: &mut dyn DigestStream, path: &Path) -> InfraResult<()> {
+    let mut file = std::fs::File::open(path).map_err(|e| InfraError::Io {
+        operation: IoOperation::Read,
+        path: Some(path.to_path_buf()),
+        message: e.to_string(),
+        context: None,
+        source: None,
+    })?;
+    update_from_reader(stream, &mut file, Some(path))
+}
+
+/// Stream buffering its input for a one-shot [`Hasher::hash`], backing the
+/// default [`Hasher::start`]
+struct BufferedStream<'a, H: ?Sized> {
+    hasher: &'a H,
+    data: Vec<u8>,
+}
+
+impl<H: Hasher + ?Sized> DigestStream for BufferedStream<'_, H> {
+    fn update(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        self.hasher.hash(&self.data)
+    }
+}
 
 /// Trait for hash functions
 pub trait Hasher: Send + Sync {
     /// Hash data and return raw bytes
     fn hash(&self, data: &[u8]) -> Vec<u8>;
 
+    /// Start hashing data fed in pieces
+    ///
+    /// The default buffers everything fed and hashes it with
+    /// [`hash`](Self::hash) on finalize; implementations that can stream
+    /// should override it.
+    fn start(&self) -> Box<dyn DigestStream + '_> {
+        Box::new(BufferedStream {
+            hasher: self,
+            data: Vec::new(),
+        })
+    }
+
     /// Hash data and return hex-encoded string
     fn hash_hex(&self, data: &[u8]) -> String {
         hex::encode(self.hash(data))
@@ -15,7 +107,21 @@ pub trait Hasher: Send + Sync {
 
     /// Verify that data matches an expected hash
     fn verify(&self, data: &[u8], expected: &[u8]) -> bool {
-        constant_time_eq::constant_time_eq(&self.hash(data), expected)
+        crate::secret::constant_time_eq(&self.hash(data), expected)
+    }
+
+    /// Hash everything a reader yields
+    fn hash_reader(&self, reader: &mut dyn Read) -> InfraResult<Vec<u8>> {
+        let mut stream = self.start();
+        update_from_reader(stream.as_mut(), reader, None)?;
+        Ok(stream.finalize())
+    }
+
+    /// Hash a file, without loading it in memory
+    fn hash_file(&self, path: &Path) -> InfraResult<Vec<u8>> {
+        let mut stream = self.start();
+        update_from_file(stream.as_mut(), path)?;
+        Ok(stream.finalize())
     }
 }
 
@@ -32,13 +138,28 @@ impl Sha256Hasher {
 
 impl Hasher for Sha256Hasher {
     fn hash(&self, data: &[u8]) -> Vec<u8> {
-        let mut hasher = Sha256::new();
-        hasher.update(data);
-        hasher.finalize().to_vec()
+        Sha256::digest(data).to_vec()
+    }
+
+    fn start(&self) -> Box<dyn DigestStream + '_> {
+        Box::new(Sha256::new())
+    }
+}
+
+impl DigestStream for Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        Digest::finalize(*self).to_vec()
     }
 }
 
 /// Blake3 hasher (fast, modern)
+///
+/// With the `parallel` feature, large inputs are hashed on several threads
+/// (except on WASM, which has none).
 #[derive(Debug, Clone, Default)]
 pub struct Blake3Hasher;
 
@@ -51,7 +172,28 @@ impl Blake3Hasher {
 
 impl Hasher for Blake3Hasher {
     fn hash(&self, data: &[u8]) -> Vec<u8> {
-        blake3::hash(data).as_bytes().to_vec()
+        let mut stream = self.start();
+        stream.update(data);
+        stream.finalize()
+    }
+
+    fn start(&self) -> Box<dyn DigestStream + '_> {
+        Box::new(blake3::Hasher::new())
+    }
+}
+
+impl DigestStream for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if data.len() >= PARALLEL_THRESHOLD {
+            self.update_rayon(data);
+            return;
+        }
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        blake3::Hasher::finalize(&self).as_bytes().to_vec()
     }
 }
 
@@ -122,6 +264,7 @@ impl PasswordHasher {
                         operation: CryptoOperation::Hash,
                         message: e.to_string(),
                         context: None,
+                        source: None,
                     },
                 )?;
 
@@ -134,11 +277,36 @@ impl PasswordHasher {
                         operation: CryptoOperation::Hash,
                         message: e.to_string(),
                         context: None,
+                        source: None,
                     })
             }
         }
     }
 
+    /// Derive a 32-byte key from a password and salt, as for encrypting
+    /// with a passphrase
+    pub fn derive_key(&self, password: &str, salt: &[u8]) -> InfraResult<SecretBytes> {
+        use argon2::{Argon2, Params};
+
+        match self.algorithm {
+            PasswordAlgorithm::Argon2id {
+                memory_cost,
+                time_cost,
+                parallelism,
+            } => {
+                let params = Params::new(memory_cost, time_cost, parallelism, Some(32))
+                    .map_err(|e| kdf_error(&e.to_string()))?;
+                let argon2 =
+                    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+                let mut key = SecretBytes::new(vec![0; 32]);
+                argon2
+                    .hash_password_into(password.as_bytes(), salt, key.expose_secret_mut())
+                    .map_err(|e| kdf_error(&e.to_string()))?;
+                Ok(key)
+            }
+        }
+    }
+
     /// Verify a password against a hash
     pub fn verify(&self, password: &str, hash: &str) -> InfraResult<bool> {
         use argon2::{password_hash::PasswordVerifier, Argon2, PasswordHash};
@@ -147,6 +315,7 @@ impl PasswordHasher {
             operation: CryptoOperation::Verify,
             message: e.to_string(),
             context: None,
+            source: None,
         })?;
 
         Ok(Argon2::default()
@@ -155,6 +324,15 @@ impl PasswordHasher {
     }
 }
 
+fn kdf_error(message: &str) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::KeyDerivation,
+        message: message.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
 /// Convenience function to hash with SHA-256
 #[must_use]
 pub fn sha256(data: &[u8]) -> Vec<u8> {
@@ -210,6 +388,45 @@ mod tests {
         assert!(!hasher.verify("wrong_password", &hash).unwrap());
     }
 
+    #[test]
+    fn test_streaming() {
+        let data: Vec<u8> = (0..1_000_000u32).flat_map(u32::to_le_bytes).collect();
+        let hashers: [&dyn Hasher; 2] = [&Sha256Hasher::new(), &Blake3Hasher::new()];
+        for hasher in hashers {
+            let mut stream = hasher.start();
+            for chunk in data.chunks(100_000) {
+                stream.update(chunk);
+            }
+            let expected = hasher.hash(&data);
+            assert_eq!(stream.finalize(), expected);
+            assert_eq!(hasher.hash_reader(&mut data.as_slice()).unwrap(), expected);
+
+            let path = std::env::temp_dir().join(format!("infra-crypto-{}", std::process::id()));
+            std::fs::write(&path, &data).unwrap();
+            assert_eq!(hasher.hash_file(&path).unwrap(), expected);
+            std::fs::remove_file(&path).unwrap();
+        }
+        assert_eq!(
+            Blake3Hasher::new().hash(&data),
+            blake3::hash(&data).as_bytes()
+        );
+        assert!(Sha256Hasher::new()
+            .hash_file(Path::new("/nonexistent/file"))
+            .is_err());
+
+        // Hashers implementing only `hash` stream through a buffer
+        struct OneShot;
+        impl Hasher for OneShot {
+            fn hash(&self, data: &[u8]) -> Vec<u8> {
+                sha256(data)
+            }
+        }
+        assert_eq!(
+            OneShot.hash_reader(&mut data.as_slice()).unwrap(),
+            sha256(&data)
+        );
+    }
+
     #[test]
     fn test_hasher_verify() {
         let hasher = Sha256Hasher::new();
@@ -1,16 +1,24 @@
 //! JWT (JSON Web Token) support.
 
-use chrono::{Duration, Utc};
-use infra_errors::{AuthErrorKind, CryptoOperation, InfraError, InfraResult};
+use crate::secret::SecretBytes;
+use crate::sign::{Keypair, PublicKey};
+use chrono::{DateTime, Duration, Utc};
+use infra_errors::{AuthErrorKind, CryptoOperation, ErrorSource, InfraError, InfraResult};
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, KeyAlgorithm, PublicKeyUse};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+pub use jsonwebtoken::jwk::{Jwk, JwkSet};
+
 /// JWT algorithm
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JwtAlgorithm {
     HS256,
     HS384,
     HS512,
+    RS256,
+    ES256,
+    EdDSA,
 }
 
 impl JwtAlgorithm {
@@ -19,12 +27,34 @@ impl JwtAlgorithm {
             Self::HS256 => jsonwebtoken::Algorithm::HS256,
             Self::HS384 => jsonwebtoken::Algorithm::HS384,
             Self::HS512 => jsonwebtoken::Algorithm::HS512,
+            Self::RS256 => jsonwebtoken::Algorithm::RS256,
+            Self::ES256 => jsonwebtoken::Algorithm::ES256,
+            Self::EdDSA => jsonwebtoken::Algorithm::EdDSA,
+        }
+    }
+
+    fn from_jsonwebtoken(algorithm: jsonwebtoken::Algorithm) -> Option<Self> {
+        match algorithm {
+            jsonwebtoken::Algorithm::HS256 => Some(Self::HS256),
+            jsonwebtoken::Algorithm::HS384 => Some(Self::HS384),
+            jsonwebtoken::Algorithm::HS512 => Some(Self::HS512),
+            jsonwebtoken::Algorithm::RS256 => Some(Self::RS256),
+            jsonwebtoken::Algorithm::ES256 => Some(Self::ES256),
+            jsonwebtoken::Algorithm::EdDSA => Some(Self::EdDSA),
+            _ => None,
         }
     }
 }
 
+/// DER prefix of a PKCS#8 v1 Ed25519 private key, followed by the 32-byte
+/// seed
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
 /// Standard JWT claims
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawClaims<T>")]
 pub struct Claims<T = serde_json::Value> {
     /// Expiration time (Unix timestamp)
     pub exp: i64,
@@ -39,9 +69,15 @@ pub struct Claims<T = serde_json::Value> {
     /// Issuer
     #[serde(skip_serializing_if = "Option::is_none")]
     pub iss: Option<String>,
-    /// Audience
+    /// Audience (the first, when a token lists several)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aud: Option<String>,
+    /// All audiences of a verified token, which many identity providers
+    /// issue as a list
+    ///
+    /// Only `aud` is written when signing.
+    #[serde(skip_serializing)]
+    pub audiences: Vec<String>,
     /// JWT ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jti: Option<String>,
@@ -50,6 +86,48 @@ pub struct Claims<T = serde_json::Value> {
     pub payload: T,
 }
 
+/// Claims as encoded, with `aud` as a string or a list
+#[derive(Deserialize)]
+struct RawClaims<T> {
+    exp: i64,
+    iat: i64,
+    nbf: Option<i64>,
+    sub: Option<String>,
+    iss: Option<String>,
+    aud: Option<Audience>,
+    jti: Option<String>,
+    #[serde(flatten)]
+    payload: T,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl<T> From<RawClaims<T>> for Claims<T> {
+    fn from(raw: RawClaims<T>) -> Self {
+        let audiences = match raw.aud {
+            Some(Audience::One(aud)) => vec![aud],
+            Some(Audience::Many(auds)) => auds,
+            None => Vec::new(),
+        };
+        Self {
+            exp: raw.exp,
+            iat: raw.iat,
+            nbf: raw.nbf,
+            sub: raw.sub,
+            iss: raw.iss,
+            aud: audiences.first().cloned(),
+            audiences,
+            jti: raw.jti,
+            payload: raw.payload,
+        }
+    }
+}
+
 impl<T: Default> Claims<T> {
     /// Create new claims with expiration
     #[must_use]
@@ -62,6 +140,7 @@ impl<T: Default> Claims<T> {
             sub: None,
             iss: None,
             aud: None,
+            audiences: Vec::new(),
             jti: None,
             payload: T::default(),
         }
@@ -80,6 +159,7 @@ impl<T> Claims<T> {
             sub: None,
             iss: None,
             aud: None,
+            audiences: Vec::new(),
             jti: None,
             payload,
         }
@@ -102,7 +182,9 @@ impl<T> Claims<T> {
     /// Set audience
     #[must_use]
     pub fn with_audience(mut self, aud: impl Into<String>) -> Self {
-        self.aud = Some(aud.into());
+        let aud = aud.into();
+        self.audiences = vec![aud.clone()];
+        self.aud = Some(aud);
         self
     }
 
@@ -113,65 +195,439 @@ impl<T> Claims<T> {
         self
     }
 
+    /// Set the issue time, moving the expiration with it
+    ///
+    /// Useful when time is simulated, as claims are otherwise issued at the
+    /// current system time.
+    #[must_use]
+    pub fn issued_at(mut self, iat: DateTime<Utc>) -> Self {
+        self.exp += iat.timestamp() - self.iat;
+        self.iat = iat.timestamp();
+        self
+    }
+
     /// Check if the token is expired
     #[must_use]
     pub fn is_expired(&self) -> bool {
-        Utc::now().timestamp() > self.exp
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Check if the token is expired at the given time
+    #[must_use]
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        now.timestamp() > self.exp
+    }
+}
+
+/// Checks applied to claims when verifying a token
+///
+/// By default any issuer and subject are accepted, tokens carrying an
+/// audience are rejected, `nbf` is not checked (as before these options
+/// existed; enable [`validate_nbf`](Self::validate_nbf) to enforce it), and
+/// 60 seconds of clock skew are tolerated on `exp` and `nbf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ValidationOptions {
+    /// Accepted issuers; any when empty
+    pub issuers: Vec<String>,
+    /// Accepted audiences
+    pub audiences: Vec<String>,
+    /// Reject tokens without an `iss` claim
+    pub require_issuer: bool,
+    /// Reject tokens without an `aud` claim
+    pub require_audience: bool,
+    /// Reject tokens without a `sub` claim
+    pub require_subject: bool,
+    /// Clock skew tolerated on `exp` and `nbf`
+    pub leeway: Duration,
+    /// Reject tokens used before their `nbf` time
+    pub validate_nbf: bool,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            issuers: Vec::new(),
+            audiences: Vec::new(),
+            require_issuer: false,
+            require_audience: false,
+            require_subject: false,
+            leeway: Duration::seconds(60),
+            validate_nbf: false,
+        }
+    }
+}
+
+impl ValidationOptions {
+    /// Create default options
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept tokens issued by `iss`, and require an issuer
+    #[must_use]
+    pub fn issuer(mut self, iss: impl Into<String>) -> Self {
+        self.issuers.push(iss.into());
+        self.require_issuer = true;
+        self
+    }
+
+    /// Accept tokens intended for `aud`
+    #[must_use]
+    pub fn audience(mut self, aud: impl Into<String>) -> Self {
+        self.audiences.push(aud.into());
+        self
+    }
+
+    /// Reject tokens without an `aud` claim
+    #[must_use]
+    pub fn require_audience(mut self) -> Self {
+        self.require_audience = true;
+        self
+    }
+
+    /// Reject tokens without a `sub` claim
+    #[must_use]
+    pub fn require_subject(mut self) -> Self {
+        self.require_subject = true;
+        self
+    }
+
+    /// Set the clock skew tolerated on `exp` and `nbf`
+    #[must_use]
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Set whether tokens are rejected before their `nbf` time
+    #[must_use]
+    pub fn validate_nbf(mut self, validate: bool) -> Self {
+        self.validate_nbf = validate;
+        self
+    }
+
+    fn to_jsonwebtoken(&self, algorithm: JwtAlgorithm) -> Validation {
+        let mut validation = Validation::new(algorithm.to_jsonwebtoken());
+        if !self.issuers.is_empty() {
+            validation.set_issuer(&self.issuers);
+        }
+        if !self.audiences.is_empty() {
+            validation.set_audience(&self.audiences);
+        }
+        let mut required = vec!["exp"];
+        for (claim, require) in [
+            ("iss", self.require_issuer),
+            ("aud", self.require_audience),
+            ("sub", self.require_subject),
+        ] {
+            if require {
+                required.push(claim);
+            }
+        }
+        validation.set_required_spec_claims(&required);
+        validation.leeway = u64::try_from(self.leeway.num_seconds()).unwrap_or(0);
+        validation.validate_nbf = self.validate_nbf;
+        validation
     }
 }
 
 /// JWT signer and verifier
+///
+/// HMAC signers share one secret for signing and verifying. RSA and ECDSA
+/// signers take PEM-encoded keys, and `EdDSA` signers Ed25519 keys from the
+/// sign module; all of them can verify only when created from a public key
+/// or a JWK.
 pub struct JwtSigner {
     algorithm: JwtAlgorithm,
-    encoding_key: EncodingKey,
+    key_id: Option<String>,
+    encoding_key: Option<EncodingKey>,
     decoding_key: DecodingKey,
+    validation: ValidationOptions,
+}
+
+fn jwk_error(message: &str) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::Verify,
+        message: message.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
+fn key_error(operation: CryptoOperation, e: jsonwebtoken::errors::Error) -> InfraError {
+    InfraError::Crypto {
+        operation,
+        message: format!("Invalid key: {e}"),
+        context: None,
+        source: Some(ErrorSource::new(e)),
+    }
 }
 
 impl JwtSigner {
     /// Create a new JWT signer with HS256
     #[must_use]
     pub fn hs256(secret: &[u8]) -> Self {
-        Self {
-            algorithm: JwtAlgorithm::HS256,
-            encoding_key: EncodingKey::from_secret(secret),
-            decoding_key: DecodingKey::from_secret(secret),
-        }
+        Self::new(
+            JwtAlgorithm::HS256,
+            Some(EncodingKey::from_secret(secret)),
+            DecodingKey::from_secret(secret),
+        )
     }
 
     /// Create a new JWT signer with HS384
     #[must_use]
     pub fn hs384(secret: &[u8]) -> Self {
-        Self {
-            algorithm: JwtAlgorithm::HS384,
-            encoding_key: EncodingKey::from_secret(secret),
-            decoding_key: DecodingKey::from_secret(secret),
-        }
+        Self::new(
+            JwtAlgorithm::HS384,
+            Some(EncodingKey::from_secret(secret)),
+            DecodingKey::from_secret(secret),
+        )
     }
 
     /// Create a new JWT signer with HS512
     #[must_use]
     pub fn hs512(secret: &[u8]) -> Self {
+        Self::new(
+            JwtAlgorithm::HS512,
+            Some(EncodingKey::from_secret(secret)),
+            DecodingKey::from_secret(secret),
+        )
+    }
+
+    /// Create an HMAC signer from a secret held as [`SecretBytes`]
+    ///
+    /// Fails for algorithms other than HS256, HS384 and HS512.
+    pub fn from_secret(algorithm: JwtAlgorithm, secret: &SecretBytes) -> InfraResult<Self> {
+        match algorithm {
+            JwtAlgorithm::HS256 => Ok(Self::hs256(secret.expose_secret())),
+            JwtAlgorithm::HS384 => Ok(Self::hs384(secret.expose_secret())),
+            JwtAlgorithm::HS512 => Ok(Self::hs512(secret.expose_secret())),
+            _ => Err(InfraError::Crypto {
+                operation: CryptoOperation::KeyGeneration,
+                message: format!("{algorithm:?} does not take a shared secret"),
+                context: None,
+                source: None,
+            }),
+        }
+    }
+
+    /// Create an RS256 signer from PEM-encoded RSA private and public keys
+    pub fn rs256(private_pem: &[u8], public_pem: &[u8]) -> InfraResult<Self> {
+        let encoding_key = EncodingKey::from_rsa_pem(private_pem)
+            .map_err(|e| key_error(CryptoOperation::Sign, e))?;
+        Ok(Self {
+            encoding_key: Some(encoding_key),
+            ..Self::rs256_verifier(public_pem)?
+        })
+    }
+
+    /// Create an RS256 verifier from a PEM-encoded RSA public key
+    pub fn rs256_verifier(public_pem: &[u8]) -> InfraResult<Self> {
+        let decoding_key = DecodingKey::from_rsa_pem(public_pem)
+            .map_err(|e| key_error(CryptoOperation::Verify, e))?;
+        Ok(Self::new(JwtAlgorithm::RS256, None, decoding_key))
+    }
+
+    /// Create an ES256 signer from PEM-encoded P-256 private (PKCS#8) and
+    /// public keys
+    pub fn es256(private_pem: &[u8], public_pem: &[u8]) -> InfraResult<Self> {
+        let encoding_key = EncodingKey::from_ec_pem(private_pem)
+            .map_err(|e| key_error(CryptoOperation::Sign, e))?;
+        Ok(Self {
+            encoding_key: Some(encoding_key),
+            ..Self::es256_verifier(public_pem)?
+        })
+    }
+
+    /// Create an ES256 verifier from a PEM-encoded P-256 public key
+    pub fn es256_verifier(public_pem: &[u8]) -> InfraResult<Self> {
+        let decoding_key = DecodingKey::from_ec_pem(public_pem)
+            .map_err(|e| key_error(CryptoOperation::Verify, e))?;
+        Ok(Self::new(JwtAlgorithm::ES256, None, decoding_key))
+    }
+
+    /// Create an `EdDSA` signer from an Ed25519 keypair
+    pub fn eddsa(keypair: &Keypair) -> InfraResult<Self> {
+        let mut pkcs8 = ED25519_PKCS8_PREFIX.to_vec();
+        pkcs8.extend_from_slice(&keypair.secret_bytes());
+        let pkcs8 = SecretBytes::new(pkcs8);
+        Ok(Self {
+            encoding_key: Some(EncodingKey::from_ed_der(pkcs8.expose_secret())),
+            ..Self::eddsa_verifier(&keypair.public_key())?
+        })
+    }
+
+    /// Create an `EdDSA` verifier from an Ed25519 public key
+    pub fn eddsa_verifier(public_key: &PublicKey) -> InfraResult<Self> {
+        if public_key.as_bytes().len() != 32 {
+            return Err(InfraError::Crypto {
+                operation: CryptoOperation::Verify,
+                message: "Ed25519 public key must be 32 bytes".to_string(),
+                context: None,
+                source: None,
+            });
+        }
+        let decoding_key = DecodingKey::from_ed_der(public_key.as_bytes());
+        Ok(Self::new(JwtAlgorithm::EdDSA, None, decoding_key))
+    }
+
+    /// Create a verifier from an RSA, P-256 or Ed25519 JSON Web Key
+    ///
+    /// The key ID is taken from the JWK. Keys whose `use` is not `sig`, or
+    /// whose `alg` is not RS256, ES256 or `EdDSA` to match the key type, are
+    /// rejected.
+    pub fn from_jwk(jwk: &Jwk) -> InfraResult<Self> {
+        let algorithm = match &jwk.algorithm {
+            AlgorithmParameters::RSA(_) => JwtAlgorithm::RS256,
+            AlgorithmParameters::EllipticCurve(params) if params.curve == EllipticCurve::P256 => {
+                JwtAlgorithm::ES256
+            }
+            AlgorithmParameters::OctetKeyPair(params) if params.curve == EllipticCurve::Ed25519 => {
+                JwtAlgorithm::EdDSA
+            }
+            _ => return Err(jwk_error("Unsupported JWK key type or curve")),
+        };
+        if !matches!(
+            jwk.common.public_key_use,
+            None | Some(PublicKeyUse::Signature)
+        ) {
+            return Err(jwk_error("JWK is not a signing key"));
+        }
+        if let Some(alg) = jwk.common.key_algorithm {
+            if !matches!(
+                (alg, algorithm),
+                (KeyAlgorithm::RS256, JwtAlgorithm::RS256)
+                    | (KeyAlgorithm::ES256, JwtAlgorithm::ES256)
+                    | (KeyAlgorithm::EdDSA, JwtAlgorithm::EdDSA)
+            ) {
+                return Err(jwk_error(&format!(
+                    "JWK algorithm {alg} does not match its {algorithm:?} key"
+                )));
+            }
+        }
+        let decoding_key =
+            DecodingKey::from_jwk(jwk).map_err(|e| key_error(CryptoOperation::Verify, e))?;
+
+        let mut signer = Self::new(algorithm, None, decoding_key);
+        signer.key_id.clone_from(&jwk.common.key_id);
+        Ok(signer)
+    }
+
+    fn new(
+        algorithm: JwtAlgorithm,
+        encoding_key: Option<EncodingKey>,
+        decoding_key: DecodingKey,
+    ) -> Self {
         Self {
-            algorithm: JwtAlgorithm::HS512,
-            encoding_key: EncodingKey::from_secret(secret),
-            decoding_key: DecodingKey::from_secret(secret),
+            algorithm,
+            key_id: None,
+            encoding_key,
+            decoding_key,
+            validation: ValidationOptions::default(),
         }
     }
 
+    /// Set the key ID written to the `kid` header of signed tokens
+    #[must_use]
+    pub fn with_key_id(mut self, kid: impl Into<String>) -> Self {
+        self.key_id = Some(kid.into());
+        self
+    }
+
+    /// Only accept tokens issued by `iss`, or another issuer added this way
+    #[must_use]
+    pub fn with_issuer(mut self, iss: impl Into<String>) -> Self {
+        self.validation = self.validation.issuer(iss);
+        self
+    }
+
+    /// Only accept tokens intended for `aud`, or another audience added
+    /// this way
+    ///
+    /// Tokens carrying an audience are rejected unless one is set.
+    #[must_use]
+    pub fn with_audience(mut self, aud: impl Into<String>) -> Self {
+        self.validation = self.validation.audience(aud);
+        self
+    }
+
+    /// Set the clock skew tolerated on `exp` and `nbf`
+    #[must_use]
+    pub fn with_leeway(mut self, leeway: Duration) -> Self {
+        self.validation.leeway = leeway;
+        self
+    }
+
+    /// Replace the claim checks
+    ///
+    /// Issuers and audiences already added with
+    /// [`with_issuer`](Self::with_issuer) and
+    /// [`with_audience`](Self::with_audience) are kept along with those of
+    /// `options`.
+    #[must_use]
+    pub fn with_validation(mut self, mut options: ValidationOptions) -> Self {
+        for iss in std::mem::take(&mut self.validation.issuers) {
+            if !options.issuers.contains(&iss) {
+                options = options.issuer(iss);
+            }
+        }
+        for aud in std::mem::take(&mut self.validation.audiences) {
+            if !options.audiences.contains(&aud) {
+                options = options.audience(aud);
+            }
+        }
+        self.validation = options;
+        self
+    }
+
+    /// Get the claim checks
+    #[must_use]
+    pub fn validation_options(&self) -> &ValidationOptions {
+        &self.validation
+    }
+
+    /// Get the algorithm
+    #[must_use]
+    pub fn algorithm(&self) -> JwtAlgorithm {
+        self.algorithm
+    }
+
+    /// Get the key ID
+    #[must_use]
+    pub fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+
+    fn validation(&self) -> Validation {
+        self.validation.to_jsonwebtoken(self.algorithm)
+    }
+
     /// Sign claims and create a JWT
     pub fn sign<T: Serialize>(&self, claims: &Claims<T>) -> InfraResult<String> {
-        let header = Header::new(self.algorithm.to_jsonwebtoken());
+        let Some(encoding_key) = &self.encoding_key else {
+            return Err(InfraError::Crypto {
+                operation: CryptoOperation::Sign,
+                message: "Signer has no private key".to_string(),
+                context: None,
+                source: None,
+            });
+        };
+        let mut header = Header::new(self.algorithm.to_jsonwebtoken());
+        header.kid.clone_from(&self.key_id);
 
-        encode(&header, claims, &self.encoding_key).map_err(|e| InfraError::Crypto {
+        encode(&header, claims, encoding_key).map_err(|e| InfraError::Crypto {
             operation: CryptoOperation::Sign,
             message: e.to_string(),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 
     /// Verify and decode a JWT
     pub fn verify<T: DeserializeOwned>(&self, token: &str) -> InfraResult<Claims<T>> {
-        let validation = Validation::new(self.algorithm.to_jsonwebtoken());
+        let validation = self.validation();
 
         decode::<Claims<T>>(token, &self.decoding_key, &validation)
             .map(|data| data.claims)
@@ -190,13 +646,57 @@ impl JwtSigner {
                     message: e.to_string(),
                     identity: None,
                     context: None,
+                    source: Some(ErrorSource::new(e)),
                 }
             })
     }
 
+    /// Verify and decode a JWT, checking `exp` and `nbf` against `now`
+    /// instead of the system clock, with the same leeway as [`verify`](Self::verify)
+    pub fn verify_at<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> InfraResult<Claims<T>> {
+        let mut validation = self.validation();
+        validation.validate_exp = false;
+        validation.validate_nbf = false;
+        let claims = decode::<Claims<T>>(token, &self.decoding_key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                message: e.to_string(),
+                identity: None,
+                context: None,
+                source: Some(ErrorSource::new(e)),
+            })?;
+        let leeway = self.validation.leeway.num_seconds().max(0);
+        let now = now.timestamp();
+
+        if claims.exp < now - leeway {
+            return Err(InfraError::Auth {
+                kind: AuthErrorKind::TokenExpired,
+                message: "ExpiredSignature".to_string(),
+                identity: None,
+                context: None,
+                source: None,
+            });
+        }
+        if self.validation.validate_nbf && claims.nbf.is_some_and(|nbf| nbf > now + leeway) {
+            return Err(InfraError::Auth {
+                kind: AuthErrorKind::InvalidToken,
+                message: "ImmatureSignature".to_string(),
+                identity: None,
+                context: None,
+                source: None,
+            });
+        }
+        Ok(claims)
+    }
+
     /// Verify without validating expiration (useful for refresh tokens)
     pub fn verify_ignore_expiry<T: DeserializeOwned>(&self, token: &str) -> InfraResult<Claims<T>> {
-        let mut validation = Validation::new(self.algorithm.to_jsonwebtoken());
+        let mut validation = self.validation();
         validation.validate_exp = false;
 
         decode::<Claims<T>>(token, &self.decoding_key, &validation)
@@ -206,10 +706,46 @@ impl JwtSigner {
                 message: e.to_string(),
                 identity: None,
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })
     }
 }
 
+/// Header of a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenHeader {
+    /// Signing algorithm, if supported
+    pub algorithm: Option<JwtAlgorithm>,
+    /// Key ID
+    pub key_id: Option<String>,
+    /// Token type, usually `JWT`
+    pub token_type: Option<String>,
+}
+
+/// Read a token's header, without verifying the token
+///
+/// Use it to pick the verification key by `kid` before verifying.
+pub fn decode_header(token: &str) -> InfraResult<TokenHeader> {
+    jsonwebtoken::decode_header(token)
+        .map(|header| TokenHeader {
+            algorithm: JwtAlgorithm::from_jsonwebtoken(header.alg),
+            key_id: header.kid,
+            token_type: header.typ,
+        })
+        .map_err(|e| InfraError::Auth {
+            kind: AuthErrorKind::InvalidToken,
+            message: e.to_string(),
+            identity: None,
+            context: None,
+            source: Some(ErrorSource::new(e)),
+        })
+}
+
+/// Read the key ID from a token's header, without verifying the token
+pub fn key_id(token: &str) -> InfraResult<Option<String>> {
+    decode_header(token).map(|header| header.key_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +777,17 @@ mod tests {
         assert_eq!(verified.iss, Some("infra".to_string()));
     }
 
+    #[test]
+    fn test_from_secret() {
+        let secret = SecretBytes::from(b"super_secret_key_at_least_32_bytes!".as_slice());
+        let signer = JwtSigner::from_secret(JwtAlgorithm::HS384, &secret).unwrap();
+        let token = signer.sign(&Claims::<()>::new(Duration::hours(1))).unwrap();
+        assert!(JwtSigner::hs384(secret.expose_secret())
+            .verify::<()>(&token)
+            .is_ok());
+        assert!(JwtSigner::from_secret(JwtAlgorithm::EdDSA, &secret).is_err());
+    }
+
     #[test]
     fn test_expired_token() {
         let signer = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!");
@@ -253,6 +800,8 @@ mod tests {
         let result: Result<Claims<()>, _> = signer.verify(&token);
 
         assert!(result.is_err());
+        // The jsonwebtoken error is kept as the source
+        assert_eq!(result.as_ref().unwrap_err().chain().count(), 2);
         if let Err(InfraError::Auth { kind, .. }) = result {
             assert_eq!(kind, AuthErrorKind::TokenExpired);
         }
@@ -269,4 +818,214 @@ mod tests {
         let result: Result<Claims<()>, _> = signer2.verify(&token);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_asymmetric_sign_verify() {
+        let rsa = JwtSigner::rs256(
+            include_bytes!("../testdata/rsa_private.pem"),
+            include_bytes!("../testdata/rsa_public.pem"),
+        )
+        .unwrap()
+        .with_key_id("rsa-1");
+        let ec = JwtSigner::es256(
+            include_bytes!("../testdata/ec_private.pem"),
+            include_bytes!("../testdata/ec_public.pem"),
+        )
+        .unwrap();
+
+        let claims: Claims<()> = Claims::new(Duration::hours(1)).with_subject("svc");
+        let token = rsa.sign(&claims).unwrap();
+        assert_eq!(key_id(&token).unwrap(), Some("rsa-1".to_string()));
+
+        let public_only =
+            JwtSigner::rs256_verifier(include_bytes!("../testdata/rsa_public.pem")).unwrap();
+        let verified: Claims<()> = public_only.verify(&token).unwrap();
+        assert_eq!(verified.sub, Some("svc".to_string()));
+        assert!(public_only.sign(&claims).is_err());
+
+        // Tokens from one key type don't verify with another
+        let result: InfraResult<Claims<()>> = ec.verify(&token);
+        assert!(result.is_err());
+        let token = ec.sign(&claims).unwrap();
+        assert!(ec.verify::<()>(&token).is_ok());
+    }
+
+    #[test]
+    fn test_issuer_and_audience() {
+        let signer = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!");
+        let claims: Claims<()> = Claims::new(Duration::hours(1))
+            .with_issuer("https://idp.example.com")
+            .with_audience("api");
+        let token = signer.sign(&claims).unwrap();
+
+        let strict = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!")
+            .with_issuer("https://idp.example.com")
+            .with_audience("api");
+        assert!(strict.verify::<()>(&token).is_ok());
+        let other = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!").with_audience("web");
+        assert!(other.verify::<()>(&token).is_err());
+    }
+
+    #[test]
+    fn test_eddsa() {
+        let keypair = Keypair::generate();
+        let signer = JwtSigner::eddsa(&keypair).unwrap().with_key_id("ed-1");
+        let claims: Claims<()> = Claims::new(Duration::hours(1)).with_subject("svc");
+        let token = signer.sign(&claims).unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.algorithm, Some(JwtAlgorithm::EdDSA));
+        assert_eq!(header.key_id, Some("ed-1".to_string()));
+
+        let verifier = JwtSigner::eddsa_verifier(&keypair.public_key()).unwrap();
+        assert_eq!(
+            verifier.verify::<()>(&token).unwrap().sub,
+            Some("svc".to_string())
+        );
+        assert!(verifier.sign(&claims).is_err());
+        let other = JwtSigner::eddsa_verifier(&Keypair::generate().public_key()).unwrap();
+        assert!(other.verify::<()>(&token).is_err());
+        assert!(JwtSigner::eddsa_verifier(&PublicKey::from_bytes(vec![0; 16])).is_err());
+    }
+
+    #[test]
+    fn test_validation_options() {
+        let signer = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!");
+        let bare = signer.sign(&Claims::<()>::new(Duration::hours(1))).unwrap();
+        let strict = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!").with_validation(
+            ValidationOptions::new()
+                .audience("api")
+                .require_audience()
+                .require_subject(),
+        );
+        assert!(strict.verify::<()>(&bare).is_err());
+        let full = signer
+            .sign(
+                &Claims::<()>::new(Duration::hours(1))
+                    .with_audience("api")
+                    .with_subject("svc"),
+            )
+            .unwrap();
+        assert!(strict.verify::<()>(&full).is_ok());
+
+        // Issuers are required once one is configured
+        let issuer = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!")
+            .with_issuer("https://idp.example.com");
+        assert!(issuer.verify::<()>(&bare).is_err());
+
+        let mut claims: Claims<()> = Claims::new(Duration::seconds(-30));
+        let recent = signer.sign(&claims).unwrap();
+        assert!(signer.verify::<()>(&recent).is_ok());
+        let no_leeway =
+            JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!").with_leeway(Duration::zero());
+        assert!(no_leeway.verify::<()>(&recent).is_err());
+
+        claims = Claims::new(Duration::hours(2));
+        claims.nbf = Some((Utc::now() + Duration::hours(1)).timestamp());
+        let early = signer.sign(&claims).unwrap();
+        assert!(signer.verify::<()>(&early).is_ok());
+        let nbf = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!")
+            .with_validation(ValidationOptions::new().validate_nbf(true));
+        assert!(nbf.verify::<()>(&early).is_err());
+
+        // Replacing the checks keeps the configured issuer and audience
+        let kept = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!")
+            .with_issuer("https://idp.example.com")
+            .with_audience("api")
+            .with_validation(ValidationOptions::new().require_subject());
+        assert_eq!(
+            kept.validation_options().issuers,
+            ["https://idp.example.com"]
+        );
+        assert_eq!(kept.validation_options().audiences, ["api"]);
+        assert!(kept.validation_options().require_issuer);
+        assert!(kept.validation_options().require_subject);
+    }
+
+    #[test]
+    fn test_from_jwk_checks() {
+        let keypair = Keypair::generate();
+        let x = crate::encode_base64url(keypair.public_key().as_bytes());
+        let jwk = |extra: &str| -> Jwk {
+            serde_json::from_str(&format!(
+                r#"{{"kty": "OKP", "crv": "Ed25519", "x": "{x}"{extra}}}"#
+            ))
+            .unwrap()
+        };
+        let token = JwtSigner::eddsa(&keypair)
+            .unwrap()
+            .sign(&Claims::<()>::new(Duration::hours(1)))
+            .unwrap();
+
+        let verifier = JwtSigner::from_jwk(&jwk(r#", "use": "sig", "alg": "EdDSA""#)).unwrap();
+        assert!(verifier.verify::<()>(&token).is_ok());
+        assert!(JwtSigner::from_jwk(&jwk(r#", "use": "enc""#)).is_err());
+        assert!(JwtSigner::from_jwk(&jwk(r#", "alg": "RS256""#)).is_err());
+        let not_ed25519: Jwk =
+            serde_json::from_str(&format!(r#"{{"kty": "OKP", "crv": "P-256", "x": "{x}"}}"#))
+                .unwrap();
+        assert!(JwtSigner::from_jwk(&not_ed25519).is_err());
+
+        let p384: Jwk = serde_json::from_str(
+            r#"{"kty": "EC", "crv": "P-384", "x": "AAAA", "y": "AAAA", "alg": "ES256"}"#,
+        )
+        .unwrap();
+        assert!(JwtSigner::from_jwk(&p384).is_err());
+    }
+
+    #[test]
+    fn test_audience_list() {
+        let claims: Claims<()> =
+            serde_json::from_str(r#"{"exp": 0, "iat": 0, "aud": ["api", "web"]}"#).unwrap();
+        assert_eq!(claims.aud, Some("api".to_string()));
+        assert_eq!(claims.audiences, ["api", "web"]);
+        let claims: Claims<()> =
+            serde_json::from_str(r#"{"exp": 0, "iat": 0, "aud": "api"}"#).unwrap();
+        assert_eq!(claims.audiences, ["api"]);
+    }
+
+    #[test]
+    fn test_verify_at() {
+        let signer = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!")
+            .with_leeway(Duration::seconds(30));
+        let issued = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let claims: Claims<()> = Claims::new(Duration::minutes(15)).issued_at(issued);
+        assert_eq!(claims.exp - claims.iat, 15 * 60);
+        let token = signer.sign(&claims).unwrap();
+
+        // Long expired by the system clock, but valid at simulated times
+        assert!(signer.verify::<()>(&token).is_err());
+        assert!(signer
+            .verify_at::<()>(&token, issued + Duration::minutes(15))
+            .is_ok());
+        assert!(signer
+            .verify_at::<()>(&token, issued + Duration::seconds(15 * 60 + 30))
+            .is_ok());
+        let err = signer
+            .verify_at::<()>(&token, issued + Duration::seconds(15 * 60 + 31))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::Auth {
+                kind: AuthErrorKind::TokenExpired,
+                ..
+            }
+        ));
+        assert!(claims.is_expired_at(issued + Duration::minutes(16)));
+        assert!(!claims.is_expired_at(issued + Duration::minutes(14)));
+
+        let mut early = claims.clone();
+        early.nbf = Some(claims.iat + 60);
+        let early = signer.sign(&early).unwrap();
+        assert!(signer.verify_at::<()>(&early, issued).is_ok());
+        let nbf = JwtSigner::hs256(b"super_secret_key_at_least_32_bytes!").with_validation(
+            ValidationOptions::new()
+                .leeway(Duration::seconds(30))
+                .validate_nbf(true),
+        );
+        assert!(nbf.verify_at::<()>(&early, issued).is_err());
+        assert!(nbf
+            .verify_at::<()>(&early, issued + Duration::seconds(30))
+            .is_ok());
+    }
 }
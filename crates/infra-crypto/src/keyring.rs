@@ -0,0 +1,436 @@
+//! Versioned keys with rotation and envelope encryption.
+
+use crate::cipher::{Aes256GcmCipher, Cipher};
+use crate::secret::SecretBytes;
+use crate::shamir::{self, Share};
+use chrono::{DateTime, Duration, Utc};
+use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Format byte leading every key ring ciphertext
+const FORMAT_V1: u8 = 1;
+
+/// Length of the format byte and key id prefix
+const HEADER_LEN: usize = 5;
+
+/// A key of the ring
+struct KeyVersion {
+    cipher: Aes256GcmCipher,
+    created_at: DateTime<Utc>,
+}
+
+/// Set of versioned AES-256-GCM keys
+///
+/// Data is encrypted with the current key and prefixed with its id, so it
+/// can be decrypted with whichever key encrypted it after the ring has been
+/// rotated. Ciphertexts are laid out as a format byte, the key id as 4
+/// big-endian bytes, then the AES-GCM nonce and ciphertext.
+///
+/// Rotating adds a new current key and keeps the others for decryption;
+/// [`reencrypt`](Self::reencrypt) moves data to the current key so old keys
+/// can eventually be [`retire`](Self::retire)d. For large values, prefer
+/// [`seal`](Self::seal), which encrypts data under a fresh data key wrapped
+/// by the ring, so rotation only has to rewrap the data key.
+pub struct KeyRing {
+    keys: BTreeMap<u32, KeyVersion>,
+    current: u32,
+    rotation_period: Option<Duration>,
+}
+
+impl KeyRing {
+    /// Create a ring whose current key is `key`, with id 1
+    #[must_use]
+    pub fn new(key: Aes256GcmCipher) -> Self {
+        Self::with_key(1, key, Utc::now())
+    }
+
+    /// Create a ring with a random key
+    pub fn generate() -> InfraResult<Self> {
+        Ok(Self::new(Aes256GcmCipher::generate()?))
+    }
+
+    /// Create a ring whose current key is `key`, as when loading a stored
+    /// ring
+    #[must_use]
+    pub fn with_key(id: u32, key: Aes256GcmCipher, created_at: DateTime<Utc>) -> Self {
+        let mut keys = BTreeMap::new();
+        keys.insert(
+            id,
+            KeyVersion {
+                cipher: key,
+                created_at,
+            },
+        );
+        Self {
+            keys,
+            current: id,
+            rotation_period: None,
+        }
+    }
+
+    /// Rotate keys older than `period` with [`rotate_if_due`](Self::rotate_if_due)
+    #[must_use]
+    pub fn with_rotation_period(mut self, period: Duration) -> Self {
+        self.rotation_period = Some(period);
+        self
+    }
+
+    /// Add a historical key, used only for decryption
+    pub fn insert(
+        &mut self,
+        id: u32,
+        key: Aes256GcmCipher,
+        created_at: DateTime<Utc>,
+    ) -> InfraResult<()> {
+        if self.keys.contains_key(&id) {
+            return Err(key_error(format!("Key {id} already exists")));
+        }
+        self.keys.insert(
+            id,
+            KeyVersion {
+                cipher: key,
+                created_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Get the id of the current key
+    #[must_use]
+    pub fn current_id(&self) -> u32 {
+        self.current
+    }
+
+    /// Get the ids of all keys, oldest first
+    #[must_use]
+    pub fn key_ids(&self) -> Vec<u32> {
+        self.keys.keys().copied().collect()
+    }
+
+    /// Get when a key was created
+    #[must_use]
+    pub fn created_at(&self, id: u32) -> Option<DateTime<Utc>> {
+        self.keys.get(&id).map(|key| key.created_at)
+    }
+
+    /// Make a random key current, returning its id
+    pub fn rotate(&mut self) -> InfraResult<u32> {
+        self.rotate_to(Aes256GcmCipher::generate()?)
+    }
+
+    /// Make `key` current, returning its id
+    ///
+    /// Fails once the highest key id is `u32::MAX`.
+    pub fn rotate_to(&mut self, key: Aes256GcmCipher) -> InfraResult<u32> {
+        let id = match self.keys.keys().next_back() {
+            Some(last) => last
+                .checked_add(1)
+                .ok_or_else(|| key_error("Key ids exhausted".to_string()))?,
+            None => 1,
+        };
+        self.keys.insert(
+            id,
+            KeyVersion {
+                cipher: key,
+                created_at: Utc::now(),
+            },
+        );
+        self.current = id;
+        Ok(id)
+    }
+
+    /// Check if the current key is older than the rotation period
+    #[must_use]
+    pub fn rotation_due(&self) -> bool {
+        let created_at = self.keys[&self.current].created_at;
+        self.rotation_period
+            .is_some_and(|period| Utc::now() - created_at >= period)
+    }
+
+    /// Rotate if the current key is older than the rotation period,
+    /// returning the new key's id
+    pub fn rotate_if_due(&mut self) -> InfraResult<Option<u32>> {
+        if self.rotation_due() {
+            self.rotate().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Remove a key that no data is encrypted with anymore
+    ///
+    /// The current key cannot be retired.
+    pub fn retire(&mut self, id: u32) -> InfraResult<()> {
+        if id == self.current {
+            return Err(InfraError::validation(format!(
+                "Cannot retire current key {id}"
+            )));
+        }
+        self.keys
+            .remove(&id)
+            .map(|_| ())
+            .ok_or_else(|| InfraError::not_found("key", id.to_string()))
+    }
+
+    /// Split a key into `count` Shamir shares, any `threshold` of which
+    /// recover it, to escrow it among operators
+    pub fn split_key(&self, id: u32, threshold: u8, count: u8) -> InfraResult<Vec<Share>> {
+        let key = self
+            .keys
+            .get(&id)
+            .ok_or_else(|| key_error(format!("Unknown key {id}")))?;
+        shamir::split_with_key_id(
+            &SecretBytes::from(key.cipher.key().as_slice()),
+            id,
+            threshold,
+            count,
+        )
+    }
+
+    /// Recover a key split with [`split_key`](Self::split_key), with its
+    /// original id
+    ///
+    /// Pass both to [`with_key`](Self::with_key) or
+    /// [`insert`](Self::insert).
+    pub fn recover_key(shares: &[Share]) -> InfraResult<(u32, Aes256GcmCipher)> {
+        let id = shares
+            .first()
+            .and_then(Share::key_id)
+            .ok_or_else(|| key_error("Shares were not split from a key ring".to_string()))?;
+        Ok((id, Aes256GcmCipher::from_secret(&shamir::combine(shares)?)?))
+    }
+
+    /// Get the id of the key that encrypted a ciphertext
+    pub fn key_id(ciphertext: &[u8]) -> InfraResult<u32> {
+        match ciphertext {
+            [FORMAT_V1, a, b, c, d, ..] => Ok(u32::from_be_bytes([*a, *b, *c, *d])),
+            [FORMAT_V1, ..] | [] => Err(decrypt_error("Ciphertext too short (missing key id)")),
+            [format, ..] => Err(decrypt_error(&format!(
+                "Unknown ciphertext format {format}"
+            ))),
+        }
+    }
+
+    /// Check if a ciphertext was encrypted with another key than the
+    /// current one
+    pub fn needs_reencryption(&self, ciphertext: &[u8]) -> InfraResult<bool> {
+        Ok(Self::key_id(ciphertext)? != self.current)
+    }
+
+    /// Re-encrypt a ciphertext with the current key
+    ///
+    /// Ciphertexts already encrypted with it are returned unchanged.
+    pub fn reencrypt(&self, ciphertext: &[u8]) -> InfraResult<Vec<u8>> {
+        if !self.needs_reencryption(ciphertext)? {
+            return Ok(ciphertext.to_vec());
+        }
+        self.encrypt(&self.decrypt(ciphertext)?)
+    }
+
+    /// Re-encrypt ciphertexts with the current key, as when migrating
+    /// stored data before retiring a key
+    pub fn reencrypt_all<'a, I>(&self, ciphertexts: I) -> InfraResult<Vec<Vec<u8>>>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        ciphertexts
+            .into_iter()
+            .map(|ct| self.reencrypt(ct))
+            .collect()
+    }
+
+    /// Encrypt data under a fresh data key wrapped with the current key
+    pub fn seal(&self, plaintext: &[u8]) -> InfraResult<Envelope> {
+        let data_key = Aes256GcmCipher::generate()?;
+        Ok(Envelope {
+            key_id: self.current,
+            wrapped_key: self.encrypt(data_key.key())?,
+            ciphertext: data_key.encrypt(plaintext)?,
+        })
+    }
+
+    /// Decrypt an envelope
+    ///
+    /// Its `key_id` must match the key that wrapped its data key.
+    pub fn open(&self, envelope: &Envelope) -> InfraResult<Vec<u8>> {
+        Self::check_envelope(envelope)?;
+        let data_key = SecretBytes::new(self.decrypt(&envelope.wrapped_key)?);
+        let data_key = Aes256GcmCipher::from_secret(&data_key)?;
+        data_key.decrypt(&envelope.ciphertext)
+    }
+
+    /// Rewrap an envelope's data key with the current key, leaving its
+    /// ciphertext unchanged
+    ///
+    /// Its `key_id` must match the key that wrapped its data key.
+    pub fn rewrap(&self, envelope: &Envelope) -> InfraResult<Envelope> {
+        Self::check_envelope(envelope)?;
+        Ok(Envelope {
+            key_id: self.current,
+            wrapped_key: self.reencrypt(&envelope.wrapped_key)?,
+            ciphertext: envelope.ciphertext.clone(),
+        })
+    }
+
+    fn check_envelope(envelope: &Envelope) -> InfraResult<()> {
+        let id = Self::key_id(&envelope.wrapped_key)?;
+        if id != envelope.key_id {
+            return Err(decrypt_error(&format!(
+                "Envelope key id {} does not match wrapping key {id}",
+                envelope.key_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for KeyRing {
+    fn encrypt(&self, plaintext: &[u8]) -> InfraResult<Vec<u8>> {
+        let ciphertext = self.keys[&self.current].cipher.encrypt(plaintext)?;
+        let mut result = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        result.push(FORMAT_V1);
+        result.extend_from_slice(&self.current.to_be_bytes());
+        result.extend(ciphertext);
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> InfraResult<Vec<u8>> {
+        let id = Self::key_id(ciphertext)?;
+        let key = self
+            .keys
+            .get(&id)
+            .ok_or_else(|| decrypt_error(&format!("Unknown key {id}")))?;
+        key.cipher.decrypt(&ciphertext[HEADER_LEN..])
+    }
+}
+
+impl std::fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyRing")
+            .field("key_ids", &self.key_ids())
+            .field("current", &self.current)
+            .field("rotation_period", &self.rotation_period)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Data encrypted under a data key wrapped by a [`KeyRing`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Id of the ring key wrapping the data key
+    pub key_id: u32,
+    /// Data key encrypted with the ring
+    pub wrapped_key: Vec<u8>,
+    /// Data encrypted with the data key
+    pub ciphertext: Vec<u8>,
+}
+
+fn key_error(message: String) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::KeyGeneration,
+        message,
+        context: None,
+        source: None,
+    }
+}
+
+fn decrypt_error(message: &str) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::Decrypt,
+        message: message.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_old_keys() {
+        let mut ring = KeyRing::generate().unwrap();
+        let old = ring.encrypt(b"secret").unwrap();
+        assert_eq!(KeyRing::key_id(&old).unwrap(), 1);
+
+        assert_eq!(ring.rotate().unwrap(), 2);
+        let new = ring.encrypt(b"secret").unwrap();
+        assert_eq!(KeyRing::key_id(&new).unwrap(), 2);
+        assert_eq!(ring.decrypt(&old).unwrap(), b"secret");
+        assert_eq!(ring.decrypt(&new).unwrap(), b"secret");
+
+        assert!(ring.needs_reencryption(&old).unwrap());
+        let migrated = ring
+            .reencrypt_all([old.as_slice(), new.as_slice()])
+            .unwrap();
+        assert_eq!(migrated[1], new);
+        assert!(!ring.needs_reencryption(&migrated[0]).unwrap());
+
+        assert!(matches!(ring.retire(2), Err(InfraError::Validation { .. })));
+        ring.retire(1).unwrap();
+        assert!(matches!(ring.retire(1), Err(InfraError::NotFound { .. })));
+        assert_eq!(ring.key_ids(), [2]);
+        assert!(ring.decrypt(&old).is_err());
+        assert_eq!(ring.decrypt(&migrated[0]).unwrap(), b"secret");
+        assert!(ring.decrypt(b"\x07garbage").is_err());
+    }
+
+    #[test]
+    fn test_scheduled_rotation() {
+        let created_at = Utc::now() - Duration::days(40);
+        let mut ring = KeyRing::with_key(7, Aes256GcmCipher::generate().unwrap(), created_at)
+            .with_rotation_period(Duration::days(30));
+        assert!(ring.rotation_due());
+        assert_eq!(ring.rotate_if_due().unwrap(), Some(8));
+        assert_eq!(ring.rotate_if_due().unwrap(), None);
+        assert!(ring
+            .insert(7, Aes256GcmCipher::generate().unwrap(), created_at)
+            .is_err());
+
+        let mut full =
+            KeyRing::with_key(u32::MAX, Aes256GcmCipher::generate().unwrap(), created_at);
+        assert!(full.rotate().is_err());
+        assert_eq!(full.current_id(), u32::MAX);
+    }
+
+    #[test]
+    fn test_envelope_rewrap() {
+        let mut ring = KeyRing::generate().unwrap();
+        let envelope = ring.seal(b"large document").unwrap();
+        ring.rotate().unwrap();
+
+        let rewrapped = ring.rewrap(&envelope).unwrap();
+        assert_eq!(rewrapped.key_id, 2);
+        assert_eq!(rewrapped.ciphertext, envelope.ciphertext);
+        ring.retire(1).unwrap();
+        assert_eq!(ring.open(&rewrapped).unwrap(), b"large document");
+        assert!(ring.open(&envelope).is_err());
+
+        let mut mislabeled = rewrapped.clone();
+        mislabeled.key_id = 1;
+        assert!(ring.open(&mislabeled).is_err());
+        assert!(ring.rewrap(&mislabeled).is_err());
+
+        let json = serde_json::to_string(&rewrapped).unwrap();
+        assert_eq!(serde_json::from_str::<Envelope>(&json).unwrap(), rewrapped);
+    }
+
+    #[test]
+    fn test_key_escrow() {
+        let ring = KeyRing::generate().unwrap();
+        let ciphertext = ring.encrypt(b"secret").unwrap();
+        let shares = ring.split_key(1, 2, 3).unwrap();
+        assert!(ring.split_key(2, 2, 3).is_err());
+
+        assert!(shares.iter().all(|share| share.key_id() == Some(1)));
+
+        let (id, key) = KeyRing::recover_key(&shares[1..]).unwrap();
+        assert_eq!(id, 1);
+        let restored = KeyRing::with_key(id, key, Utc::now());
+        assert_eq!(restored.decrypt(&ciphertext).unwrap(), b"secret");
+        assert!(KeyRing::recover_key(&shares[..1]).is_err());
+        let plain = shamir::split(&SecretBytes::random(32), 2, 3).unwrap();
+        assert!(KeyRing::recover_key(&plain).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! Passphrase-encrypted key storage.
+
+use crate::cipher::Aes256GcmCipher;
+use crate::hash::{PasswordAlgorithm, PasswordHasher};
+use crate::secret::{decode_base64, encode_base64, SecretBytes};
+use crate::sign::Keypair;
+use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use zeroize::Zeroize;
+
+/// Current keystore format version
+const VERSION: u32 = 1;
+
+/// Key derivation function of version 1
+const KDF: &str = "argon2id";
+
+/// Cipher of version 1
+const CIPHER: &str = "aes-256-gcm";
+
+/// Salt length in bytes
+const SALT_LEN: usize = 16;
+
+/// Largest Argon2id memory cost accepted, in KiB (1 GiB)
+const MAX_MEMORY_COST: u32 = 1 << 20;
+
+/// Largest Argon2id time cost accepted
+const MAX_TIME_COST: u32 = 16;
+
+/// Largest Argon2id parallelism accepted
+const MAX_PARALLELISM: u32 = 16;
+
+/// Named keys encrypted at rest with a passphrase
+///
+/// Keystores are stored as JSON documents holding a format version, the
+/// Argon2id parameters and salt, and the entries encrypted with AES-256-GCM
+/// under a key derived from the passphrase. Every save uses a fresh salt.
+/// The version and parameters are authenticated along with the entries, and
+/// files asking for more than 1 GiB of memory, 16 passes or 16 lanes are
+/// rejected before any key is derived.
+pub struct Keystore {
+    entries: BTreeMap<String, SecretBytes>,
+    kdf: PasswordAlgorithm,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    kdf: KdfParams,
+    cipher: String,
+    ciphertext: String,
+}
+
+/// Everything in a keystore file but the ciphertext, authenticated as the
+/// cipher's associated data
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u32,
+    kdf: &'a KdfParams,
+    cipher: &'a str,
+}
+
+impl Header<'_> {
+    fn aad(&self) -> InfraResult<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| keystore_error(CryptoOperation::Encrypt, &e))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl Default for Keystore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keystore {
+    /// Create an empty keystore with default Argon2id settings
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            kdf: PasswordAlgorithm::default(),
+        }
+    }
+
+    /// Set the passphrase key derivation parameters used when saving
+    #[must_use]
+    pub fn with_kdf(mut self, algorithm: PasswordAlgorithm) -> Self {
+        self.kdf = algorithm;
+        self
+    }
+
+    /// Add a key, returning the one it replaces
+    pub fn insert(&mut self, name: impl Into<String>, key: SecretBytes) -> Option<SecretBytes> {
+        self.entries.insert(name.into(), key)
+    }
+
+    /// Get a key
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&SecretBytes> {
+        self.entries.get(name)
+    }
+
+    /// Remove a key
+    pub fn remove(&mut self, name: &str) -> Option<SecretBytes> {
+        self.entries.remove(name)
+    }
+
+    /// Get the key names, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Get the number of keys
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if there are no keys
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add an Ed25519 keypair, stored as PKCS#8
+    pub fn insert_keypair(
+        &mut self,
+        name: impl Into<String>,
+        keypair: &Keypair,
+    ) -> InfraResult<()> {
+        self.insert(name, keypair.to_pkcs8_der()?);
+        Ok(())
+    }
+
+    /// Get an Ed25519 keypair added with [`insert_keypair`](Self::insert_keypair)
+    pub fn keypair(&self, name: &str) -> InfraResult<Option<Keypair>> {
+        self.get(name)
+            .map(|der| Keypair::from_pkcs8_der(der.expose_secret()))
+            .transpose()
+    }
+
+    /// Encrypt the keystore with a passphrase
+    pub fn to_bytes(&self, passphrase: &str) -> InfraResult<Vec<u8>> {
+        let PasswordAlgorithm::Argon2id {
+            memory_cost,
+            time_cost,
+            parallelism,
+        } = self.kdf;
+        check_kdf(memory_cost, time_cost, parallelism)?;
+        let salt = SecretBytes::random(SALT_LEN);
+        let key = PasswordHasher::with_algorithm(self.kdf)
+            .derive_key(passphrase, salt.expose_secret())?;
+        let cipher = Aes256GcmCipher::from_secret(&key)?;
+
+        let mut encoded: BTreeMap<&str, String> = self
+            .entries
+            .iter()
+            .map(|(name, key)| (name.as_str(), encode_base64(key.expose_secret())))
+            .collect();
+        let plaintext = serde_json::to_vec(&encoded).map(SecretBytes::new);
+        encoded.values_mut().for_each(Zeroize::zeroize);
+        let plaintext = plaintext.map_err(|e| keystore_error(CryptoOperation::Encrypt, &e))?;
+
+        let kdf = KdfParams {
+            algorithm: KDF.to_string(),
+            salt: encode_base64(salt.expose_secret()),
+            memory_cost,
+            time_cost,
+            parallelism,
+        };
+        let aad = Header {
+            version: VERSION,
+            kdf: &kdf,
+            cipher: CIPHER,
+        }
+        .aad()?;
+        let file = KeystoreFile {
+            version: VERSION,
+            kdf,
+            cipher: CIPHER.to_string(),
+            ciphertext: encode_base64(&cipher.encrypt_with_aad(plaintext.expose_secret(), &aad)?),
+        };
+        serde_json::to_vec_pretty(&file).map_err(|e| keystore_error(CryptoOperation::Encrypt, &e))
+    }
+
+    /// Decrypt a keystore with its passphrase
+    pub fn from_bytes(data: &[u8], passphrase: &str) -> InfraResult<Self> {
+        let file: KeystoreFile = serde_json::from_slice(data)
+            .map_err(|e| keystore_error(CryptoOperation::Decrypt, &e))?;
+        if file.version != VERSION {
+            return Err(keystore_error(
+                CryptoOperation::Decrypt,
+                &format!("Unsupported keystore version {}", file.version),
+            ));
+        }
+        if file.kdf.algorithm != KDF || file.cipher != CIPHER {
+            return Err(keystore_error(
+                CryptoOperation::Decrypt,
+                &format!(
+                    "Unsupported keystore algorithms {}/{}",
+                    file.kdf.algorithm, file.cipher
+                ),
+            ));
+        }
+
+        check_kdf(
+            file.kdf.memory_cost,
+            file.kdf.time_cost,
+            file.kdf.parallelism,
+        )?;
+
+        let kdf = PasswordAlgorithm::Argon2id {
+            memory_cost: file.kdf.memory_cost,
+            time_cost: file.kdf.time_cost,
+            parallelism: file.kdf.parallelism,
+        };
+        let aad = Header {
+            version: file.version,
+            kdf: &file.kdf,
+            cipher: &file.cipher,
+        }
+        .aad()?;
+        let salt = decode_base64(&file.kdf.salt)?;
+        let key = PasswordHasher::with_algorithm(kdf).derive_key(passphrase, &salt)?;
+        let plaintext = Aes256GcmCipher::from_secret(&key)?
+            .decrypt_with_aad(&decode_base64(&file.ciphertext)?, &aad)
+            .map(SecretBytes::new)
+            .map_err(|_| {
+                keystore_error(
+                    CryptoOperation::Decrypt,
+                    &"Wrong passphrase or corrupted keystore",
+                )
+            })?;
+
+        let mut encoded: BTreeMap<String, String> =
+            serde_json::from_slice(plaintext.expose_secret())
+                .map_err(|e| keystore_error(CryptoOperation::Decrypt, &e))?;
+        let entries = encoded
+            .iter()
+            .map(|(name, key)| Ok((name.clone(), SecretBytes::from_base64(key)?)))
+            .collect::<InfraResult<_>>();
+        encoded.values_mut().for_each(Zeroize::zeroize);
+        Ok(Self {
+            entries: entries?,
+            kdf,
+        })
+    }
+
+    /// Encrypt the keystore and write it to a file
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>, passphrase: &str) -> InfraResult<()> {
+        infra_fs::write(path, &self.to_bytes(passphrase)?)
+    }
+
+    /// Read and decrypt a keystore file
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<std::path::Path>, passphrase: &str) -> InfraResult<Self> {
+        Self::from_bytes(&infra_fs::read(path)?, passphrase)
+    }
+}
+
+impl std::fmt::Debug for Keystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keystore")
+            .field("names", &self.entries.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reject Argon2id parameters costlier than keystores may ask for
+fn check_kdf(memory_cost: u32, time_cost: u32, parallelism: u32) -> InfraResult<()> {
+    if memory_cost > MAX_MEMORY_COST || time_cost > MAX_TIME_COST || parallelism > MAX_PARALLELISM {
+        return Err(keystore_error(
+            CryptoOperation::KeyDerivation,
+            &format!(
+                "Keystore KDF parameters exceed the limits (memory {memory_cost} KiB, \
+                 time {time_cost}, parallelism {parallelism})"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn keystore_error(operation: CryptoOperation, e: &dyn std::fmt::Display) -> InfraError {
+    InfraError::Crypto {
+        operation,
+        message: e.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_kdf() -> PasswordAlgorithm {
+        PasswordAlgorithm::Argon2id {
+            memory_cost: 1024,
+            time_cost: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let keypair = Keypair::generate();
+        let mut keystore = Keystore::new().with_kdf(fast_kdf());
+        keystore.insert_keypair("signing", &keypair).unwrap();
+        keystore.insert("data", SecretBytes::random(32));
+
+        let data = keystore.to_bytes("correct horse").unwrap();
+        assert!(!String::from_utf8_lossy(&data).contains(&encode_base64(
+            keystore.get("data").unwrap().expose_secret()
+        )));
+        assert!(Keystore::from_bytes(&data, "wrong horse").is_err());
+
+        let loaded = Keystore::from_bytes(&data, "correct horse").unwrap();
+        assert_eq!(loaded.names().collect::<Vec<_>>(), ["data", "signing"]);
+        assert_eq!(loaded.get("data"), keystore.get("data"));
+        let restored = loaded.keypair("signing").unwrap().unwrap();
+        assert_eq!(restored.public_key(), keypair.public_key());
+        assert!(loaded.keypair("missing").unwrap().is_none());
+        assert!(format!("{loaded:?}").contains("signing"));
+    }
+
+    #[test]
+    fn test_keystore_version() {
+        let data = Keystore::new()
+            .with_kdf(fast_kdf())
+            .to_bytes("passphrase")
+            .unwrap();
+        let mut file: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(file["version"], 1);
+        file["version"] = 2.into();
+        let result = Keystore::from_bytes(&serde_json::to_vec(&file).unwrap(), "passphrase");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_keystore_kdf_params() {
+        let data = Keystore::new()
+            .with_kdf(fast_kdf())
+            .to_bytes("passphrase")
+            .unwrap();
+        let file: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        let load = |key: &str, value: u32| {
+            let mut file = file.clone();
+            file["kdf"][key] = value.into();
+            Keystore::from_bytes(&serde_json::to_vec(&file).unwrap(), "passphrase")
+        };
+
+        // Parameters are authenticated, so changing them fails decryption
+        assert!(load("time_cost", 2).is_err());
+        // Costly parameters are rejected before deriving a key
+        for (key, value) in [
+            ("memory_cost", u32::MAX),
+            ("time_cost", 1000),
+            ("parallelism", 255),
+        ] {
+            let err = load(key, value).unwrap_err();
+            assert!(err.to_string().contains("exceed"), "{key}: {err}");
+        }
+        assert!(load("time_cost", 1).is_ok());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_keystore_file() {
+        let dir = infra_fs::TempDir::new().unwrap();
+        let path = dir.path().join("keys.json");
+        let mut keystore = Keystore::new().with_kdf(fast_kdf());
+        keystore.insert("api", SecretBytes::from(b"key".as_slice()));
+        keystore.save(&path, "passphrase").unwrap();
+        let loaded = Keystore::load(&path, "passphrase").unwrap();
+        assert_eq!(loaded.get("api"), keystore.get("api"));
+    }
+}
@@ -1,21 +1,44 @@
 //! Cryptographic utilities for LLM-Dev-Ops infrastructure.
 //!
 //! Provides:
-//! - Hashing (SHA256, Blake3)
+//! - Hashing (SHA256, Blake3), streamed and over files
+//! - Message authentication (HMAC-SHA256, keyed Blake3)
 //! - Password hashing (Argon2id)
-//! - Symmetric encryption (AES-256-GCM)
+//! - Symmetric encryption (AES-256-GCM), and deterministic encryption for
+//!   lookup keys
+//! - Key rotation and envelope encryption
+//! - Shamir secret sharing, to escrow keys among operators
 //! - Digital signatures (Ed25519)
+//! - PEM/PKCS#8 key encoding and passphrase-encrypted keystores
 //! - JWT support
+//! - Constant-time comparison, zeroized secrets and encoding helpers
 
 mod hash;
+mod mac;
 mod cipher;
+mod deterministic;
+mod keyring;
+mod keystore;
+mod pem;
 mod sign;
+mod secret;
 pub mod jwt;
+pub mod shamir;
 
-pub use hash::{Hasher, Sha256Hasher, Blake3Hasher, PasswordHasher, PasswordAlgorithm};
+pub use hash::{DigestStream, Hasher, Sha256Hasher, Blake3Hasher, PasswordHasher, PasswordAlgorithm};
+pub use mac::{Hmac, Mac, MacStream};
 pub use cipher::{Cipher, Aes256GcmCipher};
+pub use deterministic::DeterministicCipher;
+pub use keyring::{Envelope, KeyRing};
+pub use keystore::Keystore;
+pub use pem::{der_to_pem, key_algorithm, pem_to_der, KeyAlgorithm, PRIVATE_KEY_LABEL, PUBLIC_KEY_LABEL};
 pub use sign::{Signer, Verifier, Ed25519Signer, Ed25519Verifier, Signature, PublicKey, Keypair};
-pub use jwt::{JwtSigner, JwtAlgorithm, Claims};
+pub use secret::{
+    constant_time_eq, decode_base64, decode_hex, encode_base64, encode_base64url, encode_hex,
+    random_token, SecretBytes,
+};
+pub use shamir::Share;
+pub use jwt::{JwtSigner, JwtAlgorithm, Claims, Jwk, JwkSet, TokenHeader, ValidationOptions};
 
 #[cfg(feature = "wasm")]
 mod wasm;
@@ -0,0 +1,115 @@
+//! PEM and PKCS#8 key encoding.
+
+use crate::secret::SecretBytes;
+use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use pkcs8::der::pem::LineEnding;
+use pkcs8::spki::SubjectPublicKeyInfoRef;
+use pkcs8::{ObjectIdentifier, PrivateKeyInfo};
+
+/// PEM label of PKCS#8 private keys
+pub const PRIVATE_KEY_LABEL: &str = "PRIVATE KEY";
+
+/// PEM label of SPKI public keys
+pub const PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const RSA_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+
+/// Algorithm of an encoded key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    /// Ed25519, usable with the sign module and `EdDSA` JWTs
+    Ed25519,
+    /// RSA, usable with RS256 JWTs
+    Rsa,
+    /// Elliptic curve, usable with ES256 JWTs when on P-256
+    Ec,
+}
+
+impl KeyAlgorithm {
+    fn from_oid(oid: ObjectIdentifier) -> InfraResult<Self> {
+        match oid {
+            ED25519_OID => Ok(Self::Ed25519),
+            RSA_OID => Ok(Self::Rsa),
+            EC_OID => Ok(Self::Ec),
+            _ => Err(parse_error(&format!("Unsupported key algorithm {oid}"))),
+        }
+    }
+}
+
+/// Decode a PEM document into its label and DER contents
+pub fn pem_to_der(pem: &str) -> InfraResult<(String, SecretBytes)> {
+    pkcs8::der::pem::decode_vec(pem.trim().as_bytes())
+        .map(|(label, der)| (label.to_string(), SecretBytes::new(der)))
+        .map_err(|e| parse_error(&format!("Invalid PEM: {e}")))
+}
+
+/// Encode DER contents as a PEM document
+pub fn der_to_pem(label: &str, der: &[u8]) -> InfraResult<String> {
+    pkcs8::der::pem::encode_string(label, LineEnding::LF, der)
+        .map_err(|e| pem_error(&format!("Cannot encode PEM: {e}")))
+}
+
+/// Get the algorithm of a DER-encoded PKCS#8 private key or SPKI public
+/// key
+///
+/// Lets callers route imported keys to the right signer before parsing
+/// them.
+pub fn key_algorithm(der: &[u8]) -> InfraResult<KeyAlgorithm> {
+    if let Ok(info) = PrivateKeyInfo::try_from(der) {
+        return KeyAlgorithm::from_oid(info.algorithm.oid);
+    }
+    let info = SubjectPublicKeyInfoRef::try_from(der)
+        .map_err(|e| parse_error(&format!("Not a PKCS#8 or SPKI key: {e}")))?;
+    KeyAlgorithm::from_oid(info.algorithm.oid)
+}
+
+/// Error for key material that cannot be parsed
+pub(crate) fn parse_error(message: &str) -> InfraError {
+    InfraError::validation(message)
+}
+
+/// Error for a key that cannot be encoded
+pub(crate) fn pem_error(message: &str) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::KeyGeneration,
+        message: message.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_algorithm() {
+        for (pem, label, algorithm) in [
+            (
+                include_str!("../testdata/rsa_public.pem"),
+                PUBLIC_KEY_LABEL,
+                KeyAlgorithm::Rsa,
+            ),
+            (
+                include_str!("../testdata/ec_private.pem"),
+                PRIVATE_KEY_LABEL,
+                KeyAlgorithm::Ec,
+            ),
+            (
+                include_str!("../testdata/ec_public.pem"),
+                PUBLIC_KEY_LABEL,
+                KeyAlgorithm::Ec,
+            ),
+        ] {
+            let (decoded_label, der) = pem_to_der(pem).unwrap();
+            assert_eq!(decoded_label, label);
+            assert_eq!(key_algorithm(der.expose_secret()).unwrap(), algorithm);
+            let reencoded = der_to_pem(label, der.expose_secret()).unwrap();
+            assert_eq!(pem_to_der(&reencoded).unwrap().1, der);
+        }
+        assert!(pem_to_der("not pem").is_err());
+        assert!(key_algorithm(b"\x30\x00").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Secret handling and encoding utilities.
+
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use infra_errors::{InfraError, InfraResult};
+use rand::RngCore;
+use zeroize::Zeroize;
+
+/// Compare two byte strings in time independent of their contents
+///
+/// Use this instead of `==` for MACs, tokens and other secrets, so an
+/// attacker cannot learn how many leading bytes of a guess are right from
+/// response times. Only the lengths leak.
+#[must_use]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    constant_time_eq::constant_time_eq(a, b)
+}
+
+/// Secret bytes, such as a key, wiped from memory on drop
+///
+/// `Debug` prints only the length, so secrets do not end up in logs, and
+/// equality is constant-time. Read the bytes with
+/// [`expose_secret`](Self::expose_secret).
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Take ownership of secret bytes
+    #[must_use]
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    /// Generate `len` random bytes
+    #[must_use]
+    pub fn random(len: usize) -> Self {
+        let mut bytes = vec![0u8; len];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// Decode a secret from standard or URL-safe base64
+    pub fn from_base64(encoded: &str) -> InfraResult<Self> {
+        decode_base64(encoded).map(Self)
+    }
+
+    /// Decode a secret from hex
+    pub fn from_hex(encoded: &str) -> InfraResult<Self> {
+        decode_hex(encoded).map(Self)
+    }
+
+    /// Get the secret bytes
+    #[must_use]
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Get the secret bytes for writing, as when filling a buffer
+    pub fn expose_secret_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+
+    /// Get the length in bytes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check if the secret is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl std::fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SecretBytes([REDACTED; {}])", self.0.len())
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Encode bytes as standard, padded base64
+#[must_use]
+pub fn encode_base64(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+/// Encode bytes as unpadded URL-safe base64, as used in tokens and JWKs
+#[must_use]
+pub fn encode_base64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Generate a random token of `len` bytes as unpadded URL-safe base64, for
+/// session ids, refresh tokens and the like
+///
+/// The token is a plain `String` handed out to clients, so unlike
+/// [`SecretBytes`] it is not wiped from memory.
+#[must_use]
+pub fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decode standard or URL-safe base64, with or without padding
+///
+/// Surrounding whitespace is ignored. Mixing the two alphabets (`+` or `/`
+/// along with `-` or `_`) is an error.
+pub fn decode_base64(encoded: &str) -> InfraResult<Vec<u8>> {
+    let unpadded = encoded.trim().trim_end_matches('=');
+    let standard = unpadded.contains(['+', '/']);
+    if standard && unpadded.contains(['-', '_']) {
+        return Err(InfraError::validation(
+            "Invalid base64: mixes standard and URL-safe alphabets",
+        ));
+    }
+    let engine = if standard {
+        &STANDARD_NO_PAD
+    } else {
+        &URL_SAFE_NO_PAD
+    };
+    engine
+        .decode(unpadded)
+        .map_err(|e| decode_error("base64", &e))
+}
+
+/// Encode bytes as lowercase hex
+#[must_use]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    hex::encode(bytes)
+}
+
+/// Decode hex in either case
+///
+/// Surrounding whitespace is ignored.
+pub fn decode_hex(encoded: &str) -> InfraResult<Vec<u8>> {
+    hex::decode(encoded.trim()).map_err(|e| decode_error("hex", &e))
+}
+
+fn decode_error(encoding: &str, e: &dyn std::fmt::Display) -> InfraError {
+    InfraError::validation(format!("Invalid {encoding}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_bytes_redacted() {
+        let secret = SecretBytes::from(b"hunter2".as_slice());
+        assert_eq!(format!("{secret:?}"), "SecretBytes([REDACTED; 7])");
+        assert_eq!(secret, SecretBytes::new(b"hunter2".to_vec()));
+        assert_ne!(secret, SecretBytes::new(b"hunter3".to_vec()));
+        assert_eq!(SecretBytes::random(32).len(), 32);
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+
+    #[test]
+    fn test_encoding_helpers() {
+        let bytes = [0xfb, 0xff, 0x01, 0x02];
+        assert_eq!(encode_base64(&bytes), "+/8BAg==");
+        assert_eq!(encode_base64url(&bytes), "-_8BAg");
+        for encoded in ["+/8BAg==", "+/8BAg", "-_8BAg", " -_8BAg==\n"] {
+            assert_eq!(decode_base64(encoded).unwrap(), bytes);
+        }
+        assert!(decode_base64("not base64!").is_err());
+        assert!(decode_base64("+_8BAg").is_err());
+        assert_eq!(decode_base64(&random_token(32)).unwrap().len(), 32);
+
+        assert_eq!(encode_hex(&bytes), "fbff0102");
+        assert_eq!(decode_hex("FBFF0102").unwrap(), bytes);
+        assert!(decode_hex("abc").is_err());
+        let secret = SecretBytes::from_hex("fbff0102").unwrap();
+        assert_eq!(secret, SecretBytes::from_base64("+/8BAg==").unwrap());
+    }
+}
@@ -0,0 +1,500 @@
+//! Shamir secret sharing.
+//!
+//! A secret is split byte by byte over GF(2^8): each byte becomes the
+//! constant term of a random polynomial of degree `threshold - 1`, and each
+//! share holds the polynomials evaluated at its index. Any `threshold`
+//! shares recover the secret by Lagrange interpolation, while fewer reveal
+//! nothing about it.
+//!
+//! Every share also carries a commitment to the secret, a BLAKE3 MAC over
+//! the split's metadata keyed by the secret and a random salt. The salt is
+//! split along with the secret, so checking a guessed secret against the
+//! commitment needs `threshold` shares too, and a single share does not
+//! allow testing guesses of a low-entropy secret offline. Recovery checks
+//! the commitment, so a tampered share is rejected rather than silently
+//! recovering a wrong secret: forging a matching commitment needs the
+//! secret and salt themselves.
+
+use crate::secret::{decode_base64, encode_base64url, SecretBytes};
+use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeSet;
+
+/// Format byte leading every encoded share
+const FORMAT_V1: u8 = 1;
+
+/// Length of the random id shared by the shares of one split
+const SET_ID_LEN: usize = 8;
+
+/// Length of the format byte, set id, threshold, index, key id flag and
+/// key id
+const HEADER_LEN: usize = 1 + SET_ID_LEN + 2 + 1 + 4;
+
+/// Length of the commitment to the secret
+const COMMITMENT_LEN: usize = 16;
+
+/// Length of the random salt split after the secret, keying the commitment
+const SALT_LEN: usize = 16;
+
+/// Context deriving the commitment key from the secret
+const COMMITMENT_CONTEXT: &str = "infra-crypto 2024-01-01 shamir commitment";
+
+/// Length of the checksum ending every encoded share
+const CHECKSUM_LEN: usize = 4;
+
+/// One share of a secret split with [`split`]
+///
+/// Shares encode to URL-safe base64 strings, which is also their serde
+/// representation, holding a format byte, the id of the split they belong
+/// to, the threshold, the share index, the id of the key ring key if any,
+/// the commitment to the secret, the share value (of the secret followed by
+/// the commitment salt) and a checksum catching transcription errors.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    set_id: [u8; SET_ID_LEN],
+    threshold: u8,
+    index: u8,
+    key_id: Option<u32>,
+    commitment: [u8; COMMITMENT_LEN],
+    value: SecretBytes,
+}
+
+impl Share {
+    /// Get the number of shares needed to recover the secret
+    #[must_use]
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Get the index of the share, from 1
+    #[must_use]
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// Get the id of the key ring key the shares recover, if split with
+    /// [`KeyRing::split_key`](crate::KeyRing::split_key)
+    #[must_use]
+    pub fn key_id(&self) -> Option<u32> {
+        self.key_id
+    }
+
+    /// Get the id shared by all shares of the same split, as hex
+    #[must_use]
+    pub fn set_id(&self) -> String {
+        hex::encode(self.set_id)
+    }
+
+    /// Encode the share as a URL-safe base64 string
+    #[must_use]
+    pub fn encode(&self) -> String {
+        // Sized up front so the share value is never left in a reallocated
+        // buffer
+        let mut data =
+            Vec::with_capacity(HEADER_LEN + COMMITMENT_LEN + self.value.len() + CHECKSUM_LEN);
+        data.push(FORMAT_V1);
+        data.extend_from_slice(&self.set_id);
+        data.push(self.threshold);
+        data.push(self.index);
+        data.push(u8::from(self.key_id.is_some()));
+        data.extend_from_slice(&self.key_id.unwrap_or_default().to_be_bytes());
+        data.extend_from_slice(&self.commitment);
+        data.extend_from_slice(self.value.expose_secret());
+        let checksum = checksum(&data);
+        data.extend_from_slice(&checksum);
+        encode_base64url(SecretBytes::new(data).expose_secret())
+    }
+
+    /// Decode a share encoded with [`encode`](Self::encode)
+    pub fn decode(encoded: &str) -> InfraResult<Self> {
+        let bytes = SecretBytes::new(decode_base64(encoded)?);
+        let data = bytes.expose_secret();
+        if data.len() <= HEADER_LEN + COMMITMENT_LEN + SALT_LEN + CHECKSUM_LEN {
+            return Err(InfraError::validation("Share too short"));
+        }
+        let (data, expected) = data.split_at(data.len() - CHECKSUM_LEN);
+        if !crate::secret::constant_time_eq(&checksum(data), expected) {
+            return Err(InfraError::validation("Share checksum mismatch"));
+        }
+        if data[0] != FORMAT_V1 {
+            return Err(InfraError::validation(format!(
+                "Unknown share format {}",
+                data[0]
+            )));
+        }
+
+        let mut set_id = [0; SET_ID_LEN];
+        set_id.copy_from_slice(&data[1..=SET_ID_LEN]);
+        let mut key_id = [0; 4];
+        key_id.copy_from_slice(&data[SET_ID_LEN + 4..HEADER_LEN]);
+        let mut commitment = [0; COMMITMENT_LEN];
+        commitment.copy_from_slice(&data[HEADER_LEN..HEADER_LEN + COMMITMENT_LEN]);
+        let share = Self {
+            set_id,
+            threshold: data[SET_ID_LEN + 1],
+            index: data[SET_ID_LEN + 2],
+            key_id: match data[SET_ID_LEN + 3] {
+                0 => None,
+                1 => Some(u32::from_be_bytes(key_id)),
+                flag => {
+                    return Err(InfraError::validation(format!(
+                        "Invalid share key id flag {flag}"
+                    )))
+                }
+            },
+            commitment,
+            value: SecretBytes::from(&data[HEADER_LEN + COMMITMENT_LEN..]),
+        };
+        if share.index == 0 || share.threshold < 2 {
+            return Err(InfraError::validation("Invalid share index or threshold"));
+        }
+        Ok(share)
+    }
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Share")
+            .field("set_id", &self.set_id())
+            .field("threshold", &self.threshold)
+            .field("index", &self.index)
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Serialize for Share {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for Share {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        Self::decode(&encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Split a secret into `count` shares, any `threshold` of which recover it
+///
+/// The threshold must be at least 2 and at most `count`, which is at most
+/// 255.
+pub fn split(secret: &SecretBytes, threshold: u8, count: u8) -> InfraResult<Vec<Share>> {
+    split_shares(secret, None, threshold, count)
+}
+
+/// Split a key ring key into shares bound to its id, like [`split`]
+///
+/// The id is covered by the commitment and returned by
+/// [`Share::key_id`], so the key can be restored under its original id.
+pub fn split_with_key_id(
+    secret: &SecretBytes,
+    key_id: u32,
+    threshold: u8,
+    count: u8,
+) -> InfraResult<Vec<Share>> {
+    split_shares(secret, Some(key_id), threshold, count)
+}
+
+fn split_shares(
+    secret: &SecretBytes,
+    key_id: Option<u32>,
+    threshold: u8,
+    count: u8,
+) -> InfraResult<Vec<Share>> {
+    if threshold < 2 || threshold > count {
+        return Err(InfraError::validation(format!(
+            "Threshold must be between 2 and the share count {count}, got {threshold}"
+        )));
+    }
+    if secret.is_empty() {
+        return Err(InfraError::validation("Cannot split an empty secret"));
+    }
+
+    let mut set_id = [0; SET_ID_LEN];
+    set_id.copy_from_slice(SecretBytes::random(SET_ID_LEN).expose_secret());
+    // The secret followed by a random salt keying the commitment
+    let mut payload = SecretBytes::random(secret.len() + SALT_LEN);
+    payload.expose_secret_mut()[..secret.len()].copy_from_slice(secret.expose_secret());
+    // Coefficients of degree 1 and up, `threshold - 1` per payload byte
+    let degree = usize::from(threshold - 1);
+    let coefficients = SecretBytes::random(payload.len() * degree);
+    let commitment = commitment(&payload, set_id, threshold, key_id);
+
+    Ok((1..=count)
+        .map(|x| {
+            let value = payload
+                .expose_secret()
+                .iter()
+                .zip(coefficients.expose_secret().chunks(degree))
+                .map(|(&constant, higher)| {
+                    // Horner's method, from the highest degree down
+                    higher
+                        .iter()
+                        .rev()
+                        .chain(std::iter::once(&constant))
+                        .fold(0, |acc, &c| gf_mul(acc, x) ^ c)
+                })
+                .collect::<Vec<_>>();
+            Share {
+                set_id,
+                threshold,
+                index: x,
+                key_id,
+                commitment,
+                value: SecretBytes::new(value),
+            }
+        })
+        .collect())
+}
+
+/// Recover a secret from at least its threshold of shares
+///
+/// All shares must come from the same split and have distinct indices. The
+/// recovered secret is checked against the shares' commitment, and shares
+/// beyond the threshold must agree with it.
+pub fn combine(shares: &[Share]) -> InfraResult<SecretBytes> {
+    let first = shares
+        .first()
+        .ok_or_else(|| combine_error("No shares given".to_string()))?;
+    if shares.iter().any(|share| {
+        share.set_id != first.set_id
+            || share.threshold != first.threshold
+            || share.key_id != first.key_id
+            || share.commitment != first.commitment
+            || share.value.len() != first.value.len()
+    }) {
+        return Err(combine_error(
+            "Shares come from different splits".to_string(),
+        ));
+    }
+    let indices: BTreeSet<u8> = shares.iter().map(|share| share.index).collect();
+    if indices.len() != shares.len() {
+        return Err(combine_error("Duplicate share index".to_string()));
+    }
+    if shares.len() < usize::from(first.threshold) {
+        return Err(combine_error(format!(
+            "Need {} shares, got {}",
+            first.threshold,
+            shares.len()
+        )));
+    }
+
+    let (shares, extra) = shares.split_at(usize::from(first.threshold));
+    let payload = interpolate(shares, 0);
+    let expected = commitment(&payload, first.set_id, first.threshold, first.key_id);
+    if !crate::secret::constant_time_eq(&expected, &first.commitment) {
+        return Err(combine_error(
+            "Recovered secret does not match the share commitment".to_string(),
+        ));
+    }
+    for share in extra {
+        let value = interpolate(shares, share.index);
+        if !crate::secret::constant_time_eq(value.expose_secret(), share.value.expose_secret()) {
+            return Err(combine_error(format!(
+                "Share {} does not match the other shares",
+                share.index
+            )));
+        }
+    }
+    let secret = &payload.expose_secret()[..payload.len() - SALT_LEN];
+    Ok(SecretBytes::from(secret))
+}
+
+/// Evaluate the polynomials through `shares` at `x`
+fn interpolate(shares: &[Share], x: u8) -> SecretBytes {
+    // Lagrange basis polynomials evaluated at x
+    let weights: Vec<u8> = shares
+        .iter()
+        .map(|share| {
+            let (numerator, denominator) = shares
+                .iter()
+                .filter(|other| other.index != share.index)
+                .fold((1, 1), |(num, den), other| {
+                    (
+                        gf_mul(num, other.index ^ x),
+                        gf_mul(den, other.index ^ share.index),
+                    )
+                });
+            gf_mul(numerator, gf_inv(denominator))
+        })
+        .collect();
+
+    let mut result = SecretBytes::new(vec![0; shares[0].value.len()]);
+    for (share, &weight) in shares.iter().zip(&weights) {
+        for (byte, &y) in result
+            .expose_secret_mut()
+            .iter_mut()
+            .zip(share.value.expose_secret())
+        {
+            *byte ^= gf_mul(y, weight);
+        }
+    }
+    result
+}
+
+/// MAC the split's metadata with a key derived from the secret and salt
+fn commitment(
+    payload: &SecretBytes,
+    set_id: [u8; SET_ID_LEN],
+    threshold: u8,
+    key_id: Option<u32>,
+) -> [u8; COMMITMENT_LEN] {
+    let key = blake3::derive_key(COMMITMENT_CONTEXT, payload.expose_secret());
+    let mut hasher = blake3::Hasher::new_keyed(&key);
+    hasher.update(&set_id);
+    hasher.update(&[threshold, u8::from(key_id.is_some())]);
+    hasher.update(&key_id.unwrap_or_default().to_be_bytes());
+    hasher.update(&(payload.len() as u64).to_be_bytes());
+    let mut commitment = [0; COMMITMENT_LEN];
+    commitment.copy_from_slice(&hasher.finalize().as_bytes()[..COMMITMENT_LEN]);
+    commitment
+}
+
+/// Multiply in GF(2^8) with the AES polynomial, without data-dependent
+/// branches
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Invert a non-zero element of GF(2^8), as `a^254`
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = gf_mul(result, power);
+        }
+        power = gf_mul(power, power);
+        exponent >>= 1;
+    }
+    result
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut checksum = [0; CHECKSUM_LEN];
+    checksum.copy_from_slice(&blake3::hash(data).as_bytes()[..CHECKSUM_LEN]);
+    checksum
+}
+
+fn combine_error(message: String) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::KeyDerivation,
+        message,
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf_arithmetic() {
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        for a in 1..=255 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn test_split_combine() {
+        let secret = SecretBytes::random(32);
+        let shares = split(&secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        assert!(shares.iter().all(|share| share.threshold() == 3));
+
+        assert_eq!(combine(&shares[..3]).unwrap(), secret);
+        assert_eq!(combine(&shares[2..]).unwrap(), secret);
+        let mixed = [shares[4].clone(), shares[0].clone(), shares[2].clone()];
+        assert_eq!(combine(&mixed).unwrap(), secret);
+        assert_eq!(combine(&shares).unwrap(), secret);
+
+        assert!(combine(&shares[..2]).is_err());
+        assert!(combine(&[shares[0].clone(), shares[0].clone(), shares[1].clone()]).is_err());
+        let other = split(&secret, 3, 5).unwrap();
+        assert!(combine(&[shares[0].clone(), shares[1].clone(), other[2].clone()]).is_err());
+
+        assert_eq!(shares[0].key_id(), None);
+        let ids = split_with_key_id(&secret, 7, 3, 5).unwrap();
+        assert_eq!(ids[0].key_id(), Some(7));
+        assert_eq!(combine(&ids[1..4]).unwrap(), secret);
+
+        assert!(split(&secret, 1, 5).is_err());
+        assert!(split(&secret, 4, 3).is_err());
+        assert!(split(&SecretBytes::default(), 2, 3).is_err());
+    }
+
+    #[test]
+    fn test_commitment_is_salted() {
+        // A one-byte secret has 256 candidates, none of which a single share
+        // can confirm without the salt split alongside it
+        let secret = SecretBytes::from([42].as_slice());
+        let shares = split(&secret, 2, 3).unwrap();
+        assert_eq!(shares[0].value.len(), 1 + SALT_LEN);
+
+        let share = &shares[0];
+        for guess in 0..=255u8 {
+            for payload in [vec![guess], [vec![guess], vec![0; SALT_LEN]].concat()] {
+                let expected = commitment(
+                    &SecretBytes::new(payload),
+                    share.set_id,
+                    share.threshold,
+                    share.key_id,
+                );
+                assert_ne!(expected, share.commitment);
+            }
+        }
+        assert_eq!(combine(&shares[1..]).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_share_encoding() {
+        let shares = split(&SecretBytes::from(b"root key".as_slice()), 2, 3).unwrap();
+        let json = serde_json::to_string(&shares).unwrap();
+        let decoded: Vec<Share> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, shares);
+        assert_eq!(decoded[1].index(), 2);
+        assert_eq!(decoded[1].set_id(), shares[0].set_id());
+        assert!(!format!("{:?}", shares[0]).contains("value"));
+
+        let encoded = shares[0].encode();
+        let mut corrupted = encoded.clone().into_bytes();
+        corrupted[12] = if corrupted[12] == b'A' { b'B' } else { b'A' };
+        assert!(Share::decode(std::str::from_utf8(&corrupted).unwrap()).is_err());
+        assert!(Share::decode(&encoded[..8]).is_err());
+        assert_eq!(Share::decode(&encoded).unwrap(), shares[0]);
+    }
+
+    #[test]
+    fn test_tampered_share() {
+        let secret = SecretBytes::random(32);
+        let shares = split_with_key_id(&secret, 3, 2, 3).unwrap();
+
+        // A share altered and re-encoded with a valid checksum still fails
+        // the commitment
+        let mut tampered = shares[0].clone();
+        tampered.value.expose_secret_mut()[0] ^= 1;
+        let tampered = Share::decode(&tampered.encode()).unwrap();
+        assert!(combine(&[tampered.clone(), shares[1].clone()]).is_err());
+
+        // ... and is caught among extra shares too
+        assert!(combine(&[shares[1].clone(), shares[2].clone(), tampered]).is_err());
+
+        let mut relabeled = shares.clone();
+        for share in &mut relabeled {
+            share.key_id = Some(4);
+        }
+        assert!(combine(&relabeled[..2]).is_err());
+    }
+}
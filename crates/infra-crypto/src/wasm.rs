@@ -2,8 +2,15 @@
 
 use crate::hash::{blake3_hex, sha256_hex, Blake3Hasher, Hasher, Sha256Hasher};
 use crate::cipher::{Aes256GcmCipher, Cipher};
+use crate::jwt::{decode_header, Claims, Jwk, JwtSigner};
+use crate::mac::{Hmac, Mac};
+use crate::sign::{Ed25519Verifier, Keypair, PublicKey, Signature, Signer, Verifier};
 use wasm_bindgen::prelude::*;
 
+fn js_error(e: impl std::fmt::Display) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
 /// Hash data with SHA-256 and return hex
 #[wasm_bindgen(js_name = sha256Hex)]
 pub fn js_sha256_hex(data: &[u8]) -> String {
@@ -28,6 +35,201 @@ pub fn js_blake3_string(data: &str) -> String {
     blake3_hex(data.as_bytes())
 }
 
+/// Compute an HMAC-SHA256 tag and return hex
+#[wasm_bindgen(js_name = hmacSha256Hex)]
+#[must_use]
+pub fn js_hmac_sha256_hex(key: &[u8], data: &[u8]) -> String {
+    Hmac::sha256(key).tag_hex(data)
+}
+
+/// Verify an HMAC-SHA256 tag in constant time
+#[wasm_bindgen(js_name = hmacSha256Verify)]
+#[must_use]
+pub fn js_hmac_sha256_verify(key: &[u8], data: &[u8], tag: &[u8]) -> bool {
+    Hmac::sha256(key).verify(data, tag)
+}
+
+/// Verify a hex Ed25519 signature with a hex public key
+#[wasm_bindgen(js_name = ed25519Verify)]
+pub fn js_ed25519_verify(
+    public_key_hex: &str,
+    data: &[u8],
+    signature_hex: &str,
+) -> Result<bool, JsValue> {
+    let verifier =
+        Ed25519Verifier::from_public_key(&PublicKey::from_hex(public_key_hex).map_err(js_error)?)
+            .map_err(js_error)?;
+    verifier
+        .verify(data, &Signature::from_hex(signature_hex).map_err(js_error)?)
+        .map_err(js_error)
+}
+
+/// Read a token's header as JSON, without verifying the token
+#[wasm_bindgen(js_name = decodeJwtHeader)]
+pub fn js_decode_jwt_header(token: &str) -> Result<String, JsValue> {
+    let header = decode_header(token).map_err(js_error)?;
+    Ok(serde_json::json!({
+        "alg": header.algorithm.map(|algorithm| format!("{algorithm:?}")),
+        "kid": header.key_id,
+        "typ": header.token_type,
+    })
+    .to_string())
+}
+
+/// Ed25519 keypair for WASM
+#[wasm_bindgen]
+pub struct JsKeypair {
+    keypair: Keypair,
+}
+
+#[wasm_bindgen]
+impl JsKeypair {
+    /// Generate a new random keypair
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> JsKeypair {
+        JsKeypair {
+            keypair: Keypair::generate(),
+        }
+    }
+
+    /// Create from a 32-byte secret key
+    #[wasm_bindgen(js_name = fromSecretKey)]
+    pub fn from_secret_key(secret_key: &[u8]) -> Result<JsKeypair, JsValue> {
+        let bytes: &[u8; 32] = secret_key
+            .try_into()
+            .map_err(|_| JsValue::from_str("Secret key must be 32 bytes"))?;
+        Keypair::from_bytes(bytes)
+            .map(|keypair| JsKeypair { keypair })
+            .map_err(js_error)
+    }
+
+    /// Create from a PKCS#8 PEM private key
+    #[wasm_bindgen(js_name = fromPem)]
+    pub fn from_pem(pem: &str) -> Result<JsKeypair, JsValue> {
+        Keypair::from_pkcs8_pem(pem)
+            .map(|keypair| JsKeypair { keypair })
+            .map_err(js_error)
+    }
+
+    /// Get the public key as hex
+    #[wasm_bindgen(js_name = publicKeyHex)]
+    #[must_use]
+    pub fn public_key_hex(&self) -> String {
+        self.keypair.public_key().to_hex()
+    }
+
+    /// Sign data and return the signature as hex
+    pub fn sign(&self, data: &[u8]) -> Result<String, JsValue> {
+        self.keypair
+            .signer()
+            .sign(data)
+            .map(|signature| signature.to_hex())
+            .map_err(js_error)
+    }
+
+    /// Verify a hex signature
+    pub fn verify(&self, data: &[u8], signature_hex: &str) -> Result<bool, JsValue> {
+        js_ed25519_verify(&self.public_key_hex(), data, signature_hex)
+    }
+}
+
+impl Default for JsKeypair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// JWT signer and verifier for WASM
+///
+/// Claims are passed and returned as JSON strings.
+#[wasm_bindgen]
+pub struct JsJwt {
+    signer: JwtSigner,
+}
+
+#[wasm_bindgen]
+impl JsJwt {
+    /// Create an HS256 signer with a shared secret
+    #[must_use]
+    pub fn hs256(secret: &[u8]) -> JsJwt {
+        JsJwt {
+            signer: JwtSigner::hs256(secret),
+        }
+    }
+
+    /// Create an `EdDSA` signer from a keypair
+    pub fn eddsa(keypair: &JsKeypair) -> Result<JsJwt, JsValue> {
+        JwtSigner::eddsa(&keypair.keypair)
+            .map(|signer| JsJwt { signer })
+            .map_err(js_error)
+    }
+
+    /// Create an `EdDSA` verifier from a hex Ed25519 public key
+    #[wasm_bindgen(js_name = eddsaVerifier)]
+    pub fn eddsa_verifier(public_key_hex: &str) -> Result<JsJwt, JsValue> {
+        let public_key = PublicKey::from_hex(public_key_hex).map_err(js_error)?;
+        JwtSigner::eddsa_verifier(&public_key)
+            .map(|signer| JsJwt { signer })
+            .map_err(js_error)
+    }
+
+    /// Create an RS256 verifier from a PEM public key
+    #[wasm_bindgen(js_name = rs256Verifier)]
+    pub fn rs256_verifier(public_pem: &str) -> Result<JsJwt, JsValue> {
+        JwtSigner::rs256_verifier(public_pem.as_bytes())
+            .map(|signer| JsJwt { signer })
+            .map_err(js_error)
+    }
+
+    /// Create an ES256 verifier from a PEM public key
+    #[wasm_bindgen(js_name = es256Verifier)]
+    pub fn es256_verifier(public_pem: &str) -> Result<JsJwt, JsValue> {
+        JwtSigner::es256_verifier(public_pem.as_bytes())
+            .map(|signer| JsJwt { signer })
+            .map_err(js_error)
+    }
+
+    /// Create a verifier from a JSON Web Key
+    #[wasm_bindgen(js_name = fromJwk)]
+    pub fn from_jwk(jwk_json: &str) -> Result<JsJwt, JsValue> {
+        let jwk: Jwk = serde_json::from_str(jwk_json).map_err(js_error)?;
+        JwtSigner::from_jwk(&jwk)
+            .map(|signer| JsJwt { signer })
+            .map_err(js_error)
+    }
+
+    /// Only accept tokens issued by `issuer`
+    #[wasm_bindgen(js_name = withIssuer)]
+    #[must_use]
+    pub fn with_issuer(self, issuer: &str) -> JsJwt {
+        JsJwt {
+            signer: self.signer.with_issuer(issuer),
+        }
+    }
+
+    /// Only accept tokens intended for `audience`
+    #[wasm_bindgen(js_name = withAudience)]
+    #[must_use]
+    pub fn with_audience(self, audience: &str) -> JsJwt {
+        JsJwt {
+            signer: self.signer.with_audience(audience),
+        }
+    }
+
+    /// Sign JSON claims, which must include `exp` and `iat`
+    pub fn sign(&self, claims_json: &str) -> Result<String, JsValue> {
+        let claims: Claims = serde_json::from_str(claims_json).map_err(js_error)?;
+        self.signer.sign(&claims).map_err(js_error)
+    }
+
+    /// Verify a token and return its claims as JSON
+    pub fn verify(&self, token: &str) -> Result<String, JsValue> {
+        let claims: Claims = self.signer.verify(token).map_err(js_error)?;
+        serde_json::to_string(&claims).map_err(js_error)
+    }
+}
+
 /// AES-256-GCM cipher for WASM
 #[wasm_bindgen]
 pub struct JsAes256Gcm {
@@ -41,7 +243,7 @@ impl JsAes256Gcm {
     pub fn new() -> Result<JsAes256Gcm, JsValue> {
         Aes256GcmCipher::generate()
             .map(|cipher| JsAes256Gcm { cipher })
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .map_err(js_error)
     }
 
     /// Create from a 32-byte key
@@ -49,21 +251,17 @@ impl JsAes256Gcm {
     pub fn from_key(key: &[u8]) -> Result<JsAes256Gcm, JsValue> {
         Aes256GcmCipher::from_bytes(key)
             .map(|cipher| JsAes256Gcm { cipher })
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .map_err(js_error)
     }
 
     /// Encrypt data
     pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, JsValue> {
-        self.cipher
-            .encrypt(plaintext)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+        self.cipher.encrypt(plaintext).map_err(js_error)
     }
 
     /// Decrypt data
     pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, JsValue> {
-        self.cipher
-            .decrypt(ciphertext)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+        self.cipher.decrypt(ciphertext).map_err(js_error)
     }
 
     /// Get the key as base64
@@ -78,3 +276,35 @@ impl Default for JsAes256Gcm {
         Self::new().expect("Failed to create cipher")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keypair_and_jwt() {
+        let keypair = JsKeypair::new();
+        let signature = keypair.sign(b"audit entry").unwrap();
+        assert!(js_ed25519_verify(&keypair.public_key_hex(), b"audit entry", &signature).unwrap());
+        assert!(!keypair.verify(b"tampered entry", &signature).unwrap());
+
+        let signer = JsJwt::eddsa(&keypair).unwrap();
+        let claims = r#"{"exp":4102444800,"iat":1700000000,"sub":"alice","role":"admin"}"#;
+        let token = signer.sign(claims).unwrap();
+        let verifier = JsJwt::eddsa_verifier(&keypair.public_key_hex()).unwrap();
+        let verified: serde_json::Value =
+            serde_json::from_str(&verifier.verify(&token).unwrap()).unwrap();
+        assert_eq!(verified["sub"], "alice");
+        assert_eq!(verified["role"], "admin");
+        assert!(js_decode_jwt_header(&token)
+            .unwrap()
+            .contains(r#""alg":"EdDSA""#));
+
+        let tag = js_hmac_sha256_hex(b"key", b"data");
+        assert!(js_hmac_sha256_verify(
+            b"key",
+            b"data",
+            &hex::decode(tag).unwrap()
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+//! Stable machine-readable error codes.
+
+use crate::error::InfraError;
+use crate::kinds::{
+    AuthErrorKind, CryptoOperation, IoOperation, LlmErrorKind, MqOperation, SerializationFormat,
+    VectorOperation,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
+
+/// Prefix of every error code
+const PREFIX: &str = "INFRA";
+
+/// Code domains, one per `InfraError` variant
+const DOMAINS: &[&str] = &[
+    "CONFIG",
+    "HTTP",
+    "VECTOR",
+    "AUTH",
+    "CRYPTO",
+    "IO",
+    "SERIALIZATION",
+    "VALIDATION",
+    "EXTERNAL",
+    "MESSAGE_QUEUE",
+    "SCHEMA",
+    "TIMEOUT",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "LLM_PROVIDER",
+];
+
+/// Stable error code such as `INFRA-HTTP-0429`
+///
+/// Codes are made of the `INFRA` prefix, the domain of the error and a
+/// number of at least four digits. HTTP errors use their status as the number, and other
+/// domains number their kinds or operations. Codes never change meaning, so
+/// dashboards, clients and runbooks can key off them instead of messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCode {
+    domain: &'static str,
+    number: u16,
+}
+
+/// Registered error code and what it means
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCodeInfo {
+    /// The registered code
+    pub code: ErrorCode,
+    /// Short description of the error, used as the title of problem details
+    pub description: &'static str,
+}
+
+const fn info(domain: &'static str, number: u16, description: &'static str) -> ErrorCodeInfo {
+    ErrorCodeInfo {
+        code: ErrorCode { domain, number },
+        description,
+    }
+}
+
+/// Every code `InfraError::as_code` returns, apart from HTTP statuses
+/// without an entry
+const REGISTRY: &[ErrorCodeInfo] = &[
+    info("CONFIG", 1, "Invalid or missing configuration"),
+    info("HTTP", 0, "HTTP error without a status"),
+    info("HTTP", 400, "Bad request"),
+    info("HTTP", 401, "Unauthorized"),
+    info("HTTP", 403, "Forbidden"),
+    info("HTTP", 404, "Not found"),
+    info("HTTP", 408, "Request timeout"),
+    info("HTTP", 409, "Conflict"),
+    info("HTTP", 429, "Too many requests"),
+    info("HTTP", 500, "Internal server error"),
+    info("HTTP", 502, "Bad gateway"),
+    info("HTTP", 503, "Service unavailable"),
+    info("HTTP", 504, "Gateway timeout"),
+    info("VECTOR", 1, "Vector insert failed"),
+    info("VECTOR", 2, "Vector search failed"),
+    info("VECTOR", 3, "Vector delete failed"),
+    info("VECTOR", 4, "Vector update failed"),
+    info("VECTOR", 5, "Vector indexing failed"),
+    info("VECTOR", 6, "Vector compression failed"),
+    info("VECTOR", 7, "Vector batch insert failed"),
+    info("VECTOR", 8, "Vector batch delete failed"),
+    info("VECTOR", 9, "Vector get failed"),
+    info("VECTOR", 10, "Vector stats failed"),
+    info("AUTH", 1, "Invalid credentials"),
+    info("AUTH", 2, "Token expired"),
+    info("AUTH", 3, "Insufficient permissions"),
+    info("AUTH", 4, "Invalid token"),
+    info("AUTH", 5, "Missing credentials"),
+    info("AUTH", 6, "Rate limited"),
+    info("AUTH", 7, "Account locked"),
+    info("AUTH", 8, "Session expired"),
+    info("CRYPTO", 1, "Encryption failed"),
+    info("CRYPTO", 2, "Decryption failed"),
+    info("CRYPTO", 3, "Signing failed"),
+    info("CRYPTO", 4, "Verification failed"),
+    info("CRYPTO", 5, "Hashing failed"),
+    info("CRYPTO", 6, "Key generation failed"),
+    info("CRYPTO", 7, "Key derivation failed"),
+    info("IO", 1, "Read failed"),
+    info("IO", 2, "Write failed"),
+    info("IO", 3, "Delete failed"),
+    info("IO", 4, "Create failed"),
+    info("IO", 5, "List failed"),
+    info("IO", 6, "Watch failed"),
+    info("IO", 7, "Copy failed"),
+    info("IO", 8, "Move failed"),
+    info("SERIALIZATION", 1, "Invalid JSON"),
+    info("SERIALIZATION", 2, "Invalid TOML"),
+    info("SERIALIZATION", 3, "Invalid YAML"),
+    info("SERIALIZATION", 4, "Invalid MessagePack"),
+    info("SERIALIZATION", 5, "Invalid Protobuf"),
+    info("VALIDATION", 1, "Invalid input"),
+    info("EXTERNAL", 1, "External service failed"),
+    info("MESSAGE_QUEUE", 1, "Publish failed"),
+    info("MESSAGE_QUEUE", 2, "Subscribe failed"),
+    info("MESSAGE_QUEUE", 3, "Acknowledge failed"),
+    info("MESSAGE_QUEUE", 4, "Reject failed"),
+    info("MESSAGE_QUEUE", 5, "Connect failed"),
+    info("MESSAGE_QUEUE", 6, "Disconnect failed"),
+    info("MESSAGE_QUEUE", 7, "Inspect failed"),
+    info("MESSAGE_QUEUE", 8, "Purge failed"),
+    info("MESSAGE_QUEUE", 9, "Cancel failed"),
+    info("SCHEMA", 1, "Schema violation"),
+    info("TIMEOUT", 1, "Operation timed out"),
+    info("NOT_FOUND", 1, "Resource not found"),
+    info("ALREADY_EXISTS", 1, "Resource already exists"),
+    info("LLM_PROVIDER", 1, "LLM provider rate limited"),
+    info("LLM_PROVIDER", 2, "LLM context too long"),
+    info("LLM_PROVIDER", 3, "LLM content filtered"),
+    info("LLM_PROVIDER", 4, "LLM provider overloaded"),
+];
+
+impl ErrorCode {
+    /// Get the domain, such as `HTTP`
+    #[must_use]
+    pub fn domain(&self) -> &'static str {
+        self.domain
+    }
+
+    /// Get the number within the domain
+    #[must_use]
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    /// Get the code as a string, such as `INFRA-HTTP-0429`
+    #[must_use]
+    pub fn as_str(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the description of a registered code
+    #[must_use]
+    pub fn description(&self) -> Option<&'static str> {
+        REGISTRY
+            .iter()
+            .find(|info| info.code == *self)
+            .map(|info| info.description)
+    }
+
+    /// Check if the code is registered
+    #[must_use]
+    pub fn is_registered(&self) -> bool {
+        self.description().is_some()
+    }
+
+    /// Get all registered codes
+    #[must_use]
+    pub fn registry() -> &'static [ErrorCodeInfo] {
+        REGISTRY
+    }
+
+    const fn new(domain: &'static str, number: u16) -> Self {
+        Self { domain, number }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{PREFIX}-{}-{:04}", self.domain, self.number)
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = InfraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InfraError::validation(format!("Invalid error code: {s}"));
+        let rest = s
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .ok_or_else(invalid)?;
+        let (domain, number) = rest.rsplit_once('-').ok_or_else(invalid)?;
+        let domain = DOMAINS
+            .iter()
+            .find(|known| **known == domain)
+            .ok_or_else(invalid)?;
+        // Zero-padded to four digits, so longer numbers have no leading zero
+        let canonical = number.len() == 4 || (number.len() > 4 && !number.starts_with('0'));
+        if !canonical || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let number = number.parse().map_err(|_| invalid())?;
+        Ok(Self::new(domain, number))
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl InfraError {
+    /// Get the stable code of this error
+    #[must_use]
+    pub fn as_code(&self) -> ErrorCode {
+        match self {
+            Self::Config { .. } => ErrorCode::new("CONFIG", 1),
+            Self::Http { status, .. } => ErrorCode::new("HTTP", status.unwrap_or(0)),
+            Self::Vector { operation, .. } => ErrorCode::new(
+                "VECTOR",
+                match operation {
+                    VectorOperation::Insert => 1,
+                    VectorOperation::Search => 2,
+                    VectorOperation::Delete => 3,
+                    VectorOperation::Update => 4,
+                    VectorOperation::Index => 5,
+                    VectorOperation::Compress => 6,
+                    VectorOperation::BatchInsert => 7,
+                    VectorOperation::BatchDelete => 8,
+                    VectorOperation::Get => 9,
+                    VectorOperation::Stats => 10,
+                },
+            ),
+            Self::Auth { kind, .. } => ErrorCode::new(
+                "AUTH",
+                match kind {
+                    AuthErrorKind::InvalidCredentials => 1,
+                    AuthErrorKind::TokenExpired => 2,
+                    AuthErrorKind::InsufficientPermissions => 3,
+                    AuthErrorKind::InvalidToken => 4,
+                    AuthErrorKind::MissingCredentials => 5,
+                    AuthErrorKind::RateLimited => 6,
+                    AuthErrorKind::AccountLocked => 7,
+                    AuthErrorKind::SessionExpired => 8,
+                },
+            ),
+            Self::Crypto { operation, .. } => ErrorCode::new(
+                "CRYPTO",
+                match operation {
+                    CryptoOperation::Encrypt => 1,
+                    CryptoOperation::Decrypt => 2,
+                    CryptoOperation::Sign => 3,
+                    CryptoOperation::Verify => 4,
+                    CryptoOperation::Hash => 5,
+                    CryptoOperation::KeyGeneration => 6,
+                    CryptoOperation::KeyDerivation => 7,
+                },
+            ),
+            Self::Io { operation, .. } => ErrorCode::new(
+                "IO",
+                match operation {
+                    IoOperation::Read => 1,
+                    IoOperation::Write => 2,
+                    IoOperation::Delete => 3,
+                    IoOperation::Create => 4,
+                    IoOperation::List => 5,
+                    IoOperation::Watch => 6,
+                    IoOperation::Copy => 7,
+                    IoOperation::Move => 8,
+                },
+            ),
+            Self::Serialization { format, .. } => ErrorCode::new(
+                "SERIALIZATION",
+                match format {
+                    SerializationFormat::Json => 1,
+                    SerializationFormat::Toml => 2,
+                    SerializationFormat::Yaml => 3,
+                    SerializationFormat::MessagePack => 4,
+                    SerializationFormat::Protobuf => 5,
+                },
+            ),
+            Self::Validation { .. } => ErrorCode::new("VALIDATION", 1),
+            Self::External { .. } => ErrorCode::new("EXTERNAL", 1),
+            Self::MessageQueue { operation, .. } => ErrorCode::new(
+                "MESSAGE_QUEUE",
+                match operation {
+                    MqOperation::Publish => 1,
+                    MqOperation::Subscribe => 2,
+                    MqOperation::Acknowledge => 3,
+                    MqOperation::Reject => 4,
+                    MqOperation::Connect => 5,
+                    MqOperation::Disconnect => 6,
+                    MqOperation::Inspect => 7,
+                    MqOperation::Purge => 8,
+                    MqOperation::Cancel => 9,
+                },
+            ),
+            Self::Schema { .. } => ErrorCode::new("SCHEMA", 1),
+            Self::Timeout { .. } => ErrorCode::new("TIMEOUT", 1),
+            Self::NotFound { .. } => ErrorCode::new("NOT_FOUND", 1),
+            Self::AlreadyExists { .. } => ErrorCode::new("ALREADY_EXISTS", 1),
+            Self::LlmProvider { kind, .. } => ErrorCode::new(
+                "LLM_PROVIDER",
+                match kind {
+                    LlmErrorKind::RateLimited => 1,
+                    LlmErrorKind::ContextTooLong => 2,
+                    LlmErrorKind::ContentFiltered => 3,
+                    LlmErrorKind::Overloaded => 4,
+                },
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{
+        mock_auth_error, mock_http_error, mock_io_error, mock_llm_provider_error,
+        mock_timeout_error,
+    };
+
+    #[test]
+    fn test_error_codes() {
+        let code = mock_http_error(429).as_code();
+        assert_eq!(code.to_string(), "INFRA-HTTP-0429");
+        assert_eq!(code.description(), Some("Too many requests"));
+        assert_eq!(
+            mock_auth_error(AuthErrorKind::RateLimited)
+                .as_code()
+                .as_str(),
+            "INFRA-AUTH-0006"
+        );
+        assert_eq!(
+            mock_io_error(IoOperation::Write).as_code().as_str(),
+            "INFRA-IO-0002"
+        );
+        assert_eq!(
+            mock_llm_provider_error(LlmErrorKind::ContentFiltered)
+                .as_code()
+                .as_str(),
+            "INFRA-LLM_PROVIDER-0003"
+        );
+        assert!(mock_timeout_error().as_code().is_registered());
+        assert!(!mock_http_error(418).as_code().is_registered());
+    }
+
+    #[test]
+    fn test_parse_codes() {
+        for info in ErrorCode::registry() {
+            assert!(DOMAINS.contains(&info.code.domain()));
+            assert_eq!(
+                info.code.to_string().parse::<ErrorCode>().unwrap(),
+                info.code
+            );
+        }
+        let code: ErrorCode = "INFRA-NOT_FOUND-0001".parse().unwrap();
+        assert_eq!(code.domain(), "NOT_FOUND");
+        assert_eq!(code.number(), 1);
+
+        let large = mock_http_error(10000).as_code();
+        assert_eq!(large.to_string(), "INFRA-HTTP-10000");
+        assert_eq!(large.to_string().parse::<ErrorCode>().unwrap(), large);
+
+        for invalid in [
+            "INFRA-HTTP-429",
+            "INFRA-HTTPS-0429",
+            "HTTP-0429",
+            "INFRA-HTTP-04x9",
+            "INFRA-HTTP-00429",
+            "INFRA-HTTP-70000",
+        ] {
+            assert!(invalid.parse::<ErrorCode>().is_err());
+        }
+
+        let json = serde_json::to_string(&code).unwrap();
+        assert_eq!(json, "\"INFRA-NOT_FOUND-0001\"");
+        assert_eq!(serde_json::from_str::<ErrorCode>(&json).unwrap(), code);
+    }
+}
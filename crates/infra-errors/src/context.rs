@@ -1,8 +1,25 @@
 //! Error context for enhanced debugging and tracing.
 
+use crate::error::InfraError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Function returning the trace IDs of the current span
+pub type TraceIdsProvider = fn() -> Option<TraceIds>;
+
+/// Provider installed with [`set_trace_ids_provider`]
+static TRACE_IDS_PROVIDER: OnceLock<TraceIdsProvider> = OnceLock::new();
+
+/// Install the function [`TraceIds::current`] gets trace IDs from
+///
+/// This crate does not depend on OpenTelemetry, so the tracing setup
+/// (`infra_otel::init_tracing`) installs it. Only the first call has an
+/// effect; returns whether it was this one.
+pub fn set_trace_ids_provider(provider: TraceIdsProvider) -> bool {
+    TRACE_IDS_PROVIDER.set(provider).is_ok()
+}
 
 /// Context that can be attached to any InfraError
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +90,37 @@ impl ErrorContext {
         self.trace_ids = trace_ids;
         self
     }
+
+    /// Create a context at a source location, with the current trace IDs
+    ///
+    /// Usually called through [`err_ctx!`](crate::err_ctx), which fills in
+    /// the location.
+    #[must_use]
+    pub fn capture(location: SourceLocation) -> Self {
+        Self::new()
+            .with_location(location)
+            .with_trace_ids(TraceIds::current())
+    }
+
+    /// Merge a context attached later, further up the call stack
+    ///
+    /// The ID, timestamp, location and trace IDs of this context, closest to
+    /// where the error occurred, are kept when set. Attributes are combined,
+    /// with the later context's winning, and remediation steps appended.
+    #[must_use]
+    pub fn merge(mut self, later: Self) -> Self {
+        if self.location.is_none() {
+            self.location = later.location;
+        }
+        if self.trace_ids.trace_id.is_none() {
+            self.trace_ids = later.trace_ids;
+        }
+        self.attributes.extend(later.attributes);
+        if let Some(steps) = later.remediation {
+            self.remediation.get_or_insert_with(Vec::new).extend(steps);
+        }
+        self
+    }
 }
 
 /// Source location information
@@ -129,6 +177,58 @@ impl TraceIds {
         self.parent_span_id = Some(parent_span_id.into());
         self
     }
+
+    /// Get the trace IDs of the current span
+    ///
+    /// Empty outside a span, or until a provider is installed with
+    /// [`set_trace_ids_provider`].
+    #[must_use]
+    pub fn current() -> Self {
+        TRACE_IDS_PROVIDER
+            .get()
+            .and_then(|provider| provider())
+            .unwrap_or_default()
+    }
+}
+
+/// Extension trait attaching context to errors of results
+pub trait ResultExt<T> {
+    /// Convert the error and attach a context to it
+    ///
+    /// The context is only built on error. If the error already has a
+    /// context, the two are merged with [`ErrorContext::merge`].
+    ///
+    /// ```
+    /// use infra_errors::{err_ctx, InfraResult, ResultExt};
+    ///
+    /// fn read_prompt(path: &str) -> InfraResult<String> {
+    ///     std::fs::read_to_string(path).with_context(|| err_ctx!("path" => path))
+    /// }
+    ///
+    /// let err = read_prompt("/missing/prompt.txt").unwrap_err();
+    /// let context = err.context().unwrap();
+    /// assert_eq!(context.attributes["path"], "/missing/prompt.txt");
+    /// assert!(context.location.is_some());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the converted error, with the context attached, if `self` is
+    /// an error.
+    #[allow(clippy::result_large_err)] // the crate's own error type
+    fn with_context<F>(self, context: F) -> Result<T, InfraError>
+    where
+        F: FnOnce() -> ErrorContext;
+}
+
+impl<T, E: Into<InfraError>> ResultExt<T> for Result<T, E> {
+    #[allow(clippy::result_large_err)]
+    fn with_context<F>(self, context: F) -> Result<T, InfraError>
+    where
+        F: FnOnce() -> ErrorContext,
+    {
+        self.map_err(|e| e.into().with_context(context()))
+    }
 }
 
 /// Macro for capturing source location
@@ -139,21 +239,77 @@ macro_rules! source_location {
     };
 }
 
+/// Macro creating an error context with the source location, the current
+/// trace IDs and key-value attributes
+///
+/// ```
+/// use infra_errors::{err_ctx, InfraError};
+///
+/// let err = InfraError::validation("Prompt too long")
+///     .with_context(err_ctx!("model" => "gpt-4o", "tokens" => 9000.to_string()));
+/// assert_eq!(err.context().unwrap().attributes["model"], "gpt-4o");
+/// ```
+#[macro_export]
+macro_rules! err_ctx {
+    () => {
+        $crate::ErrorContext::capture($crate::source_location!())
+    };
+    ($($key:expr => $value:expr),+ $(,)?) => {
+        $crate::err_ctx!()$(.with_attribute($key, $value))+
+    };
+}
+
 /// Macro for creating an error with context
+///
+/// Replaces any context the error already has.
 #[macro_export]
 macro_rules! infra_error {
     ($error:expr) => {{
         let mut err = $error;
-        err.set_context($crate::ErrorContext::new().with_location($crate::source_location!()));
+        err.set_context($crate::err_ctx!());
         err
     }};
     ($error:expr, $($key:expr => $value:expr),+ $(,)?) => {{
-        let mut ctx = $crate::ErrorContext::new().with_location($crate::source_location!());
-        $(
-            ctx = ctx.with_attribute($key, $value);
-        )+
         let mut err = $error;
-        err.set_context(ctx);
+        err.set_context($crate::err_ctx!($($key => $value),+));
         err
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InfraResult;
+
+    #[test]
+    fn test_result_context() {
+        let line = line!() + 2;
+        let result: InfraResult<()> =
+            Err(InfraError::validation("bad")).with_context(|| err_ctx!("model" => "gpt-4o"));
+        let err = result
+            .with_context(|| err_ctx!("model" => "claude", "tenant" => "acme"))
+            .unwrap_err();
+        let context = err.context().unwrap();
+        assert_eq!(context.attributes["model"], "claude");
+        assert_eq!(context.attributes["tenant"], "acme");
+        let location = context.location.as_ref().unwrap();
+        assert!(location.file.ends_with("context.rs"));
+        assert_eq!(location.line, line);
+
+        let io: Result<(), std::io::Error> = Err(std::io::ErrorKind::NotFound.into());
+        let err = io.with_context(|| err_ctx!()).unwrap_err();
+        assert!(matches!(err, InfraError::Io { .. }));
+        assert!(err.context().unwrap().location.is_some());
+
+        let ok: Result<u8, std::io::Error> = Ok(1);
+        assert_eq!(ok.with_context(|| unreachable!()).unwrap(), 1);
+
+        let err = infra_error!(
+            InfraError::validation("bad").with_context(err_ctx!("model" => "gpt-4o")),
+            "tenant" => "acme"
+        );
+        let attributes = &err.context().unwrap().attributes;
+        assert!(!attributes.contains_key("model"));
+        assert_eq!(attributes["tenant"], "acme");
+    }
+}
@@ -2,16 +2,46 @@
 
 use crate::context::ErrorContext;
 use crate::kinds::{
-    AuthErrorKind, CryptoOperation, IoOperation, MqOperation,
+    AuthErrorKind, CryptoOperation, IoOperation, LlmErrorKind, MqOperation,
     SerializationFormat, VectorOperation,
 };
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
+/// Underlying error an `InfraError` was created from
+///
+/// Shared rather than boxed so errors stay cloneable. It derefs to the
+/// original error, so [`InfraError::chain`] yields that error itself and
+/// callers can downcast it.
+#[derive(Clone)]
+pub struct ErrorSource(Arc<dyn std::error::Error + Send + Sync + 'static>);
+
+impl ErrorSource {
+    /// Wrap an error
+    pub fn new(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self(Arc::new(err))
+    }
+}
+
+impl std::ops::Deref for ErrorSource {
+    type Target = dyn std::error::Error + Send + Sync + 'static;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl std::fmt::Debug for ErrorSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
 /// Primary error type for all infra operations
-#[derive(Debug, Error, Serialize, Deserialize)]
+#[derive(Debug, Clone, Error, Serialize, Deserialize)]
 pub enum InfraError {
     /// Configuration errors
     #[error("Configuration error: {message}")]
@@ -20,6 +50,8 @@ pub enum InfraError {
         key: Option<String>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// HTTP/Network errors
@@ -30,6 +62,8 @@ pub enum InfraError {
         url: Option<String>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Vector operation errors
@@ -40,6 +74,8 @@ pub enum InfraError {
         dimensions: Option<usize>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Authentication/Authorization errors
@@ -50,6 +86,8 @@ pub enum InfraError {
         identity: Option<String>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Cryptographic errors
@@ -59,6 +97,8 @@ pub enum InfraError {
         message: String,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// I/O errors
@@ -69,6 +109,8 @@ pub enum InfraError {
         message: String,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Serialization errors
@@ -79,6 +121,8 @@ pub enum InfraError {
         location: Option<String>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Validation errors
@@ -90,6 +134,8 @@ pub enum InfraError {
         actual: Option<String>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// External service errors
@@ -102,6 +148,8 @@ pub enum InfraError {
         retry_after: Option<Duration>,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Message queue errors
@@ -112,6 +160,8 @@ pub enum InfraError {
         message: String,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Schema errors
@@ -122,6 +172,8 @@ pub enum InfraError {
         message: String,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Timeout errors
@@ -132,6 +184,8 @@ pub enum InfraError {
         duration: Duration,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Resource not found
@@ -141,6 +195,8 @@ pub enum InfraError {
         resource_id: String,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 
     /// Resource already exists
@@ -150,6 +206,21 @@ pub enum InfraError {
         resource_id: String,
         #[serde(skip)]
         context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
+    },
+    /// LLM provider errors
+    #[error("LLM provider error ({provider}, {kind}): {message}")]
+    LlmProvider {
+        provider: String,
+        model: Option<String>,
+        kind: LlmErrorKind,
+        message: String,
+        request_id: Option<String>,
+        #[serde(skip)]
+        context: Option<ErrorContext>,
+        #[serde(skip)]
+        source: Option<ErrorSource>,
     },
 }
 
@@ -212,38 +283,20 @@ impl InfraError {
             Self::Timeout { .. } => "timeout",
             Self::NotFound { .. } => "not_found",
             Self::AlreadyExists { .. } => "already_exists",
+            Self::LlmProvider { .. } => "llm_provider",
         }
     }
 
     /// Check if this error is retryable
     #[must_use]
     pub fn is_retryable(&self) -> bool {
-        match self {
-            Self::Http { status: Some(s), .. } => *s >= 500 || *s == 429,
-            Self::External { retry_after, .. } => retry_after.is_some(),
-            Self::Auth { kind: AuthErrorKind::RateLimited, .. } => true,
-            Self::MessageQueue { .. } => true,
-            Self::Timeout { .. } => true,
-            Self::Io { operation, .. } => matches!(
-                operation,
-                IoOperation::Read | IoOperation::Write
-            ),
-            _ => false,
-        }
+        self.retry_advice().retryable
     }
 
     /// Get retry delay if applicable
     #[must_use]
     pub fn retry_after(&self) -> Option<Duration> {
-        match self {
-            Self::External { retry_after, .. } => *retry_after,
-            Self::Auth { kind: AuthErrorKind::RateLimited, .. } => {
-                Some(Duration::from_secs(60))
-            }
-            Self::Http { status: Some(429), .. } => Some(Duration::from_secs(30)),
-            Self::Timeout { .. } => Some(Duration::from_secs(1)),
-            _ => None,
-        }
+        self.retry_advice().delay
     }
 
     /// Set error context
@@ -262,12 +315,24 @@ impl InfraError {
             | Self::Schema { context, .. }
             | Self::Timeout { context, .. }
             | Self::NotFound { context, .. }
-            | Self::AlreadyExists { context, .. } => {
+            | Self::AlreadyExists { context, .. }
+            | Self::LlmProvider { context, .. } => {
                 *context = Some(ctx);
             }
         }
     }
 
+    /// Attach a context, merging it into any context the error already has
+    #[must_use]
+    pub fn with_context(mut self, ctx: ErrorContext) -> Self {
+        let ctx = match self.context().cloned() {
+            Some(existing) => existing.merge(ctx),
+            None => ctx,
+        };
+        self.set_context(ctx);
+        self
+    }
+
     /// Get error context
     #[must_use]
     pub fn context(&self) -> Option<&ErrorContext> {
@@ -285,10 +350,54 @@ impl InfraError {
             | Self::Schema { context, .. }
             | Self::Timeout { context, .. }
             | Self::NotFound { context, .. }
-            | Self::AlreadyExists { context, .. } => context.as_ref(),
+            | Self::AlreadyExists { context, .. }
+            | Self::LlmProvider { context, .. } => context.as_ref(),
         }
     }
 
+    /// Set the underlying error
+    pub fn set_source(&mut self, err: impl std::error::Error + Send + Sync + 'static) {
+        let err = ErrorSource::new(err);
+        match self {
+            Self::Config { source, .. }
+            | Self::Http { source, .. }
+            | Self::Vector { source, .. }
+            | Self::Auth { source, .. }
+            | Self::Crypto { source, .. }
+            | Self::Io { source, .. }
+            | Self::Serialization { source, .. }
+            | Self::Validation { source, .. }
+            | Self::External { source, .. }
+            | Self::MessageQueue { source, .. }
+            | Self::Schema { source, .. }
+            | Self::Timeout { source, .. }
+            | Self::NotFound { source, .. }
+            | Self::AlreadyExists { source, .. }
+            | Self::LlmProvider { source, .. } => {
+                *source = Some(err);
+            }
+        }
+    }
+
+    /// Attach the underlying error
+    #[must_use]
+    pub fn with_source(mut self, err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        self.set_source(err);
+        self
+    }
+
+    /// Iterate over this error and its sources, outermost first
+    #[must_use]
+    pub fn chain(&self) -> ErrorChain<'_> {
+        ErrorChain { next: Some(self) }
+    }
+
+    /// Get the innermost error of the chain
+    #[must_use]
+    pub fn root_cause(&self) -> &(dyn std::error::Error + 'static) {
+        self.chain().last().unwrap_or(self)
+    }
+
     /// Create a config error
     #[must_use]
     pub fn config(message: impl Into<String>) -> Self {
@@ -296,6 +405,7 @@ impl InfraError {
             message: message.into(),
             key: None,
             context: None,
+            source: None,
         }
     }
 
@@ -306,6 +416,7 @@ impl InfraError {
             message: message.into(),
             key: Some(key.into()),
             context: None,
+            source: None,
         }
     }
 
@@ -317,6 +428,7 @@ impl InfraError {
             message: message.into(),
             url: None,
             context: None,
+            source: None,
         }
     }
 
@@ -328,6 +440,7 @@ impl InfraError {
             message: message.into(),
             url: None,
             context: None,
+            source: None,
         }
     }
 
@@ -340,6 +453,7 @@ impl InfraError {
             expected: None,
             actual: None,
             context: None,
+            source: None,
         }
     }
 
@@ -357,6 +471,7 @@ impl InfraError {
             expected,
             actual,
             context: None,
+            source: None,
         }
     }
 
@@ -367,6 +482,7 @@ impl InfraError {
             resource_type: resource_type.into(),
             resource_id: resource_id.into(),
             context: None,
+            source: None,
         }
     }
 
@@ -377,8 +493,51 @@ impl InfraError {
             operation: operation.into(),
             duration,
             context: None,
+            source: None,
         }
     }
+
+    /// Create an LLM provider error
+    #[must_use]
+    pub fn llm_provider(
+        provider: impl Into<String>,
+        kind: LlmErrorKind,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::LlmProvider {
+            provider: provider.into(),
+            model: None,
+            kind,
+            message: message.into(),
+            request_id: None,
+            context: None,
+            source: None,
+        }
+    }
+}
+
+/// Iterator over an error and its sources
+#[derive(Clone)]
+pub struct ErrorChain<'a> {
+    next: Option<&'a (dyn std::error::Error + 'static)>,
+}
+
+impl<'a> Iterator for ErrorChain<'a> {
+    type Item = &'a (dyn std::error::Error + 'static);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        self.next = current.source();
+        Some(current)
+    }
+}
+
+impl std::fmt::Debug for ErrorChain<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.clone().map(ToString::to_string))
+            .finish()
+    }
 }
 
 // Conversion from std::io::Error
@@ -389,6 +548,7 @@ impl From<std::io::Error> for InfraError {
             path: None,
             message: err.to_string(),
             context: None,
+            source: Some(ErrorSource::new(err)),
         }
     }
 }
@@ -401,94 +561,42 @@ impl From<serde_json::Error> for InfraError {
             message: err.to_string(),
             location: Some(format!("line {}, column {}", err.line(), err.column())),
             context: None,
+            source: Some(ErrorSource::new(err)),
         }
     }
 }
 
-impl Clone for InfraError {
-    fn clone(&self) -> Self {
-        match self {
-            Self::Config { message, key, context } => Self::Config {
-                message: message.clone(),
-                key: key.clone(),
-                context: context.clone(),
-            },
-            Self::Http { status, message, url, context } => Self::Http {
-                status: *status,
-                message: message.clone(),
-                url: url.clone(),
-                context: context.clone(),
-            },
-            Self::Vector { operation, message, dimensions, context } => Self::Vector {
-                operation: *operation,
-                message: message.clone(),
-                dimensions: *dimensions,
-                context: context.clone(),
-            },
-            Self::Auth { kind, message, identity, context } => Self::Auth {
-                kind: *kind,
-                message: message.clone(),
-                identity: identity.clone(),
-                context: context.clone(),
-            },
-            Self::Crypto { operation, message, context } => Self::Crypto {
-                operation: *operation,
-                message: message.clone(),
-                context: context.clone(),
-            },
-            Self::Io { operation, path, message, context } => Self::Io {
-                operation: *operation,
-                path: path.clone(),
-                message: message.clone(),
-                context: context.clone(),
-            },
-            Self::Serialization { format, message, location, context } => Self::Serialization {
-                format: *format,
-                message: message.clone(),
-                location: location.clone(),
-                context: context.clone(),
-            },
-            Self::Validation { field, message, expected, actual, context } => Self::Validation {
-                field: field.clone(),
-                message: message.clone(),
-                expected: expected.clone(),
-                actual: actual.clone(),
-                context: context.clone(),
-            },
-            Self::External { service, operation, message, retry_after, context } => Self::External {
-                service: service.clone(),
-                operation: operation.clone(),
-                message: message.clone(),
-                retry_after: *retry_after,
-                context: context.clone(),
-            },
-            Self::MessageQueue { queue, operation, message, context } => Self::MessageQueue {
-                queue: queue.clone(),
-                operation: *operation,
-                message: message.clone(),
-                context: context.clone(),
-            },
-            Self::Schema { schema_id, path, message, context } => Self::Schema {
-                schema_id: schema_id.clone(),
-                path: path.clone(),
-                message: message.clone(),
-                context: context.clone(),
-            },
-            Self::Timeout { operation, duration, context } => Self::Timeout {
-                operation: operation.clone(),
-                duration: *duration,
-                context: context.clone(),
-            },
-            Self::NotFound { resource_type, resource_id, context } => Self::NotFound {
-                resource_type: resource_type.clone(),
-                resource_id: resource_id.clone(),
-                context: context.clone(),
-            },
-            Self::AlreadyExists { resource_type, resource_id, context } => Self::AlreadyExists {
-                resource_type: resource_type.clone(),
-                resource_id: resource_id.clone(),
-                context: context.clone(),
-            },
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_chain() {
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset");
+        let err = InfraError::from(io);
+        assert_eq!(err.chain().count(), 2);
+        assert_eq!(err.root_cause().to_string(), "connection reset");
+        assert!(err.clone().root_cause().is::<std::io::Error>());
+
+        let err = InfraError::http("Upstream failed").with_source(err);
+        let messages: Vec<String> = err.chain().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "HTTP error: Upstream failed",
+                "I/O read error: connection reset",
+                "connection reset",
+            ]
+        );
+
+        let plain = InfraError::validation("bad input");
+        assert_eq!(plain.chain().count(), 1);
+        assert_eq!(plain.root_cause().to_string(), plain.to_string());
+
+        // Sources are not serialized
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(!json.contains("connection reset"));
+        let restored: InfraError = serde_json::from_str(&json).unwrap();
+        assert!(std::error::Error::source(&restored).is_none());
     }
 }
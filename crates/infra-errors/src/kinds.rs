@@ -13,6 +13,8 @@ pub enum VectorOperation {
     Compress,
     BatchInsert,
     BatchDelete,
+    Get,
+    Stats,
 }
 
 impl std::fmt::Display for VectorOperation {
@@ -26,6 +28,8 @@ impl std::fmt::Display for VectorOperation {
             Self::Compress => write!(f, "compress"),
             Self::BatchInsert => write!(f, "batch_insert"),
             Self::BatchDelete => write!(f, "batch_delete"),
+            Self::Get => write!(f, "get"),
+            Self::Stats => write!(f, "stats"),
         }
     }
 }
@@ -143,6 +147,9 @@ pub enum MqOperation {
     Reject,
     Connect,
     Disconnect,
+    Inspect,
+    Purge,
+    Cancel,
 }
 
 impl std::fmt::Display for MqOperation {
@@ -154,6 +161,33 @@ impl std::fmt::Display for MqOperation {
             Self::Reject => write!(f, "reject"),
             Self::Connect => write!(f, "connect"),
             Self::Disconnect => write!(f, "disconnect"),
+            Self::Inspect => write!(f, "inspect"),
+            Self::Purge => write!(f, "purge"),
+            Self::Cancel => write!(f, "cancel"),
+        }
+    }
+}
+
+/// LLM provider failure modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LlmErrorKind {
+    /// The provider's rate limit or quota was exceeded
+    RateLimited,
+    /// The prompt and completion do not fit the model's context window
+    ContextTooLong,
+    /// The prompt or completion was blocked by content filtering
+    ContentFiltered,
+    /// The provider is overloaded or failing
+    Overloaded,
+}
+
+impl std::fmt::Display for LlmErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited => write!(f, "rate_limited"),
+            Self::ContextTooLong => write!(f, "context_too_long"),
+            Self::ContentFiltered => write!(f, "content_filtered"),
+            Self::Overloaded => write!(f, "overloaded"),
         }
     }
 }
@@ -2,12 +2,22 @@
 //!
 //! This crate provides:
 //! - `InfraError`: The unified error enum for all infra operations
-//! - Error conversion traits for external error types
+//! - Error conversion traits for external error types, keeping the
+//!   original error as the source
 //! - WASM-compatible error representation
 //! - OpenTelemetry span recording utilities
-//! - Retry logic helpers
+//! - Retry logic helpers, with per-error retry advice
+//! - Stable machine-readable error codes
+//! - HTTP status mapping and RFC 7807 problem details
+//! - `err_ctx!` and `ResultExt::with_context` attaching the source
+//!   location, trace IDs and attributes to errors
+//! - Structured JSON log rendering with `to_log_value()`, with credentials
+//!   redacted
 
 mod error;
+mod code;
+mod problem;
+mod log;
 mod kinds;
 mod context;
 mod retry;
@@ -17,13 +27,19 @@ mod wasm;
 
 pub mod testing;
 
-pub use error::InfraError;
+pub use error::{ErrorChain, ErrorSource, InfraError};
+pub use code::{ErrorCode, ErrorCodeInfo};
+pub use problem::{ProblemDetails, PROBLEM_JSON_CONTENT_TYPE};
+pub use log::LogValue;
 pub use kinds::{
-    AuthErrorKind, CryptoOperation, IoOperation, MqOperation,
+    AuthErrorKind, CryptoOperation, IoOperation, LlmErrorKind, MqOperation,
     SerializationFormat, VectorOperation,
 };
-pub use context::{ErrorContext, SourceLocation, TraceIds};
-pub use retry::{RetryConfig, RetryStrategy};
+pub use context::{
+    set_trace_ids_provider, ErrorContext, ResultExt, SourceLocation, TraceIds,
+    TraceIdsProvider,
+};
+pub use retry::{RetryAdvice, RetryConfig, RetryStrategy};
 
 /// Result type alias using InfraError
 pub type InfraResult<T> = Result<T, InfraError>;
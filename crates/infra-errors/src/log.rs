@@ -0,0 +1,278 @@
+//! Structured log rendering.
+
+use crate::error::InfraError;
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Replacement of redacted values
+const REDACTED: &str = "[REDACTED]";
+
+/// Words of field and attribute names whose values are redacted
+///
+/// Names are split into lowercase words at `_`, `-`, `.` and camelCase
+/// boundaries, so `auth_token` and `accessToken` match `token` while
+/// `max_tokens` does not. Entries of several words match consecutive words.
+const SENSITIVE_KEYS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "secrets",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "cookie",
+    "cookies",
+    "credential",
+    "credentials",
+    "private_key",
+];
+
+impl InfraError {
+    /// Render as a JSON object for structured logs
+    ///
+    /// The object always has the same shape, whatever the variant:
+    ///
+    /// - `type`: the [`error_type`](Self::error_type)
+    /// - `code`: the stable [`ErrorCode`](crate::ErrorCode)
+    /// - `message`: the display message
+    /// - `retryable`: whether the error is retryable
+    /// - `fields`: the variant's own fields, such as `url` or `queue`
+    /// - `context`: the attached [`ErrorContext`](crate::ErrorContext), or
+    ///   null
+    /// - `causes`: the messages of the underlying errors, outermost first
+    ///
+    /// Fields and context attributes named like credentials (`password`,
+    /// `token`, `api_key`, ...) are redacted, as are the query strings of
+    /// URLs anywhere in the fields, message and causes.
+    #[must_use]
+    pub fn to_log_value(&self) -> Value {
+        let mut fields = match serde_json::to_value(self) {
+            // Externally tagged, so the variant's fields are the only entry
+            Ok(Value::Object(variant)) => match variant.into_iter().next() {
+                Some((_, Value::Object(fields))) => fields,
+                _ => Map::new(),
+            },
+            _ => Map::new(),
+        };
+        fields.remove("message");
+        redact_fields(&mut fields);
+
+        let context = self.context().and_then(|context| {
+            let mut context = serde_json::to_value(context).ok()?;
+            if let Some(Value::Object(attributes)) = context.get_mut("attributes") {
+                redact_fields(attributes);
+            }
+            Some(context)
+        });
+
+        json!({
+            "type": self.error_type(),
+            "code": self.as_code().to_string(),
+            "message": redact_urls(&self.to_string()),
+            "retryable": self.is_retryable(),
+            "fields": fields,
+            "context": context,
+            "causes": self
+                .chain()
+                .skip(1)
+                .map(|cause| redact_urls(&cause.to_string()))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Get a value displaying as the compact JSON of
+    /// [`to_log_value`](Self::to_log_value), for `%error.log_value()` in
+    /// `tracing` macros
+    #[must_use]
+    pub fn log_value(&self) -> LogValue<'_> {
+        LogValue(self)
+    }
+
+    /// Get the structured log rendering as a `tracing` field value
+    ///
+    /// ```ignore
+    /// tracing::error!(error = err.as_tracing_value(), "Request failed");
+    /// ```
+    #[cfg(feature = "tracing")]
+    #[must_use]
+    pub fn as_tracing_value(&self) -> tracing::field::DisplayValue<LogValue<'_>> {
+        tracing::field::display(self.log_value())
+    }
+}
+
+/// Error displaying as its structured log JSON
+///
+/// Created by [`InfraError::log_value`].
+#[derive(Debug, Clone, Copy)]
+pub struct LogValue<'a>(&'a InfraError);
+
+impl fmt::Display for LogValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0.to_log_value(), f)
+    }
+}
+
+/// Redact the values of sensitive keys and URL query strings
+fn redact_fields(fields: &mut Map<String, Value>) {
+    for (key, value) in fields.iter_mut() {
+        if is_sensitive(key) {
+            *value = Value::String(REDACTED.to_string());
+        } else if key == "url" {
+            if let Some((base, _)) = value.as_str().and_then(|url| url.split_once('?')) {
+                *value = Value::String(format!("{base}?{REDACTED}"));
+            }
+        } else if let Value::String(text) = value {
+            *text = redact_urls(text);
+        }
+    }
+    // Validation errors name the field whose values they carry
+    let sensitive_field = fields
+        .get("field")
+        .and_then(Value::as_str)
+        .is_some_and(is_sensitive);
+    if sensitive_field {
+        for key in ["expected", "actual"] {
+            if let Some(value) = fields.get_mut(key).filter(|value| !value.is_null()) {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+    }
+}
+
+/// Check if a field or attribute name looks like a credential
+fn is_sensitive(key: &str) -> bool {
+    let words = key_words(key);
+    SENSITIVE_KEYS.iter().any(|sensitive| {
+        let sensitive: Vec<&str> = sensitive.split('_').collect();
+        words
+            .windows(sensitive.len())
+            .any(|window| window.iter().eq(sensitive.iter()))
+    })
+}
+
+/// Split a name into lowercase words
+fn key_words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in key.chars() {
+        if !c.is_ascii_alphanumeric() {
+            words.push(std::mem::take(&mut word));
+            previous_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c.to_ascii_lowercase());
+    }
+    words.push(word);
+    words.retain(|word| !word.is_empty());
+    words
+}
+
+/// Redact the query strings of URLs in free text
+fn redact_urls(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(scheme) = rest.find("://") {
+        let end = rest[scheme..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>'))
+            .map_or(rest.len(), |end| scheme + end);
+        let url = &rest[..end];
+        match url.find('?') {
+            Some(query) => {
+                redacted.push_str(&url[..=query]);
+                redacted.push_str(REDACTED);
+            }
+            None => redacted.push_str(url),
+        }
+        rest = &rest[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::err_ctx;
+
+    #[test]
+    fn test_log_value() {
+        let err = InfraError::Http {
+            status: Some(503),
+            message: "Service unavailable".to_string(),
+            url: Some("https://api.example.com/v1/chat?api_key=sk-123".to_string()),
+            context: None,
+            source: None,
+        }
+        .with_context(err_ctx!("model" => "gpt-4o", "auth_token" => "sk-123"))
+        .with_source(std::io::Error::other("connection reset"));
+
+        let value = err.to_log_value();
+        assert_eq!(value["type"], "http");
+        assert_eq!(value["code"], "INFRA-HTTP-0503");
+        assert_eq!(value["message"], "HTTP error: Service unavailable");
+        assert_eq!(value["retryable"], true);
+        assert_eq!(value["fields"]["status"], 503);
+        assert!(value["fields"].get("message").is_none());
+        assert_eq!(
+            value["fields"]["url"],
+            "https://api.example.com/v1/chat?[REDACTED]"
+        );
+        assert_eq!(value["context"]["attributes"]["model"], "gpt-4o");
+        assert_eq!(value["context"]["attributes"]["auth_token"], REDACTED);
+        assert_eq!(value["causes"], json!(["connection reset"]));
+        assert!(!err.log_value().to_string().contains("sk-123"));
+
+        let err = InfraError::config("Bad key in https://example.com/keys?api_key=sk-123")
+            .with_context(err_ctx!("max_tokens" => "512", "accessToken" => "sk-123"))
+            .with_source(std::io::Error::other(
+                "GET http://x.test/?token=sk-123 failed",
+            ));
+        let value = err.to_log_value();
+        assert_eq!(
+            value["message"],
+            "Configuration error: Bad key in https://example.com/keys?[REDACTED]"
+        );
+        assert_eq!(
+            value["causes"],
+            json!(["GET http://x.test/?[REDACTED] failed"])
+        );
+        assert_eq!(value["context"]["attributes"]["max_tokens"], "512");
+        assert_eq!(value["context"]["attributes"]["accessToken"], REDACTED);
+        assert!(!err.log_value().to_string().contains("sk-123"));
+
+        let err = InfraError::Validation {
+            field: Some("password".to_string()),
+            message: "Too short".to_string(),
+            expected: Some("12 characters".to_string()),
+            actual: Some("hunter2".to_string()),
+            context: None,
+            source: None,
+        };
+        let value = err.to_log_value();
+        assert_eq!(value["fields"]["actual"], REDACTED);
+        assert!(value["context"].is_null());
+        assert_eq!(value["causes"], json!([]));
+    }
+
+    #[test]
+    fn test_is_sensitive() {
+        for key in [
+            "password",
+            "auth_token",
+            "accessToken",
+            "X-Api-Key",
+            "client_secret",
+        ] {
+            assert!(is_sensitive(key), "{key}");
+        }
+        for key in ["max_tokens", "prompt_tokens", "tokens", "key", "secretary"] {
+            assert!(!is_sensitive(key), "{key}");
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! HTTP status mapping and RFC 7807 problem details.
+
+use crate::code::ErrorCode;
+use crate::error::InfraError;
+use crate::kinds::{AuthErrorKind, CryptoOperation, LlmErrorKind};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Content type of problem details bodies
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Problem type of problems identified by their status alone
+const ABOUT_BLANK: &str = "about:blank";
+
+/// RFC 7807 problem details, the JSON body of error responses
+///
+/// Besides the standard members, problems carry the stable error code, the
+/// trace ID of the failed request and, for retryable errors, the seconds to
+/// wait before retrying.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// URI identifying the problem type
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the problem type
+    pub title: String,
+    /// HTTP status
+    pub status: u16,
+    /// Explanation of this occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// URI identifying this occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Stable error code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    /// Trace ID of the failed request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Seconds to wait before retrying
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+impl ProblemDetails {
+    /// Create a problem identified by its status
+    #[must_use]
+    pub fn new(status: u16) -> Self {
+        Self {
+            problem_type: ABOUT_BLANK.to_string(),
+            title: status_title(status).to_string(),
+            status,
+            detail: None,
+            instance: None,
+            code: None,
+            trace_id: None,
+            retry_after: None,
+        }
+    }
+
+    /// Set the problem type URI
+    #[must_use]
+    pub fn with_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// Set the title
+    #[must_use]
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Set the status
+    #[must_use]
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Set the detail
+    #[must_use]
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set the instance URI, usually the request path
+    #[must_use]
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Set the trace ID
+    #[must_use]
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    /// Set the delay before retrying, rounded up to whole seconds
+    #[must_use]
+    pub fn with_retry_after(mut self, delay: Duration) -> Self {
+        self.retry_after = Some(delay.as_secs() + u64::from(delay.subsec_nanos() > 0));
+        self
+    }
+
+    /// Serialize to a JSON body
+    #[must_use]
+    pub fn to_json(&self) -> Vec<u8> {
+        // Only strings and integers, which always serialize
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+}
+
+/// Problems for server errors leave out the detail, since the messages of
+/// those errors describe internals such as file paths, configuration and
+/// upstream responses. The code and trace ID still identify the failure.
+impl From<&InfraError> for ProblemDetails {
+    fn from(err: &InfraError) -> Self {
+        let code = err.as_code();
+        let status = err.to_http_status();
+        let mut problem = Self::new(status);
+        if status < 500 {
+            problem = problem.with_detail(err.to_string());
+        }
+        if let Some(description) = code.description() {
+            problem.title = description.to_string();
+        }
+        problem.code = Some(code);
+        problem.trace_id = err
+            .context()
+            .and_then(|context| context.trace_ids.trace_id.clone());
+        if let Some(delay) = err.retry_after() {
+            problem = problem.with_retry_after(delay);
+        }
+        problem
+    }
+}
+
+impl InfraError {
+    /// Get the HTTP status to respond with
+    #[must_use]
+    pub fn to_http_status(&self) -> u16 {
+        match self {
+            Self::Http {
+                status: Some(status),
+                ..
+            } if (400..600).contains(status) => *status,
+            Self::Auth { kind, .. } => match kind {
+                AuthErrorKind::InsufficientPermissions | AuthErrorKind::AccountLocked => 403,
+                AuthErrorKind::RateLimited => 429,
+                AuthErrorKind::InvalidCredentials
+                | AuthErrorKind::TokenExpired
+                | AuthErrorKind::InvalidToken
+                | AuthErrorKind::MissingCredentials
+                | AuthErrorKind::SessionExpired => 401,
+            },
+            Self::Crypto {
+                operation: CryptoOperation::Verify,
+                ..
+            } => 401,
+            Self::Serialization { .. } | Self::Validation { .. } => 400,
+            Self::Schema { .. } => 422,
+            Self::External {
+                retry_after: Some(_),
+                ..
+            }
+            | Self::MessageQueue { .. } => 503,
+            Self::Http { .. } | Self::External { .. } => 502,
+            Self::Timeout { .. } => 504,
+            Self::NotFound { .. } => 404,
+            Self::AlreadyExists { .. } => 409,
+            Self::LlmProvider { kind, .. } => match kind {
+                LlmErrorKind::RateLimited => 429,
+                LlmErrorKind::ContextTooLong => 413,
+                LlmErrorKind::ContentFiltered => 422,
+                LlmErrorKind::Overloaded => 503,
+            },
+            Self::Config { .. } | Self::Vector { .. } | Self::Crypto { .. } | Self::Io { .. } => {
+                500
+            }
+        }
+    }
+
+    /// Convert to RFC 7807 problem details
+    #[must_use]
+    pub fn to_problem_details(&self) -> ProblemDetails {
+        ProblemDetails::from(self)
+    }
+}
+
+/// Get the reason phrase of a status
+fn status_title(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ErrorContext, TraceIds};
+    use crate::testing::{
+        mock_auth_error, mock_http_error, mock_llm_provider_error, mock_not_found_error,
+    };
+
+    #[test]
+    fn test_http_status() {
+        assert_eq!(mock_http_error(429).to_http_status(), 429);
+        assert_eq!(InfraError::http("Connection refused").to_http_status(), 502);
+        assert_eq!(
+            mock_auth_error(AuthErrorKind::InsufficientPermissions).to_http_status(),
+            403
+        );
+        assert_eq!(
+            mock_auth_error(AuthErrorKind::TokenExpired).to_http_status(),
+            401
+        );
+        assert_eq!(InfraError::validation("bad").to_http_status(), 400);
+        assert_eq!(mock_not_found_error("model", "gpt").to_http_status(), 404);
+        assert_eq!(
+            InfraError::timeout("completion", Duration::from_secs(30)).to_http_status(),
+            504
+        );
+        assert_eq!(InfraError::config("missing key").to_http_status(), 500);
+        assert_eq!(
+            mock_llm_provider_error(LlmErrorKind::ContextTooLong).to_http_status(),
+            413
+        );
+    }
+
+    #[test]
+    fn test_problem_details() {
+        let mut err = mock_auth_error(AuthErrorKind::RateLimited);
+        err.set_context(
+            ErrorContext::new().with_trace_ids(TraceIds::new(Some("abc123".to_string()), None)),
+        );
+        let problem = err.to_problem_details();
+        assert_eq!(problem.status, 429);
+        assert_eq!(problem.title, "Rate limited");
+        assert_eq!(problem.retry_after, Some(60));
+
+        let json: serde_json::Value = serde_json::from_slice(&problem.to_json()).unwrap();
+        assert_eq!(json["type"], "about:blank");
+        assert_eq!(json["code"], "INFRA-AUTH-0006");
+        assert_eq!(json["trace_id"], "abc123");
+        assert!(json.get("instance").is_none());
+        assert!(problem.detail.is_some());
+
+        let problem = InfraError::config("missing key in /etc/secrets.toml").to_problem_details();
+        assert_eq!(problem.status, 500);
+        assert!(problem.detail.is_none());
+        assert!(problem.code.is_some());
+
+        let problem = ProblemDetails::new(404)
+            .with_detail("No route")
+            .with_instance("/v1/missing");
+        assert_eq!(problem.title, "Not Found");
+        let parsed: ProblemDetails = serde_json::from_slice(&problem.to_json()).unwrap();
+        assert_eq!(parsed, problem);
+    }
+}
@@ -1,8 +1,145 @@
-//! Retry configuration and strategies.
+//! Retry configuration, strategies and advice.
 
+use crate::error::InfraError;
+use crate::kinds::{AuthErrorKind, IoOperation, LlmErrorKind, MqOperation};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// Whether and how to retry after an error
+///
+/// Computed by [`InfraError::retry_advice`] from the error variant and the
+/// kind of operation that failed, so every retry loop classifies errors the
+/// same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryAdvice {
+    /// Whether retrying may succeed
+    pub retryable: bool,
+    /// Delay to wait before retrying, when the error suggests one
+    pub delay: Option<Duration>,
+    /// Whether the failed attempt certainly had no effect, so even
+    /// non-idempotent operations can be retried without duplicating it
+    pub idempotency_safe: bool,
+}
+
+impl RetryAdvice {
+    /// Advice not to retry
+    #[must_use]
+    pub const fn permanent() -> Self {
+        Self {
+            retryable: false,
+            delay: None,
+            idempotency_safe: false,
+        }
+    }
+
+    /// Advice to retry an operation that may have taken effect
+    #[must_use]
+    pub const fn transient() -> Self {
+        Self {
+            retryable: true,
+            delay: None,
+            idempotency_safe: false,
+        }
+    }
+
+    /// Advice to retry an operation that was rejected before taking effect
+    #[must_use]
+    pub const fn rejected() -> Self {
+        Self {
+            retryable: true,
+            delay: None,
+            idempotency_safe: true,
+        }
+    }
+
+    /// Advice after a connection failure, for errors carrying no status
+    ///
+    /// Failures to connect are idempotency-safe, as the request was never
+    /// sent; failures after sending may have reached the server.
+    #[must_use]
+    pub const fn connection_failure(connected: bool) -> Self {
+        if connected {
+            Self::transient()
+        } else {
+            Self::rejected()
+        }
+    }
+
+    /// Set the delay to wait before retrying
+    #[must_use]
+    pub const fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Check if an operation may be retried
+    ///
+    /// Idempotent operations, or ones sent with an idempotency key, may be
+    /// retried after any retryable error; others only after errors that are
+    /// idempotency-safe.
+    #[must_use]
+    pub const fn allows_retry(&self, idempotent: bool) -> bool {
+        self.retryable && (idempotent || self.idempotency_safe)
+    }
+}
+
+impl InfraError {
+    /// Get the advice on retrying after this error
+    ///
+    /// Rate limiting and unavailability signalled up front (HTTP 429 and 503,
+    /// external services giving a retry delay) reject the request before it
+    /// has any effect, so they are idempotency-safe. Server errors and
+    /// timeouts leave the outcome unknown. Message queue failures depend on
+    /// the operation: publishing again may duplicate the message and purging
+    /// again may drop newer ones, while connecting, subscribing, inspecting
+    /// or cancelling a schedule again cannot.
+    #[must_use]
+    pub fn retry_advice(&self) -> RetryAdvice {
+        match self {
+            Self::Http {
+                status: Some(429), ..
+            } => RetryAdvice::rejected().with_delay(Duration::from_secs(30)),
+            Self::Http {
+                status: Some(503), ..
+            }
+            | Self::LlmProvider {
+                kind: LlmErrorKind::RateLimited | LlmErrorKind::Overloaded,
+                ..
+            } => RetryAdvice::rejected(),
+            Self::Http {
+                status: Some(status),
+                ..
+            } if *status >= 500 => RetryAdvice::transient(),
+            Self::External {
+                retry_after: Some(delay),
+                ..
+            } => RetryAdvice::rejected().with_delay(*delay),
+            Self::Auth {
+                kind: AuthErrorKind::RateLimited,
+                ..
+            } => RetryAdvice::rejected().with_delay(Duration::from_secs(60)),
+            Self::MessageQueue { operation, .. } => match operation {
+                MqOperation::Publish
+                | MqOperation::Acknowledge
+                | MqOperation::Reject
+                | MqOperation::Purge => RetryAdvice::transient(),
+                MqOperation::Subscribe
+                | MqOperation::Connect
+                | MqOperation::Disconnect
+                | MqOperation::Inspect
+                | MqOperation::Cancel => RetryAdvice::rejected(),
+            },
+            Self::Timeout { .. } => RetryAdvice::transient().with_delay(Duration::from_secs(1)),
+            Self::Io { operation, .. } => match operation {
+                IoOperation::Read => RetryAdvice::rejected(),
+                IoOperation::Write => RetryAdvice::transient(),
+                _ => RetryAdvice::permanent(),
+            },
+            _ => RetryAdvice::permanent(),
+        }
+    }
+}
+
 /// Configuration for retry behavior
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetryConfig {
@@ -153,6 +290,44 @@ pub enum RetryStrategy {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_retry_advice() {
+        let limited = InfraError::http_with_status(429, "Too many requests").retry_advice();
+        assert!(limited.allows_retry(false));
+        assert_eq!(limited.delay, Some(Duration::from_secs(30)));
+
+        let server = InfraError::http_with_status(502, "Bad gateway").retry_advice();
+        assert!(server.allows_retry(true));
+        assert!(!server.allows_retry(false));
+
+        let publish = InfraError::MessageQueue {
+            queue: "events".to_string(),
+            operation: MqOperation::Publish,
+            message: "Broker unreachable".to_string(),
+            context: None,
+            source: None,
+        };
+        assert!(publish.is_retryable());
+        assert!(!publish.retry_advice().idempotency_safe);
+
+        assert_eq!(
+            InfraError::validation("Bad input").retry_advice(),
+            RetryAdvice::permanent()
+        );
+        assert!(!InfraError::http_with_status(404, "Not found").is_retryable());
+        assert!(
+            InfraError::llm_provider("anthropic", LlmErrorKind::Overloaded, "Overloaded")
+                .retry_advice()
+                .allows_retry(false)
+        );
+        assert!(
+            !InfraError::llm_provider("openai", LlmErrorKind::ContentFiltered, "Blocked")
+                .is_retryable()
+        );
+        assert!(RetryAdvice::connection_failure(false).allows_retry(false));
+        assert!(!RetryAdvice::connection_failure(true).allows_retry(false));
+    }
+
     #[test]
     fn test_exponential_backoff() {
         let config = RetryConfig::new()
@@ -1,6 +1,8 @@
 //! Test utilities for infra-errors.
 
-use crate::{InfraError, VectorOperation, AuthErrorKind, CryptoOperation, IoOperation};
+use crate::{
+    InfraError, VectorOperation, AuthErrorKind, CryptoOperation, IoOperation, LlmErrorKind,
+};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -11,6 +13,7 @@ pub fn mock_config_error(message: &str) -> InfraError {
         message: message.to_string(),
         key: Some("test.key".to_string()),
         context: None,
+        source: None,
     }
 }
 
@@ -22,6 +25,7 @@ pub fn mock_http_error(status: u16) -> InfraError {
         message: format!("HTTP {status}"),
         url: Some("http://test.example.com".to_string()),
         context: None,
+        source: None,
     }
 }
 
@@ -33,6 +37,7 @@ pub fn mock_vector_error(operation: VectorOperation) -> InfraError {
         message: format!("Mock {operation} error"),
         dimensions: Some(128),
         context: None,
+        source: None,
     }
 }
 
@@ -44,6 +49,7 @@ pub fn mock_auth_error(kind: AuthErrorKind) -> InfraError {
         message: format!("Mock auth error: {kind}"),
         identity: Some("test@example.com".to_string()),
         context: None,
+        source: None,
     }
 }
 
@@ -54,6 +60,7 @@ pub fn mock_crypto_error(operation: CryptoOperation) -> InfraError {
         operation,
         message: format!("Mock crypto {operation} error"),
         context: None,
+        source: None,
     }
 }
 
@@ -65,6 +72,7 @@ pub fn mock_io_error(operation: IoOperation) -> InfraError {
         path: Some(PathBuf::from("/test/path")),
         message: format!("Mock I/O {operation} error"),
         context: None,
+        source: None,
     }
 }
 
@@ -75,6 +83,7 @@ pub fn mock_timeout_error() -> InfraError {
         operation: "test_operation".to_string(),
         duration: Duration::from_secs(30),
         context: None,
+        source: None,
     }
 }
 
@@ -85,6 +94,7 @@ pub fn mock_not_found_error(resource_type: &str, resource_id: &str) -> InfraErro
         resource_type: resource_type.to_string(),
         resource_id: resource_id.to_string(),
         context: None,
+        source: None,
     }
 }
 
@@ -97,6 +107,21 @@ pub fn mock_validation_error(field: &str, message: &str) -> InfraError {
         expected: Some("valid value".to_string()),
         actual: Some("invalid value".to_string()),
         context: None,
+        source: None,
+    }
+}
+
+/// Create a mock LLM provider error for testing
+#[must_use]
+pub fn mock_llm_provider_error(kind: LlmErrorKind) -> InfraError {
+    InfraError::LlmProvider {
+        provider: "openai".to_string(),
+        model: Some("gpt-4o".to_string()),
+        kind,
+        message: "Test LLM provider error".to_string(),
+        request_id: Some("req_test".to_string()),
+        context: None,
+        source: None,
     }
 }
 
@@ -121,5 +146,9 @@ mod tests {
 
         let timeout_err = mock_timeout_error();
         assert!(timeout_err.is_retryable());
+
+        let llm_err = mock_llm_provider_error(LlmErrorKind::ContextTooLong);
+        assert_eq!(llm_err.error_type(), "llm_provider");
+        assert!(!llm_err.is_retryable());
     }
 }
@@ -7,6 +7,7 @@ use wasm_bindgen::prelude::*;
 #[wasm_bindgen]
 pub struct JsInfraError {
     error_type: String,
+    code: String,
     message: String,
     details: JsValue,
 }
@@ -19,6 +20,12 @@ impl JsInfraError {
         self.error_type.clone()
     }
 
+    /// Get the stable error code, such as `INFRA-HTTP-0429`
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> String {
+        self.code.clone()
+    }
+
     /// Get the error message
     #[wasm_bindgen(getter)]
     pub fn message(&self) -> String {
@@ -51,11 +58,13 @@ impl JsInfraError {
 impl From<InfraError> for JsInfraError {
     fn from(err: InfraError) -> Self {
         let error_type = err.error_type().to_string();
+        let code = err.as_code().to_string();
         let message = err.to_string();
         let details = serialize_error_details(&err);
 
         Self {
             error_type,
+            code,
             message,
             details,
         }
@@ -41,6 +41,7 @@ pub fn read_json<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> Infr
         message: e.to_string(),
         location: None,
         context: None,
+        source: Some(infra_errors::ErrorSource::new(e)),
     })
 }
 
@@ -52,6 +53,7 @@ pub fn write_json<T: serde::Serialize>(path: impl AsRef<Path>, data: &T) -> Infr
             message: e.to_string(),
             location: None,
             context: None,
+            source: Some(infra_errors::ErrorSource::new(e)),
         }
     })?;
     write(path, content.as_bytes())
@@ -65,6 +67,7 @@ pub fn glob_files(pattern: &str) -> InfraResult<Vec<std::path::PathBuf>> {
             path: Some(std::path::PathBuf::from(pattern)),
             message: e.to_string(),
             context: None,
+            source: Some(infra_errors::ErrorSource::new(e)),
         })?
         .filter_map(|r| r.ok())
         .collect::<Vec<_>>()
@@ -80,6 +83,7 @@ pub fn walk_dir(path: impl AsRef<Path>) -> InfraResult<Vec<std::path::PathBuf>>
             path: Some(path.as_ref().to_path_buf()),
             message: e.to_string(),
             context: None,
+            source: Some(infra_errors::ErrorSource::new(e)),
         })?;
         if entry.file_type().is_file() {
             files.push(entry.path().to_path_buf());
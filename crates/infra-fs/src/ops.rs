@@ -1,6 +1,6 @@
 //! Basic file operations.
 
-use infra_errors::{InfraError, InfraResult, IoOperation};
+use infra_errors::{ErrorSource, InfraError, InfraResult, IoOperation};
 use std::fs;
 use std::path::Path;
 
@@ -12,6 +12,7 @@ pub fn read(path: impl AsRef<Path>) -> InfraResult<Vec<u8>> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -23,6 +24,7 @@ pub fn read_string(path: impl AsRef<Path>) -> InfraResult<String> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -38,6 +40,7 @@ pub fn write(path: impl AsRef<Path>, contents: &[u8]) -> InfraResult<()> {
                 path: Some(parent.to_path_buf()),
                 message: e.to_string(),
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })?;
         }
     }
@@ -47,6 +50,7 @@ pub fn write(path: impl AsRef<Path>, contents: &[u8]) -> InfraResult<()> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -64,6 +68,7 @@ pub fn append(path: impl AsRef<Path>, contents: &[u8]) -> InfraResult<()> {
             path: Some(path.to_path_buf()),
             message: e.to_string(),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })?;
 
     file.write_all(contents).map_err(|e| InfraError::Io {
@@ -71,6 +76,7 @@ pub fn append(path: impl AsRef<Path>, contents: &[u8]) -> InfraResult<()> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -87,6 +93,7 @@ pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> InfraResult<u64> {
                 path: Some(parent.to_path_buf()),
                 message: e.to_string(),
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })?;
         }
     }
@@ -96,6 +103,7 @@ pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> InfraResult<u64> {
         path: Some(from.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -113,6 +121,7 @@ pub fn remove(path: impl AsRef<Path>) -> InfraResult<()> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -129,6 +138,7 @@ pub fn create_dir(path: impl AsRef<Path>) -> InfraResult<()> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
@@ -140,6 +150,7 @@ pub fn create_dir_all(path: impl AsRef<Path>) -> InfraResult<()> {
         path: Some(path.to_path_buf()),
         message: e.to_string(),
         context: None,
+        source: Some(ErrorSource::new(e)),
     })
 }
 
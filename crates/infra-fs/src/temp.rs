@@ -1,6 +1,6 @@
 //! Temporary file and directory utilities.
 
-use infra_errors::{InfraError, InfraResult, IoOperation};
+use infra_errors::{ErrorSource, InfraError, InfraResult, IoOperation};
 use std::path::{Path, PathBuf};
 use tempfile;
 
@@ -18,6 +18,7 @@ impl TempFile {
             path: None,
             message: format!("Failed to create temp file: {e}"),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })?;
 
         let path = file.path().to_path_buf();
@@ -35,6 +36,7 @@ impl TempFile {
                 path: None,
                 message: format!("Failed to create temp file: {e}"),
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })?;
 
         let path = file.path().to_path_buf();
@@ -54,6 +56,7 @@ impl TempFile {
             path: Some(self.path.clone()),
             message: e.to_string(),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 
@@ -64,6 +67,7 @@ impl TempFile {
             path: Some(self.path.clone()),
             message: e.to_string(),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 }
@@ -82,6 +86,7 @@ impl TempDir {
             path: None,
             message: format!("Failed to create temp directory: {e}"),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })?;
 
         let path = dir.path().to_path_buf();
@@ -99,6 +104,7 @@ impl TempDir {
                 path: None,
                 message: format!("Failed to create temp directory: {e}"),
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })?;
 
         let path = dir.path().to_path_buf();
@@ -119,6 +125,7 @@ impl TempDir {
             path: Some(path.clone()),
             message: e.to_string(),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })?;
         Ok(path)
     }
@@ -131,6 +138,7 @@ impl TempDir {
             path: Some(path.clone()),
             message: e.to_string(),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })?;
         Ok(path)
     }
@@ -1,96 +1,39 @@
 //! HTTP client with retry and circuit breaker.
 
-use crate::{CircuitBreakerConfig, RetryConfig};
-use infra_errors::{InfraError, InfraResult};
+use crate::{CircuitBreakerConfig, Method, MiddlewareStack, Request, Response, RetryConfig};
+use infra_errors::{ErrorContext, ErrorSource, InfraError, InfraResult, RetryAdvice};
+use infra_retry::{CircuitBreaker, IDEMPOTENCY_KEY_HEADER};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-
-/// Circuit breaker state
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum CircuitState {
-    Closed,
-    Open,
-    HalfOpen,
-}
+use std::time::Duration;
 
-/// Circuit breaker
-struct CircuitBreaker {
-    state: RwLock<CircuitState>,
-    failure_count: AtomicU32,
-    success_count: AtomicU32,
-    last_failure_time: AtomicU64,
-    config: CircuitBreakerConfig,
-}
+/// Name recorded on circuit breaker span events
+const CIRCUIT_NAME: &str = "http";
 
-impl CircuitBreaker {
-    fn new(config: CircuitBreakerConfig) -> Self {
-        Self {
-            state: RwLock::new(CircuitState::Closed),
-            failure_count: AtomicU32::new(0),
-            success_count: AtomicU32::new(0),
-            last_failure_time: AtomicU64::new(0),
-            config,
-        }
-    }
+/// Context attribute of status errors holding the server's request ID
+pub const REQUEST_ID_ATTRIBUTE: &str = "request_id";
 
-    async fn allow_request(&self) -> bool {
-        let state = *self.state.read().await;
-        match state {
-            CircuitState::Closed => true,
-            CircuitState::Open => {
-                let last_failure = self.last_failure_time.load(Ordering::Relaxed);
-                let now = Instant::now().elapsed().as_secs();
-                if now - last_failure > self.config.open_duration.as_secs() {
-                    let mut state = self.state.write().await;
-                    *state = CircuitState::HalfOpen;
-                    true
-                } else {
-                    false
-                }
-            }
-            CircuitState::HalfOpen => true,
-        }
-    }
-
-    async fn record_success(&self) {
-        let state = *self.state.read().await;
-        if state == CircuitState::HalfOpen {
-            let count = self.success_count.fetch_add(1, Ordering::Relaxed) + 1;
-            if count >= self.config.success_threshold {
-                let mut state = self.state.write().await;
-                *state = CircuitState::Closed;
-                self.failure_count.store(0, Ordering::Relaxed);
-                self.success_count.store(0, Ordering::Relaxed);
-            }
-        }
-    }
+/// Context attribute of status errors holding the code of a JSON error body
+pub const ERROR_CODE_ATTRIBUTE: &str = "error_code";
 
-    async fn record_failure(&self) {
-        let count = self.failure_count.fetch_add(1, Ordering::Relaxed) + 1;
-        self.last_failure_time.store(
-            Instant::now().elapsed().as_secs(),
-            Ordering::Relaxed,
-        );
+/// Response headers carrying the server's ID of a request
+const REQUEST_ID_HEADERS: &[&str] = &["x-request-id", "request-id"];
 
-        if count >= self.config.failure_threshold {
-            let mut state = self.state.write().await;
-            *state = CircuitState::Open;
-        }
-    }
-}
+/// Largest error body read for its error code
+const MAX_ERROR_BODY: usize = 64 * 1024;
 
 /// HTTP client builder
 pub struct HttpClientBuilder {
     base_url: Option<String>,
     timeout: Duration,
     retry_config: RetryConfig,
-    circuit_breaker_config: Option<CircuitBreakerConfig>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
     default_headers: HashMap<String, String>,
+    middleware: Option<MiddlewareStack>,
+    #[cfg(feature = "chaos")]
+    network: Option<crate::SimNetworkMiddleware>,
 }
 
 impl Default for HttpClientBuilder {
@@ -106,8 +49,11 @@ impl HttpClientBuilder {
             base_url: None,
             timeout: Duration::from_secs(30),
             retry_config: RetryConfig::default(),
-            circuit_breaker_config: None,
+            circuit_breaker: None,
             default_headers: HashMap::new(),
+            middleware: None,
+            #[cfg(feature = "chaos")]
+            network: None,
         }
     }
 
@@ -131,7 +77,13 @@ impl HttpClientBuilder {
 
     /// Enable circuit breaker
     pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
-        self.circuit_breaker_config = Some(config);
+        self.circuit_breaker = Some(Arc::new(CircuitBreaker::new(CIRCUIT_NAME, config)));
+        self
+    }
+
+    /// Use a circuit breaker shared with other clients of the same service
+    pub fn shared_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
         self
     }
 
@@ -141,6 +93,37 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Run every attempt of every request through a middleware stack
+    ///
+    /// Requests reach the middleware without the client's default headers,
+    /// and responses are read in full before being passed to it. A
+    /// middleware failure without an HTTP status counts as a transport
+    /// failure, of connecting when raised on the request and after sending
+    /// when raised on the response; other middleware failures follow their
+    /// [`RetryAdvice`].
+    pub fn middleware(mut self, stack: MiddlewareStack) -> Self {
+        self.middleware = Some(stack);
+        self
+    }
+
+    /// Send requests over an infra-sim network, from a client node to a
+    /// server node
+    ///
+    /// The network runs after any other middleware, as the last step before
+    /// sending and the first after receiving. Traffic lost before reaching
+    /// the server is retried like a refused connection, and lost replies
+    /// like a failure after sending.
+    #[cfg(feature = "chaos")]
+    pub fn network(
+        mut self,
+        network: infra_sim::SimNetwork,
+        client: impl Into<String>,
+        server: impl Into<String>,
+    ) -> Self {
+        self.network = Some(crate::SimNetworkMiddleware::new(network, client, server));
+        self
+    }
+
     /// Build the client
     pub fn build(self) -> InfraResult<HttpClient> {
         let mut headers = HeaderMap::new();
@@ -151,6 +134,7 @@ impl HttpClientBuilder {
                     message: format!("Invalid header name: {e}"),
                     url: None,
                     context: None,
+                    source: Some(ErrorSource::new(e)),
                 }
             })?;
             let header_value = HeaderValue::try_from(value.as_str()).map_err(|e| {
@@ -159,6 +143,7 @@ impl HttpClientBuilder {
                     message: format!("Invalid header value: {e}"),
                     url: None,
                     context: None,
+                    source: Some(ErrorSource::new(e)),
                 }
             })?;
             headers.insert(header_name, header_value);
@@ -173,17 +158,23 @@ impl HttpClientBuilder {
                 message: format!("Failed to build HTTP client: {e}"),
                 url: None,
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })?;
 
-        let circuit_breaker = self
-            .circuit_breaker_config
-            .map(|config| Arc::new(CircuitBreaker::new(config)));
+        #[cfg(feature = "chaos")]
+        let middleware = match self.network {
+            Some(network) => Some(self.middleware.unwrap_or_default().add(network)),
+            None => self.middleware,
+        };
+        #[cfg(not(feature = "chaos"))]
+        let middleware = self.middleware;
 
         Ok(HttpClient {
             client,
             base_url: self.base_url,
             retry_config: self.retry_config,
-            circuit_breaker,
+            circuit_breaker: self.circuit_breaker,
+            middleware: middleware.map(Arc::new),
         })
     }
 }
@@ -194,6 +185,7 @@ pub struct HttpClient {
     base_url: Option<String>,
     retry_config: RetryConfig,
     circuit_breaker: Option<Arc<CircuitBreaker>>,
+    middleware: Option<Arc<MiddlewareStack>>,
 }
 
 impl HttpClient {
@@ -217,18 +209,26 @@ impl HttpClient {
     }
 
     /// Execute a request with retry
+    ///
+    /// Whether a failure is retried, and after how long, follows the error's
+    /// [`RetryAdvice`], with the server's `Retry-After` header taking
+    /// precedence over the advised delay. Requests with non-idempotent
+    /// methods and no `Idempotency-Key` header are only retried after
+    /// failures that are idempotency-safe, such as a 429 or a refused
+    /// connection, so they are never applied twice.
     async fn execute_with_retry(
         &self,
         request_builder: reqwest::RequestBuilder,
     ) -> InfraResult<reqwest::Response> {
         // Check circuit breaker
         if let Some(cb) = &self.circuit_breaker {
-            if !cb.allow_request().await {
+            if !cb.allow() {
                 return Err(InfraError::Http {
                     status: Some(503),
                     message: "Circuit breaker is open".to_string(),
                     url: None,
                     context: None,
+                    source: None,
                 });
             }
         }
@@ -247,59 +247,72 @@ impl HttpClient {
                     message: "Request body cannot be cloned for retry".to_string(),
                     url: None,
                     context: None,
+                    source: None,
+                })?
+                .build()
+                .map_err(|e| InfraError::Http {
+                    status: None,
+                    message: format!("Invalid request: {e}"),
+                    url: None,
+                    context: None,
+                    source: Some(ErrorSource::new(e)),
                 })?;
+            let idempotent = request.method().is_idempotent()
+                || request.headers().contains_key(IDEMPOTENCY_KEY_HEADER);
 
-            match request.send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        if let Some(cb) = &self.circuit_breaker {
-                            cb.record_success().await;
-                        }
-                        return Ok(response);
-                    }
-
-                    let status = response.status().as_u16();
-
-                    // Don't retry client errors (4xx)
-                    if status >= 400 && status < 500 {
-                        return Err(InfraError::Http {
-                            status: Some(status),
-                            message: format!("HTTP error: {}", response.status()),
-                            url: None,
-                            context: None,
-                        });
-                    }
-
-                    // Retry server errors (5xx)
-                    if attempts > self.retry_config.max_retries {
-                        if let Some(cb) = &self.circuit_breaker {
-                            cb.record_failure().await;
-                        }
-                        return Err(InfraError::Http {
-                            status: Some(status),
-                            message: format!("HTTP error after {} retries: {}", attempts, response.status()),
-                            url: None,
-                            context: None,
-                        });
+            let (error, advice, demanded) = match self.send(request).await {
+                Ok(response) if response.status().is_success() => {
+                    if let Some(cb) = &self.circuit_breaker {
+                        cb.record_success();
                     }
+                    return Ok(response);
                 }
-                Err(e) => {
-                    if attempts > self.retry_config.max_retries {
-                        if let Some(cb) = &self.circuit_breaker {
-                            cb.record_failure().await;
-                        }
-                        return Err(InfraError::Http {
-                            status: None,
-                            message: format!("Request failed after {} retries: {}", attempts, e),
-                            url: None,
-                            context: None,
-                        });
+                Ok(response) => {
+                    let status = response.status();
+                    let demanded = retry_after(response.headers());
+                    let error = InfraError::Http {
+                        status: Some(status.as_u16()),
+                        message: format!("HTTP error: {status}"),
+                        url: None,
+                        context: failure_context(response).await,
+                        source: None,
+                    };
+                    let advice = error.retry_advice();
+                    (error, advice, demanded)
+                }
+                Err((error, advice)) => (error, advice, None),
+            };
+
+            let server_failure = !matches!(
+                error,
+                InfraError::Http { status: Some(status), .. } if status < 500
+            );
+            // A delay demanded by the server is honored or given up on, an
+            // advised one is capped like the backoff
+            let max_delay = self.retry_config.max_delay;
+            let wait = match demanded {
+                Some(after) => after,
+                None => advice.delay.map_or(delay, |after| after.min(max_delay)),
+            }
+            .max(delay);
+            if !advice.allows_retry(idempotent)
+                || attempts > self.retry_config.max_retries
+                || demanded.is_some_and(|after| after > max_delay)
+            {
+                // A client error shows the server is answering
+                if let Some(cb) = &self.circuit_breaker {
+                    if server_failure {
+                        cb.record_failure();
+                    } else {
+                        cb.record_success();
                     }
                 }
+                return Err(error);
             }
 
             // Wait before retry
-            tokio::time::sleep(delay).await;
+            infra_otel::record_retry_attempt(attempts + 1, wait, &error.to_string());
+            tokio::time::sleep(wait).await;
             delay = std::cmp::min(
                 Duration::from_secs_f64(delay.as_secs_f64() * self.retry_config.multiplier),
                 self.retry_config.max_delay,
@@ -307,6 +320,56 @@ impl HttpClient {
         }
     }
 
+    /// Send one attempt of a request, through the middleware if any
+    async fn send(
+        &self,
+        mut request: reqwest::Request,
+    ) -> Result<reqwest::Response, (InfraError, RetryAdvice)> {
+        let Some(stack) = &self.middleware else {
+            return self
+                .client
+                .execute(request)
+                .await
+                .map_err(transport_failure);
+        };
+
+        let processed = stack
+            .process_request(to_request(&request)?)
+            .await
+            .map_err(|e| middleware_failure(e, false))?;
+        apply_request(&mut request, processed)?;
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(transport_failure)?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response.bytes().await.map_err(transport_failure)?.to_vec();
+        let processed = stack
+            .process_response(Response {
+                status,
+                headers,
+                body,
+            })
+            .await
+            .map_err(|e| middleware_failure(e, true))?;
+
+        let mut response = http::Response::builder().status(processed.status);
+        for (name, value) in &processed.headers {
+            response = response.header(name, value);
+        }
+        response
+            .body(processed.body)
+            .map(reqwest::Response::from)
+            .map_err(|e| invalid_request(format!("Invalid response from middleware: {e}"), e))
+    }
+
     /// Send a GET request
     pub async fn get(&self, path: &str) -> InfraResult<reqwest::Response> {
         let url = self.build_url(path);
@@ -321,6 +384,22 @@ impl HttpClient {
         self.execute_with_retry(request).await
     }
 
+    /// Send a POST request with a raw body and content type
+    pub async fn post_bytes(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> InfraResult<reqwest::Response> {
+        let url = self.build_url(path);
+        let request = self
+            .client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body);
+        self.execute_with_retry(request).await
+    }
+
     /// Send a PUT request with JSON body
     pub async fn put<T: Serialize>(&self, path: &str, body: &T) -> InfraResult<reqwest::Response> {
         let url = self.build_url(path);
@@ -343,6 +422,7 @@ impl HttpClient {
             message: format!("Failed to parse JSON response: {e}"),
             url: Some(self.build_url(path)),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 
@@ -358,6 +438,282 @@ impl HttpClient {
             message: format!("Failed to parse JSON response: {e}"),
             url: Some(self.build_url(path)),
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 }
+
+/// Error and retry advice of a request that failed in transport
+fn transport_failure(e: reqwest::Error) -> (InfraError, RetryAdvice) {
+    let advice = RetryAdvice::connection_failure(!e.is_connect());
+    let error = InfraError::Http {
+        status: None,
+        message: format!("Request failed: {e}"),
+        url: None,
+        context: None,
+        source: Some(ErrorSource::new(e)),
+    };
+    (error, advice)
+}
+
+/// Retry advice of a middleware failure, raised after sending or not
+fn middleware_failure(error: InfraError, sent: bool) -> (InfraError, RetryAdvice) {
+    let advice = match error {
+        InfraError::Http { status: None, .. } => RetryAdvice::connection_failure(sent),
+        _ => error.retry_advice(),
+    };
+    (error, advice)
+}
+
+/// Error of a request or response left invalid by the middleware
+fn invalid_request(
+    message: String,
+    source: impl std::error::Error + Send + Sync + 'static,
+) -> (InfraError, RetryAdvice) {
+    let error = InfraError::Http {
+        status: None,
+        message,
+        url: None,
+        context: None,
+        source: Some(ErrorSource::new(source)),
+    };
+    (error, RetryAdvice::permanent())
+}
+
+/// Copy a request for the middleware
+///
+/// Header values that are not text are left out, and repeated headers
+/// keep a single value.
+fn to_request(request: &reqwest::Request) -> Result<Request, (InfraError, RetryAdvice)> {
+    let method = match *request.method() {
+        http::Method::GET => Method::Get,
+        http::Method::POST => Method::Post,
+        http::Method::PUT => Method::Put,
+        http::Method::DELETE => Method::Delete,
+        http::Method::PATCH => Method::Patch,
+        http::Method::HEAD => Method::Head,
+        http::Method::OPTIONS => Method::Options,
+        ref method => {
+            let error = InfraError::Http {
+                status: None,
+                message: format!("Method {method} is not supported by middleware"),
+                url: Some(request.url().to_string()),
+                context: None,
+                source: None,
+            };
+            return Err((error, RetryAdvice::permanent()));
+        }
+    };
+    Ok(Request {
+        method,
+        url: request.url().to_string(),
+        headers: request
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .map(<[u8]>::to_vec),
+    })
+}
+
+/// Apply the middleware's changes to a request
+fn apply_request(
+    request: &mut reqwest::Request,
+    processed: Request,
+) -> Result<(), (InfraError, RetryAdvice)> {
+    *request.method_mut() = processed.method.into();
+    *request.url_mut() = reqwest::Url::parse(&processed.url)
+        .map_err(|e| invalid_request(format!("Invalid URL from middleware: {e}"), e))?;
+    let headers = request.headers_mut();
+    headers.clear();
+    for (name, value) in &processed.headers {
+        let name = HeaderName::try_from(name.as_str())
+            .map_err(|e| invalid_request(format!("Invalid header name: {e}"), e))?;
+        let value = HeaderValue::try_from(value.as_str())
+            .map_err(|e| invalid_request(format!("Invalid header value: {e}"), e))?;
+        headers.append(name, value);
+    }
+    *request.body_mut() = processed.body.map(reqwest::Body::from);
+    Ok(())
+}
+
+/// Describe a failed response in its error's context
+///
+/// Records the server's request ID and the `error.code`, or else the
+/// `error.type`, of a JSON error body such as
+/// `{"error": {"code": "context_length_exceeded"}}`.
+async fn failure_context(mut response: reqwest::Response) -> Option<ErrorContext> {
+    let request_id = REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| response.headers().get(*name)?.to_str().ok())
+        .map(str::to_string);
+
+    let mut body = Vec::new();
+    while let Ok(Some(chunk)) = response.chunk().await {
+        body.extend_from_slice(&chunk);
+        if body.len() > MAX_ERROR_BODY {
+            body.clear();
+            break;
+        }
+    }
+    let code = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|body| {
+            let error = body.get("error")?;
+            let code = error.get("code").filter(|code| !code.is_null());
+            code.or_else(|| error.get("type"))?
+                .as_str()
+                .map(str::to_string)
+        });
+
+    if request_id.is_none() && code.is_none() {
+        return None;
+    }
+    let mut context = ErrorContext::new();
+    if let Some(request_id) = request_id {
+        context = context.with_attribute(REQUEST_ID_ATTRIBUTE, request_id);
+    }
+    if let Some(code) = code {
+        context = context.with_attribute(ERROR_CODE_ATTRIBUTE, code);
+    }
+    Some(context)
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+///
+/// Dates in the past mean retrying right away.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(std::time::SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            HeaderValue::from_static("120"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        let date = std::time::SystemTime::now() + Duration::from_secs(120);
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            HeaderValue::from_str(&httpdate::fmt_http_date(date)).unwrap(),
+        );
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(115) && delay <= Duration::from_secs(120));
+
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            HeaderValue::from_static("soon"),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_failures() {
+        use crate::Middleware;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        struct Failing {
+            attempts: Arc<AtomicU32>,
+            status: Option<u16>,
+        }
+
+        #[async_trait::async_trait]
+        impl Middleware for Failing {
+            async fn before(&self, _request: Request) -> InfraResult<Request> {
+                self.attempts.fetch_add(1, Ordering::SeqCst);
+                Err(InfraError::Http {
+                    status: self.status,
+                    message: "unreachable".to_string(),
+                    url: None,
+                    context: None,
+                    source: None,
+                })
+            }
+        }
+
+        let retry = RetryConfig {
+            max_retries: 2,
+            initial_delay: Duration::from_millis(1),
+            ..RetryConfig::default()
+        };
+        let client = |status| {
+            let attempts = Arc::new(AtomicU32::new(0));
+            let failing = Failing {
+                attempts: attempts.clone(),
+                status,
+            };
+            let client = HttpClient::builder()
+                .retry(retry.clone())
+                .middleware(MiddlewareStack::new().add(failing))
+                .build()
+                .unwrap();
+            (client, attempts)
+        };
+
+        // Failing before sending is retried even for a POST
+        let (failing, attempts) = client(None);
+        assert!(failing.post("http://localhost/x", &1).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let (failing, attempts) = client(Some(400));
+        assert!(failing.get("http://localhost/x").await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failure_context() {
+        let response = http::Response::builder()
+            .status(400)
+            .header("x-request-id", "req_123")
+            .body(r#"{"error": {"code": "context_length_exceeded", "type": "invalid_request_error"}}"#)
+            .unwrap();
+        let context = failure_context(response.into()).await.unwrap();
+        assert_eq!(context.attributes[REQUEST_ID_ATTRIBUTE], "req_123");
+        assert_eq!(
+            context.attributes[ERROR_CODE_ATTRIBUTE],
+            "context_length_exceeded"
+        );
+
+        let response = http::Response::builder()
+            .status(529)
+            .body(r#"{"error": {"type": "overloaded_error"}}"#)
+            .unwrap();
+        let context = failure_context(response.into()).await.unwrap();
+        assert_eq!(context.attributes[ERROR_CODE_ATTRIBUTE], "overloaded_error");
+        assert!(!context.attributes.contains_key(REQUEST_ID_ATTRIBUTE));
+
+        let response = http::Response::builder()
+            .status(502)
+            .body("Bad gateway")
+            .unwrap();
+        assert!(failure_context(response.into()).await.is_none());
+    }
+}
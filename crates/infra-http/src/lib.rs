@@ -7,17 +7,29 @@
 mod client;
 #[cfg(feature = "server")]
 mod server;
+#[cfg(feature = "metrics-push")]
+mod push;
 mod request;
 mod response;
 mod middleware;
+mod signing;
+#[cfg(feature = "chaos")]
+mod network;
 
 #[cfg(feature = "client")]
-pub use client::{HttpClient, HttpClientBuilder};
+pub use client::{HttpClient, HttpClientBuilder, ERROR_CODE_ATTRIBUTE, REQUEST_ID_ATTRIBUTE};
+#[cfg(feature = "metrics-push")]
+pub use push::HttpPushTransport;
 #[cfg(feature = "server")]
-pub use server::{ServerBuilder, Router};
+pub use server::{ApiError, ServerBuilder, Router};
 pub use request::{Request, RequestBuilder};
 pub use response::{Response, ResponseExt};
 pub use middleware::{Middleware, MiddlewareStack};
+pub use signing::{SigningMiddleware, WebhookVerifier, SIGNATURE_HEADER};
+#[cfg(feature = "chaos")]
+pub use network::SimNetworkMiddleware;
+
+pub use infra_retry::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
 
 use std::time::Duration;
 
@@ -71,27 +83,6 @@ impl Default for RetryConfig {
     }
 }
 
-/// Circuit breaker configuration
-#[derive(Debug, Clone)]
-pub struct CircuitBreakerConfig {
-    /// Failure threshold before opening
-    pub failure_threshold: u32,
-    /// Success threshold to close
-    pub success_threshold: u32,
-    /// Duration to stay open
-    pub open_duration: Duration,
-}
-
-impl Default for CircuitBreakerConfig {
-    fn default() -> Self {
-        Self {
-            failure_threshold: 5,
-            success_threshold: 3,
-            open_duration: Duration::from_secs(30),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,140 @@
+//! Simulated network conditions for tests.
+
+use crate::middleware::Middleware;
+use crate::request::Request;
+use crate::response::Response;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult};
+use infra_sim::{NetworkFault, SimNetwork};
+
+/// Middleware routing traffic over an infra-sim [`SimNetwork`]
+///
+/// Requests travel from the client node to the server node before being
+/// sent, and responses back after being received, with the latency, loss
+/// and partitions of those links. Lost traffic fails with an HTTP error
+/// without status, like a transport failure. `HttpClientBuilder::network`
+/// sends a client's requests through it.
+#[derive(Clone)]
+pub struct SimNetworkMiddleware {
+    network: SimNetwork,
+    client: String,
+    server: String,
+}
+
+impl SimNetworkMiddleware {
+    /// Create for traffic between a client node and a server node
+    pub fn new(network: SimNetwork, client: impl Into<String>, server: impl Into<String>) -> Self {
+        Self {
+            network,
+            client: client.into(),
+            server: server.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for SimNetworkMiddleware {
+    async fn before(&self, request: Request) -> InfraResult<Request> {
+        self.network
+            .transmit(&self.client, &self.server)
+            .await
+            .map_err(|fault| network_error(&fault, Some(&request.url)))?;
+        Ok(request)
+    }
+
+    async fn after(&self, response: Response) -> InfraResult<Response> {
+        self.network
+            .transmit(&self.server, &self.client)
+            .await
+            .map_err(|fault| network_error(&fault, None))?;
+        Ok(response)
+    }
+
+    fn name(&self) -> &str {
+        "sim-network"
+    }
+}
+
+fn network_error(fault: &NetworkFault, url: Option<&str>) -> InfraError {
+    InfraError::Http {
+        status: None,
+        message: fault.to_string(),
+        url: url.map(ToString::to_string),
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MiddlewareStack;
+    use infra_sim::{simulate, LinkConditions};
+    use std::time::Duration;
+
+    #[test]
+    fn test_sim_network_middleware() {
+        let network = SimNetwork::with_default(
+            LinkConditions::new().constant_latency(Duration::from_millis(20)),
+        );
+        let stack = MiddlewareStack::new().add(SimNetworkMiddleware::new(
+            network.clone(),
+            "app",
+            "gateway",
+        ));
+
+        let elapsed = simulate(async {
+            let start = tokio::time::Instant::now();
+            let request = stack
+                .process_request(Request::get("http://gateway/v1/models"))
+                .await
+                .unwrap();
+            assert_eq!(request.url, "http://gateway/v1/models");
+            stack.process_response(Response::ok()).await.unwrap();
+            start.elapsed()
+        });
+        assert_eq!(elapsed, Duration::from_millis(40));
+
+        network.partition(&["app"], &["gateway"]);
+        let err =
+            simulate(stack.process_request(Request::get("http://gateway/v1/models"))).unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::Http {
+                status: None,
+                url: Some(_),
+                ..
+            }
+        ));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_http_client_network() {
+        use crate::{HttpClient, RetryConfig};
+
+        let network = SimNetwork::with_default(
+            LinkConditions::new().constant_latency(Duration::from_millis(20)),
+        );
+        network.partition(&["app"], &["gateway"]);
+        let client = HttpClient::builder()
+            .retry(RetryConfig {
+                max_retries: 2,
+                initial_delay: Duration::from_secs(1),
+                ..RetryConfig::default()
+            })
+            .network(network, "app", "gateway")
+            .build()
+            .unwrap();
+
+        // Requests lost on the way out never reach the server, so even a
+        // POST is retried
+        let (err, elapsed) = simulate(async {
+            let start = tokio::time::Instant::now();
+            let err = client.post("http://gateway/v1/chat", &1).await.unwrap_err();
+            (err, start.elapsed())
+        });
+        assert!(matches!(err, InfraError::Http { status: None, .. }));
+        assert_eq!(elapsed, Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,44 @@
+//! HTTP transport for pushed metrics.
+
+use crate::HttpClient;
+use async_trait::async_trait;
+use infra_errors::InfraResult;
+use infra_otel::{PushFormat, PushTransport};
+use serde_json::Value;
+
+/// Pushes encoded metrics to a collector with a JSON POST
+///
+/// For [`PushFormat::OtlpJson`] point the URL at the collector's
+/// `/v1/metrics` endpoint.
+pub struct HttpPushTransport {
+    client: HttpClient,
+    url: String,
+}
+
+impl HttpPushTransport {
+    /// Create a transport with a default client
+    pub fn new(url: impl Into<String>) -> InfraResult<Self> {
+        Ok(Self::with_client(HttpClient::new()?, url))
+    }
+
+    /// Create a transport with a configured client
+    pub fn with_client(client: HttpClient, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+
+    /// Get the collector URL
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl PushTransport for HttpPushTransport {
+    async fn push(&self, payload: &Value, _format: PushFormat) -> InfraResult<()> {
+        self.client.post(&self.url, payload).await?;
+        Ok(())
+    }
+}
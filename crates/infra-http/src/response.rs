@@ -1,6 +1,8 @@
 //! HTTP response utilities.
 
-use infra_errors::{InfraError, InfraResult};
+use infra_errors::{
+    ErrorSource, InfraError, InfraResult, ProblemDetails, PROBLEM_JSON_CONTENT_TYPE,
+};
 
 /// HTTP response wrapper
 #[derive(Debug)]
@@ -63,6 +65,24 @@ impl Response {
         Self::new(500)
     }
 
+    /// Create an error response from an error, with a problem details body
+    pub fn problem(error: &InfraError) -> Self {
+        Self::from_problem(&error.to_problem_details())
+    }
+
+    /// Create an error response from problem details
+    ///
+    /// Problems with a retry delay also get a `Retry-After` header.
+    pub fn from_problem(problem: &ProblemDetails) -> Self {
+        let response = Self::new(problem.status)
+            .header("Content-Type", PROBLEM_JSON_CONTENT_TYPE)
+            .body(problem.to_json());
+        match problem.retry_after {
+            Some(retry_after) => response.header("Retry-After", retry_after.to_string()),
+            None => response,
+        }
+    }
+
     /// Check if the response is successful
     pub fn is_success(&self) -> bool {
         self.status >= 200 && self.status < 300
@@ -105,6 +125,7 @@ impl Response {
             message: format!("Failed to parse JSON: {e}"),
             url: None,
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 
@@ -115,6 +136,7 @@ impl Response {
             message: format!("Invalid UTF-8: {e}"),
             url: None,
             context: None,
+            source: Some(ErrorSource::new(e)),
         })
     }
 }
@@ -135,6 +157,7 @@ impl ResponseExt for Response {
                 message: format!("HTTP error: {}", self.status),
                 url: None,
                 context: None,
+                source: None,
             })
         }
     }
@@ -150,4 +173,17 @@ mod tests {
         assert!(Response::bad_request().is_client_error());
         assert!(Response::internal_error().is_server_error());
     }
+
+    #[test]
+    fn test_problem_response() {
+        let response = Response::problem(&InfraError::not_found("model", "gpt-5"));
+        assert_eq!(response.status, 404);
+        assert_eq!(
+            response.headers.get("Content-Type").map(String::as_str),
+            Some(PROBLEM_JSON_CONTENT_TYPE)
+        );
+        let problem: ProblemDetails = response.parse_json().unwrap();
+        assert_eq!(problem.code.unwrap().to_string(), "INFRA-NOT_FOUND-0001");
+        assert!(!response.headers.contains_key("Retry-After"));
+    }
 }
@@ -1,7 +1,9 @@
 //! HTTP server utilities.
 
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response as AxumResponse};
 use axum::Router as AxumRouter;
-use infra_errors::{InfraError, InfraResult};
+use infra_errors::{ErrorSource, InfraError, InfraResult, PROBLEM_JSON_CONTENT_TYPE};
 use std::net::SocketAddr;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -115,6 +117,7 @@ impl ServerBuilder {
                 message: format!("Failed to bind to {}: {}", self.addr, e),
                 url: None,
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })?;
 
         axum::serve(listener, app)
@@ -124,6 +127,71 @@ impl ServerBuilder {
                 message: format!("Server error: {e}"),
                 url: None,
                 context: None,
+                source: Some(ErrorSource::new(e)),
             })
     }
 }
+
+/// Error returned from axum handlers, responding with problem details
+///
+/// The status comes from [`InfraError::to_http_status`], the body is the
+/// error's RFC 7807 problem details, and retryable errors get a
+/// `Retry-After` header, so handlers can use `?` on any [`InfraResult`]:
+///
+/// ```ignore
+/// async fn get_model(Path(id): Path<String>) -> Result<Json<Model>, ApiError> {
+///     Ok(Json(registry.get(&id)?))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ApiError(pub InfraError);
+
+impl From<InfraError> for ApiError {
+    fn from(error: InfraError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> AxumResponse {
+        let problem = self.0.to_problem_details();
+        let status =
+            StatusCode::from_u16(problem.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (
+            status,
+            [(header::CONTENT_TYPE, PROBLEM_JSON_CONTENT_TYPE)],
+            problem.to_json(),
+        )
+            .into_response();
+        if let Some(retry_after) = problem.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_errors::AuthErrorKind;
+
+    #[test]
+    fn test_api_error_response() {
+        let response = ApiError::from(InfraError::Auth {
+            kind: AuthErrorKind::RateLimited,
+            message: "Slow down".to_string(),
+            identity: None,
+            context: None,
+            source: None,
+        })
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            PROBLEM_JSON_CONTENT_TYPE
+        );
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+    }
+}
@@ -0,0 +1,273 @@
+//! Request signing and webhook signature verification.
+//!
+//! Signatures are sent as `t=<unix seconds>,v1=<hex tag>`, where the tag is
+//! a MAC over the timestamp, a `.`, and the signed content. Verification
+//! rejects timestamps outside a tolerance window to stop replays, and
+//! accepts any of several `v1` tags so senders can sign with both the old
+//! and new secret while rotating.
+
+use crate::middleware::Middleware;
+use crate::request::Request;
+use async_trait::async_trait;
+use infra_crypto::Mac;
+use infra_errors::{CryptoOperation, InfraError, InfraResult};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Header carrying request signatures
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Default tolerance between signing and verification times
+const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+/// Signature scheme shared by webhooks and signed requests
+#[derive(Clone)]
+struct SignatureScheme {
+    mac: Arc<dyn Mac>,
+    tolerance: Duration,
+}
+
+impl SignatureScheme {
+    fn new(mac: Arc<dyn Mac>) -> Self {
+        Self {
+            mac,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    fn tag(&self, timestamp: u64, prefix: &str, body: &[u8]) -> Vec<u8> {
+        let mut stream = self.mac.start();
+        stream.update(timestamp.to_string().as_bytes());
+        stream.update(b".");
+        stream.update(prefix.as_bytes());
+        stream.update(body);
+        stream.finalize()
+    }
+
+    fn sign(&self, timestamp: u64, prefix: &str, body: &[u8]) -> String {
+        let tag = self.tag(timestamp, prefix, body);
+        format!("t={timestamp},v1={}", infra_crypto::encode_hex(&tag))
+    }
+
+    fn verify(&self, header: &str, prefix: &str, body: &[u8]) -> InfraResult<()> {
+        let mut timestamp = None;
+        let mut tags = Vec::new();
+        for part in header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => {
+                    timestamp = Some(
+                        value
+                            .parse::<u64>()
+                            .map_err(|_| verify_error("Invalid signature timestamp"))?,
+                    );
+                }
+                Some(("v1", value)) => tags.push(value),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or_else(|| verify_error("Missing signature timestamp"))?;
+        if tags.is_empty() {
+            return Err(verify_error("Missing v1 signature"));
+        }
+        if now().abs_diff(timestamp) > self.tolerance.as_secs() {
+            return Err(verify_error("Signature timestamp outside tolerance"));
+        }
+
+        let expected = self.tag(timestamp, prefix, body);
+        let matched = tags.iter().any(|tag| {
+            infra_crypto::decode_hex(tag)
+                .is_ok_and(|tag| infra_crypto::constant_time_eq(&tag, &expected))
+        });
+        if matched {
+            Ok(())
+        } else {
+            Err(verify_error("Signature mismatch"))
+        }
+    }
+}
+
+/// Signs and verifies webhook payloads
+///
+/// ```text
+/// let verifier = WebhookVerifier::new(Hmac::sha256(secret));
+/// verifier.verify(&headers["X-Signature"], &body)?;
+/// ```
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    scheme: SignatureScheme,
+}
+
+impl WebhookVerifier {
+    /// Create with a MAC keyed with the webhook secret
+    pub fn new(mac: impl Mac + 'static) -> Self {
+        Self {
+            scheme: SignatureScheme::new(Arc::new(mac)),
+        }
+    }
+
+    /// Set how far the signature timestamp may be from now
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.scheme.tolerance = tolerance;
+        self
+    }
+
+    /// Sign a payload with the current time, returning the header value
+    pub fn sign(&self, body: &[u8]) -> String {
+        self.sign_at(now(), body)
+    }
+
+    /// Sign a payload with a given unix timestamp
+    pub fn sign_at(&self, timestamp: u64, body: &[u8]) -> String {
+        self.scheme.sign(timestamp, "", body)
+    }
+
+    /// Verify a signature header against the payload
+    pub fn verify(&self, header: &str, body: &[u8]) -> InfraResult<()> {
+        self.scheme.verify(header, "", body)
+    }
+}
+
+/// Middleware signing outgoing requests
+///
+/// The signature covers the method, URL and body, so a signed request
+/// cannot be replayed against another endpoint. Servers check it with
+/// [`verify`](Self::verify) using the same key.
+#[derive(Clone)]
+pub struct SigningMiddleware {
+    scheme: SignatureScheme,
+    header: String,
+}
+
+impl SigningMiddleware {
+    /// Create with a MAC keyed with the shared secret
+    pub fn new(mac: impl Mac + 'static) -> Self {
+        Self {
+            scheme: SignatureScheme::new(Arc::new(mac)),
+            header: SIGNATURE_HEADER.to_string(),
+        }
+    }
+
+    /// Set the header carrying the signature
+    pub fn header(mut self, name: impl Into<String>) -> Self {
+        self.header = name.into();
+        self
+    }
+
+    /// Set how far the signature timestamp may be from now
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.scheme.tolerance = tolerance;
+        self
+    }
+
+    /// Sign a request
+    pub fn sign(&self, request: &mut Request) {
+        let signature = self.scheme.sign(
+            now(),
+            &signed_prefix(request),
+            request.body.as_deref().unwrap_or_default(),
+        );
+        request.headers.insert(self.header.clone(), signature);
+    }
+
+    /// Verify the signature of a received request
+    pub fn verify(&self, request: &Request) -> InfraResult<()> {
+        let header = request
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&self.header))
+            .map(|(_, value)| value)
+            .ok_or_else(|| verify_error("Missing signature header"))?;
+        self.scheme.verify(
+            header,
+            &signed_prefix(request),
+            request.body.as_deref().unwrap_or_default(),
+        )
+    }
+}
+
+#[async_trait]
+impl Middleware for SigningMiddleware {
+    async fn before(&self, mut request: Request) -> InfraResult<Request> {
+        self.sign(&mut request);
+        Ok(request)
+    }
+
+    fn name(&self) -> &str {
+        "signing"
+    }
+}
+
+/// Request parts signed before the body
+fn signed_prefix(request: &Request) -> String {
+    format!(
+        "{}\n{}\n",
+        http::Method::from(request.method).as_str(),
+        request.url
+    )
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn verify_error(message: &str) -> InfraError {
+    InfraError::Crypto {
+        operation: CryptoOperation::Verify,
+        message: message.to_string(),
+        context: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Method, MiddlewareStack};
+    use infra_crypto::Hmac;
+
+    #[test]
+    fn test_webhook_signature() {
+        let verifier = WebhookVerifier::new(Hmac::sha256(b"whsec_old"));
+        let body = br#"{"event":"completion.finished"}"#;
+        let timestamp = now();
+        let header = verifier.sign_at(timestamp, body);
+        verifier.verify(&header, body).unwrap();
+        assert!(verifier.verify(&header, b"{}").is_err());
+        assert!(verifier.verify("v1=00", body).is_err());
+
+        // Signed with both secrets while rotating
+        let new = WebhookVerifier::new(Hmac::sha256(b"whsec_new"));
+        let signed = new.sign_at(timestamp, body);
+        let (_, new_tag) = signed.split_once(',').unwrap();
+        let rotated = format!("{header},{new_tag}");
+        new.verify(&rotated, body).unwrap();
+        verifier.verify(&rotated, body).unwrap();
+
+        let stale = verifier.sign_at(now() - 600, body);
+        assert!(verifier.verify(&stale, body).is_err());
+        verifier
+            .clone()
+            .tolerance(Duration::from_secs(900))
+            .verify(&stale, body)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signing_middleware() {
+        let signer = SigningMiddleware::new(Hmac::keyed_blake3(b"shared secret"));
+        let stack = MiddlewareStack::new().add(signer.clone());
+
+        let request = Request::new(Method::Post, "http://llm-gateway/v1/chat");
+        let mut request = stack.process_request(request).await.unwrap();
+        assert!(request.headers[SIGNATURE_HEADER].starts_with("t="));
+        signer.verify(&request).unwrap();
+
+        request.url = "http://llm-gateway/v1/admin".to_string();
+        assert!(signer.verify(&request).is_err());
+        request.headers.clear();
+        assert!(signer.verify(&request).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Proptest strategies for JSON documents.
+//!
+//! Numbers are integers or short binary fractions, which survive a text
+//! round trip exactly, so properties can compare documents with `==`.
+
+use crate::Json;
+use proptest::prelude::*;
+use serde_json::{Map, Number, Value};
+
+/// Strategy for JSON scalars
+pub fn json_scalar() -> impl Strategy<Value = Json> {
+    scalar().prop_map(Json::from)
+}
+
+/// Strategy for JSON documents of any shape
+///
+/// Documents nest up to four levels, with up to eight entries per array or
+/// object.
+pub fn json() -> impl Strategy<Value = Json> {
+    value().prop_map(Json::from)
+}
+
+/// Strategy for JSON objects
+pub fn json_object() -> impl Strategy<Value = Json> {
+    prop::collection::btree_map(key(), value(), 0..8)
+        .prop_map(|entries| Json::from(Value::Object(entries.into_iter().collect())))
+}
+
+/// Strategy for object keys, drawn from a small alphabet so that objects
+/// generated independently share keys
+pub fn key() -> impl Strategy<Value = String> {
+    "[a-e]{1,2}"
+}
+
+fn scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        (-1_000_000i32..1_000_000).prop_map(|n| {
+            Number::from_f64(f64::from(n) / 8.0).map_or(Value::Null, Value::Number)
+        }),
+        ".{0,12}".prop_map(Value::String),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    scalar().prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Value::Array),
+            prop::collection::btree_map(key(), inner, 0..8)
+                .prop_map(|entries| Value::Object(entries.into_iter().collect::<Map<_, _>>())),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, merge, JsonDiff};
+
+    /// Swap the sides of a diff entry
+    fn mirror(entry: &JsonDiff) -> JsonDiff {
+        match entry.clone() {
+            JsonDiff::Added { path, value } => JsonDiff::Removed { path, value },
+            JsonDiff::Removed { path, value } => JsonDiff::Added { path, value },
+            JsonDiff::Changed { path, old, new } => JsonDiff::Changed {
+                path,
+                old: new,
+                new: old,
+            },
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_text_round_trip(doc in json()) {
+            prop_assert_eq!(Json::parse(&doc.to_string()).unwrap(), doc);
+        }
+
+        #[test]
+        fn prop_diff_empty_iff_equal(a in json(), b in json()) {
+            prop_assert!(diff(&a, &a).is_empty());
+            prop_assert_eq!(diff(&a, &b).is_empty(), a == b);
+        }
+
+        #[test]
+        fn prop_diff_mirrors(a in json(), b in json()) {
+            let mut forward: Vec<_> = diff(&a, &b).iter().map(mirror).map(|d| format!("{d:?}")).collect();
+            let mut backward: Vec<_> = diff(&b, &a).iter().map(|d| format!("{d:?}")).collect();
+            forward.sort();
+            backward.sort();
+            prop_assert_eq!(forward, backward);
+        }
+
+        #[test]
+        fn prop_merge_idempotent(base in json(), patch in json_object()) {
+            let once = merge(&base, &patch);
+            prop_assert_eq!(merge(&once, &patch), once);
+        }
+
+        #[test]
+        fn prop_merge_scalar_replaces(base in json(), patch in json_scalar()) {
+            prop_assert_eq!(merge(&base, &patch), patch);
+        }
+    }
+}
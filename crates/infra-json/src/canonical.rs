@@ -0,0 +1,154 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+
+use crate::Json;
+use serde_json::{Number, Value};
+use std::fmt::Write;
+
+/// Serialize a value in its canonical form (RFC 8785, "JCS")
+///
+/// Equal values always produce identical text, which makes the output
+/// suitable for hashing, signing and snapshot comparison:
+/// - no whitespace is emitted
+/// - object members are sorted by the UTF-16 code units of their names
+/// - numbers are written as ECMAScript does for IEEE 754 doubles, so `1.0`
+///   becomes `1` and `1e21` becomes `1e+21`
+/// - strings use the minimal escaping of `JSON.stringify`
+///
+/// Integers beyond 2^53 lose precision, as they do in any JCS implementation.
+#[must_use]
+pub fn canonicalize(value: &Json) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value.as_inner());
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(members) => {
+            let mut members: Vec<_> = members.iter().collect();
+            members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (name, member)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, name);
+                out.push(':');
+                write_value(out, member);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // serde_json escapes exactly as JSON.stringify does
+    out.push_str(&serde_json::to_string(s).unwrap_or_default());
+}
+
+/// Write a number as ECMAScript's `Number.prototype.toString` would
+fn write_number(out: &mut String, n: &Number) {
+    let value = n.as_f64().unwrap_or_default();
+    if value == 0.0 {
+        out.push('0');
+        return;
+    }
+    if value < 0.0 {
+        out.push('-');
+    }
+
+    // Shortest round-trip digits, as `d.ddde±x`
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits = mantissa.replace('.', "");
+    // At most 17 digits
+    let k = i32::try_from(digits.len()).unwrap_or(i32::MAX);
+    let n = exponent.parse::<i32>().unwrap_or_default() + 1;
+    let zeros = |count: i32| "0".repeat(usize::try_from(count).unwrap_or_default());
+
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&zeros(n - k));
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(usize::try_from(n).unwrap_or_default());
+        let _ = write!(out, "{int}.{frac}");
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&zeros(-n));
+        out.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.push_str(first);
+        if !rest.is_empty() {
+            let _ = write!(out, ".{rest}");
+        }
+        let _ = write!(out, "e{}{}", if n > 0 { '+' } else { '-' }, (n - 1).abs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(s: &str) -> String {
+        canonicalize(&Json::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_canonical_structure() {
+        assert_eq!(
+            canonical(r#"{ "b": [1, {"z": null, "a": true}], "a": "x" }"#),
+            r#"{"a":"x","b":[1,{"a":true,"z":null}]}"#
+        );
+        // Sorted by UTF-16 code units: U+1F600 (surrogates) before U+FB33
+        assert_eq!(
+            canonical(r#"{"\ufb33": 1, "\ud83d\ude00": 2, "\r": 3, "1": 4}"#),
+            "{\"\\r\":3,\"1\":4,\"\u{1f600}\":2,\"\u{fb33}\":1}"
+        );
+        assert_eq!(
+            canonical(r#""\u20ac\/\u0001\n""#),
+            "\"\u{20ac}/\\u0001\\n\""
+        );
+    }
+
+    #[test]
+    fn test_canonical_numbers() {
+        let cases = [
+            ("0", "0"),
+            ("-0.0", "0"),
+            ("1.0", "1"),
+            ("-1.5", "-1.5"),
+            ("1e21", "1e+21"),
+            ("1e20", "100000000000000000000"),
+            ("123.456e-2", "1.23456"),
+            ("0.000001", "0.000001"),
+            ("0.0000001", "1e-7"),
+            ("4.50", "4.5"),
+            ("2e-3", "0.002"),
+            ("1.7976931348623157e308", "1.7976931348623157e+308"),
+            ("5e-324", "5e-324"),
+            ("9007199254740993", "9007199254740992"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(canonical(input), expected, "canonicalizing {input}");
+        }
+        // RFC 8785 sample 333333333.33333329, which serde_json parses inexactly
+        let value = Json::from(serde_json::json!(333_333_333.333_333_3_f64));
+        assert_eq!(canonicalize(&value), "333333333.3333333");
+    }
+}
@@ -4,7 +4,11 @@
 //! - JSON value wrapper with path queries
 //! - Streaming JSON parsing
 //! - JSON diff and merge utilities
+//! - Canonical serialization (RFC 8785 JCS)
+//! - Masking of sensitive values by path and pattern
+//! - Lenient repair of malformed JSON (e.g. LLM output)
 //! - WASM-compatible API
+//! - Proptest strategies for JSON documents (`proptest` feature)
 
 use infra_errors::{InfraError, InfraResult, SerializationFormat};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -13,6 +17,16 @@ use std::collections::HashMap;
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+mod canonical;
+mod mask;
+mod repair;
+
+pub use canonical::canonicalize;
+pub use mask::{Masker, API_KEY_PATTERN, BEARER_PATTERN, DEFAULT_MASK, EMAIL_PATTERN};
+pub use repair::repair;
+
 /// JSON value wrapper with additional capabilities
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -71,6 +85,7 @@ impl Json {
                 message: e.to_string(),
                 location: Some(format!("line {}, column {}", e.line(), e.column())),
                 context: None,
+                source: None,
             }
         })
     }
@@ -94,6 +109,12 @@ impl Json {
         serde_json::to_string_pretty(&self.0).unwrap_or_default()
     }
 
+    /// Convert to a canonical JSON string (RFC 8785), see [`canonicalize`]
+    #[must_use]
+    pub fn to_canonical_string(&self) -> String {
+        canonicalize(self)
+    }
+
     /// Convert to bytes
     #[must_use]
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -395,24 +416,27 @@ pub fn merge(base: &Json, patch: &Json) -> Json {
 }
 
 fn merge_recursive(base: &serde_json::Value, patch: &serde_json::Value) -> serde_json::Value {
-    match (base, patch) {
-        (serde_json::Value::Object(base_obj), serde_json::Value::Object(patch_obj)) => {
-            let mut result = base_obj.clone();
-
-            for (key, patch_val) in patch_obj {
-                if patch_val.is_null() {
-                    result.remove(key);
-                } else if let Some(base_val) = result.get(key) {
-                    result.insert(key.clone(), merge_recursive(base_val, patch_val));
-                } else {
-                    result.insert(key.clone(), patch_val.clone());
-                }
-            }
+    let serde_json::Value::Object(patch_obj) = patch else {
+        return patch.clone();
+    };
 
-            serde_json::Value::Object(result)
+    // A patch object applies to an empty object when the base is not one
+    let mut result = match base {
+        serde_json::Value::Object(base_obj) => base_obj.clone(),
+        _ => serde_json::Map::new(),
+    };
+
+    for (key, patch_val) in patch_obj {
+        if patch_val.is_null() {
+            result.remove(key);
+        } else {
+            let base_val = result.get(key).unwrap_or(&serde_json::Value::Null);
+            let merged = merge_recursive(base_val, patch_val);
+            result.insert(key.clone(), merged);
         }
-        _ => patch.clone(),
     }
+
+    serde_json::Value::Object(result)
 }
 
 /// Macro for creating JSON objects easily
@@ -512,4 +536,14 @@ mod tests {
         assert_eq!(result.get_path("b").unwrap().as_i64(), Some(3));
         assert_eq!(result.get_path("c").unwrap().as_i64(), Some(4));
     }
+
+    #[test]
+    fn test_json_merge_nested_null() {
+        // RFC 7396: nulls remove members even where the base has no object
+        let base = Json::parse(r#"{"a": 1}"#).unwrap();
+        let patch = Json::parse(r#"{"b": {"c": null, "d": 2}}"#).unwrap();
+
+        let result = merge(&base, &patch);
+        assert_eq!(result, Json::parse(r#"{"a": 1, "b": {"d": 2}}"#).unwrap());
+    }
 }
@@ -0,0 +1,210 @@
+//! Masking of sensitive values by path and pattern.
+
+use crate::Json;
+use infra_errors::{InfraError, InfraResult};
+use regex::Regex;
+
+/// Default replacement for masked values
+pub const DEFAULT_MASK: &str = "[REDACTED]";
+
+/// Regex matching email addresses
+pub const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+
+/// Regex matching common API key and token formats
+pub const API_KEY_PATTERN: &str =
+    r"\b(?:sk|pk|rk)-[A-Za-z0-9_-]{16,}\b|\bAKIA[0-9A-Z]{16}\b|\bgh[pousr]_[A-Za-z0-9]{36,}\b";
+
+/// Regex matching bearer tokens
+pub const BEARER_PATTERN: &str = r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]+";
+
+/// Masks sensitive values in JSON documents.
+///
+/// Path rules replace the whole value at a dot-notation path, where `*`
+/// matches any single object key or array index. Pattern rules replace
+/// matching substrings inside every string value.
+#[derive(Debug, Clone)]
+pub struct Masker {
+    paths: Vec<Vec<String>>,
+    patterns: Vec<Regex>,
+    replacement: String,
+}
+
+impl Default for Masker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Masker {
+    /// Create an empty masker
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            paths: Vec::new(),
+            patterns: Vec::new(),
+            replacement: DEFAULT_MASK.to_string(),
+        }
+    }
+
+    /// Create a masker for emails, API keys and bearer tokens
+    ///
+    /// # Panics
+    ///
+    /// Never panics in practice: the built-in patterns are valid regexes.
+    #[must_use]
+    pub fn pii() -> Self {
+        let mut masker = Self::new();
+        for pattern in [EMAIL_PATTERN, API_KEY_PATTERN, BEARER_PATTERN] {
+            masker
+                .patterns
+                .push(Regex::new(pattern).expect("built-in pattern is valid"));
+        }
+        masker
+    }
+
+    /// Mask the value at a dot-notation path
+    #[must_use]
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.paths
+            .push(path.split('.').map(normalize_segment).collect());
+        self
+    }
+
+    /// Mask substrings matching a regex
+    pub fn with_pattern(mut self, pattern: &str) -> InfraResult<Self> {
+        let regex = Regex::new(pattern).map_err(|e| {
+            InfraError::validation_field("pattern", e.to_string(), None, Some(pattern.to_string()))
+        })?;
+        self.patterns.push(regex);
+        Ok(self)
+    }
+
+    /// Set the replacement string
+    #[must_use]
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Check whether any rules are configured
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Mask a string using the pattern rules
+    #[must_use]
+    pub fn mask_str(&self, s: &str) -> String {
+        let mut out = s.to_string();
+        for pattern in &self.patterns {
+            if pattern.is_match(&out) {
+                out = pattern
+                    .replace_all(&out, self.replacement.as_str())
+                    .into_owned();
+            }
+        }
+        out
+    }
+
+    /// Mask a JSON value in place
+    pub fn mask_value(&self, value: &mut serde_json::Value) {
+        let mut path = Vec::new();
+        self.mask_recursive(value, &mut path);
+    }
+
+    /// Mask a member of an object in place, as if the object were the root
+    ///
+    /// Masks `value` exactly as [`mask_value`](Self::mask_value) would mask
+    /// it at path `key`, without building the enclosing object.
+    pub fn mask_member(&self, key: &str, value: &mut serde_json::Value) {
+        let mut path = vec![key.to_string()];
+        self.mask_recursive(value, &mut path);
+    }
+
+    /// Mask a JSON document, returning the masked copy
+    #[must_use]
+    pub fn mask(&self, json: &Json) -> Json {
+        let mut value = json.as_inner().clone();
+        self.mask_value(&mut value);
+        Json::from(value)
+    }
+
+    fn mask_recursive(&self, value: &mut serde_json::Value, path: &mut Vec<String>) {
+        if !value.is_null() && self.path_matches(path) {
+            *value = serde_json::Value::String(self.replacement.clone());
+            return;
+        }
+
+        match value {
+            serde_json::Value::Object(obj) => {
+                for (key, child) in obj.iter_mut() {
+                    path.push(key.clone());
+                    self.mask_recursive(child, path);
+                    path.pop();
+                }
+            }
+            serde_json::Value::Array(arr) => {
+                for (i, child) in arr.iter_mut().enumerate() {
+                    path.push(i.to_string());
+                    self.mask_recursive(child, path);
+                    path.pop();
+                }
+            }
+            serde_json::Value::String(s) if !self.patterns.is_empty() => {
+                *s = self.mask_str(s);
+            }
+            _ => {}
+        }
+    }
+
+    fn path_matches(&self, path: &[String]) -> bool {
+        !path.is_empty()
+            && self.paths.iter().any(|rule| {
+                rule.len() == path.len()
+                    && rule
+                        .iter()
+                        .zip(path)
+                        .all(|(r, p)| r == "*" || r == p)
+            })
+    }
+}
+
+fn normalize_segment(segment: &str) -> String {
+    segment
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .unwrap_or(segment)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_paths() {
+        let json = Json::parse(
+            r#"{"user": {"password": "hunter2", "name": "a"}, "keys": [{"secret": "x"}, {"secret": "y"}]}"#,
+        )
+        .unwrap();
+        let masker = Masker::new().with_path("user.password").with_path("keys.*.secret");
+
+        let masked = masker.mask(&json);
+        assert_eq!(masked.get_path("user.password").unwrap().as_str(), Some(DEFAULT_MASK));
+        assert_eq!(masked.get_path("user.name").unwrap().as_str(), Some("a"));
+        assert_eq!(masked.get_path("keys.[1].secret").unwrap().as_str(), Some(DEFAULT_MASK));
+
+        let mut user = json.get_path("user").unwrap().as_inner().clone();
+        masker.mask_member("user", &mut user);
+        assert_eq!(user["password"], DEFAULT_MASK);
+        assert_eq!(user["name"], "a");
+    }
+
+    #[test]
+    fn test_mask_pii_patterns() {
+        let masker = Masker::pii();
+        let masked = masker.mask_str("contact bob@example.com with key sk-abcdefghijklmnop1234");
+        assert_eq!(masked, "contact [REDACTED] with key [REDACTED]");
+        assert!(Masker::new().with_pattern("(").is_err());
+    }
+}
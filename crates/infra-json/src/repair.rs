@@ -0,0 +1,110 @@
+//! Lenient parsing of almost-JSON text, such as LLM output.
+
+use crate::Json;
+use infra_errors::InfraResult;
+
+/// Parse JSON, repairing common defects if strict parsing fails
+///
+/// Repairs applied, in order:
+/// - surrounding prose and Markdown code fences are stripped, keeping the
+///   first `{...}` or `[...]` value
+/// - trailing commas before `}` and `]` are removed
+/// - unterminated strings, objects and arrays (truncated output) are closed
+///
+/// Returns the strict parse error if the repaired text still does not parse.
+pub fn repair(s: &str) -> InfraResult<Json> {
+    let strict = Json::parse(s);
+    if strict.is_ok() {
+        return strict;
+    }
+
+    let Some(start) = s.find(['{', '[']) else {
+        return strict;
+    };
+    Json::parse(&balance(&s[start..])).or(strict)
+}
+
+/// Rewrite the value starting at the beginning of `s`, dropping trailing
+/// commas, ignoring anything after it ends and closing whatever is left open
+fn balance(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 8);
+    let mut stack = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in s.chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => stack.push('}'),
+            '[' => stack.push(']'),
+            '}' | ']' => {
+                trim_trailing_comma(&mut out);
+                if stack.pop() != Some(c) {
+                    break;
+                }
+                out.push(c);
+                if stack.is_empty() {
+                    return out;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+
+    if in_string {
+        if escaped {
+            out.pop();
+        }
+        out.push('"');
+    }
+    trim_trailing_comma(&mut out);
+    while let Some(close) = stack.pop() {
+        out.push(close);
+    }
+    out
+}
+
+fn trim_trailing_comma(out: &mut String) {
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    if out.ends_with(',') {
+        out.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair() {
+        let fenced =
+            "Sure! Here you go:\n```json\n{\"name\": \"a\", \"tags\": [\"x\", \"y\",],}\n```";
+        let json = repair(fenced).unwrap();
+        assert_eq!(json.get_path("name").unwrap().as_str(), Some("a"));
+        assert_eq!(json.get_path("tags.[1]").unwrap().as_str(), Some("y"));
+
+        let truncated = r#"{"items": [{"id": 1}, {"id": 2, "label": "unfini"#;
+        let json = repair(truncated).unwrap();
+        assert_eq!(
+            json.get_path("items.[1].label").unwrap().as_str(),
+            Some("unfini")
+        );
+
+        assert!(repair("no json here").is_err());
+    }
+}
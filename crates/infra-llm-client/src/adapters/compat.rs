@@ -0,0 +1,201 @@
+//! Adapter for self-hosted servers exposing an OpenAI-compatible API.
+
+use async_trait::async_trait;
+use futures::Stream;
+use infra_http::HttpClient;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use super::openai::ChatApi;
+use crate::error::Result;
+use crate::provider::LlmProvider;
+use crate::types::{EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, StreamChunk};
+
+/// Default base URL of a local Ollama server.
+pub const OLLAMA_BASE_URL: &str = "http://localhost:11434/v1";
+
+/// Default base URL of a local vLLM server.
+pub const VLLM_BASE_URL: &str = "http://localhost:8000/v1";
+
+/// Adapter for OpenAI-compatible servers such as Ollama, vLLM or LM Studio.
+///
+/// Responses are parsed leniently: missing `model`, `usage` and embedding
+/// `index` fields are tolerated, and non-standard finish reasons (`eos`,
+/// `max_length`, ...) are normalized to `stop` and `length`.
+#[derive(Clone)]
+pub struct OpenAiCompatAdapter {
+    name: String,
+    base_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    stream_usage: bool,
+    client: Arc<OnceLock<HttpClient>>,
+}
+
+impl std::fmt::Debug for OpenAiCompatAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAiCompatAdapter")
+            .field("name", &self.name)
+            .field("base_url", &self.base_url)
+            .field("api_key", &self.api_key.as_ref().map(|_| "[REDACTED]"))
+            .field("timeout", &self.timeout)
+            .field("stream_usage", &self.stream_usage)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OpenAiCompatAdapter {
+    /// Creates an unauthenticated adapter reporting `name` as its provider.
+    #[must_use]
+    pub fn new(name: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            base_url: base_url.into(),
+            api_key: None,
+            // Local models can be slow to load and generate
+            timeout: Duration::from_secs(300),
+            stream_usage: false,
+            client: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Creates an adapter for a local Ollama server.
+    #[must_use]
+    pub fn ollama() -> Self {
+        Self::new("ollama", OLLAMA_BASE_URL)
+    }
+
+    /// Creates an adapter for a local vLLM server.
+    #[must_use]
+    pub fn vllm() -> Self {
+        Self::new("vllm", VLLM_BASE_URL)
+    }
+
+    /// Authenticates with a bearer token.
+    #[must_use]
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self.client = Arc::new(OnceLock::new());
+        self
+    }
+
+    /// Sets the request timeout.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.client = Arc::new(OnceLock::new());
+        self
+    }
+
+    /// Requests a usage chunk at the end of streams (`stream_options`).
+    ///
+    /// Off by default, as older servers reject the unknown field.
+    #[must_use]
+    pub fn with_stream_usage(mut self, enabled: bool) -> Self {
+        self.stream_usage = enabled;
+        self
+    }
+
+    /// Returns the base URL for the API, including the `/v1` prefix.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the request timeout.
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn client(&self) -> Result<&HttpClient> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let mut builder = HttpClient::builder()
+            .base_url(&self.base_url)
+            .timeout(self.timeout);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.header("Authorization", format!("Bearer {api_key}"));
+        }
+        let client = builder.build()?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    fn api(&self) -> Result<ChatApi<'_>> {
+        Ok(ChatApi {
+            client: self.client()?,
+            provider: &self.name,
+            stream_usage: self.stream_usage,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatAdapter {
+    async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        self.api()?.complete(request).await
+    }
+
+    async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        self.api()?.stream(request).await
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.api()?.embed(request).await
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::openai::{parse_chunk, parse_completion, parse_embeddings};
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lenient_parsing() {
+        // Ollama: no usage on some versions, custom finish reasons
+        let response = parse_completion(json!({
+            "model": "llama3.1:8b",
+            "choices": [{ "message": { "content": "Hi" }, "finish_reason": "eos" }]
+        }))
+        .unwrap();
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+        assert!(response.usage.is_none());
+
+        // vLLM: empty finish reason on intermediate chunks, no model
+        let chunk =
+            parse_chunk(r#"{"choices":[{"delta":{"content":"Hi"},"finish_reason":""}]}"#).unwrap();
+        assert_eq!(chunk.content, "Hi");
+        assert!(chunk.finish_reason.is_none());
+        let chunk =
+            parse_chunk(r#"{"choices":[{"delta":{},"finish_reason":"max_length"}]}"#).unwrap();
+        assert_eq!(chunk.finish_reason.as_deref(), Some("length"));
+
+        // Embeddings without indices
+        let embeddings = parse_embeddings(json!({
+            "data": [{ "embedding": [0.1] }, { "embedding": [0.2] }]
+        }))
+        .unwrap();
+        assert_eq!(embeddings.embeddings[1].index, 1);
+    }
+
+    #[test]
+    fn test_presets() {
+        let ollama = OpenAiCompatAdapter::ollama();
+        assert_eq!(ollama.provider_name(), "ollama");
+        assert_eq!(ollama.base_url(), OLLAMA_BASE_URL);
+
+        let vllm = OpenAiCompatAdapter::vllm().with_api_key("token");
+        assert!(format!("{vllm:?}").contains("[REDACTED]"));
+        assert!(!format!("{vllm:?}").contains("token\""));
+    }
+}
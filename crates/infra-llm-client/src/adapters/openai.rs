@@ -0,0 +1,816 @@
+//! OpenAI Chat Completions and Embeddings adapter.
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use infra_http::HttpClient;
+use infra_otel::LlmSpanExt;
+use serde::Deserialize;
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::Instrument;
+
+use super::openai_batch;
+use crate::batch::{self, BatchOptions, BatchResult};
+use crate::error::{LlmClientError, Result};
+use crate::provider::LlmProvider;
+use crate::sse::SseDecoder;
+use crate::types::{
+    Embedding, EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, StreamChunk, ToolCall,
+    ToolCallDelta, Usage,
+};
+
+/// Default OpenAI API base URL.
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Adapter for OpenAI's Chat Completions and Embeddings APIs.
+///
+/// Requests go through infra-http. Chat and embeddings calls are POSTs, so
+/// infra-http only retries failures that show the request was not applied,
+/// such as a 429 or a refused connection, and never a 5xx. Errors are mapped
+/// to typed [`LlmClientError`]s (see [`LlmClientError::is_retryable`]) for
+/// callers to retry themselves.
+#[derive(Clone)]
+pub struct OpenAiAdapter {
+    api_key: String,
+    base_url: String,
+    organization: Option<String>,
+    timeout: Duration,
+    client: Arc<OnceLock<HttpClient>>,
+}
+
+impl std::fmt::Debug for OpenAiAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenAiAdapter")
+            .field("api_key", &"[REDACTED]")
+            .field("base_url", &self.base_url)
+            .field("organization", &self.organization)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OpenAiAdapter {
+    /// Creates a new OpenAI adapter.
+    #[must_use]
+    pub fn new(api_key: String) -> Self {
+        Self::with_base_url(api_key, OPENAI_BASE_URL.to_string())
+    }
+
+    /// Creates a new OpenAI adapter with a custom base URL.
+    #[must_use]
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
+        Self {
+            api_key,
+            base_url,
+            organization: None,
+            timeout: Duration::from_secs(60),
+            client: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Sets the organization ID.
+    #[must_use]
+    pub fn with_organization(mut self, organization: impl Into<String>) -> Self {
+        self.organization = Some(organization.into());
+        self.client = Arc::new(OnceLock::new());
+        self
+    }
+
+    /// Sets the request timeout.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.client = Arc::new(OnceLock::new());
+        self
+    }
+
+    /// Returns the base URL for the API.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the organization ID sent as `OpenAI-Organization`, if any.
+    #[must_use]
+    pub fn organization(&self) -> Option<&str> {
+        self.organization.as_deref()
+    }
+
+    /// Returns the request timeout.
+    #[must_use]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn client(&self) -> Result<&HttpClient> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let mut builder = HttpClient::builder()
+            .base_url(&self.base_url)
+            .timeout(self.timeout)
+            .header("Authorization", format!("Bearer {}", self.api_key));
+        if let Some(organization) = &self.organization {
+            builder = builder.header("OpenAI-Organization", organization);
+        }
+        let client = builder.build()?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    /// Runs bodies through the Batch API, failing every item if the job does.
+    async fn native_batch(
+        &self,
+        endpoint: &str,
+        len: usize,
+        bodies: Result<Vec<Value>>,
+        options: &BatchOptions,
+    ) -> Vec<Result<Value>> {
+        if len == 0 {
+            return Vec::new();
+        }
+        let results = match (bodies, self.client()) {
+            (Ok(bodies), Ok(client)) => {
+                openai_batch::run(client, endpoint, &bodies, options.poll_interval).await
+            }
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        };
+        results.unwrap_or_else(|e| {
+            let message = format!("batch failed: {e}");
+            (0..len)
+                .map(|_| Err(LlmClientError::ProviderError(message.clone())))
+                .collect()
+        })
+    }
+
+    fn api(&self) -> Result<ChatApi<'_>> {
+        Ok(ChatApi {
+            client: self.client()?,
+            provider: "openai",
+            stream_usage: true,
+        })
+    }
+}
+
+/// Token usage as reported by OpenAI (embeddings omit completion tokens).
+#[allow(clippy::struct_field_names)]
+#[derive(Debug, Deserialize)]
+struct OpenAiUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+    #[serde(default)]
+    total_tokens: u32,
+}
+
+impl From<OpenAiUsage> for Usage {
+    fn from(usage: OpenAiUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletion {
+    #[serde(default)]
+    model: String,
+    choices: Vec<ChatChoice>,
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<OpenAiToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiToolCall {
+    #[serde(default)]
+    id: String,
+    function: OpenAiFunction,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenAiFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiToolCallDelta {
+    #[serde(default)]
+    index: usize,
+    id: Option<String>,
+    #[serde(default)]
+    function: OpenAiFunction,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<OpenAiToolCallDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingList {
+    #[serde(default)]
+    model: String,
+    data: Vec<EmbeddingData>,
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: Option<usize>,
+}
+
+/// Maps finish reasons from OpenAI-compatible servers onto OpenAI's values.
+///
+/// Self-hosted servers report end-of-sequence and token-limit stops under
+/// various names, and some send an empty reason on intermediate chunks.
+pub(crate) fn normalize_finish_reason(reason: Option<String>) -> Option<String> {
+    match reason.as_deref()? {
+        "" => None,
+        "eos" | "eos_token" | "end_turn" | "stop_sequence" => Some("stop".to_string()),
+        "max_tokens" | "max_length" => Some("length".to_string()),
+        _ => reason,
+    }
+}
+
+/// Builds the Chat Completions request body.
+pub(crate) fn chat_body(request: &LlmRequest, stream: bool, stream_usage: bool) -> Result<Value> {
+    let mut body = serde_json::to_value(request)?;
+    body["stream"] = Value::Bool(stream);
+    if stream && stream_usage {
+        body["stream_options"] = serde_json::json!({ "include_usage": true });
+    }
+    Ok(body)
+}
+
+/// Parses a Chat Completions response.
+pub(crate) fn parse_completion(value: Value) -> Result<LlmResponse> {
+    let completion: ChatCompletion = serde_json::from_value(value)?;
+    let choice =
+        completion.choices.into_iter().next().ok_or_else(|| {
+            LlmClientError::InvalidResponse("response has no choices".to_string())
+        })?;
+
+    Ok(LlmResponse {
+        content: choice.message.content.unwrap_or_default(),
+        model: completion.model,
+        finish_reason: normalize_finish_reason(choice.finish_reason),
+        usage: completion.usage.map(Usage::from),
+        tool_calls: choice
+            .message
+            .tool_calls
+            .into_iter()
+            .map(|call| ToolCall {
+                id: call.id,
+                name: call.function.name.unwrap_or_default(),
+                arguments: call.function.arguments.unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
+
+/// Parses one streamed Chat Completions chunk.
+pub(crate) fn parse_chunk(data: &str) -> Result<StreamChunk> {
+    let chunk: ChatCompletionChunk = serde_json::from_str(data)?;
+    let choice = chunk.choices.into_iter().next();
+    let (content, finish_reason, tool_calls) = match choice {
+        Some(choice) => (
+            choice.delta.content.unwrap_or_default(),
+            choice.finish_reason,
+            choice.delta.tool_calls,
+        ),
+        None => (String::new(), None, Vec::new()),
+    };
+
+    Ok(StreamChunk {
+        content,
+        model: chunk.model,
+        finish_reason: normalize_finish_reason(finish_reason),
+        usage: chunk.usage.map(Usage::from),
+        tool_calls: tool_calls
+            .into_iter()
+            .map(|call| ToolCallDelta {
+                index: call.index,
+                id: call.id,
+                name: call.function.name,
+                arguments: call.function.arguments.unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
+
+/// Parses an Embeddings response.
+pub(crate) fn parse_embeddings(value: Value) -> Result<EmbeddingResponse> {
+    let list: EmbeddingList = serde_json::from_value(value)?;
+    Ok(EmbeddingResponse {
+        model: list.model,
+        embeddings: list
+            .data
+            .into_iter()
+            .enumerate()
+            .map(|(position, data)| Embedding {
+                embedding: data.embedding,
+                index: data.index.unwrap_or(position),
+            })
+            .collect(),
+        usage: list.usage.map(Usage::from),
+    })
+}
+
+/// Converts an SSE byte stream into stream chunks, ending at `[DONE]`.
+pub(crate) fn sse_chunks<S, B, E>(
+    bytes: S,
+) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let state = (
+        bytes,
+        SseDecoder::new(),
+        std::collections::VecDeque::new(),
+        false,
+    );
+    Box::pin(futures::stream::unfold(
+        state,
+        |(mut bytes, mut decoder, mut pending, mut done)| async move {
+            loop {
+                if let Some(item) = pending.pop_front() {
+                    return Some((item, (bytes, decoder, pending, done)));
+                }
+                if done {
+                    return None;
+                }
+                match bytes.next().await {
+                    Some(Ok(chunk)) => {
+                        for event in decoder.push(chunk.as_ref()) {
+                            if event.data == "[DONE]" {
+                                done = true;
+                                break;
+                            }
+                            pending.push_back(parse_chunk(&event.data));
+                        }
+                    }
+                    Some(Err(e)) => {
+                        done = true;
+                        pending.push_back(Err(LlmClientError::NetworkError(e.to_string())));
+                    }
+                    None => done = true,
+                }
+            }
+        },
+    ))
+}
+
+fn llm_span(provider: &str, request: &LlmRequest) -> tracing::Span {
+    let mut builder = infra_otel::llm_span(provider, &request.model);
+    if let Some(temperature) = request.temperature {
+        builder = builder.temperature(f64::from(temperature));
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        builder = builder.max_tokens(u64::from(max_tokens));
+    }
+    builder.build()
+}
+
+/// Keeps `span` open until the chunk stream ends, polling every chunk inside
+/// it and recording usage, finish reason and response model as they arrive.
+fn in_span(
+    span: tracing::Span,
+    chunks: Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>,
+) -> Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>> {
+    Box::pin(futures::stream::unfold(
+        (chunks, span),
+        |(mut chunks, span)| async move {
+            let item = chunks.next().instrument(span.clone()).await?;
+            if let Ok(chunk) = &item {
+                if let Some(usage) = chunk.usage {
+                    span.record_usage(
+                        u64::from(usage.prompt_tokens),
+                        u64::from(usage.completion_tokens),
+                    );
+                }
+                if let Some(reason) = &chunk.finish_reason {
+                    span.record_finish_reason(reason);
+                }
+                if !chunk.model.is_empty() {
+                    span.record_response_model(&chunk.model);
+                }
+            }
+            Some((item, (chunks, span)))
+        },
+    ))
+}
+
+/// Chat Completions and Embeddings calls shared by OpenAI-style adapters.
+pub(crate) struct ChatApi<'a> {
+    pub client: &'a HttpClient,
+    pub provider: &'a str,
+    /// Whether to request a final usage chunk when streaming.
+    pub stream_usage: bool,
+}
+
+impl ChatApi<'_> {
+    async fn post(&self, path: &str, model: &str, body: &Value) -> Result<Value> {
+        let response = self
+            .client
+            .post(path, body)
+            .await
+            .map_err(|e| LlmClientError::from_provider_http(e, self.provider, Some(model)))?;
+        response
+            .json()
+            .await
+            .map_err(|e| LlmClientError::InvalidResponse(e.to_string()))
+    }
+
+    pub async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let span = llm_span(self.provider, &request);
+        async {
+            let body = chat_body(&request, false, false)?;
+            let mut response = parse_completion(
+                self.post("/chat/completions", &request.model, &body)
+                    .await?,
+            )?;
+            if response.model.is_empty() {
+                response.model = request.model;
+            }
+
+            let span = tracing::Span::current();
+            if let Some(usage) = response.usage {
+                span.record_usage(
+                    u64::from(usage.prompt_tokens),
+                    u64::from(usage.completion_tokens),
+                );
+            }
+            if let Some(reason) = &response.finish_reason {
+                span.record_finish_reason(reason);
+            }
+            span.record_response_model(&response.model);
+            Ok(response)
+        }
+        .instrument(span)
+        .await
+    }
+
+    pub async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        let body = chat_body(&request, true, self.stream_usage)?;
+        let span = llm_span(self.provider, &request);
+        let response = self
+            .client
+            .post("/chat/completions", &body)
+            .instrument(span.clone())
+            .await
+            .map_err(|e| {
+                LlmClientError::from_provider_http(e, self.provider, Some(&request.model))
+            })?;
+        Ok(in_span(span, sse_chunks(response.bytes_stream())))
+    }
+
+    pub async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        let body = serde_json::to_value(&request)?;
+        let span = infra_otel::llm_span(self.provider, &request.model)
+            .operation("embeddings")
+            .build();
+        let mut response = parse_embeddings(
+            self.post("/embeddings", &request.model, &body)
+                .instrument(span)
+                .await?,
+        )?;
+        if response.model.is_empty() {
+            response.model = request.model;
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiAdapter {
+    async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        self.api()?.complete(request).await
+    }
+
+    async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        self.api()?.stream(request).await
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.api()?.embed(request).await
+    }
+
+    async fn complete_batch(
+        &self,
+        requests: Vec<LlmRequest>,
+        options: &BatchOptions,
+    ) -> BatchResult<LlmResponse> {
+        if !options.native {
+            return batch::complete_concurrent(self, requests, options).await;
+        }
+        let bodies = requests
+            .iter()
+            .map(|request| chat_body(request, false, false))
+            .collect();
+        let results = self
+            .native_batch(
+                openai_batch::CHAT_COMPLETIONS_ENDPOINT,
+                requests.len(),
+                bodies,
+                options,
+            )
+            .await;
+        BatchResult::new(
+            results
+                .into_iter()
+                .map(|result| result.and_then(parse_completion))
+                .collect(),
+        )
+    }
+
+    async fn embed_batch(
+        &self,
+        requests: Vec<EmbeddingRequest>,
+        options: &BatchOptions,
+    ) -> BatchResult<EmbeddingResponse> {
+        if !options.native {
+            return batch::embed_concurrent(self, requests, options).await;
+        }
+        let bodies = requests
+            .iter()
+            .map(|request| serde_json::to_value(request).map_err(Into::into))
+            .collect();
+        let results = self
+            .native_batch(
+                openai_batch::EMBEDDINGS_ENDPOINT,
+                requests.len(),
+                bodies,
+                options,
+            )
+            .await;
+        BatchResult::new(
+            results
+                .into_iter()
+                .map(|result| result.and_then(parse_embeddings))
+                .collect(),
+        )
+    }
+
+    fn provider_name(&self) -> &str {
+        "openai"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Message, ResponseFormat, Role};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_completion() {
+        let response = parse_completion(json!({
+            "id": "chatcmpl-1",
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Hi!" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 9, "completion_tokens": 2, "total_tokens": 11 }
+        }))
+        .unwrap();
+
+        assert_eq!(response.content, "Hi!");
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(response.usage.unwrap().total_tokens, 11);
+    }
+
+    #[test]
+    fn test_parse_tool_calls() {
+        let response = parse_completion(json!({
+            "model": "gpt-4o",
+            "choices": [{
+                "message": {
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }))
+        .unwrap();
+        assert_eq!(response.tool_calls[0].name, "get_weather");
+        assert_eq!(response.tool_calls[0].id, "call_1");
+
+        let chunk = parse_chunk(
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"ci"}}]}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.tool_calls[0].index, 0);
+        assert!(chunk.tool_calls[0].name.is_none());
+        assert_eq!(chunk.tool_calls[0].arguments, "{\"ci");
+    }
+
+    #[test]
+    fn test_builders() {
+        let adapter = OpenAiAdapter::new("sk-test".to_string())
+            .with_organization("org-1")
+            .with_timeout(Duration::from_secs(5));
+        assert_eq!(adapter.base_url(), OPENAI_BASE_URL);
+        assert_eq!(adapter.organization(), Some("org-1"));
+        assert_eq!(adapter.timeout(), Duration::from_secs(5));
+        assert!(!format!("{adapter:?}").contains("sk-test"));
+    }
+
+    #[test]
+    fn test_chat_body() {
+        let request = LlmRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: "Hello".to_string(),
+            }],
+            temperature: Some(0.0),
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: Some(ResponseFormat::JsonObject),
+        };
+
+        let body = chat_body(&request, true, true).unwrap();
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["stream_options"]["include_usage"], true);
+        assert!(body.get("max_tokens").is_none());
+        assert_eq!(body["response_format"]["type"], "json_object");
+    }
+
+    #[tokio::test]
+    async fn test_sse_chunks() {
+        let frames: Vec<std::result::Result<&[u8], std::io::Error>> = vec![
+            Ok(b"data: {\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n"),
+            Ok(b"data: {\"model\":\"gpt-4o\",\"choices\":[{\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n"),
+            Ok(b"data: {\"model\":\"gpt-4o\",\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\ndata: [DONE]\n\n"),
+        ];
+        let chunks: Vec<_> = sse_chunks(futures::stream::iter(frames)).collect().await;
+
+        assert_eq!(chunks.len(), 3);
+        let text: String = chunks
+            .iter()
+            .map(|c| c.as_ref().unwrap().content.as_str())
+            .collect();
+        assert_eq!(text, "Hello");
+        assert_eq!(
+            chunks[1].as_ref().unwrap().finish_reason.as_deref(),
+            Some("stop")
+        );
+        assert_eq!(chunks[2].as_ref().unwrap().usage.unwrap().total_tokens, 5);
+    }
+
+    fn mock_request(content: &str) -> LlmRequest {
+        LlmRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_complete_against_mock_server() {
+        use infra_sim::{MockBuilder, MockResponse, MockServer, Route};
+
+        let chat = Route::post("/v1/chat/completions")
+            .header("authorization", "Bearer sk-test")
+            .body_json(json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}));
+        let server = MockServer::start(
+            MockBuilder::new()
+                .route(
+                    chat.clone()
+                        .respond(MockResponse::error(401, "invalid key"))
+                        .respond(
+                            MockResponse::json(&json!({
+                                "model": "gpt-4o",
+                                "choices": [{"message": {"content": "Hello!"}, "finish_reason": "stop"}],
+                                "usage": {"prompt_tokens": 5, "completion_tokens": 2, "total_tokens": 7}
+                            }))
+                            .unwrap(),
+                        )
+                        .expect(2),
+                )
+                .build(),
+        )
+        .await
+        .unwrap();
+        let adapter = OpenAiAdapter::with_base_url("sk-test".to_string(), server.uri("/v1"));
+
+        let err = adapter.complete(mock_request("Hi")).await.unwrap_err();
+        assert!(matches!(err, LlmClientError::AuthenticationError(_)));
+
+        let response = adapter.complete(mock_request("Hi")).await.unwrap();
+        assert_eq!(response.content, "Hello!");
+        assert_eq!(response.usage.unwrap().total_tokens, 7);
+
+        server.service().verify();
+        assert_eq!(server.service().requests_matching(&chat).await.len(), 2);
+
+        let sent: serde_json::Value = server.service().requests().await[1].body_json().unwrap();
+        infra_sim::assert_json_snapshot!("openai/chat_request", sent);
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock_server() {
+        use infra_sim::{MockBuilder, MockResponse, MockServer, Route};
+
+        let server = MockServer::start(
+            MockBuilder::new()
+                .route(
+                    Route::post("/v1/chat/completions")
+                        .body_json(json!({"stream": true}))
+                        .respond(
+                            MockResponse::sse([
+                                json!({"model": "gpt-4o", "choices": [{"delta": {"content": "Hel"}}]}),
+                                json!({"model": "gpt-4o", "choices": [{"delta": {"content": "lo"}, "finish_reason": "stop"}]}),
+                            ])
+                            .with_chunk("data: [DONE]\n\n")
+                            .with_chunk_delay(Duration::from_millis(5)),
+                        ),
+                )
+                .build(),
+        )
+        .await
+        .unwrap();
+        let adapter = OpenAiAdapter::with_base_url("sk-test".to_string(), server.uri("/v1"));
+
+        let chunks: Vec<_> = adapter
+            .stream(mock_request("Hi"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        let text: String = chunks
+            .iter()
+            .map(|c| c.as_ref().unwrap().content.as_str())
+            .collect();
+        assert_eq!(text, "Hello");
+        assert_eq!(
+            chunks[1].as_ref().unwrap().finish_reason.as_deref(),
+            Some("stop")
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! OpenAI Batch API support.
+//!
+//! Requests are uploaded as a JSONL file, submitted as a batch job and
+//! polled until the job reaches a terminal state; results are then matched
+//! back to requests by `custom_id`.
+
+use infra_errors::InfraError;
+use infra_http::HttpClient;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::error::{LlmClientError, Result};
+
+/// Chat Completions endpoint as named by the Batch API.
+pub(crate) const CHAT_COMPLETIONS_ENDPOINT: &str = "/v1/chat/completions";
+
+/// Embeddings endpoint as named by the Batch API.
+pub(crate) const EMBEDDINGS_ENDPOINT: &str = "/v1/embeddings";
+
+const BOUNDARY: &str = "----infra-llm-client-batch-boundary";
+
+#[derive(Debug, Deserialize)]
+struct FileObject {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchJob {
+    id: String,
+    status: String,
+    output_file_id: Option<String>,
+    error_file_id: Option<String>,
+}
+
+impl BatchJob {
+    fn is_terminal(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "completed" | "failed" | "expired" | "cancelled"
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultLine {
+    custom_id: String,
+    response: Option<ResultResponse>,
+    error: Option<ResultError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultResponse {
+    status_code: u16,
+    body: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultError {
+    message: String,
+}
+
+/// Encodes requests as Batch API JSONL, using the index as `custom_id`.
+pub(crate) fn encode_jsonl(endpoint: &str, bodies: &[Value]) -> String {
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(i, body)| {
+            json!({
+                "custom_id": i.to_string(),
+                "method": "POST",
+                "url": endpoint,
+                "body": body,
+            })
+            .to_string()
+                + "\n"
+        })
+        .collect()
+}
+
+fn multipart(jsonl: &str) -> Vec<u8> {
+    // JSON escapes line breaks, so the boundary cannot occur in the payload
+    format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nbatch\r\n\
+         --{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"batch.jsonl\"\r\n\
+         Content-Type: application/jsonl\r\n\r\n{jsonl}\r\n--{BOUNDARY}--\r\n"
+    )
+    .into_bytes()
+}
+
+/// Parses Batch API output lines into per-item results.
+pub(crate) fn decode_results(output: &str, len: usize, status: &str) -> Vec<Result<Value>> {
+    let mut by_id: HashMap<usize, Result<Value>> = HashMap::new();
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let Ok(line) = serde_json::from_str::<ResultLine>(line) else {
+            continue;
+        };
+        let Ok(index) = line.custom_id.parse::<usize>() else {
+            continue;
+        };
+        let result = match (line.response, line.error) {
+            (Some(response), _) if (200..300).contains(&response.status_code) => Ok(response.body),
+            (Some(response), _) => {
+                let message = response.body["error"]["message"]
+                    .as_str()
+                    .unwrap_or("batch request failed")
+                    .to_string();
+                Err(LlmClientError::from_http(InfraError::http_with_status(
+                    response.status_code,
+                    message,
+                )))
+            }
+            (None, Some(error)) => Err(LlmClientError::ProviderError(error.message)),
+            (None, None) => Err(LlmClientError::InvalidResponse(
+                "batch result has neither response nor error".to_string(),
+            )),
+        };
+        by_id.insert(index, result);
+    }
+
+    (0..len)
+        .map(|i| {
+            by_id.remove(&i).unwrap_or_else(|| {
+                Err(LlmClientError::ProviderError(format!(
+                    "batch {status} without a result for item {i}"
+                )))
+            })
+        })
+        .collect()
+}
+
+async fn file_content(client: &HttpClient, file_id: &str) -> Result<String> {
+    client
+        .get(&format!("/files/{file_id}/content"))
+        .await
+        .map_err(LlmClientError::from_http)?
+        .text()
+        .await
+        .map_err(|e| LlmClientError::NetworkError(e.to_string()))
+}
+
+/// Runs request bodies through the Batch API and waits for the results.
+pub(crate) async fn run(
+    client: &HttpClient,
+    endpoint: &str,
+    bodies: &[Value],
+    poll_interval: Duration,
+) -> Result<Vec<Result<Value>>> {
+    let jsonl = encode_jsonl(endpoint, bodies);
+    let file: FileObject = client
+        .post_bytes(
+            "/files",
+            &format!("multipart/form-data; boundary={BOUNDARY}"),
+            multipart(&jsonl),
+        )
+        .await
+        .map_err(LlmClientError::from_http)?
+        .json()
+        .await
+        .map_err(|e| LlmClientError::InvalidResponse(e.to_string()))?;
+
+    let mut job: BatchJob = client
+        .post_json(
+            "/batches",
+            &json!({
+                "input_file_id": file.id,
+                "endpoint": endpoint,
+                "completion_window": "24h",
+            }),
+        )
+        .await
+        .map_err(LlmClientError::from_http)?;
+
+    while !job.is_terminal() {
+        tokio::time::sleep(poll_interval).await;
+        job = client
+            .get_json(&format!("/batches/{}", job.id))
+            .await
+            .map_err(LlmClientError::from_http)?;
+    }
+    tracing::debug!(batch_id = %job.id, status = %job.status, "OpenAI batch finished");
+
+    let mut output = String::new();
+    for file_id in [&job.output_file_id, &job.error_file_id]
+        .into_iter()
+        .flatten()
+    {
+        output.push_str(&file_content(client, file_id).await?);
+        output.push('\n');
+    }
+    Ok(decode_results(&output, bodies.len(), &job.status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_jsonl() {
+        let jsonl = encode_jsonl(CHAT_COMPLETIONS_ENDPOINT, &[json!({"model": "gpt-4o"})]);
+        let line: Value = serde_json::from_str(jsonl.trim_end()).unwrap();
+        assert_eq!(line["custom_id"], "0");
+        assert_eq!(line["url"], "/v1/chat/completions");
+        assert_eq!(line["body"]["model"], "gpt-4o");
+    }
+
+    #[test]
+    fn test_decode_results() {
+        let output = [
+            r#"{"custom_id":"1","response":{"status_code":200,"body":{"ok":true}},"error":null}"#,
+            r#"{"custom_id":"0","response":{"status_code":429,"body":{"error":{"message":"slow down"}}},"error":null}"#,
+        ]
+        .join("\n");
+
+        let results = decode_results(&output, 3, "expired");
+        assert!(matches!(
+            results[0],
+            Err(LlmClientError::RateLimitExceeded(_))
+        ));
+        assert_eq!(results[1].as_ref().unwrap()["ok"], true);
+        assert!(results[2]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("expired"));
+    }
+}
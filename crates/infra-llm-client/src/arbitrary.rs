@@ -0,0 +1,101 @@
+//! Proptest strategies for chat messages and requests.
+
+use crate::types::{LlmRequest, Message, ResponseFormat, Role};
+use proptest::prelude::*;
+
+/// Strategy for message roles.
+pub fn role() -> impl Strategy<Value = Role> {
+    prop_oneof![Just(Role::System), Just(Role::User), Just(Role::Assistant)]
+}
+
+/// Strategy for messages with up to 200 characters of content.
+pub fn message() -> impl Strategy<Value = Message> {
+    (role(), ".{0,200}").prop_map(|(role, content)| Message { role, content })
+}
+
+/// Strategy for conversations of up to `max_len` messages.
+pub fn messages(max_len: usize) -> impl Strategy<Value = Vec<Message>> {
+    prop::collection::vec(message(), 0..=max_len)
+}
+
+/// Strategy for completion requests with optional sampling parameters.
+pub fn llm_request() -> impl Strategy<Value = LlmRequest> {
+    (
+        prop::sample::select(vec!["gpt-4o", "gpt-3.5-turbo", "claude-3-opus", "llama3"]),
+        messages(8),
+        prop::option::of((0u8..=20).prop_map(|t| f32::from(t) / 10.0)),
+        prop::option::of(1u32..4096),
+        prop::option::of(1u32..4),
+        prop::option::of(any::<bool>()),
+        prop::option::of(prop::collection::vec("[a-z\n]{1,4}", 1..3)),
+        prop::option::of(prop_oneof![
+            Just(ResponseFormat::Text),
+            Just(ResponseFormat::JsonObject)
+        ]),
+    )
+        .prop_map(
+            |(model, messages, temperature, max_tokens, n, stream, stop, response_format)| {
+                LlmRequest {
+                    model: model.to_string(),
+                    messages,
+                    temperature,
+                    max_tokens,
+                    top_p: None,
+                    n,
+                    stream,
+                    stop,
+                    response_format,
+                }
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::{truncate_messages, EstimatingTokenizer, Tokenizer, TruncationStrategy};
+
+    fn truncation_strategy() -> impl Strategy<Value = TruncationStrategy> {
+        prop_oneof![
+            Just(TruncationStrategy::DropOldest),
+            Just(TruncationStrategy::DropMiddle)
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_request_serde_round_trip(request in llm_request()) {
+            let encoded = serde_json::to_value(&request).unwrap();
+            let decoded: LlmRequest = serde_json::from_value(encoded.clone()).unwrap();
+            prop_assert_eq!(&decoded.messages, &request.messages);
+            prop_assert_eq!(serde_json::to_value(&decoded).unwrap(), encoded);
+        }
+
+        #[test]
+        fn prop_truncation_keeps_system_and_last(
+            messages in messages(12),
+            budget in 0usize..400,
+            strategy in truncation_strategy(),
+        ) {
+            let tokenizer = EstimatingTokenizer::new(4.0);
+            let kept = truncate_messages(&messages, budget, strategy, &tokenizer);
+
+            // The result is a subsequence of the input
+            let mut remaining = messages.iter();
+            prop_assert!(kept.iter().all(|k| remaining.any(|m| m == k)));
+
+            let system = |m: &&Message| m.role == Role::System;
+            prop_assert_eq!(
+                kept.iter().filter(system).count(),
+                messages.iter().filter(system).count()
+            );
+            prop_assert_eq!(kept.last(), messages.last());
+
+            if strategy == TruncationStrategy::DropOldest {
+                let protected = messages.iter().filter(system).count()
+                    + usize::from(messages.last().is_some_and(|m| m.role != Role::System));
+                prop_assert!(tokenizer.count_messages(&kept) <= budget || kept.len() == protected);
+            }
+        }
+    }
+}
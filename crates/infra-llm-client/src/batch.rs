@@ -0,0 +1,310 @@
+//! Batch completions and embeddings.
+//!
+//! Batches run client-side with bounded concurrency, optionally throttled by
+//! an infra-rate-limit [`RateLimiter`]. Providers with a native batch API
+//! (OpenAI) use it instead when [`BatchOptions::with_native`] is set.
+//! Results are reported per item, so partial failures do not discard the
+//! successful responses.
+
+use futures::StreamExt;
+use infra_rate_limit::RateLimiter;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{LlmClientError, Result};
+use crate::provider::LlmProvider;
+use crate::types::{EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse};
+
+/// Default number of requests in flight for client-side batches.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+
+/// Options controlling how a batch is executed.
+#[derive(Clone)]
+pub struct BatchOptions {
+    /// Maximum number of requests in flight.
+    pub concurrency: usize,
+    /// Limiter each request must acquire a permit from before being sent.
+    pub rate_limiter: Option<Arc<dyn RateLimiter>>,
+    /// Use the provider's native batch API when it has one.
+    pub native: bool,
+    /// How often to poll a native batch job for completion.
+    pub poll_interval: Duration,
+}
+
+impl std::fmt::Debug for BatchOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchOptions")
+            .field("concurrency", &self.concurrency)
+            .field("rate_limiter", &self.rate_limiter.is_some())
+            .field("native", &self.native)
+            .field("poll_interval", &self.poll_interval)
+            .finish()
+    }
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_BATCH_CONCURRENCY,
+            rate_limiter: None,
+            native: false,
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Sets the maximum number of requests in flight (at least 1).
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Throttles requests through a rate limiter.
+    #[must_use]
+    pub fn with_rate_limiter(mut self, limiter: Arc<dyn RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Uses the provider's native batch API when available.
+    ///
+    /// Native batches are cheaper but may take hours to complete.
+    #[must_use]
+    pub fn with_native(mut self, native: bool) -> Self {
+        self.native = native;
+        self
+    }
+
+    /// Sets the polling interval for native batch jobs.
+    #[must_use]
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+}
+
+/// Per-item results of a batch, in request order.
+#[derive(Debug)]
+pub struct BatchResult<T> {
+    results: Vec<Result<T>>,
+}
+
+impl<T> BatchResult<T> {
+    /// Creates a batch result from per-item results.
+    #[must_use]
+    pub fn new(results: Vec<Result<T>>) -> Self {
+        Self { results }
+    }
+
+    /// Returns the number of items in the batch.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns `true` if the batch has no items.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns the result for the item at `index`.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Result<T>> {
+        self.results.get(index)
+    }
+
+    /// Iterates over successful items with their index.
+    pub fn successes(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.as_ref().ok().map(|v| (i, v)))
+    }
+
+    /// Iterates over failed items with their index.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &LlmClientError)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.as_ref().err().map(|e| (i, e)))
+    }
+
+    /// Returns the number of failed items.
+    #[must_use]
+    pub fn failure_count(&self) -> usize {
+        self.failures().count()
+    }
+
+    /// Returns `true` if every item succeeded.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(std::result::Result::is_ok)
+    }
+
+    /// Returns `true` if some, but not all, items failed.
+    #[must_use]
+    pub fn is_partial_failure(&self) -> bool {
+        let failures = self.failure_count();
+        failures > 0 && failures < self.len()
+    }
+
+    /// Consumes the batch, returning the per-item results.
+    #[must_use]
+    pub fn into_results(self) -> Vec<Result<T>> {
+        self.results
+    }
+}
+
+/// Runs completions client-side with bounded, rate-limited concurrency.
+pub async fn complete_concurrent<P: LlmProvider + ?Sized>(
+    provider: &P,
+    requests: Vec<LlmRequest>,
+    options: &BatchOptions,
+) -> BatchResult<LlmResponse> {
+    run(requests, options, |request| provider.complete(request)).await
+}
+
+/// Runs embeddings client-side with bounded, rate-limited concurrency.
+pub async fn embed_concurrent<P: LlmProvider + ?Sized>(
+    provider: &P,
+    requests: Vec<EmbeddingRequest>,
+    options: &BatchOptions,
+) -> BatchResult<EmbeddingResponse> {
+    run(requests, options, |request| provider.embed(request)).await
+}
+
+async fn run<R, T, F, Fut>(requests: Vec<R>, options: &BatchOptions, call: F) -> BatchResult<T>
+where
+    F: Fn(R) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let limiter = options.rate_limiter.as_deref();
+    let call = &call;
+    let results = futures::stream::iter(requests)
+        .map(|request| async move {
+            if let Some(limiter) = limiter {
+                limiter
+                    .acquire()
+                    .await
+                    .map_err(|e| LlmClientError::RateLimitExceeded(e.to_string()))?;
+            }
+            call(request).await
+        })
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+    BatchResult::new(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EmbeddingInput, Message, Role, StreamChunk};
+    use async_trait::async_trait;
+    use futures::Stream;
+    use infra_rate_limit::{RateLimitConfig, TokenBucket};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct EchoProvider {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmProvider for EchoProvider {
+        async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let content = request.messages[0].content.clone();
+            if content == "fail" {
+                return Err(LlmClientError::InvalidRequest("bad prompt".to_string()));
+            }
+            Ok(LlmResponse {
+                content,
+                model: request.model,
+                finish_reason: None,
+                usage: None,
+                tool_calls: Vec::new(),
+            })
+        }
+
+        async fn stream(
+            &self,
+            _request: LlmRequest,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+            Err(LlmClientError::Unsupported("streaming".to_string()))
+        }
+
+        async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+            Ok(EmbeddingResponse {
+                model: request.model,
+                embeddings: Vec::new(),
+                usage: None,
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    fn request(content: &str) -> LlmRequest {
+        LlmRequest {
+            model: "echo".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_failure_preserves_order() {
+        let provider = EchoProvider::default();
+        let requests = ["a", "fail", "c", "d", "e"].map(request).to_vec();
+        let options = BatchOptions::default().with_concurrency(2);
+
+        let batch = provider.complete_batch(requests, &options).await;
+        assert_eq!(batch.len(), 5);
+        assert!(batch.is_partial_failure());
+        assert_eq!(
+            batch.failures().map(|(i, _)| i).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(batch.get(2).unwrap().as_ref().unwrap().content, "c");
+        assert!(provider.max_in_flight.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_batch() {
+        let provider = EchoProvider::default();
+        let limiter = TokenBucket::new(RateLimitConfig::per_second(1000.0).unwrap());
+        let options = BatchOptions::default().with_rate_limiter(Arc::new(limiter));
+
+        let requests = (0..3)
+            .map(|i| EmbeddingRequest {
+                model: "echo".to_string(),
+                input: EmbeddingInput::Single(i.to_string()),
+            })
+            .collect();
+        let batch = provider.embed_batch(requests, &options).await;
+        assert!(batch.is_success());
+        assert_eq!(batch.successes().count(), 3);
+    }
+}
@@ -0,0 +1,282 @@
+//! Response caching for completions and embeddings.
+//!
+//! [`CachedProvider`] serves repeated requests from an infra-cache backend,
+//! keyed by a hash of the normalized request. By default only deterministic
+//! completions (temperature 0) are cached; embeddings are always cached.
+
+use async_trait::async_trait;
+use futures::Stream;
+use infra_cache::Cache;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::provider::LlmProvider;
+use crate::types::{
+    EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, Message, StreamChunk,
+};
+
+/// Prefix for all cache keys written by [`CachedProvider`].
+pub const CACHE_KEY_PREFIX: &str = "llm";
+
+/// Provider decorator caching responses in an infra-cache backend.
+///
+/// Cache failures never fail a request; they are logged and treated as
+/// misses. Streaming requests are never cached.
+#[derive(Debug)]
+pub struct CachedProvider<P, C> {
+    inner: P,
+    cache: C,
+    ttl: Option<Duration>,
+    cache_nondeterministic: bool,
+    bypass: AtomicBool,
+}
+
+impl<P: LlmProvider, C: Cache> CachedProvider<P, C> {
+    /// Wraps a provider with a cache, using the cache's default TTL.
+    #[must_use]
+    pub fn new(inner: P, cache: C) -> Self {
+        Self {
+            inner,
+            cache,
+            ttl: None,
+            cache_nondeterministic: false,
+            bypass: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets the TTL for cached responses.
+    #[must_use]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Also caches completions sampled with a non-zero temperature.
+    #[must_use]
+    pub fn with_cache_nondeterministic(mut self, enabled: bool) -> Self {
+        self.cache_nondeterministic = enabled;
+        self
+    }
+
+    /// Sets whether the cache is bypassed.
+    #[must_use]
+    pub fn with_bypass(self, bypass: bool) -> Self {
+        self.set_bypass(bypass);
+        self
+    }
+
+    /// Bypasses the cache (neither reading nor writing) until re-enabled.
+    pub fn set_bypass(&self, bypass: bool) {
+        self.bypass.store(bypass, Ordering::Relaxed);
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Returns the cache backend.
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    fn is_cacheable(&self, request: &LlmRequest) -> bool {
+        self.cache_nondeterministic || request.temperature.is_some_and(|t| t == 0.0)
+    }
+
+    async fn cached<T, F>(&self, key: Option<String>, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync + 'static,
+        F: std::future::Future<Output = Result<T>> + Send,
+    {
+        let Some(key) = key.filter(|_| !self.bypass.load(Ordering::Relaxed)) else {
+            return fetch.await;
+        };
+
+        match self.cache.get::<T>(&key).await {
+            Ok(Some(value)) => return Ok(value),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(error = %e, key = %key, "LLM cache read failed"),
+        }
+
+        let value = fetch.await?;
+        let serialized = serde_json::to_value(&value)?;
+        if let Err(e) = self.cache.set(&key, serialized, self.ttl).await {
+            tracing::warn!(error = %e, key = %key, "LLM cache write failed");
+        }
+        Ok(value)
+    }
+}
+
+/// Returns the cache key for a completion request.
+///
+/// Requests differing only in streaming or in leading/trailing whitespace
+/// of message contents share a key.
+#[must_use]
+pub fn completion_key(provider: &str, request: &LlmRequest) -> String {
+    let mut normalized = request.clone();
+    normalized.stream = None;
+    normalized.messages = request
+        .messages
+        .iter()
+        .map(|m| Message {
+            role: m.role,
+            content: m.content.trim().to_string(),
+        })
+        .collect();
+    key("complete", provider, &normalized)
+}
+
+/// Returns the cache key for an embedding request.
+#[must_use]
+pub fn embedding_key(provider: &str, request: &EmbeddingRequest) -> String {
+    key("embed", provider, request)
+}
+
+fn key<T: Serialize>(operation: &str, provider: &str, request: &T) -> String {
+    // Round-trip through `Value` so object keys are sorted
+    let canonical = serde_json::to_value(request)
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    let digest = Sha256::digest(canonical.as_bytes());
+
+    let mut key = format!("{CACHE_KEY_PREFIX}:{operation}:{provider}:");
+    for byte in digest {
+        let _ = write!(key, "{byte:02x}");
+    }
+    key
+}
+
+#[async_trait]
+impl<P: LlmProvider, C: Cache> LlmProvider for CachedProvider<P, C> {
+    async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let key = self
+            .is_cacheable(&request)
+            .then(|| completion_key(self.inner.provider_name(), &request));
+        self.cached(key, self.inner.complete(request)).await
+    }
+
+    async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        self.inner.stream(request).await
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        let key = Some(embedding_key(self.inner.provider_name(), &request));
+        self.cached(key, self.inner.embed(request)).await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LlmClientError;
+    use crate::types::Role;
+    use infra_cache::InMemoryCache;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmProvider for CountingProvider {
+        async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(LlmResponse {
+                content: format!("response {n}"),
+                model: request.model,
+                finish_reason: Some("stop".to_string()),
+                usage: None,
+                tool_calls: Vec::new(),
+            })
+        }
+
+        async fn stream(
+            &self,
+            _request: LlmRequest,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+            Err(LlmClientError::Unsupported("streaming".to_string()))
+        }
+
+        async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(EmbeddingResponse {
+                model: request.model,
+                embeddings: Vec::new(),
+                usage: None,
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    fn request(content: &str, temperature: f32) -> LlmRequest {
+        LlmRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            temperature: Some(temperature),
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_requests_are_cached() {
+        let provider = CachedProvider::new(CountingProvider::default(), InMemoryCache::unlimited());
+
+        let first = provider.complete(request("Hello", 0.0)).await.unwrap();
+        let second = provider.complete(request("  Hello\n", 0.0)).await.unwrap();
+        assert_eq!(first.content, second.content);
+        assert_eq!(provider.inner().calls.load(Ordering::SeqCst), 1);
+
+        provider.complete(request("Hello", 0.7)).await.unwrap();
+        provider.complete(request("Hello", 0.7)).await.unwrap();
+        assert_eq!(provider.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_bypass() {
+        let provider = CachedProvider::new(CountingProvider::default(), InMemoryCache::unlimited())
+            .with_bypass(true);
+
+        provider.complete(request("Hello", 0.0)).await.unwrap();
+        provider.complete(request("Hello", 0.0)).await.unwrap();
+        assert_eq!(provider.inner().calls.load(Ordering::SeqCst), 2);
+        assert_eq!(provider.cache().len().await.unwrap(), 0);
+
+        provider.set_bypass(false);
+        provider.complete(request("Hello", 0.0)).await.unwrap();
+        provider.complete(request("Hello", 0.0)).await.unwrap();
+        assert_eq!(provider.inner().calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_keys_differ_by_provider() {
+        let request = request("Hello", 0.0);
+        let openai = completion_key("openai", &request);
+        assert!(openai.starts_with("llm:complete:openai:"));
+        assert_ne!(openai, completion_key("anthropic", &request));
+    }
+}
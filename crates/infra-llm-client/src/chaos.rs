@@ -0,0 +1,163 @@
+//! Fault injection for resilience tests.
+//!
+//! [`ChaosProvider`] wraps any provider and injects latency, failures and
+//! corrupted responses according to an infra-sim [`ChaosPolicy`], so retry,
+//! fallback and validation layers can be exercised end to end.
+
+use async_trait::async_trait;
+use futures::Stream;
+use infra_sim::ChaosPolicy;
+use std::pin::Pin;
+
+use crate::error::{LlmClientError, Result};
+use crate::provider::LlmProvider;
+use crate::types::{EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, StreamChunk};
+
+/// Provider decorator injecting faults into another provider.
+///
+/// Operations are `complete` (including each request of a batch), `stream`
+/// and `embed`. Injected failures are [`LlmClientError::ProviderError`]. A
+/// corrupted completion has one bit of its content flipped, and a corrupted
+/// embedding one bit of its vectors. Streams are delayed or failed before
+/// the first chunk, but not corrupted.
+pub struct ChaosProvider<P> {
+    inner: P,
+    policy: ChaosPolicy,
+}
+
+impl<P> std::fmt::Debug for ChaosProvider<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChaosProvider").finish_non_exhaustive()
+    }
+}
+
+impl<P: LlmProvider> ChaosProvider<P> {
+    /// Wraps a provider with a chaos policy.
+    pub fn new(inner: P, policy: impl Into<ChaosPolicy>) -> Self {
+        Self {
+            inner,
+            policy: policy.into(),
+        }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    async fn disturb(&self, operation: &str) -> Result<()> {
+        self.policy
+            .disturb(operation)
+            .await
+            .map_err(LlmClientError::ProviderError)
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for ChaosProvider<P> {
+    async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        self.disturb("complete").await?;
+        let mut response = self.inner.complete(request).await?;
+
+        let injector = self.policy.injector("complete");
+        if injector.should_corrupt() {
+            let mut bytes = response.content.into_bytes();
+            injector.corrupt_bytes(&mut bytes);
+            response.content = String::from_utf8_lossy(&bytes).into_owned();
+        }
+        Ok(response)
+    }
+
+    async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        self.disturb("stream").await?;
+        self.inner.stream(request).await
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.disturb("embed").await?;
+        let mut response = self.inner.embed(request).await?;
+
+        let injector = self.policy.injector("embed");
+        if injector.should_corrupt() {
+            let mut values: Vec<f32> = response
+                .embeddings
+                .iter()
+                .flat_map(|e| e.embedding.iter().copied())
+                .collect();
+            injector.corrupt_floats(&mut values);
+            let mut values = values.into_iter();
+            for embedding in &mut response.embeddings {
+                for value in &mut embedding.embedding {
+                    *value = values.next().unwrap_or(*value);
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::mock::MockProvider;
+    use crate::types::{EmbeddingInput, Message, Role};
+    use infra_sim::{ChaosConfig, ChaosMode};
+
+    fn request(content: &str) -> LlmRequest {
+        LlmRequest {
+            model: "mock-1".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chaos_provider_failures() {
+        let policy = ChaosPolicy::disabled().operation(
+            "complete",
+            ChaosConfig::new(ChaosMode::AlwaysFail).with_error_message("overloaded"),
+        );
+        let provider = ChaosProvider::new(MockProvider::new().with_template("ok"), policy);
+
+        let err = provider.complete(request("hi")).await.unwrap_err();
+        assert!(matches!(err, LlmClientError::ProviderError(ref m) if m == "overloaded"));
+        assert_eq!(provider.inner().call_count(), 0);
+
+        let embedding = provider
+            .embed(EmbeddingRequest {
+                model: "mock-embed".to_string(),
+                input: EmbeddingInput::Single("hi".to_string()),
+            })
+            .await
+            .unwrap();
+        assert_eq!(embedding.embeddings.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_chaos_provider_corruption() {
+        let provider = ChaosProvider::new(
+            MockProvider::new().with_template("The answer is 42"),
+            ChaosConfig::default().with_corruption_probability(1.0),
+        );
+
+        let response = provider.complete(request("hi")).await.unwrap();
+        assert_ne!(response.content, "The answer is 42");
+        assert_eq!(provider.provider_name(), "mock");
+    }
+}
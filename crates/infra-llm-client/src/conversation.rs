@@ -0,0 +1,663 @@
+//! Conversation history with pluggable storage and memory policies.
+//!
+//! A [`Conversation`] keeps the message history of a session in a
+//! [`ConversationStore`] (in memory, infra-cache or infra-fs), and applies a
+//! [`MemoryPolicy`] to keep the messages it sends within the model's context
+//! window, either by dropping old messages or by summarizing them.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[cfg(any(feature = "cache", feature = "fs"))]
+use crate::error::LlmClientError;
+use crate::error::Result;
+use crate::provider::LlmProvider;
+use crate::tokenizer::{
+    context_window, truncate_messages, EstimatingTokenizer, Tokenizer, TruncationStrategy,
+};
+use crate::types::{LlmRequest, LlmResponse, Message, Role};
+
+/// Tokens reserved for the reply when a budget is derived from the context window.
+pub const DEFAULT_RESPONSE_RESERVE: usize = 1024;
+
+/// Instructions used to summarize older messages.
+pub const DEFAULT_SUMMARY_PROMPT: &str = "Summarize the conversation below for your own \
+    future reference. Keep facts, decisions, names and open questions; omit pleasantries. \
+    Reply with the summary only.";
+
+/// Persisted state of a conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationState {
+    /// Messages not yet folded into the summary, oldest first.
+    pub messages: Vec<Message>,
+    /// Summary of earlier messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// Storage backend for conversation state.
+#[async_trait]
+pub trait ConversationStore: Send + Sync {
+    /// Loads the state of a conversation, if it exists.
+    async fn load(&self, id: &str) -> Result<Option<ConversationState>>;
+
+    /// Saves the state of a conversation.
+    async fn save(&self, id: &str, state: &ConversationState) -> Result<()>;
+
+    /// Deletes a conversation, returning `true` if it existed.
+    async fn delete(&self, id: &str) -> Result<bool>;
+}
+
+/// How a conversation is kept within the model's context window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryPolicy {
+    /// Send the whole history.
+    Unbounded,
+    /// Send only the most recent messages.
+    LastMessages(usize),
+    /// Drop the oldest messages to fit a token budget.
+    ///
+    /// `None` uses the model's context window less
+    /// [`DEFAULT_RESPONSE_RESERVE`].
+    TokenBudget(Option<usize>),
+    /// Summarize older messages once the history exceeds a token budget,
+    /// keeping the most recent messages verbatim.
+    Summarize {
+        /// Token budget; `None` derives it as for [`MemoryPolicy::TokenBudget`].
+        budget: Option<usize>,
+        /// Number of recent messages never folded into the summary (at least 1).
+        keep_recent: usize,
+    },
+}
+
+impl Default for MemoryPolicy {
+    fn default() -> Self {
+        Self::TokenBudget(None)
+    }
+}
+
+/// Message history of a session, backed by a [`ConversationStore`].
+///
+/// The system prompt is configuration, not state: it is never stored and
+/// is always sent first, followed by the summary (if any) and the history.
+#[derive(Debug)]
+pub struct Conversation<S> {
+    id: String,
+    model: String,
+    store: S,
+    system_prompt: Option<String>,
+    policy: MemoryPolicy,
+    state: ConversationState,
+}
+
+impl<S: ConversationStore> Conversation<S> {
+    /// Starts an empty conversation.
+    #[must_use]
+    pub fn new(id: impl Into<String>, model: impl Into<String>, store: S) -> Self {
+        Self {
+            id: id.into(),
+            model: model.into(),
+            store,
+            system_prompt: None,
+            policy: MemoryPolicy::default(),
+            state: ConversationState::default(),
+        }
+    }
+
+    /// Resumes a stored conversation, or starts an empty one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    pub async fn load(id: impl Into<String>, model: impl Into<String>, store: S) -> Result<Self> {
+        let mut conversation = Self::new(id, model, store);
+        if let Some(state) = conversation.store.load(&conversation.id).await? {
+            conversation.state = state;
+        }
+        Ok(conversation)
+    }
+
+    /// Sets the system prompt sent with every request.
+    #[must_use]
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Sets the memory policy.
+    #[must_use]
+    pub fn with_policy(mut self, policy: MemoryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the conversation identifier.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the model requests are built for.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the messages not yet folded into the summary.
+    pub fn history(&self) -> &[Message] {
+        &self.state.messages
+    }
+
+    /// Returns the summary of earlier messages.
+    pub fn summary(&self) -> Option<&str> {
+        self.state.summary.as_deref()
+    }
+
+    /// Returns the backing store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Appends a message to the history.
+    pub fn push(&mut self, message: Message) {
+        self.state.messages.push(message);
+    }
+
+    /// Appends a user message to the history.
+    pub fn push_user(&mut self, content: impl Into<String>) {
+        self.push(Message {
+            role: Role::User,
+            content: content.into(),
+        });
+    }
+
+    /// Appends an assistant message to the history.
+    pub fn push_assistant(&mut self, content: impl Into<String>) {
+        self.push(Message {
+            role: Role::Assistant,
+            content: content.into(),
+        });
+    }
+
+    /// Clears the history and summary (the store is updated on [`save`](Self::save)).
+    pub fn clear(&mut self) {
+        self.state = ConversationState::default();
+    }
+
+    /// Writes the current state to the store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    pub async fn save(&self) -> Result<()> {
+        self.store.save(&self.id, &self.state).await
+    }
+
+    /// Deletes the conversation from the store, returning `true` if it existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    pub async fn delete(&self) -> Result<bool> {
+        self.store.delete(&self.id).await
+    }
+
+    /// Returns the messages to send, with the memory policy applied.
+    #[must_use]
+    pub fn messages(&self) -> Vec<Message> {
+        let history = match self.policy {
+            MemoryPolicy::LastMessages(n) => {
+                &self.state.messages[self.state.messages.len().saturating_sub(n)..]
+            }
+            _ => &self.state.messages[..],
+        };
+        let mut messages: Vec<Message> = self
+            .preamble()
+            .into_iter()
+            .chain(history.iter().cloned())
+            .collect();
+
+        match self.policy {
+            MemoryPolicy::TokenBudget(budget) | MemoryPolicy::Summarize { budget, .. } => {
+                messages = truncate_messages(
+                    &messages,
+                    self.budget(budget),
+                    TruncationStrategy::DropOldest,
+                    &self.tokenizer(),
+                );
+            }
+            MemoryPolicy::Unbounded | MemoryPolicy::LastMessages(_) => {}
+        }
+        messages
+    }
+
+    /// Builds a request for the next turn.
+    #[must_use]
+    pub fn request(&self) -> LlmRequest {
+        LlmRequest {
+            model: self.model.clone(),
+            messages: self.messages(),
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    /// Returns `true` if the summarize policy's budget is exceeded.
+    #[must_use]
+    pub fn needs_compaction(&self) -> bool {
+        let MemoryPolicy::Summarize {
+            budget,
+            keep_recent,
+        } = self.policy
+        else {
+            return false;
+        };
+        if self.state.messages.len() <= keep_recent.max(1) {
+            return false;
+        }
+        let total = self.tokenizer().count_messages(&self.preamble())
+            + self.tokenizer().count_messages(&self.state.messages);
+        total > self.budget(budget)
+    }
+
+    /// Folds older messages into the summary when the budget is exceeded.
+    ///
+    /// Returns `true` if the history was compacted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the summarization request fails; the history is
+    /// left unchanged.
+    pub async fn compact<P: LlmProvider + ?Sized>(&mut self, provider: &P) -> Result<bool> {
+        let MemoryPolicy::Summarize { keep_recent, .. } = self.policy else {
+            return Ok(false);
+        };
+        if !self.needs_compaction() {
+            return Ok(false);
+        }
+
+        // The latest message is always kept, so a failed turn can be undone
+        let split = self.state.messages.len() - keep_recent.max(1);
+        let mut transcript = String::new();
+        if let Some(summary) = &self.state.summary {
+            transcript.push_str("Earlier summary:\n");
+            transcript.push_str(summary);
+            transcript.push_str("\n\n");
+        }
+        for message in &self.state.messages[..split] {
+            transcript.push_str(role_name(message.role));
+            transcript.push_str(": ");
+            transcript.push_str(&message.content);
+            transcript.push('\n');
+        }
+
+        let mut request = self.request();
+        request.messages = vec![
+            Message {
+                role: Role::System,
+                content: DEFAULT_SUMMARY_PROMPT.to_string(),
+            },
+            Message {
+                role: Role::User,
+                content: transcript,
+            },
+        ];
+        let summary = provider.complete(request).await?.content;
+
+        self.state.summary = Some(summary.trim().to_string());
+        self.state.messages.drain(..split);
+        Ok(true)
+    }
+
+    /// Sends a user message and records the reply.
+    ///
+    /// Compacts the history first if needed, and saves the state after a
+    /// successful reply. On failure the user message is removed again, so
+    /// the turn can be retried.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compaction, the completion or saving fails.
+    pub async fn send<P: LlmProvider + ?Sized>(
+        &mut self,
+        provider: &P,
+        content: impl Into<String>,
+    ) -> Result<LlmResponse> {
+        self.push_user(content);
+        let result = match self.compact(provider).await {
+            Ok(_) => provider.complete(self.request()).await,
+            Err(e) => Err(e),
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.state.messages.pop();
+                return Err(e);
+            }
+        };
+        self.push_assistant(response.content.clone());
+        self.save().await?;
+        Ok(response)
+    }
+
+    fn preamble(&self) -> Vec<Message> {
+        let system = self.system_prompt.iter().map(|prompt| Message {
+            role: Role::System,
+            content: prompt.clone(),
+        });
+        let summary = self.state.summary.iter().map(|summary| Message {
+            role: Role::System,
+            content: format!("Summary of the earlier conversation:\n{summary}"),
+        });
+        system.chain(summary).collect()
+    }
+
+    fn tokenizer(&self) -> EstimatingTokenizer {
+        EstimatingTokenizer::for_model(&self.model)
+    }
+
+    fn budget(&self, budget: Option<usize>) -> usize {
+        budget
+            .unwrap_or_else(|| context_window(&self.model).saturating_sub(DEFAULT_RESPONSE_RESERVE))
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+/// In-process conversation store.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    states: Mutex<HashMap<String, ConversationState>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn states(&self) -> std::sync::MutexGuard<'_, HashMap<String, ConversationState>> {
+        self.states
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl ConversationStore for MemoryStore {
+    async fn load(&self, id: &str) -> Result<Option<ConversationState>> {
+        Ok(self.states().get(id).cloned())
+    }
+
+    async fn save(&self, id: &str, state: &ConversationState) -> Result<()> {
+        self.states().insert(id.to_string(), state.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Ok(self.states().remove(id).is_some())
+    }
+}
+
+/// Conversation store backed by an infra-cache backend.
+///
+/// Entries expire with the cache's TTL, which makes it suitable for
+/// short-lived sessions.
+#[cfg(feature = "cache")]
+#[derive(Debug)]
+pub struct CacheStore<C> {
+    cache: C,
+    ttl: Option<std::time::Duration>,
+}
+
+#[cfg(feature = "cache")]
+impl<C: infra_cache::Cache> CacheStore<C> {
+    /// Creates a store using the cache's default TTL.
+    #[must_use]
+    pub fn new(cache: C) -> Self {
+        Self { cache, ttl: None }
+    }
+
+    /// Sets the TTL of stored conversations.
+    #[must_use]
+    pub fn with_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn key(id: &str) -> String {
+        format!("{}:conversation:{id}", crate::cache::CACHE_KEY_PREFIX)
+    }
+}
+
+#[cfg(feature = "cache")]
+fn storage_error(error: &infra_cache::CacheError) -> LlmClientError {
+    LlmClientError::StorageError(error.to_string())
+}
+
+#[cfg(feature = "cache")]
+#[async_trait]
+impl<C: infra_cache::Cache> ConversationStore for CacheStore<C> {
+    async fn load(&self, id: &str) -> Result<Option<ConversationState>> {
+        self.cache
+            .get(&Self::key(id))
+            .await
+            .map_err(|e| storage_error(&e))
+    }
+
+    async fn save(&self, id: &str, state: &ConversationState) -> Result<()> {
+        self.cache
+            .set(&Self::key(id), state.clone(), self.ttl)
+            .await
+            .map_err(|e| storage_error(&e))
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        self.cache
+            .delete(&Self::key(id))
+            .await
+            .map_err(|e| storage_error(&e))
+    }
+}
+
+/// Conversation store writing one JSON file per conversation.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl FileStore {
+    /// Creates a store in `dir`, which is created on first save.
+    #[must_use]
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the file holding a conversation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the identifier is not a plain file name
+    /// (ASCII letters, digits, `-`, `_` and `.`, not starting with `.`).
+    pub fn path(&self, id: &str) -> Result<std::path::PathBuf> {
+        let valid = !id.is_empty()
+            && !id.starts_with('.')
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(LlmClientError::InvalidRequest(format!(
+                "invalid conversation id: {id:?}"
+            )));
+        }
+        Ok(self.dir.join(format!("{id}.json")))
+    }
+}
+
+/// Runs filesystem IO on the blocking thread pool.
+#[cfg(feature = "fs")]
+async fn blocking<T, F>(io: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(io)
+        .await
+        .map_err(|e| LlmClientError::StorageError(e.to_string()))?
+}
+
+#[cfg(feature = "fs")]
+#[async_trait]
+impl ConversationStore for FileStore {
+    async fn load(&self, id: &str) -> Result<Option<ConversationState>> {
+        let path = self.path(id)?;
+        blocking(move || {
+            if !infra_fs::exists(&path) {
+                return Ok(None);
+            }
+            Ok(Some(infra_fs::read_json(path)?))
+        })
+        .await
+    }
+
+    async fn save(&self, id: &str, state: &ConversationState) -> Result<()> {
+        let path = self.path(id)?;
+        let state = state.clone();
+        blocking(move || Ok(infra_fs::write_json(path, &state)?)).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        let path = self.path(id)?;
+        blocking(move || {
+            if !infra_fs::exists(&path) {
+                return Ok(false);
+            }
+            infra_fs::remove(path)?;
+            Ok(true)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_windowing_policies() {
+        let mut conversation = Conversation::new("c1", "gpt-4", MemoryStore::new())
+            .with_system_prompt("Be brief.")
+            .with_policy(MemoryPolicy::LastMessages(2));
+        for i in 0..5 {
+            conversation.push_user(format!("question {i}"));
+            conversation.push_assistant(format!("answer {i}"));
+        }
+
+        let messages = conversation.messages();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, Role::System);
+        assert_eq!(messages[1].content, "question 4");
+
+        let conversation = conversation.with_policy(MemoryPolicy::TokenBudget(Some(40)));
+        let messages = conversation.request().messages;
+        assert_eq!(messages[0].content, "Be brief.");
+        assert_eq!(messages.last().unwrap().content, "answer 4");
+        assert!(messages.len() < 11);
+    }
+
+    #[tokio::test]
+    async fn test_store_roundtrip() {
+        let store = MemoryStore::new();
+        let mut conversation = Conversation::new("c1", "gpt-4o", store);
+        conversation.push_user("hello");
+        conversation.save().await.unwrap();
+
+        let resumed = Conversation::load("c1", "gpt-4o", conversation.store)
+            .await
+            .unwrap();
+        assert_eq!(resumed.history().len(), 1);
+        assert!(resumed.delete().await.unwrap());
+        assert!(resumed.store().load("c1").await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_send_and_summarize() {
+        use crate::mock::MockProvider;
+
+        let provider = MockProvider::new().with_template("reply to {{last_message}}");
+        let mut conversation = Conversation::new("c2", "gpt-4", MemoryStore::new())
+            .with_system_prompt("Be brief.")
+            .with_policy(MemoryPolicy::Summarize {
+                budget: None,
+                keep_recent: 2,
+            });
+
+        for i in 0..3 {
+            conversation
+                .send(&provider, format!("tell me about topic number {i}"))
+                .await
+                .unwrap();
+        }
+        assert!(conversation.summary().is_none());
+        assert_eq!(conversation.history().len(), 6);
+
+        // Shrink the budget so the next turn must be compacted
+        conversation = conversation.with_policy(MemoryPolicy::Summarize {
+            budget: Some(1),
+            keep_recent: 2,
+        });
+        assert!(conversation.needs_compaction());
+        provider.push_response("User asked about topics 0-2.");
+        let response = conversation.send(&provider, "and topic 3?").await.unwrap();
+        assert_eq!(response.content, "reply to and topic 3?");
+        assert_eq!(conversation.summary(), Some("User asked about topics 0-2."));
+        assert_eq!(conversation.history().len(), 3);
+
+        let sent = provider.last_request().unwrap().messages;
+        assert!(sent[1].content.contains("topics 0-2"));
+        assert_eq!(sent.last().unwrap().content, "and topic 3?");
+
+        let stored = conversation.store().load("c2").await.unwrap().unwrap();
+        assert_eq!(stored.messages.len(), 3);
+
+        // Failed turns leave the history unchanged
+        provider.push_error(crate::error::LlmClientError::Timeout("slow".to_string()));
+        assert!(conversation.send(&provider, "again").await.is_err());
+        assert_eq!(conversation.history().len(), 3);
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_file_store() {
+        let dir = infra_fs::TempDir::new().unwrap();
+        let store = FileStore::new(dir.path().join("conversations"));
+        assert!(store.path("../escape").is_err());
+
+        let state = ConversationState {
+            messages: vec![Message {
+                role: Role::User,
+                content: "hi".to_string(),
+            }],
+            summary: Some("greeting".to_string()),
+        };
+        store.save("session-1", &state).await.unwrap();
+        assert_eq!(store.load("session-1").await.unwrap(), Some(state));
+        assert!(store.delete("session-1").await.unwrap());
+        assert_eq!(store.load("session-1").await.unwrap(), None);
+    }
+}
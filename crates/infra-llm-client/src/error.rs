@@ -1,7 +1,13 @@
 //! Error types for LLM client operations.
 
+use infra_errors::{AuthErrorKind, InfraError, LlmErrorKind, RetryAdvice};
+use infra_http::{ERROR_CODE_ATTRIBUTE, REQUEST_ID_ATTRIBUTE};
 use thiserror::Error;
 
+/// Error code OpenAI-compatible APIs use when a prompt exceeds the context
+/// window
+const CONTEXT_LENGTH_CODE: &str = "context_length_exceeded";
+
 /// Errors that can occur during LLM client operations.
 #[derive(Error, Debug)]
 pub enum LlmClientError {
@@ -35,7 +41,7 @@ pub enum LlmClientError {
 
     /// An underlying infrastructure error occurred.
     #[error("Infrastructure error: {0}")]
-    InfraError(#[from] infra_errors::InfraError),
+    InfraError(#[from] InfraError),
 
     /// A network or I/O error occurred.
     #[error("Network error: {0}")]
@@ -45,6 +51,19 @@ pub enum LlmClientError {
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
 
+    /// A storage backend (such as a conversation store) failed.
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    /// The request or response was blocked by a guardrail.
+    #[error("Blocked by guardrail {guardrail}: {reason}")]
+    GuardrailBlocked {
+        /// Name of the guardrail that blocked the content.
+        guardrail: String,
+        /// Why the content was blocked.
+        reason: String,
+    },
+
     /// The operation is not supported by this provider.
     #[error("Operation not supported: {0}")]
     Unsupported(String),
@@ -54,5 +73,276 @@ pub enum LlmClientError {
     Unknown(String),
 }
 
+impl LlmClientError {
+    /// Maps an infra-http error to a typed client error based on its status code.
+    #[must_use]
+    pub fn from_http(error: InfraError) -> Self {
+        match error {
+            InfraError::Http {
+                status: Some(status),
+                message,
+                ..
+            } => match status {
+                400 | 422 => Self::InvalidRequest(message),
+                401 | 403 => Self::AuthenticationError(message),
+                404 => Self::ModelNotFound(message),
+                408 => Self::Timeout(message),
+                429 => Self::RateLimitExceeded(message),
+                _ if status >= 500 => Self::ProviderError(message),
+                _ => Self::Unknown(message),
+            },
+            InfraError::Http {
+                status: None,
+                message,
+                ..
+            } => Self::NetworkError(message),
+            InfraError::Timeout { operation, .. } => Self::Timeout(operation),
+            other => Self::InfraError(other),
+        }
+    }
+
+    /// Maps an infra-http error from a provider's API, attributing
+    /// LLM-specific failures to the provider and model.
+    ///
+    /// Rate limits (429), provider-side failures (5xx) and prompts exceeding
+    /// the context window (413, or the `context_length_exceeded` error code)
+    /// become [`InfraError::LlmProvider`] errors carrying the request ID the
+    /// provider returned; other errors map as in [`from_http`](Self::from_http).
+    #[must_use]
+    pub fn from_provider_http(error: InfraError, provider: &str, model: Option<&str>) -> Self {
+        let InfraError::Http {
+            status: Some(status),
+            message,
+            context,
+            ..
+        } = &error
+        else {
+            return Self::from_http(error);
+        };
+        let attribute = |name: &str| context.as_ref()?.attributes.get(name).cloned();
+        let kind = match status {
+            429 => LlmErrorKind::RateLimited,
+            413 => LlmErrorKind::ContextTooLong,
+            _ if attribute(ERROR_CODE_ATTRIBUTE).as_deref() == Some(CONTEXT_LENGTH_CODE) => {
+                LlmErrorKind::ContextTooLong
+            }
+            _ if *status >= 500 => LlmErrorKind::Overloaded,
+            _ => return Self::from_http(error),
+        };
+        let infra = InfraError::LlmProvider {
+            provider: provider.to_string(),
+            model: model.map(str::to_string),
+            kind,
+            message: message.clone(),
+            request_id: attribute(REQUEST_ID_ATTRIBUTE),
+            context: None,
+            source: None,
+        };
+        Self::InfraError(infra.with_source(error))
+    }
+
+    /// Returns the advice on retrying after this error.
+    ///
+    /// Wrapped infra errors follow [`InfraError::retry_advice`]. Rate limits
+    /// reject the request before it has any effect; timeouts, network
+    /// failures and provider-side (5xx) errors leave the outcome unknown;
+    /// request, authentication and parsing errors are permanent.
+    #[must_use]
+    pub fn retry_advice(&self) -> RetryAdvice {
+        match self {
+            Self::RateLimitExceeded(_) => RetryAdvice::rejected(),
+            Self::Timeout(_) | Self::NetworkError(_) | Self::ProviderError(_) => {
+                RetryAdvice::transient()
+            }
+            Self::InfraError(e) => e.retry_advice(),
+            _ => RetryAdvice::permanent(),
+        }
+    }
+
+    /// Returns `true` if the operation may succeed when retried.
+    ///
+    /// See [`retry_advice`](Self::retry_advice).
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        self.retry_advice().retryable
+    }
+}
+
+impl LlmClientError {
+    /// Returns the LLM-specific failure mode of the error, if it has one.
+    ///
+    /// Rate limits, provider-side failures and guardrail blocks map to their
+    /// kinds. Prompts exceeding the context window are only recognized from
+    /// the provider's structured response, by
+    /// [`from_provider_http`](Self::from_provider_http).
+    #[must_use]
+    pub fn llm_kind(&self) -> Option<LlmErrorKind> {
+        match self {
+            Self::RateLimitExceeded(_) => Some(LlmErrorKind::RateLimited),
+            Self::ProviderError(_) => Some(LlmErrorKind::Overloaded),
+            Self::GuardrailBlocked { .. } => Some(LlmErrorKind::ContentFiltered),
+            Self::InfraError(InfraError::LlmProvider { kind, .. }) => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Converts to an [`InfraError`], attributing LLM-specific failures to
+    /// the provider and model.
+    ///
+    /// Errors with an [`llm_kind`](Self::llm_kind) become
+    /// [`InfraError::LlmProvider`]; the others map to the closest general
+    /// variant. Wrapped infra errors are returned as they are, and every
+    /// other error is kept as the source.
+    #[must_use]
+    pub fn into_infra_error(self, provider: &str, model: Option<&str>) -> InfraError {
+        let error = match self {
+            Self::InfraError(error) => return error,
+            Self::SerializationError(error) => return error.into(),
+            error => error,
+        };
+        let message = error.to_string();
+        let infra = if let Some(kind) = error.llm_kind() {
+            InfraError::LlmProvider {
+                provider: provider.to_string(),
+                model: model.map(str::to_string),
+                kind,
+                message,
+                request_id: None,
+                context: None,
+                source: None,
+            }
+        } else {
+            match &error {
+                Self::AuthenticationError(_) => InfraError::Auth {
+                    kind: AuthErrorKind::InvalidCredentials,
+                    message,
+                    identity: None,
+                    context: None,
+                    source: None,
+                },
+                Self::ModelNotFound(name) => InfraError::not_found("model", model.unwrap_or(name)),
+                Self::Timeout(_) => InfraError::http_with_status(504, message),
+                Self::NetworkError(_) => InfraError::http(message),
+                Self::InvalidRequest(_) | Self::Unsupported(_) => InfraError::validation(message),
+                _ => InfraError::External {
+                    service: provider.to_string(),
+                    operation: "llm_request".to_string(),
+                    message,
+                    retry_after: None,
+                    context: None,
+                    source: None,
+                },
+            }
+        };
+        infra.with_source(error)
+    }
+}
+
+impl From<LlmClientError> for InfraError {
+    /// Converts with an unknown provider; prefer
+    /// [`LlmClientError::into_infra_error`] where the provider is known.
+    fn from(error: LlmClientError) -> Self {
+        error.into_infra_error("unknown", None)
+    }
+}
+
 /// A specialized Result type for LLM client operations.
 pub type Result<T> = std::result::Result<T, LlmClientError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_http_status_mapping() {
+        let err =
+            |status| LlmClientError::from_http(InfraError::http_with_status(status, "failed"));
+
+        assert!(matches!(err(429), LlmClientError::RateLimitExceeded(_)));
+        assert!(matches!(err(401), LlmClientError::AuthenticationError(_)));
+        assert!(matches!(err(503), LlmClientError::ProviderError(_)));
+        assert!(err(429).is_retryable());
+        assert!(err(502).is_retryable());
+        assert!(!err(400).is_retryable());
+        assert!(err(429).retry_advice().allows_retry(false));
+        assert!(!err(502).retry_advice().allows_retry(false));
+    }
+
+    #[test]
+    fn test_into_infra_error() {
+        let err = LlmClientError::RateLimitExceeded("slow down".to_string())
+            .into_infra_error("openai", Some("gpt-4o"));
+        assert!(matches!(
+            &err,
+            InfraError::LlmProvider {
+                kind: LlmErrorKind::RateLimited,
+                provider,
+                model: Some(model),
+                ..
+            } if provider == "openai" && model == "gpt-4o"
+        ));
+        assert!(err.is_retryable());
+        assert!(err
+            .chain()
+            .nth(1)
+            .is_some_and(|source| source.is::<LlmClientError>()));
+
+        let err: InfraError = LlmClientError::InvalidRequest("Missing messages".to_string()).into();
+        assert_eq!(err.error_type(), "validation");
+
+        let err = LlmClientError::GuardrailBlocked {
+            guardrail: "pii".to_string(),
+            reason: "Email address".to_string(),
+        };
+        assert_eq!(err.llm_kind(), Some(LlmErrorKind::ContentFiltered));
+        let wrapped = LlmClientError::InfraError(InfraError::validation("bad"));
+        assert_eq!(
+            wrapped.into_infra_error("openai", None).error_type(),
+            "validation"
+        );
+    }
+
+    #[test]
+    fn test_from_provider_http() {
+        let http = |status, code: Option<&str>| {
+            let mut context =
+                infra_errors::ErrorContext::new().with_attribute(REQUEST_ID_ATTRIBUTE, "req_123");
+            if let Some(code) = code {
+                context = context.with_attribute(ERROR_CODE_ATTRIBUTE, code);
+            }
+            InfraError::http_with_status(status, format!("HTTP error: {status}"))
+                .with_context(context)
+        };
+        let err = |status, code| {
+            LlmClientError::from_provider_http(http(status, code), "openai", Some("gpt-4o"))
+                .into_infra_error("openai", Some("gpt-4o"))
+        };
+
+        let too_long = err(400, Some(CONTEXT_LENGTH_CODE));
+        assert!(matches!(
+            &too_long,
+            InfraError::LlmProvider {
+                kind: LlmErrorKind::ContextTooLong,
+                request_id: Some(id),
+                ..
+            } if id == "req_123"
+        ));
+        assert_eq!(too_long.to_http_status(), 413);
+        assert!(matches!(
+            err(429, None),
+            InfraError::LlmProvider {
+                kind: LlmErrorKind::RateLimited,
+                ..
+            }
+        ));
+        assert!(matches!(
+            err(503, None),
+            InfraError::LlmProvider {
+                kind: LlmErrorKind::Overloaded,
+                ..
+            }
+        ));
+        assert_eq!(err(400, Some("invalid_value")).error_type(), "validation");
+        assert!(LlmClientError::from_provider_http(http(429, None), "openai", None).is_retryable());
+    }
+}
@@ -8,11 +8,26 @@
 //! - Core `LlmProvider` trait for implementing provider-specific adapters
 //! - Common types for LLM requests, responses, and messages
 //! - Error handling for LLM operations
-//! - Placeholder adapters for future provider implementations
+//! - Batch completions and embeddings with per-item results
+//! - A middleware pipeline for request mutation, templating, logging and guardrails
+//! - Streaming aggregation into complete responses, including partial tool calls
+//! - Conversation memory with pluggable stores and windowing/summarization policies
+//! - Offline token estimation and context-window budgeting
+//! - An OpenAI adapter (chat completions, streaming and embeddings) built on infra-http
+//! - An adapter for OpenAI-compatible self-hosted servers (Ollama, vLLM)
 //!
 //! ## Features
 //!
 //! - `std` (default): Enable standard library support
+//! - `audit`: Emit infra-audit LLM events from `AuditMiddleware`
+//! - `guardrails`: Input/output content policies (secrets, jailbreaks, length) loadable from infra-config
+//! - `fs`: Persist conversations as JSON files with infra-fs via `FileStore`
+//! - `cache`: Cache completions and embeddings in infra-cache via `CachedProvider`
+//! - `redact`: Mask sensitive prompt data with `RedactionMiddleware` (infra-json)
+//! - `mock`: Scripted `MockProvider` with infra-sim chaos injection for tests
+//! - `chaos`: `ChaosProvider` fault injection decorator for resilience tests
+//! - `structured`: Validate and repair JSON responses against a `ResponseFormat`
+//! - `proptest`: Proptest strategies for messages and requests in `arbitrary`
 //!
 //! ## Example
 //!
@@ -38,13 +53,53 @@
 //! ```
 
 pub mod adapters;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod batch;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod conversation;
 pub mod error;
+#[cfg(feature = "guardrails")]
+pub mod guardrails;
+pub mod middleware;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod provider;
+mod sse;
+pub mod stream;
+#[cfg(feature = "structured")]
+pub mod structured;
+pub mod tokenizer;
 pub mod types;
 
 // Re-export commonly used items
+pub use batch::{BatchOptions, BatchResult};
+pub use conversation::{Conversation, ConversationStore, MemoryPolicy, MemoryStore};
 pub use error::LlmClientError;
+pub use middleware::{CallContext, MiddlewareProvider, ProviderMiddleware};
 pub use provider::LlmProvider;
+pub use stream::{collect_stream, text_deltas, StreamAccumulator, MAX_STREAMED_TOOL_CALLS};
+pub use tokenizer::{
+    context_window, fits_in_context, prompt_tokens, truncate_messages, EstimatingTokenizer,
+    ModelFamily, Tokenizer, TruncationStrategy,
+};
 pub use types::{
-    EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, Message, Role,
+    EmbeddingRequest, EmbeddingResponse, JsonSchemaFormat, LlmRequest, LlmResponse, Message,
+    ResponseFormat, Role, ToolCall, ToolCallDelta,
+};
+
+#[cfg(feature = "cache")]
+pub use cache::CachedProvider;
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosProvider;
+#[cfg(feature = "guardrails")]
+pub use guardrails::{
+    Guardrail, GuardrailMiddleware, GuardrailOutcome, GuardrailPolicy, GuardrailSet,
 };
+#[cfg(feature = "mock")]
+pub use mock::MockProvider;
+#[cfg(feature = "structured")]
+pub use structured::{parse_structured, StructuredOutputProvider};
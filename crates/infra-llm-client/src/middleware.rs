@@ -0,0 +1,744 @@
+//! Middleware pipeline for LLM providers.
+//!
+//! [`MiddlewareProvider`] wraps any [`LlmProvider`] with a stack of
+//! [`ProviderMiddleware`]s. Requests pass through each middleware in order
+//! before being sent, and responses pass back through them in reverse order.
+//! A middleware rejects a request by returning an error from
+//! [`ProviderMiddleware::before`], which is how guardrails plug in.
+
+use async_trait::async_trait;
+use futures::Stream;
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{LlmClientError, Result};
+use crate::provider::LlmProvider;
+use crate::tokenizer::{context_window, fits_in_context, prompt_tokens};
+use crate::types::{
+    EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, Message, Role, StreamChunk,
+};
+
+/// Information about the call a middleware is processing.
+#[derive(Debug, Clone, Copy)]
+pub struct CallContext<'a> {
+    /// Name of the wrapped provider.
+    pub provider: &'a str,
+    /// When the call entered the pipeline.
+    pub started: Instant,
+}
+
+impl CallContext<'_> {
+    /// Returns the time elapsed since the call entered the pipeline.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Hook around completion and streaming requests.
+#[async_trait]
+pub trait ProviderMiddleware: Send + Sync {
+    /// Processes a request before it is sent.
+    ///
+    /// Returning an error rejects the request without calling the provider.
+    async fn before(&self, _ctx: &CallContext<'_>, _request: &mut LlmRequest) -> Result<()> {
+        Ok(())
+    }
+
+    /// Processes a completion response after it is received.
+    ///
+    /// Not called for streaming requests.
+    async fn after(
+        &self,
+        _ctx: &CallContext<'_>,
+        _request: &LlmRequest,
+        _response: &mut LlmResponse,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Observes a failed request, whichever stage it failed in.
+    async fn on_error(
+        &self,
+        _ctx: &CallContext<'_>,
+        _request: &LlmRequest,
+        _error: &LlmClientError,
+    ) {
+    }
+
+    /// Middleware name for debugging.
+    fn name(&self) -> &str {
+        "anonymous"
+    }
+}
+
+/// Provider decorator running requests through a middleware stack.
+///
+/// Embedding requests are passed to the wrapped provider unchanged.
+pub struct MiddlewareProvider<P> {
+    inner: P,
+    middlewares: Vec<Arc<dyn ProviderMiddleware>>,
+}
+
+impl<P: std::fmt::Debug> std::fmt::Debug for MiddlewareProvider<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareProvider")
+            .field("inner", &self.inner)
+            .field(
+                "middlewares",
+                &self
+                    .middlewares
+                    .iter()
+                    .map(|m| m.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<P: LlmProvider> MiddlewareProvider<P> {
+    /// Wraps a provider with an empty middleware stack.
+    #[must_use]
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Appends a middleware; earlier middlewares see requests first.
+    #[must_use]
+    pub fn with<M: ProviderMiddleware + 'static>(self, middleware: M) -> Self {
+        self.with_shared(Arc::new(middleware))
+    }
+
+    /// Appends a middleware shared with other pipelines.
+    #[must_use]
+    pub fn with_shared(mut self, middleware: Arc<dyn ProviderMiddleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Returns the names of the middlewares, in request order.
+    pub fn middleware_names(&self) -> Vec<&str> {
+        self.middlewares.iter().map(|m| m.name()).collect()
+    }
+
+    fn context(&self) -> CallContext<'_> {
+        CallContext {
+            provider: self.inner.provider_name(),
+            started: Instant::now(),
+        }
+    }
+
+    async fn prepare(&self, ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        for middleware in &self.middlewares {
+            middleware.before(ctx, request).await?;
+        }
+        Ok(())
+    }
+
+    async fn notify(&self, ctx: &CallContext<'_>, request: &LlmRequest, error: &LlmClientError) {
+        for middleware in &self.middlewares {
+            middleware.on_error(ctx, request, error).await;
+        }
+    }
+
+    async fn run_complete(
+        &self,
+        ctx: &CallContext<'_>,
+        request: &mut LlmRequest,
+    ) -> Result<LlmResponse> {
+        self.prepare(ctx, request).await?;
+        let mut response = self.inner.complete(request.clone()).await?;
+        for middleware in self.middlewares.iter().rev() {
+            middleware.after(ctx, request, &mut response).await?;
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for MiddlewareProvider<P> {
+    async fn complete(&self, mut request: LlmRequest) -> Result<LlmResponse> {
+        let ctx = self.context();
+        let result = self.run_complete(&ctx, &mut request).await;
+        if let Err(error) = &result {
+            self.notify(&ctx, &request, error).await;
+        }
+        result
+    }
+
+    async fn stream(
+        &self,
+        mut request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        let ctx = self.context();
+        let result = match self.prepare(&ctx, &mut request).await {
+            Ok(()) => self.inner.stream(request.clone()).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = &result {
+            self.notify(&ctx, &request, error).await;
+        }
+        result
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.inner.embed(request).await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+/// Middleware mutating requests with a closure.
+pub struct MapRequestMiddleware<F> {
+    map: F,
+}
+
+impl<F: Fn(&mut LlmRequest) + Send + Sync> MapRequestMiddleware<F> {
+    /// Creates a middleware applying `map` to every request.
+    #[must_use]
+    pub fn new(map: F) -> Self {
+        Self { map }
+    }
+}
+
+#[async_trait]
+impl<F: Fn(&mut LlmRequest) + Send + Sync> ProviderMiddleware for MapRequestMiddleware<F> {
+    async fn before(&self, _ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        (self.map)(request);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "map_request"
+    }
+}
+
+/// Prompt templating middleware.
+///
+/// Replaces `{{name}}` placeholders in system messages with configured
+/// variables, and optionally prepends a system prompt when the request has
+/// none. User and assistant contents are never templated, so they cannot
+/// inject placeholders. Unknown placeholders are left untouched.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateMiddleware {
+    vars: BTreeMap<String, String>,
+    system_prompt: Option<String>,
+}
+
+impl TemplateMiddleware {
+    /// Creates a template middleware without variables.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a template variable.
+    #[must_use]
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Prepends a system prompt (itself templated) to requests without one.
+    #[must_use]
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Renders a template with the configured variables.
+    #[must_use]
+    pub fn render(&self, template: &str) -> String {
+        self.vars
+            .iter()
+            .fold(template.to_string(), |out, (name, value)| {
+                out.replace(&format!("{{{{{name}}}}}"), value)
+            })
+    }
+}
+
+#[async_trait]
+impl ProviderMiddleware for TemplateMiddleware {
+    async fn before(&self, _ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        for message in &mut request.messages {
+            if message.role == Role::System {
+                message.content = self.render(&message.content);
+            }
+        }
+        if let Some(prompt) = &self.system_prompt {
+            if !request.messages.iter().any(|m| m.role == Role::System) {
+                request.messages.insert(
+                    0,
+                    Message {
+                        role: Role::System,
+                        content: self.render(prompt),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "template"
+    }
+}
+
+/// Middleware logging requests, responses and failures via `tracing`.
+///
+/// Message contents are never logged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl ProviderMiddleware for LoggingMiddleware {
+    async fn before(&self, ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        tracing::info!(
+            provider = ctx.provider,
+            model = %request.model,
+            messages = request.messages.len(),
+            "Sending LLM request"
+        );
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        ctx: &CallContext<'_>,
+        _request: &LlmRequest,
+        response: &mut LlmResponse,
+    ) -> Result<()> {
+        tracing::info!(
+            provider = ctx.provider,
+            model = %response.model,
+            finish_reason = response.finish_reason.as_deref().unwrap_or(""),
+            total_tokens = response.usage.map_or(0, |u| u.total_tokens),
+            latency_ms = u64::try_from(ctx.elapsed().as_millis()).unwrap_or(u64::MAX),
+            "Received LLM response"
+        );
+        Ok(())
+    }
+
+    async fn on_error(&self, ctx: &CallContext<'_>, request: &LlmRequest, error: &LlmClientError) {
+        tracing::warn!(
+            provider = ctx.provider,
+            model = %request.model,
+            error = %error,
+            "LLM request failed"
+        );
+    }
+
+    fn name(&self) -> &str {
+        "logging"
+    }
+}
+
+/// Guardrail rejecting requests that exceed the model's context window.
+///
+/// The prompt size is estimated offline, and the request's `max_tokens`
+/// is reserved for the completion.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContextLimitMiddleware;
+
+#[async_trait]
+impl ProviderMiddleware for ContextLimitMiddleware {
+    async fn before(&self, _ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        if fits_in_context(request, &request.model) {
+            return Ok(());
+        }
+        Err(LlmClientError::InvalidRequest(format!(
+            "prompt of ~{} tokens plus {} completion tokens exceeds the {}-token context window of {}",
+            prompt_tokens(request),
+            request.max_tokens.unwrap_or(0),
+            context_window(&request.model),
+            request.model
+        )))
+    }
+
+    fn name(&self) -> &str {
+        "context_limit"
+    }
+}
+
+/// Middleware masking sensitive data in prompts with an infra-json [`Masker`].
+///
+/// [`Masker`]: infra_json::Masker
+#[cfg(feature = "redact")]
+#[derive(Debug, Clone)]
+pub struct RedactionMiddleware {
+    masker: infra_json::Masker,
+    responses: bool,
+}
+
+#[cfg(feature = "redact")]
+impl RedactionMiddleware {
+    /// Creates a middleware applying the masker's pattern rules to prompts.
+    #[must_use]
+    pub fn new(masker: infra_json::Masker) -> Self {
+        Self {
+            masker,
+            responses: false,
+        }
+    }
+
+    /// Creates a middleware masking emails, API keys and bearer tokens.
+    #[must_use]
+    pub fn pii() -> Self {
+        Self::new(infra_json::Masker::pii())
+    }
+
+    /// Also masks completion contents.
+    #[must_use]
+    pub fn with_responses(mut self, enabled: bool) -> Self {
+        self.responses = enabled;
+        self
+    }
+}
+
+#[cfg(feature = "redact")]
+#[async_trait]
+impl ProviderMiddleware for RedactionMiddleware {
+    async fn before(&self, _ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        for message in &mut request.messages {
+            message.content = self.masker.mask_str(&message.content);
+        }
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        _ctx: &CallContext<'_>,
+        _request: &LlmRequest,
+        response: &mut LlmResponse,
+    ) -> Result<()> {
+        if self.responses {
+            response.content = self.masker.mask_str(&response.content);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "redaction"
+    }
+}
+
+/// Middleware emitting infra-audit LLM events.
+///
+/// Records a prompt-submitted event per request and a completion-returned
+/// event per response or failure. Audit failures are logged and never fail
+/// the request.
+#[cfg(feature = "audit")]
+#[derive(Clone, Default)]
+pub struct AuditMiddleware {
+    logger: Option<Arc<infra_audit::AuditLogger>>,
+}
+
+#[cfg(feature = "audit")]
+impl std::fmt::Debug for AuditMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditMiddleware")
+            .field("global", &self.logger.is_none())
+            .finish()
+    }
+}
+
+#[cfg(feature = "audit")]
+impl AuditMiddleware {
+    /// Creates a middleware logging to the global audit logger.
+    #[must_use]
+    pub fn global() -> Self {
+        Self { logger: None }
+    }
+
+    /// Creates a middleware logging to a specific audit logger.
+    #[must_use]
+    pub fn new(logger: Arc<infra_audit::AuditLogger>) -> Self {
+        Self {
+            logger: Some(logger),
+        }
+    }
+
+    pub(crate) async fn log(&self, event: infra_audit::AuditEvent) {
+        let result = match &self.logger {
+            Some(logger) => logger.log(event).await,
+            None => infra_audit::log(event).await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(error = %e, "Failed to write LLM audit event");
+        }
+    }
+}
+
+#[cfg(feature = "audit")]
+#[async_trait]
+impl ProviderMiddleware for AuditMiddleware {
+    async fn before(&self, ctx: &CallContext<'_>, request: &mut LlmRequest) -> Result<()> {
+        let event = infra_audit::LlmEventBuilder::prompt_submitted(&request.model, ctx.provider)
+            .prompt_tokens(prompt_tokens(request) as u64)
+            .build();
+        self.log(event).await;
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        ctx: &CallContext<'_>,
+        _request: &LlmRequest,
+        response: &mut LlmResponse,
+    ) -> Result<()> {
+        let mut event =
+            infra_audit::LlmEventBuilder::completion_returned(&response.model, ctx.provider)
+                .latency(ctx.elapsed());
+        if let Some(usage) = response.usage {
+            event = event.tokens(
+                u64::from(usage.prompt_tokens),
+                u64::from(usage.completion_tokens),
+            );
+        }
+        if let Some(reason) = &response.finish_reason {
+            event = event.finish_reason(reason.clone());
+        }
+        self.log(event.build()).await;
+        Ok(())
+    }
+
+    async fn on_error(&self, ctx: &CallContext<'_>, request: &LlmRequest, error: &LlmClientError) {
+        let event = infra_audit::LlmEventBuilder::completion_returned(&request.model, ctx.provider)
+            .outcome(infra_audit::Outcome::Failure)
+            .latency(ctx.elapsed())
+            .error(error.to_string())
+            .build();
+        self.log(event).await;
+    }
+
+    fn name(&self) -> &str {
+        "audit"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Usage;
+    use std::sync::Mutex;
+
+    struct EchoProvider;
+
+    #[async_trait]
+    impl LlmProvider for EchoProvider {
+        async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+            let content = request
+                .messages
+                .iter()
+                .map(|m| m.content.as_str())
+                .collect::<Vec<_>>()
+                .join("|");
+            Ok(LlmResponse {
+                content,
+                model: request.model,
+                finish_reason: Some("stop".to_string()),
+                usage: Some(Usage {
+                    prompt_tokens: 10,
+                    completion_tokens: 5,
+                    total_tokens: 15,
+                }),
+                tool_calls: Vec::new(),
+            })
+        }
+
+        async fn stream(
+            &self,
+            _request: LlmRequest,
+        ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+            Ok(Box::pin(futures::stream::empty()))
+        }
+
+        async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+            Ok(EmbeddingResponse {
+                model: request.model,
+                embeddings: Vec::new(),
+                usage: None,
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "echo"
+        }
+    }
+
+    /// Records the order in which hooks run.
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ProviderMiddleware for Recorder {
+        async fn before(&self, _ctx: &CallContext<'_>, _request: &mut LlmRequest) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("before:{}", self.name));
+            Ok(())
+        }
+
+        async fn after(
+            &self,
+            _ctx: &CallContext<'_>,
+            _request: &LlmRequest,
+            _response: &mut LlmResponse,
+        ) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("after:{}", self.name));
+            Ok(())
+        }
+
+        async fn on_error(
+            &self,
+            _ctx: &CallContext<'_>,
+            _request: &LlmRequest,
+            _error: &LlmClientError,
+        ) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("error:{}", self.name));
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn request(content: &str) -> LlmRequest {
+        LlmRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hook_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = MiddlewareProvider::new(EchoProvider)
+            .with(Recorder {
+                name: "a",
+                log: log.clone(),
+            })
+            .with(Recorder {
+                name: "b",
+                log: log.clone(),
+            });
+        assert_eq!(provider.middleware_names(), vec!["a", "b"]);
+
+        provider.complete(request("hi")).await.unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["before:a", "before:b", "after:b", "after:a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_template_and_mutation() {
+        let provider = MiddlewareProvider::new(EchoProvider)
+            .with(
+                TemplateMiddleware::new()
+                    .with_var("user", "Ada")
+                    .with_system_prompt("You are helping {{user}}."),
+            )
+            .with(MapRequestMiddleware::new(|request: &mut LlmRequest| {
+                request.model = "gpt-4o-mini".to_string();
+            }));
+
+        let response = provider
+            .complete(request("Hi, I'm {{user}} ({{unknown}})"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.content,
+            "You are helping Ada.|Hi, I'm {{user}} ({{unknown}})"
+        );
+        assert_eq!(response.model, "gpt-4o-mini");
+    }
+
+    #[tokio::test]
+    async fn test_guardrail_rejects_before_sending() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = MiddlewareProvider::new(EchoProvider)
+            .with(Recorder {
+                name: "a",
+                log: log.clone(),
+            })
+            .with(ContextLimitMiddleware);
+
+        let mut oversized = request("hi");
+        oversized.max_tokens = Some(1_000_000);
+        let err = provider.complete(oversized.clone()).await.unwrap_err();
+        assert!(matches!(err, LlmClientError::InvalidRequest(_)));
+        assert!(provider.stream(oversized).await.is_err());
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["before:a", "error:a", "before:a", "error:a"]
+        );
+
+        assert!(provider.complete(request("hi")).await.is_ok());
+    }
+
+    #[cfg(feature = "redact")]
+    #[tokio::test]
+    async fn test_redaction() {
+        let provider = MiddlewareProvider::new(EchoProvider).with(RedactionMiddleware::pii());
+        let response = provider
+            .complete(request("Contact ada@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Contact [REDACTED]");
+    }
+
+    #[cfg(feature = "audit")]
+    #[tokio::test]
+    async fn test_audit_events() {
+        use infra_audit::{AuditLogger, LlmEventData, LlmEventKind, MemorySink};
+
+        let sink = Arc::new(MemorySink::new());
+        let logger = Arc::new(AuditLogger::new(sink.clone()));
+        let provider = MiddlewareProvider::new(EchoProvider).with(AuditMiddleware::new(logger));
+
+        provider.complete(request("hi")).await.unwrap();
+        let events = sink.events().await;
+        assert_eq!(events.len(), 2);
+
+        let (kind, data) = LlmEventData::from_event(&events[0]).unwrap();
+        assert_eq!(kind, LlmEventKind::PromptSubmitted);
+        assert_eq!(data.provider.as_deref(), Some("echo"));
+
+        let (kind, data) = LlmEventData::from_event(&events[1]).unwrap();
+        assert_eq!(kind, LlmEventKind::CompletionReturned);
+        assert_eq!(data.total_tokens(), Some(15));
+        assert_eq!(data.finish_reason.as_deref(), Some("stop"));
+    }
+}
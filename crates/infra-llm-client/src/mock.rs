@@ -0,0 +1,400 @@
+//! Deterministic mock provider for tests and simulations.
+//!
+//! [`MockProvider`] answers from a script of queued replies, falling back to
+//! a response template. Latency and failures can be injected through an
+//! infra-sim [`ChaosInjector`], and every request is recorded for assertions.
+
+use async_trait::async_trait;
+use futures::Stream;
+use infra_sim::ChaosInjector;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::{LlmClientError, Result};
+use crate::provider::LlmProvider;
+use crate::tokenizer::{EstimatingTokenizer, Tokenizer};
+use crate::types::{
+    Embedding, EmbeddingInput, EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, Role,
+    StreamChunk, Usage,
+};
+
+/// Template used when no scripted reply is queued.
+pub const DEFAULT_TEMPLATE: &str = "Mock response to: {{last_message}}";
+
+/// A scripted reply.
+#[derive(Debug)]
+enum Reply {
+    Content(String),
+    Error(LlmClientError),
+}
+
+/// Scripted, deterministic [`LlmProvider`] implementation.
+///
+/// Templates may reference `{{model}}` and `{{last_message}}` (the content
+/// of the last user message).
+pub struct MockProvider {
+    replies: Mutex<VecDeque<Reply>>,
+    template: String,
+    latency: Option<Duration>,
+    chaos: Option<ChaosInjector>,
+    embedding_dimensions: usize,
+    requests: Mutex<Vec<LlmRequest>>,
+    embedding_requests: Mutex<Vec<EmbeddingRequest>>,
+}
+
+impl std::fmt::Debug for MockProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockProvider")
+            .field("template", &self.template)
+            .field("latency", &self.latency)
+            .field("chaos", &self.chaos.is_some())
+            .field("embedding_dimensions", &self.embedding_dimensions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockProvider {
+    /// Creates a mock provider answering with [`DEFAULT_TEMPLATE`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            replies: Mutex::new(VecDeque::new()),
+            template: DEFAULT_TEMPLATE.to_string(),
+            latency: None,
+            chaos: None,
+            embedding_dimensions: 8,
+            requests: Mutex::new(Vec::new()),
+            embedding_requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Sets the template used when no scripted reply is queued.
+    #[must_use]
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Delays every call by a fixed duration.
+    #[must_use]
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Injects latency and failures from a chaos injector.
+    ///
+    /// Injected failures surface as retryable [`LlmClientError::ProviderError`]s.
+    #[must_use]
+    pub fn with_chaos(mut self, chaos: ChaosInjector) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Sets the dimensionality of generated embeddings.
+    #[must_use]
+    pub fn with_embedding_dimensions(mut self, dimensions: usize) -> Self {
+        self.embedding_dimensions = dimensions;
+        self
+    }
+
+    /// Queues a reply with the given content.
+    #[must_use]
+    pub fn with_response(self, content: impl Into<String>) -> Self {
+        self.push_response(content);
+        self
+    }
+
+    /// Queues a reply with the given content.
+    pub fn push_response(&self, content: impl Into<String>) {
+        lock(&self.replies).push_back(Reply::Content(content.into()));
+    }
+
+    /// Queues an error reply.
+    pub fn push_error(&self, error: LlmClientError) {
+        lock(&self.replies).push_back(Reply::Error(error));
+    }
+
+    /// Returns all completion and streaming requests received so far.
+    pub fn requests(&self) -> Vec<LlmRequest> {
+        lock(&self.requests).clone()
+    }
+
+    /// Returns the most recent completion or streaming request.
+    pub fn last_request(&self) -> Option<LlmRequest> {
+        lock(&self.requests).last().cloned()
+    }
+
+    /// Returns all embedding requests received so far.
+    pub fn embedding_requests(&self) -> Vec<EmbeddingRequest> {
+        lock(&self.embedding_requests).clone()
+    }
+
+    /// Returns the total number of requests received.
+    pub fn call_count(&self) -> usize {
+        lock(&self.requests).len() + lock(&self.embedding_requests).len()
+    }
+
+    /// Clears recorded requests and queued replies.
+    pub fn reset(&self) {
+        lock(&self.replies).clear();
+        lock(&self.requests).clear();
+        lock(&self.embedding_requests).clear();
+    }
+
+    async fn disturb(&self) -> Result<()> {
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+        if let Some(chaos) = &self.chaos {
+            chaos
+                .apply_async(())
+                .await
+                .map_err(LlmClientError::ProviderError)?;
+        }
+        Ok(())
+    }
+
+    fn reply(&self, request: &LlmRequest) -> Result<LlmResponse> {
+        let content = match lock(&self.replies).pop_front() {
+            Some(Reply::Content(content)) => content,
+            Some(Reply::Error(error)) => return Err(error),
+            None => self.render(request),
+        };
+
+        let tokenizer = EstimatingTokenizer::for_model(&request.model);
+        let prompt_tokens = count(tokenizer.count_messages(&request.messages));
+        let completion_tokens = count(tokenizer.count_tokens(&content));
+        Ok(LlmResponse {
+            content,
+            model: request.model.clone(),
+            finish_reason: Some("stop".to_string()),
+            usage: Some(Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+            }),
+            tool_calls: Vec::new(),
+        })
+    }
+
+    fn render(&self, request: &LlmRequest) -> String {
+        let last_message = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User)
+            .map_or("", |m| m.content.as_str());
+        self.template
+            .replace("{{model}}", &request.model)
+            .replace("{{last_message}}", last_message)
+    }
+
+    fn embedding(&self, text: &str) -> Vec<f32> {
+        // SHA-256-seeded values in [-1, 1], stable across runs and releases
+        (0..self.embedding_dimensions)
+            .map(|i| {
+                let digest = Sha256::new()
+                    .chain_update(text.as_bytes())
+                    .chain_update((i as u64).to_le_bytes())
+                    .finalize();
+                let mut seed = [0u8; 8];
+                seed.copy_from_slice(&digest[..8]);
+                #[allow(clippy::cast_precision_loss)]
+                let unit = (u64::from_le_bytes(seed) >> 11) as f64 / (1u64 << 53) as f64;
+                #[allow(clippy::cast_possible_truncation)]
+                let value = (unit * 2.0 - 1.0) as f32;
+                value
+            })
+            .collect()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn count(tokens: usize) -> u32 {
+    u32::try_from(tokens).unwrap_or(u32::MAX)
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        lock(&self.requests).push(request.clone());
+        self.disturb().await?;
+        self.reply(&request)
+    }
+
+    async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        lock(&self.requests).push(request.clone());
+        self.disturb().await?;
+        let response = self.reply(&request)?;
+
+        // One chunk per word, with usage and finish reason on the last; an
+        // empty reply still yields that final chunk
+        let mut words: Vec<String> = response
+            .content
+            .split_inclusive(' ')
+            .map(str::to_string)
+            .collect();
+        if words.is_empty() {
+            words.push(String::new());
+        }
+        let last = words.len().saturating_sub(1);
+        let chunks: Vec<Result<StreamChunk>> = words
+            .into_iter()
+            .enumerate()
+            .map(|(i, content)| {
+                Ok(StreamChunk {
+                    content,
+                    model: response.model.clone(),
+                    finish_reason: (i == last).then(|| "stop".to_string()),
+                    usage: if i == last { response.usage } else { None },
+                    tool_calls: Vec::new(),
+                })
+            })
+            .collect();
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        lock(&self.embedding_requests).push(request.clone());
+        self.disturb().await?;
+
+        let inputs = match &request.input {
+            EmbeddingInput::Single(text) => vec![text.as_str()],
+            EmbeddingInput::Multiple(texts) => texts.iter().map(String::as_str).collect(),
+        };
+        let tokenizer = EstimatingTokenizer::for_model(&request.model);
+        let prompt_tokens = count(inputs.iter().map(|t| tokenizer.count_tokens(t)).sum());
+
+        Ok(EmbeddingResponse {
+            model: request.model.clone(),
+            embeddings: inputs
+                .iter()
+                .enumerate()
+                .map(|(index, text)| Embedding {
+                    embedding: self.embedding(text),
+                    index,
+                })
+                .collect(),
+            usage: Some(Usage {
+                prompt_tokens,
+                completion_tokens: 0,
+                total_tokens: prompt_tokens,
+            }),
+        })
+    }
+
+    fn provider_name(&self) -> &str {
+        "mock"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Message;
+    use futures::StreamExt;
+    use infra_sim::{ChaosConfig, ChaosMode};
+
+    fn request(content: &str) -> LlmRequest {
+        LlmRequest {
+            model: "mock-1".to_string(),
+            messages: vec![Message {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scripted_then_template() {
+        let provider = MockProvider::new()
+            .with_template("[{{model}}] {{last_message}}")
+            .with_response("first");
+        provider.push_error(LlmClientError::RateLimitExceeded("slow down".to_string()));
+
+        assert_eq!(
+            provider.complete(request("a")).await.unwrap().content,
+            "first"
+        );
+        let err = provider.complete(request("b")).await.unwrap_err();
+        assert!(err.is_retryable());
+        assert_eq!(
+            provider.complete(request("c")).await.unwrap().content,
+            "[mock-1] c"
+        );
+
+        assert_eq!(provider.call_count(), 3);
+        assert_eq!(provider.last_request().unwrap().messages[0].content, "c");
+    }
+
+    #[tokio::test]
+    async fn test_stream_and_embed() {
+        let provider = MockProvider::new().with_response("hello mock world");
+        let chunks: Vec<_> = provider
+            .stream(request("hi"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[2].as_ref().unwrap().usage.is_some());
+
+        provider.push_response("");
+        let chunks: Vec<_> = provider
+            .stream(request("hi"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        let last = chunks[0].as_ref().unwrap();
+        assert_eq!(last.content, "");
+        assert_eq!(last.finish_reason.as_deref(), Some("stop"));
+
+        let embed = |text: &str| EmbeddingRequest {
+            model: "mock-embed".to_string(),
+            input: EmbeddingInput::Single(text.to_string()),
+        };
+        let a = provider.embed(embed("same")).await.unwrap();
+        let b = provider.embed(embed("same")).await.unwrap();
+        assert_eq!(a.embeddings[0].embedding, b.embeddings[0].embedding);
+        assert_eq!(a.embeddings[0].embedding.len(), 8);
+        let c = provider.embed(embed("other")).await.unwrap();
+        assert_ne!(a.embeddings[0].embedding, c.embeddings[0].embedding);
+    }
+
+    #[tokio::test]
+    async fn test_chaos_failure() {
+        let provider = MockProvider::new()
+            .with_chaos(ChaosInjector::new(ChaosConfig::new(ChaosMode::AlwaysFail)));
+
+        let err = provider.complete(request("hi")).await.unwrap_err();
+        assert!(matches!(err, LlmClientError::ProviderError(_)));
+        assert_eq!(provider.requests().len(), 1);
+    }
+}
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use futures::Stream;
 use std::pin::Pin;
 
+use crate::batch::{self, BatchOptions, BatchResult};
 use crate::error::Result;
 use crate::types::{EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, StreamChunk};
 
@@ -64,6 +65,38 @@ pub trait LlmProvider: Send + Sync {
     /// or if authentication/authorization fails.
     async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse>;
 
+    /// Performs a batch of completion requests.
+    ///
+    /// The default implementation sends the requests concurrently, bounded
+    /// by `options`. Providers with a native batch API may override this.
+    ///
+    /// # Returns
+    ///
+    /// Returns one result per request, in request order.
+    async fn complete_batch(
+        &self,
+        requests: Vec<LlmRequest>,
+        options: &BatchOptions,
+    ) -> BatchResult<LlmResponse> {
+        batch::complete_concurrent(self, requests, options).await
+    }
+
+    /// Performs a batch of embedding requests.
+    ///
+    /// The default implementation sends the requests concurrently, bounded
+    /// by `options`. Providers with a native batch API may override this.
+    ///
+    /// # Returns
+    ///
+    /// Returns one result per request, in request order.
+    async fn embed_batch(
+        &self,
+        requests: Vec<EmbeddingRequest>,
+        options: &BatchOptions,
+    ) -> BatchResult<EmbeddingResponse> {
+        batch::embed_concurrent(self, requests, options).await
+    }
+
     /// Returns the name of this provider (e.g., "openai", "anthropic").
     fn provider_name(&self) -> &str;
 }
@@ -0,0 +1,78 @@
+//! Incremental Server-Sent Events decoding for streaming responses.
+
+/// A decoded server-sent event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SseEvent {
+    /// The event type, if an `event:` field was present.
+    pub event: Option<String>,
+    /// The event data, with multiple `data:` lines joined by newlines.
+    pub data: String,
+}
+
+/// Decoder turning arbitrary byte chunks into complete events.
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Creates an empty decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a chunk of bytes and returns every event completed by it.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend(chunk.iter().filter(|b| **b != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(event) = Self::parse_block(&String::from_utf8_lossy(&block)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn parse_block(block: &str) -> Option<SseEvent> {
+        let mut event = None;
+        let mut data: Option<String> = None;
+
+        for line in block.lines() {
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => event = Some(value.to_string()),
+                "data" => match &mut data {
+                    Some(existing) => {
+                        existing.push('\n');
+                        existing.push_str(value);
+                    }
+                    None => data = Some(value.to_string()),
+                },
+                // Comments (empty field name), ids and retry hints are ignored
+                _ => {}
+            }
+        }
+
+        data.map(|data| SseEvent { event, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_handles_split_chunks() {
+        let mut decoder = SseDecoder::new();
+        assert_eq!(decoder.push(b"data: {\"a\""), Vec::new());
+        let events = decoder.push(b":1}\r\n\r\n: keep-alive\n\nevent: done\ndata: [DONE]\n\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data, "{\"a\":1}");
+        assert_eq!(events[1].event.as_deref(), Some("done"));
+        assert_eq!(events[1].data, "[DONE]");
+    }
+}
@@ -0,0 +1,326 @@
+//! Aggregation of streaming responses.
+//!
+//! [`StreamAccumulator`] assembles [`StreamChunk`]s into a complete
+//! [`LlmResponse`] as they arrive, joining partial tool calls and tracking
+//! token counts. [`collect_stream`] drains a stream into a response, and
+//! [`text_deltas`] reduces a stream to its non-empty text fragments.
+
+use futures::{Stream, StreamExt};
+
+use crate::error::{LlmClientError, Result};
+use crate::tokenizer::{EstimatingTokenizer, Tokenizer};
+use crate::types::{LlmResponse, StreamChunk, ToolCall, Usage};
+
+/// Most tool calls a streamed response may carry.
+///
+/// Tool call deltas are placed by their index, which comes from the provider,
+/// so it is capped before allocating.
+pub const MAX_STREAMED_TOOL_CALLS: usize = 128;
+
+/// Incrementally assembles a streamed response.
+#[derive(Debug, Clone, Default)]
+pub struct StreamAccumulator {
+    content: String,
+    model: String,
+    finish_reason: Option<String>,
+    usage: Option<Usage>,
+    tool_calls: Vec<ToolCall>,
+    chunks: usize,
+}
+
+impl StreamAccumulator {
+    /// Creates an empty accumulator.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a chunk, returning its text delta.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LlmClientError::InvalidResponse`] if a tool call delta has
+    /// an index of [`MAX_STREAMED_TOOL_CALLS`] or more; the chunk is then
+    /// ignored.
+    pub fn push(&mut self, chunk: &StreamChunk) -> Result<&str> {
+        if let Some(delta) = chunk
+            .tool_calls
+            .iter()
+            .find(|delta| delta.index >= MAX_STREAMED_TOOL_CALLS)
+        {
+            return Err(LlmClientError::InvalidResponse(format!(
+                "Tool call index {} exceeds the limit of {MAX_STREAMED_TOOL_CALLS} tool calls",
+                delta.index
+            )));
+        }
+
+        self.chunks += 1;
+        if self.model.is_empty() {
+            self.model.clone_from(&chunk.model);
+        }
+        if chunk.finish_reason.is_some() {
+            self.finish_reason.clone_from(&chunk.finish_reason);
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        for delta in &chunk.tool_calls {
+            if self.tool_calls.len() <= delta.index {
+                self.tool_calls.resize_with(delta.index + 1, || ToolCall {
+                    id: String::new(),
+                    name: String::new(),
+                    arguments: String::new(),
+                });
+            }
+            let call = &mut self.tool_calls[delta.index];
+            if let Some(id) = &delta.id {
+                call.id.clone_from(id);
+            }
+            if let Some(name) = &delta.name {
+                call.name.push_str(name);
+            }
+            call.arguments.push_str(&delta.arguments);
+        }
+
+        let start = self.content.len();
+        self.content.push_str(&chunk.content);
+        Ok(&self.content[start..])
+    }
+
+    /// Returns the text received so far.
+    #[must_use]
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Returns the tool calls assembled so far.
+    ///
+    /// Arguments of the last call may still be incomplete JSON until the
+    /// stream finishes.
+    #[must_use]
+    pub fn tool_calls(&self) -> &[ToolCall] {
+        &self.tool_calls
+    }
+
+    /// Returns the finish reason, once the final chunk has arrived.
+    #[must_use]
+    pub fn finish_reason(&self) -> Option<&str> {
+        self.finish_reason.as_deref()
+    }
+
+    /// Returns `true` once a chunk carrying a finish reason has arrived.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finish_reason.is_some()
+    }
+
+    /// Returns the usage reported by the provider, if any.
+    #[must_use]
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+
+    /// Returns the number of chunks received.
+    #[must_use]
+    pub fn chunk_count(&self) -> usize {
+        self.chunks
+    }
+
+    /// Returns the completion token count so far.
+    ///
+    /// Uses the provider's count once reported, and an offline estimate of
+    /// the received text and tool calls until then.
+    #[must_use]
+    pub fn completion_tokens(&self) -> u32 {
+        if let Some(usage) = self.usage {
+            return usage.completion_tokens;
+        }
+        let tokenizer = EstimatingTokenizer::for_model(&self.model);
+        let tokens = tokenizer.count_tokens(&self.content)
+            + self
+                .tool_calls
+                .iter()
+                .map(|c| tokenizer.count_tokens(&c.name) + tokenizer.count_tokens(&c.arguments))
+                .sum::<usize>();
+        u32::try_from(tokens).unwrap_or(u32::MAX)
+    }
+
+    /// Converts the accumulated chunks into a response.
+    #[must_use]
+    pub fn into_response(self) -> LlmResponse {
+        LlmResponse {
+            content: self.content,
+            model: self.model,
+            finish_reason: self.finish_reason,
+            usage: self.usage,
+            tool_calls: self.tool_calls,
+        }
+    }
+}
+
+/// Drains a stream into a single response.
+///
+/// # Errors
+///
+/// Returns the first error yielded by the stream or raised by
+/// [`StreamAccumulator::push`].
+pub async fn collect_stream<S>(stream: S) -> Result<LlmResponse>
+where
+    S: Stream<Item = Result<StreamChunk>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut accumulator = StreamAccumulator::new();
+    while let Some(chunk) = stream.next().await {
+        accumulator.push(&chunk?)?;
+    }
+    Ok(accumulator.into_response())
+}
+
+/// Reduces a stream to its text fragments, skipping chunks without text.
+///
+/// Errors are passed through so callers can surface them.
+pub fn text_deltas<S>(stream: S) -> impl Stream<Item = Result<String>>
+where
+    S: Stream<Item = Result<StreamChunk>>,
+{
+    stream.filter_map(|chunk| async move {
+        match chunk {
+            Ok(chunk) if chunk.content.is_empty() => None,
+            Ok(chunk) => Some(Ok(chunk.content)),
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LlmClientError;
+    use crate::types::ToolCallDelta;
+
+    fn chunk(content: &str) -> StreamChunk {
+        StreamChunk {
+            content: content.to_string(),
+            model: "gpt-4o".to_string(),
+            finish_reason: None,
+            usage: None,
+            tool_calls: Vec::new(),
+        }
+    }
+
+    fn tool_delta(index: usize, name: Option<&str>, arguments: &str) -> StreamChunk {
+        StreamChunk {
+            tool_calls: vec![ToolCallDelta {
+                index,
+                id: name.map(|n| format!("call_{n}")),
+                name: name.map(str::to_string),
+                arguments: arguments.to_string(),
+            }],
+            ..chunk("")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_stream() {
+        let mut last = chunk("!");
+        last.finish_reason = Some("stop".to_string());
+        last.usage = Some(Usage {
+            prompt_tokens: 4,
+            completion_tokens: 3,
+            total_tokens: 7,
+        });
+        let chunks = vec![Ok(chunk("Hel")), Ok(chunk("lo")), Ok(last)];
+
+        let response = collect_stream(futures::stream::iter(chunks)).await.unwrap();
+        assert_eq!(response.content, "Hello!");
+        assert_eq!(response.model, "gpt-4o");
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(response.usage.unwrap().total_tokens, 7);
+
+        let failing = vec![
+            Ok(chunk("Hel")),
+            Err(LlmClientError::NetworkError("reset".to_string())),
+        ];
+        assert!(collect_stream(futures::stream::iter(failing))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_partial_tool_calls() {
+        let mut accumulator = StreamAccumulator::new();
+        for chunk in [
+            tool_delta(0, Some("get_weather"), ""),
+            tool_delta(0, None, "{\"city\":"),
+            tool_delta(1, Some("get_time"), "{}"),
+            tool_delta(0, None, "\"Paris\"}"),
+        ] {
+            assert_eq!(accumulator.push(&chunk).unwrap(), "");
+        }
+
+        let calls = accumulator.tool_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_get_weather");
+        assert_eq!(calls[0].arguments, "{\"city\":\"Paris\"}");
+        let args: serde_json::Value = calls[0].parse_arguments().unwrap();
+        assert_eq!(args["city"], "Paris");
+        assert_eq!(calls[1].name, "get_time");
+    }
+
+    #[test]
+    fn test_tool_call_index_limit() {
+        let mut accumulator = StreamAccumulator::new();
+        let err = accumulator
+            .push(&tool_delta(MAX_STREAMED_TOOL_CALLS, Some("huge"), "{}"))
+            .unwrap_err();
+        assert!(matches!(err, LlmClientError::InvalidResponse(_)));
+        assert!(accumulator.tool_calls().is_empty());
+        assert_eq!(accumulator.chunk_count(), 0);
+
+        accumulator
+            .push(&tool_delta(MAX_STREAMED_TOOL_CALLS - 1, Some("last"), "{}"))
+            .unwrap();
+        assert_eq!(accumulator.tool_calls().len(), MAX_STREAMED_TOOL_CALLS);
+    }
+
+    #[test]
+    fn test_token_tracking() {
+        let mut accumulator = StreamAccumulator::new();
+        assert_eq!(accumulator.completion_tokens(), 0);
+        assert_eq!(
+            accumulator.push(&chunk("Hello there, ")).unwrap(),
+            "Hello there, "
+        );
+        accumulator.push(&chunk("how are you today?")).unwrap();
+        let estimate = accumulator.completion_tokens();
+        assert!(estimate > 0);
+        assert_eq!(accumulator.chunk_count(), 2);
+        assert!(!accumulator.is_finished());
+
+        let mut last = chunk("");
+        last.finish_reason = Some("stop".to_string());
+        last.usage = Some(Usage {
+            prompt_tokens: 1,
+            completion_tokens: 42,
+            total_tokens: 43,
+        });
+        accumulator.push(&last).unwrap();
+        assert_eq!(accumulator.completion_tokens(), 42);
+        assert!(accumulator.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_text_deltas() {
+        let chunks = vec![
+            Ok(chunk("Hi")),
+            Ok(tool_delta(0, Some("noop"), "{}")),
+            Ok(chunk(" there")),
+        ];
+        let deltas: Vec<String> = text_deltas(futures::stream::iter(chunks))
+            .map(|d| d.unwrap())
+            .collect()
+            .await;
+        assert_eq!(deltas, vec!["Hi", " there"]);
+    }
+}
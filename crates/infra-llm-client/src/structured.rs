@@ -0,0 +1,197 @@
+//! Structured output enforcement.
+//!
+//! [`StructuredOutputProvider`] wraps any provider and, for requests with a
+//! JSON [`ResponseFormat`], parses (and optionally repairs) the response with
+//! infra-json and validates it against the schema with infra-schema.
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde_json::Value;
+use std::pin::Pin;
+
+use crate::error::{LlmClientError, Result};
+use crate::provider::LlmProvider;
+use crate::types::{
+    EmbeddingRequest, EmbeddingResponse, LlmRequest, LlmResponse, ResponseFormat, StreamChunk,
+};
+
+/// Parses response content according to a response format.
+///
+/// With `repair` set, fenced, truncated or trailing-comma JSON is repaired
+/// before parsing. Returns `Value::String` for [`ResponseFormat::Text`].
+///
+/// # Errors
+///
+/// Returns [`LlmClientError::InvalidResponse`] if the content is not JSON or
+/// does not match the schema.
+pub fn parse_structured(format: &ResponseFormat, content: &str, repair: bool) -> Result<Value> {
+    if !format.is_json() {
+        return Ok(Value::String(content.to_string()));
+    }
+
+    let parsed = if repair {
+        infra_json::repair(content)
+    } else {
+        infra_json::Json::parse(content)
+    };
+    let value = parsed
+        .map_err(|e| LlmClientError::InvalidResponse(format!("response is not JSON: {e}")))?
+        .into_inner();
+
+    if let Some(schema) = format.schema() {
+        let result = infra_schema::validate(schema, &value)?;
+        if !result.is_valid() {
+            let errors: Vec<String> = result.errors().iter().map(ToString::to_string).collect();
+            return Err(LlmClientError::InvalidResponse(format!(
+                "response does not match schema: {}",
+                errors.join("; ")
+            )));
+        }
+    }
+
+    Ok(value)
+}
+
+/// Provider decorator enforcing [`LlmRequest::response_format`].
+///
+/// Successful completions have their content replaced by the canonical
+/// (compact) JSON. Streams are passed through unchecked.
+#[derive(Debug, Clone)]
+pub struct StructuredOutputProvider<P> {
+    inner: P,
+    repair: bool,
+}
+
+impl<P: LlmProvider> StructuredOutputProvider<P> {
+    /// Wraps a provider, repairing malformed JSON by default.
+    #[must_use]
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            repair: true,
+        }
+    }
+
+    /// Sets whether malformed JSON is repaired before validation.
+    #[must_use]
+    pub fn with_repair(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider> LlmProvider for StructuredOutputProvider<P> {
+    async fn complete(&self, request: LlmRequest) -> Result<LlmResponse> {
+        let format = request.response_format.clone();
+        let mut response = self.inner.complete(request).await?;
+
+        if let Some(format) = format.filter(ResponseFormat::is_json) {
+            response.content =
+                parse_structured(&format, &response.content, self.repair)?.to_string();
+        }
+        Ok(response)
+    }
+
+    async fn stream(
+        &self,
+        request: LlmRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>> {
+        self.inner.stream(request).await
+    }
+
+    async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.inner.embed(request).await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn person_format() -> ResponseFormat {
+        ResponseFormat::json_schema(
+            "person",
+            json!({
+                "type": "object",
+                "properties": { "name": { "type": "string" }, "age": { "type": "integer" } },
+                "required": ["name", "age"]
+            }),
+        )
+    }
+
+    #[test]
+    fn test_parse_structured_repairs_and_validates() {
+        let format = person_format();
+
+        let value = parse_structured(
+            &format,
+            "```json\n{\"name\": \"Ada\", \"age\": 36,}\n```",
+            true,
+        )
+        .unwrap();
+        assert_eq!(value, json!({ "name": "Ada", "age": 36 }));
+
+        assert!(parse_structured(&format, "{\"name\": \"Ada\", \"age\": 36,}", false).is_err());
+        let err = parse_structured(&format, "{\"name\": \"Ada\"}", true).unwrap_err();
+        assert!(matches!(err, LlmClientError::InvalidResponse(_)));
+    }
+
+    #[test]
+    fn test_strict_schema_rejects_unknown_properties() {
+        let format = ResponseFormat::json_schema(
+            "team",
+            json!({
+                "type": "object",
+                "properties": {
+                    "lead": { "$ref": "#/$defs/person" },
+                    "members": { "type": "array", "items": { "$ref": "#/$defs/person" } }
+                },
+                "required": ["lead", "members"],
+                "$defs": {
+                    "person": {
+                        "type": "object",
+                        "properties": { "name": { "type": "string" } },
+                        "required": ["name"]
+                    }
+                }
+            }),
+        );
+
+        let schema = format.schema().unwrap();
+        assert_eq!(schema["additionalProperties"], json!(false));
+        assert_eq!(
+            schema["$defs"]["person"]["additionalProperties"],
+            json!(false)
+        );
+
+        let valid = r#"{"lead": {"name": "Ada"}, "members": [{"name": "Grace"}]}"#;
+        assert!(parse_structured(&format, valid, false).is_ok());
+        for invalid in [
+            r#"{"lead": {"name": "Ada"}, "members": [], "extra": 1}"#,
+            r#"{"lead": {"name": "Ada", "age": 36}, "members": []}"#,
+            r#"{"lead": {"name": "Ada"}, "members": [{"name": "Grace", "age": 1}]}"#,
+        ] {
+            assert!(
+                parse_structured(&format, invalid, false).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_text_format_passes_through() {
+        let value = parse_structured(&ResponseFormat::Text, "plain", true).unwrap();
+        assert_eq!(value, Value::String("plain".to_string()));
+    }
+}
@@ -0,0 +1,295 @@
+//! Token counting and context-window management.
+//!
+//! Estimates are offline heuristics tuned per model family; they are meant
+//! for budgeting prompts before sending them, not for exact billing.
+
+use crate::types::{LlmRequest, Message, Role};
+
+/// Context window assumed for unrecognized models.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8192;
+
+/// Counts tokens in text and conversations.
+pub trait Tokenizer: Send + Sync {
+    /// Returns the number of tokens in `text`.
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Returns the number of tokens used by a message, including role overhead.
+    fn count_message(&self, message: &Message) -> usize {
+        self.count_tokens(&message.content) + 4
+    }
+
+    /// Returns the number of prompt tokens used by a conversation.
+    fn count_messages(&self, messages: &[Message]) -> usize {
+        // Every reply is primed with an assistant header
+        messages
+            .iter()
+            .map(|m| self.count_message(m))
+            .sum::<usize>()
+            + 3
+    }
+}
+
+/// A family of models sharing a tokenizer and naming scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelFamily {
+    /// OpenAI `GPT-4o`, `GPT-4.1` and o-series models (`o200k_base`).
+    Gpt4o,
+    /// OpenAI `GPT-4` and `GPT-3.5` models (`cl100k_base`).
+    Gpt4,
+    /// Anthropic Claude models.
+    Claude,
+    /// Meta Llama models.
+    Llama,
+    /// Mistral and Mixtral models.
+    Mistral,
+    /// Google Gemini models.
+    Gemini,
+    /// Anything else.
+    Unknown,
+}
+
+impl ModelFamily {
+    /// Detects the family from a model name.
+    #[must_use]
+    pub fn from_model(model: &str) -> Self {
+        let model = model.to_ascii_lowercase();
+        let name = model.rsplit('/').next().unwrap_or(&model);
+
+        if name.starts_with("gpt-4o")
+            || name.starts_with("gpt-4.1")
+            || name.starts_with("chatgpt-4o")
+            || (name.starts_with('o') && name[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            Self::Gpt4o
+        } else if name.starts_with("gpt-4") || name.starts_with("gpt-3.5") {
+            Self::Gpt4
+        } else if name.starts_with("claude") {
+            Self::Claude
+        } else if name.contains("llama") {
+            Self::Llama
+        } else if name.contains("mistral") || name.contains("mixtral") {
+            Self::Mistral
+        } else if name.starts_with("gemini") {
+            Self::Gemini
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Returns the average number of characters per token for English text.
+    #[must_use]
+    pub fn chars_per_token(self) -> f64 {
+        match self {
+            Self::Gpt4o => 4.2,
+            Self::Gpt4 | Self::Gemini => 4.0,
+            Self::Llama => 3.8,
+            Self::Claude | Self::Mistral => 3.5,
+            Self::Unknown => 3.2,
+        }
+    }
+}
+
+/// Returns the context window, in tokens, of a model.
+#[must_use]
+pub fn context_window(model: &str) -> usize {
+    let name = model.to_ascii_lowercase();
+    match ModelFamily::from_model(model) {
+        ModelFamily::Gpt4o if name.contains("gpt-4.1") => 1_047_576,
+        ModelFamily::Gpt4o if name.contains("gpt-4o") => 128_000,
+        ModelFamily::Gpt4o | ModelFamily::Claude => 200_000,
+        ModelFamily::Gpt4 if name.contains("turbo") && name.starts_with("gpt-4") => 128_000,
+        ModelFamily::Gpt4 if name.contains("32k") => 32_768,
+        ModelFamily::Gpt4 if name.starts_with("gpt-3.5") => 16_385,
+        ModelFamily::Llama if name.contains("llama-3.1") || name.contains("llama-3.2") => 128_000,
+        ModelFamily::Mistral => 32_768,
+        ModelFamily::Gemini => 1_048_576,
+        _ => DEFAULT_CONTEXT_WINDOW,
+    }
+}
+
+/// Offline token estimator based on character and word counts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimatingTokenizer {
+    chars_per_token: f64,
+}
+
+impl EstimatingTokenizer {
+    /// Creates an estimator with a fixed characters-per-token ratio.
+    #[must_use]
+    pub fn new(chars_per_token: f64) -> Self {
+        Self { chars_per_token }
+    }
+
+    /// Creates an estimator tuned for a model.
+    #[must_use]
+    pub fn for_model(model: &str) -> Self {
+        Self::new(ModelFamily::from_model(model).chars_per_token())
+    }
+}
+
+impl Tokenizer for EstimatingTokenizer {
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn count_tokens(&self, text: &str) -> usize {
+        if text.is_empty() {
+            return 0;
+        }
+        // Short words and punctuation-heavy text tokenize worse than the
+        // character ratio suggests, so take the larger of both estimates.
+        let by_chars = (text.chars().count() as f64 / self.chars_per_token).ceil() as usize;
+        let by_words = text.split_whitespace().count() * 4 / 3;
+        by_chars.max(by_words).max(1)
+    }
+}
+
+/// Returns the estimated number of prompt tokens in a request.
+#[must_use]
+pub fn prompt_tokens(request: &LlmRequest) -> usize {
+    EstimatingTokenizer::for_model(&request.model).count_messages(&request.messages)
+}
+
+/// Returns `true` if the request's prompt plus its `max_tokens` output
+/// reservation fits in the context window of `model`.
+#[must_use]
+pub fn fits_in_context(request: &LlmRequest, model: &str) -> bool {
+    let tokenizer = EstimatingTokenizer::for_model(model);
+    let reserved = request.max_tokens.map_or(0, |t| t as usize);
+    tokenizer.count_messages(&request.messages) + reserved <= context_window(model)
+}
+
+/// How to shorten a conversation that exceeds its token budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationStrategy {
+    /// Keep system messages and drop the oldest other messages first.
+    #[default]
+    DropOldest,
+    /// Keep system messages and the first exchange, dropping messages after
+    /// it until the conversation fits.
+    DropMiddle,
+}
+
+/// Shortens `messages` so they fit within `budget` tokens.
+///
+/// System messages and the final message are never dropped, so the result
+/// may still exceed the budget if they alone do.
+#[must_use]
+pub fn truncate_messages(
+    messages: &[Message],
+    budget: usize,
+    strategy: TruncationStrategy,
+    tokenizer: &dyn Tokenizer,
+) -> Vec<Message> {
+    let mut kept: Vec<bool> = vec![true; messages.len()];
+    let mut total = tokenizer.count_messages(messages);
+
+    let droppable: Vec<usize> = (0..messages.len().saturating_sub(1))
+        .filter(|&i| messages[i].role != Role::System)
+        .collect();
+    let order: Vec<usize> = match strategy {
+        TruncationStrategy::DropOldest => droppable,
+        // Skip the first user message and the reply to it
+        TruncationStrategy::DropMiddle => droppable.into_iter().skip(2).collect(),
+    };
+
+    for i in order {
+        if total <= budget {
+            break;
+        }
+        kept[i] = false;
+        total -= tokenizer.count_message(&messages[i]);
+    }
+
+    messages
+        .iter()
+        .zip(kept)
+        .filter(|(_, keep)| *keep)
+        .map(|(message, _)| message.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> Message {
+        Message {
+            role,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_model_family_and_window() {
+        assert_eq!(ModelFamily::from_model("gpt-4o-mini"), ModelFamily::Gpt4o);
+        assert_eq!(ModelFamily::from_model("o3-mini"), ModelFamily::Gpt4o);
+        assert_eq!(ModelFamily::from_model("gpt-3.5-turbo"), ModelFamily::Gpt4);
+        assert_eq!(
+            ModelFamily::from_model("meta-llama/Llama-3.1-8B-Instruct"),
+            ModelFamily::Llama
+        );
+        assert_eq!(context_window("gpt-4o"), 128_000);
+        assert_eq!(context_window("gpt-4"), 8192);
+        assert_eq!(context_window("claude-3-5-sonnet-20241022"), 200_000);
+        assert_eq!(context_window("my-local-model"), DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_fits_in_context() {
+        let mut request = LlmRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![message(Role::User, &"word ".repeat(1000))],
+            temperature: None,
+            max_tokens: Some(1000),
+            top_p: None,
+            n: None,
+            stream: None,
+            stop: None,
+            response_format: None,
+        };
+        assert!(fits_in_context(&request, "gpt-4"));
+
+        request.max_tokens = Some(8000);
+        assert!(!fits_in_context(&request, "gpt-4"));
+        assert!(fits_in_context(&request, "gpt-4o"));
+    }
+
+    #[test]
+    fn test_truncate_messages() {
+        let tokenizer = EstimatingTokenizer::new(4.0);
+        let long = |c: &str| c.repeat(400);
+        let messages = vec![
+            message(Role::System, "Be brief."),
+            message(Role::User, &long("a")),
+            message(Role::Assistant, &long("b")),
+            message(Role::User, &long("c")),
+            message(Role::Assistant, &long("d")),
+            message(Role::User, "And now?"),
+        ];
+        let budget = 250;
+
+        let oldest = truncate_messages(
+            &messages,
+            budget,
+            TruncationStrategy::DropOldest,
+            &tokenizer,
+        );
+        assert_eq!(oldest.len(), 4);
+        assert_eq!(oldest[0].role, Role::System);
+        assert_eq!(oldest[1].content, long("c"));
+        assert_eq!(oldest.last().unwrap().content, "And now?");
+        assert!(tokenizer.count_messages(&oldest) <= budget);
+
+        let middle = truncate_messages(
+            &messages,
+            budget,
+            TruncationStrategy::DropMiddle,
+            &tokenizer,
+        );
+        assert_eq!(middle.len(), 4);
+        assert_eq!(middle[1].content, long("a"));
+        assert_eq!(middle[2].content, long("b"));
+    }
+}
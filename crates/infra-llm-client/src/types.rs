@@ -48,6 +48,105 @@ pub struct LlmRequest {
     /// Sequences where the API will stop generating further tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
+    /// Constrains the output format (JSON mode or a JSON schema).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// The output format requested from the model.
+///
+/// Serializes to OpenAI's `response_format` shape; other adapters translate
+/// it to their own mechanism.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free-form text (the default).
+    Text,
+    /// Any syntactically valid JSON object.
+    JsonObject,
+    /// JSON conforming to a schema.
+    JsonSchema {
+        /// The schema definition.
+        json_schema: JsonSchemaFormat,
+    },
+}
+
+impl ResponseFormat {
+    /// Creates a strict JSON schema format.
+    ///
+    /// Every object schema without an explicit `additionalProperties` is
+    /// closed with `additionalProperties: false`, as strict mode requires.
+    #[must_use]
+    pub fn json_schema(name: impl Into<String>, mut schema: serde_json::Value) -> Self {
+        close_objects(&mut schema);
+        Self::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: name.into(),
+                description: None,
+                schema,
+                strict: true,
+            },
+        }
+    }
+
+    /// Returns `true` if the response must be JSON.
+    #[must_use]
+    pub fn is_json(&self) -> bool {
+        !matches!(self, Self::Text)
+    }
+
+    /// Returns the schema the response must conform to, if any.
+    #[must_use]
+    pub fn schema(&self) -> Option<&serde_json::Value> {
+        match self {
+            Self::JsonSchema { json_schema } => Some(&json_schema.schema),
+            _ => None,
+        }
+    }
+}
+
+/// Sets `additionalProperties: false` on every object schema that leaves it
+/// unspecified, descending through nested subschemas.
+fn close_objects(schema: &mut serde_json::Value) {
+    let Some(map) = schema.as_object_mut() else {
+        return;
+    };
+    if map.get("type").and_then(serde_json::Value::as_str) == Some("object")
+        || map.contains_key("properties")
+    {
+        map.entry("additionalProperties")
+            .or_insert(serde_json::Value::Bool(false));
+    }
+    for keyword in ["properties", "$defs", "definitions"] {
+        if let Some(serde_json::Value::Object(subschemas)) = map.get_mut(keyword) {
+            subschemas.values_mut().for_each(close_objects);
+        }
+    }
+    for keyword in ["items", "additionalProperties", "not"] {
+        if let Some(subschema) = map.get_mut(keyword) {
+            close_objects(subschema);
+        }
+    }
+    for keyword in ["anyOf", "oneOf", "allOf", "prefixItems"] {
+        if let Some(serde_json::Value::Array(subschemas)) = map.get_mut(keyword) {
+            subschemas.iter_mut().for_each(close_objects);
+        }
+    }
+}
+
+/// A named JSON schema for structured output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    /// The schema name.
+    pub name: String,
+    /// A description of what the schema represents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The JSON schema.
+    pub schema: serde_json::Value,
+    /// Whether the provider should enforce the schema exactly.
+    #[serde(default)]
+    pub strict: bool,
 }
 
 /// A response from an LLM completion request.
@@ -62,6 +161,31 @@ pub struct LlmResponse {
     /// Usage statistics for the request.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<Usage>,
+    /// Tool calls requested by the model.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// A tool (function) call requested by the model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Provider-assigned call identifier.
+    pub id: String,
+    /// Name of the tool to invoke.
+    pub name: String,
+    /// Arguments as a JSON-encoded string.
+    pub arguments: String,
+}
+
+impl ToolCall {
+    /// Deserializes the JSON-encoded arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the arguments are not valid JSON for `T`.
+    pub fn parse_arguments<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_str(&self.arguments)
+    }
 }
 
 /// Token usage statistics for an LLM request.
@@ -124,4 +248,29 @@ pub struct StreamChunk {
     pub model: String,
     /// The reason the generation stopped, if this is the final chunk.
     pub finish_reason: Option<String>,
+    /// Usage statistics, reported by some providers on the last chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    /// Fragments of tool calls being streamed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCallDelta>,
+}
+
+/// A fragment of a streamed tool call.
+///
+/// The identifier and name arrive with the first fragment for a given
+/// `index`; the arguments arrive in pieces that must be concatenated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    /// Position of the tool call within the response.
+    pub index: usize,
+    /// Call identifier, if present in this fragment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Tool name, if present in this fragment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Piece of the JSON-encoded arguments.
+    #[serde(default)]
+    pub arguments: String,
 }
@@ -0,0 +1,97 @@
+//! Proptest strategies for messages.
+
+use crate::message::{Message, MessageBuilder};
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Strategy for message headers with short lowercase names
+pub fn headers() -> impl Strategy<Value = BTreeMap<String, String>> {
+    prop::collection::btree_map("x-[a-z]{1,8}", "[ -~]{0,16}", 0..4)
+}
+
+/// Strategy for messages with arbitrary bodies, headers, correlation IDs,
+/// reply queues, TTLs and delivery counts
+pub fn message() -> impl Strategy<Value = Message> {
+    (
+        prop::collection::vec(any::<u8>(), 0..64),
+        headers(),
+        prop::option::of("[a-z0-9-]{1,12}"),
+        prop::option::of("[a-z.]{1,12}"),
+        prop::option::of(1u64..3_600_000),
+        0u32..5,
+    )
+        .prop_map(
+            |(body, headers, correlation_id, reply_to, ttl_ms, deliveries)| {
+                let mut builder = MessageBuilder::new().body(body);
+                for (key, value) in headers {
+                    builder = builder.header(key, value);
+                }
+                if let Some(id) = correlation_id {
+                    builder = builder.correlation_id(id);
+                }
+                if let Some(queue) = reply_to {
+                    builder = builder.reply_to(queue);
+                }
+                if let Some(ms) = ttl_ms {
+                    builder = builder.ttl(Duration::from_millis(ms));
+                }
+                let mut message = builder.build();
+                for _ in 0..deliveries {
+                    message.increment_delivery();
+                }
+                message
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same(a: &Message, b: &Message) -> Result<(), TestCaseError> {
+        prop_assert_eq!(a.id(), b.id());
+        prop_assert_eq!(a.body(), b.body());
+        prop_assert_eq!(a.headers(), b.headers());
+        prop_assert_eq!(a.correlation_id(), b.correlation_id());
+        prop_assert_eq!(a.reply_to(), b.reply_to());
+        prop_assert_eq!(a.timestamp(), b.timestamp());
+        prop_assert_eq!(a.ttl(), b.ttl());
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_message_serde_round_trip(message in message()) {
+            let encoded = serde_json::to_vec(&message).unwrap();
+            let decoded: Message = serde_json::from_slice(&encoded).unwrap();
+            assert_same(&message, &decoded)?;
+            prop_assert_eq!(decoded.delivery_count(), message.delivery_count());
+        }
+
+        #[cfg(feature = "memory")]
+        #[test]
+        fn prop_memory_queue_is_fifo(messages in prop::collection::vec(message(), 0..8)) {
+            use crate::{MemoryQueue, Queue};
+
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap();
+            let received = runtime.block_on(async {
+                let queue = MemoryQueue::new("prop");
+                queue.publish_batch(messages.clone()).await.unwrap();
+                let mut received = Vec::new();
+                while let Some(message) = queue.receive().await.unwrap() {
+                    received.push(message);
+                }
+                received
+            });
+
+            prop_assert_eq!(received.len(), messages.len());
+            for (sent, received) in messages.iter().zip(&received) {
+                assert_same(sent, received)?;
+            }
+        }
+    }
+}
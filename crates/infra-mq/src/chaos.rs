@@ -0,0 +1,169 @@
+//! Fault injection for resilience tests.
+
+use crate::message::Message;
+use crate::queue::{Queue, QueueStats};
+use crate::schedule::Schedule;
+use crate::Ack;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, MqOperation};
+use infra_sim::ChaosPolicy;
+use std::time::Duration;
+
+/// Queue injecting faults into another queue
+///
+/// Latency, failures and corruption come from an infra-sim [`ChaosPolicy`]
+/// keyed by operation: `publish` (also delayed and scheduled publishing),
+/// `receive`, `ack`, `len`, `stats` and `purge`. A corrupted delivery has
+/// one bit of its body flipped.
+pub struct ChaosQueue<Q> {
+    inner: Q,
+    policy: ChaosPolicy,
+}
+
+impl<Q: Queue> ChaosQueue<Q> {
+    /// Wrap a queue
+    pub fn new(inner: Q, policy: impl Into<ChaosPolicy>) -> Self {
+        Self {
+            inner,
+            policy: policy.into(),
+        }
+    }
+
+    /// Get the wrapped queue
+    pub fn inner(&self) -> &Q {
+        &self.inner
+    }
+
+    async fn disturb(&self, name: &str, operation: MqOperation) -> InfraResult<()> {
+        self.policy
+            .disturb(name)
+            .await
+            .map_err(|message| InfraError::MessageQueue {
+                queue: self.inner.name().to_string(),
+                operation,
+                message,
+                context: None,
+                source: None,
+            })
+    }
+
+    fn corrupt(&self, message: Option<Message>) -> Option<Message> {
+        let injector = self.policy.injector("receive");
+        message.map(|mut message| {
+            if injector.should_corrupt() {
+                injector.corrupt_bytes(message.body_mut());
+            }
+            message
+        })
+    }
+}
+
+#[async_trait]
+impl<Q: Queue> Queue for ChaosQueue<Q> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn publish(&self, message: Message) -> InfraResult<()> {
+        self.disturb("publish", MqOperation::Publish).await?;
+        self.inner.publish(message).await
+    }
+
+    async fn receive(&self) -> InfraResult<Option<Message>> {
+        self.disturb("receive", MqOperation::Subscribe).await?;
+        Ok(self.corrupt(self.inner.receive().await?))
+    }
+
+    async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>> {
+        self.disturb("receive", MqOperation::Subscribe).await?;
+        Ok(self.corrupt(self.inner.receive_timeout(timeout).await?))
+    }
+
+    async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()> {
+        self.disturb("ack", MqOperation::Acknowledge).await?;
+        self.inner.ack(message_id, ack).await
+    }
+
+    async fn len(&self) -> InfraResult<usize> {
+        self.disturb("len", MqOperation::Inspect).await?;
+        self.inner.len().await
+    }
+
+    async fn stats(&self) -> InfraResult<QueueStats> {
+        self.disturb("stats", MqOperation::Inspect).await?;
+        self.inner.stats().await
+    }
+
+    async fn purge(&self) -> InfraResult<usize> {
+        self.disturb("purge", MqOperation::Purge).await?;
+        self.inner.purge().await
+    }
+
+    async fn publish_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.disturb("publish", MqOperation::Publish).await?;
+        self.inner.publish_delayed(message, delay).await
+    }
+
+    async fn requeue_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.disturb("publish", MqOperation::Publish).await?;
+        self.inner.requeue_delayed(message, delay).await
+    }
+
+    async fn schedule(&self, message: Message, schedule: Schedule) -> InfraResult<String> {
+        self.disturb("publish", MqOperation::Publish).await?;
+        self.inner.schedule(message, schedule).await
+    }
+
+    async fn cancel_schedule(&self, schedule_id: &str) -> InfraResult<bool> {
+        self.inner.cancel_schedule(schedule_id).await
+    }
+}
+
+#[cfg(all(test, feature = "memory"))]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryQueue;
+    use infra_sim::{ChaosConfig, ChaosMode};
+
+    #[tokio::test]
+    async fn test_chaos_queue_failures() {
+        let policy =
+            ChaosPolicy::disabled().operation("publish", ChaosConfig::new(ChaosMode::AlwaysFail));
+        let queue = ChaosQueue::new(MemoryQueue::new("jobs"), policy);
+
+        let err = queue
+            .publish(Message::new(b"job".to_vec()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::MessageQueue {
+                operation: MqOperation::Publish,
+                ref queue,
+                ..
+            } if queue == "jobs"
+        ));
+        assert!(err.is_retryable());
+
+        queue
+            .inner()
+            .publish(Message::new(b"job".to_vec()))
+            .await
+            .unwrap();
+        let message = queue.receive().await.unwrap().unwrap();
+        assert_eq!(message.body(), b"job");
+    }
+
+    #[tokio::test]
+    async fn test_chaos_queue_corruption() {
+        let queue = ChaosQueue::new(
+            MemoryQueue::new("jobs"),
+            ChaosConfig::default().with_corruption_probability(1.0),
+        );
+        queue.publish(Message::new(b"job".to_vec())).await.unwrap();
+
+        let message = queue.receive().await.unwrap().unwrap();
+        assert_ne!(message.body(), b"job");
+        assert_eq!(message.body().len(), 3);
+    }
+}
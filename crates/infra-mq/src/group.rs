@@ -0,0 +1,353 @@
+//! Consumer groups for the in-memory queue.
+
+use crate::memory::MemoryQueue;
+use crate::message::Message;
+use crate::queue::{Queue, QueueStats};
+use crate::schedule::Schedule;
+use crate::Ack;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, MqOperation};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Mutex;
+
+/// Messages waiting for, or held by, one set of competing consumers
+#[derive(Default)]
+pub(crate) struct Deliveries {
+    ready: VecDeque<Message>,
+    pending: Vec<InFlight>,
+}
+
+/// A received message awaiting acknowledgment
+struct InFlight {
+    message: Message,
+    /// When the message becomes visible to other consumers again
+    deadline: Option<Instant>,
+}
+
+impl Deliveries {
+    pub(crate) fn push(&mut self, message: Message) {
+        self.ready.push_back(message);
+    }
+
+    /// Take up to `max` messages, hiding them from other consumers until
+    /// `visibility_timeout` elapses or they are acknowledged
+    pub(crate) fn take(
+        &mut self,
+        max: usize,
+        visibility_timeout: Option<Duration>,
+    ) -> Vec<Message> {
+        self.reclaim_expired();
+        let deadline = visibility_timeout.map(|timeout| Instant::now() + timeout);
+        let count = max.min(self.ready.len());
+        let mut messages = Vec::with_capacity(count);
+        for mut message in self.ready.drain(..count) {
+            message.increment_delivery();
+            self.pending.push(InFlight {
+                message: message.clone(),
+                deadline,
+            });
+            messages.push(message);
+        }
+        messages
+    }
+
+    /// Settle a received message, returning `false` if it is not pending
+    pub(crate) fn ack(&mut self, message_id: &str, ack: Ack) -> bool {
+        let Some(index) = self
+            .pending
+            .iter()
+            .position(|p| p.message.id() == message_id)
+        else {
+            return false;
+        };
+        let message = self.pending.remove(index).message;
+
+        match ack {
+            Ack::Ok => {
+                // Message processed, remove from pending
+            }
+            Ack::Requeue => {
+                // Put back in queue
+                self.ready.push_front(message);
+            }
+            Ack::Reject => {
+                // Message rejected, could go to dead letter queue
+                tracing::warn!(message_id = %message_id, "Message rejected");
+            }
+        }
+        true
+    }
+
+    pub(crate) fn len(&mut self) -> usize {
+        self.reclaim_expired();
+        self.ready.len()
+    }
+
+    /// Get the number of waiting messages
+    pub(crate) fn depth(&self) -> usize {
+        self.ready.len()
+    }
+
+    /// Drop the oldest waiting message to make room for a newer one
+    pub(crate) fn drop_oldest(&mut self) -> Option<Message> {
+        self.ready.pop_front()
+    }
+
+    /// Get how long the oldest waiting message has been queued
+    pub(crate) fn oldest_age(&self) -> Option<Duration> {
+        let oldest = self.ready.front()?;
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        Some(now.saturating_sub(Duration::from_millis(oldest.timestamp())))
+    }
+
+    pub(crate) fn stats(&mut self) -> QueueStats {
+        self.reclaim_expired();
+        QueueStats {
+            depth: self.ready.len(),
+            in_flight: self.pending.len(),
+            oldest_age: self.oldest_age(),
+        }
+    }
+
+    pub(crate) fn purge(&mut self) -> usize {
+        let count = self.ready.len();
+        self.ready.clear();
+        count
+    }
+
+    /// Make messages whose visibility timeout elapsed available again
+    fn reclaim_expired(&mut self) {
+        let now = Instant::now();
+        let mut expired = Vec::new();
+        self.pending.retain(|in_flight| match in_flight.deadline {
+            Some(deadline) if deadline <= now => {
+                expired.push(in_flight.message.clone());
+                false
+            }
+            _ => true,
+        });
+        for message in expired.into_iter().rev() {
+            tracing::debug!(message_id = %message.id(), "Visibility timeout elapsed, redelivering");
+            self.ready.push_front(message);
+        }
+    }
+}
+
+/// A named group of competing consumers on a [`MemoryQueue`]
+///
+/// Every group receives its own copy of each message published after it was
+/// created, and within a group each message goes to one consumer. Share a
+/// group between workers to split the load, or give each subscriber its own
+/// group, for example with [`MemoryQueue::broadcast`], to fan messages out.
+///
+/// A received message is hidden from the group's other consumers until it is
+/// acknowledged or the queue's visibility timeout elapses, after which it is
+/// delivered again. Delivery is therefore at least once.
+pub struct ConsumerGroup {
+    queue: MemoryQueue,
+    name: String,
+    deliveries: Arc<Mutex<Deliveries>>,
+    /// Broadcast groups are removed from the queue when dropped
+    ephemeral: bool,
+}
+
+impl ConsumerGroup {
+    pub(crate) fn new(
+        queue: MemoryQueue,
+        name: String,
+        deliveries: Arc<Mutex<Deliveries>>,
+        ephemeral: bool,
+    ) -> Self {
+        Self {
+            queue,
+            name,
+            deliveries,
+            ephemeral,
+        }
+    }
+
+    /// Get the underlying queue
+    pub fn queue(&self) -> &MemoryQueue {
+        &self.queue
+    }
+}
+
+impl Drop for ConsumerGroup {
+    fn drop(&mut self) {
+        if self.ephemeral {
+            self.queue.remove_group(&self.name);
+        }
+    }
+}
+
+#[async_trait]
+impl Queue for ConsumerGroup {
+    /// Get the group name
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, message: Message) -> InfraResult<()> {
+        self.queue.publish(message).await
+    }
+
+    async fn publish_batch(&self, messages: Vec<Message>) -> InfraResult<()> {
+        self.queue.publish_batch(messages).await
+    }
+
+    async fn receive(&self) -> InfraResult<Option<Message>> {
+        let messages = self.queue.take(Some(&self.name), &self.deliveries, 1).await;
+        Ok(messages.into_iter().next())
+    }
+
+    async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>> {
+        let messages = self
+            .queue
+            .poll(Some(&self.name), &self.deliveries, 1, timeout)
+            .await;
+        Ok(messages.into_iter().next())
+    }
+
+    async fn receive_batch(&self, max: usize, wait: Duration) -> InfraResult<Vec<Message>> {
+        Ok(self
+            .queue
+            .poll(Some(&self.name), &self.deliveries, max, wait)
+            .await)
+    }
+
+    async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()> {
+        if self.deliveries.lock().await.ack(message_id, ack) {
+            Ok(())
+        } else {
+            Err(InfraError::MessageQueue {
+                operation: MqOperation::Acknowledge,
+                queue: format!("{}/{}", self.queue.name(), self.name),
+                message: format!("Message not found: {message_id}"),
+                context: None,
+                source: None,
+            })
+        }
+    }
+
+    async fn len(&self) -> InfraResult<usize> {
+        self.queue.promote_due().await;
+        Ok(self.deliveries.lock().await.len())
+    }
+
+    async fn stats(&self) -> InfraResult<QueueStats> {
+        self.queue.promote_due().await;
+        Ok(self.deliveries.lock().await.stats())
+    }
+
+    /// Purge messages waiting for this group only
+    async fn purge(&self) -> InfraResult<usize> {
+        let mut deliveries = self.deliveries.lock().await;
+        let count = deliveries.purge();
+        self.queue.observe(Some(&self.name), &deliveries);
+        self.queue.notify_space();
+        Ok(count)
+    }
+
+    async fn publish_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.queue.publish_delayed(message, delay).await
+    }
+
+    /// Redeliver to this group only
+    async fn requeue_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.queue
+            .requeue_delayed_to(Some(&self.name), message, delay)
+            .await;
+        Ok(())
+    }
+
+    async fn schedule(&self, message: Message, schedule: Schedule) -> InfraResult<String> {
+        self.queue.schedule(message, schedule).await
+    }
+
+    async fn cancel_schedule(&self, schedule_id: &str) -> InfraResult<bool> {
+        self.queue.cancel_schedule(schedule_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::MessageBuilder;
+    use crate::queue::QueueConfig;
+
+    async fn publish(queue: &MemoryQueue, count: usize) {
+        for i in 0..count {
+            let msg = MessageBuilder::new()
+                .body_string(&format!("Job {i}"))
+                .build();
+            queue.publish(msg).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_group_members_compete() {
+        let queue = MemoryQueue::new("jobs");
+        let worker_a = queue.group("workers");
+        let worker_b = queue.group("workers");
+        publish(&queue, 4).await;
+
+        let mut seen = Vec::new();
+        for worker in [&worker_a, &worker_b, &worker_a, &worker_b] {
+            let message = worker.receive().await.unwrap().unwrap();
+            worker.ack(message.id(), Ack::Ok).await.unwrap();
+            seen.push(message.body_string().unwrap());
+        }
+
+        assert_eq!(seen, ["Job 0", "Job 1", "Job 2", "Job 3"]);
+        assert!(worker_a.is_empty().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_groups_each_receive_every_message() {
+        let queue = MemoryQueue::new("events");
+        let audit = queue.group("audit");
+        let metrics = queue.broadcast();
+        publish(&queue, 2).await;
+
+        assert_eq!(audit.len().await.unwrap(), 2);
+        assert_eq!(metrics.len().await.unwrap(), 2);
+        // Nothing is kept for receivers on the queue itself that never came
+        assert_eq!(queue.len().await.unwrap(), 0);
+        assert!(queue.receive().await.unwrap().is_none());
+        publish(&queue, 1).await;
+        assert_eq!(queue.len().await.unwrap(), 1);
+
+        // Groups only see messages published after they were created
+        assert!(queue.group("late").is_empty().await.unwrap());
+
+        drop(metrics);
+        assert_eq!(queue.group_names(), ["audit", "late"]);
+    }
+
+    #[tokio::test]
+    async fn test_visibility_timeout_redelivers() {
+        let config = QueueConfig::new("jobs").visibility_timeout(Duration::from_millis(30));
+        let queue = MemoryQueue::with_config(config);
+        let workers = queue.group("workers");
+        publish(&queue, 1).await;
+
+        let first = workers.receive().await.unwrap().unwrap();
+        assert!(workers.receive().await.unwrap().is_none());
+
+        // The first consumer stalls without acknowledging
+        let redelivered = workers
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(redelivered.id(), first.id());
+        assert_eq!(redelivered.delivery_count(), 2);
+
+        workers.ack(redelivered.id(), Ack::Ok).await.unwrap();
+        assert!(workers.ack(first.id(), Ack::Ok).await.is_err());
+    }
+}
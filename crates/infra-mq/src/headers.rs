@@ -0,0 +1,105 @@
+//! Standard message headers and trace propagation.
+
+use crate::message::Message;
+
+/// W3C trace context of the publishing span
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// W3C vendor-specific trace state
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// ID of the request that caused the message
+pub const REQUEST_ID_HEADER: &str = "request-id";
+
+/// Tenant the message belongs to
+pub const TENANT_HEADER: &str = "tenant";
+
+/// Header naming the schema a payload conforms to
+pub const SCHEMA_ID_HEADER: &str = "schema-id";
+
+/// Header explaining why a message was dead-lettered
+pub const DEAD_LETTER_REASON_HEADER: &str = "dead-letter-reason";
+
+/// Header counting how many times a message has been retried
+pub const RETRY_ATTEMPT_HEADER: &str = "retry-attempt";
+
+/// Header with the Unix time in milliseconds a retried message becomes visible
+pub const RETRY_VISIBLE_AT_HEADER: &str = "retry-next-visible-at";
+
+/// Header carrying the error of a failed RPC request
+pub const RPC_ERROR_HEADER: &str = "rpc-error";
+
+/// Add the current trace context to `message` unless it already carries one
+#[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+pub(crate) fn inject_trace_context(message: &mut Message) {
+    #[cfg(feature = "otel")]
+    if message.traceparent().is_none() {
+        if let Some(context) = infra_otel::TraceContext::current() {
+            message.set_header(TRACEPARENT_HEADER, context.to_traceparent());
+            if let Some(state) = context.trace_state {
+                message.set_header(TRACESTATE_HEADER, state);
+            }
+        }
+    }
+}
+
+/// Create the span a message is handled in, continuing the publisher's trace
+pub(crate) fn consume_span(queue: &str, message: &Message) -> tracing::Span {
+    let span = tracing::info_span!(
+        "mq.consume",
+        queue = %queue,
+        message_id = %message.id(),
+        request_id = tracing::field::Empty,
+        tenant = tracing::field::Empty,
+    );
+    if let Some(request_id) = message.request_id() {
+        span.record("request_id", request_id);
+    }
+    if let Some(tenant) = message.tenant() {
+        span.record("tenant", tenant);
+    }
+
+    #[cfg(feature = "otel")]
+    if let Some(mut parent) = message
+        .traceparent()
+        .and_then(infra_otel::TraceContext::from_traceparent)
+    {
+        parent.trace_state = message.header(TRACESTATE_HEADER).cloned();
+        parent.attach(&span);
+    }
+
+    span
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use super::*;
+    use crate::message::MessageBuilder;
+    use opentelemetry::trace::TracerProvider as _;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_trace_context_round_trip() {
+        let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let publish = tracing::info_span!("publish");
+            let mut message = MessageBuilder::new().tenant("acme").build();
+            let published = publish.in_scope(|| {
+                inject_trace_context(&mut message);
+                infra_otel::TraceContext::current().unwrap()
+            });
+            assert_eq!(
+                message.traceparent(),
+                Some(published.to_traceparent().as_str())
+            );
+
+            let consume = consume_span("jobs", &message);
+            let consumed = consume.in_scope(|| infra_otel::TraceContext::current().unwrap());
+            assert_eq!(consumed.trace_id, published.trace_id);
+            assert_ne!(consumed.span_id, published.span_id);
+        });
+    }
+}
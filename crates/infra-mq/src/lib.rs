@@ -3,21 +3,48 @@
 //! This crate provides a unified interface for message queues with
 //! pluggable backends (in-memory, Redis, RabbitMQ).
 
+mod headers;
 mod message;
 mod queue;
 mod publisher;
+mod rpc;
+mod schedule;
 mod subscriber;
+mod typed;
 
+#[cfg(feature = "memory")]
+mod group;
 #[cfg(feature = "memory")]
 mod memory;
+#[cfg(feature = "memory")]
+mod wheel;
+#[cfg(feature = "chaos")]
+mod chaos;
+#[cfg(feature = "chaos")]
+mod network;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
 
+pub use headers::{
+    DEAD_LETTER_REASON_HEADER, REQUEST_ID_HEADER, RETRY_ATTEMPT_HEADER, RETRY_VISIBLE_AT_HEADER,
+    RPC_ERROR_HEADER, SCHEMA_ID_HEADER, TENANT_HEADER, TRACEPARENT_HEADER, TRACESTATE_HEADER,
+};
 pub use message::{Message, MessageBuilder, MessageHeaders};
-pub use queue::{Queue, QueueConfig};
+pub use queue::{OverflowPolicy, Queue, QueueConfig, QueueStats};
 pub use publisher::Publisher;
+pub use rpc::{RpcClient, RpcFnHandler, RpcHandler, RpcServer};
+pub use schedule::Schedule;
 pub use subscriber::{Subscriber, MessageHandler};
+pub use typed::{TypedFnHandler, TypedHandler, TypedPublisher, TypedSubscriber};
 
+#[cfg(feature = "memory")]
+pub use group::ConsumerGroup;
 #[cfg(feature = "memory")]
 pub use memory::MemoryQueue;
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosQueue;
+#[cfg(feature = "chaos")]
+pub use network::NetworkQueue;
 
 use infra_errors::InfraResult;
 use std::sync::Arc;
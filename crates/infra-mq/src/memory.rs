@@ -1,120 +1,527 @@
 //! In-memory queue implementation.
 
+use crate::group::{ConsumerGroup, Deliveries};
 use crate::message::Message;
-use crate::queue::Queue;
+use crate::queue::{OverflowPolicy, Queue, QueueConfig, QueueStats};
+use crate::schedule::Schedule;
+use crate::wheel::TimerWheel;
 use crate::Ack;
 use async_trait::async_trait;
 use infra_errors::{InfraError, InfraResult, MqOperation};
-use std::collections::VecDeque;
-use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Mutex;
+use std::collections::BTreeMap;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{Mutex, Notify};
+use uuid::Uuid;
+
+/// Number of timer wheel slots; with 10ms ticks one rotation is ~5s
+const WHEEL_SLOTS: usize = 512;
+const WHEEL_RESOLUTION: Duration = Duration::from_millis(10);
+
+/// A message waiting in the timer wheel
+enum Timer {
+    /// Published once when due
+    Delayed(Message),
+    /// Returned to the queue itself (`None`) or one group only when due
+    Redelivery {
+        group: Option<String>,
+        message: Message,
+    },
+    /// Published each time the schedule fires
+    Scheduled {
+        id: String,
+        message: Message,
+        schedule: Schedule,
+        due: SystemTime,
+    },
+}
 
 /// In-memory queue implementation
+///
+/// Delayed and scheduled messages wait in a timer wheel and are moved to
+/// the queue when it is next read. Cloning the queue gives another handle
+/// to the same messages.
+///
+/// Once [consumer groups](Self::group) exist, receivers on the queue itself
+/// only get copies of new messages if they have received from it before, so
+/// messages are not kept for receivers that never come.
+///
+/// With [`QueueConfig::max_length`] set, each consumer group, and the queue
+/// itself while it receives copies, holds at most that many waiting
+/// messages, and publishing to a full recipient follows the configured
+/// [`OverflowPolicy`]. Delayed and scheduled
+/// messages are admitted when due regardless of the limit.
+#[derive(Clone)]
 pub struct MemoryQueue {
     name: String,
-    messages: Arc<Mutex<VecDeque<Message>>>,
-    pending: Arc<Mutex<Vec<Message>>>,
+    visibility_timeout: Option<Duration>,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    deliveries: Arc<Mutex<Deliveries>>,
+    /// Set once the queue itself has been received from
+    base_consumer: Arc<AtomicBool>,
+    groups: Arc<StdMutex<BTreeMap<String, Arc<Mutex<Deliveries>>>>>,
+    timers: Arc<Mutex<TimerWheel<Timer>>>,
+    /// Signalled when messages are taken from a full queue
+    space: Arc<Notify>,
+    #[cfg(feature = "otel")]
+    metrics: Option<Arc<infra_otel::MetricsRegistry>>,
 }
 
 impl MemoryQueue {
     /// Create a new in-memory queue
     pub fn new(name: impl Into<String>) -> Self {
+        Self::with_config(QueueConfig::new(name))
+    }
+
+    /// Create an in-memory queue from a configuration
+    pub fn with_config(config: QueueConfig) -> Self {
         Self {
-            name: name.into(),
-            messages: Arc::new(Mutex::new(VecDeque::new())),
-            pending: Arc::new(Mutex::new(Vec::new())),
+            name: config.name,
+            visibility_timeout: config.visibility_timeout,
+            capacity: config.max_length.map(|max| max as usize),
+            overflow: config.overflow,
+            deliveries: Arc::new(Mutex::new(Deliveries::default())),
+            base_consumer: Arc::new(AtomicBool::new(false)),
+            groups: Arc::new(StdMutex::new(BTreeMap::new())),
+            timers: Arc::new(Mutex::new(TimerWheel::new(WHEEL_SLOTS, WHEEL_RESOLUTION))),
+            space: Arc::new(Notify::new()),
+            #[cfg(feature = "otel")]
+            metrics: None,
         }
     }
-}
 
-#[async_trait]
-impl Queue for MemoryQueue {
-    fn name(&self) -> &str {
-        &self.name
+    /// Export depth and lag gauges, labelled with the queue and group names
+    ///
+    /// Records `mq_queue_depth` (waiting messages), `mq_queue_lag_ms` (age of
+    /// the oldest waiting message) and `mq_messages_dropped_total`.
+    #[cfg(feature = "otel")]
+    pub fn with_metrics(mut self, registry: Arc<infra_otel::MetricsRegistry>) -> Self {
+        self.metrics = Some(registry);
+        self
     }
 
-    async fn publish(&self, message: Message) -> InfraResult<()> {
-        let mut messages = self.messages.lock().await;
-        messages.push_back(message);
-        Ok(())
+    /// Join the consumer group `name`, creating it if needed
+    ///
+    /// Handles for the same name share the group's messages. Receivers on
+    /// the queue itself form a separate group of their own.
+    pub fn group(&self, name: impl Into<String>) -> ConsumerGroup {
+        let name = name.into();
+        let deliveries = Arc::clone(self.lock_groups().entry(name.clone()).or_default());
+        ConsumerGroup::new(self.clone(), name, deliveries, false)
     }
 
-    async fn receive(&self) -> InfraResult<Option<Message>> {
-        let mut messages = self.messages.lock().await;
-        if let Some(mut message) = messages.pop_front() {
-            message.increment_delivery();
+    /// Create a private group that receives every message, for fan-out
+    ///
+    /// The group is removed when the returned handle is dropped.
+    pub fn broadcast(&self) -> ConsumerGroup {
+        let name = format!("broadcast-{}", Uuid::new_v4());
+        let deliveries = Arc::new(Mutex::new(Deliveries::default()));
+        self.lock_groups()
+            .insert(name.clone(), Arc::clone(&deliveries));
+        ConsumerGroup::new(self.clone(), name, deliveries, true)
+    }
+
+    /// Remove a consumer group and its undelivered messages
+    pub fn remove_group(&self, name: &str) -> bool {
+        let removed = self.lock_groups().remove(name).is_some();
+        if removed {
+            // Publishers blocked on the group can proceed
+            self.notify_space();
+        }
+        removed
+    }
 
-            // Move to pending
-            let mut pending = self.pending.lock().await;
-            pending.push(message.clone());
+    /// Get the names of the consumer groups
+    pub fn group_names(&self) -> Vec<String> {
+        self.lock_groups().keys().cloned().collect()
+    }
 
-            Ok(Some(message))
-        } else {
-            Ok(None)
+    fn lock_groups(&self) -> MutexGuard<'_, BTreeMap<String, Arc<Mutex<Deliveries>>>> {
+        // The map is never left inconsistent, so a poisoned lock is still usable
+        self.groups.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn notify_space(&self) {
+        self.space.notify_waiters();
+    }
+
+    /// Update the gauges for the queue itself or one of its groups
+    #[allow(unused_variables)]
+    pub(crate) fn observe(&self, group: Option<&str>, deliveries: &Deliveries) {
+        #[cfg(feature = "otel")]
+        if let Some(registry) = &self.metrics {
+            let mut labels = vec![("queue", self.name.as_str())];
+            labels.extend(group.map(|group| ("group", group)));
+            let depth = i64::try_from(deliveries.depth()).unwrap_or(i64::MAX);
+            let lag = deliveries.oldest_age().unwrap_or_default().as_millis();
+            let lag = i64::try_from(lag).unwrap_or(i64::MAX);
+            registry.gauge_with("mq_queue_depth", &labels).set(depth);
+            registry.gauge_with("mq_queue_lag_ms", &labels).set(lag);
         }
     }
 
-    async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>> {
-        let start = std::time::Instant::now();
+    fn record_dropped(&self, count: usize) {
+        tracing::debug!(queue = %self.name, count, "Queue full, dropped oldest messages");
+        #[cfg(feature = "otel")]
+        if let Some(registry) = &self.metrics {
+            registry
+                .counter_with("mq_messages_dropped_total", &[("queue", &self.name)])
+                .add(count as u64);
+        }
+    }
 
-        loop {
-            if let Some(message) = self.receive().await? {
-                return Ok(Some(message));
-            }
+    fn full_error(&self, waited: Option<Duration>) -> InfraError {
+        let capacity = self.capacity.unwrap_or_default();
+        let message = match waited {
+            Some(timeout) => format!("Queue full (capacity {capacity}) after waiting {timeout:?}"),
+            None => format!("Queue full (capacity {capacity})"),
+        };
+        InfraError::MessageQueue {
+            operation: MqOperation::Publish,
+            queue: self.name.clone(),
+            message,
+            context: None,
+            source: None,
+        }
+    }
 
-            if start.elapsed() >= timeout {
-                return Ok(None);
+    /// Hand messages to every group, and to the queue's own receivers if
+    /// there are no groups or they have received before
+    ///
+    /// Returns `false` without delivering anything if `bounded` and a
+    /// recipient has no room under a rejecting or blocking policy.
+    async fn deliver(&self, messages: &[Message], bounded: bool) -> bool {
+        let groups: Vec<_> = self
+            .lock_groups()
+            .iter()
+            .map(|(name, group)| (name.clone(), Arc::clone(group)))
+            .collect();
+
+        // Lock every recipient first so the capacity check and the delivery
+        // are atomic; groups are always locked in name order, then the queue
+        let mut recipients = Vec::with_capacity(groups.len() + 1);
+        for (name, group) in &groups {
+            recipients.push((Some(name.as_str()), group.lock().await));
+        }
+        if groups.is_empty() || self.base_consumer.load(Ordering::Acquire) {
+            recipients.push((None, self.deliveries.lock().await));
+        }
+
+        let capacity = self.capacity.filter(|_| bounded);
+        if let Some(capacity) = capacity {
+            if self.overflow != OverflowPolicy::DropOldest
+                && recipients
+                    .iter_mut()
+                    .any(|(_, deliveries)| deliveries.len() + messages.len() > capacity)
+            {
+                return false;
             }
+        }
 
-            tokio::time::sleep(Duration::from_millis(10)).await;
+        let mut dropped = 0;
+        for (group, deliveries) in &mut recipients {
+            for message in messages {
+                deliveries.push(message.clone());
+            }
+            if let Some(capacity) = capacity {
+                while deliveries.depth() > capacity && deliveries.drop_oldest().is_some() {
+                    dropped += 1;
+                }
+            }
+            self.observe(*group, deliveries);
         }
+        if dropped > 0 {
+            self.record_dropped(dropped);
+        }
+        true
     }
 
-    async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()> {
-        let mut pending = self.pending.lock().await;
-        let pos = pending.iter().position(|m| m.id() == message_id);
-
-        match pos {
-            Some(index) => {
-                let message = pending.remove(index);
+    /// Move due delayed and scheduled messages into the queue
+    pub(crate) async fn promote_due(&self) {
+        let mut timers = self.timers.lock().await;
+        let due = timers.advance(Instant::now());
+        if due.is_empty() {
+            return;
+        }
 
-                match ack {
-                    Ack::Ok => {
-                        // Message processed, remove from pending
-                    }
-                    Ack::Requeue => {
-                        // Put back in queue
-                        let mut messages = self.messages.lock().await;
-                        messages.push_front(message);
-                    }
-                    Ack::Reject => {
-                        // Message rejected, could go to dead letter queue
-                        tracing::warn!(message_id = %message_id, "Message rejected");
+        let mut messages = Vec::with_capacity(due.len());
+        let mut redeliveries = Vec::new();
+        for timer in due {
+            match timer {
+                Timer::Delayed(message) => messages.push(message),
+                Timer::Redelivery { group, message } => redeliveries.push((group, message)),
+                Timer::Scheduled {
+                    id,
+                    message,
+                    schedule,
+                    due,
+                } => {
+                    messages.push(message.renewed());
+
+                    // Skip runs missed while the queue was not read
+                    let now = SystemTime::now();
+                    let next = schedule
+                        .next_after(due)
+                        .filter(|next| *next > now)
+                        .or_else(|| schedule.next_after(now));
+                    match next {
+                        Some(next) => {
+                            let delay = next.duration_since(now).unwrap_or_default();
+                            timers.insert(
+                                Instant::now() + delay,
+                                Timer::Scheduled {
+                                    id,
+                                    message,
+                                    schedule,
+                                    due: next,
+                                },
+                            );
+                        }
+                        None => tracing::debug!(schedule_id = %id, "Schedule finished"),
                     }
                 }
+            }
+        }
+        drop(timers);
+        if !messages.is_empty() {
+            self.deliver(&messages, false).await;
+        }
+        for (group, message) in redeliveries {
+            self.redeliver(group.as_deref(), message).await;
+        }
+    }
+
+    /// Return a message to the queue itself or one of its groups
+    async fn redeliver(&self, group: Option<&str>, message: Message) {
+        let deliveries = match group {
+            Some(name) => {
+                let Some(deliveries) = self.lock_groups().get(name).cloned() else {
+                    tracing::debug!(group = name, message_id = %message.id(), "Group removed, dropping redelivery");
+                    return;
+                };
+                deliveries
+            }
+            None => Arc::clone(&self.deliveries),
+        };
+        let mut deliveries = deliveries.lock().await;
+        deliveries.push(message);
+        self.observe(group, &deliveries);
+    }
+
+    /// Redeliver a message to the queue itself or one group after `delay`
+    pub(crate) async fn requeue_delayed_to(
+        &self,
+        group: Option<&str>,
+        message: Message,
+        delay: Duration,
+    ) {
+        self.timers.lock().await.insert(
+            Instant::now() + delay,
+            Timer::Redelivery {
+                group: group.map(String::from),
+                message,
+            },
+        );
+    }
+
+    /// Take up to `max` messages for the queue itself or one of its groups
+    pub(crate) async fn take(
+        &self,
+        group: Option<&str>,
+        deliveries: &Mutex<Deliveries>,
+        max: usize,
+    ) -> Vec<Message> {
+        self.promote_due().await;
+        let mut deliveries = deliveries.lock().await;
+        let messages = deliveries.take(max, self.visibility_timeout);
+        self.observe(group, &deliveries);
+        if !messages.is_empty() {
+            self.notify_space();
+        }
+        messages
+    }
+
+    /// Poll until at least one message can be taken or `wait` elapses
+    pub(crate) async fn poll(
+        &self,
+        group: Option<&str>,
+        deliveries: &Mutex<Deliveries>,
+        max: usize,
+        wait: Duration,
+    ) -> Vec<Message> {
+        if max == 0 {
+            return Vec::new();
+        }
+        let start = Instant::now();
+
+        loop {
+            let messages = self.take(group, deliveries, max).await;
+            if !messages.is_empty() || start.elapsed() >= wait {
+                return messages;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
 
+#[async_trait]
+impl Queue for MemoryQueue {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn publish(&self, message: Message) -> InfraResult<()> {
+        self.publish_batch(vec![message]).await
+    }
+
+    /// Publish messages atomically: all are delivered, or none if full
+    ///
+    /// Under a blocking policy, a batch larger than the capacity fails
+    /// immediately, as waiting would never make room for it.
+    async fn publish_batch(&self, messages: Vec<Message>) -> InfraResult<()> {
+        let OverflowPolicy::Block(timeout) = self.overflow else {
+            return if self.deliver(&messages, true).await {
                 Ok(())
+            } else {
+                Err(self.full_error(None))
+            };
+        };
+        if let Some(capacity) = self.capacity.filter(|capacity| messages.len() > *capacity) {
+            return Err(InfraError::MessageQueue {
+                operation: MqOperation::Publish,
+                queue: self.name.clone(),
+                message: format!(
+                    "Batch of {} messages exceeds the queue capacity of {capacity}",
+                    messages.len()
+                ),
+                context: None,
+                source: None,
+            });
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            // Register for wakeups before checking, so none are missed
+            let mut space = pin!(self.space.notified());
+            space.as_mut().enable();
+
+            if self.deliver(&messages, true).await {
+                return Ok(());
             }
-            None => Err(InfraError::MessageQueue {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if tokio::time::timeout(remaining, space).await.is_err() {
+                return Err(self.full_error(Some(timeout)));
+            }
+        }
+    }
+
+    async fn receive(&self) -> InfraResult<Option<Message>> {
+        self.base_consumer.store(true, Ordering::Release);
+        let messages = self.take(None, &self.deliveries, 1).await;
+        Ok(messages.into_iter().next())
+    }
+
+    async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>> {
+        self.base_consumer.store(true, Ordering::Release);
+        let messages = self.poll(None, &self.deliveries, 1, timeout).await;
+        Ok(messages.into_iter().next())
+    }
+
+    async fn receive_batch(&self, max: usize, wait: Duration) -> InfraResult<Vec<Message>> {
+        self.base_consumer.store(true, Ordering::Release);
+        Ok(self.poll(None, &self.deliveries, max, wait).await)
+    }
+
+    async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()> {
+        if self.deliveries.lock().await.ack(message_id, ack) {
+            Ok(())
+        } else {
+            Err(InfraError::MessageQueue {
                 operation: MqOperation::Acknowledge,
                 queue: self.name.clone(),
                 message: format!("Message not found: {message_id}"),
                 context: None,
-            }),
+                source: None,
+            })
         }
     }
 
     async fn len(&self) -> InfraResult<usize> {
-        let messages = self.messages.lock().await;
-        Ok(messages.len())
+        self.promote_due().await;
+        Ok(self.deliveries.lock().await.len())
+    }
+
+    async fn stats(&self) -> InfraResult<QueueStats> {
+        self.promote_due().await;
+        Ok(self.deliveries.lock().await.stats())
     }
 
     async fn purge(&self) -> InfraResult<usize> {
-        let mut messages = self.messages.lock().await;
-        let count = messages.len();
-        messages.clear();
-        Ok(count)
+        // Delayed messages are purged too; schedules must be cancelled
+        let delayed = self.timers.lock().await.retain(|timer| {
+            matches!(
+                timer,
+                Timer::Scheduled { .. } | Timer::Redelivery { group: Some(_), .. }
+            )
+        });
+        let mut deliveries = self.deliveries.lock().await;
+        let count = deliveries.purge();
+        self.observe(None, &deliveries);
+        self.notify_space();
+        Ok(count + delayed)
+    }
+
+    async fn publish_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.timers
+            .lock()
+            .await
+            .insert(Instant::now() + delay, Timer::Delayed(message));
+        Ok(())
+    }
+
+    /// Redeliver to receivers on the queue itself only, not to any group
+    async fn requeue_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.requeue_delayed_to(None, message, delay).await;
+        Ok(())
+    }
+
+    async fn schedule(&self, message: Message, schedule: Schedule) -> InfraResult<String> {
+        let now = SystemTime::now();
+        let due = schedule
+            .next_after(now)
+            .ok_or_else(|| InfraError::MessageQueue {
+                operation: MqOperation::Publish,
+                queue: self.name.clone(),
+                message: format!("Schedule never fires: {schedule}"),
+                context: None,
+                source: None,
+            })?;
+
+        let id = Uuid::new_v4().to_string();
+        let delay = due.duration_since(now).unwrap_or_default();
+        self.timers.lock().await.insert(
+            Instant::now() + delay,
+            Timer::Scheduled {
+                id: id.clone(),
+                message,
+                schedule,
+                due,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn cancel_schedule(&self, schedule_id: &str) -> InfraResult<bool> {
+        let removed = self
+            .timers
+            .lock()
+            .await
+            .retain(|timer| !matches!(timer, Timer::Scheduled { id, .. } if id == schedule_id));
+        Ok(removed > 0)
     }
 }
 
@@ -185,4 +592,219 @@ mod tests {
         assert_eq!(count, 5);
         assert!(queue.is_empty().await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_memory_queue_publish_delayed() {
+        let queue = MemoryQueue::new("test");
+
+        let msg = MessageBuilder::new().body_string("Retry").build();
+        queue
+            .publish_delayed(msg, Duration::from_millis(50))
+            .await
+            .unwrap();
+
+        assert!(queue.receive().await.unwrap().is_none());
+
+        let received = queue
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received.body_string(), Some("Retry".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_memory_queue_schedule() {
+        let queue = MemoryQueue::new("test");
+
+        let msg = MessageBuilder::new().body_string("Tick").build();
+        let id = queue
+            .schedule(msg, Schedule::every(Duration::from_millis(20)))
+            .await
+            .unwrap();
+
+        let first = queue
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        let second = queue
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.body_string(), Some("Tick".to_string()));
+        assert_ne!(first.id(), second.id());
+
+        assert!(queue.cancel_schedule(&id).await.unwrap());
+        assert!(!queue.cancel_schedule(&id).await.unwrap());
+        queue.purge().await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(queue.is_empty().await.unwrap());
+    }
+
+    fn bounded(overflow: OverflowPolicy) -> MemoryQueue {
+        MemoryQueue::with_config(QueueConfig::new("bounded").max_length(2).overflow(overflow))
+    }
+
+    fn message(body: &str) -> Message {
+        MessageBuilder::new().body_string(body).build()
+    }
+
+    #[tokio::test]
+    async fn test_bounded_queue_rejects_when_full() {
+        let queue = bounded(OverflowPolicy::Reject);
+        queue.publish(message("a")).await.unwrap();
+        queue.publish(message("b")).await.unwrap();
+
+        let err = queue.publish(message("c")).await.unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::MessageQueue {
+                operation: MqOperation::Publish,
+                ..
+            }
+        ));
+
+        // A full consumer group also blocks delivery to the queue
+        let group = queue.group("slow");
+        assert_eq!(queue.purge().await.unwrap(), 2);
+        queue.publish(message("c")).await.unwrap();
+        queue.publish(message("d")).await.unwrap();
+        queue.purge().await.unwrap();
+        assert!(queue.publish(message("e")).await.is_err());
+        assert_eq!(group.len().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_queue_drops_oldest() {
+        let queue = bounded(OverflowPolicy::DropOldest);
+        for body in ["a", "b", "c"] {
+            queue.publish(message(body)).await.unwrap();
+        }
+
+        assert_eq!(queue.len().await.unwrap(), 2);
+        let oldest = queue.receive().await.unwrap().unwrap();
+        assert_eq!(oldest.body_string(), Some("b".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_bounded_queue_capacity_per_group() {
+        let queue = bounded(OverflowPolicy::Reject);
+        let workers = queue.group("workers");
+        queue.publish(message("a")).await.unwrap();
+        queue.publish(message("b")).await.unwrap();
+        assert!(queue.publish(message("c")).await.is_err());
+
+        let received = workers.receive().await.unwrap().unwrap();
+        workers.ack(received.id(), Ack::Ok).await.unwrap();
+        queue.publish(message("c")).await.unwrap();
+        assert_eq!(workers.len().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_queue_blocks_until_space() {
+        let queue = bounded(OverflowPolicy::Block(Duration::from_millis(50)));
+        queue.publish(message("a")).await.unwrap();
+        queue.publish(message("b")).await.unwrap();
+
+        let err = queue.publish(message("c")).await.unwrap_err();
+        assert!(err.to_string().contains("after waiting"));
+
+        let consumer = queue.clone();
+        let receive = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            consumer.receive().await.unwrap()
+        });
+        queue.publish(message("c")).await.unwrap();
+        assert!(receive.await.unwrap().is_some());
+        assert_eq!(queue.len().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_queue_rejects_oversized_batch() {
+        let queue = bounded(OverflowPolicy::Block(Duration::from_secs(60)));
+
+        let batch = vec![message("a"), message("b"), message("c")];
+        let err = queue.publish_batch(batch).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds the queue capacity"));
+        assert_eq!(queue.len().await.unwrap(), 0);
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn test_queue_gauges() {
+        let registry = Arc::new(infra_otel::MetricsRegistry::new());
+        let queue = bounded(OverflowPolicy::DropOldest).with_metrics(registry.clone());
+        let group = queue.group("workers");
+        // Receivers on the queue itself get copies too
+        assert!(queue.receive().await.unwrap().is_none());
+        for body in ["a", "b", "c"] {
+            queue.publish(message(body)).await.unwrap();
+        }
+        group.receive().await.unwrap().unwrap();
+
+        let depth = |labels: &[(&str, &str)]| registry.gauge_with("mq_queue_depth", labels).get();
+        assert_eq!(depth(&[("queue", "bounded")]), 2);
+        assert_eq!(depth(&[("queue", "bounded"), ("group", "workers")]), 1);
+        assert_eq!(
+            registry
+                .counter_with("mq_messages_dropped_total", &[("queue", "bounded")])
+                .get(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queue_stats() {
+        let queue = MemoryQueue::new("test");
+        assert_eq!(queue.stats().await.unwrap(), QueueStats::default());
+
+        queue.publish(message("a")).await.unwrap();
+        queue.publish(message("b")).await.unwrap();
+        let received = queue.receive().await.unwrap().unwrap();
+
+        let stats = queue.stats().await.unwrap();
+        assert_eq!(stats.depth, 1);
+        assert_eq!(stats.in_flight, 1);
+        assert!(stats.oldest_age.is_some());
+
+        queue.ack(received.id(), Ack::Ok).await.unwrap();
+        assert_eq!(queue.stats().await.unwrap().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_publish_receive() {
+        let queue = MemoryQueue::new("test");
+        let batch = (0..5).map(|i| message(&format!("Message {i}"))).collect();
+        queue.publish_batch(batch).await.unwrap();
+        assert_eq!(queue.len().await.unwrap(), 5);
+
+        let first = queue.receive_batch(3, Duration::ZERO).await.unwrap();
+        let rest = queue.receive_batch(10, Duration::ZERO).await.unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1].body_string(), Some("Message 4".to_string()));
+        assert!(rest.iter().all(|m| m.delivery_count() == 1));
+
+        for message in first.iter().chain(&rest) {
+            queue.ack(message.id(), Ack::Ok).await.unwrap();
+        }
+        let empty = queue
+            .receive_batch(10, Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_publish_is_all_or_nothing() {
+        let queue = bounded(OverflowPolicy::Reject);
+        queue.publish(message("a")).await.unwrap();
+
+        let batch = vec![message("b"), message("c")];
+        assert!(queue.publish_batch(batch).await.is_err());
+        assert_eq!(queue.len().await.unwrap(), 1);
+    }
 }
@@ -1,5 +1,6 @@
 //! Message types.
 
+use crate::headers::{REQUEST_ID_HEADER, SCHEMA_ID_HEADER, TENANT_HEADER, TRACEPARENT_HEADER};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{Duration, SystemTime};
@@ -77,6 +78,26 @@ impl Message {
         self.headers.get(key)
     }
 
+    /// Get the W3C traceparent of the publishing span
+    pub fn traceparent(&self) -> Option<&str> {
+        self.header(TRACEPARENT_HEADER).map(String::as_str)
+    }
+
+    /// Get the ID of the request that caused the message
+    pub fn request_id(&self) -> Option<&str> {
+        self.header(REQUEST_ID_HEADER).map(String::as_str)
+    }
+
+    /// Get the tenant
+    pub fn tenant(&self) -> Option<&str> {
+        self.header(TENANT_HEADER).map(String::as_str)
+    }
+
+    /// Get the schema ID of the payload
+    pub fn schema_id(&self) -> Option<&str> {
+        self.header(SCHEMA_ID_HEADER).map(String::as_str)
+    }
+
     /// Get correlation ID
     pub fn correlation_id(&self) -> Option<&str> {
         self.correlation_id.as_deref()
@@ -119,6 +140,34 @@ impl Message {
     pub fn increment_delivery(&mut self) {
         self.delivery_count += 1;
     }
+
+    /// Get the body for in-place changes
+    #[cfg(feature = "chaos")]
+    pub(crate) fn body_mut(&mut self) -> &mut [u8] {
+        &mut self.body
+    }
+
+    /// Set a header on the message
+    pub(crate) fn set_header(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.headers.insert(key.into(), value.into());
+    }
+
+    /// Set a header on the message
+    pub(crate) fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_header(key, value);
+        self
+    }
+
+    /// Copy the message as a new publication with a fresh ID and timestamp
+    pub(crate) fn renewed(&self) -> Self {
+        let fresh = Self::new(Vec::new());
+        Self {
+            id: fresh.id,
+            timestamp: fresh.timestamp,
+            delivery_count: 0,
+            ..self.clone()
+        }
+    }
 }
 
 /// Message builder
@@ -162,6 +211,21 @@ impl MessageBuilder {
         self
     }
 
+    /// Set the ID of the request that caused the message
+    pub fn request_id(self, id: impl Into<String>) -> Self {
+        self.header(REQUEST_ID_HEADER, id)
+    }
+
+    /// Set the tenant
+    pub fn tenant(self, tenant: impl Into<String>) -> Self {
+        self.header(TENANT_HEADER, tenant)
+    }
+
+    /// Set the schema ID of the payload
+    pub fn schema_id(self, id: impl Into<String>) -> Self {
+        self.header(SCHEMA_ID_HEADER, id)
+    }
+
     /// Set correlation ID
     pub fn correlation_id(mut self, id: impl Into<String>) -> Self {
         self.message.correlation_id = Some(id.into());
@@ -211,6 +275,21 @@ mod tests {
         assert!(msg.ttl().is_some());
     }
 
+    #[test]
+    fn test_standard_headers() {
+        let msg = MessageBuilder::new()
+            .request_id("req-1")
+            .tenant("acme")
+            .schema_id("job.v1")
+            .build();
+
+        assert_eq!(msg.request_id(), Some("req-1"));
+        assert_eq!(msg.tenant(), Some("acme"));
+        assert_eq!(msg.schema_id(), Some("job.v1"));
+        assert_eq!(msg.header(TENANT_HEADER), Some(&"acme".to_string()));
+        assert!(msg.traceparent().is_none());
+    }
+
     #[test]
     fn test_message_expiry() {
         let mut msg = MessageBuilder::new()
@@ -0,0 +1,179 @@
+//! Simulated network conditions for tests.
+
+use crate::message::Message;
+use crate::queue::{Queue, QueueStats};
+use crate::schedule::Schedule;
+use crate::Ack;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, MqOperation};
+use infra_sim::SimNetwork;
+use std::time::Duration;
+
+/// Queue reached over an infra-sim [`SimNetwork`]
+///
+/// Each operation sends a request from the client node to the broker node
+/// and a reply back. A lost publish never reaches the queue, while a lost
+/// delivery leaves the message received but unacknowledged, as with a real
+/// broker.
+pub struct NetworkQueue<Q> {
+    inner: Q,
+    network: SimNetwork,
+    client: String,
+    broker: String,
+}
+
+impl<Q: Queue> NetworkQueue<Q> {
+    /// Wrap a queue hosted on the broker node, used from the client node
+    pub fn new(
+        inner: Q,
+        network: SimNetwork,
+        client: impl Into<String>,
+        broker: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            network,
+            client: client.into(),
+            broker: broker.into(),
+        }
+    }
+
+    /// Get the wrapped queue
+    pub fn inner(&self) -> &Q {
+        &self.inner
+    }
+
+    async fn send(&self, from: &str, to: &str, operation: MqOperation) -> InfraResult<()> {
+        self.network
+            .transmit(from, to)
+            .await
+            .map_err(|fault| InfraError::MessageQueue {
+                queue: self.inner.name().to_string(),
+                operation,
+                message: fault.to_string(),
+                context: None,
+                source: None,
+            })
+    }
+
+    /// Run an operation on the broker, with its request and reply
+    async fn round_trip<T>(
+        &self,
+        operation: MqOperation,
+        call: impl std::future::Future<Output = InfraResult<T>> + Send,
+    ) -> InfraResult<T> {
+        self.send(&self.client, &self.broker, operation).await?;
+        let result = call.await;
+        self.send(&self.broker, &self.client, operation).await?;
+        result
+    }
+}
+
+#[async_trait]
+impl<Q: Queue> Queue for NetworkQueue<Q> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn publish(&self, message: Message) -> InfraResult<()> {
+        self.round_trip(MqOperation::Publish, self.inner.publish(message))
+            .await
+    }
+
+    async fn receive(&self) -> InfraResult<Option<Message>> {
+        self.round_trip(MqOperation::Subscribe, self.inner.receive())
+            .await
+    }
+
+    async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>> {
+        self.round_trip(MqOperation::Subscribe, self.inner.receive_timeout(timeout))
+            .await
+    }
+
+    async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()> {
+        self.round_trip(MqOperation::Acknowledge, self.inner.ack(message_id, ack))
+            .await
+    }
+
+    async fn len(&self) -> InfraResult<usize> {
+        self.round_trip(MqOperation::Inspect, self.inner.len())
+            .await
+    }
+
+    async fn stats(&self) -> InfraResult<QueueStats> {
+        self.round_trip(MqOperation::Inspect, self.inner.stats())
+            .await
+    }
+
+    async fn purge(&self) -> InfraResult<usize> {
+        self.round_trip(MqOperation::Purge, self.inner.purge())
+            .await
+    }
+
+    async fn publish_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.round_trip(
+            MqOperation::Publish,
+            self.inner.publish_delayed(message, delay),
+        )
+        .await
+    }
+
+    async fn requeue_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.round_trip(
+            MqOperation::Publish,
+            self.inner.requeue_delayed(message, delay),
+        )
+        .await
+    }
+
+    async fn schedule(&self, message: Message, schedule: Schedule) -> InfraResult<String> {
+        self.round_trip(MqOperation::Publish, self.inner.schedule(message, schedule))
+            .await
+    }
+
+    async fn cancel_schedule(&self, schedule_id: &str) -> InfraResult<bool> {
+        self.round_trip(MqOperation::Cancel, self.inner.cancel_schedule(schedule_id))
+            .await
+    }
+}
+
+#[cfg(all(test, feature = "memory"))]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryQueue;
+    use infra_sim::LinkConditions;
+
+    #[tokio::test]
+    async fn test_network_queue() {
+        let network = SimNetwork::new();
+        let queue = NetworkQueue::new(
+            MemoryQueue::new("jobs"),
+            network.clone(),
+            "worker",
+            "broker",
+        );
+
+        network.partition(&["worker"], &["broker"]);
+        let err = queue
+            .publish(Message::new(b"job".to_vec()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::MessageQueue {
+                operation: MqOperation::Publish,
+                ..
+            }
+        ));
+        assert_eq!(queue.inner().len().await.unwrap(), 0);
+
+        network.heal();
+        queue.publish(Message::new(b"job".to_vec())).await.unwrap();
+
+        // The delivery is lost on its way back to the worker
+        network.set_link("broker", "worker", LinkConditions::new().loss(1.0));
+        assert!(queue.receive().await.is_err());
+        assert_eq!(queue.inner().len().await.unwrap(), 0);
+        assert_eq!(network.stats().dropped, 1);
+    }
+}
@@ -1,5 +1,6 @@
 //! Message publisher.
 
+use crate::headers;
 use crate::message::{Message, MessageBuilder};
 use crate::queue::Queue;
 use infra_errors::InfraResult;
@@ -18,10 +19,20 @@ impl Publisher {
     }
 
     /// Publish a message
-    pub async fn publish(&self, message: Message) -> InfraResult<()> {
+    ///
+    /// The current trace context is added to the `traceparent` header so
+    /// consumers continue the publisher's trace.
+    pub async fn publish(&self, mut message: Message) -> InfraResult<()> {
+        headers::inject_trace_context(&mut message);
         self.queue.publish(message).await
     }
 
+    /// Publish several messages in one batch
+    pub async fn publish_batch(&self, mut messages: Vec<Message>) -> InfraResult<()> {
+        messages.iter_mut().for_each(headers::inject_trace_context);
+        self.queue.publish_batch(messages).await
+    }
+
     /// Publish a raw byte message
     pub async fn publish_bytes(&self, body: Vec<u8>) -> InfraResult<()> {
         let message = MessageBuilder::new().body(body).build();
@@ -43,6 +54,7 @@ impl Publisher {
                 message: e.to_string(),
                 location: None,
                 context: None,
+                source: None,
             })?
             .build();
         self.publish(message).await
@@ -1,11 +1,35 @@
 //! Queue trait and configuration.
 
 use crate::message::Message;
+use crate::schedule::Schedule;
 use crate::Ack;
 use async_trait::async_trait;
-use infra_errors::InfraResult;
+use infra_errors::{InfraError, InfraResult, MqOperation};
 use std::time::Duration;
 
+/// What publishing to a full bounded queue does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Fail the publish
+    #[default]
+    Reject,
+    /// Discard the oldest waiting messages to make room
+    DropOldest,
+    /// Wait up to the timeout for room, then fail the publish
+    Block(Duration),
+}
+
+/// Point-in-time queue statistics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueStats {
+    /// Messages waiting to be received
+    pub depth: usize,
+    /// Messages received but not yet acknowledged
+    pub in_flight: usize,
+    /// How long the oldest waiting message has been queued
+    pub oldest_age: Option<Duration>,
+}
+
 /// Queue configuration
 #[derive(Debug, Clone)]
 pub struct QueueConfig {
@@ -21,6 +45,10 @@ pub struct QueueConfig {
     pub dead_letter_queue: Option<String>,
     /// Maximum retries before dead-lettering
     pub max_retries: u32,
+    /// How long a received message stays hidden before it is redelivered
+    pub visibility_timeout: Option<Duration>,
+    /// What publishing does once `max_length` is reached
+    pub overflow: OverflowPolicy,
 }
 
 impl QueueConfig {
@@ -33,6 +61,8 @@ impl QueueConfig {
             message_ttl: None,
             dead_letter_queue: None,
             max_retries: 3,
+            visibility_timeout: None,
+            overflow: OverflowPolicy::default(),
         }
     }
 
@@ -65,6 +95,18 @@ impl QueueConfig {
         self.max_retries = max;
         self
     }
+
+    /// Set the visibility timeout for unacknowledged messages
+    pub fn visibility_timeout(mut self, timeout: Duration) -> Self {
+        self.visibility_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the overflow policy for a bounded queue
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
 }
 
 /// Queue trait
@@ -76,12 +118,44 @@ pub trait Queue: Send + Sync {
     /// Publish a message to the queue
     async fn publish(&self, message: Message) -> InfraResult<()>;
 
+    /// Publish several messages, amortizing per-message overhead
+    ///
+    /// The default publishes them one at a time.
+    async fn publish_batch(&self, messages: Vec<Message>) -> InfraResult<()> {
+        for message in messages {
+            self.publish(message).await?;
+        }
+        Ok(())
+    }
+
     /// Receive a message from the queue
     async fn receive(&self) -> InfraResult<Option<Message>>;
 
     /// Receive a message with timeout
     async fn receive_timeout(&self, timeout: Duration) -> InfraResult<Option<Message>>;
 
+    /// Receive up to `max` messages, waiting up to `wait` for the first
+    ///
+    /// Returns as soon as any messages are available, so the batch may be
+    /// smaller than `max`, and is empty if none arrive in time.
+    async fn receive_batch(&self, max: usize, wait: Duration) -> InfraResult<Vec<Message>> {
+        let mut messages = Vec::new();
+        if max == 0 {
+            return Ok(messages);
+        }
+        let Some(first) = self.receive_timeout(wait).await? else {
+            return Ok(messages);
+        };
+        messages.push(first);
+        while messages.len() < max {
+            match self.receive().await? {
+                Some(message) => messages.push(message),
+                None => break,
+            }
+        }
+        Ok(messages)
+    }
+
     /// Acknowledge a message
     async fn ack(&self, message_id: &str, ack: Ack) -> InfraResult<()>;
 
@@ -93,6 +167,60 @@ pub trait Queue: Send + Sync {
         Ok(self.len().await? == 0)
     }
 
+    /// Get point-in-time statistics
+    ///
+    /// The default reports only the depth from [`Queue::len`].
+    async fn stats(&self) -> InfraResult<QueueStats> {
+        Ok(QueueStats {
+            depth: self.len().await?,
+            ..QueueStats::default()
+        })
+    }
+
     /// Purge all messages from the queue
     async fn purge(&self) -> InfraResult<usize>;
+
+    /// Publish a message that becomes visible to receivers after `delay`
+    ///
+    /// Useful for retrying a failed LLM job later without holding a worker.
+    /// Backends without delayed delivery return an error.
+    async fn publish_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        let _ = (message, delay);
+        Err(unsupported(self.name(), "Delayed delivery"))
+    }
+
+    /// Make a received message available again to this queue's consumers
+    /// only, after `delay`
+    ///
+    /// Unlike [`Queue::publish_delayed`], the message is not fanned out to
+    /// other consumer groups. The default publishes it with
+    /// [`Queue::publish_delayed`], which is equivalent for queues without
+    /// consumer groups.
+    async fn requeue_delayed(&self, message: Message, delay: Duration) -> InfraResult<()> {
+        self.publish_delayed(message, delay).await
+    }
+
+    /// Publish a copy of `message` each time `schedule` fires
+    ///
+    /// Each copy gets a fresh ID. Returns an ID for [`Queue::cancel_schedule`].
+    async fn schedule(&self, message: Message, schedule: Schedule) -> InfraResult<String> {
+        let _ = (message, schedule);
+        Err(unsupported(self.name(), "Scheduled delivery"))
+    }
+
+    /// Stop a schedule, returning whether it was active
+    async fn cancel_schedule(&self, schedule_id: &str) -> InfraResult<bool> {
+        let _ = schedule_id;
+        Err(unsupported(self.name(), "Scheduled delivery"))
+    }
+}
+
+fn unsupported(queue: &str, feature: &str) -> InfraError {
+    InfraError::MessageQueue {
+        operation: MqOperation::Publish,
+        queue: queue.to_string(),
+        message: format!("{feature} is not supported by this backend"),
+        context: None,
+        source: None,
+    }
 }
@@ -0,0 +1,312 @@
+//! Request/reply over message queues.
+
+use crate::headers::RPC_ERROR_HEADER;
+use crate::message::{Message, MessageBuilder};
+use crate::publisher::Publisher;
+use crate::queue::Queue;
+use crate::subscriber::{MessageHandler, Subscriber};
+use crate::Ack;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, MqOperation};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// How long the reply listener waits for each poll
+const LISTEN_INTERVAL: Duration = Duration::from_millis(100);
+
+type Waiters = Arc<Mutex<HashMap<String, oneshot::Sender<Message>>>>;
+
+fn lock(waiters: &Waiters) -> MutexGuard<'_, HashMap<String, oneshot::Sender<Message>>> {
+    waiters.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Client side of request/reply over a queue
+///
+/// Requests carry a fresh correlation ID and name the client's reply queue
+/// in `reply_to`. A background task, spawned on creation, receives replies
+/// and hands each to the call waiting on its correlation ID. Give every
+/// client its own reply queue, since clients sharing one would consume each
+/// other's replies.
+pub struct RpcClient {
+    publisher: Publisher,
+    queue: String,
+    reply_to: String,
+    waiters: Waiters,
+    timeout: Duration,
+    listener: JoinHandle<()>,
+}
+
+impl RpcClient {
+    /// Create a client sending requests to `requests` and receiving replies
+    /// on `replies`
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn new(requests: Arc<dyn Queue>, replies: Arc<dyn Queue>) -> Self {
+        let waiters = Waiters::default();
+        Self {
+            queue: requests.name().to_string(),
+            publisher: Publisher::new(requests),
+            reply_to: replies.name().to_string(),
+            waiters: waiters.clone(),
+            timeout: Duration::from_secs(30),
+            listener: tokio::spawn(listen(replies, waiters)),
+        }
+    }
+
+    /// Set how long to wait for a reply
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send a request and wait for its reply
+    ///
+    /// Fails with a timeout error if no reply arrives in time, and with a
+    /// message queue error if the server's handler failed.
+    pub async fn call(&self, request: MessageBuilder) -> InfraResult<Message> {
+        let correlation_id = Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        lock(&self.waiters).insert(correlation_id.clone(), tx);
+
+        let request = request
+            .correlation_id(&correlation_id)
+            .reply_to(&self.reply_to)
+            .build();
+        let result = self.exchange(request, rx).await;
+        lock(&self.waiters).remove(&correlation_id);
+
+        let reply = result?;
+        match reply.header(RPC_ERROR_HEADER) {
+            Some(error) => Err(InfraError::MessageQueue {
+                operation: MqOperation::Reject,
+                queue: self.queue.clone(),
+                message: format!("Request failed: {error}"),
+                context: None,
+                source: None,
+            }),
+            None => Ok(reply),
+        }
+    }
+
+    async fn exchange(
+        &self,
+        request: Message,
+        reply: oneshot::Receiver<Message>,
+    ) -> InfraResult<Message> {
+        self.publisher.publish(request).await?;
+        match tokio::time::timeout(self.timeout, reply).await {
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => Err(InfraError::MessageQueue {
+                operation: MqOperation::Subscribe,
+                queue: self.reply_to.clone(),
+                message: "Reply listener stopped".to_string(),
+                context: None,
+                source: None,
+            }),
+            Err(_) => Err(InfraError::timeout(
+                format!("RPC call to {}", self.queue),
+                self.timeout,
+            )),
+        }
+    }
+}
+
+impl Drop for RpcClient {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+/// Receive replies and wake the calls waiting on them
+async fn listen(replies: Arc<dyn Queue>, waiters: Waiters) {
+    loop {
+        let reply = match replies.receive_timeout(LISTEN_INTERVAL).await {
+            Ok(Some(reply)) => reply,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::error!(queue = %replies.name(), error = %e, "Error receiving reply");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        if let Err(e) = replies.ack(reply.id(), Ack::Ok).await {
+            tracing::warn!(message_id = %reply.id(), error = %e, "Failed to acknowledge reply");
+        }
+
+        let waiter = reply
+            .correlation_id()
+            .and_then(|id| lock(&waiters).remove(id));
+        match waiter {
+            Some(waiter) => {
+                let _ = waiter.send(reply);
+            }
+            None => {
+                // The call timed out, or the reply is not ours
+                tracing::debug!(message_id = %reply.id(), "Discarding unmatched reply");
+            }
+        }
+    }
+}
+
+/// Handler for RPC requests
+#[async_trait]
+pub trait RpcHandler: Send + Sync {
+    /// Handle a request, returning the reply
+    async fn handle(&self, request: &Message) -> InfraResult<MessageBuilder>;
+}
+
+/// Simple function handler for RPC requests
+pub struct RpcFnHandler<F> {
+    handler: F,
+}
+
+#[async_trait]
+impl<F> RpcHandler for RpcFnHandler<F>
+where
+    F: Fn(&Message) -> InfraResult<MessageBuilder> + Send + Sync,
+{
+    async fn handle(&self, request: &Message) -> InfraResult<MessageBuilder> {
+        (self.handler)(request)
+    }
+}
+
+/// Server side of request/reply over a queue
+///
+/// Replies go to the queue named in each request's `reply_to`, which must
+/// be registered with [`RpcServer::with_reply_queue`], carrying the
+/// request's correlation ID. Handler errors are returned to the caller in
+/// the `rpc-error` header. Requests without a known reply queue are
+/// rejected.
+pub struct RpcServer {
+    queue: Arc<dyn Queue>,
+    responder: Responder,
+}
+
+impl RpcServer {
+    /// Create a new server
+    pub fn new(queue: Arc<dyn Queue>, handler: Arc<dyn RpcHandler>) -> Self {
+        Self {
+            queue,
+            responder: Responder {
+                handler,
+                replies: HashMap::new(),
+            },
+        }
+    }
+
+    /// Create with a function handler
+    pub fn with_fn<F>(queue: Arc<dyn Queue>, handler: F) -> Self
+    where
+        F: Fn(&Message) -> InfraResult<MessageBuilder> + Send + Sync + 'static,
+    {
+        Self::new(queue, Arc::new(RpcFnHandler { handler }))
+    }
+
+    /// Allow replies to a queue, looked up by its name
+    pub fn with_reply_queue(mut self, queue: Arc<dyn Queue>) -> Self {
+        self.responder
+            .replies
+            .insert(queue.name().to_string(), Publisher::new(queue));
+        self
+    }
+
+    /// Convert into a [`Subscriber`] to configure polling and start serving
+    pub fn into_subscriber(self) -> Subscriber {
+        Subscriber::new(self.queue, Arc::new(self.responder))
+    }
+
+    /// Start serving requests
+    pub async fn start(self) -> InfraResult<()> {
+        self.into_subscriber().start().await
+    }
+}
+
+/// Adapts an [`RpcHandler`] to a [`MessageHandler`]
+struct Responder {
+    handler: Arc<dyn RpcHandler>,
+    replies: HashMap<String, Publisher>,
+}
+
+#[async_trait]
+impl MessageHandler for Responder {
+    async fn handle(&self, request: &Message) -> Ack {
+        let Some(publisher) = request.reply_to().and_then(|name| self.replies.get(name)) else {
+            tracing::warn!(
+                message_id = %request.id(),
+                reply_to = ?request.reply_to(),
+                "Request has no known reply queue"
+            );
+            return Ack::Reject;
+        };
+
+        let mut reply = match self.handler.handle(request).await {
+            Ok(reply) => reply,
+            Err(e) => MessageBuilder::new().header(RPC_ERROR_HEADER, e.to_string()),
+        };
+        if let Some(correlation_id) = request.correlation_id() {
+            reply = reply.correlation_id(correlation_id);
+        }
+
+        match publisher.publish(reply.build()).await {
+            Ok(()) => Ack::Ok,
+            Err(e) => {
+                tracing::error!(message_id = %request.id(), error = %e, "Failed to send reply");
+                Ack::Requeue
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "memory"))]
+mod tests {
+    use super::*;
+    use crate::MemoryQueue;
+
+    #[tokio::test]
+    async fn test_request_reply() {
+        let requests: Arc<dyn Queue> = Arc::new(MemoryQueue::new("embed"));
+        let replies: Arc<dyn Queue> = Arc::new(MemoryQueue::new("embed.replies"));
+
+        let server = RpcServer::with_fn(requests.clone(), |request| {
+            let body = request.body_string().unwrap_or_default();
+            if body.is_empty() {
+                return Err(InfraError::validation("Empty request"));
+            }
+            Ok(MessageBuilder::new().body_string(&body.to_uppercase()))
+        })
+        .with_reply_queue(replies.clone())
+        .into_subscriber();
+        let client = RpcClient::new(requests, replies).timeout(Duration::from_secs(1));
+
+        let call = client.call(MessageBuilder::new().body_string("hello"));
+        let (reply, processed) = tokio::join!(call, server.process_one());
+        assert_eq!(processed.unwrap(), Some(Ack::Ok));
+        assert_eq!(reply.unwrap().body_string(), Some("HELLO".to_string()));
+
+        let call = client.call(MessageBuilder::new().body_string(""));
+        let (reply, _) = tokio::join!(call, server.process_one());
+        assert!(matches!(reply, Err(InfraError::MessageQueue { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_call_times_out() {
+        let requests: Arc<dyn Queue> = Arc::new(MemoryQueue::new("embed"));
+        let replies: Arc<dyn Queue> = Arc::new(MemoryQueue::new("embed.replies"));
+        let client = RpcClient::new(requests.clone(), replies).timeout(Duration::from_millis(30));
+
+        let err = client
+            .call(MessageBuilder::new().body_string("hello"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, InfraError::Timeout { .. }));
+        assert!(lock(&client.waiters).is_empty());
+
+        // Unknown reply queues are rejected by the server
+        let server = RpcServer::with_fn(requests, |_| Ok(MessageBuilder::new())).into_subscriber();
+        assert_eq!(server.process_one().await.unwrap(), Some(Ack::Reject));
+    }
+}
@@ -0,0 +1,262 @@
+//! Recurring publication schedules.
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, TimeZone, Timelike, Utc};
+use infra_errors::{InfraError, InfraResult};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// When a scheduled message is published
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    kind: ScheduleKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScheduleKind {
+    Every(Duration),
+    Cron(Box<Cron>),
+}
+
+impl Schedule {
+    /// Publish every `interval`, starting one interval from now
+    ///
+    /// Intervals shorter than a millisecond are rounded up to one.
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            kind: ScheduleKind::Every(interval.max(Duration::from_millis(1))),
+        }
+    }
+
+    /// Publish on a cron expression, evaluated in UTC
+    ///
+    /// Expressions have five fields: minute, hour, day of month, month and
+    /// day of week (0 or 7 is Sunday). Each field accepts `*`, values,
+    /// ranges (`1-5`), steps (`*/15`, `0-30/10`) and comma-separated lists.
+    /// As in cron, when both day fields are restricted either may match.
+    pub fn cron(expression: &str) -> InfraResult<Self> {
+        Ok(Self {
+            kind: ScheduleKind::Cron(Box::new(expression.parse()?)),
+        })
+    }
+
+    /// Get the first publication time strictly after `after`
+    ///
+    /// Returns `None` if a cron expression never matches, such as
+    /// `0 0 31 2 *`.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        match &self.kind {
+            ScheduleKind::Every(interval) => after.checked_add(*interval),
+            ScheduleKind::Cron(cron) => cron
+                .next_after(DateTime::<Utc>::from(after))
+                .map(SystemTime::from),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ScheduleKind::Every(interval) => write!(f, "every {interval:?}"),
+            ScheduleKind::Cron(cron) => write!(f, "{}", cron.expression),
+        }
+    }
+}
+
+/// Parsed five-field cron expression, one bit per allowed value
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cron {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl FromStr for Cron {
+    type Err = InfraError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(InfraError::validation_field(
+                "schedule",
+                "Cron expression must have five fields",
+                Some("minute hour day month weekday".to_string()),
+                Some(expression.to_string()),
+            ));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // Both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+
+        Ok(Self {
+            expression: expression.to_string(),
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+}
+
+impl Cron {
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let mut time = start;
+
+        // Every satisfiable expression matches within a leap-year cycle
+        while time.year() <= start.year() + 8 {
+            if !has(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.matches_day(&time) {
+                time = time.with_hour(0)?.with_minute(0)? + ChronoDuration::days(1);
+            } else if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + ChronoDuration::hours(1);
+            } else if !has(self.minutes, time.minute()) {
+                time += ChronoDuration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Parse one cron field into a bit set of the allowed values
+fn parse_field(field: &str, min: u32, max: u32) -> InfraResult<u64> {
+    let invalid = || {
+        InfraError::validation_field(
+            "schedule",
+            "Invalid cron field",
+            Some(format!("values between {min} and {max}")),
+            Some(field.to_string()),
+        )
+    };
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(invalid)
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // A single value with a step runs to the end of the range
+                None if part.contains('/') => (number(range)?, max),
+                None => {
+                    let value = number(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(rfc3339: &str) -> SystemTime {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().into()
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let every_15 = Schedule::cron("*/15 * * * *").unwrap();
+        assert_eq!(
+            every_15.next_after(at("2024-03-10T10:07:30Z")),
+            Some(at("2024-03-10T10:15:00Z"))
+        );
+
+        // 09:30 on weekdays; 2024-03-09 is a Saturday
+        let weekdays = Schedule::cron("30 9 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(at("2024-03-08T09:30:00Z")),
+            Some(at("2024-03-11T09:30:00Z"))
+        );
+
+        let leap_day = Schedule::cron("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap_day.next_after(at("2024-03-01T00:00:00Z")),
+            Some(at("2028-02-29T00:00:00Z"))
+        );
+        assert_eq!(
+            Schedule::cron("0 0 31 2 *")
+                .unwrap()
+                .next_after(at("2024-01-01T00:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_cron() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(Schedule::cron(expression).is_err(), "{expression}");
+        }
+    }
+
+    #[test]
+    fn test_interval_schedule() {
+        let schedule = Schedule::every(Duration::from_secs(30));
+        let now = SystemTime::now();
+        assert_eq!(
+            schedule.next_after(now),
+            Some(now + Duration::from_secs(30))
+        );
+        assert_eq!(schedule.to_string(), "every 30s");
+    }
+}
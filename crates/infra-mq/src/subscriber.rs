@@ -1,5 +1,6 @@
 //! Message subscriber.
 
+use crate::headers;
 use crate::message::Message;
 use crate::queue::Queue;
 use crate::Ack;
@@ -8,6 +9,14 @@ use infra_errors::InfraResult;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tracing::Instrument;
+
+#[cfg(feature = "retry")]
+use crate::headers::{RETRY_ATTEMPT_HEADER, RETRY_VISIBLE_AT_HEADER};
+#[cfg(feature = "otel")]
+use infra_otel::{MetricsRegistry, Timer};
+#[cfg(feature = "retry")]
+use infra_retry::RetryPolicy;
 
 /// Message handler trait
 #[async_trait]
@@ -50,6 +59,10 @@ pub struct Subscriber {
     handler: Arc<dyn MessageHandler>,
     poll_interval: Duration,
     shutdown_rx: Option<mpsc::Receiver<()>>,
+    #[cfg(feature = "retry")]
+    retry: Option<Arc<dyn RetryPolicy>>,
+    #[cfg(feature = "otel")]
+    metrics: Option<Arc<MetricsRegistry>>,
 }
 
 impl Subscriber {
@@ -60,6 +73,10 @@ impl Subscriber {
             handler,
             poll_interval: Duration::from_millis(100),
             shutdown_rx: None,
+            #[cfg(feature = "retry")]
+            retry: None,
+            #[cfg(feature = "otel")]
+            metrics: None,
         }
     }
 
@@ -83,6 +100,35 @@ impl Subscriber {
         self
     }
 
+    /// Redeliver requeued messages later, with delays from `policy`
+    ///
+    /// When the handler returns [`Ack::Requeue`], the message is republished
+    /// with [`Queue::publish_delayed`] after the policy's delay for its
+    /// attempt, recorded in the `retry-attempt` and `retry-next-visible-at`
+    /// headers. Once the policy gives up the message is rejected. Queues
+    /// without delayed delivery fall back to an immediate requeue.
+    #[cfg(feature = "retry")]
+    pub fn with_retry(mut self, policy: Arc<dyn RetryPolicy>) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Record consumer metrics in `registry`, labelled by queue name
+    ///
+    /// Records handler latency in the `mq_handler_duration_seconds`
+    /// histogram and acknowledgments in the `mq_messages_acked_total`
+    /// counter, labelled with the `ack` outcome. While running, the
+    /// subscriber also refreshes the `mq_consumer_depth`,
+    /// `mq_consumer_in_flight` and `mq_consumer_lag_ms` gauges from
+    /// [`Queue::stats`] after each poll. They are named apart from the
+    /// `mq_queue_*` gauges of [`MemoryQueue::with_metrics`](crate::MemoryQueue::with_metrics),
+    /// so a queue and its subscriber never overwrite each other's values.
+    #[cfg(feature = "otel")]
+    pub fn with_metrics(mut self, registry: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(registry);
+        self
+    }
+
     /// Start consuming messages
     pub async fn start(mut self) -> InfraResult<()> {
         tracing::info!(queue = %self.queue.name(), "Starting subscriber");
@@ -101,8 +147,7 @@ impl Subscriber {
                 Ok(Some(message)) => {
                     tracing::debug!(message_id = %message.id(), "Received message");
 
-                    let ack = self.handler.handle(&message).await;
-                    self.queue.ack(message.id(), ack).await?;
+                    let ack = self.handle(&message).await?;
 
                     tracing::debug!(
                         message_id = %message.id(),
@@ -118,6 +163,9 @@ impl Subscriber {
                     tokio::time::sleep(Duration::from_secs(1)).await;
                 }
             }
+
+            #[cfg(feature = "otel")]
+            self.record_stats().await;
         }
 
         Ok(())
@@ -126,11 +174,239 @@ impl Subscriber {
     /// Process a single message (for testing)
     pub async fn process_one(&self) -> InfraResult<Option<Ack>> {
         if let Some(message) = self.queue.receive().await? {
-            let ack = self.handler.handle(&message).await;
-            self.queue.ack(message.id(), ack).await?;
-            Ok(Some(ack))
+            Ok(Some(self.handle(&message).await?))
         } else {
             Ok(None)
         }
     }
+
+    /// Handle and acknowledge a message, returning the acknowledgment sent
+    ///
+    /// The handler runs in an `mq.consume` span that continues the trace in
+    /// the message's `traceparent` header.
+    async fn handle(&self, message: &Message) -> InfraResult<Ack> {
+        let span = headers::consume_span(self.queue.name(), message);
+        #[cfg(feature = "otel")]
+        let timer = self.metrics.as_ref().map(|registry| {
+            Timer::start(registry.histogram_with(
+                "mq_handler_duration_seconds",
+                &[("queue", self.queue.name())],
+            ))
+        });
+        let ack = self.handler.handle(message).instrument(span).await;
+        #[cfg(feature = "otel")]
+        drop(timer);
+
+        #[cfg(feature = "retry")]
+        let ack = match (ack, &self.retry) {
+            (Ack::Requeue, Some(policy)) => self.retry_later(message, policy.as_ref()).await,
+            _ => ack,
+        };
+
+        self.queue.ack(message.id(), ack).await?;
+
+        #[cfg(feature = "otel")]
+        if let Some(registry) = &self.metrics {
+            let outcome = match ack {
+                Ack::Ok => "ok",
+                Ack::Requeue => "requeue",
+                Ack::Reject => "reject",
+            };
+            registry
+                .counter_with(
+                    "mq_messages_acked_total",
+                    &[("queue", self.queue.name()), ("ack", outcome)],
+                )
+                .inc();
+        }
+        Ok(ack)
+    }
+
+    /// Refresh the queue gauges from the queue's statistics
+    #[cfg(feature = "otel")]
+    async fn record_stats(&self) {
+        let Some(registry) = &self.metrics else {
+            return;
+        };
+        let stats = match self.queue.stats().await {
+            Ok(stats) => stats,
+            Err(e) => {
+                tracing::debug!(error = %e, "Failed to read queue stats");
+                return;
+            }
+        };
+
+        let labels = [("queue", self.queue.name())];
+        let gauge = |value: u128| i64::try_from(value).unwrap_or(i64::MAX);
+        registry
+            .gauge_with("mq_consumer_depth", &labels)
+            .set(gauge(stats.depth as u128));
+        registry
+            .gauge_with("mq_consumer_in_flight", &labels)
+            .set(gauge(stats.in_flight as u128));
+        registry
+            .gauge_with("mq_consumer_lag_ms", &labels)
+            .set(gauge(stats.oldest_age.unwrap_or_default().as_millis()));
+    }
+
+    /// Schedule a redelivery, returning how to settle the original message
+    #[cfg(feature = "retry")]
+    async fn retry_later(&self, message: &Message, policy: &dyn RetryPolicy) -> Ack {
+        let attempt = message
+            .header(RETRY_ATTEMPT_HEADER)
+            .and_then(|attempt| attempt.parse::<u32>().ok())
+            .unwrap_or(0);
+        let Some(delay) = policy.delay_for(attempt) else {
+            tracing::warn!(message_id = %message.id(), attempt, "Retries exhausted");
+            return Ack::Reject;
+        };
+
+        let visible_at = std::time::SystemTime::now() + delay;
+        let visible_at_ms = visible_at
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let retry = message
+            .clone()
+            .with_header(RETRY_ATTEMPT_HEADER, (attempt + 1).to_string())
+            .with_header(RETRY_VISIBLE_AT_HEADER, visible_at_ms.to_string());
+
+        match self.queue.requeue_delayed(retry, delay).await {
+            Ok(()) => {
+                tracing::debug!(message_id = %message.id(), attempt, ?delay, "Scheduled retry");
+                Ack::Ok
+            }
+            Err(e) => {
+                tracing::warn!(message_id = %message.id(), error = %e, "Delayed retry failed, requeueing");
+                Ack::Requeue
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "memory", any(feature = "retry", feature = "otel")))]
+mod tests {
+    use super::*;
+    use crate::{MemoryQueue, MessageBuilder};
+
+    #[cfg(feature = "retry")]
+    #[tokio::test]
+    async fn test_requeued_messages_retry_with_backoff() {
+        let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new("jobs"));
+        queue
+            .publish(MessageBuilder::new().body_string("job").build())
+            .await
+            .unwrap();
+
+        let policy = Arc::new(infra_retry::FixedDelay::new(Duration::from_millis(30), 2));
+        let subscriber = Subscriber::with_fn(queue.clone(), |_| Ack::Requeue).with_retry(policy);
+
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Ok));
+        // Not redelivered until the delay has passed
+        assert!(queue.is_empty().await.unwrap());
+
+        let retry = queue
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            retry.header(RETRY_ATTEMPT_HEADER).map(String::as_str),
+            Some("1")
+        );
+        assert!(retry.header(RETRY_VISIBLE_AT_HEADER).is_some());
+        queue.ack(retry.id(), Ack::Requeue).await.unwrap();
+
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Ok));
+        let retry = queue
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            retry.header(RETRY_ATTEMPT_HEADER).map(String::as_str),
+            Some("2")
+        );
+        queue.ack(retry.id(), Ack::Requeue).await.unwrap();
+
+        // The policy allows two retries, so the third failure is rejected
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Reject));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(queue.is_empty().await.unwrap());
+    }
+
+    #[cfg(feature = "retry")]
+    #[tokio::test]
+    async fn test_group_retries_stay_in_group() {
+        let queue = MemoryQueue::new("jobs");
+        let workers: Arc<dyn Queue> = Arc::new(queue.group("workers"));
+        let audit = queue.group("audit");
+        queue
+            .publish(MessageBuilder::new().body_string("job").build())
+            .await
+            .unwrap();
+
+        let policy = Arc::new(infra_retry::FixedDelay::new(Duration::from_millis(10), 1));
+        let subscriber = Subscriber::with_fn(workers.clone(), |_| Ack::Requeue).with_retry(policy);
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Ok));
+
+        let retry = workers
+            .receive_timeout(Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            retry.header(RETRY_ATTEMPT_HEADER).map(String::as_str),
+            Some("1")
+        );
+        // Other groups only ever see the original message
+        assert_eq!(audit.len().await.unwrap(), 1);
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn test_consumer_metrics() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new("jobs"));
+        for body in ["good", "bad", "later"] {
+            queue
+                .publish(MessageBuilder::new().body_string(body).build())
+                .await
+                .unwrap();
+        }
+
+        let subscriber = Subscriber::with_fn(queue.clone(), |message| {
+            match message.body_string().as_deref() {
+                Some("good") => Ack::Ok,
+                _ => Ack::Reject,
+            }
+        })
+        .with_metrics(registry.clone());
+        subscriber.process_one().await.unwrap();
+        subscriber.process_one().await.unwrap();
+        subscriber.record_stats().await;
+
+        let labels = [("queue", "jobs")];
+        let acked = |ack| {
+            registry
+                .counter_with(
+                    "mq_messages_acked_total",
+                    &[("queue", "jobs"), ("ack", ack)],
+                )
+                .get()
+        };
+        assert_eq!(acked("ok"), 1);
+        assert_eq!(acked("reject"), 1);
+        assert_eq!(
+            registry
+                .histogram_with("mq_handler_duration_seconds", &labels)
+                .count(),
+            2
+        );
+        assert_eq!(registry.gauge_with("mq_consumer_depth", &labels).get(), 1);
+        assert_eq!(
+            registry.gauge_with("mq_consumer_in_flight", &labels).get(),
+            0
+        );
+    }
 }
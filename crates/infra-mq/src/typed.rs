@@ -0,0 +1,379 @@
+//! Typed publishing and consumption.
+
+use crate::headers::{self, DEAD_LETTER_REASON_HEADER};
+use crate::message::{Message, MessageBuilder};
+use crate::queue::Queue;
+use crate::subscriber::{MessageHandler, Subscriber};
+use crate::Ack;
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, SerializationFormat};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[cfg(feature = "schema")]
+use infra_schema::SchemaRegistry;
+
+/// Schema a payload is checked against
+#[cfg(feature = "schema")]
+#[derive(Clone)]
+struct Contract {
+    registry: Arc<SchemaRegistry>,
+    schema_id: String,
+}
+
+#[cfg(feature = "schema")]
+impl Contract {
+    fn check(&self, payload: &Value) -> InfraResult<()> {
+        self.registry
+            .validate(&self.schema_id, payload)?
+            .into_result()
+            .map_err(|e| match e {
+                InfraError::Schema {
+                    path,
+                    message,
+                    context,
+                    source,
+                    ..
+                } => InfraError::Schema {
+                    schema_id: Some(self.schema_id.clone()),
+                    path,
+                    message,
+                    context,
+                    source,
+                },
+                other => other,
+            })
+    }
+}
+
+fn json_error(e: &serde_json::Error) -> InfraError {
+    InfraError::Serialization {
+        format: SerializationFormat::Json,
+        message: e.to_string(),
+        location: None,
+        context: None,
+        source: None,
+    }
+}
+
+/// Publisher of JSON-serialized `T` payloads
+pub struct TypedPublisher<T> {
+    queue: Arc<dyn Queue>,
+    #[cfg(feature = "schema")]
+    contract: Option<Contract>,
+    _payload: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> TypedPublisher<T> {
+    /// Create a new typed publisher
+    pub fn new(queue: Arc<dyn Queue>) -> Self {
+        Self {
+            queue,
+            #[cfg(feature = "schema")]
+            contract: None,
+            _payload: PhantomData,
+        }
+    }
+
+    /// Validate payloads against a registered schema before publishing
+    ///
+    /// Published messages carry the schema ID in the `schema-id` header.
+    #[cfg(feature = "schema")]
+    pub fn with_schema(
+        mut self,
+        registry: Arc<SchemaRegistry>,
+        schema_id: impl Into<String>,
+    ) -> Self {
+        self.contract = Some(Contract {
+            registry,
+            schema_id: schema_id.into(),
+        });
+        self
+    }
+
+    /// Build the message for a payload without publishing it
+    pub fn message(&self, payload: &T) -> InfraResult<Message> {
+        let body = serde_json::to_value(payload).map_err(|e| json_error(&e))?;
+        let builder = MessageBuilder::new()
+            .body_json(&body)
+            .map_err(|e| json_error(&e))?;
+
+        #[cfg(feature = "schema")]
+        let builder = match &self.contract {
+            Some(contract) => {
+                contract.check(&body)?;
+                builder.schema_id(contract.schema_id.clone())
+            }
+            None => builder,
+        };
+
+        let mut message = builder.build();
+        headers::inject_trace_context(&mut message);
+        Ok(message)
+    }
+
+    /// Serialize, validate and publish a payload
+    pub async fn publish(&self, payload: &T) -> InfraResult<()> {
+        self.queue.publish(self.message(payload)?).await
+    }
+}
+
+/// Handler for typed payloads
+#[async_trait]
+pub trait TypedHandler<T>: Send + Sync {
+    /// Handle a decoded payload
+    async fn handle(&self, message: &Message, payload: T) -> Ack;
+}
+
+/// Simple function handler for typed payloads
+pub struct TypedFnHandler<F> {
+    handler: F,
+}
+
+#[async_trait]
+impl<T, F> TypedHandler<T> for TypedFnHandler<F>
+where
+    T: Send + 'static,
+    F: Fn(&Message, T) -> Ack + Send + Sync,
+{
+    async fn handle(&self, message: &Message, payload: T) -> Ack {
+        (self.handler)(message, payload)
+    }
+}
+
+/// Subscriber that decodes JSON payloads into `T` before handling them
+///
+/// Messages that fail to decode or violate the schema never reach the
+/// handler. They are rejected, or republished to a dead-letter queue with
+/// the reason in the `dead-letter-reason` header when one is configured.
+pub struct TypedSubscriber<T> {
+    queue: Arc<dyn Queue>,
+    decoder: Decoder<T>,
+}
+
+impl<T: DeserializeOwned + Send + 'static> TypedSubscriber<T> {
+    /// Create a new typed subscriber
+    pub fn new(queue: Arc<dyn Queue>, handler: Arc<dyn TypedHandler<T>>) -> Self {
+        Self {
+            queue,
+            decoder: Decoder {
+                handler,
+                dead_letter: None,
+                #[cfg(feature = "schema")]
+                contract: None,
+                _payload: PhantomData,
+            },
+        }
+    }
+
+    /// Create with a function handler
+    pub fn with_fn<F>(queue: Arc<dyn Queue>, handler: F) -> Self
+    where
+        F: Fn(&Message, T) -> Ack + Send + Sync + 'static,
+    {
+        Self::new(queue, Arc::new(TypedFnHandler { handler }))
+    }
+
+    /// Validate payloads against a registered schema before handling
+    ///
+    /// Messages whose `schema-id` header names a different schema are
+    /// treated as violations too.
+    #[cfg(feature = "schema")]
+    pub fn with_schema(
+        mut self,
+        registry: Arc<SchemaRegistry>,
+        schema_id: impl Into<String>,
+    ) -> Self {
+        self.decoder.contract = Some(Contract {
+            registry,
+            schema_id: schema_id.into(),
+        });
+        self
+    }
+
+    /// Republish contract violations to a dead-letter queue
+    pub fn with_dead_letter(mut self, queue: Arc<dyn Queue>) -> Self {
+        self.decoder.dead_letter = Some(queue);
+        self
+    }
+
+    /// Convert into a [`Subscriber`] to configure polling and start consuming
+    pub fn into_subscriber(self) -> Subscriber {
+        Subscriber::new(self.queue, Arc::new(self.decoder))
+    }
+
+    /// Start consuming messages
+    pub async fn start(self) -> InfraResult<()> {
+        self.into_subscriber().start().await
+    }
+}
+
+/// Adapts a [`TypedHandler`] to a [`MessageHandler`]
+struct Decoder<T> {
+    handler: Arc<dyn TypedHandler<T>>,
+    dead_letter: Option<Arc<dyn Queue>>,
+    #[cfg(feature = "schema")]
+    contract: Option<Contract>,
+    _payload: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Decoder<T> {
+    fn decode(&self, message: &Message) -> InfraResult<T> {
+        let body: Value = message.body_json().map_err(|e| json_error(&e))?;
+
+        #[cfg(feature = "schema")]
+        if let Some(contract) = &self.contract {
+            if let Some(id) = message.schema_id() {
+                if id != contract.schema_id {
+                    return Err(InfraError::Schema {
+                        schema_id: Some(contract.schema_id.clone()),
+                        path: None,
+                        message: format!("Unexpected schema: {id}"),
+                        context: None,
+                        source: None,
+                    });
+                }
+            }
+            contract.check(&body)?;
+        }
+
+        serde_json::from_value(body).map_err(|e| json_error(&e))
+    }
+}
+
+#[async_trait]
+impl<T: DeserializeOwned + Send + 'static> MessageHandler for Decoder<T> {
+    async fn handle(&self, message: &Message) -> Ack {
+        let error = match self.decode(message) {
+            Ok(payload) => return self.handler.handle(message, payload).await,
+            Err(e) => e,
+        };
+        tracing::warn!(message_id = %message.id(), error = %error, "Invalid message payload");
+
+        let Some(dead_letter) = &self.dead_letter else {
+            return Ack::Reject;
+        };
+        let dead = message
+            .clone()
+            .with_header(DEAD_LETTER_REASON_HEADER, error.to_string());
+
+        match dead_letter.publish(dead).await {
+            Ok(()) => Ack::Ok,
+            Err(e) => {
+                // Leave the message on the source queue rather than lose it
+                tracing::error!(message_id = %message.id(), error = %e, "Failed to dead-letter message");
+                Ack::Requeue
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "memory"))]
+mod tests {
+    use super::*;
+    use crate::MemoryQueue;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Job {
+        prompt: String,
+        max_tokens: u32,
+    }
+
+    fn job(prompt: &str) -> Job {
+        Job {
+            prompt: prompt.to_string(),
+            max_tokens: 64,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_round_trip() {
+        let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new("jobs"));
+        TypedPublisher::new(queue.clone())
+            .publish(&job("Hello"))
+            .await
+            .unwrap();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let subscriber = TypedSubscriber::with_fn(queue, move |_, job: Job| {
+            sink.lock().unwrap().push(job);
+            Ack::Ok
+        })
+        .into_subscriber();
+
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Ok));
+        assert_eq!(*received.lock().unwrap(), [job("Hello")]);
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_messages_are_dead_lettered() {
+        let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new("jobs"));
+        let dead_letter: Arc<dyn Queue> = Arc::new(MemoryQueue::new("jobs.dlq"));
+        queue
+            .publish(MessageBuilder::new().body_string("not json").build())
+            .await
+            .unwrap();
+
+        let rejecting =
+            TypedSubscriber::with_fn(queue.clone(), |_, _: Job| Ack::Ok).into_subscriber();
+        assert_eq!(rejecting.process_one().await.unwrap(), Some(Ack::Reject));
+
+        queue
+            .publish(MessageBuilder::new().body_string("{}").build())
+            .await
+            .unwrap();
+        let subscriber = TypedSubscriber::with_fn(queue.clone(), |_, _: Job| Ack::Ok)
+            .with_dead_letter(dead_letter.clone())
+            .into_subscriber();
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Ok));
+
+        let dead = dead_letter.receive().await.unwrap().unwrap();
+        assert_eq!(dead.body_string(), Some("{}".to_string()));
+        assert!(dead.header(DEAD_LETTER_REASON_HEADER).is_some());
+        assert!(queue.is_empty().await.unwrap());
+    }
+
+    #[cfg(feature = "schema")]
+    #[tokio::test]
+    async fn test_schema_validation() {
+        let mut registry = SchemaRegistry::new();
+        registry
+            .register(
+                "job.v1",
+                &serde_json::json!({
+                    "type": "object",
+                    "properties": { "prompt": { "type": "string", "minLength": 1 } }
+                }),
+            )
+            .unwrap();
+        registry
+            .register("job.v2", &serde_json::json!({ "type": "object" }))
+            .unwrap();
+        let registry = Arc::new(registry);
+
+        let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new("jobs"));
+        let publisher = TypedPublisher::new(queue.clone()).with_schema(registry.clone(), "job.v1");
+
+        let err = publisher.publish(&job("")).await.unwrap_err();
+        assert!(matches!(err, InfraError::Schema { .. }));
+        assert!(queue.is_empty().await.unwrap());
+
+        publisher.publish(&job("Hello")).await.unwrap();
+        let message = queue.receive().await.unwrap().unwrap();
+        assert_eq!(message.schema_id(), Some("job.v1"));
+        queue.ack(message.id(), Ack::Requeue).await.unwrap();
+
+        // A consumer expecting a different contract rejects the message
+        let subscriber = TypedSubscriber::with_fn(queue.clone(), |_, _: Job| Ack::Ok)
+            .with_schema(registry, "job.v2")
+            .into_subscriber();
+        assert_eq!(subscriber.process_one().await.unwrap(), Some(Ack::Reject));
+        assert!(queue.is_empty().await.unwrap());
+    }
+}
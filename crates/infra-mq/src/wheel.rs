@@ -0,0 +1,128 @@
+//! Hashed timer wheel for delayed delivery.
+
+use std::time::{Duration, Instant};
+
+/// Timer wheel bucketing items by due tick
+///
+/// Inserting is O(1). Advancing visits one slot per elapsed tick, at most
+/// one full rotation; items due further out than a rotation stay in their
+/// slot until a later pass reaches their tick.
+pub(crate) struct TimerWheel<T> {
+    slots: Vec<Vec<(u64, T)>>,
+    resolution: Duration,
+    origin: Instant,
+    next_tick: u64,
+    len: usize,
+}
+
+impl<T> TimerWheel<T> {
+    /// Create a wheel with `slots` buckets of `resolution` each
+    pub(crate) fn new(slots: usize, resolution: Duration) -> Self {
+        Self {
+            slots: (0..slots.max(1)).map(|_| Vec::new()).collect(),
+            resolution: resolution.max(Duration::from_millis(1)),
+            origin: Instant::now(),
+            next_tick: 0,
+            len: 0,
+        }
+    }
+
+    /// Schedule `item` to be returned once `due` has passed
+    pub(crate) fn insert(&mut self, due: Instant, item: T) {
+        let offset = due.saturating_duration_since(self.origin).as_nanos();
+        let resolution = self.resolution.as_nanos();
+        // Round up so items are never returned early
+        let tick = u64::try_from(offset.div_ceil(resolution)).unwrap_or(u64::MAX);
+        let tick = tick.max(self.next_tick);
+        let slot = self.slot(tick);
+        self.slots[slot].push((tick, item));
+        self.len += 1;
+    }
+
+    /// Remove and return every item due at or before `now`, earliest first
+    pub(crate) fn advance(&mut self, now: Instant) -> Vec<T> {
+        let elapsed =
+            now.saturating_duration_since(self.origin).as_nanos() / self.resolution.as_nanos();
+        let current = u64::try_from(elapsed).unwrap_or(u64::MAX);
+        if current < self.next_tick {
+            return Vec::new();
+        }
+
+        let ticks = (current - self.next_tick + 1).min(self.slots.len() as u64);
+        let mut due = Vec::new();
+        for tick in self.next_tick..self.next_tick + ticks {
+            let slot = self.slot(tick);
+            let (ready, waiting) = std::mem::take(&mut self.slots[slot])
+                .into_iter()
+                .partition(|(tick, _)| *tick <= current);
+            self.slots[slot] = waiting;
+            due.extend::<Vec<_>>(ready);
+        }
+        self.next_tick = current + 1;
+        self.len -= due.len();
+
+        due.sort_by_key(|(tick, _)| *tick);
+        due.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// Keep only the items matching `keep`, returning how many were removed
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) -> usize {
+        let before = self.len;
+        for slot in &mut self.slots {
+            slot.retain(|(_, item)| keep(item));
+        }
+        self.len = self.slots.iter().map(Vec::len).sum();
+        before - self.len
+    }
+
+    fn slot(&self, tick: u64) -> usize {
+        (tick % self.slots.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wheel_returns_due_items_in_order() {
+        let mut wheel = TimerWheel::new(8, Duration::from_millis(10));
+        let origin = wheel.origin;
+
+        wheel.insert(origin + Duration::from_millis(30), "c");
+        wheel.insert(origin + Duration::from_millis(10), "a");
+        wheel.insert(origin + Duration::from_millis(20), "b");
+        // More than one rotation out
+        wheel.insert(origin + Duration::from_millis(250), "d");
+
+        assert!(wheel.advance(origin + Duration::from_millis(5)).is_empty());
+        assert_eq!(
+            wheel.advance(origin + Duration::from_millis(35)),
+            vec!["a", "b", "c"]
+        );
+        // Shares a slot with tick 1, but is not due yet
+        assert!(wheel
+            .advance(origin + Duration::from_millis(200))
+            .is_empty());
+        assert_eq!(
+            wheel.advance(origin + Duration::from_millis(250)),
+            vec!["d"]
+        );
+        assert_eq!(wheel.len, 0);
+    }
+
+    #[test]
+    fn test_wheel_retain() {
+        let mut wheel = TimerWheel::new(4, Duration::from_millis(10));
+        let origin = wheel.origin;
+        for i in 0..6 {
+            wheel.insert(origin + Duration::from_millis(i * 10), i);
+        }
+
+        assert_eq!(wheel.retain(|i| i % 2 == 0), 3);
+        assert_eq!(
+            wheel.advance(origin + Duration::from_secs(1)),
+            vec![0, 2, 4]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Built-in runtime and process metrics.
+
+use crate::metrics::MetricsRegistry;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Number of tokio worker threads
+pub const TOKIO_WORKERS: &str = "tokio.workers";
+/// Number of alive tokio tasks
+pub const TOKIO_ALIVE_TASKS: &str = "tokio.tasks.alive";
+/// Number of tasks in the tokio global queue
+pub const TOKIO_GLOBAL_QUEUE_DEPTH: &str = "tokio.global_queue.depth";
+/// Total process CPU time (user + system) in milliseconds
+pub const PROCESS_CPU_TIME_MS: &str = "process.cpu.time_ms";
+/// Process resident set size in bytes
+pub const PROCESS_RSS_BYTES: &str = "process.memory.rss_bytes";
+/// Number of open file descriptors
+pub const PROCESS_OPEN_FDS: &str = "process.open_fds";
+
+/// Collector sampling tokio runtime and process metrics into a registry
+///
+/// Process metrics are read from `/proc` and are only available on Linux.
+pub struct RuntimeCollector {
+    registry: Arc<MetricsRegistry>,
+    interval: Duration,
+}
+
+impl RuntimeCollector {
+    /// Create a collector writing to a registry
+    pub fn new(registry: Arc<MetricsRegistry>) -> Self {
+        Self {
+            registry,
+            interval: Duration::from_secs(15),
+        }
+    }
+
+    /// Set the collection interval
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Collect all metrics once
+    pub fn collect_once(&self) {
+        self.collect_runtime();
+        self.collect_process();
+    }
+
+    /// Collect on the configured interval until the task is aborted
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.interval);
+            loop {
+                ticker.tick().await;
+                self.collect_once();
+            }
+        })
+    }
+
+    fn set(&self, name: &str, value: u64) {
+        self.registry
+            .gauge(name)
+            .set(i64::try_from(value).unwrap_or(i64::MAX));
+    }
+
+    fn collect_runtime(&self) {
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let metrics = handle.metrics();
+        self.set(TOKIO_WORKERS, metrics.num_workers() as u64);
+        self.set(TOKIO_ALIVE_TASKS, metrics.num_alive_tasks() as u64);
+        self.set(TOKIO_GLOBAL_QUEUE_DEPTH, metrics.global_queue_depth() as u64);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn collect_process(&self) {
+        if let Some(cpu_ms) = procfs::cpu_time_ms() {
+            self.set(PROCESS_CPU_TIME_MS, cpu_ms);
+        }
+        if let Some(rss) = procfs::rss_bytes() {
+            self.set(PROCESS_RSS_BYTES, rss);
+        }
+        if let Some(fds) = procfs::open_fds() {
+            self.set(PROCESS_OPEN_FDS, fds);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn collect_process(&self) {}
+}
+
+#[cfg(target_os = "linux")]
+mod procfs {
+    use std::fs;
+
+    /// Kernel clock ticks per second (`USER_HZ`), fixed at 100 on Linux
+    const TICKS_PER_SECOND: u64 = 100;
+
+    pub(super) fn cpu_time_ms() -> Option<u64> {
+        let stat = fs::read_to_string("/proc/self/stat").ok()?;
+        // The command name may contain spaces; fields resume after the last ')'
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        // utime and stime are fields 14 and 15 of the full line
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        Some((utime + stime) * 1000 / TICKS_PER_SECOND)
+    }
+
+    pub(super) fn rss_bytes() -> Option<u64> {
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb * 1024)
+    }
+
+    pub(super) fn open_fds() -> Option<u64> {
+        Some(fs::read_dir("/proc/self/fd").ok()?.count() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_once() {
+        let registry = Arc::new(MetricsRegistry::new());
+        RuntimeCollector::new(registry.clone()).collect_once();
+
+        assert!(registry.gauge(TOKIO_WORKERS).get() >= 1);
+        #[cfg(target_os = "linux")]
+        {
+            assert!(registry.gauge(PROCESS_RSS_BYTES).get() > 0);
+            assert!(registry.gauge(PROCESS_OPEN_FDS).get() > 0);
+        }
+    }
+}
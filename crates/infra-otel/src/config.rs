@@ -1,6 +1,16 @@
 //! OpenTelemetry configuration.
 
+use crate::sampling::RuntimeSampler;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Default OTLP export timeout
+pub const DEFAULT_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_export_timeout() -> Duration {
+    DEFAULT_EXPORT_TIMEOUT
+}
 
 /// Exporter configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +21,15 @@ pub enum ExporterConfig {
     Otlp {
         endpoint: String,
         protocol: OtlpProtocol,
+        /// Headers (gRPC metadata) sent with every export
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Payload compression
+        #[serde(default)]
+        compression: Option<OtlpCompression>,
+        /// Export request timeout
+        #[serde(default = "default_export_timeout")]
+        timeout: Duration,
     },
     /// Jaeger exporter
     Jaeger {
@@ -26,6 +45,87 @@ impl Default for ExporterConfig {
     }
 }
 
+impl ExporterConfig {
+    /// Create an OTLP exporter configuration
+    pub fn otlp(endpoint: impl Into<String>, protocol: OtlpProtocol) -> Self {
+        Self::Otlp {
+            endpoint: endpoint.into(),
+            protocol,
+            headers: HashMap::new(),
+            compression: None,
+            timeout: DEFAULT_EXPORT_TIMEOUT,
+        }
+    }
+
+    /// Create an OTLP/gRPC exporter configuration
+    pub fn otlp_grpc(endpoint: impl Into<String>) -> Self {
+        Self::otlp(endpoint, OtlpProtocol::Grpc)
+    }
+
+    /// Create an OTLP/HTTP (protobuf) exporter configuration
+    pub fn otlp_http(endpoint: impl Into<String>) -> Self {
+        Self::otlp(endpoint, OtlpProtocol::HttpBinary)
+    }
+
+    /// Add an export header (OTLP only)
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if let Self::Otlp { headers, .. } = &mut self {
+            headers.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    /// Set payload compression (OTLP only)
+    pub fn with_compression(mut self, value: OtlpCompression) -> Self {
+        if let Self::Otlp { compression, .. } = &mut self {
+            *compression = Some(value);
+        }
+        self
+    }
+
+    /// Set the export timeout (OTLP only)
+    pub fn with_timeout(mut self, value: Duration) -> Self {
+        if let Self::Otlp { timeout, .. } = &mut self {
+            *timeout = value;
+        }
+        self
+    }
+}
+
+/// OTLP payload compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtlpCompression {
+    Gzip,
+    Zstd,
+}
+
+/// Batching and queueing limits for exporters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// Maximum number of spans buffered before new spans are dropped
+    pub max_queue_size: usize,
+    /// Maximum number of spans per export request
+    pub max_export_batch_size: usize,
+    /// Delay between span exports
+    pub scheduled_delay: Duration,
+    /// Timeout for a single export
+    pub max_export_timeout: Duration,
+    /// Interval between metric exports
+    pub metrics_interval: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_queue_size: 2048,
+            max_export_batch_size: 512,
+            scheduled_delay: Duration::from_secs(5),
+            max_export_timeout: Duration::from_secs(30),
+            metrics_interval: Duration::from_secs(60),
+        }
+    }
+}
+
 /// OTLP protocol
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum OtlpProtocol {
@@ -55,8 +155,14 @@ pub struct OtelConfig {
     pub trace_exporter: ExporterConfig,
     /// Metrics exporter configuration
     pub metrics_exporter: ExporterConfig,
+    /// Export batching and queue limits
+    #[serde(default)]
+    pub batch: BatchConfig,
     /// Sample ratio (0.0 to 1.0)
     pub sample_ratio: f64,
+    /// Runtime sampler; overrides `sample_ratio` when set
+    #[serde(skip)]
+    pub sampler: Option<RuntimeSampler>,
     /// Enable console logging
     pub console_logging: bool,
     /// Log level filter
@@ -74,7 +180,9 @@ impl Default for OtelConfig {
             environment: None,
             trace_exporter: ExporterConfig::default(),
             metrics_exporter: ExporterConfig::default(),
+            batch: BatchConfig::default(),
             sample_ratio: 1.0,
+            sampler: None,
             console_logging: true,
             log_level: "info".to_string(),
             json_logs: false,
@@ -132,6 +240,18 @@ impl OtelConfigBuilder {
         self
     }
 
+    /// Set export batching and queue limits
+    pub fn batch(mut self, batch: BatchConfig) -> Self {
+        self.config.batch = batch;
+        self
+    }
+
+    /// Set a runtime sampler whose strategy can be changed later
+    pub fn sampler(mut self, sampler: RuntimeSampler) -> Self {
+        self.config.sampler = Some(sampler);
+        self
+    }
+
     /// Set sample ratio
     pub fn sample_ratio(mut self, ratio: f64) -> Self {
         self.config.sample_ratio = ratio.clamp(0.0, 1.0);
@@ -1,6 +1,9 @@
 //! Trace context and propagation.
 
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use infra_errors::TraceIds;
 use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 /// Trace context for distributed tracing
 #[derive(Debug, Clone)]
@@ -17,10 +20,56 @@ pub struct TraceContext {
 
 impl TraceContext {
     /// Create from current span (requires tracing-opentelemetry integration)
+    ///
+    /// Returns `None` outside a span, or when no OpenTelemetry layer is
+    /// installed, for example before [`init_tracing`](crate::init_tracing).
     pub fn current() -> Option<Self> {
-        // This requires the OpenTelemetrySpanExt trait to be properly set up
-        // For now, return None as a placeholder
-        None
+        let context = tracing::Span::current().context();
+        let span = context.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() {
+            return None;
+        }
+
+        let trace_state = span_context.trace_state().header();
+        Some(Self {
+            trace_id: span_context.trace_id().to_string(),
+            span_id: span_context.span_id().to_string(),
+            trace_flags: span_context.trace_flags().to_u8(),
+            trace_state: (!trace_state.is_empty()).then_some(trace_state),
+        })
+    }
+
+    /// Convert to the trace IDs recorded in error contexts
+    pub fn trace_ids(&self) -> TraceIds {
+        TraceIds::new(Some(self.trace_id.clone()), Some(self.span_id.clone()))
+    }
+
+    /// Make `span` a child of this context, typically received from a remote
+    /// service, so both appear in the same trace
+    ///
+    /// Does nothing if the IDs are not valid hex.
+    pub fn attach(&self, span: &tracing::Span) {
+        let (Ok(trace_id), Ok(span_id)) = (
+            TraceId::from_hex(&self.trace_id),
+            SpanId::from_hex(&self.span_id),
+        ) else {
+            return;
+        };
+        let trace_state = self
+            .trace_state
+            .as_deref()
+            .and_then(|state| state.parse::<TraceState>().ok())
+            .unwrap_or_default();
+
+        let remote = SpanContext::new(
+            trace_id,
+            span_id,
+            TraceFlags::new(self.trace_flags),
+            true,
+            trace_state,
+        );
+        span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote));
     }
 
     /// Create from trace and span IDs
@@ -59,6 +108,13 @@ impl TraceContext {
     }
 }
 
+/// Get the trace IDs of the current span, installed by
+/// [`init_tracing`](crate::init_tracing) as the provider of
+/// [`TraceIds::current`]
+pub(crate) fn current_trace_ids() -> Option<TraceIds> {
+    TraceContext::current().map(|context| context.trace_ids())
+}
+
 /// Context propagation for distributed systems
 #[derive(Debug, Clone, Default)]
 pub struct PropagationContext {
@@ -122,6 +178,34 @@ mod tests {
         assert_eq!(parsed.span_id, ctx.span_id);
     }
 
+    #[test]
+    fn test_attach_continues_remote_trace() {
+        use opentelemetry::trace::TracerProvider as _;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let remote = TraceContext::new("0af7651916cd43dd8448eb211c80319c", "b7ad6b7169203331");
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(TraceContext::current().is_none());
+
+            let span = tracing::info_span!("consume");
+            remote.attach(&span);
+            let _entered = span.enter();
+
+            let current = TraceContext::current().unwrap();
+            assert_eq!(current.trace_id, remote.trace_id);
+            assert_ne!(current.span_id, remote.span_id);
+
+            infra_errors::set_trace_ids_provider(current_trace_ids);
+            let context = infra_errors::err_ctx!();
+            assert_eq!(context.trace_ids.trace_id, Some(remote.trace_id.clone()));
+            assert_eq!(context.trace_ids.span_id, Some(current.span_id));
+        });
+    }
+
     #[test]
     fn test_propagation_context() {
         let mut ctx = PropagationContext::new();
@@ -5,7 +5,12 @@ use infra_errors::{InfraError, InfraResult};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 /// Initialize tracing with OpenTelemetry
+///
+/// Also makes errors record the current trace and span IDs in contexts
+/// created with [`infra_errors::err_ctx!`].
 pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
+    infra_errors::set_trace_ids_provider(crate::context::current_trace_ids);
+
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(&config.log_level));
 
@@ -25,6 +30,7 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                         message: e.to_string(),
                         retry_after: None,
                         context: None,
+                        source: None,
                     }
                 })?;
             } else {
@@ -38,6 +44,7 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                         message: e.to_string(),
                         retry_after: None,
                         context: None,
+                        source: None,
                     }
                 })?;
             }
@@ -51,33 +58,20 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                     message: e.to_string(),
                     retry_after: None,
                     context: None,
+                    source: None,
                 }
             })?;
         }
         #[cfg(feature = "otlp")]
-        ExporterConfig::Otlp { endpoint, protocol } => {
-            use opentelemetry_otlp::WithExportConfig;
-            use opentelemetry_sdk::trace::TracerProvider;
-
-            let exporter = opentelemetry_otlp::SpanExporter::builder()
-                .with_tonic()
-                .with_endpoint(endpoint)
-                .build()
-                .map_err(|e| InfraError::External {
-                    service: "otlp".to_string(),
-                    operation: "init".to_string(),
-                    message: e.to_string(),
-                    retry_after: None,
-                    context: None,
-                })?;
-
-            let provider = TracerProvider::builder()
-                .with_batch_exporter(exporter)
-                .build();
+        ExporterConfig::Otlp { .. } => {
+            use opentelemetry::trace::TracerProvider as _;
 
+            let provider = otlp::tracer_provider(config)?;
             let tracer = provider.tracer(config.service_name.clone());
-            let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+            opentelemetry::global::set_tracer_provider(provider.clone());
+            otlp::store_tracer_provider(provider);
 
+            let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
             let fmt_layer = tracing_subscriber::fmt::layer().with_target(true);
 
             subscriber
@@ -90,6 +84,7 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                     message: e.to_string(),
                     retry_after: None,
                     context: None,
+                    source: None,
                 })?;
         }
         #[cfg(not(feature = "otlp"))]
@@ -98,6 +93,7 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                 key: Some("trace_exporter".to_string()),
                 message: "OTLP exporter requires 'otlp' feature".to_string(),
                 context: None,
+                source: None,
             });
         }
         #[cfg(feature = "jaeger")]
@@ -107,6 +103,7 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                 key: Some("trace_exporter".to_string()),
                 message: "Jaeger exporter not yet implemented".to_string(),
                 context: None,
+                source: None,
             });
         }
         #[cfg(not(feature = "jaeger"))]
@@ -115,6 +112,7 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
                 key: Some("trace_exporter".to_string()),
                 message: "Jaeger exporter requires 'jaeger' feature".to_string(),
                 context: None,
+                source: None,
             });
         }
     }
@@ -123,11 +121,28 @@ pub fn init_tracing(config: &OtelConfig) -> InfraResult<()> {
 }
 
 /// Initialize metrics
+///
+/// With an OTLP metrics exporter this installs a global meter provider that
+/// pushes on `batch.metrics_interval`; other exporters are a no-op.
 #[cfg(feature = "metrics")]
-pub fn init_metrics(_config: &OtelConfig) -> InfraResult<()> {
-    // Metrics initialization
-    // For now, just a placeholder that can be extended
-    Ok(())
+pub fn init_metrics(config: &OtelConfig) -> InfraResult<()> {
+    match &config.metrics_exporter {
+        #[cfg(feature = "otlp")]
+        ExporterConfig::Otlp { .. } => {
+            let provider = otlp::meter_provider(config)?;
+            opentelemetry::global::set_meter_provider(provider.clone());
+            otlp::store_meter_provider(provider);
+            Ok(())
+        }
+        #[cfg(not(feature = "otlp"))]
+        ExporterConfig::Otlp { .. } => Err(InfraError::Config {
+            key: Some("metrics_exporter".to_string()),
+            message: "OTLP exporter requires 'otlp' feature".to_string(),
+            context: None,
+            source: None,
+        }),
+        _ => Ok(()),
+    }
 }
 
 #[cfg(not(feature = "metrics"))]
@@ -136,6 +151,227 @@ pub fn init_metrics(_config: &OtelConfig) -> InfraResult<()> {
 }
 
 /// Shutdown OpenTelemetry
-pub fn shutdown() {
+///
+/// Flushes queued spans and metrics to their exporters before shutting the
+/// providers down. Returns the first flush or shutdown error.
+pub fn shutdown() -> InfraResult<()> {
+    #[cfg(feature = "otlp")]
+    let result = otlp::shutdown();
+    #[cfg(not(feature = "otlp"))]
+    let result = Ok(());
+
     opentelemetry::global::shutdown_tracer_provider();
+    result
+}
+
+/// OTLP exporter pipelines
+#[cfg(feature = "otlp")]
+mod otlp {
+    use crate::config::{BatchConfig, ExporterConfig, OtelConfig, OtlpCompression, OtlpProtocol};
+    use crate::sampling::{RuntimeSampler, SamplingConfig, TailSamplingProcessor};
+    use infra_errors::{InfraError, InfraResult};
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{
+        Compression, MetricExporter, Protocol, SpanExporter, WithExportConfig, WithHttpConfig,
+        WithTonicConfig,
+    };
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::runtime;
+    use opentelemetry_sdk::trace::{self, BatchSpanProcessor, TracerProvider};
+    use opentelemetry_sdk::Resource;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tonic::metadata::{AsciiMetadataKey, MetadataMap, MetadataValue};
+
+    static TRACER_PROVIDER: Mutex<Option<TracerProvider>> = Mutex::new(None);
+    static METER_PROVIDER: Mutex<Option<SdkMeterProvider>> = Mutex::new(None);
+
+    fn otlp_error(operation: &str, message: impl ToString) -> InfraError {
+        InfraError::External {
+            service: "otlp".to_string(),
+            operation: operation.to_string(),
+            message: message.to_string(),
+            retry_after: None,
+            context: None,
+            source: None,
+        }
+    }
+
+    fn resource(config: &OtelConfig) -> Resource {
+        let mut attributes = vec![KeyValue::new("service.name", config.service_name.clone())];
+        if let Some(version) = &config.service_version {
+            attributes.push(KeyValue::new("service.version", version.clone()));
+        }
+        if let Some(namespace) = &config.service_namespace {
+            attributes.push(KeyValue::new("service.namespace", namespace.clone()));
+        }
+        if let Some(environment) = &config.environment {
+            attributes.push(KeyValue::new("deployment.environment", environment.clone()));
+        }
+        Resource::new(attributes)
+    }
+
+    fn compression(value: OtlpCompression) -> Compression {
+        match value {
+            OtlpCompression::Gzip => Compression::Gzip,
+            OtlpCompression::Zstd => Compression::Zstd,
+        }
+    }
+
+    fn metadata(headers: &HashMap<String, String>) -> InfraResult<MetadataMap> {
+        let mut map = MetadataMap::new();
+        for (key, value) in headers {
+            let key = AsciiMetadataKey::from_bytes(key.as_bytes())
+                .map_err(|e| otlp_error("init", format!("invalid header '{key}': {e}")))?;
+            let value = MetadataValue::try_from(value.as_str())
+                .map_err(|e| otlp_error("init", format!("invalid header value: {e}")))?;
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+
+    /// Apply endpoint settings to an exporter builder for either transport
+    macro_rules! build_exporter {
+        ($builder:expr, $exporter:expr) => {{
+            let ExporterConfig::Otlp {
+                endpoint,
+                protocol,
+                headers,
+                compression: compress,
+                timeout,
+            } = $exporter
+            else {
+                unreachable!("build_exporter called with a non-OTLP exporter");
+            };
+            match protocol {
+                OtlpProtocol::Grpc => {
+                    let mut builder = $builder
+                        .with_tonic()
+                        .with_endpoint(endpoint.clone())
+                        .with_timeout(*timeout)
+                        .with_metadata(metadata(headers)?);
+                    if let Some(c) = compress {
+                        builder = builder.with_compression(compression(*c));
+                    }
+                    builder.build()
+                }
+                OtlpProtocol::HttpBinary | OtlpProtocol::HttpJson => {
+                    let wire = if matches!(protocol, OtlpProtocol::HttpJson) {
+                        Protocol::HttpJson
+                    } else {
+                        Protocol::HttpBinary
+                    };
+                    if compress.is_some() {
+                        tracing::warn!("OTLP compression is only supported over gRPC, ignoring");
+                    }
+                    $builder
+                        .with_http()
+                        .with_endpoint(endpoint.clone())
+                        .with_protocol(wire)
+                        .with_timeout(*timeout)
+                        .with_headers(headers.clone())
+                        .build()
+                }
+            }
+        }};
+    }
+
+    fn batch_config(batch: &BatchConfig) -> trace::BatchConfig {
+        trace::BatchConfigBuilder::default()
+            .with_max_queue_size(batch.max_queue_size)
+            .with_max_export_batch_size(batch.max_export_batch_size)
+            .with_scheduled_delay(batch.scheduled_delay)
+            .with_max_export_timeout(batch.max_export_timeout)
+            .build()
+    }
+
+    /// Build a batching tracer provider for the configured OTLP endpoint
+    pub(super) fn tracer_provider(config: &OtelConfig) -> InfraResult<TracerProvider> {
+        let exporter: SpanExporter =
+            build_exporter!(SpanExporter::builder(), &config.trace_exporter)
+                .map_err(|e| otlp_error("init", e))?;
+
+        let processor = BatchSpanProcessor::builder(exporter, runtime::Tokio)
+            .with_batch_config(batch_config(&config.batch))
+            .build();
+
+        let sampler = config
+            .sampler
+            .clone()
+            .unwrap_or_else(|| RuntimeSampler::new(SamplingConfig::ratio(config.sample_ratio)));
+
+        Ok(TracerProvider::builder()
+            .with_span_processor(TailSamplingProcessor::new(processor))
+            .with_sampler(sampler)
+            .with_resource(resource(config))
+            .build())
+    }
+
+    /// Build a periodic-push meter provider for the configured OTLP endpoint
+    pub(super) fn meter_provider(config: &OtelConfig) -> InfraResult<SdkMeterProvider> {
+        let exporter: MetricExporter =
+            build_exporter!(MetricExporter::builder(), &config.metrics_exporter)
+                .map_err(|e| otlp_error("init", e))?;
+
+        let reader = PeriodicReader::builder(exporter, runtime::Tokio)
+            .with_interval(config.batch.metrics_interval)
+            .with_timeout(config.batch.max_export_timeout)
+            .build();
+
+        Ok(SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(resource(config))
+            .build())
+    }
+
+    pub(super) fn store_tracer_provider(provider: TracerProvider) {
+        let previous = TRACER_PROVIDER
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .replace(provider);
+        drop(previous);
+    }
+
+    pub(super) fn store_meter_provider(provider: SdkMeterProvider) {
+        let previous = METER_PROVIDER
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .replace(provider);
+        drop(previous);
+    }
+
+    /// Flush and shut down the installed providers
+    pub(super) fn shutdown() -> InfraResult<()> {
+        let mut result = Ok(());
+
+        let tracer = TRACER_PROVIDER
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        if let Some(provider) = tracer {
+            for flushed in provider.force_flush() {
+                if let Err(e) = flushed {
+                    result = result.and(Err(otlp_error("flush", e)));
+                }
+            }
+            if let Err(e) = provider.shutdown() {
+                result = result.and(Err(otlp_error("shutdown", e)));
+            }
+        }
+
+        let meter = METER_PROVIDER
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        if let Some(provider) = meter {
+            if let Err(e) = provider.force_flush() {
+                result = result.and(Err(otlp_error("flush", e)));
+            }
+            if let Err(e) = provider.shutdown() {
+                result = result.and(Err(otlp_error("shutdown", e)));
+            }
+        }
+
+        result
+    }
 }
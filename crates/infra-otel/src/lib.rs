@@ -3,17 +3,41 @@
 //! This crate provides unified observability with tracing, metrics, and
 //! distributed context propagation.
 
+#[cfg(feature = "runtime-metrics")]
+mod collector;
 mod config;
 mod context;
 mod init;
 mod span;
+mod timer;
 mod metrics;
+#[cfg(feature = "push")]
+mod push;
+mod resilience;
+mod sampling;
 
-pub use config::{OtelConfig, ExporterConfig};
+#[cfg(feature = "runtime-metrics")]
+pub use collector::{
+    RuntimeCollector, PROCESS_CPU_TIME_MS, PROCESS_OPEN_FDS, PROCESS_RSS_BYTES,
+    TOKIO_ALIVE_TASKS, TOKIO_GLOBAL_QUEUE_DEPTH, TOKIO_WORKERS,
+};
+pub use config::{BatchConfig, ExporterConfig, OtelConfig, OtlpCompression, OtlpProtocol};
 pub use context::{TraceContext, PropagationContext};
 pub use init::{init_tracing, init_metrics, shutdown};
-pub use span::{SpanBuilder, SpanExt};
-pub use metrics::{Counter, Gauge, Histogram, MetricsRegistry};
+#[cfg(feature = "push")]
+pub use push::{encode_json, encode_otlp_json, MetricsPusher, PushFormat, PushTransport};
+pub use resilience::{
+    record_circuit_state_change, record_rate_limited, record_retry_attempt, record_retry_exhausted,
+};
+pub use sampling::{
+    RuntimeSampler, SamplingConfig, SamplingStrategy, TailPredicate, TailSamplingProcessor,
+};
+pub use span::{llm_span, LlmSpanBuilder, LlmSpanExt, SpanBuilder, SpanExt};
+pub use timer::{Timer, OUTCOME_OK};
+pub use metrics::{
+    Bucket, Counter, Exemplar, Gauge, Histogram, HistogramSnapshot, MetricPoint, MetricValue,
+    MetricsRegistry, DEFAULT_MAX_CARDINALITY, OVERFLOW_LABEL,
+};
 
 use infra_errors::InfraResult;
 
@@ -69,4 +93,34 @@ mod tests {
         assert_eq!(config.service_name, "test-service");
         assert_eq!(config.service_version, Some("1.0.0".to_string()));
     }
+
+    #[test]
+    fn test_otlp_exporter_config() {
+        let exporter = ExporterConfig::otlp_grpc("http://collector:4317")
+            .with_header("authorization", "Bearer token")
+            .with_compression(OtlpCompression::Gzip)
+            .with_timeout(std::time::Duration::from_secs(3));
+
+        match exporter {
+            ExporterConfig::Otlp {
+                endpoint,
+                protocol,
+                headers,
+                compression,
+                timeout,
+            } => {
+                assert_eq!(endpoint, "http://collector:4317");
+                assert!(matches!(protocol, OtlpProtocol::Grpc));
+                assert_eq!(headers.get("authorization").unwrap(), "Bearer token");
+                assert_eq!(compression, Some(OtlpCompression::Gzip));
+                assert_eq!(timeout, std::time::Duration::from_secs(3));
+            }
+            other => panic!("unexpected exporter: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_shutdown_without_init() {
+        assert!(shutdown().is_ok());
+    }
 }
@@ -2,7 +2,10 @@
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+use crate::context::TraceContext;
 
 /// Counter metric
 pub struct Counter {
@@ -49,6 +52,11 @@ impl Counter {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Get the metric labels
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
 }
 
 /// Gauge metric
@@ -68,6 +76,15 @@ impl Gauge {
         }
     }
 
+    /// Create with labels
+    pub fn with_labels(name: impl Into<String>, labels: HashMap<String, String>) -> Self {
+        Self {
+            value: AtomicI64::new(0),
+            name: name.into(),
+            labels,
+        }
+    }
+
     /// Set the value
     pub fn set(&self, value: i64) {
         self.value.store(value, Ordering::Relaxed);
@@ -97,48 +114,172 @@ impl Gauge {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Get the metric labels
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+}
+
+/// Default histogram buckets (seconds)
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Default quantiles tracked by histograms
+pub const DEFAULT_QUANTILES: &[f64] = &[0.5, 0.95, 0.99];
+
+/// A sample value linked to the trace it was observed in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exemplar {
+    /// Observed value
+    pub value: f64,
+    /// Trace ID of the observation
+    pub trace_id: String,
+    /// Observation time
+    pub timestamp: SystemTime,
+}
+
+/// Cumulative bucket count
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    /// Inclusive upper bound (`f64::INFINITY` for the overflow bucket)
+    pub upper_bound: f64,
+    /// Number of observations less than or equal to the bound
+    pub count: u64,
+    /// Most recent exemplar falling into this bucket
+    pub exemplar: Option<Exemplar>,
+}
+
+/// Point-in-time view of a histogram
+#[derive(Debug, Clone)]
+pub struct HistogramSnapshot {
+    /// Metric name
+    pub name: String,
+    /// Number of observations
+    pub count: u64,
+    /// Sum of observed values
+    pub sum: f64,
+    /// Metric labels
+    pub labels: HashMap<String, String>,
+    /// Cumulative buckets, ending with `+Inf`
+    pub buckets: Vec<Bucket>,
+    /// Estimated `(quantile, value)` pairs
+    pub quantiles: Vec<(f64, f64)>,
 }
 
 /// Histogram metric
+///
+/// Tracks bucket counts, a float sum, streaming quantile estimates (P²
+/// algorithm) and per-bucket exemplars.
 pub struct Histogram {
     buckets: Vec<f64>,
     counts: Vec<AtomicU64>,
     sum: AtomicU64,
     count: AtomicU64,
+    quantiles: Mutex<Vec<P2Quantile>>,
+    exemplars: Mutex<Vec<Option<Exemplar>>>,
     name: String,
+    labels: HashMap<String, String>,
 }
 
 impl Histogram {
     /// Create a new histogram with default buckets
     pub fn new(name: impl Into<String>) -> Self {
-        Self::with_buckets(
-            name,
-            vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0],
-        )
+        Self::with_buckets(name, DEFAULT_BUCKETS.to_vec())
     }
 
     /// Create with custom buckets
-    pub fn with_buckets(name: impl Into<String>, buckets: Vec<f64>) -> Self {
-        let counts = buckets.iter().map(|_| AtomicU64::new(0)).collect();
+    pub fn with_buckets(name: impl Into<String>, mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|b| b.is_finite());
+        buckets.sort_by(f64::total_cmp);
+        buckets.dedup();
+
+        // One extra slot for the +Inf bucket
+        let slots = buckets.len() + 1;
         Self {
             buckets,
-            counts,
-            sum: AtomicU64::new(0),
+            counts: (0..slots).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
             count: AtomicU64::new(0),
+            quantiles: Mutex::new(
+                DEFAULT_QUANTILES
+                    .iter()
+                    .map(|&q| P2Quantile::new(q))
+                    .collect(),
+            ),
+            exemplars: Mutex::new(vec![None; slots]),
             name: name.into(),
+            labels: HashMap::new(),
         }
     }
 
+    /// Create with default buckets and labels
+    pub fn with_labels(name: impl Into<String>, labels: HashMap<String, String>) -> Self {
+        Self {
+            labels,
+            ..Self::new(name)
+        }
+    }
+
+    /// Set the quantiles to estimate (each in `0.0..=1.0`)
+    pub fn with_quantiles(self, quantiles: &[f64]) -> Self {
+        let estimators = quantiles
+            .iter()
+            .filter(|q| (0.0..=1.0).contains(*q))
+            .map(|&q| P2Quantile::new(q))
+            .collect();
+        *self.quantiles.lock().unwrap() = estimators;
+        self
+    }
+
     /// Observe a value
     pub fn observe(&self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let slot = self.buckets.partition_point(|b| *b < value);
+        self.counts[slot].fetch_add(1, Ordering::Relaxed);
         self.count.fetch_add(1, Ordering::Relaxed);
-        self.sum.fetch_add(value.to_bits(), Ordering::Relaxed);
-
-        for (i, bucket) in self.buckets.iter().enumerate() {
-            if value <= *bucket {
-                self.counts[i].fetch_add(1, Ordering::Relaxed);
+        let mut current = self.sum.load(Ordering::Relaxed);
+        loop {
+            let next = (f64::from_bits(current) + value).to_bits();
+            match self.sum.compare_exchange_weak(
+                current,
+                next,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
             }
         }
+
+        for estimator in self.quantiles.lock().unwrap().iter_mut() {
+            estimator.observe(value);
+        }
+    }
+
+    /// Observe a value and attach the trace ID as an exemplar
+    pub fn observe_with_exemplar(&self, value: f64, trace_id: impl Into<String>) {
+        if value.is_nan() {
+            return;
+        }
+        self.observe(value);
+        let slot = self.buckets.partition_point(|b| *b < value);
+        self.exemplars.lock().unwrap()[slot] = Some(Exemplar {
+            value,
+            trace_id: trace_id.into(),
+            timestamp: SystemTime::now(),
+        });
+    }
+
+    /// Observe a value, attaching the current trace as an exemplar if any
+    pub fn observe_traced(&self, value: f64) {
+        match TraceContext::current() {
+            Some(ctx) => self.observe_with_exemplar(value, ctx.trace_id),
+            None => self.observe(value),
+        }
     }
 
     /// Get observation count
@@ -146,71 +287,361 @@ impl Histogram {
         self.count.load(Ordering::Relaxed)
     }
 
+    /// Get the sum of observed values
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
+
+    /// Get the mean of observed values
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.sum() / count as f64)
+    }
+
+    /// Get the estimate for a tracked quantile
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        self.quantiles
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| (e.q - q).abs() < f64::EPSILON)
+            .and_then(P2Quantile::estimate)
+    }
+
+    /// Get the estimated median
+    pub fn p50(&self) -> Option<f64> {
+        self.quantile(0.5)
+    }
+
+    /// Get the estimated 95th percentile
+    pub fn p95(&self) -> Option<f64> {
+        self.quantile(0.95)
+    }
+
+    /// Get the estimated 99th percentile
+    pub fn p99(&self) -> Option<f64> {
+        self.quantile(0.99)
+    }
+
+    /// Get cumulative bucket counts, ending with `+Inf`
+    pub fn buckets(&self) -> Vec<Bucket> {
+        let exemplars = self.exemplars.lock().unwrap();
+        let mut cumulative = 0;
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                cumulative += count.load(Ordering::Relaxed);
+                Bucket {
+                    upper_bound: self.buckets.get(i).copied().unwrap_or(f64::INFINITY),
+                    count: cumulative,
+                    exemplar: exemplars[i].clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Take a snapshot for export
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let quantiles = self
+            .quantiles
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|e| e.estimate().map(|v| (e.q, v)))
+            .collect();
+        HistogramSnapshot {
+            name: self.name.clone(),
+            labels: self.labels.clone(),
+            count: self.count(),
+            sum: self.sum(),
+            buckets: self.buckets(),
+            quantiles,
+        }
+    }
+
     /// Get the metric name
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Get the metric labels
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+}
+
+/// Streaming quantile estimator using the P² algorithm (Jain & Chlamtac)
+///
+/// Keeps five markers instead of the full sample, so memory is constant.
+#[derive(Debug, Clone)]
+struct P2Quantile {
+    q: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired: [f64; 5],
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    fn new(q: f64) -> Self {
+        Self {
+            q,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * q, 1.0 + 4.0 * q, 3.0 + 2.0 * q, 5.0],
+            increments: [0.0, q / 2.0, q, (1.0 + q) / 2.0, 1.0],
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(f64::total_cmp);
+            }
+            return;
+        }
+        self.count += 1;
+
+        let h = &mut self.heights;
+        let k = if value < h[0] {
+            h[0] = value;
+            0
+        } else if value >= h[4] {
+            h[4] = value;
+            3
+        } else {
+            (1..5).find(|&i| value < h[i]).map_or(3, |i| i - 1)
+        };
+
+        for position in &mut self.positions[k + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(self.increments) {
+            *desired += increment;
+        }
+
+        for i in 1..4 {
+            let d = self.desired[i] - self.positions[i];
+            let n = &self.positions;
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let s = d.signum();
+                let candidate = self.parabolic(i, s);
+                self.heights[i] =
+                    if self.heights[i - 1] < candidate && candidate < self.heights[i + 1] {
+                        candidate
+                    } else {
+                        self.linear(i, s)
+                    };
+                self.positions[i] += s;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, s: f64) -> f64 {
+        let (h, n) = (&self.heights, &self.positions);
+        h[i] + s / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + s) * (h[i + 1] - h[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - s) * (h[i] - h[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, s: f64) -> f64 {
+        let j = if s > 0.0 { i + 1 } else { i - 1 };
+        self.heights[i]
+            + s * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+
+    fn estimate(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            n if n < 5 => {
+                let mut sample = self.heights[..n].to_vec();
+                sample.sort_by(f64::total_cmp);
+                let rank = ((n - 1) as f64 * self.q).round() as usize;
+                Some(sample[rank])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Default maximum number of label sets per metric
+pub const DEFAULT_MAX_CARDINALITY: usize = 1000;
+
+/// Label attached to the series that absorbs label sets over the cardinality limit
+pub const OVERFLOW_LABEL: &str = "otel.metric.overflow";
+
+/// Sorted label pairs identifying a series
+type LabelSet = Vec<(String, String)>;
+
+/// Series of one metric, keyed by label set
+type Series<T> = RwLock<HashMap<String, HashMap<LabelSet, Arc<T>>>>;
+
+fn label_set(labels: &[(&str, &str)]) -> LabelSet {
+    let mut set: LabelSet = labels
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect();
+    set.sort();
+    set.dedup_by(|a, b| a.0 == b.0);
+    set
+}
+
+/// Exported metric value
+#[derive(Debug, Clone)]
+pub enum MetricValue {
+    Counter(u64),
+    Gauge(i64),
+    Histogram(HistogramSnapshot),
+}
+
+/// A single exported series
+#[derive(Debug, Clone)]
+pub struct MetricPoint {
+    /// Metric name
+    pub name: String,
+    /// Series labels
+    pub labels: HashMap<String, String>,
+    /// Current value
+    pub value: MetricValue,
 }
 
 /// Metrics registry
+///
+/// Metrics are keyed by name and label set. Each metric holds at most
+/// `max_cardinality` label sets; further label sets are folded into a single
+/// series labeled [`OVERFLOW_LABEL`].
 pub struct MetricsRegistry {
-    counters: RwLock<HashMap<String, Arc<Counter>>>,
-    gauges: RwLock<HashMap<String, Arc<Gauge>>>,
-    histograms: RwLock<HashMap<String, Arc<Histogram>>>,
+    counters: Series<Counter>,
+    gauges: Series<Gauge>,
+    histograms: Series<Histogram>,
+    max_cardinality: usize,
 }
 
 impl MetricsRegistry {
     /// Create a new registry
     pub fn new() -> Self {
+        Self::with_max_cardinality(DEFAULT_MAX_CARDINALITY)
+    }
+
+    /// Create a registry with a per-metric cardinality limit
+    pub fn with_max_cardinality(max_cardinality: usize) -> Self {
         Self {
             counters: RwLock::new(HashMap::new()),
             gauges: RwLock::new(HashMap::new()),
             histograms: RwLock::new(HashMap::new()),
+            max_cardinality,
         }
     }
 
     /// Get or create a counter
     pub fn counter(&self, name: &str) -> Arc<Counter> {
-        let counters = self.counters.read().unwrap();
-        if let Some(counter) = counters.get(name) {
-            return Arc::clone(counter);
-        }
-        drop(counters);
+        self.counter_with(name, &[])
+    }
 
-        let mut counters = self.counters.write().unwrap();
-        counters
-            .entry(name.to_string())
-            .or_insert_with(|| Arc::new(Counter::new(name)))
-            .clone()
+    /// Get or create a counter series with labels
+    pub fn counter_with(&self, name: &str, labels: &[(&str, &str)]) -> Arc<Counter> {
+        self.series(&self.counters, name, labels, Counter::with_labels)
     }
 
     /// Get or create a gauge
     pub fn gauge(&self, name: &str) -> Arc<Gauge> {
-        let gauges = self.gauges.read().unwrap();
-        if let Some(gauge) = gauges.get(name) {
-            return Arc::clone(gauge);
-        }
-        drop(gauges);
+        self.gauge_with(name, &[])
+    }
 
-        let mut gauges = self.gauges.write().unwrap();
-        gauges
-            .entry(name.to_string())
-            .or_insert_with(|| Arc::new(Gauge::new(name)))
-            .clone()
+    /// Get or create a gauge series with labels
+    pub fn gauge_with(&self, name: &str, labels: &[(&str, &str)]) -> Arc<Gauge> {
+        self.series(&self.gauges, name, labels, Gauge::with_labels)
     }
 
     /// Get or create a histogram
     pub fn histogram(&self, name: &str) -> Arc<Histogram> {
-        let histograms = self.histograms.read().unwrap();
-        if let Some(histogram) = histograms.get(name) {
-            return Arc::clone(histogram);
+        self.histogram_with(name, &[])
+    }
+
+    /// Get or create a histogram series with labels
+    pub fn histogram_with(&self, name: &str, labels: &[(&str, &str)]) -> Arc<Histogram> {
+        self.series(&self.histograms, name, labels, Histogram::with_labels)
+    }
+
+    /// Get the number of label sets registered for a metric
+    pub fn cardinality(&self, name: &str) -> usize {
+        [
+            self.counters.read().unwrap().get(name).map(HashMap::len),
+            self.gauges.read().unwrap().get(name).map(HashMap::len),
+            self.histograms.read().unwrap().get(name).map(HashMap::len),
+        ]
+        .into_iter()
+        .flatten()
+        .sum()
+    }
+
+    /// Collect the current value of every series
+    pub fn collect(&self) -> Vec<MetricPoint> {
+        let mut points = Vec::new();
+        for series in self.counters.read().unwrap().values() {
+            points.extend(series.values().map(|c| MetricPoint {
+                name: c.name().to_string(),
+                labels: c.labels().clone(),
+                value: MetricValue::Counter(c.get()),
+            }));
+        }
+        for series in self.gauges.read().unwrap().values() {
+            points.extend(series.values().map(|g| MetricPoint {
+                name: g.name().to_string(),
+                labels: g.labels().clone(),
+                value: MetricValue::Gauge(g.get()),
+            }));
         }
-        drop(histograms);
+        for series in self.histograms.read().unwrap().values() {
+            points.extend(series.values().map(|h| MetricPoint {
+                name: h.name().to_string(),
+                labels: h.labels().clone(),
+                value: MetricValue::Histogram(h.snapshot()),
+            }));
+        }
+        points
+    }
 
-        let mut histograms = self.histograms.write().unwrap();
-        histograms
-            .entry(name.to_string())
-            .or_insert_with(|| Arc::new(Histogram::new(name)))
+    fn series<T>(
+        &self,
+        metrics: &Series<T>,
+        name: &str,
+        labels: &[(&str, &str)],
+        create: impl FnOnce(String, HashMap<String, String>) -> T,
+    ) -> Arc<T> {
+        let key = label_set(labels);
+
+        let read = metrics.read().unwrap();
+        if let Some(metric) = read.get(name).and_then(|series| series.get(&key)) {
+            return Arc::clone(metric);
+        }
+        drop(read);
+
+        let mut write = metrics.write().unwrap();
+        let series = write.entry(name.to_string()).or_default();
+        let key = if series.contains_key(&key) || series.len() < self.max_cardinality {
+            key
+        } else {
+            let overflow = vec![(OVERFLOW_LABEL.to_string(), "true".to_string())];
+            if !series.contains_key(&overflow) {
+                tracing::warn!(
+                    metric = name,
+                    limit = self.max_cardinality,
+                    "Metric cardinality limit reached, folding new label sets into overflow series"
+                );
+            }
+            overflow
+        };
+        series
+            .entry(key)
+            .or_insert_with_key(|key| {
+                Arc::new(create(name.to_string(), key.iter().cloned().collect()))
+            })
             .clone()
     }
 }
@@ -249,6 +680,53 @@ mod tests {
         histogram.observe(1.5);
         histogram.observe(2.5);
         assert_eq!(histogram.count(), 3);
+        assert!((histogram.sum() - 4.5).abs() < 1e-9);
+        assert!((histogram.mean().unwrap() - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_histogram_buckets() {
+        let histogram = Histogram::with_buckets("latency", vec![1.0, 0.1, 10.0]);
+        histogram.observe(0.05);
+        histogram.observe(0.5);
+        histogram.observe(0.5);
+        histogram.observe(20.0);
+
+        let bounds: Vec<_> = histogram
+            .buckets()
+            .iter()
+            .map(|b| (b.upper_bound, b.count))
+            .collect();
+        assert_eq!(
+            bounds,
+            vec![(0.1, 1), (1.0, 3), (10.0, 3), (f64::INFINITY, 4)]
+        );
+    }
+
+    #[test]
+    fn test_histogram_quantiles() {
+        let histogram = Histogram::new("latency");
+        assert_eq!(histogram.p50(), None);
+
+        for i in 1..=1000 {
+            histogram.observe(f64::from(i));
+        }
+
+        assert!((histogram.p50().unwrap() - 500.0).abs() < 25.0);
+        assert!((histogram.p95().unwrap() - 950.0).abs() < 25.0);
+        assert!((histogram.p99().unwrap() - 990.0).abs() < 25.0);
+        assert_eq!(histogram.snapshot().quantiles.len(), 3);
+    }
+
+    #[test]
+    fn test_histogram_exemplars() {
+        let histogram = Histogram::with_buckets("latency", vec![1.0]);
+        histogram.observe_with_exemplar(0.5, "trace-a");
+        histogram.observe_with_exemplar(5.0, "trace-b");
+
+        let buckets = histogram.buckets();
+        assert_eq!(buckets[0].exemplar.as_ref().unwrap().trace_id, "trace-a");
+        assert_eq!(buckets[1].exemplar.as_ref().unwrap().value, 5.0);
     }
 
     #[test]
@@ -259,4 +737,41 @@ mod tests {
         counter1.inc();
         assert_eq!(counter2.get(), 1);
     }
+
+    #[test]
+    fn test_labeled_series() {
+        let registry = MetricsRegistry::new();
+        let ok = registry.counter_with("requests", &[("status", "200"), ("method", "GET")]);
+        let same = registry.counter_with("requests", &[("method", "GET"), ("status", "200")]);
+        let err = registry.counter_with("requests", &[("status", "500"), ("method", "GET")]);
+
+        ok.inc();
+        same.inc();
+        err.inc();
+
+        assert_eq!(ok.get(), 2);
+        assert_eq!(err.get(), 1);
+        assert_eq!(ok.labels().get("status").unwrap(), "200");
+        assert_eq!(registry.cardinality("requests"), 2);
+
+        let points = registry.collect();
+        assert_eq!(points.len(), 2);
+        assert!(points.iter().any(
+            |p| p.labels.get("status").map(String::as_str) == Some("500")
+                && matches!(p.value, MetricValue::Counter(1))
+        ));
+    }
+
+    #[test]
+    fn test_cardinality_guard() {
+        let registry = MetricsRegistry::with_max_cardinality(2);
+        for user in ["a", "b", "c", "d"] {
+            registry.counter_with("logins", &[("user", user)]).inc();
+        }
+
+        assert_eq!(registry.cardinality("logins"), 3);
+        let overflow = registry.counter_with("logins", &[("user", "e")]);
+        assert_eq!(overflow.labels().get(OVERFLOW_LABEL).unwrap(), "true");
+        assert_eq!(overflow.get(), 2);
+    }
 }
@@ -0,0 +1,318 @@
+//! Push-mode metrics export.
+//!
+//! For batch jobs and WASM environments that cannot be scraped, a
+//! [`MetricsPusher`] periodically serializes a [`MetricsRegistry`] and hands
+//! it to a [`PushTransport`] (infra-http provides one).
+
+use crate::metrics::{MetricPoint, MetricValue, MetricsRegistry};
+use async_trait::async_trait;
+use infra_errors::InfraResult;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+/// Wire format for pushed metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushFormat {
+    /// Flat JSON document with one entry per series
+    Json,
+    /// OTLP/JSON `ExportMetricsServiceRequest`
+    OtlpJson,
+}
+
+/// Transport delivering encoded metrics to a collector
+#[async_trait]
+pub trait PushTransport: Send + Sync {
+    /// Send an encoded metrics payload
+    async fn push(&self, payload: &Value, format: PushFormat) -> InfraResult<()>;
+}
+
+/// Periodically pushes registry snapshots through a transport
+pub struct MetricsPusher {
+    registry: Arc<MetricsRegistry>,
+    transport: Arc<dyn PushTransport>,
+    format: PushFormat,
+    interval: Duration,
+    service_name: String,
+}
+
+impl MetricsPusher {
+    /// Create a pusher for a registry
+    pub fn new(registry: Arc<MetricsRegistry>, transport: Arc<dyn PushTransport>) -> Self {
+        Self {
+            registry,
+            transport,
+            format: PushFormat::Json,
+            interval: Duration::from_secs(60),
+            service_name: "unknown".to_string(),
+        }
+    }
+
+    /// Set the wire format
+    pub fn with_format(mut self, format: PushFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the push interval
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the service name attached to pushed metrics
+    pub fn with_service_name(mut self, name: impl Into<String>) -> Self {
+        self.service_name = name.into();
+        self
+    }
+
+    /// Encode the current registry contents
+    pub fn encode(&self) -> Value {
+        let points = self.registry.collect();
+        match self.format {
+            PushFormat::Json => encode_json(&points, &self.service_name, SystemTime::now()),
+            PushFormat::OtlpJson => encode_otlp_json(&points, &self.service_name, SystemTime::now()),
+        }
+    }
+
+    /// Push a single snapshot
+    pub async fn push_once(&self) -> InfraResult<()> {
+        self.transport.push(&self.encode(), self.format).await
+    }
+
+    /// Push on the configured interval until the task is aborted
+    ///
+    /// Failed pushes are logged and retried on the next tick. Must be called
+    /// from within a tokio runtime.
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.push_once().await {
+                    tracing::warn!(error = %e, "Failed to push metrics");
+                }
+            }
+        })
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+}
+
+fn sorted(labels: &HashMap<String, String>) -> BTreeMap<&str, &str> {
+    labels.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+}
+
+/// Encode metric points as a flat JSON document
+pub fn encode_json(points: &[MetricPoint], service_name: &str, time: SystemTime) -> Value {
+    let metrics: Vec<Value> = points
+        .iter()
+        .map(|point| {
+            let value = match &point.value {
+                MetricValue::Counter(v) => json!({ "type": "counter", "value": v }),
+                MetricValue::Gauge(v) => json!({ "type": "gauge", "value": v }),
+                MetricValue::Histogram(h) => json!({
+                    "type": "histogram",
+                    "count": h.count,
+                    "sum": h.sum,
+                    "buckets": h.buckets.iter().map(|b| json!({
+                        "le": if b.upper_bound.is_finite() { json!(b.upper_bound) } else { json!("+Inf") },
+                        "count": b.count,
+                    })).collect::<Vec<_>>(),
+                    "quantiles": h.quantiles.iter().map(|(q, v)| json!({ "quantile": q, "value": v })).collect::<Vec<_>>(),
+                }),
+            };
+            json!({
+                "name": point.name,
+                "labels": sorted(&point.labels),
+                "value": value,
+            })
+        })
+        .collect();
+
+    json!({
+        "service": service_name,
+        "timestamp_ms": unix_nanos(time) / 1_000_000,
+        "metrics": metrics,
+    })
+}
+
+fn otlp_attributes(labels: &HashMap<String, String>) -> Vec<Value> {
+    sorted(labels)
+        .into_iter()
+        .map(|(k, v)| json!({ "key": k, "value": { "stringValue": v } }))
+        .collect()
+}
+
+/// Encode metric points as an OTLP/JSON `ExportMetricsServiceRequest`
+pub fn encode_otlp_json(points: &[MetricPoint], service_name: &str, time: SystemTime) -> Value {
+    // OTLP/JSON encodes 64-bit integers as strings
+    let now = unix_nanos(time).to_string();
+
+    let mut by_name: BTreeMap<&str, Vec<&MetricPoint>> = BTreeMap::new();
+    for point in points {
+        by_name.entry(point.name.as_str()).or_default().push(point);
+    }
+
+    let metrics: Vec<Value> = by_name
+        .into_iter()
+        .filter_map(|(name, series)| {
+            let data_points = |f: &dyn Fn(&MetricPoint) -> Value| -> Vec<Value> {
+                series
+                    .iter()
+                    .map(|p| {
+                        let mut dp = f(p);
+                        dp["attributes"] = json!(otlp_attributes(&p.labels));
+                        dp["timeUnixNano"] = json!(now);
+                        dp
+                    })
+                    .collect()
+            };
+            let metric = match series.first()?.value {
+                MetricValue::Counter(_) => json!({
+                    "name": name,
+                    "sum": {
+                        "dataPoints": data_points(&|p| match p.value {
+                            MetricValue::Counter(v) => json!({ "asInt": v.to_string() }),
+                            _ => json!({}),
+                        }),
+                        "aggregationTemporality": 2,
+                        "isMonotonic": true,
+                    },
+                }),
+                MetricValue::Gauge(_) => json!({
+                    "name": name,
+                    "gauge": {
+                        "dataPoints": data_points(&|p| match p.value {
+                            MetricValue::Gauge(v) => json!({ "asInt": v.to_string() }),
+                            _ => json!({}),
+                        }),
+                    },
+                }),
+                MetricValue::Histogram(_) => json!({
+                    "name": name,
+                    "histogram": {
+                        "dataPoints": data_points(&|p| match &p.value {
+                            MetricValue::Histogram(h) => {
+                                // OTLP bucket counts are per-bucket, not cumulative
+                                let mut previous = 0;
+                                let counts: Vec<String> = h
+                                    .buckets
+                                    .iter()
+                                    .map(|b| {
+                                        let count = b.count - previous;
+                                        previous = b.count;
+                                        count.to_string()
+                                    })
+                                    .collect();
+                                let bounds: Vec<f64> = h
+                                    .buckets
+                                    .iter()
+                                    .map(|b| b.upper_bound)
+                                    .filter(|b| b.is_finite())
+                                    .collect();
+                                json!({
+                                    "count": h.count.to_string(),
+                                    "sum": h.sum,
+                                    "bucketCounts": counts,
+                                    "explicitBounds": bounds,
+                                })
+                            }
+                            _ => json!({}),
+                        }),
+                        "aggregationTemporality": 2,
+                    },
+                }),
+            };
+            Some(metric)
+        })
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [{ "key": "service.name", "value": { "stringValue": service_name } }],
+            },
+            "scopeMetrics": [{
+                "scope": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingTransport {
+        payloads: Mutex<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl PushTransport for RecordingTransport {
+        async fn push(&self, payload: &Value, _format: PushFormat) -> InfraResult<()> {
+            self.payloads.lock().unwrap().push(payload.clone());
+            Ok(())
+        }
+    }
+
+    fn registry() -> Arc<MetricsRegistry> {
+        let registry = Arc::new(MetricsRegistry::new());
+        registry.counter_with("requests", &[("status", "200")]).add(3);
+        registry.gauge("in_flight").set(2);
+        let histogram = registry.histogram("latency");
+        histogram.observe(0.02);
+        histogram.observe(0.3);
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_push_json() {
+        let transport = Arc::new(RecordingTransport::default());
+        let pusher = MetricsPusher::new(registry(), transport.clone()).with_service_name("batch");
+        pusher.push_once().await.unwrap();
+
+        let payloads = transport.payloads.lock().unwrap();
+        let payload = &payloads[0];
+        assert_eq!(payload["service"], "batch");
+        let requests = payload["metrics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|m| m["name"] == "requests")
+            .unwrap();
+        assert_eq!(requests["labels"]["status"], "200");
+        assert_eq!(requests["value"]["value"], 3);
+    }
+
+    #[test]
+    fn test_encode_otlp_json() {
+        let points = registry().collect();
+        let payload = encode_otlp_json(&points, "batch", SystemTime::now());
+        let metrics = payload["resourceMetrics"][0]["scopeMetrics"][0]["metrics"]
+            .as_array()
+            .unwrap();
+
+        let requests = metrics.iter().find(|m| m["name"] == "requests").unwrap();
+        assert_eq!(requests["sum"]["dataPoints"][0]["asInt"], "3");
+        assert_eq!(requests["sum"]["dataPoints"][0]["attributes"][0]["key"], "status");
+
+        let latency = metrics.iter().find(|m| m["name"] == "latency").unwrap();
+        let point = &latency["histogram"]["dataPoints"][0];
+        assert_eq!(point["count"], "2");
+        let total: u64 = point["bucketCounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_str().unwrap().parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(total, 2);
+    }
+}
@@ -0,0 +1,129 @@
+//! Span events for retries, circuit breakers and rate limits.
+//!
+//! These helpers emit events on the current span so resilience behavior
+//! shows up uniformly on traces, whichever crate triggered it.
+
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Record that an operation failed and will be retried after `delay`
+///
+/// `attempt` is the number of the upcoming attempt (the first retry is 2).
+pub fn record_retry_attempt(attempt: u32, delay: Duration, error: &dyn Display) {
+    tracing::warn!(
+        retry.attempt = attempt,
+        retry.delay_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX),
+        error.message = %error,
+        "retry"
+    );
+}
+
+/// Record that an operation failed and retrying was abandoned
+///
+/// `attempts` is the total number of attempts made.
+pub fn record_retry_exhausted(attempts: u32, error: &dyn Display) {
+    tracing::warn!(
+        retry.attempts = attempts,
+        error.message = %error,
+        "retry_exhausted"
+    );
+}
+
+/// Record a circuit breaker transition
+pub fn record_circuit_state_change(circuit: &str, from: &dyn Display, to: &dyn Display) {
+    tracing::warn!(
+        circuit.name = circuit,
+        circuit.from = %from,
+        circuit.to = %to,
+        "circuit_state_change"
+    );
+}
+
+/// Record that a request was rejected by a rate limiter
+pub fn record_rate_limited(limiter: &str, wait: Duration) {
+    tracing::info!(
+        rate_limit.limiter = limiter,
+        rate_limit.wait_ms = u64::try_from(wait.as_millis()).unwrap_or(u64::MAX),
+        "rate_limited"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    /// Records every event as its `name=value` fields.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        events: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    struct FieldVisitor<'a>(&'a mut Vec<String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push(format!("{}={value:?}", field.name()));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.push(format!("{}={value}", field.name()));
+        }
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for Recorder {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = Vec::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            self.events.lock().unwrap().push(fields);
+        }
+    }
+
+    #[test]
+    fn test_resilience_events() {
+        let recorder = Recorder::default();
+        let subscriber = tracing_subscriber::registry().with(recorder.clone());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("operation");
+            let _guard = span.enter();
+
+            record_retry_attempt(2, Duration::from_millis(100), &"connection reset");
+            record_retry_exhausted(3, &"connection reset");
+            record_circuit_state_change("payments", &"closed", &"open");
+            record_rate_limited("token_bucket", Duration::from_millis(20));
+        });
+
+        let events = recorder.events.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                vec![
+                    "message=retry",
+                    "retry.attempt=2",
+                    "retry.delay_ms=100",
+                    "error.message=connection reset",
+                ],
+                vec![
+                    "message=retry_exhausted",
+                    "retry.attempts=3",
+                    "error.message=connection reset",
+                ],
+                vec![
+                    "message=circuit_state_change",
+                    "circuit.name=payments",
+                    "circuit.from=closed",
+                    "circuit.to=open",
+                ],
+                vec![
+                    "message=rate_limited",
+                    "rate_limit.limiter=token_bucket",
+                    "rate_limit.wait_ms=20",
+                ],
+            ]
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Runtime-configurable trace sampling.
+
+use opentelemetry::trace::{
+    Link, SamplingDecision, SamplingResult, SpanContext, SpanKind, Status, TraceContextExt,
+    TraceId, TraceResult,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_sdk::export::trace::SpanData;
+use opentelemetry_sdk::trace::{Sampler, ShouldSample, Span, SpanProcessor};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+/// Head sampling strategy for root spans
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SamplingStrategy {
+    /// Sample every trace
+    AlwaysOn,
+    /// Sample no traces
+    AlwaysOff,
+    /// Sample a fraction of traces, keyed on the trace ID
+    Ratio { ratio: f64 },
+    /// Sample at most `per_second` traces per second
+    RateLimited { per_second: f64 },
+}
+
+impl Default for SamplingStrategy {
+    fn default() -> Self {
+        Self::AlwaysOn
+    }
+}
+
+/// Sampling configuration, loadable from configuration files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingConfig {
+    /// Strategy for root spans
+    #[serde(default)]
+    pub strategy: SamplingStrategy,
+    /// Follow the parent span's sampling decision when there is one
+    #[serde(default = "default_true")]
+    pub parent_based: bool,
+    /// Record unsampled spans so error spans can be kept by [`TailSamplingProcessor`]
+    #[serde(default)]
+    pub keep_errors: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: SamplingStrategy::default(),
+            parent_based: true,
+            keep_errors: false,
+        }
+    }
+}
+
+impl SamplingConfig {
+    /// Create a configuration with a root strategy
+    pub fn new(strategy: SamplingStrategy) -> Self {
+        Self {
+            strategy,
+            ..Self::default()
+        }
+    }
+
+    /// Parent-based ratio sampling
+    pub fn ratio(ratio: f64) -> Self {
+        Self::new(SamplingStrategy::Ratio { ratio })
+    }
+
+    /// Parent-based rate-limited sampling
+    pub fn rate_limited(per_second: f64) -> Self {
+        Self::new(SamplingStrategy::RateLimited { per_second })
+    }
+
+    /// Set whether parent decisions are honored
+    pub fn with_parent_based(mut self, enabled: bool) -> Self {
+        self.parent_based = enabled;
+        self
+    }
+
+    /// Keep error spans regardless of the head decision
+    pub fn with_keep_errors(mut self, enabled: bool) -> Self {
+        self.keep_errors = enabled;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn try_acquire(&mut self, per_second: f64) -> bool {
+        let now = Instant::now();
+        let capacity = per_second.max(1.0);
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SamplerState {
+    config: RwLock<SamplingConfig>,
+    bucket: Mutex<TokenBucket>,
+}
+
+/// Sampler whose strategy can be swapped while the process runs
+///
+/// Clones share state, so a handle kept after installing the sampler in a
+/// tracer provider can be used to [`update`](Self::update) it later.
+#[derive(Debug, Clone)]
+pub struct RuntimeSampler {
+    state: Arc<SamplerState>,
+}
+
+impl RuntimeSampler {
+    /// Create a sampler with an initial configuration
+    pub fn new(config: SamplingConfig) -> Self {
+        let tokens = match config.strategy {
+            SamplingStrategy::RateLimited { per_second } => per_second.max(1.0),
+            _ => 0.0,
+        };
+        Self {
+            state: Arc::new(SamplerState {
+                config: RwLock::new(config),
+                bucket: Mutex::new(TokenBucket {
+                    tokens,
+                    last: Instant::now(),
+                }),
+            }),
+        }
+    }
+
+    /// Get the current configuration
+    pub fn config(&self) -> SamplingConfig {
+        self.state.config.read().unwrap().clone()
+    }
+
+    /// Replace the configuration; applies to spans started afterwards
+    pub fn update(&self, config: SamplingConfig) {
+        tracing::info!(?config, "Updating trace sampling configuration");
+        *self.state.config.write().unwrap() = config;
+    }
+
+    fn sample_root(&self, strategy: &SamplingStrategy, trace_id: TraceId) -> bool {
+        match strategy {
+            SamplingStrategy::AlwaysOn => true,
+            SamplingStrategy::AlwaysOff => false,
+            SamplingStrategy::Ratio { ratio } => {
+                let result = Sampler::TraceIdRatioBased(*ratio).should_sample(
+                    None,
+                    trace_id,
+                    "",
+                    &SpanKind::Internal,
+                    &[],
+                    &[],
+                );
+                result.decision == SamplingDecision::RecordAndSample
+            }
+            SamplingStrategy::RateLimited { per_second } => {
+                self.state.bucket.lock().unwrap().try_acquire(*per_second)
+            }
+        }
+    }
+}
+
+impl Default for RuntimeSampler {
+    fn default() -> Self {
+        Self::new(SamplingConfig::default())
+    }
+}
+
+impl ShouldSample for RuntimeSampler {
+    fn should_sample(
+        &self,
+        parent_context: Option<&Context>,
+        trace_id: TraceId,
+        _name: &str,
+        _span_kind: &SpanKind,
+        _attributes: &[KeyValue],
+        _links: &[Link],
+    ) -> SamplingResult {
+        let config = self.config();
+        let parent = parent_context
+            .filter(|cx| cx.has_active_span())
+            .map(|cx| cx.span().span_context().clone());
+
+        let sampled = match &parent {
+            Some(parent) if config.parent_based => parent.is_sampled(),
+            _ => self.sample_root(&config.strategy, trace_id),
+        };
+
+        let decision = if sampled {
+            SamplingDecision::RecordAndSample
+        } else if config.keep_errors {
+            SamplingDecision::RecordOnly
+        } else {
+            SamplingDecision::Drop
+        };
+
+        SamplingResult {
+            decision,
+            attributes: Vec::new(),
+            trace_state: parent
+                .map(|p| p.trace_state().clone())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl RuntimeSampler {
+    /// Reload the configuration from a file
+    pub fn reload_from_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> infra_errors::InfraResult<()> {
+        let config: SamplingConfig = infra_config::load_file(path)?;
+        if config != self.config() {
+            self.update(config);
+        }
+        Ok(())
+    }
+
+    /// Poll a configuration file and apply changes as it is modified
+    ///
+    /// Reload errors are logged and the previous configuration is kept.
+    pub fn watch_file(
+        &self,
+        path: impl Into<std::path::PathBuf>,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let sampler = self.clone();
+        let path = path.into();
+        tokio::spawn(async move {
+            let mut last_modified = None;
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                if modified.is_none() || modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+                if let Err(e) = sampler.reload_from_file(&path) {
+                    tracing::warn!(path = %path.display(), error = %e, "Failed to reload sampling config");
+                }
+            }
+        })
+    }
+}
+
+/// Predicate deciding whether an unsampled span is kept at the end
+pub type TailPredicate = Arc<dyn Fn(&SpanData) -> bool + Send + Sync>;
+
+/// Span processor that promotes recorded-but-unsampled spans to exported
+/// when a tail predicate matches (by default, spans with error status)
+///
+/// Requires a sampler that records dropped spans, e.g. [`RuntimeSampler`]
+/// with `keep_errors` enabled.
+pub struct TailSamplingProcessor<P> {
+    inner: P,
+    keep: TailPredicate,
+}
+
+impl<P: SpanProcessor> TailSamplingProcessor<P> {
+    /// Wrap a processor, keeping error spans
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            keep: Arc::new(|span| matches!(span.status, Status::Error { .. })),
+        }
+    }
+
+    /// Replace the tail predicate
+    pub fn keep_if(
+        mut self,
+        predicate: impl Fn(&SpanData) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.keep = Arc::new(predicate);
+        self
+    }
+}
+
+impl<P> std::fmt::Debug for TailSamplingProcessor<P>
+where
+    P: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TailSamplingProcessor")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: SpanProcessor> SpanProcessor for TailSamplingProcessor<P> {
+    fn on_start(&self, span: &mut Span, cx: &Context) {
+        self.inner.on_start(span, cx);
+    }
+
+    fn on_end(&self, mut span: SpanData) {
+        if !span.span_context.is_sampled() {
+            if !(self.keep)(&span) {
+                return;
+            }
+            let ctx = &span.span_context;
+            span.span_context = SpanContext::new(
+                ctx.trace_id(),
+                ctx.span_id(),
+                ctx.trace_flags().with_sampled(true),
+                ctx.is_remote(),
+                ctx.trace_state().clone(),
+            );
+        }
+        self.inner.on_end(span);
+    }
+
+    fn force_flush(&self) -> TraceResult<()> {
+        self.inner.force_flush()
+    }
+
+    fn shutdown(&self) -> TraceResult<()> {
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanId, TraceFlags, TraceState};
+
+    fn decide(sampler: &RuntimeSampler, parent: Option<&Context>, id: u128) -> SamplingDecision {
+        sampler
+            .should_sample(
+                parent,
+                TraceId::from(id),
+                "op",
+                &SpanKind::Internal,
+                &[],
+                &[],
+            )
+            .decision
+    }
+
+    #[test]
+    fn test_runtime_sampler_update() {
+        let sampler = RuntimeSampler::new(SamplingConfig::new(SamplingStrategy::AlwaysOff));
+        assert_eq!(decide(&sampler, None, 1), SamplingDecision::Drop);
+
+        sampler.update(SamplingConfig::new(SamplingStrategy::AlwaysOff).with_keep_errors(true));
+        assert_eq!(decide(&sampler, None, 1), SamplingDecision::RecordOnly);
+
+        sampler.update(SamplingConfig::ratio(1.0));
+        assert_eq!(decide(&sampler, None, 1), SamplingDecision::RecordAndSample);
+    }
+
+    #[test]
+    fn test_parent_based() {
+        let sampler = RuntimeSampler::new(SamplingConfig::new(SamplingStrategy::AlwaysOff));
+        let parent = SpanContext::new(
+            TraceId::from(7),
+            SpanId::from(1),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(parent);
+        assert_eq!(
+            decide(&sampler, Some(&cx), 7),
+            SamplingDecision::RecordAndSample
+        );
+
+        sampler.update(SamplingConfig::new(SamplingStrategy::AlwaysOff).with_parent_based(false));
+        assert_eq!(decide(&sampler, Some(&cx), 7), SamplingDecision::Drop);
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let sampler = RuntimeSampler::new(SamplingConfig::rate_limited(2.0));
+        let sampled = (0..10)
+            .filter(|i| decide(&sampler, None, *i) == SamplingDecision::RecordAndSample)
+            .count();
+        assert_eq!(sampled, 2);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_reload_from_file() {
+        let path = std::env::temp_dir().join(format!("sampling-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"strategy": {"type": "ratio", "ratio": 0.25}, "keep_errors": true}"#,
+        )
+        .unwrap();
+
+        let sampler = RuntimeSampler::default();
+        sampler.reload_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let config = sampler.config();
+        assert_eq!(config.strategy, SamplingStrategy::Ratio { ratio: 0.25 });
+        assert!(config.parent_based);
+        assert!(config.keep_errors);
+    }
+}
@@ -101,6 +101,113 @@ pub fn external_span(service: &str, operation: &str) -> Span {
     )
 }
 
+/// Builder for LLM spans following the OpenTelemetry gen_ai semantic conventions
+pub struct LlmSpanBuilder {
+    operation: String,
+    provider: String,
+    model: String,
+    temperature: Option<f64>,
+    max_tokens: Option<u64>,
+}
+
+impl LlmSpanBuilder {
+    /// Create a builder for a chat completion against a provider's model
+    pub fn new(provider: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            operation: "chat".to_string(),
+            provider: provider.into(),
+            model: model.into(),
+            temperature: None,
+            max_tokens: None,
+        }
+    }
+
+    /// Set the operation name (`chat`, `text_completion`, `embeddings`)
+    pub fn operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = operation.into();
+        self
+    }
+
+    /// Set the requested sampling temperature
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the requested maximum output tokens
+    pub fn max_tokens(mut self, max_tokens: u64) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Build the span; response attributes are recorded with [`LlmSpanExt`]
+    pub fn build(self) -> Span {
+        let span = tracing::info_span!(
+            "gen_ai",
+            otel.name = %format!("{} {}", self.operation, self.model),
+            otel.kind = "client",
+            gen_ai.operation.name = %self.operation,
+            gen_ai.system = %self.provider,
+            gen_ai.request.model = %self.model,
+            gen_ai.request.temperature = tracing::field::Empty,
+            gen_ai.request.max_tokens = tracing::field::Empty,
+            gen_ai.response.model = tracing::field::Empty,
+            gen_ai.response.finish_reasons = tracing::field::Empty,
+            gen_ai.usage.input_tokens = tracing::field::Empty,
+            gen_ai.usage.output_tokens = tracing::field::Empty,
+            gen_ai.usage.cost_usd = tracing::field::Empty,
+            error = tracing::field::Empty,
+            error.message = tracing::field::Empty,
+        );
+        if let Some(temperature) = self.temperature {
+            span.record("gen_ai.request.temperature", temperature);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            span.record("gen_ai.request.max_tokens", max_tokens);
+        }
+        span
+    }
+}
+
+/// Create an LLM span builder for a provider and model
+pub fn llm_span(provider: &str, model: &str) -> LlmSpanBuilder {
+    LlmSpanBuilder::new(provider, model)
+}
+
+/// Extension trait for recording LLM response attributes
+pub trait LlmSpanExt {
+    /// Record prompt and completion token counts
+    fn record_usage(&self, input_tokens: u64, output_tokens: u64);
+
+    /// Record the reason generation stopped
+    fn record_finish_reason(&self, reason: &str);
+
+    /// Record the model that actually served the request
+    fn record_response_model(&self, model: &str);
+
+    /// Record the request cost in USD
+    fn record_cost(&self, cost_usd: f64);
+}
+
+impl LlmSpanExt for Span {
+    fn record_usage(&self, input_tokens: u64, output_tokens: u64) {
+        self.record("gen_ai.usage.input_tokens", input_tokens);
+        self.record("gen_ai.usage.output_tokens", output_tokens);
+    }
+
+    fn record_finish_reason(&self, reason: &str) {
+        self.record("gen_ai.response.finish_reasons", reason);
+    }
+
+    fn record_response_model(&self, model: &str) {
+        self.record("gen_ai.response.model", model);
+    }
+
+    fn record_cost(&self, cost_usd: f64) {
+        self.record("gen_ai.usage.cost_usd", cost_usd);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,4 +222,18 @@ mod tests {
         // Just verify it doesn't panic
         let _guard = span.enter();
     }
+
+    #[test]
+    fn test_llm_span() {
+        let span = llm_span("openai", "gpt-4o")
+            .temperature(0.2)
+            .max_tokens(256)
+            .build();
+
+        span.record_usage(120, 40);
+        span.record_finish_reason("stop");
+        span.record_response_model("gpt-4o-2024-08-06");
+        span.record_cost(0.0012);
+        let _guard = span.enter();
+    }
 }
@@ -0,0 +1,171 @@
+//! Timers and RAII measurement guards.
+
+use crate::metrics::{Gauge, Histogram, MetricsRegistry};
+use infra_errors::InfraResult;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Outcome label value for successful operations
+pub const OUTCOME_OK: &str = "ok";
+
+/// Guard recording elapsed seconds into a histogram when dropped
+///
+/// Durations are observed with the current trace attached as an exemplar.
+/// An optional in-flight gauge is incremented on start and decremented when
+/// the timer finishes.
+#[must_use = "the timer records when dropped; binding it to `_` stops it immediately"]
+pub struct Timer {
+    histogram: Option<Arc<Histogram>>,
+    in_flight: Option<Arc<Gauge>>,
+    start: Instant,
+}
+
+impl Timer {
+    /// Start timing into a histogram
+    pub fn start(histogram: Arc<Histogram>) -> Self {
+        Self {
+            histogram: Some(histogram),
+            in_flight: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Track the timed operation in an in-flight gauge
+    pub fn with_in_flight(mut self, gauge: Arc<Gauge>) -> Self {
+        gauge.inc();
+        if let Some(previous) = self.in_flight.replace(gauge) {
+            previous.dec();
+        }
+        self
+    }
+
+    /// Get the time elapsed so far
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Stop the timer and record the duration
+    pub fn stop(mut self) -> Duration {
+        self.finish(true)
+    }
+
+    /// Stop the timer without recording a duration
+    pub fn discard(mut self) {
+        self.finish(false);
+    }
+
+    fn finish(&mut self, record: bool) -> Duration {
+        let elapsed = self.start.elapsed();
+        if let Some(histogram) = self.histogram.take() {
+            if record {
+                histogram.observe_traced(elapsed.as_secs_f64());
+            }
+        }
+        if let Some(gauge) = self.in_flight.take() {
+            gauge.dec();
+        }
+        elapsed
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.finish(true);
+    }
+}
+
+impl MetricsRegistry {
+    /// Start a timer on `{name}.duration`, tracking `{name}.in_flight`
+    pub fn timer(&self, name: &str) -> Timer {
+        Timer::start(self.histogram(&format!("{name}.duration")))
+            .with_in_flight(self.gauge(&format!("{name}.in_flight")))
+    }
+
+    /// Measure a fallible operation
+    ///
+    /// Records the duration in `{name}.duration` labeled with `outcome`
+    /// (`ok` or the error's [`error_type`](infra_errors::InfraError::error_type))
+    /// and tracks concurrency in `{name}.in_flight`. If the returned future
+    /// is dropped before completing, the operation leaves the in-flight gauge
+    /// and no duration is recorded.
+    pub async fn measure<T, F>(&self, name: &str, operation: F) -> InfraResult<T>
+    where
+        F: Future<Output = InfraResult<T>>,
+    {
+        // Timer without a histogram, as the outcome label is only known at
+        // the end; dropping it leaves the in-flight gauge
+        let timer = Timer {
+            histogram: None,
+            in_flight: None,
+            start: Instant::now(),
+        }
+        .with_in_flight(self.gauge(&format!("{name}.in_flight")));
+
+        let result = operation.await;
+
+        let outcome = match &result {
+            Ok(_) => OUTCOME_OK,
+            Err(e) => e.error_type(),
+        };
+        let elapsed = timer.stop();
+        self.histogram_with(&format!("{name}.duration"), &[("outcome", outcome)])
+            .observe_traced(elapsed.as_secs_f64());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_errors::InfraError;
+
+    #[test]
+    fn test_timer_records_on_drop() {
+        let histogram = Arc::new(Histogram::new("op"));
+        let in_flight = Arc::new(Gauge::new("op.in_flight"));
+
+        {
+            let _timer = Timer::start(histogram.clone()).with_in_flight(in_flight.clone());
+            assert_eq!(in_flight.get(), 1);
+        }
+        assert_eq!(histogram.count(), 1);
+        assert_eq!(in_flight.get(), 0);
+
+        Timer::start(histogram.clone()).discard();
+        assert_eq!(histogram.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_measure_outcome() {
+        let registry = MetricsRegistry::new();
+
+        let ok = registry.measure("db.query", async { Ok(1) }).await;
+        assert_eq!(ok.unwrap(), 1);
+        let err: InfraResult<()> = registry
+            .measure("db.query", async {
+                Err(InfraError::timeout("query", Duration::from_secs(1)))
+            })
+            .await;
+        assert!(err.is_err());
+
+        let ok = registry.histogram_with("db.query.duration", &[("outcome", "ok")]);
+        let timeout = registry.histogram_with("db.query.duration", &[("outcome", "timeout")]);
+        assert_eq!(ok.count(), 1);
+        assert_eq!(timeout.count(), 1);
+        assert_eq!(registry.gauge("db.query.in_flight").get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_measure_cancelled() {
+        let registry = MetricsRegistry::new();
+
+        let pending = registry.measure("db.query", std::future::pending::<InfraResult<()>>());
+        let cancelled = tokio::time::timeout(Duration::from_millis(1), pending).await;
+        assert!(cancelled.is_err());
+
+        assert_eq!(registry.gauge("db.query.in_flight").get(), 0);
+        let ok = registry.histogram_with("db.query.duration", &[("outcome", "ok")]);
+        assert_eq!(ok.count(), 0);
+    }
+}
@@ -9,6 +9,8 @@
 //! - Async/await support via tokio
 //! - Thread-safe implementations
 //! - Configurable rate limits and burst sizes
+//! - Windows measured against an `infra-time` time source, such as a
+//!   simulated clock
 //!
 //! # Examples
 //!
@@ -50,6 +50,23 @@ pub trait RateLimiter: Send + Sync {
     /// Returns immediately with the result.
     async fn try_acquire(&self) -> RateLimitResult;
 
+    /// Attempts to acquire `permits` permits at once without waiting.
+    ///
+    /// Either every permit is taken or none is. Requests for more permits
+    /// than the limiter ever holds are always denied.
+    ///
+    /// The default implementation takes permits one at a time, so a denial
+    /// may leave some of them taken; the built-in strategies override it.
+    async fn try_acquire_n(&self, permits: u64) -> RateLimitResult {
+        for _ in 0..permits {
+            let result = self.try_acquire().await;
+            if result.is_denied() {
+                return result;
+            }
+        }
+        RateLimitResult::Allowed
+    }
+
     /// Returns the number of available permits.
     async fn available(&self) -> u64;
 
@@ -6,7 +6,9 @@ use crate::{
     limiter::{RateLimitResult, RateLimiter},
 };
 use async_trait::async_trait;
+use infra_time::{TimeHandle, TimeSource};
 use parking_lot::Mutex;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Fixed window rate limiter.
@@ -17,6 +19,7 @@ use std::time::{Duration, Instant};
 pub struct FixedWindowLimiter {
     config: RateLimitConfig,
     state: Mutex<WindowState>,
+    clock: TimeHandle,
 }
 
 #[derive(Debug)]
@@ -34,12 +37,26 @@ impl FixedWindowLimiter {
                 count: 0,
                 window_start: Instant::now(),
             }),
+            clock: TimeHandle::default(),
         }
     }
 
+    /// Measures windows against a time source, such as a simulated clock,
+    /// rather than the system clock.
+    ///
+    /// [`try_acquire`](RateLimiter::try_acquire) and
+    /// [`available`](RateLimiter::available) then follow the source, while
+    /// [`acquire`](RateLimiter::acquire) still waits on tokio timers.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self.state.get_mut().window_start = self.clock.instant();
+        self
+    }
+
     /// Resets the window if it has expired.
     fn maybe_reset_window(&self, state: &mut WindowState) {
-        let now = Instant::now();
+        let now = self.clock.instant();
         let elapsed = now.duration_since(state.window_start);
 
         if elapsed >= self.config.window_size {
@@ -51,7 +68,7 @@ impl FixedWindowLimiter {
     /// Calculates wait time until the next window.
     fn calculate_wait_time(&self, state: &WindowState) -> Duration {
         let window_end = state.window_start + self.config.window_size;
-        let now = Instant::now();
+        let now = self.clock.instant();
         window_end.saturating_duration_since(now)
     }
 }
@@ -71,14 +88,20 @@ impl RateLimiter for FixedWindowLimiter {
     }
 
     async fn try_acquire(&self) -> RateLimitResult {
+        self.try_acquire_n(1).await
+    }
+
+    async fn try_acquire_n(&self, permits: u64) -> RateLimitResult {
         let mut state = self.state.lock();
         self.maybe_reset_window(&mut state);
 
-        if state.count < self.config.burst_size {
-            state.count += 1;
+        if state.count.saturating_add(permits) <= self.config.burst_size {
+            state.count += permits;
             RateLimitResult::Allowed
         } else {
             let wait_time = self.calculate_wait_time(&state);
+            #[cfg(feature = "otel")]
+            infra_otel::record_rate_limited("fixed_window", wait_time);
             RateLimitResult::Denied { wait_time }
         }
     }
@@ -92,7 +115,7 @@ impl RateLimiter for FixedWindowLimiter {
     async fn reset(&self) {
         let mut state = self.state.lock();
         state.count = 0;
-        state.window_start = Instant::now();
+        state.window_start = self.clock.instant();
     }
 }
 
@@ -140,4 +163,23 @@ mod tests {
         limiter.try_acquire().await;
         assert_eq!(limiter.available().await, 4);
     }
+
+    #[tokio::test]
+    async fn test_fixed_window_simulated_time() {
+        let clock = Arc::new(infra_sim::SimulatedClock::new());
+        let config = RateLimitConfig::new(1.0, 3, Duration::from_secs(60)).unwrap();
+        let limiter = FixedWindowLimiter::new(config).with_time_source(clock.clone());
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire().await.is_allowed());
+        }
+        clock.advance(Duration::from_secs(45));
+        match limiter.try_acquire().await {
+            RateLimitResult::Denied { wait_time } => assert_eq!(wait_time, Duration::from_secs(15)),
+            RateLimitResult::Allowed => panic!("window should be full"),
+        }
+
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(limiter.available().await, 3);
+    }
 }
@@ -6,9 +6,11 @@ use crate::{
     limiter::{RateLimitResult, RateLimiter},
 };
 use async_trait::async_trait;
+use infra_time::{TimeHandle, TimeSource};
 use parking_lot::Mutex;
 use std::{
     collections::VecDeque,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
@@ -20,11 +22,15 @@ use std::{
 pub struct SlidingWindowLimiter {
     config: RateLimitConfig,
     state: Mutex<WindowState>,
+    clock: TimeHandle,
 }
 
 #[derive(Debug)]
 struct WindowState {
-    requests: VecDeque<Instant>,
+    /// Time and permit count of each request in the window
+    requests: VecDeque<(Instant, u64)>,
+    /// Permits taken by the requests in the window
+    used: u64,
 }
 
 impl SlidingWindowLimiter {
@@ -34,30 +40,48 @@ impl SlidingWindowLimiter {
             config,
             state: Mutex::new(WindowState {
                 requests: VecDeque::new(),
+                used: 0,
             }),
+            clock: TimeHandle::default(),
         }
     }
 
+    /// Measures windows against a time source, such as a simulated clock,
+    /// rather than the system clock.
+    ///
+    /// [`try_acquire`](RateLimiter::try_acquire) and
+    /// [`available`](RateLimiter::available) then follow the source, while
+    /// [`acquire`](RateLimiter::acquire) still waits on tokio timers.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self
+    }
+
     /// Removes expired requests from the window.
     fn clean_expired(&self, state: &mut WindowState, now: Instant) {
         let cutoff = now - self.config.window_size;
-        while let Some(&first) = state.requests.front() {
+        while let Some(&(first, permits)) = state.requests.front() {
             if first < cutoff {
                 state.requests.pop_front();
+                state.used -= permits;
             } else {
                 break;
             }
         }
     }
 
-    /// Calculates wait time until the next slot becomes available.
-    fn calculate_wait_time(&self, state: &WindowState, now: Instant) -> Duration {
-        if let Some(&oldest) = state.requests.front() {
-            let window_end = oldest + self.config.window_size;
-            window_end.saturating_duration_since(now)
-        } else {
-            Duration::ZERO
+    /// Calculates wait time until enough slots become available.
+    fn calculate_wait_time(&self, state: &WindowState, now: Instant, permits: u64) -> Duration {
+        let mut used = state.used;
+        for &(time, count) in &state.requests {
+            used -= count;
+            if used + permits <= self.config.burst_size {
+                let window_end = time + self.config.window_size;
+                return window_end.saturating_duration_since(now);
+            }
         }
+        self.config.window_size
     }
 }
 
@@ -76,32 +100,40 @@ impl RateLimiter for SlidingWindowLimiter {
     }
 
     async fn try_acquire(&self) -> RateLimitResult {
+        self.try_acquire_n(1).await
+    }
+
+    async fn try_acquire_n(&self, permits: u64) -> RateLimitResult {
         let mut state = self.state.lock();
-        let now = Instant::now();
+        let now = self.clock.instant();
 
         self.clean_expired(&mut state, now);
 
-        if (state.requests.len() as u64) < self.config.burst_size {
-            state.requests.push_back(now);
+        if state.used.saturating_add(permits) <= self.config.burst_size {
+            state.requests.push_back((now, permits));
+            state.used += permits;
             RateLimitResult::Allowed
         } else {
-            let wait_time = self.calculate_wait_time(&state, now);
+            let wait_time = self.calculate_wait_time(&state, now, permits);
+            #[cfg(feature = "otel")]
+            infra_otel::record_rate_limited("sliding_window", wait_time);
             RateLimitResult::Denied { wait_time }
         }
     }
 
     async fn available(&self) -> u64 {
         let mut state = self.state.lock();
-        let now = Instant::now();
+        let now = self.clock.instant();
 
         self.clean_expired(&mut state, now);
 
-        self.config.burst_size.saturating_sub(state.requests.len() as u64)
+        self.config.burst_size.saturating_sub(state.used)
     }
 
     async fn reset(&self) {
         let mut state = self.state.lock();
         state.requests.clear();
+        state.used = 0;
     }
 }
 
@@ -139,4 +171,33 @@ mod tests {
         // Should allow new requests
         assert!(limiter.try_acquire().await.is_allowed());
     }
+
+    #[tokio::test]
+    async fn test_sliding_window_acquire_many() {
+        let config = RateLimitConfig::new(10.0, 10, Duration::from_secs(60)).unwrap();
+        let limiter = SlidingWindowLimiter::new(config);
+
+        assert!(limiter.try_acquire_n(6).await.is_allowed());
+        assert!(limiter.try_acquire_n(3).await.is_allowed());
+        // Nothing is taken when the request does not fit
+        assert!(limiter.try_acquire_n(2).await.is_denied());
+        assert_eq!(limiter.available().await, 1);
+        assert!(limiter.try_acquire_n(11).await.is_denied());
+    }
+
+    #[tokio::test]
+    async fn test_sliding_window_simulated_time() {
+        let clock = Arc::new(infra_sim::SimulatedClock::new());
+        let config = RateLimitConfig::new(1.0, 2, Duration::from_secs(10)).unwrap();
+        let limiter = SlidingWindowLimiter::new(config).with_time_source(clock.clone());
+
+        assert!(limiter.try_acquire().await.is_allowed());
+        clock.advance(Duration::from_secs(6));
+        assert!(limiter.try_acquire().await.is_allowed());
+        assert!(!limiter.try_acquire().await.is_allowed());
+
+        // Only the first request has left the window
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(limiter.available().await, 1);
+    }
 }
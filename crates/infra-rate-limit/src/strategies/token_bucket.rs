@@ -6,7 +6,9 @@ use crate::{
     limiter::{RateLimitResult, RateLimiter},
 };
 use async_trait::async_trait;
+use infra_time::{TimeHandle, TimeSource};
 use parking_lot::Mutex;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Token bucket rate limiter.
@@ -17,6 +19,7 @@ use std::time::{Duration, Instant};
 pub struct TokenBucket {
     config: RateLimitConfig,
     state: Mutex<BucketState>,
+    clock: TimeHandle,
 }
 
 #[derive(Debug)]
@@ -34,12 +37,26 @@ impl TokenBucket {
                 tokens: config.burst_size as f64,
                 last_refill: Instant::now(),
             }),
+            clock: TimeHandle::default(),
         }
     }
 
+    /// Measures refills against a time source, such as a simulated clock,
+    /// rather than the system clock.
+    ///
+    /// [`try_acquire`](RateLimiter::try_acquire) and
+    /// [`available`](RateLimiter::available) then follow the source, while
+    /// [`acquire`](RateLimiter::acquire) still waits on tokio timers.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self.state.get_mut().last_refill = self.clock.instant();
+        self
+    }
+
     /// Refills tokens based on elapsed time.
     fn refill(&self, state: &mut BucketState) {
-        let now = Instant::now();
+        let now = self.clock.instant();
         let elapsed = now.duration_since(state.last_refill);
         let new_tokens = elapsed.as_secs_f64() * self.config.requests_per_second;
 
@@ -70,15 +87,22 @@ impl RateLimiter for TokenBucket {
     }
 
     async fn try_acquire(&self) -> RateLimitResult {
+        self.try_acquire_n(1).await
+    }
+
+    async fn try_acquire_n(&self, permits: u64) -> RateLimitResult {
         let mut state = self.state.lock();
         self.refill(&mut state);
 
-        if state.tokens >= 1.0 {
-            state.tokens -= 1.0;
+        let permits = permits as f64;
+        if state.tokens >= permits {
+            state.tokens -= permits;
             RateLimitResult::Allowed
         } else {
-            let tokens_needed = 1.0 - state.tokens;
+            let tokens_needed = permits - state.tokens;
             let wait_time = self.calculate_wait_time(tokens_needed);
+            #[cfg(feature = "otel")]
+            infra_otel::record_rate_limited("token_bucket", wait_time);
             RateLimitResult::Denied { wait_time }
         }
     }
@@ -92,7 +116,7 @@ impl RateLimiter for TokenBucket {
     async fn reset(&self) {
         let mut state = self.state.lock();
         state.tokens = self.config.burst_size as f64;
-        state.last_refill = Instant::now();
+        state.last_refill = self.clock.instant();
     }
 }
 
@@ -130,4 +154,31 @@ mod tests {
         // Should have at least 1 token available
         assert!(limiter.available().await >= 1);
     }
+
+    #[tokio::test]
+    async fn test_token_bucket_acquire_many() {
+        let config = RateLimitConfig::new(0.001, 10, Duration::from_secs(1)).unwrap();
+        let limiter = TokenBucket::new(config);
+
+        assert!(limiter.try_acquire_n(7).await.is_allowed());
+        // Nothing is taken when the request does not fit
+        assert!(limiter.try_acquire_n(4).await.is_denied());
+        assert_eq!(limiter.available().await, 3);
+        assert!(limiter.try_acquire_n(3).await.is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_simulated_time() {
+        let clock = Arc::new(infra_sim::SimulatedClock::new());
+        let config = RateLimitConfig::new(1.0, 2, Duration::from_secs(1)).unwrap();
+        let limiter = TokenBucket::new(config).with_time_source(clock.clone());
+
+        assert!(limiter.try_acquire().await.is_allowed());
+        assert!(limiter.try_acquire().await.is_allowed());
+        assert!(!limiter.try_acquire().await.is_allowed());
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(limiter.available().await, 1);
+        assert!(limiter.try_acquire().await.is_allowed());
+    }
 }
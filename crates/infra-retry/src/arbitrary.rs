@@ -0,0 +1,105 @@
+//! Proptest strategies for retry policy configurations.
+//!
+//! The generated configurations stay within realistic ranges (delays up to a
+//! minute, at most ten attempts), so that properties exercise the policies
+//! rather than float overflow.
+
+use crate::circuit::CircuitBreakerConfig;
+use crate::strategies::{ExponentialBackoff, FixedDelay, WithJitter};
+use proptest::prelude::*;
+use std::time::Duration;
+
+/// Strategy for delays between zero and ten seconds, in whole milliseconds.
+pub fn delay() -> impl Strategy<Value = Duration> {
+    (0u64..=10_000).prop_map(Duration::from_millis)
+}
+
+/// Strategy for attempt limits between zero and ten.
+pub fn max_attempts() -> impl Strategy<Value = u32> {
+    0u32..=10
+}
+
+/// Strategy for [`ExponentialBackoff`] policies with a multiplier of at
+/// least one and a maximum delay no smaller than the initial delay.
+pub fn exponential_backoff() -> impl Strategy<Value = ExponentialBackoff> {
+    (delay(), 0u64..=60_000, 1.0f64..4.0, max_attempts()).prop_map(
+        |(initial, extra_ms, multiplier, attempts)| {
+            ExponentialBackoff::new()
+                .with_initial_delay(initial)
+                .with_max_delay(initial + Duration::from_millis(extra_ms))
+                .with_multiplier(multiplier)
+                .with_max_attempts(attempts)
+        },
+    )
+}
+
+/// Strategy for [`FixedDelay`] policies.
+pub fn fixed_delay() -> impl Strategy<Value = FixedDelay> {
+    (delay(), max_attempts()).prop_map(|(delay, attempts)| FixedDelay::new(delay, attempts))
+}
+
+/// Strategy for [`WithJitter`] wrappers around policies from `inner`.
+pub fn with_jitter<S: Strategy>(inner: S) -> impl Strategy<Value = WithJitter<S::Value>> {
+    (inner, 0.0f64..=1.0).prop_map(|(policy, factor)| WithJitter::new(policy, factor))
+}
+
+/// Strategy for [`CircuitBreakerConfig`] thresholds.
+pub fn circuit_breaker_config() -> impl Strategy<Value = CircuitBreakerConfig> {
+    (1u32..=10, 1u32..=5, delay()).prop_map(|(failures, successes, open_duration)| {
+        CircuitBreakerConfig {
+            failure_threshold: failures,
+            success_threshold: successes,
+            open_duration,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{RetryDecision, RetryPolicy};
+
+    fn error() -> std::io::Error {
+        std::io::Error::other("transient")
+    }
+
+    proptest! {
+        #[test]
+        fn prop_backoff_bounded_and_monotonic(policy in exponential_backoff()) {
+            let delays: Vec<Duration> = (0..policy.max_attempts)
+                .map(|attempt| policy.delay_for(attempt).unwrap())
+                .collect();
+
+            prop_assert!(delays.iter().all(|d| *d <= policy.max_delay));
+            prop_assert!(delays.windows(2).all(|w| w[0] <= w[1]));
+        }
+
+        #[test]
+        fn prop_backoff_stops_at_max_attempts(policy in exponential_backoff(), extra in 0u32..5) {
+            let attempt = policy.max_attempts + extra;
+            prop_assert_eq!(policy.delay_for(attempt), None);
+            prop_assert_eq!(policy.should_retry(attempt, &error()), RetryDecision::Stop);
+        }
+
+        #[test]
+        fn prop_fixed_delay_is_constant(policy in fixed_delay()) {
+            for attempt in 0..policy.max_attempts {
+                prop_assert_eq!(policy.should_retry(attempt, &error()), RetryDecision::Retry(policy.delay));
+            }
+            prop_assert_eq!(policy.delay_for(policy.max_attempts), None);
+        }
+
+        #[test]
+        fn prop_jitter_stays_within_factor(policy in with_jitter(fixed_delay())) {
+            let base = policy.inner.delay.as_secs_f64() * 1000.0;
+            let spread = base * policy.jitter_factor / 2.0;
+
+            for attempt in 0..policy.max_attempts() {
+                let delay = policy.delay_for(attempt).unwrap().as_secs_f64() * 1000.0;
+                prop_assert!(delay >= (base - spread).floor() - 1.0);
+                prop_assert!(delay <= base + spread + 1e-6);
+            }
+            prop_assert_eq!(policy.max_attempts(), policy.inner.max_attempts);
+        }
+    }
+}
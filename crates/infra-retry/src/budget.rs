@@ -0,0 +1,296 @@
+//! Retry budgets shared across operations.
+//!
+//! A [`RetryBudget`] limits retries to a fraction of successful calls, so a
+//! failing dependency does not receive a multiple of its normal load from
+//! retries. Each success deposits a fraction of a token, each retry
+//! withdraws a whole token, and retries are refused once the bucket is empty.
+//! [`RetryBudget::per_window`] instead allows a fixed number of retries per
+//! fixed time window, however many calls succeed.
+
+use crate::policy::{RetryDecision, RetryPolicy};
+use infra_time::{TimeHandle, TimeSource};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Token bucket capping the fraction of calls that may be retried.
+///
+/// Share one budget (behind an [`Arc`]) between every operation calling the
+/// same dependency.
+#[derive(Debug)]
+pub struct RetryBudget {
+    ratio: f64,
+    max_tokens: f64,
+    min_per_second: f64,
+    window: Option<Duration>,
+    state: Mutex<BudgetState>,
+    clock: TimeHandle,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    tokens: f64,
+    refilled_at: Option<Instant>,
+}
+
+impl RetryBudget {
+    /// Creates a budget allowing retries for `ratio` of successful calls.
+    ///
+    /// With a ratio of `0.1`, at most one retry is allowed per ten
+    /// successes, beyond the initial burst of 10 tokens.
+    #[must_use]
+    pub fn new(ratio: f64) -> Self {
+        let max_tokens = 10.0;
+        Self {
+            ratio: ratio.max(0.0),
+            max_tokens,
+            min_per_second: 0.0,
+            window: None,
+            state: Mutex::new(BudgetState {
+                tokens: max_tokens,
+                refilled_at: None,
+            }),
+            clock: TimeHandle::default(),
+        }
+    }
+
+    /// Creates a budget allowing `max_retries` retries per `window`.
+    ///
+    /// Time is divided into fixed windows, the first starting with the first
+    /// use of the budget, and the bucket is refilled to `max_retries` tokens
+    /// at the start of each; successes deposit nothing.
+    #[must_use]
+    pub fn per_window(max_retries: u32, window: Duration) -> Self {
+        let mut budget = Self::new(0.0).with_max_tokens(f64::from(max_retries));
+        budget.window = Some(window);
+        budget
+    }
+
+    /// Sets the bucket capacity, which is also the initial burst of retries.
+    #[must_use]
+    pub fn with_max_tokens(mut self, max_tokens: f64) -> Self {
+        self.max_tokens = max_tokens.max(0.0);
+        self.lock().tokens = self.max_tokens;
+        self
+    }
+
+    /// Adds tokens over time, so a few retries are allowed even when no
+    /// calls succeed.
+    #[must_use]
+    pub fn with_min_retries_per_second(mut self, per_second: f64) -> Self {
+        self.min_per_second = per_second.max(0.0);
+        self
+    }
+
+    /// Measures windows and refills against a time source, such as an
+    /// infra-sim simulated clock, rather than the system clock.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self
+    }
+
+    /// Records a successful call, depositing `ratio` tokens.
+    pub fn deposit(&self) {
+        let mut state = self.refill();
+        state.tokens = (state.tokens + self.ratio).min(self.max_tokens);
+    }
+
+    /// Withdraws a token for a retry, returning `false` if the budget is
+    /// exhausted.
+    pub fn try_withdraw(&self) -> bool {
+        let mut state = self.refill();
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of tokens currently available.
+    pub fn available(&self) -> f64 {
+        self.refill().tokens
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BudgetState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn refill(&self) -> std::sync::MutexGuard<'_, BudgetState> {
+        let mut state = self.lock();
+        if let Some(window) = self.window {
+            let now = self.clock.instant();
+            if state
+                .refilled_at
+                .map_or(true, |start| now.saturating_duration_since(start) >= window)
+            {
+                state.tokens = self.max_tokens;
+                state.refilled_at = Some(now);
+            }
+        } else if self.min_per_second > 0.0 {
+            let now = self.clock.instant();
+            if let Some(refilled_at) = state.refilled_at {
+                let elapsed = now.saturating_duration_since(refilled_at).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * self.min_per_second).min(self.max_tokens);
+            }
+            state.refilled_at = Some(now);
+        }
+        state
+    }
+}
+
+impl Default for RetryBudget {
+    /// Allows retries for 20% of successful calls.
+    fn default() -> Self {
+        Self::new(0.2)
+    }
+}
+
+/// Policy wrapper that only retries while a [`RetryBudget`] has tokens.
+///
+/// Successful calls reported by the executor are deposited into the budget.
+#[derive(Debug, Clone)]
+pub struct BudgetedPolicy<P> {
+    /// The underlying retry policy.
+    pub inner: P,
+    /// The shared budget.
+    pub budget: Arc<RetryBudget>,
+}
+
+impl<P> BudgetedPolicy<P> {
+    /// Wraps a policy with a shared budget.
+    pub fn new(inner: P, budget: Arc<RetryBudget>) -> Self {
+        Self { inner, budget }
+    }
+
+    /// Withdraws a token for a retry decided by the inner policy.
+    fn withdraw(&self, decision: RetryDecision) -> RetryDecision {
+        match decision {
+            RetryDecision::Retry(delay) if self.budget.try_withdraw() => {
+                RetryDecision::Retry(delay)
+            }
+            _ => RetryDecision::Stop,
+        }
+    }
+}
+
+impl<P: RetryPolicy> RetryPolicy for BudgetedPolicy<P> {
+    fn should_retry(&self, attempt: u32, error: &dyn std::error::Error) -> RetryDecision {
+        self.withdraw(self.inner.should_retry(attempt, error))
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) -> RetryDecision {
+        self.withdraw(self.inner.decide(attempt, error))
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        self.inner.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.inner.max_attempts()
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.budget.deposit();
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) {
+        self.inner.on_failure(attempt, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::retry_with_policy;
+    use crate::strategies::FixedDelay;
+    use infra_sim::SimulatedClock;
+    use std::io;
+
+    #[test]
+    fn test_budget_withdraw_and_deposit() {
+        let budget = RetryBudget::new(0.5).with_max_tokens(2.0);
+        assert!(budget.try_withdraw());
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+
+        budget.deposit();
+        assert!(!budget.try_withdraw());
+        budget.deposit();
+        assert!(budget.try_withdraw());
+
+        for _ in 0..10 {
+            budget.deposit();
+        }
+        assert!((budget.available() - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_min_retries_per_second() {
+        let clock = Arc::new(SimulatedClock::new());
+        let budget = RetryBudget::new(0.0)
+            .with_max_tokens(1.0)
+            .with_min_retries_per_second(10.0)
+            .with_time_source(clock.clone());
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+
+        clock.advance(Duration::from_millis(100));
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+    }
+
+    #[test]
+    fn test_per_window() {
+        let clock = Arc::new(SimulatedClock::new());
+        let budget =
+            RetryBudget::per_window(2, Duration::from_secs(60)).with_time_source(clock.clone());
+        assert!(budget.try_withdraw());
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+
+        budget.deposit();
+        assert!(!budget.try_withdraw());
+        assert!(budget.available() < 1.0);
+
+        // No refill within the window, a full bucket in the next one
+        clock.advance(Duration::from_secs(59));
+        assert!(!budget.try_withdraw());
+        clock.advance(Duration::from_secs(1));
+        assert!(budget.try_withdraw());
+        assert!(budget.try_withdraw());
+        assert!(!budget.try_withdraw());
+    }
+
+    #[tokio::test]
+    async fn test_budget_stops_retry_storm() {
+        let budget = Arc::new(RetryBudget::new(0.1).with_max_tokens(3.0));
+        let policy = BudgetedPolicy::new(FixedDelay::new(Duration::ZERO, 5), budget.clone());
+
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let attempts = &attempts;
+        for _ in 0..3 {
+            let result: Result<(), io::Error> = retry_with_policy(
+                move || async move {
+                    attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    Err(io::Error::other("down"))
+                },
+                &policy,
+            )
+            .await;
+            assert!(result.is_err());
+        }
+
+        // 3 initial attempts plus the 3 retries the budget allowed
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 6);
+        assert!(!budget.try_withdraw());
+
+        let ok: Result<&str, io::Error> = retry_with_policy(|| async { Ok("up") }, &policy).await;
+        assert!(ok.is_ok());
+        assert!(budget.available() > 0.0);
+    }
+}
@@ -0,0 +1,363 @@
+//! Circuit breaker shared between calls to one dependency.
+//!
+//! [`CircuitBreaker`] is synchronous and needs no async runtime, so the same
+//! breaker backs the resilient executor, the infra-http client and the
+//! per-backend breakers of infra-router. [`CircuitBreakerPolicy`] applies it
+//! to a plain [`RetryPolicy`], without an executor.
+
+use crate::classify::{ClassifyError, ErrorClass, InfraErrorClassifier};
+use crate::policy::{RetryDecision, RetryPolicy};
+use infra_time::{TimeHandle, TimeSource};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// State of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls are allowed.
+    Closed,
+    /// Calls are rejected until the open duration elapses.
+    Open,
+    /// Trial calls are allowed, one at a time, to probe whether the
+    /// dependency recovered.
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "closed"),
+            Self::Open => write!(f, "open"),
+            Self::HalfOpen => write!(f, "half_open"),
+        }
+    }
+}
+
+/// Thresholds for a [`CircuitBreaker`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures before opening.
+    pub failure_threshold: u32,
+    /// Consecutive half-open successes before closing.
+    pub success_threshold: u32,
+    /// How long to stay open before allowing trial calls.
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            success_threshold: 3,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Circuit breaker shared between calls to one dependency.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+    clock: TimeHandle,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    failures: u32,
+    successes: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open probe in flight was admitted
+    probing: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker.
+    pub fn new(name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                failures: 0,
+                successes: 0,
+                opened_at: None,
+                probing: None,
+            }),
+            clock: TimeHandle::default(),
+        }
+    }
+
+    /// Measures the open duration against a time source, such as an
+    /// infra-sim simulated clock, rather than the system clock.
+    #[must_use]
+    pub fn with_time_source(mut self, source: Arc<dyn TimeSource>) -> Self {
+        self.clock = TimeHandle::from_source(source);
+        self
+    }
+
+    /// Returns the breaker's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Returns `true` if a call may proceed, moving from open to half-open
+    /// once the open duration has elapsed.
+    ///
+    /// While half-open, one probe is admitted at a time: further calls are
+    /// rejected until its outcome is recorded. A probe whose outcome is not
+    /// recorded within the open duration is presumed lost, and another one
+    /// is admitted.
+    pub fn allow(&self) -> bool {
+        let mut state = self.lock();
+        let now = self.clock.instant();
+        let elapsed = |at: Instant| now.saturating_duration_since(at);
+        match state.state {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => {
+                if state
+                    .probing
+                    .is_some_and(|at| elapsed(at) < self.config.open_duration)
+                {
+                    return false;
+                }
+                state.probing = Some(now);
+                true
+            }
+            CircuitState::Open => {
+                if state
+                    .opened_at
+                    .is_some_and(|at| elapsed(at) >= self.config.open_duration)
+                {
+                    self.transition(&mut state, CircuitState::HalfOpen);
+                    state.probing = Some(now);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Records a successful call.
+    pub fn record_success(&self) {
+        let mut state = self.lock();
+        state.failures = 0;
+        state.probing = None;
+        if state.state == CircuitState::HalfOpen {
+            state.successes += 1;
+            if state.successes >= self.config.success_threshold {
+                self.transition(&mut state, CircuitState::Closed);
+            }
+        }
+    }
+
+    /// Records a failed call.
+    pub fn record_failure(&self) {
+        let mut state = self.lock();
+        state.failures += 1;
+        state.probing = None;
+        let trip = match state.state {
+            CircuitState::Closed => state.failures >= self.config.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if trip {
+            state.opened_at = Some(self.clock.instant());
+            self.transition(&mut state, CircuitState::Open);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    #[cfg_attr(not(feature = "otel"), allow(clippy::unused_self))]
+    fn transition(&self, state: &mut BreakerState, to: CircuitState) {
+        #[cfg(feature = "otel")]
+        infra_otel::record_circuit_state_change(&self.name, &state.state, &to);
+        state.state = to;
+        state.successes = 0;
+        if to == CircuitState::Closed {
+            state.failures = 0;
+        }
+    }
+}
+
+/// Policy wrapper that stops retrying while a [`CircuitBreaker`] is open.
+///
+/// Every failed attempt, including the last one of a call, is recorded on
+/// the breaker and every success reported by the executor closes it again,
+/// so consecutive failures are counted across all calls sharing the
+/// breaker. Errors the classifier deems permanent, such as rejected
+/// requests, show the dependency is answering and are recorded as
+/// successes. Once the breaker opens, retries stop until the open duration
+/// has elapsed; the next retry then probes the dependency in the half-open
+/// state.
+///
+/// The first attempt of each call is not gated, as policies are only
+/// consulted after a failure. Use
+/// [`ResilientExecutor`](crate::ResilientExecutor) to reject calls up front.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerPolicy<P, C = InfraErrorClassifier> {
+    /// The underlying retry policy.
+    pub inner: P,
+    /// The shared circuit breaker.
+    pub breaker: Arc<CircuitBreaker>,
+    /// The classifier telling failures of the dependency from permanent
+    /// errors.
+    pub classifier: C,
+}
+
+impl<P> CircuitBreakerPolicy<P> {
+    /// Wraps a policy with a shared circuit breaker, classifying errors with
+    /// the [`InfraErrorClassifier`].
+    pub fn new(inner: P, breaker: Arc<CircuitBreaker>) -> Self {
+        Self::with_classifier(inner, breaker, InfraErrorClassifier)
+    }
+}
+
+impl<P, C> CircuitBreakerPolicy<P, C> {
+    /// Wraps a policy with a shared circuit breaker and a custom classifier.
+    pub fn with_classifier(inner: P, breaker: Arc<CircuitBreaker>, classifier: C) -> Self {
+        Self {
+            inner,
+            breaker,
+            classifier,
+        }
+    }
+
+    /// Admits a retry decided by the inner policy through the breaker.
+    fn admit(&self, decision: RetryDecision) -> RetryDecision {
+        match decision {
+            RetryDecision::Retry(delay) if self.breaker.allow() => RetryDecision::Retry(delay),
+            _ => RetryDecision::Stop,
+        }
+    }
+}
+
+impl<P: RetryPolicy, C: ClassifyError> RetryPolicy for CircuitBreakerPolicy<P, C> {
+    fn should_retry(&self, attempt: u32, error: &dyn std::error::Error) -> RetryDecision {
+        self.admit(self.inner.should_retry(attempt, error))
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) -> RetryDecision {
+        self.admit(self.inner.decide(attempt, error))
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        self.inner.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.inner.max_attempts()
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.breaker.record_success();
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) {
+        if self.classifier.classify(error) == ErrorClass::Permanent {
+            self.breaker.record_success();
+        } else {
+            self.breaker.record_failure();
+        }
+        self.inner.on_failure(attempt, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combinators::PolicyExt;
+    use crate::executor::retry_with_policy;
+    use crate::strategies::FixedDelay;
+    use infra_errors::InfraError;
+    use infra_sim::SimulatedClock;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_breaker_policy_stops_retries_across_calls() {
+        let clock = Arc::new(SimulatedClock::new());
+        let breaker = Arc::new(
+            CircuitBreaker::new(
+                "backend",
+                CircuitBreakerConfig {
+                    failure_threshold: 3,
+                    success_threshold: 1,
+                    open_duration: Duration::from_secs(30),
+                },
+            )
+            .with_time_source(clock.clone()),
+        );
+        let policy = FixedDelay::new(Duration::ZERO, 5).with_circuit_breaker(breaker.clone());
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+        let failing = move || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(io::Error::other("down"))
+        };
+
+        // The third consecutive failure opens the breaker
+        assert!(retry_with_policy(failing, &policy).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // While open, each call gets its first attempt but no retries
+        assert!(retry_with_policy(failing, &policy).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+        // After the cooldown a half-open probe is retried, and its success
+        // closes the breaker
+        clock.advance(Duration::from_secs(30));
+        let recovering = move || async move {
+            if attempts.fetch_add(1, Ordering::SeqCst) < 5 {
+                Err(io::Error::other("down"))
+            } else {
+                Ok("up")
+            }
+        };
+        assert_eq!(retry_with_policy(recovering, &policy).await.unwrap(), "up");
+        assert_eq!(attempts.load(Ordering::SeqCst), 6);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_policy_records_last_failure() {
+        let breaker = Arc::new(CircuitBreaker::new(
+            "backend",
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                success_threshold: 1,
+                open_duration: Duration::from_secs(60),
+            },
+        ));
+        let policy = FixedDelay::new(Duration::ZERO, 0).with_circuit_breaker(breaker.clone());
+
+        // Permanent errors show the dependency is answering
+        for _ in 0..3 {
+            let rejected = || async { Err::<(), _>(InfraError::validation("bad request")) };
+            assert!(retry_with_policy(rejected, &policy).await.is_err());
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // Without retries, the only failure of each call is recorded
+        for _ in 0..2 {
+            let failing = || async { Err::<(), _>(io::Error::other("down")) };
+            assert!(retry_with_policy(failing, &policy).await.is_err());
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+}
@@ -0,0 +1,297 @@
+//! Error classification for retry decisions.
+//!
+//! A [`ClassifyError`] implementation inspects a failed attempt's error and
+//! decides whether it is worth retrying at all. [`Classified`] wraps any
+//! [`RetryPolicy`] with a classifier, stopping on permanent errors and
+//! honoring server-provided retry-after delays.
+
+use crate::policy::{RetryDecision, RetryPolicy};
+use infra_errors::{InfraError, RetryAdvice};
+use std::error::Error;
+use std::time::Duration;
+
+/// How an error should be treated by a retry policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The error may succeed on retry; use the policy's delay.
+    Transient,
+    /// The error may succeed on retry, but not before the given delay.
+    RetryAfter(Duration),
+    /// Retrying cannot help.
+    Permanent,
+}
+
+/// Classifies errors for retry purposes.
+pub trait ClassifyError: Send + Sync {
+    /// Classifies the error from a failed attempt.
+    fn classify(&self, error: &(dyn Error + 'static)) -> ErrorClass;
+}
+
+impl<F> ClassifyError for F
+where
+    F: Fn(&(dyn Error + 'static)) -> ErrorClass + Send + Sync,
+{
+    fn classify(&self, error: &(dyn Error + 'static)) -> ErrorClass {
+        self(error)
+    }
+}
+
+impl From<RetryAdvice> for ErrorClass {
+    fn from(advice: RetryAdvice) -> Self {
+        match (advice.retryable, advice.delay) {
+            (false, _) => Self::Permanent,
+            (true, Some(delay)) => Self::RetryAfter(delay),
+            (true, None) => Self::Transient,
+        }
+    }
+}
+
+/// Classifier based on [`InfraError::retry_advice`].
+///
+/// The error and its source chain are searched for an [`InfraError`]. Errors
+/// of other types are treated as transient.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InfraErrorClassifier;
+
+impl InfraErrorClassifier {
+    /// Classifies an [`InfraError`].
+    #[must_use]
+    pub fn classify_infra(error: &InfraError) -> ErrorClass {
+        error.retry_advice().into()
+    }
+}
+
+impl ClassifyError for InfraErrorClassifier {
+    fn classify(&self, error: &(dyn Error + 'static)) -> ErrorClass {
+        find_infra(error).map_or(ErrorClass::Transient, Self::classify_infra)
+    }
+}
+
+/// Classifier for operations that are not idempotent, based on
+/// [`InfraError::retry_advice`].
+///
+/// Like [`InfraErrorClassifier`], but errors after which the operation may
+/// already have taken effect, such as server errors and timeouts, are
+/// permanent, so retrying never applies it twice. Errors of other types are
+/// treated as permanent too. Operations sent with an idempotency key can
+/// use [`InfraErrorClassifier`] instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NonIdempotentClassifier;
+
+impl ClassifyError for NonIdempotentClassifier {
+    fn classify(&self, error: &(dyn Error + 'static)) -> ErrorClass {
+        match find_infra(error).map(InfraError::retry_advice) {
+            Some(advice) if advice.allows_retry(false) => advice.into(),
+            _ => ErrorClass::Permanent,
+        }
+    }
+}
+
+/// Finds the first [`InfraError`] in an error's source chain.
+fn find_infra<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a InfraError> {
+    let mut current = Some(error);
+    while let Some(error) = current {
+        if let Some(infra) = error.downcast_ref::<InfraError>() {
+            return Some(infra);
+        }
+        current = error.source();
+    }
+    None
+}
+
+/// Policy wrapper that consults a [`ClassifyError`] before retrying.
+///
+/// Permanent errors stop immediately. Retry-after delays replace shorter
+/// delays from the inner policy, and stop retrying when they exceed
+/// [`max_retry_after`](Self::with_max_retry_after).
+#[derive(Debug, Clone)]
+pub struct Classified<P, C = InfraErrorClassifier> {
+    /// The underlying retry policy.
+    pub inner: P,
+    /// The error classifier.
+    pub classifier: C,
+    /// Longest server-provided delay that will be honored.
+    pub max_retry_after: Option<Duration>,
+}
+
+impl<P> Classified<P> {
+    /// Wraps a policy with the [`InfraErrorClassifier`].
+    pub fn new(inner: P) -> Self {
+        Self::with_classifier(inner, InfraErrorClassifier)
+    }
+}
+
+impl<P, C> Classified<P, C> {
+    /// Wraps a policy with a custom classifier.
+    pub fn with_classifier(inner: P, classifier: C) -> Self {
+        Self {
+            inner,
+            classifier,
+            max_retry_after: None,
+        }
+    }
+
+    /// Sets the longest retry-after delay to wait for before giving up.
+    #[must_use]
+    pub fn with_max_retry_after(mut self, max: Duration) -> Self {
+        self.max_retry_after = Some(max);
+        self
+    }
+}
+
+impl<P: RetryPolicy, C: ClassifyError> RetryPolicy for Classified<P, C> {
+    /// Defers to the inner policy, as classifying needs an error that can be
+    /// downcast, see [`decide`](RetryPolicy::decide).
+    fn should_retry(&self, attempt: u32, error: &dyn Error) -> RetryDecision {
+        self.inner.should_retry(attempt, error)
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn Error + 'static)) -> RetryDecision {
+        let class = self.classifier.classify(error);
+        if class == ErrorClass::Permanent {
+            return RetryDecision::Stop;
+        }
+        match (self.inner.decide(attempt, error), class) {
+            (RetryDecision::Retry(_), ErrorClass::RetryAfter(after))
+                if self.max_retry_after.is_some_and(|max| after > max) =>
+            {
+                RetryDecision::Stop
+            }
+            (RetryDecision::Retry(delay), ErrorClass::RetryAfter(after)) => {
+                RetryDecision::Retry(delay.max(after))
+            }
+            (decision, _) => decision,
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        self.inner.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.inner.max_attempts()
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn Error + 'static)) {
+        self.inner.on_failure(attempt, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::retry_with_policy;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("request failed")]
+    struct Wrapped(#[source] InfraError);
+
+    #[test]
+    fn test_infra_classifier() {
+        let classifier = InfraErrorClassifier;
+        assert_eq!(
+            classifier.classify(&InfraError::validation("bad input")),
+            ErrorClass::Permanent
+        );
+        assert_eq!(
+            classifier.classify(&InfraError::http_with_status(503, "unavailable")),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            classifier.classify(&InfraError::http_with_status(429, "slow down")),
+            ErrorClass::RetryAfter(Duration::from_secs(30))
+        );
+        assert_eq!(
+            classifier.classify(&Wrapped(InfraError::validation("bad input"))),
+            ErrorClass::Permanent
+        );
+        assert_eq!(
+            classifier.classify(&io::Error::other("reset")),
+            ErrorClass::Transient
+        );
+    }
+
+    #[test]
+    fn test_non_idempotent_classifier() {
+        let classifier = NonIdempotentClassifier;
+        assert_eq!(
+            classifier.classify(&InfraError::http_with_status(502, "bad gateway")),
+            ErrorClass::Permanent
+        );
+        assert_eq!(
+            classifier.classify(&Wrapped(InfraError::http_with_status(503, "unavailable"))),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            classifier.classify(&InfraError::http_with_status(429, "slow down")),
+            ErrorClass::RetryAfter(Duration::from_secs(30))
+        );
+        assert_eq!(
+            classifier.classify(&io::Error::other("reset")),
+            ErrorClass::Permanent
+        );
+    }
+
+    #[test]
+    fn test_retry_after_honored() {
+        let policy = Classified::new(FixedDelay::new(Duration::from_millis(10), 3));
+        let limited = InfraError::http_with_status(429, "slow down");
+        assert_eq!(
+            policy.decide(0, &limited),
+            RetryDecision::Retry(Duration::from_secs(30))
+        );
+        assert_eq!(
+            policy.decide(0, &InfraError::http_with_status(502, "bad gateway")),
+            RetryDecision::Retry(Duration::from_millis(10))
+        );
+
+        let capped = policy.with_max_retry_after(Duration::from_secs(5));
+        assert_eq!(capped.decide(0, &limited), RetryDecision::Stop);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_error_not_retried() {
+        let policy = Classified::new(FixedDelay::new(Duration::ZERO, 5));
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+
+        let result: Result<(), InfraError> = retry_with_policy(
+            move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(InfraError::validation("bad input"))
+            },
+            &policy,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_closure_classifier() {
+        let classifier = |error: &(dyn Error + 'static)| {
+            if error.to_string().contains("fatal") {
+                ErrorClass::Permanent
+            } else {
+                ErrorClass::Transient
+            }
+        };
+        let policy = Classified::with_classifier(FixedDelay::new(Duration::ZERO, 2), classifier);
+        assert_eq!(
+            policy.decide(0, &io::Error::other("fatal")),
+            RetryDecision::Stop
+        );
+        assert_eq!(
+            policy.decide(0, &io::Error::other("reset")),
+            RetryDecision::Retry(Duration::ZERO)
+        );
+    }
+}
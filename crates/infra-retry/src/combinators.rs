@@ -0,0 +1,372 @@
+//! Combinators for building retry policies from simpler ones.
+//!
+//! [`PolicyExt`] is implemented for every [`RetryPolicy`], so behaviors such
+//! as "three fast retries, then slow ones, for at most 30 seconds in total"
+//! can be written declaratively:
+//!
+//! ```
+//! use infra_retry::{ExponentialBackoff, FixedDelay, PolicyExt};
+//! use std::time::Duration;
+//!
+//! let policy = FixedDelay::new(Duration::from_millis(50), 3)
+//!     .chain(ExponentialBackoff::default().with_initial_delay(Duration::from_secs(1)))
+//!     .max_total_delay(Duration::from_secs(30));
+//! ```
+//!
+//! [`first_n_with`] takes the first retries of a longer policy, so the same
+//! can be written as `first_n_with(3, fast).then(slow)`.
+
+use crate::budget::{BudgetedPolicy, RetryBudget};
+use crate::circuit::{CircuitBreaker, CircuitBreakerPolicy};
+use crate::policy::{RetryDecision, RetryPolicy};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Limits `policy` to its first `n` retries.
+pub fn first_n_with<P: RetryPolicy>(n: u32, policy: P) -> FirstN<P> {
+    FirstN { inner: policy, n }
+}
+
+/// Extension methods for combining retry policies.
+pub trait PolicyExt: RetryPolicy + Sized {
+    /// Uses `next` once this policy has used all its retries.
+    ///
+    /// Attempt numbers passed to `next` restart at zero. If this policy stops
+    /// before exhausting its attempts, for example on a permanent error, the
+    /// chain stops too.
+    fn chain<B: RetryPolicy>(self, next: B) -> Then<Self, B> {
+        Then { first: self, next }
+    }
+
+    /// Uses `next` once this policy has used all its retries, like
+    /// [`chain`](Self::chain).
+    fn then<B: RetryPolicy>(self, next: B) -> Then<Self, B> {
+        self.chain(next)
+    }
+
+    /// Stops retrying once the delays so far plus the next delay would exceed
+    /// `max`.
+    ///
+    /// Earlier delays are taken from [`RetryPolicy::delay_for`], so jitter
+    /// applied to them is not accounted for.
+    fn max_total_delay(self, max: Duration) -> MaxTotalDelay<Self> {
+        MaxTotalDelay { inner: self, max }
+    }
+
+    /// Only retries while `budget` has tokens, see [`BudgetedPolicy`].
+    ///
+    /// Clones of the budget can be shared by policies for many operations,
+    /// capping their combined retries.
+    fn with_budget(self, budget: Arc<RetryBudget>) -> BudgetedPolicy<Self> {
+        BudgetedPolicy::new(self, budget)
+    }
+
+    /// Stops retrying while `breaker` is open, see [`CircuitBreakerPolicy`].
+    ///
+    /// Share the breaker between the policies of every operation calling the
+    /// same dependency, so their failures open it together.
+    fn with_circuit_breaker(self, breaker: Arc<CircuitBreaker>) -> CircuitBreakerPolicy<Self> {
+        CircuitBreakerPolicy::new(self, breaker)
+    }
+
+    /// Only retries errors for which `predicate` returns `true`.
+    fn only_if<F>(self, predicate: F) -> OnlyIf<Self, F>
+    where
+        F: Fn(&(dyn Error + 'static)) -> bool + Send + Sync,
+    {
+        OnlyIf {
+            inner: self,
+            predicate,
+        }
+    }
+}
+
+impl<P: RetryPolicy> PolicyExt for P {}
+
+/// Policy limited to the first retries of another, see [`first_n_with`].
+#[derive(Debug, Clone)]
+pub struct FirstN<P> {
+    inner: P,
+    n: u32,
+}
+
+impl<P: RetryPolicy> RetryPolicy for FirstN<P> {
+    fn should_retry(&self, attempt: u32, error: &dyn Error) -> RetryDecision {
+        if attempt >= self.n {
+            return RetryDecision::Stop;
+        }
+        self.inner.should_retry(attempt, error)
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn Error + 'static)) -> RetryDecision {
+        if attempt >= self.n {
+            return RetryDecision::Stop;
+        }
+        self.inner.decide(attempt, error)
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.n {
+            return None;
+        }
+        self.inner.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.inner.max_attempts().min(self.n)
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn Error + 'static)) {
+        self.inner.on_failure(attempt, error);
+    }
+}
+
+/// Two policies applied one after the other, see [`PolicyExt::chain`].
+#[derive(Debug, Clone)]
+pub struct Then<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<A: RetryPolicy, B: RetryPolicy> RetryPolicy for Then<A, B> {
+    fn should_retry(&self, attempt: u32, error: &dyn Error) -> RetryDecision {
+        let split = self.first.max_attempts();
+        if attempt < split {
+            self.first.should_retry(attempt, error)
+        } else {
+            self.next.should_retry(attempt - split, error)
+        }
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn Error + 'static)) -> RetryDecision {
+        let split = self.first.max_attempts();
+        if attempt < split {
+            self.first.decide(attempt, error)
+        } else {
+            self.next.decide(attempt - split, error)
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        let split = self.first.max_attempts();
+        if attempt < split {
+            self.first.delay_for(attempt)
+        } else {
+            self.next.delay_for(attempt - split)
+        }
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.first
+            .max_attempts()
+            .saturating_add(self.next.max_attempts())
+    }
+
+    fn on_success(&self, attempt: u32) {
+        let split = self.first.max_attempts();
+        if attempt < split {
+            self.first.on_success(attempt);
+        } else {
+            self.next.on_success(attempt - split);
+        }
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn Error + 'static)) {
+        let split = self.first.max_attempts();
+        if attempt < split {
+            self.first.on_failure(attempt, error);
+        } else {
+            self.next.on_failure(attempt - split, error);
+        }
+    }
+}
+
+/// Policy bounded by a cumulative delay, see [`PolicyExt::max_total_delay`].
+#[derive(Debug, Clone)]
+pub struct MaxTotalDelay<P> {
+    inner: P,
+    max: Duration,
+}
+
+impl<P: RetryPolicy> MaxTotalDelay<P> {
+    /// Stops a retry whose delay would exceed the cumulative bound.
+    fn cap(&self, attempt: u32, decision: RetryDecision) -> RetryDecision {
+        match decision {
+            RetryDecision::Retry(delay) => {
+                let waited: Duration = (0..attempt).filter_map(|a| self.inner.delay_for(a)).sum();
+                if waited + delay > self.max {
+                    RetryDecision::Stop
+                } else {
+                    RetryDecision::Retry(delay)
+                }
+            }
+            RetryDecision::Stop => RetryDecision::Stop,
+        }
+    }
+}
+
+impl<P: RetryPolicy> RetryPolicy for MaxTotalDelay<P> {
+    fn should_retry(&self, attempt: u32, error: &dyn Error) -> RetryDecision {
+        self.cap(attempt, self.inner.should_retry(attempt, error))
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn Error + 'static)) -> RetryDecision {
+        self.cap(attempt, self.inner.decide(attempt, error))
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        self.inner.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.inner.max_attempts()
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn Error + 'static)) {
+        self.inner.on_failure(attempt, error);
+    }
+}
+
+/// Policy retrying only matching errors, see [`PolicyExt::only_if`].
+#[derive(Clone)]
+pub struct OnlyIf<P, F> {
+    inner: P,
+    predicate: F,
+}
+
+impl<P: std::fmt::Debug, F> std::fmt::Debug for OnlyIf<P, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OnlyIf")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P, F> RetryPolicy for OnlyIf<P, F>
+where
+    P: RetryPolicy,
+    F: Fn(&(dyn Error + 'static)) -> bool + Send + Sync,
+{
+    /// Stops, as the predicate needs an error that can be downcast, see
+    /// [`decide`](RetryPolicy::decide).
+    fn should_retry(&self, _attempt: u32, _error: &dyn Error) -> RetryDecision {
+        RetryDecision::Stop
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn Error + 'static)) -> RetryDecision {
+        if (self.predicate)(error) {
+            self.inner.decide(attempt, error)
+        } else {
+            RetryDecision::Stop
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        self.inner.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.inner.max_attempts()
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn Error + 'static)) {
+        self.inner.on_failure(attempt, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::retry_with_policy;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn error() -> io::Error {
+        io::Error::other("down")
+    }
+
+    #[test]
+    fn test_then_switches_policy() {
+        let fast = Duration::from_millis(10);
+        let slow = Duration::from_secs(1);
+        let policy = first_n_with(2, FixedDelay::new(fast, 5)).then(FixedDelay::new(slow, 3));
+
+        assert_eq!(policy.max_attempts(), 5);
+        assert_eq!(policy.should_retry(0, &error()), RetryDecision::Retry(fast));
+        assert_eq!(policy.should_retry(1, &error()), RetryDecision::Retry(fast));
+        assert_eq!(policy.should_retry(2, &error()), RetryDecision::Retry(slow));
+        assert_eq!(policy.delay_for(4), Some(slow));
+        assert_eq!(policy.should_retry(5, &error()), RetryDecision::Stop);
+    }
+
+    #[test]
+    fn test_max_total_delay() {
+        let policy =
+            FixedDelay::new(Duration::from_secs(10), 10).max_total_delay(Duration::from_secs(30));
+
+        assert!(matches!(
+            policy.should_retry(2, &error()),
+            RetryDecision::Retry(_)
+        ));
+        assert_eq!(policy.should_retry(3, &error()), RetryDecision::Stop);
+    }
+
+    #[tokio::test]
+    async fn test_only_if() {
+        let policy =
+            FixedDelay::new(Duration::ZERO, 5).only_if(|e| e.to_string().contains("timeout"));
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+
+        let result: Result<(), io::Error> = retry_with_policy(
+            move || async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(io::Error::other("timeout"))
+                } else {
+                    Err(io::Error::other("refused"))
+                }
+            },
+            &policy,
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "refused");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_with_budget_caps_retries_across_calls() {
+        let budget = Arc::new(RetryBudget::per_window(3, Duration::from_secs(3600)));
+        let reads = FixedDelay::new(Duration::ZERO, 5).with_budget(budget.clone());
+        let writes =
+            first_n_with(1, FixedDelay::new(Duration::ZERO, 2)).with_budget(budget.clone());
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+        let failing = move || async move {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(error())
+        };
+
+        assert!(retry_with_policy(failing, &writes).await.is_err());
+        assert!(retry_with_policy(failing, &reads).await.is_err());
+        assert!(retry_with_policy(failing, &reads).await.is_err());
+
+        // 3 initial attempts plus 1 write retry and the 2 retries left
+        assert_eq!(attempts.load(Ordering::SeqCst), 6);
+        assert_eq!(reads.should_retry(0, &error()), RetryDecision::Stop);
+    }
+}
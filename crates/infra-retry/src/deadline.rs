@@ -0,0 +1,328 @@
+//! Retries bounded by per-attempt timeouts and an overall deadline.
+
+use crate::observer::{RetryObserver, DEFAULT_OBSERVER};
+use crate::policy::{RetryDecision, RetryPolicy};
+use std::future::Future;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::{sleep, timeout, Instant};
+
+/// Time limits applied by [`retry_with_deadline`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadline {
+    /// Maximum duration of a single attempt.
+    pub attempt_timeout: Option<Duration>,
+    /// Maximum duration of the whole operation, including delays.
+    pub total: Option<Duration>,
+}
+
+impl Deadline {
+    /// Creates a deadline with no limits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a deadline bounding the whole operation.
+    #[must_use]
+    pub fn total(total: Duration) -> Self {
+        Self::new().with_total(total)
+    }
+
+    /// Sets the per-attempt timeout.
+    #[must_use]
+    pub fn with_attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the overall deadline.
+    #[must_use]
+    pub fn with_total(mut self, total: Duration) -> Self {
+        self.total = Some(total);
+        self
+    }
+}
+
+/// Error from a single attempt made by [`retry_with_deadline`].
+#[derive(Debug, Error)]
+pub enum AttemptError<E> {
+    /// The operation returned an error.
+    #[error(transparent)]
+    Failed(E),
+    /// The attempt did not complete within the per-attempt timeout.
+    #[error("attempt timed out after {0:?}")]
+    TimedOut(Duration),
+}
+
+impl<E> AttemptError<E> {
+    /// Returns the operation's error, if the attempt did not time out.
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            Self::Failed(error) => Some(error),
+            Self::TimedOut(_) => None,
+        }
+    }
+}
+
+/// Reason [`retry_with_deadline`] gave up.
+#[derive(Debug, Error)]
+pub enum DeadlineError<E> {
+    /// The retry policy stopped or ran out of attempts.
+    #[error("retries exhausted after {attempts} attempts: {last_error}")]
+    Exhausted {
+        /// Number of attempts made.
+        attempts: u32,
+        /// Error from the last attempt.
+        last_error: AttemptError<E>,
+    },
+    /// The overall deadline elapsed, or would elapse before the next retry.
+    #[error("deadline exceeded after {attempts} attempts in {elapsed:?}")]
+    DeadlineExceeded {
+        /// Number of attempts made.
+        attempts: u32,
+        /// Time spent before giving up.
+        elapsed: Duration,
+        /// Error from the last completed attempt, if any.
+        last_error: Option<AttemptError<E>>,
+    },
+}
+
+impl<E> DeadlineError<E> {
+    /// Returns `true` if the overall deadline caused the failure.
+    pub fn is_deadline_exceeded(&self) -> bool {
+        matches!(self, Self::DeadlineExceeded { .. })
+    }
+
+    /// Returns the number of attempts made.
+    pub fn attempts(&self) -> u32 {
+        match self {
+            Self::Exhausted { attempts, .. } | Self::DeadlineExceeded { attempts, .. } => *attempts,
+        }
+    }
+
+    /// Returns the error from the last attempt, if any.
+    pub fn last_error(&self) -> Option<&AttemptError<E>> {
+        match self {
+            Self::Exhausted { last_error, .. } => Some(last_error),
+            Self::DeadlineExceeded { last_error, .. } => last_error.as_ref(),
+        }
+    }
+}
+
+/// Retries an async operation within a per-attempt timeout and an overall
+/// deadline.
+///
+/// Each attempt is cancelled once it exceeds the attempt timeout or the time
+/// remaining until the deadline, whichever is shorter. Timed-out attempts are
+/// treated as failures and offered to the policy. A retry whose delay would
+/// end past the deadline is not attempted.
+///
+/// # Errors
+///
+/// Returns [`DeadlineError::DeadlineExceeded`] if the deadline elapses before
+/// an attempt succeeds, and [`DeadlineError::Exhausted`] if the policy gives
+/// up first.
+///
+/// # Examples
+///
+/// ```no_run
+/// use infra_retry::{retry_with_deadline, Deadline, FixedDelay};
+/// use std::io;
+/// use std::time::Duration;
+///
+/// # async fn example() {
+/// let policy = FixedDelay::new(Duration::from_millis(100), 5);
+/// let deadline = Deadline::total(Duration::from_secs(2))
+///     .with_attempt_timeout(Duration::from_millis(500));
+///
+/// let result = retry_with_deadline(
+///     || async { Ok::<_, io::Error>("success") },
+///     &policy,
+///     deadline,
+/// )
+/// .await;
+///
+/// if let Err(error) = result {
+///     if error.is_deadline_exceeded() {
+///         eprintln!("gave up: {error}");
+///     }
+/// }
+/// # }
+/// ```
+pub async fn retry_with_deadline<F, Fut, T, E>(
+    mut operation: F,
+    policy: &dyn RetryPolicy,
+    deadline: Deadline,
+) -> Result<T, DeadlineError<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::error::Error + 'static,
+{
+    let started = Instant::now();
+    let expires_at = deadline.total.map(|total| started + total);
+    let max_attempts = policy.max_attempts();
+    let mut attempt = 0;
+    let mut last_error = None;
+
+    loop {
+        let remaining = expires_at.map(|at| at.saturating_duration_since(Instant::now()));
+        if remaining == Some(Duration::ZERO) {
+            return Err(DeadlineError::DeadlineExceeded {
+                attempts: attempt,
+                elapsed: started.elapsed(),
+                last_error,
+            });
+        }
+
+        let limit = match (deadline.attempt_timeout, remaining) {
+            (Some(a), Some(r)) => Some(a.min(r)),
+            (a, r) => a.or(r),
+        };
+        let result = match limit {
+            Some(limit) => match timeout(limit, operation()).await {
+                Ok(result) => result.map_err(AttemptError::Failed),
+                Err(_) if Some(limit) == remaining => {
+                    let error = AttemptError::TimedOut(limit);
+                    policy.on_failure(attempt, &error);
+                    return Err(DeadlineError::DeadlineExceeded {
+                        attempts: attempt + 1,
+                        elapsed: started.elapsed(),
+                        last_error: Some(error),
+                    });
+                }
+                Err(_) => Err(AttemptError::TimedOut(limit)),
+            },
+            None => operation().await.map_err(AttemptError::Failed),
+        };
+
+        let error = match result {
+            Ok(value) => {
+                policy.on_success(attempt);
+                DEFAULT_OBSERVER.on_success(attempt + 1);
+                return Ok(value);
+            }
+            Err(error) => error,
+        };
+        policy.on_failure(attempt, &error);
+
+        let decision = if attempt >= max_attempts {
+            RetryDecision::Stop
+        } else {
+            policy.decide(attempt, &error)
+        };
+
+        match decision {
+            RetryDecision::Retry(delay) => {
+                if expires_at.is_some_and(|at| Instant::now() + delay >= at) {
+                    DEFAULT_OBSERVER.on_give_up(attempt + 1, &error);
+                    return Err(DeadlineError::DeadlineExceeded {
+                        attempts: attempt + 1,
+                        elapsed: started.elapsed(),
+                        last_error: Some(error),
+                    });
+                }
+                DEFAULT_OBSERVER.on_retry(attempt + 2, &error, delay);
+                if delay > Duration::ZERO {
+                    sleep(delay).await;
+                }
+                last_error = Some(error);
+                attempt += 1;
+            }
+            RetryDecision::Stop => {
+                DEFAULT_OBSERVER.on_give_up(attempt + 1, &error);
+                return Err(DeadlineError::Exhausted {
+                    attempts: attempt + 1,
+                    last_error: error,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_policy_exhausted() {
+        let policy = FixedDelay::new(Duration::ZERO, 2);
+        let result: Result<(), _> = retry_with_deadline(
+            || async { Err(io::Error::other("down")) },
+            &policy,
+            Deadline::total(Duration::from_secs(5)),
+        )
+        .await;
+
+        let error = result.unwrap_err();
+        assert!(!error.is_deadline_exceeded());
+        assert_eq!(error.attempts(), 3);
+        assert!(matches!(error.last_error(), Some(AttemptError::Failed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_attempt_timeout_is_retried() {
+        let policy = FixedDelay::new(Duration::ZERO, 3);
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+
+        let result = retry_with_deadline(
+            move || async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    sleep(Duration::from_secs(5)).await;
+                }
+                Ok::<_, io::Error>("done")
+            },
+            &policy,
+            Deadline::new().with_attempt_timeout(Duration::from_millis(20)),
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_exceeded() {
+        let policy = FixedDelay::new(Duration::from_millis(20), 100);
+        let result: Result<(), _> = retry_with_deadline(
+            || async { Err(io::Error::other("down")) },
+            &policy,
+            Deadline::total(Duration::from_millis(70)),
+        )
+        .await;
+
+        let error = result.unwrap_err();
+        assert!(error.is_deadline_exceeded());
+        assert!(error.attempts() < 100);
+        assert!(error.last_error().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_slow_attempt_hits_deadline() {
+        let policy = FixedDelay::new(Duration::ZERO, 5);
+        let started = std::time::Instant::now();
+        let result: Result<(), DeadlineError<io::Error>> = retry_with_deadline(
+            || async {
+                sleep(Duration::from_secs(5)).await;
+                Ok(())
+            },
+            &policy,
+            Deadline::total(Duration::from_millis(30)),
+        )
+        .await;
+
+        let error = result.unwrap_err();
+        assert!(error.is_deadline_exceeded());
+        assert_eq!(error.attempts(), 1);
+        assert!(matches!(
+            error.last_error(),
+            Some(AttemptError::TimedOut(_))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
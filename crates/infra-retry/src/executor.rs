@@ -1,10 +1,13 @@
 //! Retry execution logic and traits.
 
+use crate::observer::{RetryObserver, DEFAULT_OBSERVER};
 use crate::policy::{RetryDecision, RetryPolicy};
+use crate::sleep::Sleeper;
+#[cfg(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))]
+use crate::sleep::DEFAULT_SLEEPER;
 use async_trait::async_trait;
 use std::future::Future;
 use std::time::Duration;
-use tokio::time::sleep;
 
 /// Trait for operations that can be retried.
 ///
@@ -48,12 +51,12 @@ pub trait Retryable {
 ///
 /// # async fn example() -> Result<(), io::Error> {
 /// let policy = ExponentialBackoff::default();
-/// let mut attempt = 0;
+/// let attempt = std::sync::atomic::AtomicU32::new(0);
+/// let attempt = &attempt;
 ///
 /// let result = retry_with_policy(
-///     || async {
-///         attempt += 1;
-///         if attempt < 3 {
+///     move || async move {
+///         if attempt.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1 < 3 {
 ///             Err(io::Error::new(io::ErrorKind::Other, "temporary error"))
 ///         } else {
 ///             Ok("success")
@@ -64,9 +67,67 @@ pub trait Retryable {
 /// # Ok(())
 /// # }
 /// ```
+#[cfg(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))]
 pub async fn retry_with_policy<F, Fut, T, E>(
+    operation: F,
+    policy: &dyn RetryPolicy,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::error::Error + 'static,
+{
+    run(operation, policy, &DEFAULT_OBSERVER, &DEFAULT_SLEEPER).await
+}
+
+/// Retries an async operation, reporting each retry to an observer.
+///
+/// Behaves like [`retry_with_policy`], which uses an observer emitting
+/// `infra-otel` span events when the `otel` feature is enabled.
+///
+/// # Errors
+///
+/// Returns the last error encountered once the policy stops retrying.
+#[cfg(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))]
+pub async fn retry_with_observer<F, Fut, T, E>(
+    operation: F,
+    policy: &dyn RetryPolicy,
+    observer: &dyn RetryObserver,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::error::Error + 'static,
+{
+    run(operation, policy, observer, &DEFAULT_SLEEPER).await
+}
+
+/// Retries an async operation, waiting between attempts with `sleeper`.
+///
+/// Use this to run retries on an async runtime other than tokio, or to
+/// control time in tests.
+///
+/// # Errors
+///
+/// Returns the last error encountered once the policy stops retrying.
+pub async fn retry_with_sleeper<F, Fut, T, E>(
+    operation: F,
+    policy: &dyn RetryPolicy,
+    sleeper: &dyn Sleeper,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::error::Error + 'static,
+{
+    run(operation, policy, &DEFAULT_OBSERVER, sleeper).await
+}
+
+pub(crate) async fn run<F, Fut, T, E>(
     mut operation: F,
     policy: &dyn RetryPolicy,
+    observer: &dyn RetryObserver,
+    sleeper: &dyn Sleeper,
 ) -> Result<T, E>
 where
     F: FnMut() -> Fut,
@@ -78,22 +139,30 @@ where
 
     loop {
         match operation().await {
-            Ok(result) => return Ok(result),
+            Ok(result) => {
+                policy.on_success(attempt);
+                observer.on_success(attempt + 1);
+                return Ok(result);
+            }
             Err(error) => {
+                policy.on_failure(attempt, &error);
                 if attempt >= max_attempts {
+                    observer.on_give_up(attempt + 1, &error);
                     return Err(error);
                 }
 
-                let decision = policy.should_retry(attempt, &error);
+                let decision = policy.decide(attempt, &error);
 
                 match decision {
                     RetryDecision::Retry(delay) => {
+                        observer.on_retry(attempt + 2, &error, delay);
                         if delay > Duration::ZERO {
-                            sleep(delay).await;
+                            sleeper.sleep(delay).await;
                         }
                         attempt += 1;
                     }
                     RetryDecision::Stop => {
+                        observer.on_give_up(attempt + 1, &error);
                         return Err(error);
                     }
                 }
@@ -112,6 +181,7 @@ where
 /// # Returns
 ///
 /// The result of the operation if successful, or the last error encountered.
+#[cfg(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))]
 pub async fn retry_retryable<R>(
     retryable: &mut R,
     policy: &dyn RetryPolicy,
@@ -124,26 +194,30 @@ where
 
     loop {
         match retryable.execute().await {
-            Ok(result) => return Ok(result),
+            Ok(result) => {
+                policy.on_success(attempt);
+                DEFAULT_OBSERVER.on_success(attempt + 1);
+                return Ok(result);
+            }
             Err(error) => {
-                if !retryable.is_retryable(&error) {
+                policy.on_failure(attempt, &error);
+                if !retryable.is_retryable(&error) || attempt >= max_attempts {
+                    DEFAULT_OBSERVER.on_give_up(attempt + 1, &error);
                     return Err(error);
                 }
 
-                if attempt >= max_attempts {
-                    return Err(error);
-                }
-
-                let decision = policy.should_retry(attempt, &error);
+                let decision = policy.decide(attempt, &error);
 
                 match decision {
                     RetryDecision::Retry(delay) => {
+                        DEFAULT_OBSERVER.on_retry(attempt + 2, &error, delay);
                         if delay > Duration::ZERO {
-                            sleep(delay).await;
+                            DEFAULT_SLEEPER.sleep(delay).await;
                         }
                         attempt += 1;
                     }
                     RetryDecision::Stop => {
+                        DEFAULT_OBSERVER.on_give_up(attempt + 1, &error);
                         return Err(error);
                     }
                 }
@@ -157,16 +231,17 @@ mod tests {
     use super::*;
     use crate::strategies::FixedDelay;
     use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[tokio::test]
     async fn test_retry_with_policy_success() {
         let policy = FixedDelay::new(Duration::from_millis(10), 3);
-        let mut attempts = 0;
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
 
         let result = retry_with_policy(
-            || async {
-                attempts += 1;
-                if attempts < 2 {
+            move || async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) + 1 < 2 {
                     Err(io::Error::new(io::ErrorKind::Other, "fail"))
                 } else {
                     Ok("success")
@@ -178,17 +253,18 @@ mod tests {
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "success");
-        assert_eq!(attempts, 2);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
     }
 
     #[tokio::test]
     async fn test_retry_with_policy_exhausted() {
         let policy = FixedDelay::new(Duration::from_millis(10), 2);
-        let mut attempts = 0;
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
 
-        let result = retry_with_policy(
-            || async {
-                attempts += 1;
+        let result: Result<(), io::Error> = retry_with_policy(
+            move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
                 Err(io::Error::new(io::ErrorKind::Other, "always fail"))
             },
             &policy,
@@ -196,7 +272,7 @@ mod tests {
         .await;
 
         assert!(result.is_err());
-        assert_eq!(attempts, 3); // Initial attempt + 2 retries
+        assert_eq!(attempts.load(Ordering::SeqCst), 3); // Initial attempt + 2 retries
     }
 
     struct TestRetryable {
@@ -0,0 +1,141 @@
+//! Idempotency keys for retried side-effectful operations.
+//!
+//! Retrying a request that already reached the server, such as a POST whose
+//! response was lost, can apply its effect twice. [`IdempotentRetry`] gives
+//! each logical operation one key, generated with `infra-id` on every call to
+//! [`execute`](IdempotentRetry::execute), and passes the same key to every
+//! attempt so the server can deduplicate them.
+
+use crate::policy::RetryPolicy;
+#[cfg(feature = "tokio")]
+use crate::report::{retry_with_report, RetryReport};
+#[cfg(feature = "tokio")]
+use infra_id::Id;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+
+/// Conventional HTTP header carrying an idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Retries operations, each under its own idempotency key.
+///
+/// # Examples
+///
+/// ```no_run
+/// use infra_retry::{ExponentialBackoff, IdempotentRetry, IDEMPOTENCY_KEY_HEADER};
+/// use std::io;
+///
+/// # async fn charge(key: String) -> Result<(), io::Error> { Ok(()) }
+/// # async fn example() {
+/// let retry = IdempotentRetry::new(ExponentialBackoff::default());
+///
+/// // Every attempt sends the same key, e.g. in an `Idempotency-Key` header.
+/// let (result, report) = retry.execute(|key| charge(key.to_string())).await;
+/// assert!(report.idempotency_key.is_some());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct IdempotentRetry<P> {
+    policy: P,
+}
+
+impl<P: RetryPolicy> IdempotentRetry<P> {
+    /// Creates a retry for operations with idempotency keys.
+    pub fn new(policy: P) -> Self {
+        Self { policy }
+    }
+
+    /// Returns the retry policy.
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Runs `operation` with a freshly generated key, retrying failures with
+    /// the policy.
+    ///
+    /// Every attempt receives the same key, and the returned report records
+    /// it in [`RetryReport::idempotency_key`]. Each call is a new logical
+    /// operation with its own key.
+    #[cfg(feature = "tokio")]
+    pub async fn execute<F, Fut, T, E>(&self, operation: F) -> (Result<T, E>, RetryReport)
+    where
+        F: FnMut(&str) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::error::Error + 'static,
+    {
+        self.execute_with_key(&Id::generate(), operation).await
+    }
+
+    /// Runs `operation` with an existing key, for example one received from
+    /// an upstream caller, retrying failures with the policy.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_with_key<F, Fut, T, E>(
+        &self,
+        key: &Id,
+        mut operation: F,
+    ) -> (Result<T, E>, RetryReport)
+    where
+        F: FnMut(&str) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::error::Error + 'static,
+    {
+        let key = key.as_str();
+        let (result, mut report) = retry_with_report(|| operation(key), &self.policy).await;
+        report.idempotency_key = Some(key.to_string());
+        (result, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_same_key_for_every_attempt() {
+        let retry = IdempotentRetry::new(FixedDelay::new(Duration::ZERO, 3));
+        let seen = Mutex::new(Vec::new());
+
+        let (result, report) = retry
+            .execute(|key| {
+                let mut seen = seen.lock().unwrap();
+                seen.push(key.to_string());
+                let attempt = seen.len();
+                async move {
+                    if attempt < 3 {
+                        Err(io::Error::other("connection reset"))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 3);
+        let seen = seen.into_inner().unwrap();
+        assert_eq!(seen.len(), 3);
+        let key = report.idempotency_key.unwrap();
+        assert!(seen.iter().all(|k| *k == key));
+    }
+
+    #[tokio::test]
+    async fn test_keys_per_operation() {
+        let retry = IdempotentRetry::new(FixedDelay::new(Duration::ZERO, 1));
+        let send = |key: &str| {
+            let key = key.to_string();
+            async move { Ok::<_, io::Error>(key) }
+        };
+
+        let (first, _) = retry.execute(send).await;
+        let (second, _) = retry.execute(send).await;
+        assert_ne!(first.unwrap(), second.unwrap());
+
+        let upstream = Id::from_trusted("order-42");
+        let (reused, report) = retry.execute_with_key(&upstream, send).await;
+        assert_eq!(reused.unwrap(), "order-42");
+        assert_eq!(report.idempotency_key.as_deref(), Some("order-42"));
+    }
+}
@@ -1,11 +1,33 @@
 //! Advanced retry policies for LLM-Dev-Ops infrastructure.
 //!
 //! This crate provides flexible retry mechanisms with various built-in strategies
-//! including exponential backoff, fixed delays, and jitter support.
+//! including exponential backoff, fixed delays, and jitter support. A shared
+//! [`RetryBudget`] can cap the fraction of calls that are retried, or the
+//! number of retries per time window, to prevent retry storms across many
+//! concurrent operations, and [`retry_with_deadline`] bounds each attempt and
+//! the whole operation in time. Wrapping a policy in
+//! [`Classified`] stops retrying permanent errors and honors retry-after
+//! delays reported by [`InfraError`](infra_errors::InfraError). Policies can be
+//! composed with the [`PolicyExt`] combinators, and [`CircuitBreakerPolicy`]
+//! stops retrying while a shared [`CircuitBreaker`] is open.
+//! [`ResilientExecutor`] combines a retry policy with a bulkhead and a
+//! circuit breaker.
 //!
 //! # Features
 //!
 //! - `std` (default): Enables standard library support.
+//! - `tokio` (default): Waits between attempts with tokio timers, and enables
+//!   [`retry_with_deadline`], [`retry_with_report`] and [`ResilientExecutor`].
+//! - `wasm`: Waits with browser timers via `gloo-timers` on `wasm32` targets.
+//!   Combine with `default-features = false` for browser-based tools.
+//! - `idempotency`: Provides [`IdempotentRetry`], which passes one `infra-id`
+//!   generated key to every attempt of an operation.
+//! - `otel`: Records retries and give-ups as span events via `infra-otel`, and
+//!   provides [`OtelObserver`] for retry counters.
+//! - `proptest`: Provides proptest strategies for policy configurations in
+//!   [`arbitrary`].
+//!
+//! Other runtimes can supply their own [`Sleeper`] to [`retry_with_sleeper`].
 //!
 //! # Examples
 //!
@@ -18,11 +40,11 @@
 //!     .with_max_attempts(5)
 //!     .with_initial_delay(std::time::Duration::from_millis(100));
 //!
-//! let mut count = 0;
+//! let count = std::sync::atomic::AtomicU32::new(0);
+//! let count = &count;
 //! let result = retry_with_policy(
-//!     || async {
-//!         count += 1;
-//!         if count < 3 {
+//!     move || async move {
+//!         if count.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1 < 3 {
 //!             Err(io::Error::new(io::ErrorKind::Other, "temporary"))
 //!         } else {
 //!             Ok("success")
@@ -39,11 +61,51 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+pub mod budget;
+pub mod circuit;
+pub mod classify;
+pub mod combinators;
+#[cfg(feature = "tokio")]
+pub mod deadline;
 pub mod executor;
+#[cfg(feature = "idempotency")]
+pub mod idempotent;
+pub mod observer;
 pub mod policy;
+pub mod report;
+#[cfg(feature = "tokio")]
+pub mod resilient;
+pub mod sleep;
 pub mod strategies;
 
 // Re-export key types for convenience
-pub use executor::{retry_retryable, retry_with_policy, Retryable};
+pub use budget::{BudgetedPolicy, RetryBudget};
+pub use circuit::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerPolicy, CircuitState};
+pub use classify::{
+    Classified, ClassifyError, ErrorClass, InfraErrorClassifier, NonIdempotentClassifier,
+};
+pub use combinators::{first_n_with, FirstN, MaxTotalDelay, OnlyIf, PolicyExt, Then};
+#[cfg(feature = "tokio")]
+pub use deadline::{retry_with_deadline, AttemptError, Deadline, DeadlineError};
+#[cfg(any(feature = "tokio", all(feature = "wasm", target_arch = "wasm32")))]
+pub use executor::{retry_retryable, retry_with_observer, retry_with_policy};
+pub use executor::{retry_with_sleeper, Retryable};
+#[cfg(feature = "idempotency")]
+pub use idempotent::{IdempotentRetry, IDEMPOTENCY_KEY_HEADER};
+#[cfg(feature = "otel")]
+pub use observer::OtelObserver;
+pub use observer::{NoopObserver, RetryObserver};
 pub use policy::{RetryDecision, RetryPolicy};
+#[cfg(feature = "tokio")]
+pub use report::retry_with_report;
+pub use report::{AttemptRecord, RetryReport};
+#[cfg(feature = "tokio")]
+pub use resilient::{ResilientError, ResilientExecutor};
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use sleep::GlooSleeper;
+#[cfg(feature = "tokio")]
+pub use sleep::TokioSleeper;
+pub use sleep::{SleepFuture, Sleeper};
 pub use strategies::{ExponentialBackoff, FixedDelay, WithJitter};
@@ -0,0 +1,188 @@
+//! Callbacks for observing retries.
+
+use std::error::Error;
+use std::time::Duration;
+
+/// Receives notifications as the executor retries an operation.
+///
+/// All methods default to doing nothing, so implementors only override the
+/// events they care about.
+pub trait RetryObserver: Send + Sync {
+    /// Called after a failed attempt, before waiting `delay` to retry.
+    ///
+    /// `attempt` is the number of the upcoming attempt (the first retry is 2).
+    fn on_retry(&self, _attempt: u32, _error: &(dyn Error + 'static), _delay: Duration) {}
+
+    /// Called when the executor stops retrying and returns `error`.
+    ///
+    /// `attempts` is the total number of attempts made.
+    fn on_give_up(&self, _attempts: u32, _error: &(dyn Error + 'static)) {}
+
+    /// Called when an attempt succeeds, with the total number of attempts.
+    fn on_success(&self, _attempts: u32) {}
+}
+
+/// Observer that ignores every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopObserver;
+
+impl RetryObserver for NoopObserver {}
+
+/// Observer emitting `infra-otel` span events, and optionally counters.
+///
+/// Retries are recorded with [`infra_otel::record_retry_attempt`] and give-ups
+/// with [`infra_otel::record_retry_exhausted`] on the current span. With
+/// [`with_metrics`](Self::with_metrics), `retry_attempts_total` and
+/// `retry_exhausted_total` counters labelled by operation are incremented too.
+#[cfg(feature = "otel")]
+#[derive(Clone, Default)]
+pub struct OtelObserver {
+    metrics: Option<RetryCounters>,
+}
+
+#[cfg(feature = "otel")]
+#[derive(Clone)]
+struct RetryCounters {
+    retries: std::sync::Arc<infra_otel::Counter>,
+    exhausted: std::sync::Arc<infra_otel::Counter>,
+}
+
+#[cfg(feature = "otel")]
+impl OtelObserver {
+    /// Creates an observer emitting span events only.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { metrics: None }
+    }
+
+    /// Also counts retries and give-ups for `operation` in `registry`.
+    #[must_use]
+    pub fn with_metrics(mut self, registry: &infra_otel::MetricsRegistry, operation: &str) -> Self {
+        let labels = [("operation", operation)];
+        self.metrics = Some(RetryCounters {
+            retries: registry.counter_with("retry_attempts_total", &labels),
+            exhausted: registry.counter_with("retry_exhausted_total", &labels),
+        });
+        self
+    }
+}
+
+#[cfg(feature = "otel")]
+impl RetryObserver for OtelObserver {
+    fn on_retry(&self, attempt: u32, error: &(dyn Error + 'static), delay: Duration) {
+        infra_otel::record_retry_attempt(attempt, delay, &error.to_string());
+        if let Some(metrics) = &self.metrics {
+            metrics.retries.inc();
+        }
+    }
+
+    fn on_give_up(&self, attempts: u32, error: &(dyn Error + 'static)) {
+        infra_otel::record_retry_exhausted(attempts, &error.to_string());
+        if let Some(metrics) = &self.metrics {
+            metrics.exhausted.inc();
+        }
+    }
+}
+
+/// Observer used by [`retry_with_policy`](crate::retry_with_policy) and
+/// [`retry_retryable`](crate::retry_retryable).
+#[cfg(feature = "otel")]
+pub(crate) static DEFAULT_OBSERVER: OtelObserver = OtelObserver::new();
+
+/// Observer used by [`retry_with_policy`](crate::retry_with_policy) and
+/// [`retry_retryable`](crate::retry_retryable).
+#[cfg(not(feature = "otel"))]
+pub(crate) static DEFAULT_OBSERVER: NoopObserver = NoopObserver;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::retry_with_observer;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RetryObserver for Recorder {
+        fn on_retry(&self, attempt: u32, error: &(dyn Error + 'static), delay: Duration) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("retry {attempt} {error} {delay:?}"));
+        }
+
+        fn on_give_up(&self, attempts: u32, error: &(dyn Error + 'static)) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("give_up {attempts} {error}"));
+        }
+
+        fn on_success(&self, attempts: u32) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("success {attempts}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_events() {
+        let policy = FixedDelay::new(Duration::ZERO, 2);
+        let recorder = Recorder::default();
+
+        let result: Result<(), io::Error> = retry_with_observer(
+            || async { Err(io::Error::other("down")) },
+            &policy,
+            &recorder,
+        )
+        .await;
+        assert!(result.is_err());
+
+        let result: Result<&str, io::Error> =
+            retry_with_observer(|| async { Ok("up") }, &policy, &recorder).await;
+        assert!(result.is_ok());
+
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec![
+                "retry 2 down 0ns",
+                "retry 3 down 0ns",
+                "give_up 3 down",
+                "success 1",
+            ]
+        );
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn test_otel_observer_counts() {
+        let registry = infra_otel::MetricsRegistry::new();
+        let observer = OtelObserver::new().with_metrics(&registry, "fetch");
+        let policy = FixedDelay::new(Duration::ZERO, 2);
+
+        let result: Result<(), io::Error> = retry_with_observer(
+            || async { Err(io::Error::other("down")) },
+            &policy,
+            &observer,
+        )
+        .await;
+        assert!(result.is_err());
+
+        let labels = [("operation", "fetch")];
+        assert_eq!(
+            registry.counter_with("retry_attempts_total", &labels).get(),
+            2
+        );
+        assert_eq!(
+            registry
+                .counter_with("retry_exhausted_total", &labels)
+                .get(),
+            1
+        );
+    }
+}
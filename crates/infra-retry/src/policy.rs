@@ -28,6 +28,23 @@ pub trait RetryPolicy: Send + Sync {
     /// A `RetryDecision` indicating whether to retry and with what delay.
     fn should_retry(&self, attempt: u32, error: &dyn std::error::Error) -> RetryDecision;
 
+    /// Determines whether a failed operation should be retried, given an
+    /// error that can be downcast to inspect its kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The current attempt number (0-indexed).
+    /// * `error` - The error that occurred during the last attempt.
+    ///
+    /// The executors call this method rather than
+    /// [`should_retry`](Self::should_retry), which it calls by default.
+    /// Policies inspecting errors, such as [`Classified`](crate::Classified),
+    /// override it, see [`ClassifyError`](crate::ClassifyError), and wrappers
+    /// forward it to the policy they wrap.
+    fn decide(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) -> RetryDecision {
+        self.should_retry(attempt, error)
+    }
+
     /// Returns the delay to wait before the next retry attempt.
     ///
     /// # Arguments
@@ -45,4 +62,28 @@ pub trait RetryPolicy: Send + Sync {
     ///
     /// The maximum number of attempts, where 0 means no retries.
     fn max_attempts(&self) -> u32;
+    /// Called by the executors when an attempt succeeds.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The attempt number that succeeded (0-indexed).
+    ///
+    /// The default implementation does nothing. Stateful policies such as
+    /// [`BudgetedPolicy`](crate::BudgetedPolicy) use it to record successes.
+    fn on_success(&self, _attempt: u32) {}
+
+    /// Called by the executors when an attempt fails, before deciding
+    /// whether to retry.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The attempt number that failed (0-indexed).
+    /// * `error` - The error that occurred.
+    ///
+    /// Unlike [`decide`](Self::decide), it is called for every
+    /// failed attempt, including the last one. The default implementation
+    /// does nothing. Stateful policies such as
+    /// [`CircuitBreakerPolicy`](crate::CircuitBreakerPolicy) use it to
+    /// record failures.
+    fn on_failure(&self, _attempt: u32, _error: &(dyn std::error::Error + 'static)) {}
 }
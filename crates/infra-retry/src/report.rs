@@ -0,0 +1,256 @@
+//! Attempt history for retried operations.
+
+#[cfg(feature = "tokio")]
+use crate::{
+    executor::run,
+    observer::{RetryObserver, DEFAULT_OBSERVER},
+    policy::RetryPolicy,
+    sleep::DEFAULT_SLEEPER,
+};
+use infra_errors::ErrorContext;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "tokio")]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
+
+/// Record of a single attempt made by [`retry_with_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptRecord {
+    /// Attempt number, starting at 1.
+    pub attempt: u32,
+    /// When the attempt started.
+    pub started_at: SystemTime,
+    /// How long the attempt took.
+    pub duration: Duration,
+    /// The attempt's error message, if it failed.
+    pub error: Option<String>,
+    /// Delay before the next attempt, if the operation was retried.
+    pub delay: Option<Duration>,
+}
+
+/// History of all attempts made for one operation.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RetryReport {
+    /// Attempts in the order they were made.
+    pub attempts: Vec<AttemptRecord>,
+    /// Time from the first attempt to the final result.
+    pub elapsed: Duration,
+    /// Idempotency key shared by all attempts, if any.
+    pub idempotency_key: Option<String>,
+}
+
+impl RetryReport {
+    /// Returns the number of attempts made.
+    #[must_use]
+    pub fn attempt_count(&self) -> u32 {
+        u32::try_from(self.attempts.len()).unwrap_or(u32::MAX)
+    }
+
+    /// Returns the number of retries, excluding the first attempt.
+    #[must_use]
+    pub fn retries(&self) -> u32 {
+        self.attempt_count().saturating_sub(1)
+    }
+
+    /// Returns the total time spent waiting between attempts.
+    #[must_use]
+    pub fn total_delay(&self) -> Duration {
+        self.attempts.iter().filter_map(|a| a.delay).sum()
+    }
+
+    /// Returns `true` if the last attempt succeeded.
+    #[must_use]
+    pub fn succeeded(&self) -> bool {
+        self.attempts.last().is_some_and(|a| a.error.is_none())
+    }
+
+    /// Returns the error messages of the failed attempts.
+    #[must_use]
+    pub fn errors(&self) -> Vec<&str> {
+        self.attempts
+            .iter()
+            .filter_map(|a| a.error.as_deref())
+            .collect()
+    }
+
+    /// Adds the attempt history to an error context as `retry.*` attributes.
+    #[must_use]
+    pub fn annotate(&self, context: ErrorContext) -> ErrorContext {
+        let mut context = context
+            .with_attribute("retry.attempts", self.attempt_count().to_string())
+            .with_attribute(
+                "retry.total_delay_ms",
+                self.total_delay().as_millis().to_string(),
+            )
+            .with_attribute("retry.elapsed_ms", self.elapsed.as_millis().to_string());
+        if let Some(key) = &self.idempotency_key {
+            context = context.with_attribute("retry.idempotency_key", key);
+        }
+        for record in &self.attempts {
+            if let Some(error) = &record.error {
+                context = context.with_attribute(format!("retry.error.{}", record.attempt), error);
+            }
+        }
+        context
+    }
+}
+
+/// Retries an async operation, returning its result with the attempt history.
+///
+/// Behaves like [`retry_with_policy`](crate::retry_with_policy), but never
+/// discards the errors of earlier attempts: each attempt's start time,
+/// duration, error and following delay are recorded in the [`RetryReport`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use infra_retry::{retry_with_report, ExponentialBackoff};
+/// use std::io;
+///
+/// # async fn example() {
+/// let (result, report) = retry_with_report(
+///     || async { Ok::<_, io::Error>("success") },
+///     &ExponentialBackoff::default(),
+/// )
+/// .await;
+///
+/// if result.is_err() {
+///     eprintln!("failed after {} attempts: {:?}", report.attempt_count(), report.errors());
+/// }
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn retry_with_report<F, Fut, T, E>(
+    mut operation: F,
+    policy: &dyn RetryPolicy,
+) -> (Result<T, E>, RetryReport)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::error::Error + 'static,
+{
+    let started = Instant::now();
+    let recorder = Recorder::default();
+    let attempts = &recorder;
+    let timed = || {
+        let started_at = SystemTime::now();
+        let attempt_started = Instant::now();
+        let attempt = operation();
+        async move {
+            let result = attempt.await;
+            let mut records = attempts.lock();
+            let number = u32::try_from(records.len()).unwrap_or(u32::MAX) + 1;
+            records.push(AttemptRecord {
+                attempt: number,
+                started_at,
+                duration: attempt_started.elapsed(),
+                error: None,
+                delay: None,
+            });
+            result
+        }
+    };
+
+    let result = run(timed, policy, &recorder, &DEFAULT_SLEEPER).await;
+    let report = RetryReport {
+        attempts: recorder
+            .attempts
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner),
+        elapsed: started.elapsed(),
+        idempotency_key: None,
+    };
+    (result, report)
+}
+
+/// Observer filling in the errors and delays of recorded attempts
+#[cfg(feature = "tokio")]
+#[derive(Default)]
+struct Recorder {
+    attempts: Mutex<Vec<AttemptRecord>>,
+}
+
+#[cfg(feature = "tokio")]
+impl Recorder {
+    fn lock(&self) -> MutexGuard<'_, Vec<AttemptRecord>> {
+        self.attempts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl RetryObserver for Recorder {
+    fn on_retry(&self, attempt: u32, error: &(dyn std::error::Error + 'static), delay: Duration) {
+        if let Some(record) = self.lock().last_mut() {
+            record.error = Some(error.to_string());
+            record.delay = Some(delay);
+        }
+        DEFAULT_OBSERVER.on_retry(attempt, error, delay);
+    }
+
+    fn on_give_up(&self, attempts: u32, error: &(dyn std::error::Error + 'static)) {
+        if let Some(record) = self.lock().last_mut() {
+            record.error = Some(error.to_string());
+        }
+        DEFAULT_OBSERVER.on_give_up(attempts, error);
+    }
+
+    fn on_success(&self, attempts: u32) {
+        DEFAULT_OBSERVER.on_success(attempts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_report_records_attempts() {
+        let policy = FixedDelay::new(Duration::from_millis(5), 5);
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+
+        let (result, report) = retry_with_report(
+            move || async move {
+                let n = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                if n < 3 {
+                    Err(io::Error::other(format!("failure {n}")))
+                } else {
+                    Ok("success")
+                }
+            },
+            &policy,
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), "success");
+        assert!(report.succeeded());
+        assert_eq!(report.attempt_count(), 3);
+        assert_eq!(report.retries(), 2);
+        assert_eq!(report.errors(), vec!["failure 1", "failure 2"]);
+        assert_eq!(report.total_delay(), Duration::from_millis(10));
+        assert!(report.attempts[0].started_at <= report.attempts[2].started_at);
+        assert_eq!(report.attempts[2].delay, None);
+        assert!(report.elapsed >= report.total_delay());
+    }
+
+    #[tokio::test]
+    async fn test_report_on_failure() {
+        let policy = FixedDelay::new(Duration::ZERO, 1);
+        let (result, report): (Result<(), io::Error>, _) =
+            retry_with_report(|| async { Err(io::Error::other("down")) }, &policy).await;
+
+        assert!(result.is_err());
+        assert!(!report.succeeded());
+        assert_eq!(report.attempt_count(), 2);
+
+        let context = report.annotate(ErrorContext::new());
+        assert_eq!(context.attributes["retry.attempts"], "2");
+        assert_eq!(context.attributes["retry.error.2"], "down");
+    }
+}
@@ -0,0 +1,414 @@
+//! Bulkhead, circuit breaker and retry combined behind one entry point.
+//!
+//! [`ResilientExecutor`] limits how many operations run concurrently, fails
+//! fast while a shared [`CircuitBreaker`] is open, and retries failures with a
+//! [`RetryPolicy`]. Clones share the same bulkhead and breaker, so one
+//! executor per downstream dependency can be handed to every caller.
+
+use crate::circuit::CircuitBreaker;
+use crate::classify::{ClassifyError, ErrorClass, InfraErrorClassifier};
+use crate::executor::run;
+use crate::observer::DEFAULT_OBSERVER;
+use crate::policy::{RetryDecision, RetryPolicy};
+use crate::sleep::DEFAULT_SLEEPER;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::time::timeout;
+
+/// Error returned by [`ResilientExecutor::execute`].
+#[derive(Debug, Error)]
+pub enum ResilientError<E> {
+    /// No concurrency slot became available within the queue timeout.
+    #[error("bulkhead full")]
+    BulkheadFull,
+    /// The circuit breaker rejected the call.
+    #[error("circuit '{name}' is open")]
+    CircuitOpen {
+        /// Name of the circuit breaker.
+        name: String,
+        /// Error of the last attempt, if the breaker opened while retrying.
+        last_error: Option<E>,
+    },
+    /// The operation failed and was not retried further.
+    #[error(transparent)]
+    Operation(E),
+}
+
+impl<E> ResilientError<E> {
+    /// Returns `true` if the call was rejected without running the operation
+    /// to completion.
+    pub fn is_rejected(&self) -> bool {
+        !matches!(self, Self::Operation(_))
+    }
+
+    /// Returns the operation's error, if it ran and failed, including the
+    /// last error before the circuit breaker stopped retries.
+    pub fn into_operation_error(self) -> Option<E> {
+        match self {
+            Self::Operation(error) => Some(error),
+            Self::CircuitOpen { last_error, .. } => last_error,
+            Self::BulkheadFull => None,
+        }
+    }
+}
+
+/// Executes operations through a bulkhead, a circuit breaker and a retry
+/// policy.
+///
+/// # Examples
+///
+/// ```no_run
+/// use infra_retry::{CircuitBreaker, CircuitBreakerConfig, ExponentialBackoff, ResilientExecutor};
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// # async fn example() {
+/// let executor = ResilientExecutor::new(ExponentialBackoff::default())
+///     .with_bulkhead(16)
+///     .with_queue_timeout(Duration::from_millis(500))
+///     .with_circuit_breaker(Arc::new(CircuitBreaker::new(
+///         "payments",
+///         CircuitBreakerConfig::default(),
+///     )));
+///
+/// let result = executor.execute(|| async { Ok::<_, io::Error>("charged") }).await;
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ResilientExecutor {
+    policy: Arc<dyn RetryPolicy>,
+    bulkhead: Option<Arc<Semaphore>>,
+    queue_timeout: Option<Duration>,
+    breaker: Option<Arc<CircuitBreaker>>,
+}
+
+impl std::fmt::Debug for ResilientExecutor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResilientExecutor")
+            .field("bulkhead", &self.bulkhead)
+            .field("queue_timeout", &self.queue_timeout)
+            .field("breaker", &self.breaker)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResilientExecutor {
+    /// Creates an executor retrying with `policy`, without concurrency limits
+    /// or circuit breaking.
+    pub fn new(policy: impl RetryPolicy + 'static) -> Self {
+        Self {
+            policy: Arc::new(policy),
+            bulkhead: None,
+            queue_timeout: None,
+            breaker: None,
+        }
+    }
+
+    /// Limits the number of operations running concurrently.
+    #[must_use]
+    pub fn with_bulkhead(mut self, max_concurrent: usize) -> Self {
+        self.bulkhead = Some(Arc::new(Semaphore::new(max_concurrent)));
+        self
+    }
+
+    /// Rejects calls that wait longer than `timeout` for a bulkhead slot.
+    ///
+    /// Without a queue timeout, calls wait until a slot is free.
+    #[must_use]
+    pub fn with_queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// Fails fast while `breaker` is open.
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Returns the circuit breaker, if any.
+    #[must_use]
+    pub fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.breaker.as_ref()
+    }
+
+    /// Returns the number of free bulkhead slots, if a bulkhead is set.
+    #[must_use]
+    pub fn available_slots(&self) -> Option<usize> {
+        self.bulkhead.as_ref().map(|b| b.available_permits())
+    }
+
+    /// Runs `operation`, retrying failures with the policy.
+    ///
+    /// A bulkhead slot is held for the whole retry sequence. Every attempt is
+    /// recorded on the circuit breaker, and retrying stops as soon as the
+    /// breaker opens. Errors the [`InfraErrorClassifier`] deems permanent
+    /// show the dependency is answering, and are recorded as successes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ResilientError::BulkheadFull`] or
+    /// [`ResilientError::CircuitOpen`] if the call is rejected or the breaker
+    /// opens while retrying, and [`ResilientError::Operation`] with the last
+    /// error once the policy stops retrying.
+    pub async fn execute<F, Fut, T, E>(&self, operation: F) -> Result<T, ResilientError<E>>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: std::error::Error + 'static,
+    {
+        let _permit = match &self.bulkhead {
+            Some(bulkhead) => {
+                let acquire = bulkhead.acquire();
+                let permit = match self.queue_timeout {
+                    Some(limit) => timeout(limit, acquire)
+                        .await
+                        .map_err(|_| ResilientError::BulkheadFull)?,
+                    None => acquire.await,
+                };
+                Some(permit.map_err(|_| ResilientError::BulkheadFull)?)
+            }
+            None => None,
+        };
+
+        let Some(breaker) = &self.breaker else {
+            return run(
+                operation,
+                &*self.policy,
+                &DEFAULT_OBSERVER,
+                &DEFAULT_SLEEPER,
+            )
+            .await
+            .map_err(ResilientError::Operation);
+        };
+        let open = |last_error| ResilientError::CircuitOpen {
+            name: breaker.name().to_string(),
+            last_error,
+        };
+        if !breaker.allow() {
+            return Err(open(None));
+        }
+
+        let gate = BreakerGate {
+            policy: &*self.policy,
+            breaker,
+            rejected: AtomicBool::new(false),
+        };
+        match run(operation, &gate, &DEFAULT_OBSERVER, &DEFAULT_SLEEPER).await {
+            Ok(value) => Ok(value),
+            Err(error) if gate.rejected.load(Ordering::Relaxed) => Err(open(Some(error))),
+            Err(error) => Err(ResilientError::Operation(error)),
+        }
+    }
+}
+
+/// Policy recording attempts on a circuit breaker and admitting retries
+/// through it
+struct BreakerGate<'a> {
+    policy: &'a dyn RetryPolicy,
+    breaker: &'a CircuitBreaker,
+    /// Whether the breaker stopped the retries
+    rejected: AtomicBool,
+}
+
+impl BreakerGate<'_> {
+    /// Admits a retry decided by the policy through the breaker.
+    fn admit(&self, decision: RetryDecision) -> RetryDecision {
+        match decision {
+            RetryDecision::Retry(_) if !self.breaker.allow() => {
+                self.rejected.store(true, Ordering::Relaxed);
+                RetryDecision::Stop
+            }
+            decision => decision,
+        }
+    }
+}
+
+impl RetryPolicy for BreakerGate<'_> {
+    fn should_retry(&self, attempt: u32, error: &dyn std::error::Error) -> RetryDecision {
+        self.admit(self.policy.should_retry(attempt, error))
+    }
+
+    fn decide(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) -> RetryDecision {
+        self.admit(self.policy.decide(attempt, error))
+    }
+
+    fn delay_for(&self, attempt: u32) -> Option<Duration> {
+        self.policy.delay_for(attempt)
+    }
+
+    fn max_attempts(&self) -> u32 {
+        self.policy.max_attempts()
+    }
+
+    fn on_success(&self, attempt: u32) {
+        self.breaker.record_success();
+        self.policy.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) {
+        if InfraErrorClassifier.classify(error) == ErrorClass::Permanent {
+            self.breaker.record_success();
+        } else {
+            self.breaker.record_failure();
+        }
+        self.policy.on_failure(attempt, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{CircuitBreakerConfig, CircuitState};
+    use crate::strategies::FixedDelay;
+    use infra_errors::InfraError;
+    use infra_sim::SimulatedClock;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    fn breaker(failure_threshold: u32, open_duration: Duration) -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_threshold,
+                success_threshold: 1,
+                open_duration,
+            },
+        ))
+    }
+
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let clock = Arc::new(SimulatedClock::new());
+        let breaker = CircuitBreaker::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                success_threshold: 1,
+                open_duration: Duration::from_secs(30),
+            },
+        )
+        .with_time_source(clock.clone());
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+
+        clock.advance(Duration::from_secs(29));
+        assert!(!breaker.allow());
+        clock.advance(Duration::from_secs(1));
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // One probe at a time
+        assert!(!breaker.allow());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_do_not_open_circuit() {
+        let breaker = breaker(2, Duration::from_secs(60));
+        let executor = ResilientExecutor::new(FixedDelay::new(Duration::ZERO, 3))
+            .with_circuit_breaker(breaker.clone());
+
+        for _ in 0..3 {
+            let result: Result<(), _> = executor
+                .execute(|| async { Err(InfraError::validation("bad request")) })
+                .await;
+            assert!(matches!(result, Err(ResilientError::Operation(_))));
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_execute_retries() {
+        let executor = ResilientExecutor::new(FixedDelay::new(Duration::ZERO, 3));
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+
+        let result = executor
+            .execute(move || async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(io::Error::other("down"))
+                } else {
+                    Ok("up")
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), "up");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_stops_retries() {
+        let breaker = breaker(2, Duration::from_secs(60));
+        let executor = ResilientExecutor::new(FixedDelay::new(Duration::ZERO, 10))
+            .with_circuit_breaker(breaker.clone());
+        let attempts = AtomicU32::new(0);
+        let attempts = &attempts;
+
+        let result: Result<(), _> = executor
+            .execute(move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(io::Error::other("down"))
+            })
+            .await;
+
+        match result {
+            Err(ResilientError::CircuitOpen { name, last_error }) => {
+                assert_eq!(name, "test");
+                assert_eq!(last_error.unwrap().to_string(), "down");
+            }
+            other => panic!("expected an open circuit, got {other:?}"),
+        }
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        let result: Result<(), ResilientError<io::Error>> =
+            executor.execute(|| async { Ok(()) }).await;
+        assert!(result.unwrap_err().is_rejected());
+    }
+
+    #[tokio::test]
+    async fn test_bulkhead_rejects_when_full() {
+        let executor = ResilientExecutor::new(FixedDelay::new(Duration::ZERO, 0))
+            .with_bulkhead(1)
+            .with_queue_timeout(Duration::from_millis(10));
+        assert_eq!(executor.available_slots(), Some(1));
+
+        let (release, wait) = tokio::sync::oneshot::channel::<()>();
+        let slow = executor.clone();
+        let blocker = tokio::spawn(async move {
+            let wait = Mutex::new(Some(wait));
+            slow.execute(|| {
+                let wait = wait.lock().unwrap().take();
+                async move {
+                    if let Some(wait) = wait {
+                        let _ = wait.await;
+                    }
+                    Ok::<_, io::Error>(())
+                }
+            })
+            .await
+        });
+        while executor.available_slots() != Some(0) {
+            tokio::task::yield_now().await;
+        }
+
+        let result = executor.execute(|| async { Ok::<_, io::Error>(()) }).await;
+        assert!(matches!(result, Err(ResilientError::BulkheadFull)));
+
+        release.send(()).unwrap();
+        assert!(blocker.await.unwrap().is_ok());
+        assert_eq!(executor.available_slots(), Some(1));
+    }
+}
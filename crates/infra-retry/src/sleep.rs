@@ -0,0 +1,111 @@
+//! Pluggable delays between retry attempts.
+//!
+//! The executors wait through a [`Sleeper`], so retry policies are not tied
+//! to one async runtime. [`TokioSleeper`] is used with the `tokio` feature
+//! (the default), and [`GlooSleeper`] with the `wasm` feature on `wasm32`
+//! targets, where it waits on browser timers.
+//!
+//! Browsers provide no `std::time::Instant`, so on `wasm32` only the
+//! executors without deadlines or reports are available, and
+//! [`RetryBudget::with_min_retries_per_second`](crate::RetryBudget::with_min_retries_per_second)
+//! must not be used.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Future returned by [`Sleeper::sleep`].
+#[cfg(not(target_arch = "wasm32"))]
+pub type SleepFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Future returned by [`Sleeper::sleep`].
+///
+/// Browser timers are not `Send`, so neither is this future on `wasm32`.
+#[cfg(target_arch = "wasm32")]
+pub type SleepFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Waits for a duration on some async runtime.
+pub trait Sleeper: Send + Sync {
+    /// Returns a future completing after `duration`.
+    fn sleep(&self, duration: Duration) -> SleepFuture;
+}
+
+/// Sleeper backed by [`tokio::time::sleep`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSleeper;
+
+#[cfg(feature = "tokio")]
+impl Sleeper for TokioSleeper {
+    fn sleep(&self, duration: Duration) -> SleepFuture {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Sleeper backed by browser timers through `gloo-timers`.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlooSleeper;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl Sleeper for GlooSleeper {
+    fn sleep(&self, duration: Duration) -> SleepFuture {
+        Box::pin(gloo_timers::future::sleep(duration))
+    }
+}
+
+/// Sleeper used by the executors that do not take one explicitly.
+#[cfg(feature = "tokio")]
+pub(crate) static DEFAULT_SLEEPER: TokioSleeper = TokioSleeper;
+
+/// Sleeper used by the executors that do not take one explicitly.
+#[cfg(all(not(feature = "tokio"), feature = "wasm", target_arch = "wasm32"))]
+pub(crate) static DEFAULT_SLEEPER: GlooSleeper = GlooSleeper;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::retry_with_sleeper;
+    use crate::strategies::FixedDelay;
+    use std::io;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingSleeper {
+        delays: Mutex<Vec<Duration>>,
+    }
+
+    impl Sleeper for RecordingSleeper {
+        fn sleep(&self, duration: Duration) -> SleepFuture {
+            self.delays.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_sleeper() {
+        let sleeper = RecordingSleeper::default();
+        let policy = FixedDelay::new(Duration::from_secs(60), 2);
+
+        let result: Result<(), io::Error> = retry_with_sleeper(
+            || async { Err(io::Error::other("down")) },
+            &policy,
+            &sleeper,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            *sleeper.delays.lock().unwrap(),
+            vec![Duration::from_secs(60); 2]
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_tokio_sleeper() {
+        let started = std::time::Instant::now();
+        TokioSleeper.sleep(Duration::from_millis(10)).await;
+        assert!(started.elapsed() >= Duration::from_millis(10));
+    }
+}
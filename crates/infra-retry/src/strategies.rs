@@ -181,6 +181,13 @@ impl<P: RetryPolicy> RetryPolicy for WithJitter<P> {
         }
     }
 
+    fn decide(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) -> RetryDecision {
+        match self.inner.decide(attempt, error) {
+            RetryDecision::Retry(delay) => RetryDecision::Retry(self.apply_jitter(delay)),
+            RetryDecision::Stop => RetryDecision::Stop,
+        }
+    }
+
     fn delay_for(&self, attempt: u32) -> Option<Duration> {
         self.inner.delay_for(attempt).map(|d| self.apply_jitter(d))
     }
@@ -188,6 +195,14 @@ impl<P: RetryPolicy> RetryPolicy for WithJitter<P> {
     fn max_attempts(&self) -> u32 {
         self.inner.max_attempts()
     }
+
+    fn on_success(&self, attempt: u32) {
+        self.inner.on_success(attempt);
+    }
+
+    fn on_failure(&self, attempt: u32, error: &(dyn std::error::Error + 'static)) {
+        self.inner.on_failure(attempt, error);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,64 @@
+//! Measures route lookup in trees of growing size, to check that it depends
+//! on the path length rather than the number of routes.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use infra_router::{Method, RouteTree};
+use std::hint::black_box;
+
+/// Build a tree of `services * 5` routes mixing literals, typed parameters
+/// and catch-alls
+fn tree(services: usize) -> RouteTree<usize> {
+    let mut tree = RouteTree::new();
+    for i in 0..services {
+        let base = format!("/api/v1/service{i}");
+        let patterns = [
+            (Method::Get, format!("{base}/items")),
+            (Method::Post, format!("{base}/items")),
+            (Method::Get, format!("{base}/items/:id<uuid>")),
+            (
+                Method::Get,
+                format!("{base}/items/:id/versions/:version<uint>"),
+            ),
+            (Method::Get, format!("{base}/files/*path")),
+        ];
+        for (method, pattern) in patterns {
+            let len = tree.len();
+            tree.insert(method, &pattern, len).unwrap();
+        }
+    }
+    tree
+}
+
+fn bench_find(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find");
+
+    for services in [10, 200, 2000] {
+        let tree = tree(services);
+        let last = services - 1;
+        let paths = [
+            ("static", format!("/api/v1/service{last}/items")),
+            (
+                "uuid",
+                format!("/api/v1/service{last}/items/0b7c5d7e-2f44-4b43-9d4c-5a3a8a1f6c21"),
+            ),
+            (
+                "params",
+                format!("/api/v1/service{last}/items/abc/versions/42"),
+            ),
+            (
+                "catch_all",
+                format!("/api/v1/service{last}/files/a/b/c.txt"),
+            ),
+        ];
+        for (name, path) in paths {
+            group.bench_with_input(BenchmarkId::new(name, tree.len()), &path, |b, path| {
+                b.iter(|| black_box(tree.find(Method::Get, black_box(path))));
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_find);
+criterion_main!(benches);
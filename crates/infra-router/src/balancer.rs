@@ -1,9 +1,13 @@
 //! Load balancing.
 
+use crate::outlier::{HealthTracker, OutlierDetection};
 use infra_errors::{InfraError, InfraResult};
+use infra_otel::MetricsRegistry;
+use infra_retry::{CircuitBreakerConfig, CircuitState};
 use rand::Rng;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 /// Backend server
@@ -48,10 +52,17 @@ pub enum Strategy {
 }
 
 /// Load balancer
+///
+/// Backends can be given circuit breakers, which stop sending requests to a
+/// failing backend until a trial request succeeds, and outlier detection,
+/// which leaves failing or slow backends out for a cooldown. Both act on
+/// outcomes reported with [`record_response`](Self::record_response) and
+/// [`record_failure`](Self::record_failure).
 pub struct LoadBalancer {
     backends: Arc<RwLock<Vec<Backend>>>,
     strategy: Strategy,
     counter: AtomicUsize,
+    health: HealthTracker,
 }
 
 impl LoadBalancer {
@@ -61,6 +72,7 @@ impl LoadBalancer {
             backends: Arc::new(RwLock::new(Vec::new())),
             strategy,
             counter: AtomicUsize::new(0),
+            health: HealthTracker::default(),
         }
     }
 
@@ -74,16 +86,35 @@ impl LoadBalancer {
         Self::new(Strategy::Random)
     }
 
+    /// Give each backend a circuit breaker
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.health.breaker = Some(config);
+        self
+    }
+
+    /// Eject failing or slow backends
+    pub fn outlier_detection(mut self, detection: OutlierDetection) -> Self {
+        self.health.detection = Some(detection);
+        self
+    }
+
+    /// Export per-backend circuit states and ejections to `registry`
+    pub fn with_metrics(mut self, registry: Arc<MetricsRegistry>) -> Self {
+        self.health.metrics = Some(registry);
+        self
+    }
+
     /// Add a backend
     pub async fn add_backend(&self, backend: Backend) {
         let mut backends = self.backends.write().await;
         backends.push(backend);
     }
 
-    /// Remove a backend by URL
+    /// Remove a backend by URL, forgetting its breaker and ejection
     pub async fn remove_backend(&self, url: &str) {
         let mut backends = self.backends.write().await;
         backends.retain(|b| b.url != url);
+        self.health.forget(url);
     }
 
     /// Mark a backend as unhealthy
@@ -103,53 +134,99 @@ impl LoadBalancer {
     }
 
     /// Get the next backend
+    ///
+    /// Skips unhealthy and ejected backends, and backends whose circuit is
+    /// open.
     pub async fn next(&self) -> InfraResult<Backend> {
         let backends = self.backends.read().await;
-        let healthy: Vec<_> = backends.iter().filter(|b| b.healthy).collect();
+        let healthy: Vec<_> = backends
+            .iter()
+            .filter(|b| b.healthy && self.health.available(&b.url))
+            .collect();
 
         if healthy.is_empty() {
-            return Err(InfraError::External {
-                service: "load_balancer".to_string(),
-                operation: "next".to_string(),
-                message: "No healthy backends available".to_string(),
-                retry_after: None,
-                context: None,
-            });
+            return Err(Self::unavailable("No healthy backends available"));
         }
 
+        // Fall through to the following backends while circuits are open
+        let start = self.pick(&healthy);
+        (0..healthy.len())
+            .map(|offset| healthy[(start + offset) % healthy.len()])
+            .find(|backend| self.health.admit(&backend.url))
+            .cloned()
+            .ok_or_else(|| Self::unavailable("All backend circuits are open"))
+    }
+
+    /// Pick the index of a backend according to the strategy
+    fn pick(&self, healthy: &[&Backend]) -> usize {
         match self.strategy {
-            Strategy::RoundRobin => {
-                let idx = self.counter.fetch_add(1, Ordering::Relaxed) % healthy.len();
-                Ok(healthy[idx].clone())
-            }
-            Strategy::Random => {
-                let idx = rand::thread_rng().gen_range(0..healthy.len());
-                Ok(healthy[idx].clone())
-            }
+            Strategy::RoundRobin => self.counter.fetch_add(1, Ordering::Relaxed) % healthy.len(),
+            Strategy::Random => rand::thread_rng().gen_range(0..healthy.len()),
             Strategy::Weighted => {
                 let total_weight: u32 = healthy.iter().map(|b| b.weight).sum();
                 if total_weight == 0 {
-                    return Ok(healthy[0].clone());
+                    return 0;
                 }
 
                 let mut rand_weight = rand::thread_rng().gen_range(0..total_weight);
-                for backend in &healthy {
+                for (idx, backend) in healthy.iter().enumerate() {
                     if rand_weight < backend.weight {
-                        return Ok((*backend).clone());
+                        return idx;
                     }
                     rand_weight -= backend.weight;
                 }
 
-                Ok(healthy[0].clone())
+                0
             }
             Strategy::LeastConnections => {
                 // Simplified: just use round-robin for now
-                let idx = self.counter.fetch_add(1, Ordering::Relaxed) % healthy.len();
-                Ok(healthy[idx].clone())
+                self.counter.fetch_add(1, Ordering::Relaxed) % healthy.len()
             }
         }
     }
 
+    fn unavailable(message: &str) -> InfraError {
+        InfraError::External {
+            service: "load_balancer".to_string(),
+            operation: "next".to_string(),
+            message: message.to_string(),
+            retry_after: None,
+            context: None,
+            source: None,
+        }
+    }
+
+    /// Record a response from a backend, failed if its status is 5xx
+    ///
+    /// Outcomes of backends no longer in the balancer are ignored.
+    pub async fn record_response(&self, url: &str, status: u16, latency: Duration) {
+        self.record(url, status >= 500, Some(latency)).await;
+    }
+
+    /// Record a request to a backend that got no response
+    ///
+    /// Outcomes of backends no longer in the balancer are ignored.
+    pub async fn record_failure(&self, url: &str) {
+        self.record(url, true, None).await;
+    }
+
+    async fn record(&self, url: &str, failed: bool, latency: Option<Duration>) {
+        let backends = self.backends.read().await;
+        if backends.iter().any(|b| b.url == url) {
+            self.health.record(url, failed, latency, backends.len());
+        }
+    }
+
+    /// Check if a backend is ejected by outlier detection
+    pub fn is_ejected(&self, url: &str) -> bool {
+        self.health.is_ejected(url)
+    }
+
+    /// Get the state of a backend's circuit, if backends have breakers
+    pub fn circuit_state(&self, url: &str) -> Option<CircuitState> {
+        self.health.circuit_state(url)
+    }
+
     /// Get all backends
     pub async fn backends(&self) -> Vec<Backend> {
         self.backends.read().await.clone()
@@ -207,4 +284,132 @@ mod tests {
         let result = balancer.next().await;
         assert!(result.is_err());
     }
+
+    async fn balancer(balancer: LoadBalancer, count: usize) -> LoadBalancer {
+        for i in 1..=count {
+            balancer
+                .add_backend(Backend::new(format!("http://server{i}")))
+                .await;
+        }
+        balancer
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_per_backend() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            success_threshold: 1,
+            open_duration: Duration::from_millis(50),
+        };
+        let lb = LoadBalancer::round_robin()
+            .circuit_breaker(config)
+            .with_metrics(registry.clone());
+        let lb = balancer(lb, 2).await;
+
+        lb.record_response("http://server1", 503, Duration::ZERO)
+            .await;
+        lb.record_failure("http://server1").await;
+        assert_eq!(lb.circuit_state("http://server1"), Some(CircuitState::Open));
+        assert_eq!(
+            lb.circuit_state("http://server2"),
+            Some(CircuitState::Closed)
+        );
+        let gauge = registry.gauge_with(
+            "gateway_backend_circuit_state",
+            &[("backend", "http://server1")],
+        );
+        assert_eq!(gauge.get(), 2);
+
+        for _ in 0..4 {
+            assert_eq!(lb.next().await.unwrap().url, "http://server2");
+        }
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let urls = [lb.next().await.unwrap().url, lb.next().await.unwrap().url];
+        assert!(urls.contains(&"http://server1".to_string()));
+        assert_eq!(
+            lb.circuit_state("http://server1"),
+            Some(CircuitState::HalfOpen)
+        );
+        // Only one probe is in flight at a time
+        for _ in 0..4 {
+            assert_eq!(lb.next().await.unwrap().url, "http://server2");
+        }
+        lb.record_response("http://server1", 200, Duration::ZERO)
+            .await;
+        assert_eq!(
+            lb.circuit_state("http://server1"),
+            Some(CircuitState::Closed)
+        );
+        assert_eq!(gauge.get(), 0);
+
+        // Removed backends are forgotten, and their outcomes ignored
+        lb.record_failure("http://server2").await;
+        lb.record_failure("http://server2").await;
+        assert_eq!(lb.circuit_state("http://server2"), Some(CircuitState::Open));
+        lb.remove_backend("http://server2").await;
+        lb.record_failure("http://server2").await;
+        lb.record_failure("http://server2").await;
+        assert_eq!(
+            lb.circuit_state("http://server2"),
+            Some(CircuitState::Closed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_outlier_ejection() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let detection = OutlierDetection::new()
+            .consecutive_errors(3)
+            .max_latency(Duration::from_millis(500), 2)
+            .ejection_duration(Duration::from_millis(50))
+            .max_ejection_percent(50);
+        let lb = LoadBalancer::round_robin()
+            .outlier_detection(detection)
+            .with_metrics(registry.clone());
+        let lb = balancer(lb, 4).await;
+
+        lb.record_response("http://server1", 502, Duration::ZERO)
+            .await;
+        lb.record_response("http://server1", 200, Duration::ZERO)
+            .await;
+        lb.record_response("http://server1", 502, Duration::ZERO)
+            .await;
+        lb.record_response("http://server1", 502, Duration::ZERO)
+            .await;
+        assert!(!lb.is_ejected("http://server1"));
+        lb.record_failure("http://server1").await;
+        assert!(lb.is_ejected("http://server1"));
+
+        lb.record_response("http://server2", 200, Duration::from_secs(1))
+            .await;
+        lb.record_response("http://server2", 200, Duration::from_secs(1))
+            .await;
+        assert!(lb.is_ejected("http://server2"));
+
+        // Ejecting a third of four backends would exceed 50%
+        for _ in 0..3 {
+            lb.record_failure("http://server3").await;
+        }
+        assert!(!lb.is_ejected("http://server3"));
+
+        for _ in 0..6 {
+            let url = lb.next().await.unwrap().url;
+            assert!(url == "http://server3" || url == "http://server4");
+        }
+        let ejections = registry.counter_with(
+            "gateway_backend_ejections_total",
+            &[("backend", "http://server2"), ("reason", "latency")],
+        );
+        assert_eq!(ejections.get(), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let mut urls: Vec<String> = Vec::new();
+        for _ in 0..4 {
+            urls.push(lb.next().await.unwrap().url);
+        }
+        assert!(urls.contains(&"http://server1".to_string()));
+        assert!(!lb.is_ejected("http://server1"));
+    }
 }
@@ -2,12 +2,30 @@
 
 use crate::balancer::{Backend, LoadBalancer, Strategy};
 use crate::handler::{Handler, HandlerResult, RequestContext};
+use crate::matcher::RouteTree;
+use crate::middleware::Middleware;
+use crate::policy::{RoutePolicies, Shadow};
 use crate::route::{Method, Route, RouteBuilder};
+use crate::split::{TrafficSplit, SPLIT_BACKEND_URL_HEADER, SPLIT_GROUP_HEADER};
+use crate::stream::{BodyStream, StreamProxy};
 use async_trait::async_trait;
 use infra_errors::{InfraError, InfraResult};
+use infra_retry::{retry_with_policy, PolicyExt, IDEMPOTENCY_KEY_HEADER};
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{RwLock, Semaphore};
+
+/// Maximum number of shadow requests in flight per gateway
+const MAX_SHADOW_REQUESTS: usize = 64;
+
+/// Request headers carrying credentials, not forwarded to shadow routes
+const CREDENTIAL_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+];
 
 /// Gateway configuration
 #[derive(Debug, Clone)]
@@ -37,8 +55,13 @@ impl Default for GatewayConfig {
 pub struct Gateway {
     config: GatewayConfig,
     routes: Vec<Route>,
-    middleware: Vec<Arc<dyn Handler>>,
+    /// Indices of the routes with a handler
+    index: RouteTree<usize>,
+    middleware: Vec<Arc<dyn Middleware>>,
     backends: HashMap<String, Arc<LoadBalancer>>,
+    splits: HashMap<String, Arc<TrafficSplit>>,
+    streams: StreamProxy,
+    shadows: Arc<Semaphore>,
 }
 
 impl Gateway {
@@ -47,19 +70,40 @@ impl Gateway {
         Self {
             config,
             routes: Vec::new(),
+            index: RouteTree::new(),
             middleware: Vec::new(),
             backends: HashMap::new(),
+            splits: HashMap::new(),
+            streams: StreamProxy::new(),
+            shadows: Arc::new(Semaphore::new(MAX_SHADOW_REQUESTS)),
         }
     }
 
     /// Add a route
-    pub fn add_route(&mut self, route: Route) {
+    ///
+    /// Routes without a handler are kept for shadowing but never matched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the gateway unchanged, if the route's path
+    /// is invalid or conflicts with an earlier route for the same method.
+    pub fn add_route(&mut self, route: Route) -> InfraResult<()> {
+        if route.handler().is_some() {
+            self.index
+                .insert(route.method(), route.path(), self.routes.len())?;
+        }
         self.routes.push(route);
+        Ok(())
     }
 
     /// Add middleware
-    pub fn add_middleware<H: Handler + 'static>(&mut self, handler: H) {
-        self.middleware.push(Arc::new(handler));
+    pub fn add_middleware<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(Arc::new(middleware));
+    }
+
+    /// Set the proxy streamed responses are sent through
+    pub fn set_stream_proxy(&mut self, proxy: StreamProxy) {
+        self.streams = proxy;
     }
 
     /// Add a backend
@@ -68,26 +112,236 @@ impl Gateway {
     }
 
     /// Route a request
-    pub async fn route(&self, method: Method, path: &str, ctx: RequestContext) -> InfraResult<HandlerResult> {
+    ///
+    /// Gateway middleware runs for every request, and route middleware for
+    /// requests matching the route. Requests matching a route's path but not
+    /// its method get a 405. Streamed responses are sent through the
+    /// gateway's [`StreamProxy`].
+    ///
+    /// Once middleware has processed the request, errors from later
+    /// middleware or the handler become problem details responses, as from
+    /// [`respond`](Self::respond), so that the middleware still sees a
+    /// response. Otherwise errors are returned.
+    pub async fn route(
+        &self,
+        method: Method,
+        path: &str,
+        mut ctx: RequestContext,
+    ) -> InfraResult<HandlerResult> {
         // Find matching route
-        for route in &self.routes {
-            if let Some(params) = route.matches(method, path) {
-                let mut route_ctx = ctx.clone();
-                route_ctx.params = params;
-
-                // Execute middleware
-                for _mw in &self.middleware {
-                    // In a real implementation, middleware could modify or short-circuit
+        let matched = self.index.find(method, path).and_then(|found| {
+            let route = &self.routes[*found.value];
+            route
+                .handler()
+                .map(|handler| (route, handler, found.params))
+        });
+
+        let mut middleware: Vec<&Arc<dyn Middleware>> = self.middleware.iter().collect();
+        if let Some((route, _, params)) = &matched {
+            middleware.extend(route.middleware());
+            ctx.params = params.clone();
+        }
+
+        // Execute middleware, stopping at the first that responds
+        let mut ran = 0;
+        let mut response = None;
+        for mw in &middleware {
+            match mw.on_request(&mut ctx).await {
+                Ok(Some(early)) => response = Some(Ok(early)),
+                Ok(None) => {
+                    ran += 1;
+                    continue;
                 }
+                Err(e) => response = Some(Err(e)),
+            }
+            break;
+        }
+
+        // Execute handler
+        let result = match (response, &matched) {
+            (Some(result), _) => result,
+            (None, Some((route, handler, _))) => self.execute(route, handler, method, &ctx).await,
+            (None, None) => Ok(self.unmatched(path)),
+        };
+        let mut response = match result {
+            Ok(response) => response,
+            Err(e) if ran > 0 => Self::failed(path, &e),
+            Err(e) => return Err(e),
+        };
+
+        for mw in middleware[..ran].iter().rev() {
+            mw.on_response(&ctx, &mut response).await?;
+        }
+        if let Some(upstream) = response.stream.as_ref().and_then(BodyStream::take) {
+            let (body, _stats) = self.streams.stream(upstream);
+            response.stream = Some(body);
+        }
+        Ok(response)
+    }
+
+    /// Route a request, responding to errors with problem details
+    ///
+    /// Unlike [`route`](Self::route), errors from middleware or handlers are
+    /// not returned but turned into responses, with the status from
+    /// [`InfraError::to_http_status`] and the request path as the problem
+    /// instance.
+    pub async fn respond(&self, method: Method, path: &str, ctx: RequestContext) -> HandlerResult {
+        match self.route(method, path, ctx).await {
+            Ok(response) => response,
+            Err(e) => Self::failed(path, &e),
+        }
+    }
+
+    /// Respond to a failed request with problem details
+    fn failed(path: &str, error: &InfraError) -> HandlerResult {
+        tracing::debug!(path, error = error.as_tracing_value(), "Request failed");
+        HandlerResult::from_problem(&error.to_problem_details().with_instance(path))
+    }
 
-                // Execute handler
-                if let Some(handler) = route.handler() {
-                    return handler.handle(route_ctx).await;
+    /// Run a route's handler under its timeout, retry and shadow policies
+    ///
+    /// Retries follow each failure's [`RetryAdvice`](infra_errors::RetryAdvice):
+    /// requests that aren't idempotent, by method or idempotency key, are
+    /// only retried after failures that certainly had no effect.
+    async fn execute(
+        &self,
+        route: &Route,
+        handler: &Arc<dyn Handler>,
+        method: Method,
+        ctx: &RequestContext,
+    ) -> InfraResult<HandlerResult> {
+        let policy = route.policy();
+        let timeout = Duration::from_millis(policy.timeout_ms.unwrap_or(self.config.timeout_ms));
+        if let Some(shadow) = &policy.shadow {
+            self.mirror(shadow, ctx, timeout);
+        }
+
+        let split = route.split().map(|name| {
+            self.split(name).ok_or_else(|| {
+                InfraError::config(format!(
+                    "Route {} has no traffic split {name}",
+                    route.path()
+                ))
+            })
+        });
+        let attempt = || async {
+            let mut ctx = ctx.clone();
+            let target = match &split {
+                Some(split) => {
+                    let split = split.as_ref().map_err(Clone::clone)?;
+                    let target = split.next().await?;
+                    ctx.headers
+                        .insert(SPLIT_GROUP_HEADER.to_string(), target.group.clone());
+                    ctx.headers
+                        .insert(SPLIT_BACKEND_URL_HEADER.to_string(), target.backend.url);
+                    Some((split, target.group))
                 }
+                None => None,
+            };
+            let result = match tokio::time::timeout(timeout, handler.handle(ctx)).await {
+                Ok(result) => result,
+                Err(_) => Ok(HandlerResult::error(504, "Gateway Timeout")),
+            };
+            if let Some((split, group)) = target {
+                let success = result.as_ref().is_ok_and(|r| r.status < 500);
+                split.record(&group, success);
             }
+            result
+        };
+        let (Some(retry), Some(budget)) = (&policy.retry, route.budget()) else {
+            return attempt().await;
+        };
+
+        // Responses with a status to retry fail the attempt, and are returned
+        // if it is the last
+        let failed = Mutex::new(None);
+        let idempotent = method.is_idempotent()
+            || ctx
+                .headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case(IDEMPOTENCY_KEY_HEADER));
+        let retry_policy = retry
+            .backoff()
+            .only_if(move |error| {
+                error
+                    .downcast_ref::<InfraError>()
+                    .is_some_and(|e| e.retry_advice().allows_retry(idempotent))
+            })
+            .with_budget(budget);
+        let result = retry_with_policy(
+            || async {
+                *failed.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                let response = attempt().await?;
+                if !retry.retry_on.contains(&response.status) {
+                    return Ok(response);
+                }
+                let status = response.status;
+                *failed.lock().unwrap_or_else(|e| e.into_inner()) = Some(response);
+                Err(InfraError::http_with_status(
+                    status,
+                    "Retryable response status",
+                ))
+            },
+            &retry_policy,
+        )
+        .await;
+
+        match (
+            result,
+            failed.into_inner().unwrap_or_else(|e| e.into_inner()),
+        ) {
+            (Err(_), Some(response)) => Ok(response),
+            (result, _) => result,
         }
+    }
 
-        Ok(HandlerResult::not_found())
+    /// Respond to a request matching no route
+    fn unmatched(&self, path: &str) -> HandlerResult {
+        let allowed = self.index.allowed_methods(path);
+        if allowed.is_empty() {
+            return HandlerResult::not_found();
+        }
+        let allow: Vec<&str> = allowed.iter().map(Method::as_str).collect();
+        HandlerResult::error(405, "Method not allowed").with_header("allow", allow.join(", "))
+    }
+
+    /// Mirror a request to a shadow route in the background
+    ///
+    /// Credentials are stripped from the mirrored request, and requests are
+    /// not mirrored while [`MAX_SHADOW_REQUESTS`] are still in flight.
+    fn mirror(&self, shadow: &Shadow, ctx: &RequestContext, timeout: Duration) {
+        if rand::random::<f64>() * 100.0 >= shadow.percent {
+            return;
+        }
+        let target = self
+            .routes
+            .iter()
+            .find(|route| route.name() == Some(shadow.route.as_str()))
+            .and_then(Route::handler);
+        let Some(handler) = target.cloned() else {
+            tracing::warn!(route = %shadow.route, "Shadow route not found");
+            return;
+        };
+
+        let Ok(permit) = self.shadows.clone().try_acquire_owned() else {
+            tracing::debug!(route = %shadow.route, "Too many shadow requests in flight");
+            return;
+        };
+
+        let mut ctx = ctx.clone().with_header("x-shadow", "true");
+        ctx.headers.retain(|name, _| {
+            !CREDENTIAL_HEADERS
+                .iter()
+                .any(|credential| name.eq_ignore_ascii_case(credential))
+        });
+        tokio::spawn(async move {
+            let _permit = permit;
+            match tokio::time::timeout(timeout, handler.handle(ctx)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::debug!(error = %e, "Shadow request failed"),
+                Err(_) => tracing::debug!("Shadow request timed out"),
+            }
+        });
     }
 
     /// Get a backend by name
@@ -95,6 +349,17 @@ impl Gateway {
         self.backends.get(name).cloned()
     }
 
+    /// Add a traffic split, named by its name
+    pub fn add_split(&mut self, split: TrafficSplit) {
+        self.splits
+            .insert(split.name().to_string(), Arc::new(split));
+    }
+
+    /// Get a traffic split by name
+    pub fn split(&self, name: &str) -> Option<Arc<TrafficSplit>> {
+        self.splits.get(name).cloned()
+    }
+
     /// Get config
     pub fn config(&self) -> &GatewayConfig {
         &self.config
@@ -105,8 +370,11 @@ impl Gateway {
 pub struct GatewayBuilder {
     config: GatewayConfig,
     routes: Vec<Route>,
-    middleware: Vec<Arc<dyn Handler>>,
+    middleware: Vec<Arc<dyn Middleware>>,
     backends: HashMap<String, LoadBalancer>,
+    splits: Vec<TrafficSplit>,
+    policies: RoutePolicies,
+    streams: Option<StreamProxy>,
 }
 
 impl GatewayBuilder {
@@ -117,6 +385,9 @@ impl GatewayBuilder {
             routes: Vec::new(),
             middleware: Vec::new(),
             backends: HashMap::new(),
+            splits: Vec::new(),
+            policies: RoutePolicies::new(),
+            streams: None,
         }
     }
 
@@ -150,9 +421,9 @@ impl GatewayBuilder {
         self
     }
 
-    /// Add middleware
-    pub fn middleware<H: Handler + 'static>(mut self, handler: H) -> Self {
-        self.middleware.push(Arc::new(handler));
+    /// Add middleware, run in the order added
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
         self
     }
 
@@ -162,17 +433,61 @@ impl GatewayBuilder {
         self
     }
 
+    /// Add a traffic split
+    pub fn split(mut self, split: TrafficSplit) -> Self {
+        self.splits.push(split);
+        self
+    }
+
+    /// Send streamed responses through a proxy, instead of one buffering 32
+    /// messages with a 60 second idle timeout
+    pub fn stream_proxy(mut self, proxy: StreamProxy) -> Self {
+        self.streams = Some(proxy);
+        self
+    }
+
+    /// Apply traffic policies to routes by name, such as policies loaded
+    /// from configuration
+    pub fn route_policies(mut self, policies: RoutePolicies) -> Self {
+        self.policies.extend(policies);
+        self
+    }
+
     /// Build the gateway
-    pub fn build(self) -> Gateway {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a route's path is invalid or conflicts with an
+    /// earlier route for the same method.
+    pub fn build(self) -> InfraResult<Gateway> {
         let mut gateway = Gateway::new(self.config);
-        gateway.routes = self.routes;
+        let mut routes = self.routes;
         gateway.middleware = self.middleware;
+        if let Some(proxy) = self.streams {
+            gateway.streams = proxy;
+        }
+
+        for (name, policy) in self.policies {
+            match routes
+                .iter_mut()
+                .find(|route| route.name() == Some(name.as_str()))
+            {
+                Some(route) => route.set_policy(policy),
+                None => tracing::warn!(route = %name, "Policy for unknown route"),
+            }
+        }
+        for route in routes {
+            gateway.add_route(route)?;
+        }
 
         for (name, balancer) in self.backends {
             gateway.backends.insert(name, Arc::new(balancer));
         }
+        for split in self.splits {
+            gateway.add_split(split);
+        }
 
-        gateway
+        Ok(gateway)
     }
 }
 
@@ -185,6 +500,8 @@ impl Default for GatewayBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::policy::{RoutePolicy, RouteRetry};
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     struct EchoHandler;
 
@@ -205,7 +522,8 @@ mod tests {
                     .handler(EchoHandler)
                     .build(),
             )
-            .build();
+            .build()
+            .unwrap();
 
         let ctx = RequestContext::new("/api/echo");
         let result = gateway.route(Method::Get, "/api/echo", ctx).await.unwrap();
@@ -213,13 +531,374 @@ mod tests {
         assert_eq!(result.status, 200);
     }
 
+    #[test]
+    fn test_gateway_rejects_conflicting_routes() {
+        let result = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/api/:id")
+                    .get()
+                    .handler(EchoHandler)
+                    .build(),
+            )
+            .route(
+                RouteBuilder::new("/api/:name")
+                    .get()
+                    .handler(EchoHandler)
+                    .build(),
+            )
+            .build();
+        assert!(result.is_err());
+
+        let result = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/api/echo")
+                    .get()
+                    .handler(EchoHandler)
+                    .build(),
+            )
+            .route(
+                RouteBuilder::new("/api/echo/")
+                    .get()
+                    .handler(EchoHandler)
+                    .build(),
+            )
+            .build();
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_gateway_not_found() {
-        let gateway = GatewayBuilder::new().build();
+        let gateway = GatewayBuilder::new().build().unwrap();
 
         let ctx = RequestContext::new("/unknown");
         let result = gateway.route(Method::Get, "/unknown", ctx).await.unwrap();
 
         assert_eq!(result.status, 404);
     }
+
+    #[tokio::test]
+    async fn test_gateway_respond() {
+        struct FailingHandler;
+
+        #[async_trait]
+        impl Handler for FailingHandler {
+            async fn handle(&self, _ctx: RequestContext) -> InfraResult<HandlerResult> {
+                Err(InfraError::validation("Missing model"))
+            }
+        }
+
+        let gateway = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/v1/chat")
+                    .post()
+                    .handler(FailingHandler)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/v1/chat");
+        let result = gateway.respond(Method::Post, "/v1/chat", ctx).await;
+        assert_eq!(result.status, 400);
+        let problem: infra_errors::ProblemDetails = serde_json::from_slice(&result.body).unwrap();
+        assert_eq!(problem.instance.as_deref(), Some("/v1/chat"));
+        assert_eq!(problem.code.unwrap().to_string(), "INFRA-VALIDATION-0001");
+    }
+
+    #[tokio::test]
+    async fn test_gateway_method_not_allowed() {
+        let gateway = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/api/items/:id")
+                    .get()
+                    .handler(EchoHandler)
+                    .build(),
+            )
+            .route(
+                RouteBuilder::new("/api/items/new")
+                    .post()
+                    .handler(EchoHandler)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/api/items/7");
+        let result = gateway
+            .route(Method::Put, "/api/items/7", ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.status, 405);
+        assert_eq!(result.headers.get("allow").map(String::as_str), Some("GET"));
+
+        let ctx = RequestContext::new("/api/items/new");
+        let result = gateway
+            .route(Method::Get, "/api/items/new", ctx)
+            .await
+            .unwrap();
+        assert_eq!(result.status, 200);
+    }
+
+    /// Fails with 503 until called `failures` times, sleeping `delay_ms` first
+    struct Flaky {
+        calls: Arc<AtomicU32>,
+        failures: u32,
+        delay_ms: u64,
+    }
+
+    #[async_trait]
+    impl Handler for Flaky {
+        async fn handle(&self, _ctx: RequestContext) -> InfraResult<HandlerResult> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            if call < self.failures {
+                Ok(HandlerResult::error(503, "Unavailable"))
+            } else {
+                Ok(HandlerResult::ok("ok"))
+            }
+        }
+    }
+
+    fn flaky(calls: &Arc<AtomicU32>, failures: u32, delay_ms: u64) -> Flaky {
+        Flaky {
+            calls: calls.clone(),
+            failures,
+            delay_ms,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_route_retries_and_timeout() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let slow_calls = Arc::new(AtomicU32::new(0));
+        let gateway = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/flaky")
+                    .name("flaky")
+                    .handler(flaky(&calls, 2, 0))
+                    .build(),
+            )
+            .route(
+                RouteBuilder::new("/slow")
+                    .handler(flaky(&slow_calls, 0, 200))
+                    .policy(RoutePolicy::new().timeout_ms(20))
+                    .build(),
+            )
+            .route_policies(RoutePolicies::from([(
+                "flaky".to_string(),
+                RoutePolicy::new().retry(RouteRetry::new(3).backoff_ms(1, 1)),
+            )]))
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/flaky");
+        let result = gateway.route(Method::Get, "/flaky", ctx).await.unwrap();
+        assert_eq!(result.status, 200);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let ctx = RequestContext::new("/slow");
+        let result = gateway.route(Method::Get, "/slow", ctx).await.unwrap();
+        assert_eq!(result.status, 504);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_only_idempotent_requests_retry_after_timeouts() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let gateway = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/slow")
+                    .handler(flaky(&calls, 0, 200))
+                    .policy(
+                        RoutePolicy::new()
+                            .timeout_ms(20)
+                            .retry(RouteRetry::new(2).backoff_ms(1, 1)),
+                    )
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/slow");
+        let result = gateway.route(Method::Post, "/slow", ctx).await.unwrap();
+        assert_eq!(result.status, 504);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let ctx = RequestContext::new("/slow").with_header("idempotency-key", "req-1");
+        let result = gateway.route(Method::Post, "/slow", ctx).await.unwrap();
+        assert_eq!(result.status, 504);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let ctx = RequestContext::new("/slow");
+        let result = gateway.route(Method::Get, "/slow", ctx).await.unwrap();
+        assert_eq!(result.status, 504);
+        assert_eq!(calls.load(Ordering::SeqCst), 7);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_is_shared() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let retry = RouteRetry::new(5).backoff_ms(0, 0).budget_ratio(0.0);
+        let gateway = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/down")
+                    .handler(flaky(&calls, u32::MAX, 0))
+                    .policy(RoutePolicy::new().retry(retry))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        for _ in 0..5 {
+            let ctx = RequestContext::new("/down");
+            let result = gateway.route(Method::Get, "/down", ctx).await.unwrap();
+            assert_eq!(result.status, 503);
+        }
+        // 5 requests plus the initial budget of 10 retries
+        assert_eq!(calls.load(Ordering::SeqCst), 15);
+    }
+
+    struct SplitEcho;
+
+    #[async_trait]
+    impl Handler for SplitEcho {
+        async fn handle(&self, ctx: RequestContext) -> InfraResult<HandlerResult> {
+            let backend = ctx
+                .header(SPLIT_BACKEND_URL_HEADER)
+                .cloned()
+                .unwrap_or_default();
+            if ctx.header(SPLIT_GROUP_HEADER).map(String::as_str) == Some("canary") {
+                return Ok(HandlerResult::error(502, "canary broke"));
+            }
+            Ok(HandlerResult::ok(backend))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_use_traffic_splits() {
+        async fn balancer(url: &str) -> LoadBalancer {
+            let balancer = LoadBalancer::round_robin();
+            balancer.add_backend(Backend::new(url)).await;
+            balancer
+        }
+
+        let gateway = GatewayBuilder::new()
+            .split(
+                TrafficSplit::new("chat")
+                    .group("stable", 50, balancer("http://stable").await)
+                    .group("canary", 50, balancer("http://canary").await),
+            )
+            .route(
+                RouteBuilder::new("/chat")
+                    .split("chat")
+                    .handler(SplitEcho)
+                    .build(),
+            )
+            .route(
+                RouteBuilder::new("/lost")
+                    .split("missing")
+                    .handler(SplitEcho)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        for _ in 0..20 {
+            let ctx = RequestContext::new("/chat");
+            let result = gateway.route(Method::Post, "/chat", ctx).await.unwrap();
+            assert!(result.status == 502 || result.body == b"http://stable");
+        }
+        let split = gateway.split("chat").unwrap();
+        let stable = split.stats_for("stable").unwrap();
+        let canary = split.stats_for("canary").unwrap();
+        assert_eq!(stable.failures + canary.successes, 0);
+        assert_eq!(stable.requests() + canary.requests(), 20);
+
+        let ctx = RequestContext::new("/lost");
+        let result = gateway.respond(Method::Get, "/lost", ctx).await;
+        assert_eq!(result.status, 500);
+    }
+
+    struct StreamingHandler;
+
+    #[async_trait]
+    impl Handler for StreamingHandler {
+        async fn handle(&self, _ctx: RequestContext) -> InfraResult<HandlerResult> {
+            let (tx, body) = BodyStream::channel(4);
+            tokio::spawn(async move {
+                for token in ["Hel", "lo"] {
+                    let _ = tx.send(token.as_bytes().to_vec()).await;
+                }
+            });
+            Ok(HandlerResult::event_stream(body))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_responses_are_proxied() {
+        let registry = Arc::new(infra_otel::MetricsRegistry::new());
+        let gateway = GatewayBuilder::new()
+            .route(RouteBuilder::new("/chat").handler(StreamingHandler).build())
+            .stream_proxy(StreamProxy::new().with_metrics(registry.clone()))
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/chat");
+        let result = gateway.route(Method::Post, "/chat", ctx).await.unwrap();
+        let mut body = result.body_stream().unwrap().take().unwrap();
+        let mut received = Vec::new();
+        while let Some(chunk) = body.recv().await {
+            received.extend(chunk);
+        }
+        assert_eq!(received, b"Hello");
+
+        let closed = registry.counter_with(
+            "gateway_stream_closed_total",
+            &[("kind", "sse"), ("reason", "backend_closed")],
+        );
+        assert_eq!(closed.get(), 1);
+    }
+
+    struct Recorder(tokio::sync::mpsc::UnboundedSender<RequestContext>);
+
+    #[async_trait]
+    impl Handler for Recorder {
+        async fn handle(&self, ctx: RequestContext) -> InfraResult<HandlerResult> {
+            let _ = self.0.send(ctx);
+            Ok(HandlerResult::internal_error("canary broke"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shadow_traffic() {
+        let (tx, mut mirrored) = tokio::sync::mpsc::unbounded_channel();
+        let gateway = GatewayBuilder::new()
+            .route(
+                RouteBuilder::new("/chat")
+                    .handler(EchoHandler)
+                    .policy(RoutePolicy::new().shadow("chat-canary", 100.0))
+                    .build(),
+            )
+            .route(
+                RouteBuilder::new("/internal/chat-canary")
+                    .name("chat-canary")
+                    .handler(Recorder(tx))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        for _ in 0..3 {
+            let ctx = RequestContext::new("/chat")
+                .with_header("Authorization", "Bearer secret")
+                .with_header("x-tenant", "acme");
+            let result = gateway.route(Method::Post, "/chat", ctx).await.unwrap();
+            assert_eq!(result.status, 200);
+        }
+        for _ in 0..3 {
+            let ctx = mirrored.recv().await.unwrap();
+            assert_eq!(ctx.header("x-shadow").map(String::as_str), Some("true"));
+            assert_eq!(ctx.header("x-tenant").map(String::as_str), Some("acme"));
+            assert!(ctx.header("Authorization").is_none());
+        }
+    }
 }
@@ -1,7 +1,9 @@
 //! Request handlers.
 
+use crate::stream::BodyStream;
 use async_trait::async_trait;
-use infra_errors::InfraResult;
+use infra_auth::Identity;
+use infra_errors::{InfraError, InfraResult, ProblemDetails, PROBLEM_JSON_CONTENT_TYPE};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::future::Future;
@@ -17,6 +19,7 @@ pub struct HandlerResult {
     pub body: Vec<u8>,
     /// Response headers
     pub headers: HashMap<String, String>,
+    pub(crate) stream: Option<BodyStream>,
 }
 
 impl HandlerResult {
@@ -26,6 +29,7 @@ impl HandlerResult {
             status: 200,
             body: body.into(),
             headers: HashMap::new(),
+            stream: None,
         }
     }
 
@@ -39,18 +43,70 @@ impl HandlerResult {
             status: 200,
             body,
             headers,
+            stream: None,
         })
     }
 
-    /// Create an error response
+    /// Create an error response with a problem details body
     pub fn error(status: u16, message: &str) -> Self {
+        Self::from_problem(&ProblemDetails::new(status).with_detail(message))
+    }
+
+    /// Create an error response from an error
+    ///
+    /// The status comes from [`InfraError::to_http_status`] and the body is
+    /// the error's problem details.
+    pub fn problem(error: &InfraError) -> Self {
+        Self::from_problem(&error.to_problem_details())
+    }
+
+    /// Create an error response from problem details
+    ///
+    /// Problems with a retry delay also get a `retry-after` header.
+    pub fn from_problem(problem: &ProblemDetails) -> Self {
+        let mut headers = HashMap::new();
+        headers.insert(
+            "content-type".to_string(),
+            PROBLEM_JSON_CONTENT_TYPE.to_string(),
+        );
+        if let Some(retry_after) = problem.retry_after {
+            headers.insert("retry-after".to_string(), retry_after.to_string());
+        }
+        Self {
+            status: problem.status,
+            body: problem.to_json(),
+            headers,
+            stream: None,
+        }
+    }
+
+    /// Create a streamed response
+    pub fn stream(stream: BodyStream) -> Self {
         Self {
-            status,
-            body: message.as_bytes().to_vec(),
+            status: 200,
+            body: Vec::new(),
             headers: HashMap::new(),
+            stream: Some(stream),
         }
     }
 
+    /// Create a server-sent event stream response
+    pub fn event_stream(stream: BodyStream) -> Self {
+        Self::stream(stream)
+            .with_header("content-type", "text/event-stream")
+            .with_header("cache-control", "no-cache")
+    }
+
+    /// Check if the body is streamed
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Get the streamed body, sent after `body`
+    pub fn body_stream(&self) -> Option<&BodyStream> {
+        self.stream.as_ref()
+    }
+
     /// Create a not found response
     pub fn not_found() -> Self {
         Self::error(404, "Not Found")
@@ -92,6 +148,8 @@ pub struct RequestContext {
     pub headers: HashMap<String, String>,
     /// Request body
     pub body: Vec<u8>,
+    /// Authenticated caller, if any
+    pub identity: Option<Identity>,
 }
 
 impl RequestContext {
@@ -103,9 +161,22 @@ impl RequestContext {
             query: HashMap::new(),
             headers: HashMap::new(),
             body: Vec::new(),
+            identity: None,
         }
     }
 
+    /// Set a header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the body
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
     /// Get a path parameter
     pub fn param(&self, name: &str) -> Option<&String> {
         self.params.get(name)
@@ -125,6 +196,25 @@ impl RequestContext {
     pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
         serde_json::from_slice(&self.body)
     }
+
+    /// Check if the request asks to upgrade to a WebSocket
+    pub fn is_websocket_upgrade(&self) -> bool {
+        let connection_upgrade = self.header("connection").is_some_and(|value| {
+            value
+                .split(',')
+                .any(|token| token.trim().eq_ignore_ascii_case("upgrade"))
+        });
+        let websocket = self
+            .header("upgrade")
+            .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+        connection_upgrade && websocket
+    }
+
+    /// Check if the request accepts a server-sent event stream
+    pub fn accepts_event_stream(&self) -> bool {
+        self.header("accept")
+            .is_some_and(|value| value.contains("text/event-stream"))
+    }
 }
 
 /// Handler trait
@@ -190,4 +280,31 @@ mod tests {
             Some(&"application/json".to_string())
         );
     }
+
+    #[test]
+    fn test_problem_result() {
+        let result = HandlerResult::problem(&InfraError::External {
+            service: "openai".to_string(),
+            operation: "complete".to_string(),
+            message: "Overloaded".to_string(),
+            retry_after: Some(std::time::Duration::from_millis(1500)),
+            context: None,
+            source: None,
+        });
+        assert_eq!(result.status, 503);
+        assert_eq!(
+            result.headers.get("content-type"),
+            Some(&PROBLEM_JSON_CONTENT_TYPE.to_string())
+        );
+        assert_eq!(result.headers.get("retry-after"), Some(&"2".to_string()));
+        let body: Value = serde_json::from_slice(&result.body).unwrap();
+        assert_eq!(body["status"], 503);
+        assert_eq!(body["code"], "INFRA-EXTERNAL-0001");
+
+        let result = HandlerResult::bad_request("Missing model");
+        let body: ProblemDetails = serde_json::from_slice(&result.body).unwrap();
+        assert_eq!(body.title, "Bad Request");
+        assert_eq!(body.detail.as_deref(), Some("Missing model"));
+        assert!(!result.headers.contains_key("retry-after"));
+    }
 }
@@ -8,12 +8,32 @@ mod matcher;
 mod handler;
 mod gateway;
 mod balancer;
+mod llm;
+mod middleware;
+mod outlier;
+mod policy;
+mod split;
+mod stream;
 
-pub use route::{Route, RouteBuilder};
-pub use matcher::{PathMatcher, MatchResult};
-pub use handler::{Handler, HandlerFn, HandlerResult};
+pub use route::{Method, Route, RouteBuilder};
+pub use matcher::{PathMatcher, MatchResult, RouteTree, TreeMatch};
+pub use handler::{Handler, HandlerFn, HandlerResult, RequestContext};
 pub use gateway::{Gateway, GatewayConfig, GatewayBuilder};
 pub use balancer::{LoadBalancer, Backend, Strategy};
+pub use llm::{
+    LlmRequestInfo, LlmRouteError, LlmRouter, ModelBackend, ModelTarget, BACKEND_HEADER,
+    BACKEND_URL_HEADER, TOKENS_HEADER,
+};
+pub use middleware::{AuthMiddleware, BodyTransform, HeaderRewrite, Middleware, RateLimitMiddleware};
+pub use outlier::{EjectionReason, OutlierDetection};
+pub use policy::{RoutePolicies, RoutePolicy, RouteRetry, Shadow};
+pub use split::{
+    GroupStats, SplitTarget, TrafficSplit, SPLIT_BACKEND_URL_HEADER, SPLIT_GROUP_HEADER,
+};
+pub use stream::{
+    BodyStream, CloseReason, ConnectionStats, Message, SseEvent, StreamKind, StreamProxy,
+    WebSocketChannel,
+};
 
 use infra_errors::InfraResult;
 
@@ -0,0 +1,502 @@
+//! LLM-aware routing by model, token estimate and provider quota.
+
+use crate::balancer::{Backend, LoadBalancer};
+use crate::handler::{HandlerResult, RequestContext};
+use crate::middleware::Middleware;
+use async_trait::async_trait;
+use infra_errors::{ErrorSource, InfraError, InfraResult, ProblemDetails};
+use infra_llm_client::{context_window, prompt_tokens, LlmRequest};
+use infra_otel::MetricsRegistry;
+use infra_rate_limit::RateLimiter;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Header carrying the name of the chosen backend
+pub const BACKEND_HEADER: &str = "x-llm-backend";
+
+/// Header carrying the URL of the chosen backend
+pub const BACKEND_URL_HEADER: &str = "x-llm-backend-url";
+
+/// Header carrying the estimated token count of the request
+pub const TOKENS_HEADER: &str = "x-llm-tokens";
+
+/// Model, size and output reservation of an LLM request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmRequestInfo {
+    /// Requested model
+    pub model: String,
+    /// Estimated prompt tokens
+    pub prompt_tokens: usize,
+    /// Tokens reserved for the output
+    pub max_tokens: usize,
+}
+
+impl LlmRequestInfo {
+    /// Describe a request
+    pub fn new(request: &LlmRequest) -> Self {
+        Self {
+            model: request.model.clone(),
+            prompt_tokens: prompt_tokens(request),
+            max_tokens: request.max_tokens.map_or(0, |t| t as usize),
+        }
+    }
+
+    /// Describe a request from its JSON body
+    pub fn from_body(body: &[u8]) -> InfraResult<Self> {
+        let request: LlmRequest = serde_json::from_slice(body)?;
+        Ok(Self::new(&request))
+    }
+
+    /// Get the tokens the request needs in the context window
+    pub fn total_tokens(&self) -> usize {
+        self.prompt_tokens + self.max_tokens
+    }
+}
+
+/// Backend serving some models
+pub struct ModelBackend {
+    name: String,
+    balancer: Arc<LoadBalancer>,
+    models: Vec<String>,
+    context_window: Option<usize>,
+    quota: Option<Arc<dyn RateLimiter>>,
+}
+
+impl ModelBackend {
+    /// Create a backend serving no model
+    pub fn new(name: impl Into<String>, balancer: LoadBalancer) -> Self {
+        Self {
+            name: name.into(),
+            balancer: Arc::new(balancer),
+            models: Vec::new(),
+            context_window: None,
+            quota: None,
+        }
+    }
+
+    /// Serve a model, or models starting with a prefix if `pattern` ends
+    /// with `*`
+    pub fn model(mut self, pattern: impl Into<String>) -> Self {
+        self.models.push(pattern.into());
+        self
+    }
+
+    /// Set the largest request, in tokens, the backend accepts
+    ///
+    /// Defaults to the context window of the requested model.
+    pub fn context_window(mut self, tokens: usize) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    /// Limit requests to the provider's token quota
+    ///
+    /// A permit is taken for every token a request routed to the backend is
+    /// estimated to use, prompt and `max_tokens` together, and the backend
+    /// is skipped while the quota cannot cover the request. The limiter's
+    /// burst size must be at least the largest request it should admit.
+    pub fn quota(mut self, limiter: Arc<dyn RateLimiter>) -> Self {
+        self.quota = Some(limiter);
+        self
+    }
+
+    /// Get the name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the balancer
+    pub fn balancer(&self) -> Arc<LoadBalancer> {
+        self.balancer.clone()
+    }
+
+    /// Check if the backend serves a model
+    pub fn serves(&self, model: &str) -> bool {
+        self.pattern(model).is_some()
+    }
+
+    /// Get the configured pattern matching a model
+    fn pattern(&self, model: &str) -> Option<&str> {
+        self.models
+            .iter()
+            .find(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => model.starts_with(prefix),
+                None => *pattern == model,
+            })
+            .map(String::as_str)
+    }
+
+    /// Get the largest request the backend accepts for a model
+    pub fn capacity(&self, model: &str) -> usize {
+        self.context_window.unwrap_or_else(|| context_window(model))
+    }
+}
+
+/// Backend chosen for an LLM request
+#[derive(Debug, Clone)]
+pub struct ModelTarget {
+    /// Name of the chosen backend
+    pub name: String,
+    /// Server within the backend
+    pub backend: Backend,
+    /// Estimated tokens of the request
+    pub tokens: usize,
+}
+
+/// Reason an LLM request could not be routed
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LlmRouteError {
+    /// No backend serves the model
+    #[error("No backend serves model {0}")]
+    UnknownModel(String),
+    /// The request is larger than every serving backend accepts
+    #[error("Request of {tokens} tokens exceeds the {max} token context of model {model}")]
+    ContextTooLarge {
+        model: String,
+        tokens: usize,
+        max: usize,
+    },
+    /// Every backend that fits is out of quota
+    #[error("Quota exhausted for model {model}")]
+    QuotaExhausted {
+        model: String,
+        retry_after: Duration,
+    },
+    /// Every backend that fits has no healthy server
+    #[error("No healthy backend for model {0}")]
+    Unavailable(String),
+}
+
+impl LlmRouteError {
+    /// Get the HTTP status to respond with
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::UnknownModel(_) => 404,
+            Self::ContextTooLarge { .. } => 413,
+            Self::QuotaExhausted { .. } => 429,
+            Self::Unavailable(_) => 503,
+        }
+    }
+
+    /// Get the label recorded on metrics
+    fn reason(&self) -> &'static str {
+        match self {
+            Self::UnknownModel(_) => "unknown_model",
+            Self::ContextTooLarge { .. } => "context_too_large",
+            Self::QuotaExhausted { .. } => "quota_exhausted",
+            Self::Unavailable(_) => "unavailable",
+        }
+    }
+
+    fn into_response(self) -> HandlerResult {
+        let problem = ProblemDetails::new(self.status()).with_detail(self.to_string());
+        let problem = match self {
+            Self::QuotaExhausted { retry_after, .. } => problem.with_retry_after(retry_after),
+            _ => problem,
+        };
+        HandlerResult::from_problem(&problem)
+    }
+}
+
+impl From<LlmRouteError> for InfraError {
+    fn from(error: LlmRouteError) -> Self {
+        let retry_after = match &error {
+            LlmRouteError::QuotaExhausted { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        };
+        InfraError::External {
+            service: "llm_router".to_string(),
+            operation: "route".to_string(),
+            message: error.to_string(),
+            retry_after,
+            context: None,
+            source: Some(ErrorSource::new(error)),
+        }
+    }
+}
+
+/// Router sending LLM requests to backends by model, size and quota
+///
+/// Among the backends serving the requested model whose context window
+/// fits the request's estimated prompt plus `max_tokens`, requests go to the
+/// one with the smallest window, so large-context backends are kept for the
+/// requests that need them. Backends out of quota or without a healthy
+/// server are skipped for the next one that fits.
+///
+/// As [`Middleware`], the router reads the request body and records its
+/// choice in the [`BACKEND_HEADER`], [`BACKEND_URL_HEADER`] and
+/// [`TOKENS_HEADER`] request headers for the handler to forward to:
+///
+/// ```ignore
+/// let router = LlmRouter::new()
+///     .backend(ModelBackend::new("gpt-4o-mini", mini).model("gpt-4o*").context_window(16_000))
+///     .backend(ModelBackend::new("gpt-4o", full).model("gpt-4o*").quota(openai_quota));
+///
+/// let gateway = GatewayBuilder::new()
+///     .route(RouteBuilder::new("/v1/chat/completions").post().middleware(router).handler(forward).build())
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub struct LlmRouter {
+    backends: Vec<ModelBackend>,
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+impl LlmRouter {
+    /// Create a router without backends
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a backend
+    pub fn backend(mut self, backend: ModelBackend) -> Self {
+        self.backends.push(backend);
+        self
+    }
+
+    /// Export routed and rejected request counts to `registry`
+    ///
+    /// Routed requests are labelled with the backend and the model pattern
+    /// it was configured with, rather than the requested model, so clients
+    /// cannot create series at will.
+    pub fn with_metrics(mut self, registry: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(registry);
+        self
+    }
+
+    /// Choose the backend for a request
+    pub async fn route(&self, request: &LlmRequestInfo) -> Result<ModelTarget, LlmRouteError> {
+        let result = self.choose(request).await;
+        if let Some(registry) = &self.metrics {
+            match &result {
+                Ok(target) => {
+                    let pattern = self
+                        .backends
+                        .iter()
+                        .find(|b| b.name == target.name)
+                        .and_then(|b| b.pattern(&request.model))
+                        .unwrap_or_default();
+                    registry.counter_with(
+                        "gateway_llm_requests_total",
+                        &[("backend", &target.name), ("model", pattern)],
+                    )
+                }
+                Err(e) => {
+                    registry.counter_with("gateway_llm_rejections_total", &[("reason", e.reason())])
+                }
+            }
+            .inc();
+        }
+        result
+    }
+
+    async fn choose(&self, request: &LlmRequestInfo) -> Result<ModelTarget, LlmRouteError> {
+        let model = &request.model;
+        let tokens = request.total_tokens();
+
+        let serving: Vec<&ModelBackend> =
+            self.backends.iter().filter(|b| b.serves(model)).collect();
+        let mut fitting: Vec<&ModelBackend> = serving
+            .iter()
+            .copied()
+            .filter(|b| b.capacity(model) >= tokens)
+            .collect();
+        if fitting.is_empty() {
+            return Err(match serving.iter().map(|b| b.capacity(model)).max() {
+                Some(max) => LlmRouteError::ContextTooLarge {
+                    model: model.clone(),
+                    tokens,
+                    max,
+                },
+                None => LlmRouteError::UnknownModel(model.clone()),
+            });
+        }
+        fitting.sort_by_key(|b| b.capacity(model));
+
+        let mut retry_after: Option<Duration> = None;
+        for backend in fitting {
+            // Pick a server first so quota is only spent on requests sent
+            let server = match backend.balancer.next().await {
+                Ok(server) => server,
+                Err(e) => {
+                    tracing::debug!(backend = %backend.name, error = %e, "Backend unavailable");
+                    continue;
+                }
+            };
+            if let Some(quota) = &backend.quota {
+                if let Some(wait) = quota.try_acquire_n(tokens as u64).await.wait_time() {
+                    tracing::debug!(backend = %backend.name, model = %model, "Backend out of quota");
+                    retry_after = Some(retry_after.map_or(wait, |w| w.min(wait)));
+                    continue;
+                }
+            }
+            return Ok(ModelTarget {
+                name: backend.name.clone(),
+                backend: server,
+                tokens,
+            });
+        }
+
+        Err(match retry_after {
+            Some(retry_after) => LlmRouteError::QuotaExhausted {
+                model: model.clone(),
+                retry_after,
+            },
+            None => LlmRouteError::Unavailable(model.clone()),
+        })
+    }
+}
+
+#[async_trait]
+impl Middleware for LlmRouter {
+    async fn on_request(&self, ctx: &mut RequestContext) -> InfraResult<Option<HandlerResult>> {
+        let request = match LlmRequestInfo::from_body(&ctx.body) {
+            Ok(request) => request,
+            Err(e) => return Ok(Some(HandlerResult::bad_request(&e.to_string()))),
+        };
+        match self.route(&request).await {
+            Ok(target) => {
+                let headers = [
+                    (BACKEND_HEADER, target.name),
+                    (BACKEND_URL_HEADER, target.backend.url),
+                    (TOKENS_HEADER, target.tokens.to_string()),
+                ];
+                for (name, value) in headers {
+                    ctx.headers.insert(name.to_string(), value);
+                }
+                Ok(None)
+            }
+            Err(e) => Ok(Some(e.into_response())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_rate_limit::{RateLimitConfig, TokenBucket};
+
+    async fn backend(name: &str) -> ModelBackend {
+        let balancer = LoadBalancer::round_robin();
+        balancer
+            .add_backend(Backend::new(format!("http://{name}")))
+            .await;
+        ModelBackend::new(name, balancer)
+    }
+
+    fn request(model: &str, prompt_tokens: usize) -> LlmRequestInfo {
+        LlmRequestInfo {
+            model: model.to_string(),
+            prompt_tokens,
+            max_tokens: 1000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_by_model_and_size() {
+        let router = LlmRouter::new()
+            .backend(
+                backend("long")
+                    .await
+                    .model("llama-*")
+                    .context_window(128_000),
+            )
+            .backend(
+                backend("short")
+                    .await
+                    .model("llama-*")
+                    .context_window(8_000),
+            )
+            .backend(backend("claude").await.model("claude-3-5-sonnet"));
+
+        let target = router.route(&request("llama-3.1-8b", 2_000)).await.unwrap();
+        assert_eq!((target.name.as_str(), target.tokens), ("short", 3_000));
+        let target = router
+            .route(&request("llama-3.1-8b", 50_000))
+            .await
+            .unwrap();
+        assert_eq!(target.name, "long");
+        let target = router.route(&request("claude-3-5-sonnet", 150_000)).await;
+        assert_eq!(target.unwrap().backend.url, "http://claude");
+
+        let error = router
+            .route(&request("llama-3.1-8b", 200_000))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), 413);
+        let error = router.route(&request("gpt-4o", 10)).await.unwrap_err();
+        assert_eq!(error, LlmRouteError::UnknownModel("gpt-4o".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_skips_backends_out_of_quota() {
+        let quota = |tokens| {
+            let config = RateLimitConfig::new(0.001, tokens, Duration::from_secs(1)).unwrap();
+            Arc::new(TokenBucket::new(config)) as Arc<dyn RateLimiter>
+        };
+        let idle = quota(10_000);
+        let registry = Arc::new(MetricsRegistry::new());
+        let router = LlmRouter::new()
+            .backend(
+                ModelBackend::new("down", LoadBalancer::round_robin())
+                    .model("gpt-4o*")
+                    .quota(idle.clone()),
+            )
+            .backend(
+                backend("primary")
+                    .await
+                    .model("gpt-4o*")
+                    .quota(quota(2_500)),
+            )
+            .backend(
+                backend("overflow")
+                    .await
+                    .model("gpt-4o*")
+                    .context_window(200_000)
+                    .quota(quota(1_500)),
+            )
+            .with_metrics(registry.clone());
+
+        // Each request takes 1,100 tokens of quota
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let target = router.route(&request("gpt-4o-2024-08-06", 100)).await;
+            names.push(target.unwrap().name);
+        }
+        assert_eq!(names, ["primary", "primary", "overflow"]);
+        // No quota is spent on a backend without a healthy server
+        assert_eq!(idle.available().await, 10_000);
+
+        let error = router.route(&request("gpt-4o", 100)).await.unwrap_err();
+        assert_eq!(error.status(), 429);
+        let response = error.into_response();
+        assert!(response.headers.contains_key("retry-after"));
+
+        let routed = registry.counter_with(
+            "gateway_llm_requests_total",
+            &[("backend", "primary"), ("model", "gpt-4o*")],
+        );
+        assert_eq!(routed.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_sets_backend_headers() {
+        let router = LlmRouter::new().backend(backend("mini").await.model("gpt-4o-mini"));
+        let body = r#"{"model":"gpt-4o-mini","messages":[{"role":"user","content":"Hello there"}],"max_tokens":64}"#;
+        let mut ctx = RequestContext::new("/v1/chat/completions").with_body(body);
+
+        assert!(router.on_request(&mut ctx).await.unwrap().is_none());
+        assert_eq!(ctx.header(BACKEND_HEADER).map(String::as_str), Some("mini"));
+        assert_eq!(
+            ctx.header(BACKEND_URL_HEADER).map(String::as_str),
+            Some("http://mini")
+        );
+        let tokens: usize = ctx.header(TOKENS_HEADER).unwrap().parse().unwrap();
+        assert!(tokens > 64);
+
+        let mut ctx = RequestContext::new("/v1/chat/completions").with_body("not json");
+        let response = router.on_request(&mut ctx).await.unwrap().unwrap();
+        assert_eq!(response.status, 400);
+    }
+}
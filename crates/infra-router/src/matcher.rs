@@ -1,76 +1,421 @@
 //! Path matching.
+//!
+//! Patterns are made of `/`-separated segments, each one of:
+//!
+//! - a literal, such as `users`
+//! - a parameter, such as `:id`, optionally constrained to a type, as in
+//!   `:id<uuid>`; types are `int`, `uint`, `uuid`, `hex`, `alpha` and `alnum`
+//! - a catch-all, such as `*path`, matching the rest of the path, which may
+//!   be empty; a bare `*` is not captured
+//!
+//! Empty segments are ignored, so trailing slashes do not matter.
+//!
+//! Unlike the regular expressions paths were matched with before, a `*`
+//! must now be the last segment: patterns such as `/a/*/b` are rejected
+//! rather than matching anything between `/a/` and `/b`. A trailing `*`
+//! matches as it did, and now also matches the path without the trailing
+//! slash, so `/files/*` matches `/files`.
 
-use regex::Regex;
+use crate::route::Method;
+use infra_errors::{InfraError, InfraResult};
 use std::collections::HashMap;
 
 /// Match result containing extracted parameters
 pub type MatchResult = HashMap<String, String>;
 
-/// Path matcher
-pub struct PathMatcher {
-    /// Original pattern
-    pattern: String,
-    /// Compiled regex
-    regex: Regex,
-    /// Parameter names in order
-    params: Vec<String>,
+/// Type a parameter must parse as
+///
+/// Ordered from most to least specific, the order in which parameters at
+/// the same position are tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Constraint {
+    Uuid,
+    Uint,
+    Int,
+    Hex,
+    Alpha,
+    Alnum,
 }
 
-impl PathMatcher {
-    /// Create a new path matcher
-    pub fn new(pattern: &str) -> Self {
-        let mut regex_pattern = String::from("^");
-        let mut params = Vec::new();
+impl Constraint {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "int" => Self::Int,
+            "uint" => Self::Uint,
+            "uuid" => Self::Uuid,
+            "hex" => Self::Hex,
+            "alpha" => Self::Alpha,
+            "alnum" => Self::Alnum,
+            _ => return None,
+        })
+    }
 
-        for segment in pattern.split('/') {
-            if segment.is_empty() {
-                continue;
+    fn matches(self, value: &str) -> bool {
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        match self {
+            Self::Int => digits(value.strip_prefix('-').unwrap_or(value)),
+            Self::Uint => digits(value),
+            Self::Uuid => {
+                value.len() == 36
+                    && value.bytes().enumerate().all(|(i, b)| match i {
+                        8 | 13 | 18 | 23 => b == b'-',
+                        _ => b.is_ascii_hexdigit(),
+                    })
             }
+            Self::Hex => !value.is_empty() && value.bytes().all(|b| b.is_ascii_hexdigit()),
+            Self::Alpha => !value.is_empty() && value.bytes().all(|b| b.is_ascii_alphabetic()),
+            Self::Alnum => !value.is_empty() && value.bytes().all(|b| b.is_ascii_alphanumeric()),
+        }
+    }
+}
+
+/// Parsed pattern segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Static(String),
+    Param(String, Option<Constraint>),
+    CatchAll(Option<String>),
+}
+
+/// Parse a pattern into segments
+fn parse_pattern(pattern: &str) -> InfraResult<Vec<Segment>> {
+    let raw: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let invalid = |reason: String| {
+        InfraError::validation(format!("Invalid route pattern {pattern:?}: {reason}"))
+    };
 
-            regex_pattern.push('/');
-
-            if let Some(param) = segment.strip_prefix(':') {
-                // Named parameter
-                params.push(param.to_string());
-                regex_pattern.push_str("([^/]+)");
-            } else if segment == "*" {
-                // Wildcard
-                regex_pattern.push_str("(.*)");
-            } else {
-                // Literal segment
-                regex_pattern.push_str(&regex::escape(segment));
+    let mut segments = Vec::with_capacity(raw.len());
+    for (i, segment) in raw.iter().enumerate() {
+        if let Some(param) = segment.strip_prefix(':') {
+            let (name, constraint) = match param.split_once('<') {
+                Some((name, rest)) => {
+                    let ty = rest
+                        .strip_suffix('>')
+                        .ok_or_else(|| invalid(format!("unclosed type in {segment:?}")))?;
+                    let constraint = Constraint::parse(ty)
+                        .ok_or_else(|| invalid(format!("unknown parameter type {ty:?}")))?;
+                    (name, Some(constraint))
+                }
+                None => (param, None),
+            };
+            if name.is_empty() {
+                return Err(invalid("unnamed parameter".to_string()));
+            }
+            segments.push(Segment::Param(name.to_string(), constraint));
+        } else if let Some(name) = segment.strip_prefix('*') {
+            if i + 1 != raw.len() {
+                return Err(invalid("catch-all must be the last segment".to_string()));
             }
+            segments.push(Segment::CatchAll(
+                (!name.is_empty()).then(|| name.to_string()),
+            ));
+        } else {
+            segments.push(Segment::Static(segment.to_string()));
         }
+    }
+    Ok(segments)
+}
 
-        regex_pattern.push_str("/?$");
+/// Split a path into non-empty segments with their byte offsets
+fn split_path(path: &str) -> Vec<(usize, &str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for part in path.split('/') {
+        if !part.is_empty() {
+            segments.push((start, part));
+        }
+        start += part.len() + 1;
+    }
+    segments
+}
 
-        let regex = Regex::new(&regex_pattern).unwrap_or_else(|_| Regex::new("^$").unwrap());
+/// Values stored for one pattern, per method
+#[derive(Debug)]
+struct Endpoints<T> {
+    methods: Vec<(Method, T)>,
+}
 
+impl<T> Default for Endpoints<T> {
+    fn default() -> Self {
         Self {
-            pattern: pattern.to_string(),
-            regex,
-            params,
+            methods: Vec::new(),
         }
     }
+}
 
-    /// Match a path and extract parameters
-    pub fn match_path(&self, path: &str) -> Option<MatchResult> {
-        self.regex.captures(path).map(|caps| {
-            let mut params = HashMap::new();
+impl<T> Endpoints<T> {
+    /// Get the value for a method, falling back to one for any method
+    fn get(&self, method: Method) -> Option<&T> {
+        let exact = self.methods.iter().find(|(m, _)| *m == method);
+        exact
+            .or_else(|| self.methods.iter().find(|(m, _)| *m == Method::Any))
+            .map(|(_, value)| value)
+    }
 
-            for (i, name) in self.params.iter().enumerate() {
-                if let Some(value) = caps.get(i + 1) {
-                    params.insert(name.clone(), value.as_str().to_string());
-                }
+    fn insert(&mut self, method: Method, value: T) -> bool {
+        if self.methods.iter().any(|(m, _)| *m == method) {
+            return false;
+        }
+        self.methods.push((method, value));
+        true
+    }
+}
+
+/// Node of a [`RouteTree`]
+#[derive(Debug)]
+struct Node<T> {
+    statics: HashMap<String, Node<T>>,
+    /// Parameter children, constrained ones first, from most to least
+    /// specific
+    params: Vec<(String, Option<Constraint>, Node<T>)>,
+    catch_all: Option<(Option<String>, Endpoints<T>)>,
+    endpoints: Endpoints<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            statics: HashMap::new(),
+            params: Vec::new(),
+            catch_all: None,
+            endpoints: Endpoints::default(),
+        }
+    }
+}
+
+impl<T> Node<T> {
+    /// Find the endpoints for the remaining segments, preferring literals,
+    /// then constrained parameters, then parameters, then catch-alls
+    ///
+    /// Branches without a route for the rest of the path are backtracked
+    /// out of, but each node is reached only through its parent, so a
+    /// search visits every node at most once.
+    fn search<'a>(
+        &'a self,
+        path: &str,
+        segments: &[(usize, &str)],
+        params: &mut Vec<(&'a str, String)>,
+        accept: &dyn Fn(&Endpoints<T>) -> bool,
+    ) -> Option<&'a Endpoints<T>> {
+        let Some(&(offset, segment)) = segments.first() else {
+            if accept(&self.endpoints) {
+                return Some(&self.endpoints);
+            }
+            // A catch-all also matches an empty rest of the path
+            let (name, endpoints) = self.catch_all.as_ref()?;
+            if !accept(endpoints) {
+                return None;
+            }
+            if let Some(name) = name {
+                params.push((name, String::new()));
             }
+            return Some(endpoints);
+        };
+        let rest = &segments[1..];
+
+        if let Some(child) = self.statics.get(segment) {
+            if let Some(found) = child.search(path, rest, params, accept) {
+                return Some(found);
+            }
+        }
+
+        for (name, constraint, child) in &self.params {
+            if constraint.is_some_and(|c| !c.matches(segment)) {
+                continue;
+            }
+            params.push((name, segment.to_string()));
+            if let Some(found) = child.search(path, rest, params, accept) {
+                return Some(found);
+            }
+            params.pop();
+        }
+
+        let (name, endpoints) = self.catch_all.as_ref()?;
+        if !accept(endpoints) {
+            return None;
+        }
+        if let Some(name) = name {
+            params.push((name, path[offset..].trim_end_matches('/').to_string()));
+        }
+        Some(endpoints)
+    }
+}
+
+/// Routes indexed by path pattern and method
+///
+/// Patterns are stored in a radix tree keyed by path segment. Literal
+/// segments take precedence over parameters, constrained parameters (from
+/// most to least specific: `uuid`, `uint`, `int`, `hex`, `alpha`, `alnum`)
+/// over unconstrained ones, and parameters over catch-alls, regardless of
+/// insertion order. A lookup falls back to the next alternative when the
+/// preferred one has no route for the rest of the path, visiting each node
+/// at most once: it takes time proportional to the path length when
+/// literals decide the route, and never more than one pass over the tree.
+#[derive(Debug)]
+pub struct RouteTree<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+/// Route found in a [`RouteTree`]
+#[derive(Debug)]
+pub struct TreeMatch<'a, T> {
+    /// Value stored for the route
+    pub value: &'a T,
+    /// Extracted parameters
+    pub params: MatchResult,
+}
+
+impl<T> RouteTree<T> {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+
+    /// Get the number of routes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the tree has no routes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a route for a method, or for every method with [`Method::Any`]
+    ///
+    /// Fails if the pattern is invalid, already has a route for the method,
+    /// or names a parameter differently than another pattern at the same
+    /// position.
+    pub fn insert(&mut self, method: Method, pattern: &str, value: T) -> InfraResult<()> {
+        let conflict = |reason: String| {
+            InfraError::validation(format!("Route {pattern:?} conflicts: {reason}"))
+        };
+        let mut node = &mut self.root;
+
+        for segment in parse_pattern(pattern)? {
+            node = match segment {
+                Segment::Static(literal) => node.statics.entry(literal).or_default(),
+                Segment::Param(name, constraint) => {
+                    let index = match node.params.iter().position(|(_, c, _)| *c == constraint) {
+                        Some(index) if node.params[index].0 != name => {
+                            return Err(conflict(format!(
+                                "parameter :{name} is also named :{}",
+                                node.params[index].0
+                            )));
+                        }
+                        Some(index) => index,
+                        None => {
+                            // Keep parameters ordered by specificity, unconstrained last
+                            let index = node
+                                .params
+                                .iter()
+                                .position(|(_, c, _)| match (c, constraint) {
+                                    (None, _) => true,
+                                    (Some(c), Some(constraint)) => *c > constraint,
+                                    (Some(_), None) => false,
+                                })
+                                .unwrap_or(node.params.len());
+                            node.params
+                                .insert(index, (name, constraint, Node::default()));
+                            index
+                        }
+                    };
+                    &mut node.params[index].2
+                }
+                Segment::CatchAll(name) => {
+                    let (existing, endpoints) = node
+                        .catch_all
+                        .get_or_insert_with(|| (name.clone(), Endpoints::default()));
+                    if *existing != name {
+                        return Err(conflict("catch-all is named differently".to_string()));
+                    }
+                    if !endpoints.insert(method, value) {
+                        return Err(conflict(format!("duplicate route for {method:?}")));
+                    }
+                    self.len += 1;
+                    return Ok(());
+                }
+            };
+        }
+
+        if !node.endpoints.insert(method, value) {
+            return Err(conflict(format!("duplicate route for {method:?}")));
+        }
+        self.len += 1;
+        Ok(())
+    }
 
-            params
+    /// Find the route for a method and path
+    pub fn find(&self, method: Method, path: &str) -> Option<TreeMatch<'_, T>> {
+        let segments = split_path(path);
+        let mut params = Vec::new();
+        let accept = |endpoints: &Endpoints<T>| endpoints.get(method).is_some();
+        let endpoints = self.root.search(path, &segments, &mut params, &accept)?;
+        Some(TreeMatch {
+            value: endpoints.get(method)?,
+            params: params
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
         })
     }
 
+    /// Get the methods with a route for a path
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let segments = split_path(path);
+        let accept = |endpoints: &Endpoints<T>| !endpoints.methods.is_empty();
+        self.root
+            .search(path, &segments, &mut Vec::new(), &accept)
+            .map(|endpoints| endpoints.methods.iter().map(|(m, _)| *m).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl<T> Default for RouteTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Path matcher for a single pattern
+pub struct PathMatcher {
+    /// Original pattern
+    pattern: String,
+    /// Routes for the pattern, or `None` if it is invalid
+    tree: Option<RouteTree<()>>,
+}
+
+impl PathMatcher {
+    /// Create a new path matcher
+    ///
+    /// Invalid patterns match nothing.
+    pub fn new(pattern: &str) -> Self {
+        let mut tree = RouteTree::new();
+        let tree = match tree.insert(Method::Any, pattern, ()) {
+            Ok(()) => Some(tree),
+            Err(e) => {
+                tracing::warn!(error = %e, "Route never matches");
+                None
+            }
+        };
+        Self {
+            pattern: pattern.to_string(),
+            tree,
+        }
+    }
+
+    /// Match a path and extract parameters
+    pub fn match_path(&self, path: &str) -> Option<MatchResult> {
+        Some(self.tree.as_ref()?.find(Method::Any, path)?.params)
+    }
+
     /// Check if a path matches
     pub fn is_match(&self, path: &str) -> bool {
-        self.regex.is_match(path)
+        self.match_path(path).is_some()
     }
 
     /// Get the pattern
@@ -115,4 +460,92 @@ mod tests {
         let matcher = PathMatcher::new("/api/users/:id");
         assert!(matcher.match_path("/api/posts/123").is_none());
     }
+
+    #[test]
+    fn test_typed_parameters_and_catch_all() {
+        let mut tree = RouteTree::new();
+        tree.insert(Method::Get, "/models/:id<uuid>", "by-uuid")
+            .unwrap();
+        tree.insert(Method::Get, "/models/:name", "by-name")
+            .unwrap();
+        tree.insert(Method::Get, "/models/latest", "latest")
+            .unwrap();
+        tree.insert(Method::Get, "/files/*path", "files").unwrap();
+        tree.insert(Method::Any, "/:version<uint>", "version")
+            .unwrap();
+
+        let id = "0b7c5d7e-2f44-4b43-9d4c-5a3a8a1f6c21";
+        let found = tree.find(Method::Get, &format!("/models/{id}")).unwrap();
+        assert_eq!((*found.value, found.params["id"].as_str()), ("by-uuid", id));
+        let found = tree.find(Method::Get, "/models/gpt-4o").unwrap();
+        assert_eq!(
+            (*found.value, found.params["name"].as_str()),
+            ("by-name", "gpt-4o")
+        );
+        assert_eq!(
+            *tree.find(Method::Get, "/models/latest").unwrap().value,
+            "latest"
+        );
+
+        let found = tree.find(Method::Get, "/files/a/b/c.txt/").unwrap();
+        assert_eq!(found.params["path"], "a/b/c.txt");
+        assert_eq!(tree.find(Method::Get, "/files").unwrap().params["path"], "");
+
+        assert!(tree.find(Method::Delete, "/1").is_some());
+        assert!(tree.find(Method::Delete, "/x").is_none());
+    }
+
+    #[test]
+    fn test_method_matching() {
+        let mut tree = RouteTree::new();
+        tree.insert(Method::Get, "/users/me", "me").unwrap();
+        tree.insert(Method::Delete, "/users/:id", "delete").unwrap();
+        tree.insert(Method::Get, "/users/:id", "get").unwrap();
+
+        assert_eq!(*tree.find(Method::Get, "/users/me").unwrap().value, "me");
+        let found = tree.find(Method::Delete, "/users/me").unwrap();
+        assert_eq!(
+            (*found.value, found.params["id"].as_str()),
+            ("delete", "me")
+        );
+        assert!(tree.find(Method::Post, "/users/7").is_none());
+        assert_eq!(
+            tree.allowed_methods("/users/7"),
+            [Method::Delete, Method::Get]
+        );
+        assert_eq!(tree.len(), 3);
+
+        assert!(tree.insert(Method::Get, "/users/:id", "again").is_err());
+        assert!(tree
+            .insert(Method::Get, "/users/:user/posts", "posts")
+            .is_err());
+        assert!(tree.insert(Method::Get, "/files/*path/raw", "raw").is_err());
+        assert!(tree
+            .insert(Method::Get, "/models/:id<float>", "float")
+            .is_err());
+        assert!(!PathMatcher::new("/models/:id<float>").is_match("/models/1"));
+    }
+
+    #[test]
+    fn test_constraint_precedence_ignores_insertion_order() {
+        for order in [["alnum", "uint", "hex"], ["hex", "alnum", "uint"]] {
+            let mut tree = RouteTree::new();
+            for ty in order {
+                tree.insert(Method::Get, &format!("/items/:id<{ty}>"), ty)
+                    .unwrap();
+            }
+            assert_eq!(*tree.find(Method::Get, "/items/42").unwrap().value, "uint");
+            assert_eq!(*tree.find(Method::Get, "/items/ff").unwrap().value, "hex");
+            assert_eq!(*tree.find(Method::Get, "/items/zz").unwrap().value, "alnum");
+        }
+    }
+
+    #[test]
+    fn test_bare_wildcard() {
+        let matcher = PathMatcher::new("/static/*");
+        assert!(matcher.is_match("/static/"));
+        assert!(matcher.is_match("/static/css/site.css"));
+        assert!(matcher.match_path("/static/app.js").unwrap().is_empty());
+        assert!(!PathMatcher::new("/a/*/b").is_match("/a/x/b"));
+    }
 }
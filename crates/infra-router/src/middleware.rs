@@ -0,0 +1,511 @@
+//! Gateway middleware.
+
+use crate::handler::{HandlerResult, RequestContext};
+use async_trait::async_trait;
+use http::header::AUTHORIZATION;
+use infra_auth::{bearer_token, AsyncIdentityProvider};
+use infra_errors::{InfraResult, ProblemDetails};
+use infra_rate_limit::RateLimiter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Request/response filter in a gateway's pipeline
+///
+/// Requests pass through middleware in the order it was added, gateway-wide
+/// middleware before route middleware; responses pass back in reverse order.
+/// Middleware that responds to a request short-circuits the rest of the
+/// pipeline, and only the middleware that ran before it sees the response.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Process a request, or respond to it directly
+    async fn on_request(&self, ctx: &mut RequestContext) -> InfraResult<Option<HandlerResult>> {
+        let _ = ctx;
+        Ok(None)
+    }
+
+    /// Process a response
+    async fn on_response(
+        &self,
+        ctx: &RequestContext,
+        response: &mut HandlerResult,
+    ) -> InfraResult<()> {
+        let _ = (ctx, response);
+        Ok(())
+    }
+}
+
+/// Middleware authenticating bearer tokens
+///
+/// Verified identities are stored in [`RequestContext::identity`]. Requests
+/// with invalid tokens get a 401, as do requests without a token unless
+/// anonymous access is allowed. The `Authorization` header name is matched
+/// case-insensitively.
+pub struct AuthMiddleware {
+    provider: Arc<dyn AsyncIdentityProvider>,
+    allow_anonymous: bool,
+}
+
+impl AuthMiddleware {
+    /// Create a middleware verifying tokens with `provider`
+    pub fn new(provider: Arc<dyn AsyncIdentityProvider>) -> Self {
+        Self {
+            provider,
+            allow_anonymous: false,
+        }
+    }
+
+    /// Let requests without a token through unauthenticated
+    pub fn allow_anonymous(mut self) -> Self {
+        self.allow_anonymous = true;
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for AuthMiddleware {
+    async fn on_request(&self, ctx: &mut RequestContext) -> InfraResult<Option<HandlerResult>> {
+        let header = ctx
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(AUTHORIZATION.as_str()))
+            .map(|(_, value)| value.as_str());
+        let token = match header.map(bearer_token) {
+            Some(Ok(token)) => token.map(str::to_string),
+            Some(Err(_)) => return Ok(Some(Self::invalid_token())),
+            None => None,
+        };
+
+        let Some(token) = token else {
+            if self.allow_anonymous {
+                return Ok(None);
+            }
+            return Ok(Some(
+                HandlerResult::error(401, "Missing authentication")
+                    .with_header("www-authenticate", "Bearer"),
+            ));
+        };
+
+        match self.provider.verify(&token).await {
+            Ok(identity) => {
+                ctx.identity = Some(identity);
+                Ok(None)
+            }
+            Err(e) => {
+                tracing::debug!(path = %ctx.path, error = %e, "Rejected token");
+                Ok(Some(Self::invalid_token()))
+            }
+        }
+    }
+}
+
+impl AuthMiddleware {
+    fn invalid_token() -> HandlerResult {
+        HandlerResult::error(401, "Invalid token")
+            .with_header("www-authenticate", "Bearer error=\"invalid_token\"")
+    }
+}
+
+/// Function choosing the rate limit bucket of a request
+type KeyFn = Box<dyn Fn(&RequestContext) -> String + Send + Sync>;
+
+/// Function creating the limiter of a new bucket
+type LimiterFactory = Box<dyn Fn() -> Arc<dyn RateLimiter> + Send + Sync>;
+
+/// How requests are assigned limiters
+enum Limiters {
+    Shared(Arc<dyn RateLimiter>),
+    Keyed {
+        key: KeyFn,
+        factory: LimiterFactory,
+        limiters: Mutex<KeyedLimiters>,
+    },
+}
+
+/// Limiters by key, with when each was last used
+#[derive(Default)]
+struct KeyedLimiters {
+    limiters: HashMap<String, (Arc<dyn RateLimiter>, u64)>,
+    uses: u64,
+}
+
+/// Default number of keys with their own limiter
+const DEFAULT_MAX_KEYS: usize = 10_000;
+
+/// Middleware enforcing rate limits
+///
+/// Requests over the limit get a 429 with a `retry-after` header.
+///
+/// Keyed limiters are kept for at most [`max_keys`](Self::max_keys) keys;
+/// beyond that the least recently used key's limiter is dropped, and the
+/// key starts afresh if it comes back.
+pub struct RateLimitMiddleware {
+    limiters: Limiters,
+    max_keys: usize,
+}
+
+impl RateLimitMiddleware {
+    /// Create a middleware sharing one limiter across all requests
+    pub fn new(limiter: Arc<dyn RateLimiter>) -> Self {
+        Self {
+            limiters: Limiters::Shared(limiter),
+            max_keys: DEFAULT_MAX_KEYS,
+        }
+    }
+
+    /// Create a middleware with a limiter per key, such as per API key or
+    /// identity, created by `factory` on first use
+    pub fn keyed<K, F>(key: K, factory: F) -> Self
+    where
+        K: Fn(&RequestContext) -> String + Send + Sync + 'static,
+        F: Fn() -> Arc<dyn RateLimiter> + Send + Sync + 'static,
+    {
+        Self {
+            limiters: Limiters::Keyed {
+                key: Box::new(key),
+                factory: Box::new(factory),
+                limiters: Mutex::new(KeyedLimiters::default()),
+            },
+            max_keys: DEFAULT_MAX_KEYS,
+        }
+    }
+
+    /// Set how many keys keep their own limiter, 10,000 by default
+    pub fn max_keys(mut self, keys: usize) -> Self {
+        self.max_keys = keys.max(1);
+        self
+    }
+
+    fn limiter(&self, ctx: &RequestContext) -> Arc<dyn RateLimiter> {
+        match &self.limiters {
+            Limiters::Shared(limiter) => limiter.clone(),
+            Limiters::Keyed {
+                key,
+                factory,
+                limiters,
+            } => {
+                let mut keyed = limiters.lock().unwrap_or_else(|e| e.into_inner());
+                let key = key(ctx);
+                if !keyed.limiters.contains_key(&key) && keyed.limiters.len() >= self.max_keys {
+                    let oldest = keyed
+                        .limiters
+                        .iter()
+                        .min_by_key(|(_, (_, used))| *used)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        keyed.limiters.remove(&oldest);
+                    }
+                }
+                keyed.uses += 1;
+                let used = keyed.uses;
+                let entry = keyed
+                    .limiters
+                    .entry(key)
+                    .or_insert_with(|| (factory(), used));
+                entry.1 = used;
+                entry.0.clone()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for RateLimitMiddleware {
+    async fn on_request(&self, ctx: &mut RequestContext) -> InfraResult<Option<HandlerResult>> {
+        let result = self.limiter(ctx).try_acquire().await;
+        let Some(wait) = result.wait_time() else {
+            return Ok(None);
+        };
+        Ok(Some(HandlerResult::from_problem(
+            &ProblemDetails::new(429).with_retry_after(wait),
+        )))
+    }
+}
+
+/// Middleware setting and removing request and response headers
+///
+/// Header names are lowercased.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderRewrite {
+    set_request: Vec<(String, String)>,
+    remove_request: Vec<String>,
+    set_response: Vec<(String, String)>,
+    remove_response: Vec<String>,
+}
+
+impl HeaderRewrite {
+    /// Create a middleware leaving headers unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a request header
+    pub fn set_request(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_request
+            .push((name.into().to_ascii_lowercase(), value.into()));
+        self
+    }
+
+    /// Remove a request header
+    pub fn remove_request(mut self, name: impl Into<String>) -> Self {
+        self.remove_request.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Set a response header
+    pub fn set_response(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_response
+            .push((name.into().to_ascii_lowercase(), value.into()));
+        self
+    }
+
+    /// Remove a response header
+    pub fn remove_response(mut self, name: impl Into<String>) -> Self {
+        self.remove_response.push(name.into().to_ascii_lowercase());
+        self
+    }
+}
+
+/// Apply removals, then additions, to a header map
+fn rewrite(headers: &mut HashMap<String, String>, set: &[(String, String)], remove: &[String]) {
+    headers.retain(|name, _| !remove.iter().any(|r| r.eq_ignore_ascii_case(name)));
+    for (name, value) in set {
+        headers.retain(|existing, _| !existing.eq_ignore_ascii_case(name));
+        headers.insert(name.clone(), value.clone());
+    }
+}
+
+#[async_trait]
+impl Middleware for HeaderRewrite {
+    async fn on_request(&self, ctx: &mut RequestContext) -> InfraResult<Option<HandlerResult>> {
+        rewrite(&mut ctx.headers, &self.set_request, &self.remove_request);
+        Ok(None)
+    }
+
+    async fn on_response(
+        &self,
+        _ctx: &RequestContext,
+        response: &mut HandlerResult,
+    ) -> InfraResult<()> {
+        rewrite(
+            &mut response.headers,
+            &self.set_response,
+            &self.remove_response,
+        );
+        Ok(())
+    }
+}
+
+/// Function transforming a body
+type TransformFn = Box<dyn Fn(Vec<u8>) -> InfraResult<Vec<u8>> + Send + Sync>;
+
+/// Middleware transforming request and response bodies
+///
+/// A failing transform fails the request.
+#[derive(Default)]
+pub struct BodyTransform {
+    request: Option<TransformFn>,
+    response: Option<TransformFn>,
+}
+
+impl BodyTransform {
+    /// Create a middleware leaving bodies unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transform request bodies with `f`
+    pub fn request<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Vec<u8>) -> InfraResult<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.request = Some(Box::new(f));
+        self
+    }
+
+    /// Transform response bodies with `f`
+    pub fn response<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Vec<u8>) -> InfraResult<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.response = Some(Box::new(f));
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for BodyTransform {
+    async fn on_request(&self, ctx: &mut RequestContext) -> InfraResult<Option<HandlerResult>> {
+        if let Some(transform) = &self.request {
+            ctx.body = transform(std::mem::take(&mut ctx.body))?;
+        }
+        Ok(None)
+    }
+
+    async fn on_response(
+        &self,
+        _ctx: &RequestContext,
+        response: &mut HandlerResult,
+    ) -> InfraResult<()> {
+        if let Some(transform) = &self.response {
+            response.body = transform(std::mem::take(&mut response.body))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::GatewayBuilder;
+    use crate::handler::Handler;
+    use crate::route::{Method, RouteBuilder};
+    use infra_auth::{Identity, IdentityProvider};
+    use infra_errors::InfraError;
+    use infra_rate_limit::{RateLimitConfig, TokenBucket};
+    use std::time::Duration;
+
+    struct Tokens;
+
+    impl IdentityProvider for Tokens {
+        fn verify(&self, token: &str) -> InfraResult<Identity> {
+            match token {
+                "ada-token" => Ok(Identity::user("ada")),
+                _ => Err(InfraError::validation("Unknown token")),
+            }
+        }
+    }
+
+    /// Echoes the caller, the `x-tenant` header and the body
+    struct Whoami;
+
+    #[async_trait]
+    impl Handler for Whoami {
+        async fn handle(&self, ctx: RequestContext) -> InfraResult<HandlerResult> {
+            let caller = ctx.identity.as_ref().map_or("anonymous", |i| i.id.as_str());
+            let tenant = ctx.header("x-tenant").map_or("-", String::as_str);
+            let body = String::from_utf8_lossy(&ctx.body);
+            Ok(HandlerResult::ok(format!("{caller} {tenant} {body}"))
+                .with_header("server", "backend"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_pipeline() {
+        let limiter =
+            TokenBucket::new(RateLimitConfig::new(0.01, 2, Duration::from_secs(60)).unwrap());
+        let gateway = GatewayBuilder::new()
+            .middleware(RateLimitMiddleware::new(Arc::new(limiter)))
+            .middleware(AuthMiddleware::new(Arc::new(Tokens)))
+            .middleware(
+                HeaderRewrite::new()
+                    .set_request("X-Tenant", "acme")
+                    .remove_response("server"),
+            )
+            .route(
+                RouteBuilder::new("/whoami")
+                    .post()
+                    .middleware(BodyTransform::new().request(|body| {
+                        Ok(String::from_utf8_lossy(&body).to_uppercase().into_bytes())
+                    }))
+                    .handler(Whoami)
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/whoami")
+            .with_header("authorization", "Bearer ada-token")
+            .with_body("hello");
+        let response = gateway.route(Method::Post, "/whoami", ctx).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ada acme HELLO");
+        assert!(!response.headers.contains_key("server"));
+
+        let ctx = RequestContext::new("/whoami").with_header("authorization", "Bearer bad");
+        let response = gateway.route(Method::Post, "/whoami", ctx).await.unwrap();
+        assert_eq!(response.status, 401);
+
+        let ctx = RequestContext::new("/whoami");
+        let response = gateway.route(Method::Post, "/whoami", ctx).await.unwrap();
+        assert_eq!(response.status, 429);
+        assert!(response.headers.contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn test_auth_header_case_and_malformed_tokens() {
+        let gateway = GatewayBuilder::new()
+            .middleware(AuthMiddleware::new(Arc::new(Tokens)).allow_anonymous())
+            .route(RouteBuilder::new("/whoami").handler(Whoami).build())
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/whoami").with_header("Authorization", "bearer ada-token");
+        let response = gateway.route(Method::Get, "/whoami", ctx).await.unwrap();
+        assert_eq!(response.body, b"ada - ");
+
+        let ctx = RequestContext::new("/whoami").with_header("authorization", "Bearer ada token");
+        let response = gateway.route(Method::Get, "/whoami", ctx).await.unwrap();
+        assert_eq!(response.status, 401);
+    }
+
+    #[tokio::test]
+    async fn test_keyed_limiters_are_bounded() {
+        let gateway = GatewayBuilder::new()
+            .middleware(
+                RateLimitMiddleware::keyed(
+                    |ctx| ctx.header("x-key").cloned().unwrap_or_default(),
+                    || {
+                        Arc::new(TokenBucket::new(
+                            RateLimitConfig::new(0.01, 1, Duration::from_secs(60)).unwrap(),
+                        ))
+                    },
+                )
+                .max_keys(2),
+            )
+            .route(RouteBuilder::new("/whoami").handler(Whoami).build())
+            .build()
+            .unwrap();
+        let status = |key: &'static str| {
+            let gateway = &gateway;
+            async move {
+                let ctx = RequestContext::new("/whoami").with_header("x-key", key);
+                gateway
+                    .route(Method::Get, "/whoami", ctx)
+                    .await
+                    .unwrap()
+                    .status
+            }
+        };
+
+        assert_eq!(status("a").await, 200);
+        assert_eq!(status("b").await, 200);
+        assert_eq!(status("a").await, 429);
+        // "b" is the least recently used key, so its limiter makes room
+        assert_eq!(status("c").await, 200);
+        assert_eq!(status("a").await, 429);
+        assert_eq!(status("b").await, 200);
+    }
+
+    #[tokio::test]
+    async fn test_errors_pass_back_through_middleware() {
+        struct Failing;
+
+        #[async_trait]
+        impl Handler for Failing {
+            async fn handle(&self, _ctx: RequestContext) -> InfraResult<HandlerResult> {
+                Err(InfraError::validation("Missing model"))
+            }
+        }
+
+        let gateway = GatewayBuilder::new()
+            .middleware(HeaderRewrite::new().set_response("x-gateway", "edge"))
+            .route(RouteBuilder::new("/fail").handler(Failing).build())
+            .build()
+            .unwrap();
+
+        let ctx = RequestContext::new("/fail");
+        let response = gateway.route(Method::Get, "/fail", ctx).await.unwrap();
+        assert_eq!(response.status, 400);
+        assert_eq!(response.headers["x-gateway"], "edge");
+    }
+}
@@ -0,0 +1,285 @@
+//! Per-backend circuit breaking and outlier ejection.
+
+use infra_otel::{Gauge, MetricsRegistry};
+use infra_retry::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Thresholds for ejecting misbehaving backends
+///
+/// A backend is ejected from load balancing for `ejection_duration` after
+/// `consecutive_errors` failed requests in a row (5xx responses or no
+/// response at all), or after `consecutive_slow` requests in a row slower
+/// than `max_latency`. At most `max_ejection_percent` of the backends are
+/// ejected at once, so an outage of every backend does not leave nothing to
+/// route to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlierDetection {
+    /// Consecutive failures before ejecting
+    pub consecutive_errors: u32,
+    /// Latency above which a request counts as slow
+    pub max_latency: Option<Duration>,
+    /// Consecutive slow requests before ejecting
+    pub consecutive_slow: u32,
+    /// How long an ejected backend is left out
+    pub ejection_duration: Duration,
+    /// Maximum percentage of backends ejected at once
+    pub max_ejection_percent: u32,
+}
+
+impl OutlierDetection {
+    /// Create detection with default thresholds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the consecutive failures before ejecting
+    pub fn consecutive_errors(mut self, count: u32) -> Self {
+        self.consecutive_errors = count;
+        self
+    }
+
+    /// Eject backends after `consecutive` requests slower than `latency`
+    pub fn max_latency(mut self, latency: Duration, consecutive: u32) -> Self {
+        self.max_latency = Some(latency);
+        self.consecutive_slow = consecutive;
+        self
+    }
+
+    /// Set how long an ejected backend is left out
+    pub fn ejection_duration(mut self, duration: Duration) -> Self {
+        self.ejection_duration = duration;
+        self
+    }
+
+    /// Set the maximum percentage of backends ejected at once
+    pub fn max_ejection_percent(mut self, percent: u32) -> Self {
+        self.max_ejection_percent = percent;
+        self
+    }
+}
+
+impl Default for OutlierDetection {
+    fn default() -> Self {
+        Self {
+            consecutive_errors: 5,
+            max_latency: None,
+            consecutive_slow: 5,
+            ejection_duration: Duration::from_secs(30),
+            max_ejection_percent: 50,
+        }
+    }
+}
+
+/// Why a backend was ejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EjectionReason {
+    /// Too many consecutive failures
+    ConsecutiveErrors,
+    /// Too many consecutive slow requests
+    Latency,
+}
+
+impl EjectionReason {
+    /// Get the label recorded on metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConsecutiveErrors => "consecutive_errors",
+            Self::Latency => "latency",
+        }
+    }
+}
+
+/// Health of one backend
+struct BackendHealth {
+    breaker: Option<CircuitBreaker>,
+    errors: u32,
+    slow: u32,
+    ejected_until: Option<Instant>,
+    metrics: Option<HealthMetrics>,
+}
+
+/// Series exported for a backend
+struct HealthMetrics {
+    circuit: Arc<Gauge>,
+    ejected: Arc<Gauge>,
+}
+
+/// Value of the circuit state gauge
+fn circuit_value(state: CircuitState) -> i64 {
+    match state {
+        CircuitState::Closed => 0,
+        CircuitState::HalfOpen => 1,
+        CircuitState::Open => 2,
+    }
+}
+
+/// Breakers and ejections of a load balancer's backends
+#[derive(Default)]
+pub(crate) struct HealthTracker {
+    pub(crate) breaker: Option<CircuitBreakerConfig>,
+    pub(crate) detection: Option<OutlierDetection>,
+    pub(crate) metrics: Option<Arc<MetricsRegistry>>,
+    backends: Mutex<HashMap<String, BackendHealth>>,
+}
+
+impl HealthTracker {
+    /// Check if a backend is outside its ejection period
+    pub(crate) fn available(&self, url: &str) -> bool {
+        let mut backends = self.lock();
+        let Some(health) = backends.get_mut(url) else {
+            return true;
+        };
+        match health.ejected_until {
+            Some(until) if Instant::now() < until => false,
+            Some(_) => {
+                health.ejected_until = None;
+                if let Some(metrics) = &health.metrics {
+                    metrics.ejected.set(0);
+                }
+                tracing::info!(backend = url, "Backend returned from ejection");
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Check if a backend's breaker lets a request through
+    pub(crate) fn admit(&self, url: &str) -> bool {
+        if self.breaker.is_none() {
+            return true;
+        }
+        let mut backends = self.lock();
+        let health = self.health(&mut backends, url);
+        let Some(breaker) = &health.breaker else {
+            return true;
+        };
+        let allowed = breaker.allow();
+        if let Some(metrics) = &health.metrics {
+            metrics.circuit.set(circuit_value(breaker.state()));
+        }
+        allowed
+    }
+
+    /// Record the outcome of a request to a backend among `total`
+    pub(crate) fn record(&self, url: &str, failed: bool, latency: Option<Duration>, total: usize) {
+        let mut backends = self.lock();
+        let now = Instant::now();
+        let ejected = backends
+            .values()
+            .filter(|h| h.ejected_until.is_some_and(|until| now < until))
+            .count();
+
+        let health = self.health(&mut backends, url);
+        if let Some(breaker) = &health.breaker {
+            if failed {
+                breaker.record_failure();
+            } else {
+                breaker.record_success();
+            }
+            if let Some(metrics) = &health.metrics {
+                metrics.circuit.set(circuit_value(breaker.state()));
+            }
+        }
+
+        let Some(detection) = &self.detection else {
+            return;
+        };
+        health.errors = if failed { health.errors + 1 } else { 0 };
+        let slow = detection
+            .max_latency
+            .zip(latency)
+            .is_some_and(|(max, latency)| latency > max);
+        health.slow = if slow { health.slow + 1 } else { 0 };
+
+        let reason = if health.errors >= detection.consecutive_errors {
+            EjectionReason::ConsecutiveErrors
+        } else if detection.max_latency.is_some() && health.slow >= detection.consecutive_slow {
+            EjectionReason::Latency
+        } else {
+            return;
+        };
+        if health.ejected_until.is_some_and(|until| now < until) {
+            return;
+        }
+        if (ejected + 1) * 100 > total * detection.max_ejection_percent as usize {
+            tracing::warn!(
+                backend = url,
+                reason = reason.as_str(),
+                "Ejection limit reached"
+            );
+            return;
+        }
+
+        health.errors = 0;
+        health.slow = 0;
+        health.ejected_until = Some(now + detection.ejection_duration);
+        if let Some(metrics) = &health.metrics {
+            metrics.ejected.set(1);
+        }
+        if let Some(registry) = &self.metrics {
+            registry
+                .counter_with(
+                    "gateway_backend_ejections_total",
+                    &[("backend", url), ("reason", reason.as_str())],
+                )
+                .inc();
+        }
+        tracing::warn!(backend = url, reason = reason.as_str(), "Backend ejected");
+    }
+
+    /// Forget the breaker and ejection of a removed backend
+    pub(crate) fn forget(&self, url: &str) {
+        if let Some(health) = self.lock().remove(url) {
+            if let Some(metrics) = health.metrics {
+                metrics.circuit.set(circuit_value(CircuitState::Closed));
+                metrics.ejected.set(0);
+            }
+        }
+    }
+
+    /// Check if a backend is ejected
+    pub(crate) fn is_ejected(&self, url: &str) -> bool {
+        let backends = self.lock();
+        backends
+            .get(url)
+            .and_then(|h| h.ejected_until)
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Get the state of a backend's breaker
+    pub(crate) fn circuit_state(&self, url: &str) -> Option<CircuitState> {
+        self.breaker.as_ref()?;
+        let backends = self.lock();
+        let state = backends.get(url).and_then(|h| h.breaker.as_ref());
+        Some(state.map_or(CircuitState::Closed, CircuitBreaker::state))
+    }
+
+    fn health<'a>(
+        &self,
+        backends: &'a mut HashMap<String, BackendHealth>,
+        url: &str,
+    ) -> &'a mut BackendHealth {
+        backends
+            .entry(url.to_string())
+            .or_insert_with(|| BackendHealth {
+                breaker: self
+                    .breaker
+                    .clone()
+                    .map(|config| CircuitBreaker::new(url, config)),
+                errors: 0,
+                slow: 0,
+                ejected_until: None,
+                metrics: self.metrics.as_ref().map(|registry| HealthMetrics {
+                    circuit: registry
+                        .gauge_with("gateway_backend_circuit_state", &[("backend", url)]),
+                    ejected: registry.gauge_with("gateway_backend_ejected", &[("backend", url)]),
+                }),
+            })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, BackendHealth>> {
+        self.backends.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
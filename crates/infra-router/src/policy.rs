@@ -0,0 +1,188 @@
+//! Per-route retry, timeout and shadowing policies.
+
+use infra_retry::{ExponentialBackoff, RetryBudget};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Traffic policy of a route
+///
+/// Deserializes from configuration, so policies can be loaded with
+/// infra-config and applied to named routes with
+/// [`GatewayBuilder::route_policies`](crate::GatewayBuilder::route_policies):
+///
+/// ```toml
+/// [chat]
+/// timeout_ms = 20000
+/// retry = { max_retries = 2, budget_ratio = 0.1 }
+/// shadow = { route = "chat-canary", percent = 5.0 }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutePolicy {
+    /// Request timeout, overriding the gateway's
+    pub timeout_ms: Option<u64>,
+    /// Retries of failed requests
+    pub retry: Option<RouteRetry>,
+    /// Mirroring of requests to another route
+    pub shadow: Option<Shadow>,
+}
+
+impl RoutePolicy {
+    /// Create a policy with the gateway's defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the timeout
+    pub fn timeout_ms(mut self, ms: u64) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+
+    /// Retry failed requests
+    pub fn retry(mut self, retry: RouteRetry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Mirror `percent` of requests to the route named `route`
+    pub fn shadow(mut self, route: impl Into<String>, percent: f64) -> Self {
+        self.shadow = Some(Shadow {
+            route: route.into(),
+            percent,
+        });
+        self
+    }
+}
+
+/// Retries of a route's failed requests
+///
+/// Requests are retried when the handler fails with a retryable error or
+/// responds with one of the `retry_on` statuses, including the 504 of a
+/// timed out attempt. Requests that aren't idempotent, by method or
+/// `Idempotency-Key` header, are only retried after failures that certainly
+/// had no effect, such as a 503. Retries back off exponentially and draw
+/// from a budget shared by the route, so a failing backend is not flooded
+/// with retries.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RouteRetry {
+    /// Maximum number of retries per request
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_backoff_ms: u64,
+    /// Maximum delay between retries
+    pub max_backoff_ms: u64,
+    /// Fraction of successful requests that may be retried
+    pub budget_ratio: f64,
+    /// Response statuses to retry
+    pub retry_on: Vec<u16>,
+}
+
+impl RouteRetry {
+    /// Create a policy retrying up to `max_retries` times
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Set the initial and maximum delay between retries
+    pub fn backoff_ms(mut self, initial: u64, max: u64) -> Self {
+        self.initial_backoff_ms = initial;
+        self.max_backoff_ms = max;
+        self
+    }
+
+    /// Set the fraction of successful requests that may be retried
+    pub fn budget_ratio(mut self, ratio: f64) -> Self {
+        self.budget_ratio = ratio;
+        self
+    }
+
+    /// Set the response statuses to retry
+    pub fn retry_on(mut self, statuses: Vec<u16>) -> Self {
+        self.retry_on = statuses;
+        self
+    }
+
+    pub(crate) fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff::new()
+            .with_initial_delay(Duration::from_millis(self.initial_backoff_ms))
+            .with_max_delay(Duration::from_millis(self.max_backoff_ms))
+            .with_max_attempts(self.max_retries)
+    }
+
+    pub(crate) fn budget(&self) -> RetryBudget {
+        RetryBudget::new(self.budget_ratio)
+    }
+}
+
+impl Default for RouteRetry {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff_ms: 50,
+            max_backoff_ms: 1000,
+            budget_ratio: 0.2,
+            retry_on: vec![502, 503, 504],
+        }
+    }
+}
+
+/// Mirroring of a route's requests to another route
+///
+/// Mirrored requests carry an `x-shadow: true` header and run in the
+/// background; their responses and errors are discarded. Point the target
+/// route at a canary backend to try it on live traffic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shadow {
+    /// Name of the route receiving mirrored requests
+    pub route: String,
+    /// Percentage of requests to mirror
+    #[serde(default = "Shadow::all")]
+    pub percent: f64,
+}
+
+impl Shadow {
+    fn all() -> f64 {
+        100.0
+    }
+}
+
+/// Policies of named routes, as loaded from configuration
+pub type RoutePolicies = HashMap<String, RoutePolicy>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies_from_config() {
+        let config = r#"
+            [chat]
+            timeout_ms = 20000
+            retry = { max_retries = 3, budget_ratio = 0.1 }
+            shadow = { route = "chat-canary", percent = 5.0 }
+
+            [embeddings]
+            retry = {}
+        "#;
+        let policies: RoutePolicies =
+            infra_config::parse(config, infra_config::ConfigFormat::Toml).unwrap();
+
+        assert_eq!(
+            policies["chat"],
+            RoutePolicy::new()
+                .timeout_ms(20000)
+                .retry(RouteRetry::new(3).budget_ratio(0.1))
+                .shadow("chat-canary", 5.0)
+        );
+        assert_eq!(
+            policies["embeddings"],
+            RoutePolicy::new().retry(RouteRetry::default())
+        );
+    }
+}
@@ -2,6 +2,9 @@
 
 use crate::handler::Handler;
 use crate::matcher::{MatchResult, PathMatcher};
+use crate::middleware::Middleware;
+use crate::policy::RoutePolicy;
+use infra_retry::RetryBudget;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -19,6 +22,26 @@ pub enum Method {
 }
 
 impl Method {
+    /// Get the method name, or `*` for any method
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Patch => "PATCH",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Any => "*",
+        }
+    }
+
+    /// Check if repeating a request with this method has no further
+    /// effect, so it can be retried safely
+    pub fn is_idempotent(&self) -> bool {
+        !matches!(self, Method::Post | Method::Patch | Method::Any)
+    }
+
     /// Check if this method matches another
     pub fn matches(&self, other: &Method) -> bool {
         *self == Method::Any || *self == *other
@@ -36,9 +59,15 @@ pub struct Route {
     /// Handler
     handler: Option<Arc<dyn Handler>>,
     /// Middleware
-    middleware: Vec<Arc<dyn Handler>>,
+    middleware: Vec<Arc<dyn Middleware>>,
     /// Route name
     name: Option<String>,
+    /// Traffic policy
+    policy: RoutePolicy,
+    /// Retry budget shared by the route's requests
+    budget: Option<Arc<RetryBudget>>,
+    /// Traffic split choosing the backend of the route's requests
+    split: Option<String>,
 }
 
 impl Route {
@@ -53,6 +82,9 @@ impl Route {
             handler: None,
             middleware: Vec::new(),
             name: None,
+            policy: RoutePolicy::default(),
+            budget: None,
+            split: None,
         }
     }
 
@@ -66,6 +98,32 @@ impl Route {
         self.method
     }
 
+    /// Get the name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Get the name of the traffic split choosing the route's backends
+    pub fn split(&self) -> Option<&str> {
+        self.split.as_deref()
+    }
+
+    /// Get the traffic policy
+    pub fn policy(&self) -> &RoutePolicy {
+        &self.policy
+    }
+
+    /// Set the traffic policy
+    pub fn set_policy(&mut self, policy: RoutePolicy) {
+        self.budget = policy.retry.as_ref().map(|retry| Arc::new(retry.budget()));
+        self.policy = policy;
+    }
+
+    /// Get the retry budget
+    pub(crate) fn budget(&self) -> Option<Arc<RetryBudget>> {
+        self.budget.clone()
+    }
+
     /// Match a path
     pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
         self.matcher.match_path(path)
@@ -83,6 +141,11 @@ impl Route {
     pub fn handler(&self) -> Option<&Arc<dyn Handler>> {
         self.handler.as_ref()
     }
+
+    /// Get the middleware
+    pub fn middleware(&self) -> &[Arc<dyn Middleware>] {
+        &self.middleware
+    }
 }
 
 /// Route builder
@@ -130,8 +193,8 @@ impl RouteBuilder {
         self
     }
 
-    /// Add middleware
-    pub fn middleware<H: Handler + 'static>(mut self, middleware: H) -> Self {
+    /// Add middleware, run after the gateway's
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
         self.route.middleware.push(Arc::new(middleware));
         self
     }
@@ -142,6 +205,19 @@ impl RouteBuilder {
         self
     }
 
+    /// Set the traffic policy
+    pub fn policy(mut self, policy: RoutePolicy) -> Self {
+        self.route.set_policy(policy);
+        self
+    }
+
+    /// Send requests through the gateway's traffic split named `split`,
+    /// see [`TrafficSplit`](crate::TrafficSplit)
+    pub fn split(mut self, split: impl Into<String>) -> Self {
+        self.route.split = Some(split.into());
+        self
+    }
+
     /// Build the route
     pub fn build(self) -> Route {
         self.route
@@ -0,0 +1,475 @@
+//! Weighted traffic splitting across backend groups.
+
+use crate::balancer::{Backend, LoadBalancer};
+use infra_errors::{InfraError, InfraResult};
+use infra_otel::{Counter, Gauge, MetricsRegistry};
+use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Request header carrying the group a split chose
+pub const SPLIT_GROUP_HEADER: &str = "x-split-group";
+
+/// Request header carrying the URL of the backend a split chose
+pub const SPLIT_BACKEND_URL_HEADER: &str = "x-split-backend-url";
+
+/// Number of buckets a success rate window is divided into
+const WINDOW_BUCKETS: u32 = 10;
+
+/// Group of backends receiving a share of traffic
+struct Group {
+    name: String,
+    balancer: Arc<LoadBalancer>,
+    outcomes: Mutex<Outcomes>,
+    metrics: Option<GroupMetrics>,
+}
+
+/// Request outcomes over a sliding window, counted in buckets
+#[derive(Default)]
+struct Outcomes {
+    buckets: VecDeque<Bucket>,
+}
+
+struct Bucket {
+    start: Instant,
+    successes: u64,
+    failures: u64,
+}
+
+impl Outcomes {
+    fn record(&mut self, success: bool, window: Duration) {
+        let now = Instant::now();
+        self.expire(now, window);
+        let bucket_len = window / WINDOW_BUCKETS;
+        let bucket = match self.buckets.back_mut() {
+            Some(bucket) if now < bucket.start + bucket_len => bucket,
+            _ => {
+                self.buckets.push_back(Bucket {
+                    start: now,
+                    successes: 0,
+                    failures: 0,
+                });
+                self.buckets.back_mut().expect("bucket was just pushed")
+            }
+        };
+        if success {
+            bucket.successes += 1;
+        } else {
+            bucket.failures += 1;
+        }
+    }
+
+    fn totals(&mut self, window: Duration) -> (u64, u64) {
+        self.expire(Instant::now(), window);
+        self.buckets
+            .iter()
+            .fold((0, 0), |(successes, failures), bucket| {
+                (successes + bucket.successes, failures + bucket.failures)
+            })
+    }
+
+    fn expire(&mut self, now: Instant, window: Duration) {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|bucket| bucket.start + window <= now)
+        {
+            self.buckets.pop_front();
+        }
+    }
+}
+
+/// Series exported for a group
+struct GroupMetrics {
+    successes: Arc<Counter>,
+    failures: Arc<Counter>,
+    weight: Arc<Gauge>,
+}
+
+impl GroupMetrics {
+    fn new(registry: &MetricsRegistry, split: &str, group: &Group, weight: u32) -> Self {
+        let labels = [("split", split), ("group", group.name.as_str())];
+        let outcome = |outcome| {
+            registry.counter_with(
+                "gateway_split_requests_total",
+                &[labels[0], labels[1], ("outcome", outcome)],
+            )
+        };
+        let metrics = Self {
+            successes: outcome("success"),
+            failures: outcome("failure"),
+            weight: registry.gauge_with("gateway_split_weight", &labels),
+        };
+        metrics.weight.set(i64::from(weight));
+        metrics
+    }
+}
+
+/// Backend chosen for a request
+#[derive(Debug, Clone)]
+pub struct SplitTarget {
+    /// Name of the chosen group
+    pub group: String,
+    /// Backend within the group
+    pub backend: Backend,
+}
+
+/// Outcomes of the requests recently sent to a group
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStats {
+    /// Group name
+    pub name: String,
+    /// Current weight
+    pub weight: u32,
+    /// Successful requests within the split's window
+    pub successes: u64,
+    /// Failed requests within the split's window
+    pub failures: u64,
+}
+
+impl GroupStats {
+    /// Get the number of requests with a recorded outcome
+    pub fn requests(&self) -> u64 {
+        self.successes + self.failures
+    }
+
+    /// Get the fraction of successful requests, if any were recorded
+    pub fn success_rate(&self) -> Option<f64> {
+        let requests = self.requests();
+        (requests > 0).then(|| self.successes as f64 / requests as f64)
+    }
+}
+
+/// Weighted split of traffic across backend groups
+///
+/// Each request picks a group with probability proportional to its weight,
+/// then a backend from the group's balancer. Weights are typically
+/// percentages, such as 95 for `stable` and 5 for `canary`, and can be
+/// adjusted at runtime to advance or roll back a canary; weights change
+/// together, so no request sees a partial update. Report each request's
+/// outcome with [`record`](Self::record) to track per-group success rates
+/// over a sliding window, the last minute by default.
+///
+/// Routes send their requests through a split registered with the gateway
+/// by naming it with [`RouteBuilder::split`](crate::RouteBuilder::split);
+/// the gateway then picks the target, passes it to the handler in the
+/// [`SPLIT_GROUP_HEADER`] and [`SPLIT_BACKEND_URL_HEADER`] request headers
+/// and records the outcome.
+///
+/// ```ignore
+/// let split = TrafficSplit::new("chat")
+///     .group("stable", 95, stable)
+///     .group("canary", 5, canary);
+///
+/// let target = split.next().await?;
+/// let ok = forward(&target.backend, request).await.is_ok();
+/// split.record(&target.group, ok);
+///
+/// if split.stats_for("canary").and_then(|s| s.success_rate()) >= Some(0.99) {
+///     split.set_weights(&[("stable", 50), ("canary", 50)])?;
+/// }
+/// ```
+pub struct TrafficSplit {
+    name: String,
+    groups: Vec<Group>,
+    /// Weights of the groups, by index
+    weights: RwLock<Vec<u32>>,
+    window: Duration,
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+impl TrafficSplit {
+    /// Create a split without groups
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            groups: Vec::new(),
+            weights: RwLock::new(Vec::new()),
+            window: Duration::from_secs(60),
+            metrics: None,
+        }
+    }
+
+    /// Add a group of backends with a weight
+    pub fn group(mut self, name: impl Into<String>, weight: u32, balancer: LoadBalancer) -> Self {
+        let mut group = Group {
+            name: name.into(),
+            balancer: Arc::new(balancer),
+            outcomes: Mutex::new(Outcomes::default()),
+            metrics: None,
+        };
+        if let Some(registry) = &self.metrics {
+            group.metrics = Some(GroupMetrics::new(registry, &self.name, &group, weight));
+        }
+        self.groups.push(group);
+        self.weights
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(weight);
+        self
+    }
+
+    /// Set how long recorded outcomes count towards success rates
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window.max(Duration::from_millis(WINDOW_BUCKETS.into()));
+        self
+    }
+
+    /// Export per-group request outcomes and weights to `registry`
+    pub fn with_metrics(mut self, registry: Arc<MetricsRegistry>) -> Self {
+        let weights = self.weights.get_mut().unwrap_or_else(|e| e.into_inner());
+        for (group, &weight) in self.groups.iter_mut().zip(weights.iter()) {
+            group.metrics = Some(GroupMetrics::new(&registry, &self.name, group, weight));
+        }
+        self.metrics = Some(registry);
+        self
+    }
+
+    /// Get the name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the balancer of a group
+    pub fn balancer(&self, group: &str) -> Option<Arc<LoadBalancer>> {
+        self.find(group).map(|g| g.balancer.clone())
+    }
+
+    /// Pick a group by weight, then a backend within it
+    ///
+    /// Fails if all weights are zero or the chosen group has no healthy
+    /// backend.
+    pub async fn next(&self) -> InfraResult<SplitTarget> {
+        let weights = self
+            .weights
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+        if total == 0 {
+            return Err(InfraError::External {
+                service: "traffic_split".to_string(),
+                operation: "next".to_string(),
+                message: format!("Traffic split {} has no weighted groups", self.name),
+                retry_after: None,
+                context: None,
+                source: None,
+            });
+        }
+
+        let mut point = rand::thread_rng().gen_range(0..total);
+        let mut chosen = &self.groups[0];
+        for (group, &weight) in self.groups.iter().zip(&weights) {
+            if point < u64::from(weight) {
+                chosen = group;
+                break;
+            }
+            point -= u64::from(weight);
+        }
+
+        Ok(SplitTarget {
+            group: chosen.name.clone(),
+            backend: chosen.balancer.next().await?,
+        })
+    }
+
+    /// Record the outcome of a request sent to a group
+    pub fn record(&self, group: &str, success: bool) {
+        let Some(group) = self.find(group) else {
+            return;
+        };
+        group
+            .outcomes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(success, self.window);
+        let metrics = group.metrics.as_ref();
+        let counter = if success {
+            metrics.map(|m| &m.successes)
+        } else {
+            metrics.map(|m| &m.failures)
+        };
+        if let Some(counter) = counter {
+            counter.inc();
+        }
+    }
+
+    /// Set the weight of a group
+    pub fn set_weight(&self, group: &str, weight: u32) -> InfraResult<()> {
+        self.set_weights(&[(group, weight)])
+    }
+
+    /// Set the weights of several groups at once
+    ///
+    /// Requests pick groups by either the old or the new weights, never a
+    /// mix. Fails without changing any weight if a group is unknown.
+    pub fn set_weights(&self, weights: &[(&str, u32)]) -> InfraResult<()> {
+        let indices = weights
+            .iter()
+            .map(|&(name, weight)| {
+                let index = self.position(name).ok_or_else(|| {
+                    InfraError::validation(format!(
+                        "Traffic split {} has no group {name}",
+                        self.name
+                    ))
+                })?;
+                Ok((index, weight))
+            })
+            .collect::<InfraResult<Vec<_>>>()?;
+
+        let mut current = self.weights.write().unwrap_or_else(|e| e.into_inner());
+        for (index, weight) in indices {
+            current[index] = weight;
+            let group = &self.groups[index];
+            if let Some(metrics) = &group.metrics {
+                metrics.weight.set(i64::from(weight));
+            }
+            tracing::info!(split = %self.name, group = %group.name, weight, "Traffic weight changed");
+        }
+        Ok(())
+    }
+
+    /// Send all traffic to one group, as when completing or rolling back a
+    /// canary
+    pub fn promote(&self, group: &str) -> InfraResult<()> {
+        if self.find(group).is_none() {
+            return Err(InfraError::validation(format!(
+                "Traffic split {} has no group {group}",
+                self.name
+            )));
+        }
+        let weights: Vec<(&str, u32)> = self
+            .groups
+            .iter()
+            .map(|g| (g.name.as_str(), if g.name == group { 100 } else { 0 }))
+            .collect();
+        self.set_weights(&weights)
+    }
+
+    /// Get the stats of every group
+    pub fn stats(&self) -> Vec<GroupStats> {
+        (0..self.groups.len())
+            .map(|index| self.group_stats(index))
+            .collect()
+    }
+
+    /// Get the stats of a group
+    pub fn stats_for(&self, group: &str) -> Option<GroupStats> {
+        self.position(group).map(|index| self.group_stats(index))
+    }
+
+    /// Forget recorded outcomes, as when starting a new rollout stage
+    ///
+    /// Exported counters keep counting.
+    pub fn reset_stats(&self) {
+        for group in &self.groups {
+            *group.outcomes.lock().unwrap_or_else(|e| e.into_inner()) = Outcomes::default();
+        }
+    }
+
+    fn find(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.groups.iter().position(|g| g.name == name)
+    }
+
+    fn group_stats(&self, index: usize) -> GroupStats {
+        let group = &self.groups[index];
+        let (successes, failures) = group
+            .outcomes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .totals(self.window);
+        GroupStats {
+            name: group.name.clone(),
+            weight: self.weights.read().unwrap_or_else(|e| e.into_inner())[index],
+            successes,
+            failures,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn balancer(url: &str) -> LoadBalancer {
+        let balancer = LoadBalancer::round_robin();
+        balancer.add_backend(Backend::new(url)).await;
+        balancer
+    }
+
+    #[tokio::test]
+    async fn test_weighted_split() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let split = TrafficSplit::new("chat")
+            .group("stable", 90, balancer("http://stable").await)
+            .group("canary", 10, balancer("http://canary").await)
+            .with_metrics(registry.clone());
+
+        let mut canary = 0;
+        for _ in 0..2000 {
+            let target = split.next().await.unwrap();
+            if target.group == "canary" {
+                assert_eq!(target.backend.url, "http://canary");
+                canary += 1;
+            }
+            split.record(&target.group, target.group == "stable");
+        }
+        assert!((100..300).contains(&canary), "canary got {canary}");
+
+        let stable = split.stats_for("stable").unwrap();
+        assert_eq!(stable.success_rate(), Some(1.0));
+        assert_eq!(split.stats_for("canary").unwrap().success_rate(), Some(0.0));
+        let failures = registry.counter_with(
+            "gateway_split_requests_total",
+            &[
+                ("split", "chat"),
+                ("group", "canary"),
+                ("outcome", "failure"),
+            ],
+        );
+        assert_eq!(failures.get(), canary);
+
+        split.promote("stable").unwrap();
+        for _ in 0..50 {
+            assert_eq!(split.next().await.unwrap().group, "stable");
+        }
+        assert!(split.set_weights(&[("stable", 50), ("beta", 50)]).is_err());
+        assert_eq!(split.stats_for("stable").unwrap().weight, 100);
+
+        split.set_weights(&[("stable", 0), ("canary", 0)]).unwrap();
+        assert!(split.next().await.is_err());
+
+        split.reset_stats();
+        assert_eq!(split.stats_for("stable").unwrap().success_rate(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_success_rate_window() {
+        let split = TrafficSplit::new("chat")
+            .group("canary", 100, balancer("http://canary").await)
+            .with_window(Duration::from_secs(10));
+
+        for _ in 0..5 {
+            split.record("canary", false);
+        }
+        tokio::time::advance(Duration::from_secs(6)).await;
+        for _ in 0..5 {
+            split.record("canary", true);
+        }
+        assert_eq!(split.stats_for("canary").unwrap().success_rate(), Some(0.5));
+
+        // The early failures age out of the window
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let stats = split.stats_for("canary").unwrap();
+        assert_eq!((stats.successes, stats.failures), (5, 0));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(split.stats_for("canary").unwrap().requests(), 0);
+    }
+}
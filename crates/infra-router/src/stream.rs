@@ -0,0 +1,600 @@
+//! Streaming (SSE) and WebSocket proxying.
+
+use infra_otel::MetricsRegistry;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Streamed response body
+///
+/// Clones share the underlying channel; whichever writes the response
+/// [takes](Self::take) it.
+#[derive(Clone)]
+pub struct BodyStream(Arc<Mutex<Option<mpsc::Receiver<Vec<u8>>>>>);
+
+impl BodyStream {
+    /// Create a body streaming the chunks received on `rx`
+    pub fn new(rx: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self(Arc::new(Mutex::new(Some(rx))))
+    }
+
+    /// Create a body and the sender feeding it, buffering `buffer` chunks
+    pub fn channel(buffer: usize) -> (mpsc::Sender<Vec<u8>>, Self) {
+        let (tx, rx) = mpsc::channel(buffer.max(1));
+        (tx, Self::new(rx))
+    }
+
+    /// Take the chunk receiver, if no one has yet
+    pub fn take(&self) -> Option<mpsc::Receiver<Vec<u8>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodyStream")
+    }
+}
+
+/// Server-sent event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event type
+    pub event: Option<String>,
+    /// Event data
+    pub data: String,
+    /// Event ID
+    pub id: Option<String>,
+    /// Reconnection delay in milliseconds
+    pub retry: Option<u64>,
+}
+
+impl SseEvent {
+    /// Create an event with data
+    pub fn new(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the event type
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set the event ID
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Encode the event in the `text/event-stream` format
+    ///
+    /// Data is sent as one `data:` field per line, whether lines end in
+    /// `\n`, `\r\n` or `\r`. Line breaks in the event type and ID are
+    /// dropped, so they can't end the field early and inject another.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = String::new();
+        if let Some(event) = &self.event {
+            out.push_str(&format!("event: {}\n", single_line(event)));
+        }
+        if let Some(id) = &self.id {
+            out.push_str(&format!("id: {}\n", single_line(id)));
+        }
+        if let Some(retry) = self.retry {
+            out.push_str(&format!("retry: {retry}\n"));
+        }
+        for line in self
+            .data
+            .split("\r\n")
+            .flat_map(|line| line.split(['\r', '\n']))
+        {
+            out.push_str(&format!("data: {line}\n"));
+        }
+        out.push('\n');
+        out.into_bytes()
+    }
+}
+
+fn single_line(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '\r' | '\n'))
+        .collect()
+}
+
+/// WebSocket message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// UTF-8 text
+    Text(String),
+    /// Binary data
+    Binary(Vec<u8>),
+    /// Ping
+    Ping(Vec<u8>),
+    /// Pong
+    Pong(Vec<u8>),
+    /// Close
+    Close,
+}
+
+impl Message {
+    fn len(&self) -> u64 {
+        match self {
+            Message::Text(text) => text.len() as u64,
+            Message::Binary(data) | Message::Ping(data) | Message::Pong(data) => data.len() as u64,
+            Message::Close => 0,
+        }
+    }
+}
+
+/// One end of a WebSocket connection: messages to send and messages
+/// received
+pub struct WebSocketChannel {
+    /// Sends messages to the peer
+    pub tx: mpsc::Sender<Message>,
+    /// Receives messages from the peer
+    pub rx: mpsc::Receiver<Message>,
+}
+
+impl WebSocketChannel {
+    /// Create two connected ends, each buffering `buffer` messages
+    pub fn pair(buffer: usize) -> (Self, Self) {
+        let (a_tx, b_rx) = mpsc::channel(buffer.max(1));
+        let (b_tx, a_rx) = mpsc::channel(buffer.max(1));
+        (Self { tx: a_tx, rx: a_rx }, Self { tx: b_tx, rx: b_rx })
+    }
+}
+
+/// Kind of proxied connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    /// Server-sent events or another streamed body
+    Sse,
+    /// WebSocket session
+    WebSocket,
+}
+
+impl StreamKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            StreamKind::Sse => "sse",
+            StreamKind::WebSocket => "websocket",
+        }
+    }
+}
+
+/// Why a proxied connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The backend finished or closed the connection
+    BackendClosed,
+    /// The client went away or closed the connection
+    ClientClosed,
+    /// Nothing was sent either way for the idle timeout
+    IdleTimeout,
+}
+
+impl CloseReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            CloseReason::BackendClosed => "backend_closed",
+            CloseReason::ClientClosed => "client_closed",
+            CloseReason::IdleTimeout => "idle_timeout",
+        }
+    }
+}
+
+/// Traffic of a proxied connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Kind of connection
+    pub kind: StreamKind,
+    /// Messages or chunks from the backend to the client
+    pub messages_down: u64,
+    /// Bytes from the backend to the client
+    pub bytes_down: u64,
+    /// Messages from the client to the backend
+    pub messages_up: u64,
+    /// Bytes from the client to the backend
+    pub bytes_up: u64,
+    /// How long the connection was open
+    pub duration: Duration,
+    /// Why the connection ended
+    pub reason: CloseReason,
+}
+
+/// Counts of traffic in one direction
+#[derive(Default)]
+struct Direction {
+    messages: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl Direction {
+    fn record(&self, bytes: u64) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// When a connection last made progress
+struct Activity(Mutex<tokio::time::Instant>);
+
+impl Activity {
+    fn new() -> Self {
+        Self(Mutex::new(tokio::time::Instant::now()))
+    }
+
+    fn touch(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = tokio::time::Instant::now();
+    }
+
+    /// Resolve once there has been no progress for `timeout`
+    async fn idle(&self, timeout: Duration) {
+        loop {
+            let deadline = *self.0.lock().unwrap_or_else(|e| e.into_inner()) + timeout;
+            if tokio::time::Instant::now() >= deadline {
+                return;
+            }
+            tokio::time::sleep_until(deadline).await;
+        }
+    }
+}
+
+/// Proxies streamed responses and WebSocket sessions
+///
+/// Messages flow through bounded buffers, so a slow reader slows the
+/// writer instead of growing memory: once a buffer is full the proxy stops
+/// reading from the other side until there is room. WebSocket directions
+/// are pumped independently, so a full buffer one way doesn't stall the
+/// other. Connections that deliver no message in either direction for the
+/// idle timeout are closed.
+#[derive(Clone)]
+pub struct StreamProxy {
+    buffer: usize,
+    idle_timeout: Duration,
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+impl StreamProxy {
+    /// Create a proxy buffering 32 messages with a 60 second idle timeout
+    pub fn new() -> Self {
+        Self {
+            buffer: 32,
+            idle_timeout: Duration::from_secs(60),
+            metrics: None,
+        }
+    }
+
+    /// Set how many messages are buffered per direction
+    pub fn buffer(mut self, messages: usize) -> Self {
+        self.buffer = messages.max(1);
+        self
+    }
+
+    /// Set how long a connection may go without traffic
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Export open connections, traffic and close reasons to `registry`
+    pub fn with_metrics(mut self, registry: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(registry);
+        self
+    }
+
+    /// Proxy a streamed backend body to the client
+    ///
+    /// Returns the body to respond with, and a task resolving to the
+    /// connection's stats once the stream ends.
+    pub fn stream(
+        &self,
+        mut upstream: mpsc::Receiver<Vec<u8>>,
+    ) -> (BodyStream, JoinHandle<ConnectionStats>) {
+        let (tx, body) = BodyStream::channel(self.buffer);
+        let proxy = self.clone();
+        let task = tokio::spawn(async move {
+            let connection = proxy.open(StreamKind::Sse);
+            let activity = Activity::new();
+            let pump = async {
+                while let Some(chunk) = upstream.recv().await {
+                    let len = chunk.len() as u64;
+                    if tx.send(chunk).await.is_err() {
+                        return CloseReason::ClientClosed;
+                    }
+                    connection.down.record(len);
+                    activity.touch();
+                }
+                CloseReason::BackendClosed
+            };
+            let reason = tokio::select! {
+                reason = pump => reason,
+                () = activity.idle(proxy.idle_timeout) => CloseReason::IdleTimeout,
+            };
+            connection.close(reason)
+        });
+        (body, task)
+    }
+
+    /// Proxy a WebSocket session between a client and a backend until
+    /// either closes or the connection goes idle
+    pub async fn bridge(
+        &self,
+        client: WebSocketChannel,
+        backend: WebSocketChannel,
+    ) -> ConnectionStats {
+        let connection = self.open(StreamKind::WebSocket);
+        let WebSocketChannel {
+            tx: to_client,
+            rx: mut from_client,
+        } = client;
+        let WebSocketChannel {
+            tx: to_backend,
+            rx: mut from_backend,
+        } = backend;
+
+        let activity = Activity::new();
+        let reason = tokio::select! {
+            reason = pump(
+                &mut from_client,
+                &to_backend,
+                &connection.up,
+                &activity,
+                CloseReason::ClientClosed,
+                CloseReason::BackendClosed,
+            ) => reason,
+            reason = pump(
+                &mut from_backend,
+                &to_client,
+                &connection.down,
+                &activity,
+                CloseReason::BackendClosed,
+                CloseReason::ClientClosed,
+            ) => reason,
+            () = activity.idle(self.idle_timeout) => CloseReason::IdleTimeout,
+        };
+
+        if reason == CloseReason::IdleTimeout {
+            let _ = to_client.try_send(Message::Close);
+            let _ = to_backend.try_send(Message::Close);
+        }
+        connection.close(reason)
+    }
+
+    fn open(&self, kind: StreamKind) -> Connection {
+        if let Some(registry) = &self.metrics {
+            registry
+                .gauge_with("gateway_stream_connections", &[("kind", kind.as_str())])
+                .inc();
+        }
+        Connection {
+            kind,
+            started: Instant::now(),
+            up: Direction::default(),
+            down: Direction::default(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// Forward WebSocket messages one way until the source closes, returning
+/// `closed`, or the target goes away, returning `gone`
+async fn pump(
+    from: &mut mpsc::Receiver<Message>,
+    to: &mpsc::Sender<Message>,
+    direction: &Direction,
+    activity: &Activity,
+    closed: CloseReason,
+    gone: CloseReason,
+) -> CloseReason {
+    loop {
+        let message = match from.recv().await {
+            Some(Message::Close) | None => {
+                let _ = to.send(Message::Close).await;
+                return closed;
+            }
+            Some(message) => message,
+        };
+        let len = message.len();
+        if to.send(message).await.is_err() {
+            return gone;
+        }
+        direction.record(len);
+        activity.touch();
+    }
+}
+
+impl Default for StreamProxy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A proxied connection in progress
+struct Connection {
+    kind: StreamKind,
+    started: Instant,
+    up: Direction,
+    down: Direction,
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+impl Connection {
+    fn close(self, reason: CloseReason) -> ConnectionStats {
+        let stats = ConnectionStats {
+            kind: self.kind,
+            messages_down: self.down.messages.load(Ordering::Relaxed),
+            bytes_down: self.down.bytes.load(Ordering::Relaxed),
+            messages_up: self.up.messages.load(Ordering::Relaxed),
+            bytes_up: self.up.bytes.load(Ordering::Relaxed),
+            duration: self.started.elapsed(),
+            reason,
+        };
+
+        if let Some(registry) = &self.metrics {
+            let kind = self.kind.as_str();
+            registry
+                .gauge_with("gateway_stream_connections", &[("kind", kind)])
+                .dec();
+            registry
+                .counter_with(
+                    "gateway_stream_closed_total",
+                    &[("kind", kind), ("reason", reason.as_str())],
+                )
+                .inc();
+            for (direction, bytes) in [("down", stats.bytes_down), ("up", stats.bytes_up)] {
+                registry
+                    .counter_with(
+                        "gateway_stream_bytes_total",
+                        &[("kind", kind), ("direction", direction)],
+                    )
+                    .add(bytes);
+            }
+            registry
+                .histogram_with("gateway_stream_duration_seconds", &[("kind", kind)])
+                .observe(stats.duration.as_secs_f64());
+        }
+        tracing::debug!(
+            kind = self.kind.as_str(),
+            reason = reason.as_str(),
+            bytes_down = stats.bytes_down,
+            bytes_up = stats.bytes_up,
+            "Stream closed"
+        );
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::RequestContext;
+
+    #[test]
+    fn test_sse_encoding() {
+        let event = SseEvent::new("line one\nline two").event("delta").id("7");
+        assert_eq!(
+            String::from_utf8(event.encode()).unwrap(),
+            "event: delta\nid: 7\ndata: line one\ndata: line two\n\n"
+        );
+
+        let event = SseEvent::new("one\r\ntwo\rthree")
+            .event("delta\r\ndata: injected")
+            .id("7\nretry: 1");
+        assert_eq!(
+            String::from_utf8(event.encode()).unwrap(),
+            "event: deltadata: injected\nid: 7retry: 1\ndata: one\ndata: two\ndata: three\n\n"
+        );
+
+        let ctx = RequestContext::new("/v1/chat")
+            .with_header("accept", "text/event-stream")
+            .with_header("connection", "keep-alive, Upgrade")
+            .with_header("upgrade", "websocket");
+        assert!(ctx.accepts_event_stream());
+        assert!(ctx.is_websocket_upgrade());
+    }
+
+    #[tokio::test]
+    async fn test_stream_backpressure_and_idle_timeout() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let proxy = StreamProxy::new()
+            .buffer(1)
+            .idle_timeout(Duration::from_millis(100))
+            .with_metrics(registry.clone());
+
+        let (backend, upstream) = mpsc::channel(1);
+        let (body, task) = proxy.stream(upstream);
+        let mut client = body.take().unwrap();
+        assert!(body.take().is_none());
+
+        // With nobody reading, the backend can only get a few chunks ahead
+        let mut sent = 0;
+        while sent < 10 {
+            let send = backend.send(SseEvent::new("token").encode());
+            if tokio::time::timeout(Duration::from_millis(10), send)
+                .await
+                .is_err()
+            {
+                break;
+            }
+            sent += 1;
+        }
+        assert!(sent < 10, "backend was not slowed down");
+
+        for _ in 0..sent {
+            client.recv().await.unwrap();
+        }
+        let stats = task.await.unwrap();
+        assert_eq!(stats.reason, CloseReason::IdleTimeout);
+        assert_eq!(stats.messages_down, sent);
+        assert!(client.recv().await.is_none());
+
+        let closed = registry.counter_with(
+            "gateway_stream_closed_total",
+            &[("kind", "sse"), ("reason", "idle_timeout")],
+        );
+        assert_eq!(closed.get(), 1);
+        let open = registry.gauge_with("gateway_stream_connections", &[("kind", "sse")]);
+        assert_eq!(open.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_bridge() {
+        let (mut client, client_end) = WebSocketChannel::pair(4);
+        let (backend_end, mut backend) = WebSocketChannel::pair(4);
+        let proxy = StreamProxy::new();
+        let session = tokio::spawn(async move { proxy.bridge(client_end, backend_end).await });
+
+        client.tx.send(Message::Text("hello".into())).await.unwrap();
+        assert_eq!(backend.rx.recv().await, Some(Message::Text("hello".into())));
+        backend
+            .tx
+            .send(Message::Binary(vec![1, 2, 3]))
+            .await
+            .unwrap();
+        assert_eq!(client.rx.recv().await, Some(Message::Binary(vec![1, 2, 3])));
+
+        client.tx.send(Message::Close).await.unwrap();
+        assert_eq!(backend.rx.recv().await, Some(Message::Close));
+
+        let stats = session.await.unwrap();
+        assert_eq!(stats.reason, CloseReason::ClientClosed);
+        assert_eq!((stats.messages_up, stats.bytes_up), (1, 5));
+        assert_eq!((stats.messages_down, stats.bytes_down), (1, 3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_websocket_directions_are_independent() {
+        let (client, client_end) = WebSocketChannel::pair(1);
+        let (backend_end, mut backend) = WebSocketChannel::pair(1);
+        let proxy = StreamProxy::new().idle_timeout(Duration::from_secs(10));
+        let session = tokio::spawn(async move { proxy.bridge(client_end, backend_end).await });
+
+        // The client never reads, so messages to it back up
+        for i in 0..3u8 {
+            backend.tx.send(Message::Binary(vec![i])).await.unwrap();
+        }
+        client.tx.send(Message::Text("up".into())).await.unwrap();
+        assert_eq!(backend.rx.recv().await, Some(Message::Text("up".into())));
+
+        // Traffic one way keeps the session open
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_secs(6)).await;
+            client.tx.send(Message::Text("up".into())).await.unwrap();
+            assert_eq!(backend.rx.recv().await, Some(Message::Text("up".into())));
+        }
+        assert!(!session.is_finished());
+
+        tokio::time::sleep(Duration::from_secs(11)).await;
+        let stats = session.await.unwrap();
+        assert_eq!(stats.reason, CloseReason::IdleTimeout);
+        assert_eq!(stats.messages_up, 4);
+    }
+}
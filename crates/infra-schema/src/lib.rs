@@ -5,10 +5,12 @@
 mod validator;
 mod builder;
 mod types;
+mod registry;
 
 pub use validator::{SchemaValidator, ValidationResult, ValidationErrorDetail};
 pub use builder::SchemaBuilder;
 pub use types::{SchemaType, Format};
+pub use registry::SchemaRegistry;
 
 use infra_errors::{InfraError, InfraResult};
 use serde_json::Value;
@@ -26,6 +28,7 @@ pub fn validate_str(schema: &str, data: &str) -> InfraResult<ValidationResult> {
         path: None,
         message: format!("Invalid schema JSON: {e}"),
         context: None,
+        source: None,
     })?;
 
     let data: Value = serde_json::from_str(data).map_err(|e| InfraError::Schema {
@@ -33,6 +36,7 @@ pub fn validate_str(schema: &str, data: &str) -> InfraResult<ValidationResult> {
         path: None,
         message: format!("Invalid data JSON: {e}"),
         context: None,
+        source: None,
     })?;
 
     validate(&schema, &data)
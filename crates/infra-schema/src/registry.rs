@@ -0,0 +1,115 @@
+//! Schema registry.
+
+use crate::validator::{SchemaValidator, ValidationResult};
+use infra_errors::{InfraError, InfraResult};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Compiled schemas looked up by ID
+///
+/// Lets producers and consumers agree on a payload contract by name, such as
+/// `llm.job.v1`, instead of passing schemas around.
+#[derive(Default, Clone)]
+pub struct SchemaRegistry {
+    schemas: HashMap<String, Arc<SchemaValidator>>,
+}
+
+impl SchemaRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile and register a schema, replacing any with the same ID
+    pub fn register(&mut self, id: impl Into<String>, schema: &Value) -> InfraResult<()> {
+        let id = id.into();
+        let validator = SchemaValidator::new(schema).map_err(|e| match e {
+            InfraError::Schema {
+                path,
+                message,
+                context,
+                source,
+                ..
+            } => InfraError::Schema {
+                schema_id: Some(id.clone()),
+                path,
+                message,
+                context,
+                source,
+            },
+            other => other,
+        })?;
+        self.schemas.insert(id, Arc::new(validator));
+        Ok(())
+    }
+
+    /// Get the validator for a schema
+    pub fn get(&self, id: &str) -> Option<Arc<SchemaValidator>> {
+        self.schemas.get(id).cloned()
+    }
+
+    /// Check if a schema is registered
+    pub fn contains(&self, id: &str) -> bool {
+        self.schemas.contains_key(id)
+    }
+
+    /// Validate data against a registered schema
+    pub fn validate(&self, id: &str, data: &Value) -> InfraResult<ValidationResult> {
+        self.schemas
+            .get(id)
+            .map(|validator| validator.validate(data))
+            .ok_or_else(|| InfraError::Schema {
+                schema_id: Some(id.to_string()),
+                path: None,
+                message: format!("Schema not registered: {id}"),
+                context: None,
+                source: None,
+            })
+    }
+
+    /// Get the registered schema IDs
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.schemas.keys().map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for SchemaRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaRegistry")
+            .field("schemas", &self.schemas.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_registry() {
+        let mut registry = SchemaRegistry::new();
+        registry
+            .register(
+                "job.v1",
+                &json!({ "type": "object", "required": ["prompt"] }),
+            )
+            .unwrap();
+
+        assert!(registry.contains("job.v1"));
+        assert!(registry
+            .validate("job.v1", &json!({ "prompt": "Hi" }))
+            .unwrap()
+            .is_valid());
+        assert!(!registry.validate("job.v1", &json!({})).unwrap().is_valid());
+
+        let err = registry.validate("job.v2", &json!({})).unwrap_err();
+        assert!(matches!(err, InfraError::Schema { schema_id: Some(id), .. } if id == "job.v2"));
+
+        let err = registry
+            .register("bad", &json!({ "type": 12 }))
+            .unwrap_err();
+        assert!(matches!(err, InfraError::Schema { schema_id: Some(id), .. } if id == "bad"));
+    }
+}
@@ -70,6 +70,7 @@ impl ValidationResult {
                 path: None,
                 message: format!("Validation failed:\n  {}", messages.join("\n  ")),
                 context: None,
+                source: None,
             })
         }
     }
@@ -88,6 +89,7 @@ impl SchemaValidator {
             path: None,
             message: format!("Failed to compile schema: {e}"),
             context: None,
+            source: None,
         })?;
 
         Ok(Self { compiled })
@@ -0,0 +1,192 @@
+//! Proptest strategies for simulation types, and helpers to shrink failing
+//! scenarios down to a minimal reproduction.
+//!
+//! [`minimize`] runs a check against generated values with a fixed seed, so
+//! the same counterexample is found on every run, and returns the failing
+//! value after proptest has shrunk it.
+
+use crate::chaos::{ChaosConfig, ChaosMode, LatencyConfig};
+use crate::network::{LatencyDistribution, LinkConditions};
+use crate::scenario::{Scenario, ScenarioBuilder, Step};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, RngAlgorithm, TestError, TestRng, TestRunner};
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// Create a test runner for `cases` cases with a fixed seed
+pub fn runner(cases: u32) -> TestRunner {
+    TestRunner::new_with_rng(
+        Config {
+            cases,
+            failure_persistence: None,
+            ..Config::default()
+        },
+        TestRng::deterministic_rng(RngAlgorithm::ChaCha),
+    )
+}
+
+/// Search for a value that fails `check` and shrink it
+///
+/// Returns `None` if all `cases` generated values pass. Otherwise returns
+/// the simplest failing value proptest could shrink the first failure to.
+pub fn minimize<S>(strategy: &S, cases: u32, check: impl Fn(&S::Value) -> bool) -> Option<S::Value>
+where
+    S: Strategy,
+    S::Value: Debug,
+{
+    match runner(cases).run(strategy, |value| {
+        if check(&value) {
+            Ok(())
+        } else {
+            Err(TestCaseError::fail("check failed"))
+        }
+    }) {
+        Ok(()) => None,
+        Err(TestError::Fail(_, value)) => Some(value),
+        Err(TestError::Abort(reason)) => panic!("minimize aborted: {reason}"),
+    }
+}
+
+/// Strategy for step delays of up to one second
+pub fn delay() -> impl Strategy<Value = Duration> {
+    (0u64..=1000).prop_map(Duration::from_millis)
+}
+
+/// Strategy for a step named after one of `names`, with an optional delay
+pub fn step(names: &[&str]) -> impl Strategy<Value = Step> {
+    let names: Vec<String> = names.iter().map(|n| (*n).to_string()).collect();
+    (prop::sample::select(names), prop::option::of(delay())).prop_map(|(name, delay)| Step {
+        name,
+        description: None,
+        delay,
+    })
+}
+
+/// Strategy for sequences of up to `max_len` steps drawn from `names`
+///
+/// Shrinking removes steps, clears delays and moves names towards the front
+/// of `names`, so list the simplest operations first.
+pub fn steps(names: &[&str], max_len: usize) -> impl Strategy<Value = Vec<Step>> {
+    prop::collection::vec(step(names), 0..=max_len)
+}
+
+/// Strategy for scenarios of up to `max_len` steps drawn from `names`
+pub fn scenario(name: &str, names: &[&str], max_len: usize) -> impl Strategy<Value = Scenario> {
+    let name = name.to_string();
+    steps(names, max_len).prop_map(move |steps| {
+        steps
+            .into_iter()
+            .fold(ScenarioBuilder::new(name.clone()), ScenarioBuilder::step)
+            .build()
+    })
+}
+
+/// Strategy for chaos configurations with probabilities in `0..=1`
+pub fn chaos_config() -> impl Strategy<Value = ChaosConfig> {
+    let mode = prop_oneof![
+        Just(ChaosMode::Disabled),
+        Just(ChaosMode::Random),
+        Just(ChaosMode::AlwaysFail),
+        Just(ChaosMode::Probabilistic),
+    ];
+    let latency = (delay(), delay(), 0.0f64..=1.0)
+        .prop_map(|(a, b, p)| LatencyConfig::new(a.min(b), a.max(b)).probability(p));
+    (mode, 0.0f64..=1.0, prop::option::of(latency), 0.0f64..=1.0).prop_map(
+        |(mode, failure_probability, latency, corruption_probability)| ChaosConfig {
+            mode,
+            failure_probability,
+            latency,
+            corruption_probability,
+            ..ChaosConfig::default()
+        },
+    )
+}
+
+/// Strategy for network link conditions with up to 50% loss
+pub fn link_conditions() -> impl Strategy<Value = LinkConditions> {
+    let latency = prop_oneof![
+        delay().prop_map(LatencyDistribution::Constant),
+        (delay(), delay()).prop_map(|(a, b)| LatencyDistribution::Uniform {
+            min: a.min(b),
+            max: a.max(b),
+        }),
+        (delay(), delay())
+            .prop_map(|(mean, std_dev)| LatencyDistribution::Normal { mean, std_dev }),
+        delay().prop_map(|mean| LatencyDistribution::Exponential { mean }),
+    ];
+    (latency, delay(), 0.0f64..=0.5).prop_map(|(latency, jitter, loss)| {
+        LinkConditions::default()
+            .latency(latency)
+            .jitter(jitter)
+            .loss(loss)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A buggy system that loses data when it crashes after a write
+    fn survives(steps: &[Step]) -> bool {
+        let mut dirty = false;
+        for step in steps {
+            match step.name.as_str() {
+                "write" => dirty = true,
+                "flush" => dirty = false,
+                "crash" if dirty => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn test_minimize_shrinks_to_smallest_failure() {
+        let failing = minimize(&steps(&["read", "flush", "write", "crash"], 20), 256, |s| {
+            survives(s)
+        })
+        .expect("a crash after a write should be found");
+
+        let names: Vec<&str> = failing.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["write", "crash"]);
+        assert!(failing.iter().all(|s| s.delay.is_none()));
+    }
+
+    #[test]
+    fn test_minimize_is_deterministic() {
+        let strategy = scenario("crash", &["read", "flush", "write", "crash"], 20);
+        let first = minimize(&strategy, 256, |s| survives(s.steps())).unwrap();
+        let second = minimize(&strategy, 256, |s| survives(s.steps())).unwrap();
+
+        assert_eq!(first.name(), "crash");
+        assert_eq!(
+            format!("{:?}", first.steps()),
+            format!("{:?}", second.steps())
+        );
+    }
+
+    #[test]
+    fn test_minimize_passing_check() {
+        assert!(minimize(&steps(&["read", "flush"], 10), 64, |s| survives(s)).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_chaos_config_probabilities(config in chaos_config()) {
+            prop_assert!((0.0..=1.0).contains(&config.failure_probability));
+            prop_assert!((0.0..=1.0).contains(&config.corruption_probability));
+            if let Some(latency) = config.latency {
+                prop_assert!(latency.min <= latency.max);
+            }
+        }
+
+        #[test]
+        fn prop_link_latency_is_sampled_within_bounds(link in link_conditions()) {
+            let mut rng = rand::thread_rng();
+            let sampled = link.latency.sample(&mut rng);
+            if let LatencyDistribution::Uniform { min, max } = link.latency {
+                prop_assert!(sampled >= min && sampled <= max);
+            }
+        }
+    }
+}
@@ -1,8 +1,16 @@
 //! Chaos testing utilities.
 
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 
+/// Most faults an injector records
+const MAX_EVENTS: usize = 10_000;
+
 /// Chaos mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChaosMode {
@@ -17,7 +25,11 @@ pub enum ChaosMode {
 }
 
 /// Chaos injection configuration
+///
+/// Build one from [`ChaosConfig::new`] or the default, which injects
+/// nothing, and the `with_*` methods.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ChaosConfig {
     /// Chaos mode
     pub mode: ChaosMode,
@@ -27,6 +39,71 @@ pub struct ChaosConfig {
     pub latency: Option<LatencyConfig>,
     /// Error message to return
     pub error_message: String,
+    /// Probability of corrupting returned data (0.0 to 1.0)
+    pub corruption_probability: f64,
+    /// Seed for random draws, making the injected faults reproducible
+    pub seed: Option<u64>,
+    /// Faults injected at given attempts, in place of random draws
+    pub schedule: FaultSchedule,
+}
+
+impl ChaosConfig {
+    /// Create a configuration with a chaos mode
+    pub fn new(mode: ChaosMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Set the failure probability
+    pub fn with_failure_probability(mut self, probability: f64) -> Self {
+        self.failure_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the latency injection
+    pub fn with_latency(mut self, latency: LatencyConfig) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Set the error message
+    pub fn with_error_message(mut self, message: impl Into<String>) -> Self {
+        self.error_message = message.into();
+        self
+    }
+
+    /// Set the probability of corrupting returned data
+    pub fn with_corruption_probability(mut self, probability: f64) -> Self {
+        self.corruption_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the seed for random draws
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the faults injected at given attempts
+    pub fn with_schedule(mut self, schedule: FaultSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Create a configuration replaying exactly the faults of an event log
+    /// recorded with this one
+    ///
+    /// Only the logged faults are injected, failing with this
+    /// configuration's error message.
+    pub fn replay(&self, events: &[ChaosEvent]) -> Self {
+        Self {
+            error_message: self.error_message.clone(),
+            schedule: FaultSchedule::from_events(events),
+            ..Self::default()
+        }
+    }
 }
 
 impl Default for ChaosConfig {
@@ -36,6 +113,9 @@ impl Default for ChaosConfig {
             failure_probability: 0.1,
             latency: None,
             error_message: "Chaos failure".to_string(),
+            corruption_probability: 0.0,
+            seed: None,
+            schedule: FaultSchedule::default(),
         }
     }
 }
@@ -68,15 +148,171 @@ impl LatencyConfig {
     }
 }
 
+/// A fault injected at one attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Fail with the configured error message
+    Fail,
+    /// Wait, then fail as timed out
+    Timeout(Duration),
+    /// Wait, then proceed
+    Delay(Duration),
+    /// Corrupt the returned data
+    Corrupt,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fail => write!(f, "fails"),
+            Self::Timeout(after) => write!(f, "times out after {after:?}"),
+            Self::Delay(delay) => write!(f, "is delayed by {delay:?}"),
+            Self::Corrupt => write!(f, "is corrupted"),
+        }
+    }
+}
+
+/// A fault injected by a [`ChaosInjector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChaosEvent {
+    /// Attempt the fault was injected at, starting at 1
+    pub attempt: u64,
+    /// The injected fault
+    pub fault: Fault,
+}
+
+impl fmt::Display for ChaosEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "attempt {} {}", self.attempt, self.fault)
+    }
+}
+
+/// Faults to inject at given attempts
+///
+/// Attempts are counted per injector, from 1, by [`ChaosInjector::apply`]
+/// and [`ChaosInjector::apply_async`]. An attempt with scheduled faults gets
+/// exactly those, and no random ones.
+///
+/// ```
+/// use infra_sim::FaultSchedule;
+/// use std::time::Duration;
+///
+/// let schedule = FaultSchedule::new()
+///     .fail(3)
+///     .timeout(7, Duration::from_secs(30));
+/// assert_eq!(schedule.to_string(), "attempt 3 fails\nattempt 7 times out after 30s\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaultSchedule {
+    faults: BTreeMap<u64, Vec<Fault>>,
+}
+
+impl FaultSchedule {
+    /// Create an empty schedule
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the schedule replaying an event log
+    pub fn from_events(events: &[ChaosEvent]) -> Self {
+        events.iter().fold(Self::new(), |schedule, event| {
+            schedule.at(event.attempt, event.fault)
+        })
+    }
+
+    /// Inject a fault at an attempt
+    pub fn at(mut self, attempt: u64, fault: Fault) -> Self {
+        self.faults.entry(attempt).or_default().push(fault);
+        self
+    }
+
+    /// Fail an attempt
+    pub fn fail(self, attempt: u64) -> Self {
+        self.at(attempt, Fault::Fail)
+    }
+
+    /// Time an attempt out after a delay
+    pub fn timeout(self, attempt: u64, after: Duration) -> Self {
+        self.at(attempt, Fault::Timeout(after))
+    }
+
+    /// Delay an attempt
+    pub fn delay(self, attempt: u64, delay: Duration) -> Self {
+        self.at(attempt, Fault::Delay(delay))
+    }
+
+    /// Corrupt the data returned by an attempt
+    pub fn corrupt(self, attempt: u64) -> Self {
+        self.at(attempt, Fault::Corrupt)
+    }
+
+    /// Get the faults scheduled at an attempt
+    pub fn faults(&self, attempt: u64) -> Option<&[Fault]> {
+        self.faults.get(&attempt).map(Vec::as_slice)
+    }
+
+    /// Check if no faults are scheduled
+    pub fn is_empty(&self) -> bool {
+        self.faults.is_empty()
+    }
+
+    /// Get the scheduled faults as events, in attempt order
+    pub fn events(&self) -> Vec<ChaosEvent> {
+        self.faults
+            .iter()
+            .flat_map(|(attempt, faults)| {
+                faults.iter().map(|fault| ChaosEvent {
+                    attempt: *attempt,
+                    fault: *fault,
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for FaultSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in self.events() {
+            writeln!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
 /// Chaos injector
+///
+/// Random draws use the configured seed, if any, so a run making the same
+/// calls in the same order injects the same faults. Every injected fault is
+/// recorded, and [`ChaosConfig::replay`] turns the log into a configuration
+/// injecting exactly those faults again.
 pub struct ChaosInjector {
     config: ChaosConfig,
+    rng: Option<Mutex<StdRng>>,
+    attempts: AtomicU64,
+    corruption: Mutex<Option<(u64, bool)>>,
+    events: Mutex<Vec<ChaosEvent>>,
 }
 
 impl ChaosInjector {
     /// Create a new chaos injector
     pub fn new(config: ChaosConfig) -> Self {
-        Self { config }
+        Self {
+            rng: config
+                .seed
+                .map(|seed| Mutex::new(StdRng::seed_from_u64(seed))),
+            config,
+            attempts: AtomicU64::new(0),
+            corruption: Mutex::new(None),
+            events: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create an injector with a seed for its random draws
+    pub fn seeded(config: ChaosConfig, seed: u64) -> Self {
+        Self::new(ChaosConfig {
+            seed: Some(seed),
+            ..config
+        })
     }
 
     /// Create with random failures
@@ -103,24 +339,73 @@ impl ChaosInjector {
             ChaosMode::Disabled => false,
             ChaosMode::AlwaysFail => true,
             ChaosMode::Random | ChaosMode::Probabilistic => {
-                let mut rng = rand::thread_rng();
-                rng.gen::<f64>() < self.config.failure_probability
+                self.draw(|rng| rng.gen::<f64>()) < self.config.failure_probability
             }
         }
     }
 
     /// Get the latency to inject (if any)
     pub fn latency(&self) -> Option<Duration> {
-        if let Some(ref latency_config) = self.config.latency {
-            let mut rng = rand::thread_rng();
+        let latency_config = self.config.latency.as_ref()?;
+        self.draw(|rng| {
             if rng.gen::<f64>() < latency_config.probability {
                 let range = latency_config.max.as_millis() - latency_config.min.as_millis();
                 let delay = latency_config.min.as_millis()
                     + (rng.gen::<f64>() * range as f64) as u128;
-                return Some(Duration::from_millis(delay as u64));
+                Some(Duration::from_millis(delay as u64))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Check if the data returned by the current attempt should be corrupted
+    ///
+    /// The answer is decided once per attempt, so checking again gives the
+    /// same answer.
+    pub fn should_corrupt(&self) -> bool {
+        let attempt = self.attempts.load(Ordering::SeqCst);
+        let mut decided = self.corruption.lock().unwrap();
+        if let Some((decided_attempt, corrupt)) = *decided {
+            if decided_attempt == attempt {
+                return corrupt;
             }
         }
-        None
+        let corrupt = match self.config.schedule.faults(attempt) {
+            Some(faults) => faults.contains(&Fault::Corrupt),
+            None => {
+                self.config.corruption_probability > 0.0
+                    && self.draw(|rng| rng.gen::<f64>()) < self.config.corruption_probability
+            }
+        };
+        *decided = Some((attempt, corrupt));
+        if corrupt {
+            self.record(attempt, Fault::Corrupt);
+        }
+        corrupt
+    }
+
+    /// Corrupt bytes by flipping a random bit
+    pub fn corrupt_bytes(&self, bytes: &mut [u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        let (index, bit) = self.draw(|rng| (rng.gen_range(0..bytes.len()), rng.gen_range(0..8)));
+        bytes[index] ^= 1 << bit;
+    }
+
+    /// Corrupt floats by flipping a random bit of one of them
+    pub fn corrupt_floats(&self, values: &mut [f32]) {
+        if values.is_empty() {
+            return;
+        }
+        let (index, bit) = self.draw(|rng| (rng.gen_range(0..values.len()), rng.gen_range(0..32)));
+        values[index] = f32::from_bits(values[index].to_bits() ^ (1 << bit));
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
     }
 
     /// Get the error message
@@ -128,22 +413,145 @@ impl ChaosInjector {
         &self.config.error_message
     }
 
+    /// Get the number of attempts made so far
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// Get the faults injected so far, in order
+    ///
+    /// Only the first 10,000 faults are recorded.
+    pub fn events(&self) -> Vec<ChaosEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
     /// Apply chaos (returns error if failure should be injected)
+    ///
+    /// Scheduled delays and timeouts are not waited for.
     pub fn apply<T>(&self, value: T) -> Result<T, String> {
-        if self.should_fail() {
-            Err(self.config.error_message.clone())
-        } else {
-            Ok(value)
-        }
+        let (_, result) = self.attempt(false);
+        result.map(|()| value)
     }
 
     /// Apply chaos asynchronously with latency
     pub async fn apply_async<T>(&self, value: T) -> Result<T, String> {
-        if let Some(latency) = self.latency() {
-            tokio::time::sleep(latency).await;
+        let (delay, result) = self.attempt(true);
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        result.map(|()| value)
+    }
+
+    /// Start an attempt, returning the delay to wait and its outcome
+    fn attempt(&self, with_latency: bool) -> (Option<Duration>, Result<(), String>) {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let faults = match self.config.schedule.faults(attempt) {
+            Some(faults) => faults.to_vec(),
+            None => {
+                let delay = if with_latency { self.latency() } else { None };
+                delay
+                    .map(Fault::Delay)
+                    .into_iter()
+                    .chain(self.should_fail().then_some(Fault::Fail))
+                    .collect()
+            }
+        };
+
+        let mut delay = None;
+        let mut result = Ok(());
+        for fault in faults {
+            match fault {
+                Fault::Fail => result = Err(self.config.error_message.clone()),
+                Fault::Timeout(after) => {
+                    delay = Some(after);
+                    result = Err(format!(
+                        "{}: timed out after {after:?}",
+                        self.config.error_message
+                    ));
+                }
+                Fault::Delay(after) => delay = Some(after),
+                // Recorded by should_corrupt once the data is returned
+                Fault::Corrupt => continue,
+            }
+            self.record(attempt, fault);
+        }
+        (delay, result)
+    }
+
+    fn record(&self, attempt: u64, fault: Fault) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() < MAX_EVENTS {
+            events.push(ChaosEvent { attempt, fault });
+        }
+    }
+
+    /// Run a random draw with the seeded generator, or the thread's one
+    fn draw<R>(&self, f: impl FnOnce(&mut dyn RngCore) -> R) -> R {
+        match &self.rng {
+            Some(rng) => f(&mut *rng.lock().unwrap()),
+            None => f(&mut rand::thread_rng()),
+        }
+    }
+}
+
+/// Chaos configuration per operation
+///
+/// Operations without their own configuration use the default one, so a
+/// wrapper can make, say, only writes fail.
+pub struct ChaosPolicy {
+    default: ChaosInjector,
+    operations: HashMap<String, ChaosInjector>,
+}
+
+impl ChaosPolicy {
+    /// Create a policy applying a configuration to every operation
+    pub fn new(config: ChaosConfig) -> Self {
+        Self {
+            default: ChaosInjector::new(config),
+            operations: HashMap::new(),
         }
+    }
+
+    /// Create a policy injecting nothing
+    pub fn disabled() -> Self {
+        Self::new(ChaosConfig::default())
+    }
+
+    /// Set the configuration of an operation
+    pub fn operation(mut self, operation: impl Into<String>, config: ChaosConfig) -> Self {
+        self.operations
+            .insert(operation.into(), ChaosInjector::new(config));
+        self
+    }
+
+    /// Get the injector of an operation
+    pub fn injector(&self, operation: &str) -> &ChaosInjector {
+        self.operations.get(operation).unwrap_or(&self.default)
+    }
+
+    /// Inject latency, then maybe fail, before an operation
+    pub async fn disturb(&self, operation: &str) -> Result<(), String> {
+        self.injector(operation).apply_async(()).await
+    }
+
+    /// Get the faults injected into an operation so far
+    ///
+    /// Operations without their own configuration share the default log.
+    pub fn events(&self, operation: &str) -> Vec<ChaosEvent> {
+        self.injector(operation).events()
+    }
+}
+
+impl Default for ChaosPolicy {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
 
-        self.apply(value)
+impl From<ChaosConfig> for ChaosPolicy {
+    fn from(config: ChaosConfig) -> Self {
+        Self::new(config)
     }
 }
 
@@ -192,4 +600,133 @@ mod tests {
             assert!(latency <= Duration::from_millis(100));
         }
     }
+
+    #[test]
+    fn test_corrupt_bytes() {
+        let injector = ChaosInjector::new(ChaosConfig {
+            corruption_probability: 1.0,
+            ..Default::default()
+        });
+        assert!(injector.should_corrupt());
+        assert!(injector.should_corrupt());
+        assert_eq!(injector.events().len(), 1);
+        let mut bytes = vec![0u8; 16];
+        injector.corrupt_bytes(&mut bytes);
+        assert_eq!(bytes.iter().map(|b| b.count_ones()).sum::<u32>(), 1);
+        injector.corrupt_bytes(&mut []);
+
+        let mut floats = vec![1.0f32, 2.0];
+        injector.corrupt_floats(&mut floats);
+        assert!(floats[0].to_bits() != 1.0f32.to_bits() || floats[1].to_bits() != 2.0f32.to_bits());
+
+        let injector = ChaosInjector::new(ChaosConfig::default());
+        assert!(!injector.should_corrupt());
+    }
+
+    #[tokio::test]
+    async fn test_chaos_policy() {
+        let policy = ChaosPolicy::disabled().operation(
+            "write",
+            ChaosConfig {
+                mode: ChaosMode::AlwaysFail,
+                error_message: "write failed".to_string(),
+                ..Default::default()
+            },
+        );
+
+        assert!(policy.disturb("read").await.is_ok());
+        assert_eq!(policy.disturb("write").await.unwrap_err(), "write failed");
+        assert!(!policy.injector("read").should_fail());
+    }
+
+    fn flaky() -> ChaosConfig {
+        ChaosConfig {
+            mode: ChaosMode::Probabilistic,
+            failure_probability: 0.3,
+            corruption_probability: 0.2,
+            ..Default::default()
+        }
+    }
+
+    fn outcomes(injector: &ChaosInjector, attempts: usize) -> Vec<bool> {
+        (0..attempts)
+            .map(|_| injector.apply(()).is_ok() && !injector.should_corrupt())
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_chaos_is_reproducible() {
+        let first = ChaosInjector::seeded(flaky(), 42);
+        let second = ChaosInjector::seeded(flaky(), 42);
+
+        assert_eq!(outcomes(&first, 50), outcomes(&second, 50));
+        assert_eq!(first.events(), second.events());
+        assert!(!first.events().is_empty());
+        assert_eq!(first.attempts(), 50);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fault_schedule() {
+        let injector = ChaosInjector::new(ChaosConfig {
+            error_message: "injected".to_string(),
+            schedule: FaultSchedule::new()
+                .fail(3)
+                .timeout(7, Duration::from_secs(30)),
+            ..Default::default()
+        });
+
+        let mut results = Vec::new();
+        for _ in 0..8 {
+            results.push(injector.apply_async(()).await);
+        }
+
+        assert!(results
+            .iter()
+            .enumerate()
+            .all(|(i, r)| r.is_ok() == (i != 2 && i != 6)));
+        assert_eq!(results[2].as_ref().unwrap_err(), "injected");
+        assert_eq!(
+            results[6].as_ref().unwrap_err(),
+            "injected: timed out after 30s"
+        );
+        assert_eq!(
+            injector.events(),
+            vec![
+                ChaosEvent {
+                    attempt: 3,
+                    fault: Fault::Fail
+                },
+                ChaosEvent {
+                    attempt: 7,
+                    fault: Fault::Timeout(Duration::from_secs(30))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay_event_log() {
+        let config = flaky().with_error_message("flaky");
+        let original = ChaosInjector::new(config.clone());
+        let expected = outcomes(&original, 40);
+
+        let replayed = ChaosInjector::new(config.replay(&original.events()));
+        assert_eq!(outcomes(&replayed, 40), expected);
+        assert_eq!(replayed.events(), original.events());
+        assert_eq!(replayed.error_message(), "flaky");
+        assert_eq!(
+            FaultSchedule::from_events(&replayed.events()).to_string(),
+            FaultSchedule::from_events(&original.events()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_event_log_is_bounded() {
+        let injector = ChaosInjector::new(ChaosConfig::new(ChaosMode::AlwaysFail));
+        for _ in 0..MAX_EVENTS + 10 {
+            assert!(injector.apply(()).is_err());
+        }
+        assert_eq!(injector.events().len(), MAX_EVENTS);
+        assert_eq!(injector.events()[0].attempt, 1);
+    }
 }
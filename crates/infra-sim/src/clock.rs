@@ -1,7 +1,9 @@
 //! Clock abstractions for time simulation.
 
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(any(test, feature = "runtime"))]
+use tokio::runtime::Handle;
 
 /// Clock trait for time abstraction
 pub trait Clock: Send + Sync {
@@ -12,9 +14,28 @@ pub trait Clock: Send + Sync {
     fn sleep(&self, duration: Duration);
 }
 
+/// Source of the current time for components with timeouts and timestamps
+///
+/// Components accepting a time source through their `with_time_source`
+/// builders (cache TTLs in infra-cache, windows in infra-rate-limit, token
+/// expiry in infra-auth, event timestamps in infra-audit, and breakers and
+/// budgets in infra-retry) read both clocks through it, so tests can drive
+/// them with a [`SimulatedClock`] instead of waiting.
+pub use infra_time::TimeSource;
+
 /// System clock (real time)
 pub struct SystemClock;
 
+impl TimeSource for SystemClock {
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
 impl Clock for SystemClock {
     fn now(&self) -> Instant {
         Instant::now()
@@ -26,9 +47,34 @@ impl Clock for SystemClock {
 }
 
 /// Simulated clock for testing
+///
+/// A clock created by a `SimRuntime` follows the runtime's virtual time
+/// instead, and cannot be advanced by hand, as tokio's timers would not see
+/// it. As a
+/// [`TimeSource`], its wall-clock time starts at the real time it was
+/// created, or at the time given to [`starting_at`](Self::starting_at).
 pub struct SimulatedClock {
     base: Instant,
+    wall_base: SystemTime,
     offset_nanos: AtomicU64,
+    #[cfg(any(test, feature = "runtime"))]
+    runtime: Option<RuntimeTime>,
+}
+
+/// Virtual time of a paused runtime
+#[cfg(any(test, feature = "runtime"))]
+struct RuntimeTime {
+    handle: Handle,
+    start: tokio::time::Instant,
+}
+
+#[cfg(any(test, feature = "runtime"))]
+impl RuntimeTime {
+    /// Get the virtual time elapsed since the clock was created
+    fn elapsed(&self) -> Duration {
+        let _guard = self.handle.enter();
+        tokio::time::Instant::now() - self.start
+    }
 }
 
 impl SimulatedClock {
@@ -36,26 +82,73 @@ impl SimulatedClock {
     pub fn new() -> Self {
         Self {
             base: Instant::now(),
+            wall_base: SystemTime::now(),
             offset_nanos: AtomicU64::new(0),
+            #[cfg(any(test, feature = "runtime"))]
+            runtime: None,
+        }
+    }
+
+    /// Create a clock whose wall-clock time starts at `start`
+    pub fn starting_at(start: SystemTime) -> Self {
+        Self {
+            wall_base: start,
+            ..Self::new()
+        }
+    }
+
+    /// Create a clock following the virtual time of a runtime
+    #[cfg(any(test, feature = "runtime"))]
+    pub(crate) fn driven_by(handle: Handle) -> Self {
+        let start = {
+            let _guard = handle.enter();
+            tokio::time::Instant::now()
+        };
+        Self {
+            runtime: Some(RuntimeTime { handle, start }),
+            ..Self::new()
         }
     }
 
     /// Advance the clock by a duration
+    ///
+    /// # Panics
+    ///
+    /// Panics if the clock follows a runtime's virtual time, which only the
+    /// runtime advances.
     pub fn advance(&self, duration: Duration) {
+        self.check_manual();
         self.offset_nanos
             .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
     }
 
     /// Set the clock to a specific offset from the base
+    ///
+    /// # Panics
+    ///
+    /// Panics if the clock follows a runtime's virtual time.
     pub fn set_offset(&self, duration: Duration) {
+        self.check_manual();
         self.offset_nanos
             .store(duration.as_nanos() as u64, Ordering::Relaxed);
     }
 
     /// Get the current offset
     pub fn offset(&self) -> Duration {
+        #[cfg(any(test, feature = "runtime"))]
+        if let Some(runtime) = &self.runtime {
+            return runtime.elapsed();
+        }
         Duration::from_nanos(self.offset_nanos.load(Ordering::Relaxed))
     }
+
+    fn check_manual(&self) {
+        #[cfg(any(test, feature = "runtime"))]
+        assert!(
+            self.runtime.is_none(),
+            "clock follows a runtime's virtual time; advance the runtime instead"
+        );
+    }
 }
 
 impl Default for SimulatedClock {
@@ -66,8 +159,7 @@ impl Default for SimulatedClock {
 
 impl Clock for SimulatedClock {
     fn now(&self) -> Instant {
-        let offset = Duration::from_nanos(self.offset_nanos.load(Ordering::Relaxed));
-        self.base + offset
+        self.base + self.offset()
     }
 
     fn sleep(&self, duration: Duration) {
@@ -76,9 +168,20 @@ impl Clock for SimulatedClock {
     }
 }
 
+impl TimeSource for SimulatedClock {
+    fn instant(&self) -> Instant {
+        self.now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.wall_base + self.offset()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_system_clock() {
@@ -110,4 +213,17 @@ mod tests {
 
         assert_eq!(clock.offset() - initial_offset, Duration::from_secs(30));
     }
+
+    #[test]
+    fn test_simulated_time_source() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let clock = Arc::new(SimulatedClock::starting_at(start));
+        let source: Arc<dyn TimeSource> = clock.clone();
+        let t1 = source.instant();
+
+        clock.advance(Duration::from_secs(90));
+
+        assert_eq!(source.system_time(), start + Duration::from_secs(90));
+        assert_eq!(source.instant() - t1, Duration::from_secs(90));
+    }
 }
@@ -0,0 +1,213 @@
+//! Embeddable HTTP server for mock services.
+
+use crate::mock::{MockRequest, MockResponse, MockService};
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::Response;
+use axum::Router;
+use futures::StreamExt;
+use infra_errors::InfraResult;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// HTTP server answering with a [`MockService`]
+///
+/// The server listens on an ephemeral localhost port until dropped, so
+/// clients pointed at [`url`](Self::url) can be integration tested without
+/// network access. Requests reach the service through
+/// [`MockService::handle_request`] with their headers and query parameters.
+/// Response delays apply before the status line, and chunked bodies (such as
+/// [`MockResponse::sse`]) are streamed chunk by chunk.
+pub struct MockServer<S> {
+    service: Arc<S>,
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl<S: MockService + 'static> MockServer<S> {
+    /// Start serving on an ephemeral localhost port
+    pub async fn start(service: S) -> InfraResult<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let service = Arc::new(service);
+        let app = Router::new()
+            .fallback(serve::<S>)
+            .with_state(Arc::clone(&service));
+        let task = tokio::spawn(async move {
+            // Accept errors are retried by axum, so this only returns on abort
+            let _ = axum::serve(listener, app).await;
+        });
+
+        Ok(Self {
+            service,
+            addr,
+            task,
+        })
+    }
+}
+
+impl<S> MockServer<S> {
+    /// Get the address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Get the base URL, such as `http://127.0.0.1:40123`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Get the URL of a path on the server
+    pub fn uri(&self, path: &str) -> String {
+        format!("{}{path}", self.url())
+    }
+
+    /// Get the service answering requests
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+}
+
+impl<S> Drop for MockServer<S> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve<S: MockService>(
+    State(service): State<Arc<S>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    let mut request = MockRequest::new(method.as_str(), path, body.to_vec());
+    for (name, value) in &headers {
+        if let Ok(value) = value.to_str() {
+            request = request.with_header(name.as_str(), value);
+        }
+    }
+
+    match service.handle_request(&request).await {
+        Ok(response) => into_response(response),
+        Err(e) => plain(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+fn into_response(response: MockResponse) -> Response {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut builder = Response::builder().status(status);
+    for (name, value) in &response.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    let body = if response.chunks.is_empty() {
+        Body::from(response.body)
+    } else {
+        let delay = response.chunk_delay;
+        let chunks = futures::stream::iter(response.chunks).then(move |chunk| async move {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            Ok::<_, Infallible>(Bytes::from(chunk))
+        });
+        let head = futures::stream::once(async move { Ok(Bytes::from(response.body)) });
+        Body::from_stream(head.chain(chunks))
+    };
+
+    builder
+        .body(body)
+        .unwrap_or_else(|e| plain(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+fn plain(status: StatusCode, message: String) -> Response {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{MockBuilder, Route};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn send(server: &MockServer<impl MockService>, request: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(server.addr()).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_routes_and_captures() {
+        let server = MockServer::start(
+            MockBuilder::new()
+                .route(
+                    Route::post("/v1/items")
+                        .header("x-api-key", "secret")
+                        .respond(MockResponse::json(&serde_json::json!({"id": 7})).unwrap())
+                        .expect(1),
+                )
+                .build(),
+        )
+        .await
+        .unwrap();
+
+        let response = send(
+            &server,
+            "POST /v1/items?dry_run=1 HTTP/1.1\r\nHost: mock\r\nX-Api-Key: secret\r\n\
+             Content-Length: 9\r\nConnection: close\r\n\r\n{\"a\": 1}\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("content-type: application/json"));
+        assert!(response.ends_with("{\"id\":7}"));
+
+        let missing = send(
+            &server,
+            "GET /other HTTP/1.1\r\nHost: mock\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(missing.starts_with("HTTP/1.1 404"));
+
+        server.service().verify();
+        let requests = server.service().requests().await;
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].query["dry_run"], "1");
+        assert_eq!(requests[0].header("X-API-KEY"), Some("secret"));
+        assert_eq!(requests[0].body_string().as_deref(), Some("{\"a\": 1}\n"));
+        assert!(server.url().starts_with("http://127.0.0.1:"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_streams_sse() {
+        let server = MockServer::start(
+            MockBuilder::new()
+                .on_get(
+                    "/events",
+                    MockResponse::sse(["one", "two"]).with_chunk_delay(Duration::from_millis(5)),
+                )
+                .build(),
+        )
+        .await
+        .unwrap();
+
+        let response = send(
+            &server,
+            "GET /events HTTP/1.1\r\nHost: mock\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(response.contains("content-type: text/event-stream"));
+        assert!(response.contains("transfer-encoding: chunked"));
+        assert!(response.contains("data: one\n\n"));
+        assert!(response.contains("data: two\n\n"));
+    }
+}
@@ -2,16 +2,59 @@
 //!
 //! This crate provides mock implementations and simulation utilities
 //! for testing infrastructure components.
+//!
+//! With the `proptest` feature, the [`arbitrary`] module provides strategies
+//! for scenarios, chaos configurations and network links, and [`arbitrary::minimize`]
+//! shrinks a failing scenario to a minimal reproduction.
+//!
+//! With the `load` feature, [`LoadGenerator`] runs open- or closed-loop load
+//! against an async closure and reports latency percentiles from infra-otel
+//! histograms.
+//!
+//! With the `http` feature, [`MockServer`] serves any [`MockService`] over
+//! HTTP on a localhost port, so HTTP clients can be tested hermetically.
+//!
+//! With the `snapshot` feature, [`assert_json_snapshot!`] compares values with
+//! canonical JSON snapshots stored in the crate's `snapshots` directory, for
+//! prompt and response regression tests.
+//!
+//! With the `runtime` feature, [`SimRuntime`] and [`simulate`] run tasks with
+//! virtual time. It enables tokio's `test-util` feature.
 
 mod clock;
 mod mock;
 mod scenario;
 mod chaos;
+#[cfg(any(test, feature = "runtime"))]
+mod runtime;
+mod network;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "load")]
+mod load;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
 
-pub use clock::{Clock, SimulatedClock, SystemClock};
-pub use mock::{MockService, MockResponse, MockBuilder};
+pub use clock::{Clock, SimulatedClock, SystemClock, TimeSource};
+pub use mock::{BuiltMock, MockBuilder, MockRequest, MockResponse, MockService, Route};
+#[cfg(feature = "http")]
+pub use http::MockServer;
 pub use scenario::{Scenario, ScenarioBuilder, Step};
-pub use chaos::{ChaosConfig, ChaosMode, ChaosInjector};
+pub use chaos::{
+    ChaosConfig, ChaosEvent, ChaosInjector, ChaosMode, ChaosPolicy, Fault, FaultSchedule,
+    LatencyConfig,
+};
+#[cfg(feature = "snapshot")]
+pub use snapshot::{SnapshotMode, SnapshotOutcome, Snapshots, UPDATE_ENV};
+#[cfg(any(test, feature = "runtime"))]
+pub use runtime::{simulate, SimRuntime};
+#[cfg(feature = "load")]
+pub use load::{LoadGenerator, LoadMode, LoadReport, REPORT_QUANTILES};
+pub use network::{
+    LatencyDistribution, LinkConditions, NetworkFault, NetworkStats, NetworkedService, SimNetwork,
+};
 
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -0,0 +1,506 @@
+//! Load generation and latency reporting.
+
+use infra_otel::{Histogram, HistogramSnapshot};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+/// Quantiles tracked by the default latency histogram
+pub const REPORT_QUANTILES: &[f64] = &[0.5, 0.9, 0.95, 0.99, 0.999];
+
+/// How a [`LoadGenerator`] schedules operations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadMode {
+    /// Start operations at a fixed rate per second, however long they take
+    Open {
+        /// Operations started per second
+        rate: f64,
+    },
+    /// Run a fixed number of workers, each starting its next operation when
+    /// the previous one completes
+    Closed {
+        /// Number of workers
+        concurrency: usize,
+    },
+}
+
+/// Load generator firing operations against an async closure
+///
+/// Latencies of successful operations are recorded in seconds into an
+/// infra-otel [`Histogram`]. In open-loop mode, latency is measured from the
+/// time an operation was scheduled to start, so a slow system under test
+/// cannot hide its queueing delay (coordinated omission). Operations that
+/// would exceed the in-flight limit are not started and count as dropped.
+///
+/// Time is read from tokio, so a run inside a [`SimRuntime`](crate::SimRuntime)
+/// takes no wall-clock time when the operations only wait on timers.
+#[derive(Clone)]
+pub struct LoadGenerator {
+    name: String,
+    mode: LoadMode,
+    duration: Duration,
+    max_operations: Option<u64>,
+    max_in_flight: usize,
+    think_time: Duration,
+    histogram: Option<Arc<Histogram>>,
+}
+
+impl LoadGenerator {
+    /// Create an open-loop generator starting `rate` operations per second
+    pub fn open(rate: f64) -> Self {
+        Self::new(LoadMode::Open { rate })
+    }
+
+    /// Create a closed-loop generator with `concurrency` workers
+    pub fn closed(concurrency: usize) -> Self {
+        Self::new(LoadMode::Closed {
+            concurrency: concurrency.max(1),
+        })
+    }
+
+    fn new(mode: LoadMode) -> Self {
+        Self {
+            name: "load".to_string(),
+            mode,
+            duration: Duration::from_secs(10),
+            max_operations: None,
+            max_in_flight: 10_000,
+            think_time: Duration::ZERO,
+            histogram: None,
+        }
+    }
+
+    /// Set the name shown in reports
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Set how long to keep starting operations
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Stop after starting this many operations
+    pub fn max_operations(mut self, max: u64) -> Self {
+        self.max_operations = Some(max);
+        self
+    }
+
+    /// Set the limit of concurrent operations in open-loop mode
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = max.max(1);
+        self
+    }
+
+    /// Set the pause between operations of a closed-loop worker
+    pub fn think_time(mut self, think_time: Duration) -> Self {
+        self.think_time = think_time;
+        self
+    }
+
+    /// Also record latencies into an existing histogram, such as one from a
+    /// metrics registry
+    ///
+    /// Reports still only cover their own run, from a fresh histogram.
+    pub fn histogram(mut self, histogram: Arc<Histogram>) -> Self {
+        self.histogram = Some(histogram);
+        self
+    }
+
+    /// Get the scheduling mode
+    pub fn mode(&self) -> LoadMode {
+        self.mode
+    }
+
+    /// Run the load and wait for every started operation to complete
+    ///
+    /// `operation` receives the index of the operation, starting at zero.
+    pub async fn run<F, Fut, T, E>(&self, operation: F) -> LoadReport
+    where
+        F: Fn(u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let recorder = Arc::new(Recorder {
+            histogram: Histogram::new(format!("{}.latency", self.name))
+                .with_quantiles(REPORT_QUANTILES),
+            shared: self.histogram.clone(),
+            completed: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            in_flight: AtomicUsize::new(0),
+        });
+        let operation = Arc::new(operation);
+
+        let start = Instant::now();
+        match self.mode {
+            LoadMode::Open { rate } => self.run_open(rate, start, &recorder, operation).await,
+            LoadMode::Closed { concurrency } => {
+                self.run_closed(concurrency, start, &recorder, operation)
+                    .await;
+            }
+        }
+
+        LoadReport {
+            name: self.name.clone(),
+            mode: self.mode,
+            elapsed: start.elapsed(),
+            completed: recorder.completed.load(Ordering::Relaxed),
+            errors: recorder.errors.load(Ordering::Relaxed),
+            dropped: recorder.dropped.load(Ordering::Relaxed),
+            latency: recorder.histogram.snapshot(),
+        }
+    }
+
+    async fn run_open<F, Fut, T, E>(
+        &self,
+        rate: f64,
+        start: Instant,
+        recorder: &Arc<Recorder>,
+        operation: Arc<F>,
+    ) where
+        F: Fn(u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let deadline = start + self.duration;
+        let mut tasks = JoinSet::new();
+        let mut index = 0;
+
+        if rate > 0.0 && rate.is_finite() {
+            // Rates too low for a representable interval start one operation
+            let interval = Duration::try_from_secs_f64(1.0 / rate).unwrap_or(Duration::MAX);
+            let mut scheduled = start;
+            while scheduled < deadline && !self.max_operations.is_some_and(|max| index >= max) {
+                tokio::time::sleep_until(scheduled).await;
+                while tasks.try_join_next().is_some() {}
+
+                if recorder.in_flight.load(Ordering::Relaxed) >= self.max_in_flight {
+                    recorder.dropped.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    recorder.in_flight.fetch_add(1, Ordering::Relaxed);
+                    let recorder = Arc::clone(recorder);
+                    let fut = operation(index);
+                    tasks.spawn(async move {
+                        let ok = fut.await.is_ok();
+                        recorder.record(scheduled, ok);
+                        recorder.in_flight.fetch_sub(1, Ordering::Relaxed);
+                    });
+                }
+                index += 1;
+                match scheduled.checked_add(interval) {
+                    Some(next) => scheduled = next,
+                    None => break,
+                }
+            }
+        }
+
+        while tasks.join_next().await.is_some() {}
+    }
+
+    async fn run_closed<F, Fut, T, E>(
+        &self,
+        concurrency: usize,
+        start: Instant,
+        recorder: &Arc<Recorder>,
+        operation: Arc<F>,
+    ) where
+        F: Fn(u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let deadline = start + self.duration;
+        let issued = Arc::new(AtomicU64::new(0));
+        let mut workers = JoinSet::new();
+
+        for _ in 0..concurrency {
+            let recorder = Arc::clone(recorder);
+            let operation = Arc::clone(&operation);
+            let issued = Arc::clone(&issued);
+            let max_operations = self.max_operations;
+            let think_time = self.think_time;
+            workers.spawn(async move {
+                while Instant::now() < deadline {
+                    let index = issued.fetch_add(1, Ordering::Relaxed);
+                    if max_operations.is_some_and(|max| index >= max) {
+                        break;
+                    }
+                    let started = Instant::now();
+                    let ok = operation(index).await.is_ok();
+                    recorder.record(started, ok);
+                    if !think_time.is_zero() {
+                        tokio::time::sleep(think_time).await;
+                    }
+                }
+            });
+        }
+
+        while workers.join_next().await.is_some() {}
+    }
+}
+
+struct Recorder {
+    histogram: Histogram,
+    shared: Option<Arc<Histogram>>,
+    completed: AtomicU64,
+    errors: AtomicU64,
+    dropped: AtomicU64,
+    in_flight: AtomicUsize,
+}
+
+impl Recorder {
+    fn record(&self, started: Instant, ok: bool) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        if ok {
+            let latency = started.elapsed().as_secs_f64();
+            self.histogram.observe(latency);
+            if let Some(shared) = &self.shared {
+                shared.observe(latency);
+            }
+        } else {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Results of a [`LoadGenerator`] run
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    /// Name of the run
+    pub name: String,
+    /// Scheduling mode
+    pub mode: LoadMode,
+    /// Time from the first operation until the last one completed
+    pub elapsed: Duration,
+    /// Operations completed, successfully or not
+    pub completed: u64,
+    /// Operations that returned an error
+    pub errors: u64,
+    /// Operations not started because too many were in flight
+    pub dropped: u64,
+    /// Latencies of successful operations, in seconds
+    pub latency: HistogramSnapshot,
+}
+
+impl LoadReport {
+    /// Get the completed operations per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.completed as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Get the fraction of completed operations that failed
+    pub fn error_rate(&self) -> f64 {
+        if self.completed > 0 {
+            self.errors as f64 / self.completed as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Get the estimated latency at a quantile tracked by the histogram
+    pub fn percentile(&self, q: f64) -> Option<Duration> {
+        self.latency
+            .quantiles
+            .iter()
+            .find(|(tracked, _)| (tracked - q).abs() < f64::EPSILON)
+            .map(|(_, secs)| Duration::from_secs_f64(secs.max(0.0)))
+    }
+
+    /// Get the mean latency of successful operations
+    pub fn mean(&self) -> Option<Duration> {
+        let successes = self.completed - self.errors;
+        (successes > 0).then(|| Duration::from_secs_f64(self.latency.sum / successes as f64))
+    }
+
+    /// Export the report as JSON, with latencies in milliseconds
+    pub fn to_json(&self) -> serde_json::Value {
+        let mode = match self.mode {
+            LoadMode::Open { rate } => serde_json::json!({ "type": "open", "rate": rate }),
+            LoadMode::Closed { concurrency } => {
+                serde_json::json!({ "type": "closed", "concurrency": concurrency })
+            }
+        };
+        let percentiles: serde_json::Map<String, serde_json::Value> = self
+            .latency
+            .quantiles
+            .iter()
+            .map(|(q, secs)| (format!("p{}", q * 100.0), serde_json::json!(secs * 1000.0)))
+            .collect();
+
+        serde_json::json!({
+            "name": self.name,
+            "mode": mode,
+            "elapsed_ms": self.elapsed.as_secs_f64() * 1000.0,
+            "completed": self.completed,
+            "errors": self.errors,
+            "dropped": self.dropped,
+            "throughput": self.throughput(),
+            "error_rate": self.error_rate(),
+            "latency_ms": {
+                "mean": self.mean().map(|d| d.as_secs_f64() * 1000.0),
+                "percentiles": percentiles,
+            },
+        })
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            LoadMode::Open { rate } => writeln!(f, "{} (open loop, {rate} ops/s)", self.name)?,
+            LoadMode::Closed { concurrency } => {
+                writeln!(f, "{} (closed loop, {concurrency} workers)", self.name)?;
+            }
+        }
+        writeln!(
+            f,
+            "  {} completed, {} errors, {} dropped in {:.3}s ({:.1} ops/s)",
+            self.completed,
+            self.errors,
+            self.dropped,
+            self.elapsed.as_secs_f64(),
+            self.throughput()
+        )?;
+        if let Some(mean) = self.mean() {
+            writeln!(f, "  mean    {:>10.3}ms", mean.as_secs_f64() * 1000.0)?;
+        }
+        for (q, secs) in &self.latency.quantiles {
+            writeln!(f, "  p{:<6} {:>10.3}ms", q * 100.0, secs * 1000.0)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::simulate;
+
+    #[test]
+    fn test_open_loop_rate_and_latency() {
+        let report = simulate(
+            LoadGenerator::open(100.0)
+                .name("store.get")
+                .duration(Duration::from_secs(1))
+                .run(|_| async {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    Ok::<_, ()>(())
+                }),
+        );
+
+        assert_eq!(report.completed, 100);
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.percentile(0.5), Some(Duration::from_millis(20)));
+        assert_eq!(report.mean(), Some(Duration::from_millis(20)));
+        assert!((report.throughput() - 100.0 / 1.01).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_closed_loop_throughput() {
+        let report = simulate(
+            LoadGenerator::closed(4)
+                .duration(Duration::from_secs(1))
+                .run(|_| async {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, ()>(())
+                }),
+        );
+
+        assert_eq!(report.completed, 80);
+        assert!((report.throughput() - 80.0).abs() < 1e-6);
+        assert_eq!(report.percentile(0.99), Some(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn test_errors_and_max_operations() {
+        let report = simulate(
+            LoadGenerator::closed(2)
+                .max_operations(10)
+                .run(|i| async move {
+                    if i % 5 == 0 {
+                        Err("boom")
+                    } else {
+                        Ok(i)
+                    }
+                }),
+        );
+
+        assert_eq!(report.completed, 10);
+        assert_eq!(report.errors, 2);
+        assert!((report.error_rate() - 0.2).abs() < 1e-9);
+        assert_eq!(report.latency.count, 8);
+    }
+
+    #[test]
+    fn test_open_loop_drops_over_in_flight_limit() {
+        let report = simulate(
+            LoadGenerator::open(100.0)
+                .duration(Duration::from_millis(500))
+                .max_in_flight(10)
+                .run(|_| async {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    Ok::<_, ()>(())
+                }),
+        );
+
+        assert_eq!(report.completed, 10);
+        assert_eq!(report.dropped, 40);
+    }
+
+    #[test]
+    fn test_report_export() {
+        let histogram = Arc::new(Histogram::new("gateway.latency"));
+        let report = simulate(
+            LoadGenerator::closed(1)
+                .name("gateway")
+                .max_operations(5)
+                .histogram(Arc::clone(&histogram))
+                .run(|_| async {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    Ok::<_, ()>(())
+                }),
+        );
+
+        assert_eq!(histogram.count(), 5);
+        let json = report.to_json();
+        assert_eq!(json["mode"]["type"], "closed");
+        assert_eq!(json["completed"], 5);
+        assert_eq!(json["latency_ms"]["percentiles"]["p50"], 10.0);
+
+        let text = report.to_string();
+        assert!(text.starts_with("gateway (closed loop, 1 workers)"));
+        assert!(text.contains("p99"));
+
+        // A reused histogram keeps counting, but reports cover their own run
+        let report = simulate(
+            LoadGenerator::closed(1)
+                .max_operations(5)
+                .histogram(Arc::clone(&histogram))
+                .run(|_| async {
+                    tokio::time::sleep(Duration::from_millis(30)).await;
+                    Ok::<_, ()>(())
+                }),
+        );
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(report.latency.count, 5);
+        assert_eq!(report.mean(), Some(Duration::from_millis(30)));
+    }
+
+    #[test]
+    fn test_open_loop_tiny_rate() {
+        let report = simulate(
+            LoadGenerator::open(1e-30)
+                .duration(Duration::from_secs(1))
+                .run(|_| async { Ok::<_, ()>(()) }),
+        );
+        assert_eq!(report.completed, 1);
+    }
+}
@@ -3,10 +3,15 @@
 use async_trait::async_trait;
 use infra_errors::InfraResult;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 /// Mock response
+///
+/// Streamed bodies are only set through [`with_chunk`](Self::with_chunk) and
+/// [`sse`](Self::sse), so responses built from the public fields keep
+/// working as plain, unchunked responses.
 #[derive(Debug, Clone)]
 pub struct MockResponse {
     /// Response body
@@ -17,41 +22,64 @@ pub struct MockResponse {
     pub headers: HashMap<String, String>,
     /// Delay before responding
     pub delay: Option<std::time::Duration>,
+    pub(crate) chunks: Vec<Vec<u8>>,
+    pub(crate) chunk_delay: Option<std::time::Duration>,
 }
 
 impl MockResponse {
     /// Create a success response
     pub fn ok(body: impl Into<Vec<u8>>) -> Self {
-        Self {
-            body: body.into(),
-            status: 200,
-            headers: HashMap::new(),
-            delay: None,
-        }
+        Self::with_status(200, body)
     }
 
     /// Create an error response
     pub fn error(status: u16, message: &str) -> Self {
+        Self::with_status(status, message.as_bytes())
+    }
+
+    /// Create a response with a status and body
+    pub fn with_status(status: u16, body: impl Into<Vec<u8>>) -> Self {
         Self {
-            body: message.as_bytes().to_vec(),
+            body: body.into(),
             status,
             headers: HashMap::new(),
             delay: None,
+            chunks: Vec::new(),
+            chunk_delay: None,
         }
     }
 
     /// Create a JSON response
     pub fn json<T: serde::Serialize>(data: &T) -> Result<Self, serde_json::Error> {
         let body = serde_json::to_vec(data)?;
-        let mut headers = HashMap::new();
-        headers.insert("content-type".to_string(), "application/json".to_string());
+        Ok(Self::ok(body).with_header("content-type", "application/json"))
+    }
 
-        Ok(Self {
-            body,
-            status: 200,
-            headers,
-            delay: None,
-        })
+    /// Create a server-sent events response streaming one unnamed event per
+    /// item
+    ///
+    /// Each item is sent as its own chunk, so a [`chunk_delay`](Self::with_chunk_delay)
+    /// spaces the events out. Use [`with_sse_event`](Self::with_sse_event)
+    /// for named events.
+    pub fn sse<I>(events: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: std::fmt::Display,
+    {
+        let mut response = Self::ok(Vec::new())
+            .with_header("content-type", "text/event-stream")
+            .with_header("cache-control", "no-cache");
+        response.chunks = events
+            .into_iter()
+            .map(|data| encode_sse(None, &data.to_string()))
+            .collect();
+        response
+    }
+
+    /// Append a named server-sent event chunk
+    pub fn with_sse_event(self, event: &str, data: impl std::fmt::Display) -> Self {
+        let chunk = encode_sse(Some(event), &data.to_string());
+        self.with_chunk(chunk)
     }
 
     /// Set a delay
@@ -59,6 +87,113 @@ impl MockResponse {
         self.delay = Some(delay);
         self
     }
+
+    /// Set a header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers
+            .insert(name.into().to_lowercase(), value.into());
+        self
+    }
+
+    /// Append a body chunk, streamed after the previous ones
+    pub fn with_chunk(mut self, chunk: impl Into<Vec<u8>>) -> Self {
+        self.chunks.push(chunk.into());
+        self
+    }
+
+    /// Set a delay before each body chunk
+    pub fn with_chunk_delay(mut self, delay: std::time::Duration) -> Self {
+        self.chunk_delay = Some(delay);
+        self
+    }
+
+    /// Get the body with all chunks appended
+    pub fn full_body(&self) -> Vec<u8> {
+        let mut body = self.body.clone();
+        for chunk in &self.chunks {
+            body.extend_from_slice(chunk);
+        }
+        body
+    }
+}
+
+/// Encode a server-sent event
+///
+/// Data is split into one `data:` field per line, whatever the line ending,
+/// and line breaks are dropped from the event name, so no value can end a
+/// field early or inject another one.
+fn encode_sse(event: Option<&str>, data: &str) -> Vec<u8> {
+    let mut encoded = String::new();
+    if let Some(event) = event {
+        encoded.push_str("event: ");
+        encoded.extend(event.chars().filter(|c| !matches!(c, '\r' | '\n')));
+        encoded.push('\n');
+    }
+    for line in data.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+        encoded.push_str("data: ");
+        encoded.push_str(line);
+        encoded.push('\n');
+    }
+    encoded.push('\n');
+    encoded.into_bytes()
+}
+
+/// A request received by a mock service
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockRequest {
+    /// Request method, uppercase
+    pub method: String,
+    /// Request path, without the query string
+    pub path: String,
+    /// Query parameters
+    pub query: HashMap<String, String>,
+    /// Request headers, with lowercase names
+    pub headers: HashMap<String, String>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    /// Create a request, splitting the query string off the path
+    pub fn new(method: &str, path: &str, body: impl Into<Vec<u8>>) -> Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        Self {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            query: query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (name.to_string(), value.to_string())
+                })
+                .collect(),
+            headers: HashMap::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers
+            .insert(name.into().to_lowercase(), value.into());
+        self
+    }
+
+    /// Get a header by case-insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// Get the body as a string, if it is valid UTF-8
+    pub fn body_string(&self) -> Option<String> {
+        String::from_utf8(self.body.clone()).ok()
+    }
+
+    /// Parse the body as JSON
+    pub fn body_json<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
 }
 
 /// Mock service trait
@@ -66,32 +201,212 @@ impl MockResponse {
 pub trait MockService: Send + Sync {
     /// Handle a request
     async fn handle(&self, method: &str, path: &str, body: &[u8]) -> InfraResult<MockResponse>;
+
+    /// Handle a request with its headers and query parameters
+    ///
+    /// Defaults to [`handle`](Self::handle) with the method, path and body.
+    async fn handle_request(&self, request: &MockRequest) -> InfraResult<MockResponse> {
+        self.handle(&request.method, &request.path, &request.body)
+            .await
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PathMatcher {
+    Exact(String),
+    Prefix(String),
+}
+
+/// Route matching requests to a sequence of responses
+///
+/// A route matches a method and a path, plus optionally headers, query
+/// parameters and body contents. Its responses are returned in order, the
+/// last one repeating once the sequence is exhausted.
+#[derive(Debug, Clone)]
+pub struct Route {
+    method: Option<String>,
+    path: PathMatcher,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    body_contains: Vec<String>,
+    body_json: Option<serde_json::Value>,
+    responses: Vec<MockResponse>,
+    expected: Option<usize>,
+}
+
+impl Route {
+    /// Create a route for a method and exact path
+    ///
+    /// A query string in `path` is required like [`query`](Self::query)
+    /// parameters, so `/search?q=rust` matches any request for `/search`
+    /// with `q=rust`.
+    pub fn new(method: &str, path: &str) -> Self {
+        let request = MockRequest::new(method, path, Vec::new());
+        Self {
+            method: Some(request.method),
+            path: PathMatcher::Exact(request.path),
+            headers: Vec::new(),
+            query: request.query.into_iter().collect(),
+            body_contains: Vec::new(),
+            body_json: None,
+            responses: Vec::new(),
+            expected: None,
+        }
+    }
+
+    /// Create a route for any method on an exact path
+    pub fn any(path: &str) -> Self {
+        Self {
+            method: None,
+            ..Self::new("", path)
+        }
+    }
+
+    /// Create a GET route
+    pub fn get(path: &str) -> Self {
+        Self::new("GET", path)
+    }
+
+    /// Create a POST route
+    pub fn post(path: &str) -> Self {
+        Self::new("POST", path)
+    }
+
+    /// Create a PUT route
+    pub fn put(path: &str) -> Self {
+        Self::new("PUT", path)
+    }
+
+    /// Create a DELETE route
+    pub fn delete(path: &str) -> Self {
+        Self::new("DELETE", path)
+    }
+
+    /// Match every path starting with `prefix` instead of the exact path
+    pub fn path_prefix(mut self, prefix: &str) -> Self {
+        self.path = PathMatcher::Prefix(prefix.to_string());
+        self
+    }
+
+    /// Require a header value
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_lowercase(), value.into()));
+        self
+    }
+
+    /// Require a query parameter value
+    pub fn query(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.query.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Require the body to contain a string
+    pub fn body_contains(mut self, needle: impl Into<String>) -> Self {
+        self.body_contains.push(needle.into());
+        self
+    }
+
+    /// Require the body to be JSON containing `expected`
+    ///
+    /// Objects match if every expected field matches, recursively, so
+    /// `{"model": "gpt-4o"}` matches any request for that model. Other values
+    /// must be equal.
+    pub fn body_json(mut self, expected: serde_json::Value) -> Self {
+        self.body_json = Some(expected);
+        self
+    }
+
+    /// Add a response to the sequence
+    pub fn respond(mut self, response: MockResponse) -> Self {
+        self.responses.push(response);
+        self
+    }
+
+    /// Expect exactly `times` matching requests, checked by
+    /// [`BuiltMock::verify`]
+    pub fn expect(mut self, times: usize) -> Self {
+        self.expected = Some(times);
+        self
+    }
+
+    /// Check whether a request matches the route
+    pub fn matches(&self, request: &MockRequest) -> bool {
+        let path_matches = match &self.path {
+            PathMatcher::Exact(path) => request.path == *path,
+            PathMatcher::Prefix(prefix) => request.path.starts_with(prefix.as_str()),
+        };
+
+        path_matches
+            && self.method.iter().all(|m| *m == request.method)
+            && self
+                .headers
+                .iter()
+                .all(|(name, value)| request.header(name) == Some(value.as_str()))
+            && self
+                .query
+                .iter()
+                .all(|(name, value)| request.query.get(name) == Some(value))
+            && self.body_contains.iter().all(|needle| {
+                let body = String::from_utf8_lossy(&request.body);
+                body.contains(needle.as_str())
+            })
+            && self.body_json.iter().all(|expected| {
+                request
+                    .body_json::<serde_json::Value>()
+                    .is_ok_and(|actual| json_contains(&actual, expected))
+            })
+    }
+
+    fn describe(&self) -> String {
+        let method = self.method.as_deref().unwrap_or("*");
+        match &self.path {
+            PathMatcher::Exact(path) => format!("{method} {path}"),
+            PathMatcher::Prefix(prefix) => format!("{method} {prefix}*"),
+        }
+    }
+}
+
+fn json_contains(actual: &serde_json::Value, expected: &serde_json::Value) -> bool {
+    match (actual, expected) {
+        (serde_json::Value::Object(actual), serde_json::Value::Object(expected)) => {
+            expected.iter().all(|(key, value)| {
+                actual
+                    .get(key)
+                    .is_some_and(|actual| json_contains(actual, value))
+            })
+        }
+        _ => actual == expected,
+    }
 }
 
 /// Mock service builder
 pub struct MockBuilder {
-    responses: HashMap<(String, String), MockResponse>,
+    routes: Vec<Route>,
     default_response: Option<MockResponse>,
-    call_count: Arc<RwLock<HashMap<(String, String), usize>>>,
 }
 
 impl MockBuilder {
     /// Create a new mock builder
     pub fn new() -> Self {
         Self {
-            responses: HashMap::new(),
+            routes: Vec::new(),
             default_response: None,
-            call_count: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// Add a response for a method and path
-    pub fn on(mut self, method: &str, path: &str, response: MockResponse) -> Self {
-        self.responses
-            .insert((method.to_uppercase(), path.to_string()), response);
+    /// Add a route
+    ///
+    /// Routes added later take precedence over earlier ones.
+    pub fn route(mut self, route: Route) -> Self {
+        self.routes.push(route);
         self
     }
 
+    /// Add a response for a method and path
+    pub fn on(self, method: &str, path: &str, response: MockResponse) -> Self {
+        self.route(Route::new(method, path).respond(response))
+    }
+
     /// Add a GET response
     pub fn on_get(self, path: &str, response: MockResponse) -> Self {
         self.on("GET", path, response)
@@ -121,11 +436,12 @@ impl MockBuilder {
     /// Build the mock service
     pub fn build(self) -> BuiltMock {
         BuiltMock {
-            responses: self.responses,
-            default_response: self.default_response.unwrap_or_else(|| {
-                MockResponse::error(404, "Not Found")
-            }),
-            call_count: self.call_count,
+            hits: self.routes.iter().map(|_| AtomicUsize::new(0)).collect(),
+            routes: self.routes,
+            default_response: self
+                .default_response
+                .unwrap_or_else(|| MockResponse::error(404, "Not Found")),
+            requests: Arc::new(RwLock::new(Vec::new())),
         }
     }
 }
@@ -137,20 +453,26 @@ impl Default for MockBuilder {
 }
 
 /// Built mock service
+///
+/// Every request is captured, and can be inspected with
+/// [`requests`](Self::requests) and [`requests_matching`](Self::requests_matching).
 pub struct BuiltMock {
-    responses: HashMap<(String, String), MockResponse>,
+    routes: Vec<Route>,
+    hits: Vec<AtomicUsize>,
     default_response: MockResponse,
-    call_count: Arc<RwLock<HashMap<(String, String), usize>>>,
+    requests: Arc<RwLock<Vec<MockRequest>>>,
 }
 
 impl BuiltMock {
     /// Get call count for a method and path
     pub async fn call_count(&self, method: &str, path: &str) -> usize {
-        let counts = self.call_count.read().await;
-        counts
-            .get(&(method.to_uppercase(), path.to_string()))
-            .copied()
-            .unwrap_or(0)
+        let method = method.to_uppercase();
+        self.requests
+            .read()
+            .await
+            .iter()
+            .filter(|r| r.method == method && r.path == path)
+            .count()
     }
 
     /// Check if a method and path was called
@@ -158,28 +480,86 @@ impl BuiltMock {
         self.call_count(method, path).await > 0
     }
 
-    /// Reset call counts
+    /// Get every request received, oldest first
+    pub async fn requests(&self) -> Vec<MockRequest> {
+        self.requests.read().await.clone()
+    }
+
+    /// Get the requests a route matches, oldest first
+    pub async fn requests_matching(&self, route: &Route) -> Vec<MockRequest> {
+        self.requests
+            .read()
+            .await
+            .iter()
+            .filter(|r| route.matches(r))
+            .cloned()
+            .collect()
+    }
+
+    /// Check that every route with an [`expect`](Route::expect)ed count
+    /// served exactly that many requests
+    ///
+    /// # Panics
+    ///
+    /// Panics listing the routes whose expectations were not met.
+    pub fn verify(&self) {
+        let unmet: Vec<String> = self
+            .routes
+            .iter()
+            .zip(&self.hits)
+            .filter_map(|(route, hits)| {
+                let hits = hits.load(Ordering::SeqCst);
+                route
+                    .expected
+                    .filter(|expected| *expected != hits)
+                    .map(|expected| {
+                        format!(
+                            "{}: expected {expected} requests, got {hits}",
+                            route.describe()
+                        )
+                    })
+            })
+            .collect();
+        assert!(
+            unmet.is_empty(),
+            "unmet mock expectations:\n{}",
+            unmet.join("\n")
+        );
+    }
+
+    /// Reset call counts, captured requests and response sequences
     pub async fn reset(&self) {
-        self.call_count.write().await.clear();
+        self.requests.write().await.clear();
+        for hits in &self.hits {
+            hits.store(0, Ordering::SeqCst);
+        }
     }
 }
 
 #[async_trait]
 impl MockService for BuiltMock {
-    async fn handle(&self, method: &str, path: &str, _body: &[u8]) -> InfraResult<MockResponse> {
-        let key = (method.to_uppercase(), path.to_string());
+    async fn handle(&self, method: &str, path: &str, body: &[u8]) -> InfraResult<MockResponse> {
+        self.handle_request(&MockRequest::new(method, path, body))
+            .await
+    }
 
-        // Increment call count
-        {
-            let mut counts = self.call_count.write().await;
-            *counts.entry(key.clone()).or_insert(0) += 1;
-        }
+    async fn handle_request(&self, request: &MockRequest) -> InfraResult<MockResponse> {
+        self.requests.write().await.push(request.clone());
 
-        // Get response
+        // Later routes take precedence
         let response = self
-            .responses
-            .get(&key)
-            .cloned()
+            .routes
+            .iter()
+            .zip(&self.hits)
+            .rev()
+            .find(|(route, _)| route.matches(request))
+            .and_then(|(route, hits)| {
+                let hit = hits.fetch_add(1, Ordering::SeqCst);
+                route
+                    .responses
+                    .get(hit.min(route.responses.len().saturating_sub(1)))
+                    .cloned()
+            })
             .unwrap_or_else(|| self.default_response.clone());
 
         // Apply delay if configured
@@ -194,6 +574,7 @@ impl MockService for BuiltMock {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[tokio::test]
     async fn test_mock_builder() {
@@ -218,4 +599,109 @@ mod tests {
         let response = mock.handle("GET", "/unknown", &[]).await.unwrap();
         assert_eq!(response.status, 500);
     }
+
+    #[tokio::test]
+    async fn test_route_matchers() {
+        let mock = MockBuilder::new()
+            .route(
+                Route::any("/v1")
+                    .path_prefix("/v1/")
+                    .respond(MockResponse::ok("v1")),
+            )
+            .route(
+                Route::post("/v1/chat")
+                    .header("Authorization", "Bearer key")
+                    .query("stream", "true")
+                    .body_json(json!({"model": "gpt-4o"}))
+                    .respond(MockResponse::ok("chat")),
+            )
+            .build();
+
+        let request = MockRequest::new(
+            "post",
+            "/v1/chat?stream=true",
+            br#"{"model":"gpt-4o","n":1}"#.to_vec(),
+        )
+        .with_header("authorization", "Bearer key");
+        assert_eq!(mock.handle_request(&request).await.unwrap().body, b"chat");
+
+        let wrong_model = MockRequest::new(
+            "POST",
+            "/v1/chat?stream=true",
+            br#"{"model":"other"}"#.to_vec(),
+        )
+        .with_header("authorization", "Bearer key");
+        assert_eq!(mock.handle_request(&wrong_model).await.unwrap().body, b"v1");
+
+        let response = mock.handle("GET", "/v2/chat", &[]).await.unwrap();
+        assert_eq!(response.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_query_in_route_path() {
+        let mock = MockBuilder::new()
+            .on_get("/search?q=rust", MockResponse::ok("rust"))
+            .build();
+
+        let response = mock.handle("GET", "/search?n=2&q=rust", &[]).await.unwrap();
+        assert_eq!(response.body, b"rust");
+        let response = mock.handle("GET", "/search?q=go", &[]).await.unwrap();
+        assert_eq!(response.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_sequenced_responses_and_expectations() {
+        let mock = MockBuilder::new()
+            .route(
+                Route::post("/jobs")
+                    .respond(MockResponse::error(503, "busy"))
+                    .respond(MockResponse::ok("done"))
+                    .expect(3),
+            )
+            .build();
+
+        let statuses = [
+            mock.handle("POST", "/jobs", b"1").await.unwrap().status,
+            mock.handle("POST", "/jobs", b"2").await.unwrap().status,
+            mock.handle("POST", "/jobs", b"3").await.unwrap().status,
+        ];
+        assert_eq!(statuses, [503, 200, 200]);
+        mock.verify();
+
+        let captured = mock
+            .requests_matching(&Route::post("/jobs").body_contains("2"))
+            .await;
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].body_string().as_deref(), Some("2"));
+
+        mock.reset().await;
+        assert_eq!(mock.handle("POST", "/jobs", &[]).await.unwrap().status, 503);
+        assert!(mock.requests().await.len() == 1);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "POST /jobs: expected 2 requests, got 1")]
+    async fn test_verify_unmet_expectation() {
+        let mock = MockBuilder::new()
+            .route(Route::post("/jobs").respond(MockResponse::ok("")).expect(2))
+            .build();
+        mock.handle("POST", "/jobs", &[]).await.unwrap();
+        mock.verify();
+    }
+
+    #[test]
+    fn test_sse_response() {
+        let response = MockResponse::sse([json!({"delta": "Hi"})])
+            .with_sse_event("message", "line one\nline two\r\nline three\rline four")
+            .with_sse_event("bad\r\nevent: injected", "[DONE]");
+
+        assert_eq!(response.headers["content-type"], "text/event-stream");
+        assert_eq!(response.chunks.len(), 3);
+        assert_eq!(
+            String::from_utf8(response.full_body()).unwrap(),
+            "data: {\"delta\":\"Hi\"}\n\n\
+             event: message\ndata: line one\ndata: line two\ndata: line three\ndata: line four\n\n\
+             event: badevent: injected\ndata: [DONE]\n\n"
+        );
+    }
 }
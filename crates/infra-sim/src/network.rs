@@ -0,0 +1,448 @@
+//! Network condition simulation.
+
+use crate::mock::{MockRequest, MockResponse, MockService};
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Distribution of one-way link latency
+#[derive(Debug, Clone, PartialEq)]
+pub enum LatencyDistribution {
+    /// Always the same latency
+    Constant(Duration),
+    /// Uniformly distributed between two latencies
+    Uniform {
+        /// Minimum latency
+        min: Duration,
+        /// Maximum latency
+        max: Duration,
+    },
+    /// Normally distributed, truncated at zero
+    Normal {
+        /// Mean latency
+        mean: Duration,
+        /// Standard deviation
+        std_dev: Duration,
+    },
+    /// Exponentially distributed, for long-tailed links
+    Exponential {
+        /// Mean latency
+        mean: Duration,
+    },
+}
+
+impl LatencyDistribution {
+    /// Sample a latency
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        match self {
+            Self::Constant(latency) => *latency,
+            Self::Uniform { min, max } if max > min => rng.gen_range(*min..=*max),
+            Self::Uniform { min, .. } => *min,
+            Self::Normal { mean, std_dev } => {
+                // Box-Muller transform
+                let u1: f64 = 1.0 - rng.gen::<f64>();
+                let u2: f64 = rng.gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                let secs = mean.as_secs_f64() + z * std_dev.as_secs_f64();
+                Duration::from_secs_f64(secs.max(0.0))
+            }
+            Self::Exponential { mean } => {
+                let u: f64 = 1.0 - rng.gen::<f64>();
+                Duration::from_secs_f64(-u.ln() * mean.as_secs_f64())
+            }
+        }
+    }
+}
+
+impl Default for LatencyDistribution {
+    fn default() -> Self {
+        Self::Constant(Duration::ZERO)
+    }
+}
+
+/// Conditions of a one-way link between two nodes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkConditions {
+    /// Latency distribution
+    pub latency: LatencyDistribution,
+    /// Extra latency, uniformly distributed between zero and this
+    pub jitter: Duration,
+    /// Probability of losing a message (0.0 to 1.0)
+    pub loss: f64,
+}
+
+impl LinkConditions {
+    /// Create a perfect link
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the latency distribution
+    pub fn latency(mut self, latency: LatencyDistribution) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Set a constant latency
+    pub fn constant_latency(self, latency: Duration) -> Self {
+        self.latency(LatencyDistribution::Constant(latency))
+    }
+
+    /// Set the jitter
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the loss probability
+    pub fn loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Why a message did not reach its destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkFault {
+    /// The nodes are on different sides of a partition
+    Partitioned {
+        /// Sending node
+        from: String,
+        /// Receiving node
+        to: String,
+    },
+    /// The message was lost on the link
+    Dropped {
+        /// Sending node
+        from: String,
+        /// Receiving node
+        to: String,
+    },
+}
+
+impl fmt::Display for NetworkFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Partitioned { from, to } => {
+                write!(f, "Network partition between {from} and {to}")
+            }
+            Self::Dropped { from, to } => write!(f, "Message from {from} to {to} was lost"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkFault {}
+
+/// Counts of simulated transmissions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Messages delivered
+    pub delivered: u64,
+    /// Messages lost on a link
+    pub dropped: u64,
+    /// Messages blocked by a partition
+    pub partitioned: u64,
+}
+
+#[derive(Default)]
+struct NetworkState {
+    default: LinkConditions,
+    links: HashMap<(String, String), LinkConditions>,
+    partitions: Vec<(HashSet<String>, HashSet<String>)>,
+    isolated: HashSet<String>,
+    stats: NetworkStats,
+}
+
+impl NetworkState {
+    fn is_partitioned(&self, from: &str, to: &str) -> bool {
+        from != to
+            && (self.isolated.contains(from)
+                || self.isolated.contains(to)
+                || self.partitions.iter().any(|(a, b)| {
+                    (a.contains(from) && b.contains(to)) || (a.contains(to) && b.contains(from))
+                }))
+    }
+}
+
+/// Simulated network between named nodes
+///
+/// Components route their traffic through [`transmit`](Self::transmit),
+/// which waits for the link latency and fails when the message is lost or
+/// the nodes are partitioned. Lost messages fail immediately rather than
+/// hanging, so tests need no timeouts. Waiting uses tokio timers, so a
+/// [`SimRuntime`](crate::SimRuntime) skips it instantly.
+///
+/// Clones share the same network.
+#[derive(Clone, Default)]
+pub struct SimNetwork {
+    state: Arc<Mutex<NetworkState>>,
+}
+
+impl SimNetwork {
+    /// Create a network of perfect links
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a network whose links default to some conditions
+    pub fn with_default(conditions: LinkConditions) -> Self {
+        let network = Self::new();
+        network.lock().default = conditions;
+        network
+    }
+
+    fn lock(&self) -> MutexGuard<'_, NetworkState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Set the conditions of the link from one node to another
+    pub fn set_link(&self, from: &str, to: &str, conditions: LinkConditions) {
+        self.lock()
+            .links
+            .insert((from.to_string(), to.to_string()), conditions);
+    }
+
+    /// Set the conditions of the links between two nodes, both ways
+    pub fn set_links(&self, a: &str, b: &str, conditions: LinkConditions) {
+        self.set_link(a, b, conditions.clone());
+        self.set_link(b, a, conditions);
+    }
+
+    /// Get the conditions of the link from one node to another
+    pub fn link(&self, from: &str, to: &str) -> LinkConditions {
+        let state = self.lock();
+        state
+            .links
+            .get(&(from.to_string(), to.to_string()))
+            .unwrap_or(&state.default)
+            .clone()
+    }
+
+    /// Partition two groups of nodes from each other
+    pub fn partition(&self, a: &[&str], b: &[&str]) {
+        let group = |nodes: &[&str]| nodes.iter().map(ToString::to_string).collect();
+        self.lock().partitions.push((group(a), group(b)));
+    }
+
+    /// Cut a node off from every other node
+    pub fn isolate(&self, node: &str) {
+        self.lock().isolated.insert(node.to_string());
+    }
+
+    /// Remove all partitions and isolations
+    pub fn heal(&self) {
+        let mut state = self.lock();
+        state.partitions.clear();
+        state.isolated.clear();
+    }
+
+    /// Check if a node can currently reach another
+    pub fn is_reachable(&self, from: &str, to: &str) -> bool {
+        !self.lock().is_partitioned(from, to)
+    }
+
+    /// Get the transmission counts
+    pub fn stats(&self) -> NetworkStats {
+        self.lock().stats
+    }
+
+    /// Send a message from one node to another
+    ///
+    /// Waits for the sampled latency of a delivered message.
+    pub async fn transmit(&self, from: &str, to: &str) -> Result<(), NetworkFault> {
+        let latency = {
+            let mut state = self.lock();
+            if state.is_partitioned(from, to) {
+                state.stats.partitioned += 1;
+                return Err(NetworkFault::Partitioned {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+            }
+
+            let conditions = state
+                .links
+                .get(&(from.to_string(), to.to_string()))
+                .unwrap_or(&state.default);
+            let mut rng = rand::thread_rng();
+            if conditions.loss > 0.0 && rng.gen::<f64>() < conditions.loss {
+                state.stats.dropped += 1;
+                return Err(NetworkFault::Dropped {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                });
+            }
+            let jitter = if conditions.jitter.is_zero() {
+                Duration::ZERO
+            } else {
+                rng.gen_range(Duration::ZERO..=conditions.jitter)
+            };
+            let latency = conditions.latency.sample(&mut rng) + jitter;
+            state.stats.delivered += 1;
+            latency
+        };
+
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        Ok(())
+    }
+
+    /// Put a mock service on a node, reached from another node
+    pub fn service<S: MockService>(
+        &self,
+        client: impl Into<String>,
+        server: impl Into<String>,
+        service: S,
+    ) -> NetworkedService<S> {
+        NetworkedService {
+            network: self.clone(),
+            client: client.into(),
+            server: server.into(),
+            inner: service,
+        }
+    }
+}
+
+/// Mock service reached over a [`SimNetwork`]
+///
+/// Requests travel from the client node to the server node and responses
+/// back, so a response can be lost after the service handled the request.
+/// Lost traffic fails with an HTTP error without status, like a transport
+/// failure.
+pub struct NetworkedService<S> {
+    network: SimNetwork,
+    client: String,
+    server: String,
+    inner: S,
+}
+
+impl<S> NetworkedService<S> {
+    /// Get the wrapped service
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<S: MockService> MockService for NetworkedService<S> {
+    async fn handle(&self, method: &str, path: &str, body: &[u8]) -> InfraResult<MockResponse> {
+        self.handle_request(&MockRequest::new(method, path, body))
+            .await
+    }
+
+    async fn handle_request(&self, request: &MockRequest) -> InfraResult<MockResponse> {
+        let fault = |fault: NetworkFault| InfraError::Http {
+            status: None,
+            message: fault.to_string(),
+            url: Some(request.path.clone()),
+            context: None,
+            source: None,
+        };
+
+        self.network
+            .transmit(&self.client, &self.server)
+            .await
+            .map_err(fault)?;
+        let response = self.inner.handle_request(request).await?;
+        self.network
+            .transmit(&self.server, &self.client)
+            .await
+            .map_err(fault)?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockBuilder;
+    use crate::runtime::simulate;
+
+    #[test]
+    fn test_latency_distributions() {
+        let mut rng = rand::thread_rng();
+        let min = Duration::from_millis(10);
+        let max = Duration::from_millis(20);
+        for _ in 0..100 {
+            let latency = LatencyDistribution::Uniform { min, max }.sample(&mut rng);
+            assert!(latency >= min && latency <= max);
+
+            let latency = LatencyDistribution::Normal {
+                mean: min,
+                std_dev: max,
+            }
+            .sample(&mut rng);
+            assert!(latency < Duration::from_secs(10));
+        }
+        assert_eq!(LatencyDistribution::Constant(min).sample(&mut rng), min);
+    }
+
+    #[test]
+    fn test_transmit_latency() {
+        let network = SimNetwork::with_default(
+            LinkConditions::new()
+                .constant_latency(Duration::from_millis(50))
+                .jitter(Duration::from_millis(10)),
+        );
+
+        let elapsed = simulate(async {
+            let start = tokio::time::Instant::now();
+            network.transmit("a", "b").await.unwrap();
+            start.elapsed()
+        });
+
+        assert!(elapsed >= Duration::from_millis(50));
+        assert!(elapsed <= Duration::from_millis(61));
+        assert_eq!(network.stats().delivered, 1);
+    }
+
+    #[tokio::test]
+    async fn test_partition_and_loss() {
+        let network = SimNetwork::new();
+        network.set_link("a", "c", LinkConditions::new().loss(1.0));
+        network.partition(&["a"], &["b"]);
+
+        assert!(matches!(
+            network.transmit("b", "a").await,
+            Err(NetworkFault::Partitioned { .. })
+        ));
+        assert!(matches!(
+            network.transmit("a", "c").await,
+            Err(NetworkFault::Dropped { .. })
+        ));
+        assert!(network.transmit("c", "a").await.is_ok());
+
+        network.isolate("c");
+        assert!(!network.is_reachable("a", "c"));
+        network.heal();
+        assert!(network.is_reachable("a", "b"));
+
+        let stats = network.stats();
+        assert_eq!(
+            (stats.delivered, stats.dropped, stats.partitioned),
+            (1, 1, 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_networked_service() {
+        let network = SimNetwork::new();
+        let mock = MockBuilder::new()
+            .on_post("/jobs", MockResponse::ok(b"{}".to_vec()))
+            .build();
+        let service = network.service("client", "server", mock);
+
+        assert!(service.handle("POST", "/jobs", &[]).await.is_ok());
+
+        // The request arrives but the response is lost
+        network.set_link("server", "client", LinkConditions::new().loss(1.0));
+        let err = service.handle("POST", "/jobs", &[]).await.unwrap_err();
+        assert!(matches!(err, InfraError::Http { status: None, .. }));
+        assert_eq!(service.inner().call_count("POST", "/jobs").await, 2);
+    }
+}
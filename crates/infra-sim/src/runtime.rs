@@ -0,0 +1,175 @@
+//! Deterministic simulation runtime.
+
+use crate::clock::SimulatedClock;
+use infra_errors::InfraResult;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::{Builder, Handle, Runtime};
+use tokio::task::JoinHandle;
+
+/// Runtime driving tokio tasks with virtual time
+///
+/// Tasks run on a single thread and time is paused: whenever every task is
+/// waiting, time jumps to the next timer. Retries, rate limiter windows and
+/// timeouts spanning minutes thus complete in milliseconds. The runtime's
+/// [`SimulatedClock`] follows the virtual time, so components reading a
+/// [`Clock`](crate::Clock) agree with tokio's timers.
+///
+/// Tasks are scheduled in a deterministic order, but tokio also makes random
+/// choices, such as which ready branch of a `select!` runs. Those are seeded
+/// only when building with `--cfg tokio_unstable`; otherwise use `biased;`
+/// selects for runs that must be reproducible.
+pub struct SimRuntime {
+    runtime: Runtime,
+    clock: Arc<SimulatedClock>,
+}
+
+impl SimRuntime {
+    /// Create a new simulation runtime
+    pub fn new() -> InfraResult<Self> {
+        let mut builder = Builder::new_current_thread();
+        builder.enable_time().start_paused(true);
+        #[cfg(tokio_unstable)]
+        builder.rng_seed(tokio::runtime::RngSeed::from_bytes(b"infra-sim"));
+        let runtime = builder.build()?;
+        let clock = Arc::new(SimulatedClock::driven_by(runtime.handle().clone()));
+        Ok(Self { runtime, clock })
+    }
+
+    /// Get the clock following the virtual time
+    pub fn clock(&self) -> Arc<SimulatedClock> {
+        Arc::clone(&self.clock)
+    }
+
+    /// Get the runtime handle
+    pub fn handle(&self) -> &Handle {
+        self.runtime.handle()
+    }
+
+    /// Get the virtual time elapsed since the runtime was created
+    pub fn elapsed(&self) -> Duration {
+        self.clock.offset()
+    }
+
+    /// Run a future to completion, auto-advancing time while it waits
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Spawn a task, run by the next [`block_on`](Self::block_on)
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.runtime.spawn(future)
+    }
+
+    /// Advance the virtual time, firing the timers due meanwhile
+    pub fn advance(&self, duration: Duration) {
+        self.runtime.block_on(tokio::time::advance(duration));
+    }
+}
+
+/// Run a future on a fresh [`SimRuntime`]
+///
+/// # Panics
+///
+/// Panics if the runtime cannot be created.
+pub fn simulate<F: Future>(future: F) -> F::Output {
+    SimRuntime::new()
+        .expect("failed to create simulation runtime")
+        .block_on(future)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_auto_advance() {
+        let runtime = SimRuntime::new().unwrap();
+        let clock = runtime.clock();
+        let start = clock.now();
+        let wall = std::time::Instant::now();
+
+        let attempts = runtime.block_on(async {
+            let mut attempts = 0;
+            let mut backoff = Duration::from_secs(1);
+            while attempts < 10 {
+                attempts += 1;
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            attempts
+        });
+
+        assert_eq!(attempts, 10);
+        assert_eq!(runtime.elapsed(), Duration::from_secs(1023));
+        assert_eq!(clock.now() - start, Duration::from_secs(1023));
+        assert!(wall.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let result = simulate(async {
+            tokio::time::timeout(
+                Duration::from_secs(30),
+                tokio::time::sleep(Duration::from_secs(3600)),
+            )
+            .await
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        let run = || {
+            let runtime = SimRuntime::new().unwrap();
+            let order = Arc::new(Mutex::new(Vec::new()));
+            let handles: Vec<_> = [30, 10, 20, 10]
+                .into_iter()
+                .enumerate()
+                .map(|(i, secs)| {
+                    let order = Arc::clone(&order);
+                    runtime.spawn(async move {
+                        tokio::time::sleep(Duration::from_secs(secs)).await;
+                        order.lock().unwrap().push(i);
+                    })
+                })
+                .collect();
+            runtime.block_on(async {
+                for handle in handles {
+                    handle.await.unwrap();
+                }
+            });
+            assert_eq!(runtime.elapsed(), Duration::from_secs(30));
+            let order = order.lock().unwrap().clone();
+            order
+        };
+
+        let first = run();
+        assert_eq!(first[3], 0);
+        assert_eq!(first, run());
+    }
+
+    #[test]
+    fn test_advance() {
+        let runtime = SimRuntime::new().unwrap();
+        let clock = runtime.clock();
+
+        runtime.advance(Duration::from_secs(60));
+        assert_eq!(clock.offset(), Duration::from_secs(60));
+        assert_eq!(runtime.elapsed(), Duration::from_secs(60));
+    }
+
+    #[test]
+    #[should_panic(expected = "follows a runtime")]
+    fn test_manual_advance_rejected() {
+        let runtime = SimRuntime::new().unwrap();
+        runtime.clock().advance(Duration::from_secs(5));
+    }
+}
@@ -38,6 +38,7 @@ impl Step {
 }
 
 /// Test scenario
+#[derive(Debug, Clone)]
 pub struct Scenario {
     /// Scenario name
     name: String,
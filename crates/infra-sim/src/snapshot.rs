@@ -0,0 +1,267 @@
+//! JSON snapshot assertions for regression tests.
+
+use infra_errors::InfraResult;
+use infra_json::{diff, Json, JsonDiff};
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable selecting the [`SnapshotMode`]
+pub const UPDATE_ENV: &str = "INFRA_UPDATE_SNAPSHOTS";
+
+/// How snapshot assertions treat missing and mismatched snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotMode {
+    /// Fail on missing and mismatched snapshots (`INFRA_UPDATE_SNAPSHOTS=no`)
+    Check,
+    /// Write missing snapshots, fail on mismatched ones (the default)
+    #[default]
+    New,
+    /// Write missing and mismatched snapshots (`INFRA_UPDATE_SNAPSHOTS=1`)
+    Overwrite,
+}
+
+impl SnapshotMode {
+    /// Read the mode from [`UPDATE_ENV`]
+    ///
+    /// `1`, `true`, `yes` and `always` overwrite, `0`, `false` and `no`
+    /// check, and anything else (or nothing) writes new snapshots only.
+    pub fn from_env() -> Self {
+        match std::env::var(UPDATE_ENV).as_deref() {
+            Ok("1" | "true" | "yes" | "always") => Self::Overwrite,
+            Ok("0" | "false" | "no") => Self::Check,
+            _ => Self::New,
+        }
+    }
+}
+
+/// Result of comparing a value with its snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    /// The snapshot matched
+    Matched,
+    /// The snapshot was missing and has been written
+    Created,
+    /// The snapshot differed and has been overwritten
+    Updated,
+    /// The snapshot is missing
+    Missing,
+    /// The snapshot differed, with a readable diff from snapshot to value
+    Mismatch(String),
+}
+
+/// Directory of JSON snapshots
+///
+/// Each snapshot is stored as `<dir>/<name>.json` in canonical form
+/// (RFC 8785, see [`infra_json::canonicalize`]), so key order and number
+/// formatting never cause spurious mismatches. Names may contain `/` to
+/// group snapshots in subdirectories.
+///
+/// Most tests use [`assert_json_snapshot!`](crate::assert_json_snapshot),
+/// which stores snapshots under the crate's `snapshots` directory.
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    mode: SnapshotMode,
+}
+
+impl Snapshots {
+    /// Create a snapshot directory, with the mode from the environment
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: SnapshotMode::from_env(),
+        }
+    }
+
+    /// Set the mode
+    pub fn with_mode(mut self, mode: SnapshotMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Get the directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the path of a snapshot
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.json"))
+    }
+
+    /// Compare a value with its snapshot, writing it as the mode allows
+    pub fn check(&self, name: &str, value: &Json) -> InfraResult<SnapshotOutcome> {
+        let path = self.path(name);
+        let actual = value.to_canonical_string();
+
+        if !infra_fs::exists(&path) {
+            if self.mode == SnapshotMode::Check {
+                return Ok(SnapshotOutcome::Missing);
+            }
+            infra_fs::write(&path, format!("{actual}\n").as_bytes())?;
+            return Ok(SnapshotOutcome::Created);
+        }
+
+        let stored = Json::parse(&infra_fs::read_string(&path)?)?;
+        if stored.to_canonical_string() == actual {
+            return Ok(SnapshotOutcome::Matched);
+        }
+        if self.mode == SnapshotMode::Overwrite {
+            infra_fs::write(&path, format!("{actual}\n").as_bytes())?;
+            return Ok(SnapshotOutcome::Updated);
+        }
+        Ok(SnapshotOutcome::Mismatch(render_diff(&diff(
+            &stored, value,
+        ))))
+    }
+
+    /// Assert that a value matches its snapshot
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value cannot be serialized, the snapshot cannot be
+    /// read or written, or the snapshot is missing or differs.
+    #[track_caller]
+    pub fn assert<T: Serialize>(&self, name: &str, value: &T) {
+        let value = Json::from_value(value)
+            .unwrap_or_else(|e| panic!("snapshot `{name}`: cannot serialize value: {e}"));
+        let path = self.path(name);
+
+        match self.check(name, &value) {
+            Ok(SnapshotOutcome::Matched | SnapshotOutcome::Created | SnapshotOutcome::Updated) => {}
+            Ok(SnapshotOutcome::Missing) => panic!(
+                "snapshot `{name}` is missing at {}\n\
+                 rerun without {UPDATE_ENV}=no to create it",
+                path.display()
+            ),
+            Ok(SnapshotOutcome::Mismatch(diff)) => panic!(
+                "snapshot `{name}` does not match {}\n{diff}\
+                 rerun with {UPDATE_ENV}=1 to accept the new value",
+                path.display()
+            ),
+            Err(e) => panic!("snapshot `{name}`: {e}"),
+        }
+    }
+}
+
+/// Render differences one per line: `-` removed, `+` added, `~` changed
+fn render_diff(diffs: &[JsonDiff]) -> String {
+    let path = |path: &str| {
+        if path.is_empty() {
+            "$".to_string()
+        } else {
+            path.to_string()
+        }
+    };
+
+    let mut out = String::new();
+    for d in diffs {
+        let _ = match d {
+            JsonDiff::Removed { path: p, value } => {
+                writeln!(out, "- {}: {}", path(p), value.to_canonical_string())
+            }
+            JsonDiff::Added { path: p, value } => {
+                writeln!(out, "+ {}: {}", path(p), value.to_canonical_string())
+            }
+            JsonDiff::Changed { path: p, old, new } => writeln!(
+                out,
+                "~ {}: {} -> {}",
+                path(p),
+                old.to_canonical_string(),
+                new.to_canonical_string()
+            ),
+        };
+    }
+    out
+}
+
+/// Assert that a serializable value matches a JSON snapshot
+///
+/// Snapshots are stored in canonical form as
+/// `$CARGO_MANIFEST_DIR/snapshots/<name>.json`. Missing snapshots are
+/// written on first run; set `INFRA_UPDATE_SNAPSHOTS=1` to accept changed
+/// values, or `INFRA_UPDATE_SNAPSHOTS=no` (e.g. in CI) to fail on missing
+/// snapshots too. See [`Snapshots`].
+///
+/// ```ignore
+/// let request = client.build_request(&prompt)?;
+/// infra_sim::assert_json_snapshot!("openai/chat_request", request);
+/// ```
+#[macro_export]
+macro_rules! assert_json_snapshot {
+    ($name:expr, $value:expr $(,)?) => {
+        $crate::Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots"))
+            .assert($name, &$value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_fs::TempDir;
+    use serde_json::json;
+
+    fn snapshots(dir: &TempDir, mode: SnapshotMode) -> Snapshots {
+        Snapshots::new(dir.path()).with_mode(mode)
+    }
+
+    #[test]
+    fn test_snapshot_created_then_matched() {
+        let dir = TempDir::new().unwrap();
+        let store = snapshots(&dir, SnapshotMode::New);
+        let value = Json::from(json!({"model": "gpt-4", "temperature": 1.0, "messages": []}));
+
+        assert_eq!(
+            store.check("chat/request", &value).unwrap(),
+            SnapshotOutcome::Created
+        );
+        assert_eq!(
+            infra_fs::read_string(store.path("chat/request")).unwrap(),
+            "{\"messages\":[],\"model\":\"gpt-4\",\"temperature\":1}\n"
+        );
+
+        let reordered = Json::from(json!({"temperature": 1, "messages": [], "model": "gpt-4"}));
+        assert_eq!(
+            store.check("chat/request", &reordered).unwrap(),
+            SnapshotOutcome::Matched
+        );
+        store.assert("chat/request", &reordered);
+    }
+
+    #[test]
+    fn test_snapshot_mismatch_diff() {
+        let dir = TempDir::new().unwrap();
+        let store = snapshots(&dir, SnapshotMode::New);
+        store.assert(
+            "response",
+            &json!({"text": "hello", "tokens": [1, 2], "stop": true}),
+        );
+
+        let changed = Json::from(json!({"text": "hullo", "tokens": [1], "usage": 3}));
+        let SnapshotOutcome::Mismatch(diff) = store.check("response", &changed).unwrap() else {
+            panic!("expected a mismatch");
+        };
+        assert!(diff.contains("~ text: \"hello\" -> \"hullo\"\n"));
+        assert!(diff.contains("- tokens[1]: 2\n"));
+        assert!(diff.contains("- stop: true\n"));
+        assert!(diff.contains("+ usage: 3\n"));
+
+        let overwrite = snapshots(&dir, SnapshotMode::Overwrite);
+        assert_eq!(
+            overwrite.check("response", &changed).unwrap(),
+            SnapshotOutcome::Updated
+        );
+        assert_eq!(
+            store.check("response", &changed).unwrap(),
+            SnapshotOutcome::Matched
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "snapshot `absent` is missing")]
+    fn test_snapshot_missing_in_check_mode() {
+        let dir = TempDir::new().unwrap();
+        snapshots(&dir, SnapshotMode::Check).assert("absent", &json!(null));
+    }
+}
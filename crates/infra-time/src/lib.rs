@@ -0,0 +1,117 @@
+//! Overridable time source for LLM-Dev-Ops infrastructure.
+//!
+//! Components with timeouts, windows and timestamps read the time through a
+//! [`TimeHandle`], which follows the system clock unless given a
+//! [`TimeSource`]. Each such component takes one through its
+//! `with_time_source` builder. infra-sim's `SimulatedClock` is a time source,
+//! so tests can drive those components instead of waiting.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+/// Source of the current time for components with timeouts and timestamps
+pub trait TimeSource: Send + Sync {
+    /// Get the current monotonic time, for measuring intervals
+    fn instant(&self) -> Instant;
+
+    /// Get the current wall-clock time, for timestamps and absolute expiry
+    fn system_time(&self) -> SystemTime;
+}
+
+impl<T: TimeSource + ?Sized> TimeSource for Arc<T> {
+    fn instant(&self) -> Instant {
+        (**self).instant()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        (**self).system_time()
+    }
+}
+
+/// Handle reading a time source, the system clock unless overridden
+#[derive(Clone, Default)]
+pub struct TimeHandle {
+    source: Option<Arc<dyn TimeSource>>,
+}
+
+impl TimeHandle {
+    /// Create a handle reading a time source
+    pub fn from_source(source: Arc<dyn TimeSource>) -> Self {
+        Self {
+            source: Some(source),
+        }
+    }
+
+    /// Check whether the handle reads a time source instead of the system clock
+    #[must_use]
+    pub fn is_overridden(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Get the current monotonic time
+    #[must_use]
+    pub fn instant(&self) -> Instant {
+        match &self.source {
+            Some(source) => source.instant(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Get the current wall-clock time
+    #[must_use]
+    pub fn system_time(&self) -> SystemTime {
+        match &self.source {
+            Some(source) => source.system_time(),
+            None => SystemTime::now(),
+        }
+    }
+}
+
+impl fmt::Debug for TimeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_overridden() {
+            f.write_str("TimeHandle(TimeSource)")
+        } else {
+            f.write_str("TimeHandle(System)")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct Fixed(Instant, SystemTime);
+
+    impl TimeSource for Fixed {
+        fn instant(&self) -> Instant {
+            self.0
+        }
+
+        fn system_time(&self) -> SystemTime {
+            self.1
+        }
+    }
+
+    #[test]
+    fn test_system_time() {
+        let handle = TimeHandle::default();
+        assert!(!handle.is_overridden());
+        let before = SystemTime::now();
+        assert!(handle.system_time() >= before);
+    }
+
+    #[test]
+    fn test_source_time() {
+        let instant = Instant::now() + Duration::from_secs(3600);
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let handle = TimeHandle::from_source(Arc::new(Fixed(instant, time)));
+
+        assert!(handle.is_overridden());
+        assert_eq!(handle.instant(), instant);
+        assert_eq!(handle.system_time(), time);
+        assert_eq!(format!("{handle:?}"), "TimeHandle(TimeSource)");
+    }
+}
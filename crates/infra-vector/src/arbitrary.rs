@@ -0,0 +1,143 @@
+//! Proptest strategies for metadata filters and vector records.
+//!
+//! Field names and values are drawn from small pools so that generated
+//! filters actually select some of the generated records.
+
+use crate::types::MetadataFilter;
+use proptest::prelude::*;
+use serde_json::{Map, Value as Json};
+
+/// Strategy for metadata field names
+pub fn field() -> impl Strategy<Value = String> {
+    prop::sample::select(vec!["category", "score", "tag", "owner"]).prop_map(String::from)
+}
+
+/// Strategy for metadata values: small integers, short strings and booleans
+pub fn metadata_value() -> impl Strategy<Value = Json> {
+    prop_oneof![
+        (0i64..10).prop_map(Json::from),
+        "[ab]{1,3}".prop_map(Json::from),
+        any::<bool>().prop_map(Json::from),
+    ]
+}
+
+/// Strategy for record metadata: an object over [`field`] names
+pub fn metadata() -> impl Strategy<Value = Json> {
+    prop::collection::btree_map(field(), metadata_value(), 0..4)
+        .prop_map(|entries| Json::Object(entries.into_iter().collect::<Map<_, _>>()))
+}
+
+/// Strategy for single-field comparison filters
+pub fn leaf_filter() -> impl Strategy<Value = MetadataFilter> {
+    prop_oneof![
+        (field(), metadata_value()).prop_map(|(f, v)| MetadataFilter::eq(f, v)),
+        (field(), metadata_value()).prop_map(|(f, v)| MetadataFilter::ne(f, v)),
+        (field(), 0i64..10).prop_map(|(f, v)| MetadataFilter::gt(f, v)),
+        (field(), 0i64..10).prop_map(|(f, v)| MetadataFilter::gte(f, v)),
+        (field(), 0i64..10).prop_map(|(f, v)| MetadataFilter::lt(f, v)),
+        (field(), 0i64..10).prop_map(|(f, v)| MetadataFilter::lte(f, v)),
+        (field(), prop::collection::vec(metadata_value(), 0..4))
+            .prop_map(|(field, values)| MetadataFilter::In { field, values }),
+        (field(), "[ab]{0,2}").prop_map(|(field, value)| MetadataFilter::Contains { field, value }),
+    ]
+}
+
+/// Strategy for filter trees combining [`leaf_filter`]s with `And`, `Or`
+/// and `Not`, nested up to four levels
+pub fn metadata_filter() -> impl Strategy<Value = MetadataFilter> {
+    leaf_filter().prop_recursive(4, 32, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(MetadataFilter::And),
+            prop::collection::vec(inner.clone(), 0..4).prop_map(MetadataFilter::Or),
+            inner.prop_map(MetadataFilter::not),
+        ]
+    })
+}
+
+/// Strategy for vectors of the given dimension with components in `-1..1`
+pub fn vector(dimensions: usize) -> impl Strategy<Value = Vec<f32>> {
+    prop::collection::vec(-1.0f32..1.0, dimensions)
+}
+
+/// Strategy for batches of records, as accepted by
+/// [`VectorStore::insert_batch`](crate::VectorStore::insert_batch), with
+/// unique ids `r0`, `r1`, ...
+pub fn records(
+    dimensions: usize,
+    max_len: usize,
+) -> impl Strategy<Value = Vec<(crate::VectorId, Vec<f32>, Option<Json>)>> {
+    prop::collection::vec(
+        (vector(dimensions), prop::option::of(metadata())),
+        0..max_len,
+    )
+    .prop_map(|records| {
+        records
+            .into_iter()
+            .enumerate()
+            .map(|(i, (vector, metadata))| {
+                (crate::VectorId::new(format!("r{i}")), vector, metadata)
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::RuVectorStore;
+    use crate::traits::VectorStore;
+    use crate::types::{VectorId, VectorStoreConfig};
+    use std::collections::BTreeSet;
+
+    type Record = (VectorId, Vec<f32>, Option<Json>);
+
+    /// Ids of the records a filter selects when searching an in-memory store
+    fn selected(records: &[Record], filter: MetadataFilter) -> BTreeSet<String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let store = RuVectorStore::new(VectorStoreConfig::new("prop", 2))
+                .await
+                .unwrap();
+            store.insert_batch(records.to_vec()).await.unwrap();
+            store
+                .search(vec![1.0, 0.0], records.len(), Some(filter))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id.as_str().to_string())
+                .collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_filter_serde_round_trip(filter in metadata_filter()) {
+            let encoded = serde_json::to_value(&filter).unwrap();
+            let decoded: MetadataFilter = serde_json::from_value(encoded.clone()).unwrap();
+            prop_assert_eq!(serde_json::to_value(&decoded).unwrap(), encoded);
+        }
+
+        #[test]
+        fn prop_filter_and_negation_partition(records in records(2, 12), filter in metadata_filter()) {
+            let with_metadata: BTreeSet<String> = records
+                .iter()
+                .filter(|(_, _, metadata)| metadata.is_some())
+                .map(|(id, _, _)| id.as_str().to_string())
+                .collect();
+            let matched = selected(&records, filter.clone());
+            let unmatched = selected(&records, MetadataFilter::not(filter));
+
+            prop_assert!(matched.is_disjoint(&unmatched));
+            prop_assert_eq!(&matched | &unmatched, with_metadata);
+        }
+
+        #[test]
+        fn prop_filter_de_morgan(records in records(2, 12), a in metadata_filter(), b in metadata_filter()) {
+            let negated_and = MetadataFilter::not(MetadataFilter::and(vec![a.clone(), b.clone()]));
+            let or_of_negations = MetadataFilter::or(vec![MetadataFilter::not(a), MetadataFilter::not(b)]);
+            prop_assert_eq!(selected(&records, negated_and), selected(&records, or_of_negations));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Fault injection for resilience tests.
+//!
+//! [`ChaosVectorStore`] wraps a vector store and injects latency, failures
+//! and corrupted reads according to an infra-sim [`ChaosPolicy`].
+
+use crate::traits::VectorStore;
+use crate::types::{
+    BatchInsertResult, CollectionStats, MetadataFilter, SearchResult, VectorId, VectorRecord,
+};
+use async_trait::async_trait;
+use infra_errors::{InfraError, InfraResult, VectorOperation};
+use infra_sim::ChaosPolicy;
+use serde_json::Value as Json;
+
+/// Vector store injecting faults into another vector store.
+///
+/// Operations are named after the [`VectorStore`] methods (`insert`,
+/// `insert_batch`, `search`, `get`, `delete`, `update_metadata`, `stats`,
+/// `exists` and `clear`). A corrupted read has one bit flipped in the
+/// returned data: a search result's score, or a record's vector.
+pub struct ChaosVectorStore<S> {
+    inner: S,
+    policy: ChaosPolicy,
+}
+
+impl<S: VectorStore> ChaosVectorStore<S> {
+    /// Wrap a vector store.
+    pub fn new(inner: S, policy: impl Into<ChaosPolicy>) -> Self {
+        Self {
+            inner,
+            policy: policy.into(),
+        }
+    }
+
+    /// Get the wrapped vector store.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    async fn disturb(&self, name: &str, operation: VectorOperation) -> InfraResult<()> {
+        self.policy
+            .disturb(name)
+            .await
+            .map_err(|message| InfraError::Vector {
+                operation,
+                message,
+                dimensions: None,
+                context: None,
+                source: None,
+            })
+    }
+}
+
+#[async_trait]
+impl<S: VectorStore> VectorStore for ChaosVectorStore<S> {
+    async fn insert(
+        &self,
+        id: VectorId,
+        vector: Vec<f32>,
+        metadata: Option<Json>,
+    ) -> InfraResult<()> {
+        self.disturb("insert", VectorOperation::Insert).await?;
+        self.inner.insert(id, vector, metadata).await
+    }
+
+    async fn insert_batch(
+        &self,
+        vectors: Vec<(VectorId, Vec<f32>, Option<Json>)>,
+    ) -> InfraResult<BatchInsertResult> {
+        self.disturb("insert_batch", VectorOperation::BatchInsert)
+            .await?;
+        self.inner.insert_batch(vectors).await
+    }
+
+    async fn search(
+        &self,
+        query: Vec<f32>,
+        k: usize,
+        filter: Option<MetadataFilter>,
+    ) -> InfraResult<Vec<SearchResult>> {
+        self.disturb("search", VectorOperation::Search).await?;
+        let mut results = self.inner.search(query, k, filter).await?;
+
+        let injector = self.policy.injector("search");
+        if injector.should_corrupt() {
+            let mut scores: Vec<f32> = results.iter().map(|r| r.score).collect();
+            injector.corrupt_floats(&mut scores);
+            for (result, score) in results.iter_mut().zip(scores) {
+                result.score = score;
+            }
+        }
+        Ok(results)
+    }
+
+    async fn get(&self, id: &VectorId) -> InfraResult<Option<VectorRecord>> {
+        self.disturb("get", VectorOperation::Get).await?;
+        let mut record = self.inner.get(id).await?;
+
+        let injector = self.policy.injector("get");
+        if let Some(record) = record.as_mut().filter(|_| injector.should_corrupt()) {
+            injector.corrupt_floats(&mut record.vector);
+        }
+        Ok(record)
+    }
+
+    async fn delete(&self, id: &VectorId) -> InfraResult<bool> {
+        self.disturb("delete", VectorOperation::Delete).await?;
+        self.inner.delete(id).await
+    }
+
+    async fn update_metadata(&self, id: &VectorId, metadata: Json) -> InfraResult<()> {
+        self.disturb("update_metadata", VectorOperation::Update)
+            .await?;
+        self.inner.update_metadata(id, metadata).await
+    }
+
+    async fn stats(&self) -> InfraResult<CollectionStats> {
+        self.disturb("stats", VectorOperation::Stats).await?;
+        self.inner.stats().await
+    }
+
+    async fn exists(&self, id: &VectorId) -> InfraResult<bool> {
+        self.disturb("exists", VectorOperation::Get).await?;
+        self.inner.exists(id).await
+    }
+
+    async fn clear(&self) -> InfraResult<()> {
+        self.disturb("clear", VectorOperation::BatchDelete).await?;
+        self.inner.clear().await
+    }
+
+    fn collection_name(&self) -> &str {
+        self.inner.collection_name()
+    }
+
+    fn dimensions(&self) -> usize {
+        self.inner.dimensions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::RuVectorStore;
+    use crate::types::VectorStoreConfig;
+    use infra_sim::{ChaosConfig, ChaosMode};
+
+    async fn store(policy: impl Into<ChaosPolicy>) -> ChaosVectorStore<RuVectorStore> {
+        let inner = RuVectorStore::new(VectorStoreConfig::new("test", 3))
+            .await
+            .unwrap();
+        ChaosVectorStore::new(inner, policy)
+    }
+
+    #[tokio::test]
+    async fn test_chaos_vector_store_failures() {
+        let store = store(
+            ChaosPolicy::disabled().operation("search", ChaosConfig::new(ChaosMode::AlwaysFail)),
+        )
+        .await;
+
+        store
+            .insert(VectorId::new("a"), vec![1.0, 0.0, 0.0], None)
+            .await
+            .unwrap();
+        let err = store
+            .search(vec![1.0, 0.0, 0.0], 1, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            InfraError::Vector {
+                operation: VectorOperation::Search,
+                ..
+            }
+        ));
+        assert!(store.get(&VectorId::new("a")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_chaos_vector_store_corruption() {
+        let store = store(ChaosConfig::default().with_corruption_probability(1.0)).await;
+        let vector = vec![1.0, 0.5, 0.25];
+        store
+            .insert(VectorId::new("a"), vector.clone(), None)
+            .await
+            .unwrap();
+
+        let record = store.get(&VectorId::new("a")).await.unwrap().unwrap();
+        assert_ne!(
+            record
+                .vector
+                .iter()
+                .map(|v| v.to_bits())
+                .collect::<Vec<_>>(),
+            vector.iter().map(|v| v.to_bits()).collect::<Vec<_>>()
+        );
+        assert_eq!(record.vector.len(), 3);
+    }
+}
@@ -105,6 +105,7 @@ impl VectorIndex {
                 ),
                 dimensions: Some(self.config.dimension),
                 context: None,
+                source: None,
             });
         }
 
@@ -149,6 +150,7 @@ impl VectorIndex {
                 ),
                 dimensions: Some(self.config.dimension),
                 context: None,
+                source: None,
             });
         }
 
@@ -13,6 +13,8 @@
 //! - `ruvector` - RuvVector integration (ruvector-core)
 //! - `wasm` - WebAssembly bindings via ruvector-gnn-wasm
 //! - `otel` - OpenTelemetry tracing instrumentation
+//! - `chaos` - [`ChaosVectorStore`] fault injection via infra-sim
+//! - `proptest` - Proptest strategies for filters and records in [`arbitrary`]
 //!
 //! # Quick Start
 //!
@@ -63,6 +65,10 @@ mod embedding;
 mod types;
 mod traits;
 mod store;
+#[cfg(feature = "chaos")]
+mod chaos;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
 
 // WASM module (feature-gated)
 #[cfg(feature = "wasm")]
@@ -90,6 +96,8 @@ pub use types::{
 };
 pub use traits::VectorStore;
 pub use store::RuVectorStore;
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosVectorStore;
 
 // Re-export WASM bindings when enabled
 #[cfg(feature = "wasm")]
@@ -36,6 +36,7 @@ pub fn cosine_similarity(a: &Vector, b: &Vector) -> InfraResult<f32> {
             message: format!("Dimension mismatch: {} vs {}", a.dim(), b.dim()),
             dimensions: Some(a.dim()),
             context: None,
+            source: None,
         });
     }
 
@@ -58,6 +59,7 @@ pub fn euclidean_distance(a: &Vector, b: &Vector) -> InfraResult<f32> {
             message: format!("Dimension mismatch: {} vs {}", a.dim(), b.dim()),
             dimensions: Some(a.dim()),
             context: None,
+            source: None,
         });
     }
 
@@ -84,6 +86,7 @@ pub fn manhattan_distance(a: &Vector, b: &Vector) -> InfraResult<f32> {
             message: format!("Dimension mismatch: {} vs {}", a.dim(), b.dim()),
             dimensions: Some(a.dim()),
             context: None,
+            source: None,
         });
     }
 
@@ -77,6 +77,7 @@ impl RuVectorStore {
                 message: "Dimensions must be greater than 0".to_string(),
                 dimensions: Some(0),
                 context: Some("VectorStoreConfig validation".to_string()),
+                source: None,
             });
         }
 
@@ -86,6 +87,7 @@ impl RuVectorStore {
                 message: format!("Dimensions {} exceeds maximum of 65536", config.dimensions),
                 dimensions: Some(config.dimensions),
                 context: Some("VectorStoreConfig validation".to_string()),
+                source: None,
             });
         }
 
@@ -116,6 +118,7 @@ impl RuVectorStore {
                 message: format!("Invalid INFRA_VECTOR_DIMENSIONS: {}", e),
                 key: Some("INFRA_VECTOR_DIMENSIONS".to_string()),
                 context: None,
+                source: None,
             })?;
 
         let distance = match std::env::var("INFRA_VECTOR_DISTANCE")
@@ -132,6 +135,7 @@ impl RuVectorStore {
                     message: format!("Unknown distance metric: {}", other),
                     key: Some("INFRA_VECTOR_DISTANCE".to_string()),
                     context: None,
+                    source: None,
                 });
             }
         };
@@ -157,6 +161,7 @@ impl RuVectorStore {
                 ),
                 dimensions: Some(vector.len()),
                 context: Some(format!("collection: {}", self.config.collection_name)),
+                source: None,
             });
         }
         Ok(())
@@ -276,6 +281,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire write lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         storage.insert(id.as_str().to_string(), stored);
@@ -337,6 +343,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire read lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         let mut results: Vec<SearchResult> = storage
@@ -377,6 +384,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire read lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         Ok(storage.get(id.as_str()).map(|stored| VectorRecord {
@@ -394,6 +402,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire write lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         Ok(storage.remove(id.as_str()).is_some())
@@ -405,6 +414,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire write lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         match storage.get_mut(id.as_str()) {
@@ -418,6 +428,7 @@ impl VectorStore for RuVectorStore {
                 message: format!("Vector not found: {}", id),
                 dimensions: None,
                 context: None,
+                source: None,
             }),
         }
     }
@@ -428,6 +439,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire read lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         // Estimate index size (vectors * dimensions * sizeof(f32) + overhead)
@@ -457,6 +469,7 @@ impl VectorStore for RuVectorStore {
             message: format!("Failed to acquire write lock: {}", e),
             dimensions: None,
             context: None,
+            source: None,
         })?;
 
         storage.clear();
@@ -402,11 +402,11 @@ pub enum MetadataFilter {
     /// String contains
     Contains { field: String, value: String },
     /// Logical AND of multiple filters
-    And(Vec<MetadataFilter>),
+    And(#[serde(with = "filters_serde")] Vec<MetadataFilter>),
     /// Logical OR of multiple filters
-    Or(Vec<MetadataFilter>),
+    Or(#[serde(with = "filters_serde")] Vec<MetadataFilter>),
     /// Logical NOT
-    Not(Box<MetadataFilter>),
+    Not(#[serde(with = "filter_serde")] Box<MetadataFilter>),
 }
 
 impl MetadataFilter {
@@ -511,6 +511,68 @@ mod duration_serde {
     }
 }
 
+// Helper modules for serializing nested filters.
+//
+// An internally tagged variant can only hold a map, so nested filters are
+// wrapped in a `filters` or `filter` field: `{"op": "not", "filter": {...}}`.
+mod filters_serde {
+    use super::MetadataFilter;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct Wrapped<'a> {
+        filters: &'a [MetadataFilter],
+    }
+
+    #[derive(Deserialize)]
+    struct Unwrapped {
+        filters: Vec<MetadataFilter>,
+    }
+
+    pub fn serialize<S>(filters: &[MetadataFilter], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Wrapped { filters }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<MetadataFilter>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Unwrapped::deserialize(deserializer)?.filters)
+    }
+}
+
+mod filter_serde {
+    use super::MetadataFilter;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct Wrapped<'a> {
+        filter: &'a MetadataFilter,
+    }
+
+    #[derive(Deserialize)]
+    struct Unwrapped {
+        filter: Box<MetadataFilter>,
+    }
+
+    pub fn serialize<S>(filter: &MetadataFilter, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Wrapped { filter }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Box<MetadataFilter>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Unwrapped::deserialize(deserializer)?.filter)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -563,6 +625,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_metadata_filter_serde() {
+        let filter = MetadataFilter::not(MetadataFilter::or(vec![
+            MetadataFilter::eq("category", "tech"),
+            MetadataFilter::lt("score", 0.5),
+        ]));
+
+        let json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(json["op"], "not");
+        assert_eq!(json["filter"]["op"], "or");
+        assert_eq!(json["filter"]["filters"][1]["op"], "lt");
+
+        let decoded: MetadataFilter = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+    }
+
     #[test]
     fn test_batch_result() {
         let result = BatchInsertResult::new(
@@ -95,6 +95,7 @@ impl Vector {
                 message: "Cannot normalize zero vector".to_string(),
                 dimensions: Some(self.dim()),
                 context: None,
+                source: None,
             });
         }
         Ok(Self {
@@ -121,6 +122,7 @@ impl Vector {
                 ),
                 dimensions: Some(self.dim()),
                 context: None,
+                source: None,
             });
         }
 
@@ -144,6 +146,7 @@ impl Vector {
                 ),
                 dimensions: Some(self.dim()),
                 context: None,
+                source: None,
             });
         }
 
@@ -169,6 +172,7 @@ impl Vector {
                 ),
                 dimensions: Some(self.dim()),
                 context: None,
+                source: None,
             });
         }
 